@@ -9,7 +9,7 @@
 use super::*;
 
 /// Contains information that is needed to interact with CSR.
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Default)]
 pub struct CsrInfo {
     /// CSR address.
     pub addr: U<LEN_CSR_ADDR>,
@@ -22,8 +22,9 @@ pub struct CsrInfo {
 ///
 /// NOTE: This type should be represented as 3-bits.
 /// - <https://github.com/chipsalliance/rocket-chip/blob/master/src/main/scala/rocket/CSR.scala#L168-L178>
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub enum CsrCmd {
+    #[default]
     W = 1,
     S = 2,
     C = 3,
@@ -61,12 +62,31 @@ pub struct CsrResp {
 
     /// Redirected PC when returning from exception.
     pub evec: u32,
+
+    /// Effects on the CSRs (for debugging purpose).
+    pub debug: CsrDebug,
+}
+
+/// Effects of an instruction on the CSRs, which are reported with the retired instruction.
+#[derive(Debug, Clone, Copy)]
+pub struct CsrDebug {
+    /// Written CSR and data, if any.
+    pub write: HOption<(U<LEN_CSR_ADDR>, u32)>,
+
+    /// `mstatus` after the instruction.
+    pub mstatus: u32,
+
+    /// `mepc` after the instruction.
+    pub mepc: u32,
+
+    /// `mcause` after the instruction.
+    pub mcause: u32,
 }
 
 /// MStatus.
 ///
 /// Omitted unused fields.
-#[derive(Debug, Default, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Default)]
 struct MStatus {
     mpie: bool,
     mie: bool,
@@ -86,7 +106,7 @@ impl MStatus {
 /// MIP.
 ///
 /// Omitted unusd fields.
-#[derive(Debug, Default, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Default)]
 struct Mip {
     mtip: bool,
     msip: bool,
@@ -194,7 +214,7 @@ pub fn csr(i: Valid<CsrReq>) -> Valid<CsrResp> {
 
         let read_only = ip.decode.clip_const::<2>(10) == 0b11.into_u();
         let cpu_wen = cpu_ren && !matches!(ip.cmd, CsrCmd::R);
-        let wen = cpu_wen && !read_only;
+        let wen = cpu_wen && !read_only && !ip.exception;
         let wdata = (if matches!(ip.cmd, CsrCmd::S | CsrCmd::C) { rdata } else { 0 } | ip.wdata)
             & !if matches!(ip.cmd, CsrCmd::C) { ip.wdata } else { 0 };
 
@@ -205,13 +225,13 @@ pub fn csr(i: Valid<CsrReq>) -> Valid<CsrResp> {
 
         let eret = insn_call || insn_break || insn_ret;
 
-        let ep = CsrResp { rdata, eret, evec: if insn_ret && !ip.decode[10] { s.mepc } else { 0x80000004 } };
-
         let s_next = CsrS {
             mstatus: if wen && matches!(decoded_addr, CsrReg::Mstatus) {
                 MStatus { mie: U::<32>::from(wdata)[3], mpie: U::<32>::from(wdata)[7] }
             } else if insn_ret && !ip.decode[10] {
                 MStatus { mie: s.mstatus.mpie, mpie: true }
+            } else if ip.exception || insn_call || insn_break {
+                MStatus { mie: false, mpie: s.mstatus.mie }
             } else {
                 s.mstatus
             },
@@ -248,6 +268,14 @@ pub fn csr(i: Valid<CsrReq>) -> Valid<CsrResp> {
             },
         };
 
+        let debug = CsrDebug {
+            write: if wen { Some((ip.decode, wdata)) } else { None },
+            mstatus: u32::from(s_next.mstatus.into_u()),
+            mepc: s_next.mepc,
+            mcause: s_next.mcause,
+        };
+        let ep = CsrResp { rdata, eret, evec: if insn_ret && !ip.decode[10] { s.mepc } else { 0x80000004 }, debug };
+
         (ep, s_next)
     })
 }
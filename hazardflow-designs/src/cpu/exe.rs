@@ -3,7 +3,7 @@
 use super::*;
 
 /// Payload from execute stage to memory stage.
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Default)]
 pub struct ExeEP {
     /// Writeback information.
     ///
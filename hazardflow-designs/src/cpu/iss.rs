@@ -0,0 +1,302 @@
+//! Instruction-set simulator.
+//!
+//! [`Iss`] executes RV32I and Zicsr instructions one at a time, decoding them with the same [`Instruction`] as the
+//! decode stage. It is the golden model of the 5-stage pipeline: running both in lockstep and comparing the retired
+//! instructions finds the first instruction whose PC, register write or effects on the CSRs diverge, instead of diffing
+//! traces by hand.
+//!
+//! The ISA semantics follow the specification, while the platform follows the pipeline:
+//!
+//! - Only machine mode is implemented, and traps jump to [`TRAP_VECTOR`], which `mtvec` reads as.
+//! - `mstatus`, `mie`, `mip`, `mscratch`, `mepc`, `mcause`, `mtval` and `medeleg` are implemented as in [`csr()`]. The
+//!     timer interrupt is always pending and interrupts are never taken. Other CSRs read as zero and ignore writes.
+//! - An instruction raising an exception retires without writing a register, as the pipeline does.
+//! - Misaligned accesses are performed byte by byte instead of raising an exception.
+//!
+//! It is available with the `sim` feature, which makes the [`U`] operations used by the decoder executable.
+
+use ::std::collections::HashMap;
+
+use super::*;
+
+/// Address of the trap handler.
+pub const TRAP_VECTOR: u32 = 0x8000_0004;
+
+/// Page size of the memory.
+const PAGE_SIZE: u32 = 1 << 12;
+
+/// Cause of an illegal instruction exception.
+const CAUSE_ILLEGAL: u32 = 0x2;
+
+/// Cause of a breakpoint exception.
+const CAUSE_BREAKPOINT: u32 = 0x3;
+
+/// Cause of an environment call from machine mode.
+const CAUSE_ECALL: u32 = 0xb;
+
+/// Retired instruction.
+#[derive(Debug, Clone, Copy)]
+pub struct Retired {
+    /// PC.
+    pub pc: u32,
+
+    /// Instruction.
+    pub inst: u32,
+
+    /// Register write. Writes to `x0` are omitted.
+    pub write: HOption<Register>,
+
+    /// Effects on the CSRs, if the instruction is executed by [`csr()`] in the pipeline, i.e., a CSR instruction or
+    /// one raising an exception.
+    pub csr: HOption<CsrDebug>,
+}
+
+/// Machine-mode CSRs.
+#[derive(Debug, Clone, Copy, Default)]
+struct Csrs {
+    mie_enabled: bool,
+    mpie: bool,
+    mepc: u32,
+    mcause: u32,
+    mtval: u32,
+    mscratch: u32,
+    medeleg: u32,
+    msip: bool,
+    mie: u32,
+}
+
+impl Csrs {
+    /// Reads a CSR.
+    fn read(&self, addr: u32) -> u32 {
+        match addr {
+            // MPP is hardwired to machine mode.
+            0x300 => 0x1800 | (u32::from(self.mpie) << 7) | (u32::from(self.mie_enabled) << 3),
+            0x302 => self.medeleg,
+            0x304 => self.mie,
+            0x305 => TRAP_VECTOR,
+            0x340 => self.mscratch,
+            0x341 => self.mepc,
+            0x342 => self.mcause,
+            0x343 => self.mtval,
+            // MTIP is always pending.
+            0x344 => (1 << 7) | (u32::from(self.msip) << 3),
+            _ => 0,
+        }
+    }
+
+    /// Writes a CSR.
+    fn write(&mut self, addr: u32, data: u32) {
+        match addr {
+            0x300 => {
+                self.mie_enabled = data & (1 << 3) != 0;
+                self.mpie = data & (1 << 7) != 0;
+            }
+            0x302 => self.medeleg = data,
+            0x304 => self.mie = data & ((1 << 7) | (1 << 3)),
+            0x340 => self.mscratch = data,
+            0x341 => self.mepc = data & !0b11,
+            0x342 => self.mcause = data & 0x8000_001f,
+            0x343 => self.mtval = data,
+            0x344 => self.msip = data & (1 << 3) != 0,
+            _ => {}
+        }
+    }
+}
+
+/// Instruction-set simulator. See the module documentation.
+#[derive(Debug, Clone)]
+pub struct Iss {
+    pc: u32,
+    regs: [u32; REGS],
+    csrs: Csrs,
+    pages: HashMap<u32, Box<[u8]>>,
+}
+
+impl Iss {
+    /// Creates a new simulator starting at `pc`, with zero-initialized registers and memory.
+    pub fn new(pc: u32) -> Self {
+        Self { pc, regs: [0; REGS], csrs: Csrs::default(), pages: HashMap::new() }
+    }
+
+    /// Returns the PC of the next instruction.
+    pub fn pc(&self) -> u32 {
+        self.pc
+    }
+
+    /// Returns the value of a register.
+    pub fn reg(&self, addr: usize) -> u32 {
+        self.regs[addr]
+    }
+
+    /// Returns the value of a CSR.
+    pub fn csr(&self, addr: u32) -> u32 {
+        self.csrs.read(addr)
+    }
+
+    /// Writes bytes to the memory, e.g., a segment of a program.
+    pub fn load(&mut self, addr: u32, bytes: &[u8]) {
+        for (offset, byte) in bytes.iter().enumerate() {
+            self.write_u8(addr.wrapping_add(offset as u32), *byte);
+        }
+    }
+
+    /// Reads `size` bytes in little endian.
+    pub fn read(&self, addr: u32, size: u32) -> u32 {
+        (0..size).fold(0, |value, i| {
+            let addr = addr.wrapping_add(i);
+            let byte = self.pages.get(&(addr / PAGE_SIZE)).map_or(0, |page| page[(addr % PAGE_SIZE) as usize]);
+            value | (u32::from(byte) << (8 * i))
+        })
+    }
+
+    /// Writes the lower `size` bytes of `value` in little endian.
+    pub fn write(&mut self, addr: u32, size: u32, value: u32) {
+        for i in 0..size {
+            self.write_u8(addr.wrapping_add(i), (value >> (8 * i)) as u8);
+        }
+    }
+
+    fn write_u8(&mut self, addr: u32, value: u8) {
+        let page = self.pages.entry(addr / PAGE_SIZE).or_insert_with(|| vec![0; PAGE_SIZE as usize].into_boxed_slice());
+        page[(addr % PAGE_SIZE) as usize] = value;
+    }
+
+    /// Executes the next instruction.
+    pub fn step(&mut self) -> Retired {
+        let pc = self.pc;
+        let inst = self.read(pc, 4);
+        let decoded = Instruction::from(inst);
+
+        if decoded.is_illegal {
+            return self.trap(pc, inst, CAUSE_ILLEGAL);
+        }
+
+        let rs1 = decoded.rs1_addr.map(|addr| Register::new(addr, self.regs[u32::from(addr) as usize]));
+        let rs2 = decoded.rs2_addr.map(|addr| Register::new(addr, self.regs[u32::from(addr) as usize]));
+        let op1 = decoded.op1_data(rs1, pc);
+        let op2 = decoded.op2_data(rs2);
+        let alu_out = match decoded.alu_op {
+            AluOp::Base(op) => alu(op1, op2, op),
+            AluOp::Mext(_) => return self.trap(pc, inst, CAUSE_ILLEGAL),
+        };
+
+        let mut next_pc = pc.wrapping_add(4);
+        if let Some(BrInfo { typ, base, offset }) = decoded.br_info(rs1, pc) {
+            let taken = match typ {
+                BrType::Jal | BrType::Jalr => true,
+                BrType::Beq | BrType::Bge | BrType::Bgeu => alu_out == 0,
+                BrType::Bne | BrType::Blt | BrType::Bltu => alu_out != 0,
+            };
+            if taken {
+                let target = base.wrapping_add(offset);
+                next_pc = if matches!(typ, BrType::Jalr) { target & !1 } else { target };
+            }
+        }
+
+        let mut mem_out = 0;
+        if let Some((fcn, typ)) = decoded.mem_info {
+            let (size, signed) = match typ {
+                MemOpTyp::B => (1, true),
+                MemOpTyp::BU => (1, false),
+                MemOpTyp::H => (2, true),
+                MemOpTyp::HU => (2, false),
+                MemOpTyp::W | MemOpTyp::WU | MemOpTyp::D => (4, false),
+            };
+            match fcn {
+                MemOpFcn::Load => {
+                    let shift = 32 - 8 * size;
+                    let data = self.read(alu_out, size) << shift;
+                    mem_out = if signed { ((data as i32) >> shift) as u32 } else { data >> shift };
+                }
+                MemOpFcn::Store => self.write(alu_out, size, rs2.unwrap().data),
+            }
+        }
+
+        let mut csr_out = 0;
+        let mut csr_write = None;
+        if let Some(CsrInfo { addr, cmd }) = decoded.csr_info {
+            let addr = u32::from(addr);
+            match cmd {
+                CsrCmd::I => match addr {
+                    0x000 => return self.trap(pc, inst, CAUSE_ECALL),
+                    0x001 => return self.trap(pc, inst, CAUSE_BREAKPOINT),
+                    _ => {
+                        // `mret`
+                        self.csrs.mie_enabled = self.csrs.mpie;
+                        self.csrs.mpie = true;
+                        next_pc = self.csrs.mepc;
+                    }
+                },
+                // `csrrsi` and `csrrci` with a zero immediate do not write, as `csrrs` and `csrrc` with `x0`.
+                CsrCmd::S | CsrCmd::C if decoded.rs1_addr.is_none() && alu_out == 0 => csr_out = self.csrs.read(addr),
+                CsrCmd::R => csr_out = self.csrs.read(addr),
+                CsrCmd::W | CsrCmd::S | CsrCmd::C => {
+                    if addr >> 10 == 0b11 {
+                        return self.trap(pc, inst, CAUSE_ILLEGAL);
+                    }
+                    csr_out = self.csrs.read(addr);
+                    let data = match cmd {
+                        CsrCmd::W => alu_out,
+                        CsrCmd::S => csr_out | alu_out,
+                        _ => csr_out & !alu_out,
+                    };
+                    self.csrs.write(addr, data);
+                    csr_write = Some((U::from(addr), data));
+                }
+            }
+        }
+
+        let write = decoded.rd_addr.zip(decoded.wb_sel).map(|(addr, wb_sel)| {
+            let data = match wb_sel {
+                WbSel::Alu => alu_out,
+                WbSel::Mem => mem_out,
+                WbSel::Csr => csr_out,
+            };
+            self.regs[u32::from(addr) as usize] = data;
+            Register::new(addr, data)
+        });
+
+        self.pc = next_pc;
+        let csr = decoded.csr_info.map(|_| self.csr_debug(csr_write));
+        Retired { pc, inst, write, csr }
+    }
+
+    /// Takes an exception raised by the instruction at `pc`.
+    fn trap(&mut self, pc: u32, inst: u32, cause: u32) -> Retired {
+        self.csrs.mpie = self.csrs.mie_enabled;
+        self.csrs.mie_enabled = false;
+        self.csrs.mepc = pc;
+        self.csrs.mcause = cause;
+        self.csrs.mtval = 0;
+        self.pc = TRAP_VECTOR;
+        Retired { pc, inst, write: None, csr: Some(self.csr_debug(None)) }
+    }
+
+    /// Returns the effects on the CSRs of an instruction, after executing it.
+    fn csr_debug(&self, write: HOption<(U<LEN_CSR_ADDR>, u32)>) -> CsrDebug {
+        CsrDebug { write, mstatus: self.csrs.read(0x300), mepc: self.csrs.mepc, mcause: self.csrs.mcause }
+    }
+}
+
+/// Executes a base ALU operation.
+///
+/// Unlike [`exe_alu`], it wraps around on overflow when executed in Rust.
+fn alu(op1: u32, op2: u32, op: BaseAluOp) -> u32 {
+    let shamt = op2 & 0x1f;
+
+    match op {
+        BaseAluOp::Add => op1.wrapping_add(op2),
+        BaseAluOp::Sub => op1.wrapping_sub(op2),
+        BaseAluOp::And => op1 & op2,
+        BaseAluOp::Or => op1 | op2,
+        BaseAluOp::Xor => op1 ^ op2,
+        BaseAluOp::Slt => u32::from((op1 as i32) < (op2 as i32)),
+        BaseAluOp::Sltu => u32::from(op1 < op2),
+        BaseAluOp::Sll => op1 << shamt,
+        BaseAluOp::Sra => ((op1 as i32) >> shamt) as u32,
+        BaseAluOp::Srl => op1 >> shamt,
+        BaseAluOp::CopyOp1 => op1,
+        BaseAluOp::CopyOp2 => op2,
+        BaseAluOp::Zero => 0,
+    }
+}
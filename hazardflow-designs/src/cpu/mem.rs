@@ -3,7 +3,7 @@
 use super::*;
 
 /// Memory access information.
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Default)]
 pub struct MemInfo {
     /// Function (load or store).
     pub fcn: MemOpFcn,
@@ -30,6 +30,9 @@ pub struct MemEP {
 
     /// Instruction (for debugging purpose).
     pub debug_inst: u32,
+
+    /// Effects on the CSRs (for debugging purpose).
+    pub debug_csr: HOption<CsrDebug>,
 }
 
 /// Hazard from memory stage to execute stage.
@@ -110,7 +113,7 @@ pub fn mem(
 
     let dmem_resp = dmem_req
         .map(|ip| {
-            let Some(MemInfo { fcn, typ, data }) = ip.mem_info else { unsafe { x() } };
+            let MemInfo { fcn, typ, data } = ip.mem_info.unwrap_or(unsafe { x() });
 
             let mem_req = match fcn {
                 MemOpFcn::Load => MemReq::load(ip.alu_out, typ),
@@ -125,11 +128,12 @@ pub fn mem(
             wb_info: ip.wb_info.map(|(addr, _)| Register::new(addr, dmem_resp.data)),
             debug_inst: ip.debug_inst,
             debug_pc: ip.pc,
+            debug_csr: None,
         });
 
     let csr_resp = csr_req
         .map(|ip| {
-            let Some(CsrInfo { cmd, addr }) = ip.csr_info else { unsafe { x() } };
+            let CsrInfo { cmd, addr } = ip.csr_info.unwrap_or(unsafe { x() });
 
             let csr_req = CsrReq { cmd, wdata: ip.alu_out, decode: addr, exception: ip.is_illegal, pc: ip.pc };
 
@@ -141,12 +145,14 @@ pub fn mem(
             wb_info: ip.wb_info.map(|(addr, _)| Register::new(addr, csr_resp.rdata)),
             debug_inst: ip.debug_inst,
             debug_pc: ip.pc,
+            debug_csr: Some(csr_resp.debug),
         });
 
     let exep = exep.map_resolver_inner_with_p::<WbR>(|ip, er| (ip, er)).map(|ip| MemEP {
         wb_info: ip.wb_info.map(|(addr, _)| Register::new(addr, ip.alu_out)),
         debug_inst: ip.debug_inst,
         debug_pc: ip.pc,
+        debug_csr: None,
     });
 
     [dmem_resp, csr_resp, exep].merge()
@@ -1,9 +1,10 @@
 //! Memory.
 
 /// Memory operation function (load or store)
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub enum MemOpFcn {
     /// Load
+    #[default]
     Load,
 
     /// Store
@@ -11,9 +12,10 @@ pub enum MemOpFcn {
 }
 
 /// Memory operation type.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub enum MemOpTyp {
     /// Byte
+    #[default]
     B = 1,
 
     /// Half
@@ -66,7 +68,7 @@ impl MemReq {
 }
 
 /// Memory Response.
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Default)]
 pub struct MemRespWithAddr {
     /// data
     pub data: u32,
@@ -13,6 +13,8 @@ pub mod csr;
 pub mod decode;
 pub mod exe;
 pub mod fetch;
+#[cfg(feature = "sim")]
+pub mod iss;
 pub mod mem;
 pub mod mem_interface;
 pub mod multiplier;
@@ -26,6 +28,8 @@ pub use csr::*;
 pub use decode::*;
 pub use exe::*;
 pub use fetch::*;
+#[cfg(feature = "sim")]
+pub use iss::*;
 pub use mem::*;
 pub use mem_interface::*;
 pub use multiplier::*;
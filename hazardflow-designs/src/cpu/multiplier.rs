@@ -3,9 +3,10 @@
 use super::*;
 
 /// Multiplier function.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub enum MulOp {
     /// MUL operation.
+    #[default]
     Mul,
     /// MULH operation.
     Mulh,
@@ -38,7 +39,7 @@ impl MulOp {
 }
 
 /// Multiplier request.
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Default)]
 pub struct MulReq {
     /// Operation type.
     pub op: MulOp,
@@ -74,7 +75,7 @@ pub struct MulS<P: Copy> {
     remainder: U<{ 2 * 32 + 2 }>,
 }
 
-impl<P: Copy> Default for MulS<P> {
+impl<P: Copy + SimDefault> Default for MulS<P> {
     fn default() -> Self {
         Self {
             status: Status::default(),
@@ -90,7 +91,7 @@ impl<P: Copy> Default for MulS<P> {
 }
 
 /// Multiplier.
-pub fn muldiv<P: Copy, R: Copy>(
+pub fn muldiv<P: Copy + SimDefault, R: Copy>(
     i: I<VrH<(P, MulReq), R>, { Dep::Helpful }>,
 ) -> I<VrH<(P, U<32>), (R, bool)>, { Dep::Helpful }> {
     unsafe {
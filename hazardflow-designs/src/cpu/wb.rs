@@ -69,6 +69,25 @@ pub fn wb(i: I<VrH<MemEP, WbR>, { Dep::Demanding }>) {
                         );
                     }
                 }
+
+                // The effects on the CSRs of the instruction retired in the same cycle.
+                if let Some(c) = p.debug_csr {
+                    match c.write {
+                        Some((addr, data)) => {
+                            display!(
+                                "csr=[%x=%x] mstatus=[%x] mepc=[%x] mcause=[%x]",
+                                addr,
+                                data,
+                                c.mstatus,
+                                c.mepc,
+                                c.mcause
+                            );
+                        }
+                        None => {
+                            display!("csr=[] mstatus=[%x] mepc=[%x] mcause=[%x]", c.mstatus, c.mepc, c.mcause);
+                        }
+                    }
+                }
             } else {
                 display!("retire=[0]");
             }
@@ -0,0 +1,24 @@
+//! Arithmetic functions of Gemmini applied to each valid payload.
+
+use crate::gemmini::arithmetic::*;
+use crate::gemmini::configs::OUTPUT_BITS;
+use crate::prelude::*;
+use crate::std::*;
+
+/// MAC unit applied to each valid payload.
+#[synthesize]
+pub fn mac_default(input: Valid<(S<8>, S<8>, S<32>)>) -> Valid<S<OUTPUT_BITS>> {
+    input.map(|(a, b, c)| mac(a, b, c))
+}
+
+/// Rounding shift applied to each valid payload.
+#[synthesize]
+pub fn rounding_shift_default(input: Valid<(S<32>, U<5>)>) -> Valid<S<32>> {
+    input.map(|(val, shamt)| rounding_shift(val, shamt))
+}
+
+/// Clipping of 40-bit integers, which are wider than `i32`, applied to each valid payload.
+#[synthesize]
+pub fn clip_with_saturation_default(input: Valid<S<40>>) -> Valid<S<OUTPUT_BITS>> {
+    input.map(clip_with_saturation::<40, OUTPUT_BITS>)
+}
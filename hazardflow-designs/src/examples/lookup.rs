@@ -0,0 +1,28 @@
+//! Lookup table implementation
+
+use crate::prelude::*;
+use crate::std::*;
+
+/// Entry of the lookup table.
+type Entry = HOption<(U<8>, u32)>;
+
+/// Returns the index and the value of the first entry with the key.
+///
+/// The entries after the first empty one are not looked up.
+fn lookup<const N: usize>(entries: Array<Entry, N>, key: U<8>) -> HOption<(U<{ clog2(N) }>, u32)>
+where [(); clog2(N)]: {
+    for i in 0..N {
+        match entries[i] {
+            Some((k, v)) if k == key => return Some((U::from(i), v)),
+            Some(_) => continue,
+            None => break,
+        }
+    }
+    None
+}
+
+/// Lookup table with 4 entries
+#[synthesize]
+pub fn lookup_table(input: Valid<(Array<Entry, 4>, U<8>)>) -> Valid<HOption<(U<2>, u32)>> {
+    input.map(|(entries, key)| lookup(entries, key))
+}
@@ -0,0 +1,71 @@
+//! Micro-operation executor
+
+use crate::prelude::*;
+use crate::std::*;
+
+/// Operand of a micro-operation.
+#[derive(Debug, Clone, Copy)]
+pub enum Operand {
+    /// Register.
+    Reg(U<2>),
+    /// Immediate.
+    Imm(U<8>),
+}
+
+/// Micro-operation.
+#[derive(Debug, Clone, Copy)]
+pub enum MicroOp {
+    /// Adds the operands, saturating at the maximum.
+    Add(Operand, Operand),
+    /// Moves the operand, if any.
+    Mov(HOption<Operand>),
+    /// Adds the first and the last registers.
+    AddEnds,
+    /// Does nothing.
+    Nop,
+}
+
+/// Returns the value of the operand.
+fn read(regs: Array<U<8>, 4>, operand: Operand) -> U<8> {
+    match operand {
+        Operand::Reg(r) => regs[r],
+        Operand::Imm(imm) => imm,
+    }
+}
+
+/// Returns the sum, if it does not overflow.
+fn checked_add(lhs: U<8>, rhs: U<8>) -> HOption<U<8>> {
+    let sum = lhs + rhs;
+    if sum[8] {
+        None
+    } else {
+        Some(sum.resize())
+    }
+}
+
+/// Returns the result of the micro-operation, if any.
+fn execute(regs: Array<U<8>, 4>, uop: MicroOp) -> HOption<U<8>> {
+    match uop {
+        // Adding zero moves the other operand.
+        MicroOp::Add(src, Operand::Imm(imm)) | MicroOp::Add(Operand::Imm(imm), src) if imm == U::from(0) => {
+            Some(read(regs, src))
+        }
+        MicroOp::Add(lhs, rhs) if let Some(sum) = checked_add(read(regs, lhs), read(regs, rhs)) => Some(sum),
+        MicroOp::Add(..) => Some(U::unsigned_max()),
+        MicroOp::Mov(src) => {
+            let Some(src) = src else { return None };
+            Some(read(regs, src))
+        }
+        MicroOp::AddEnds => {
+            let [first, .., last] = regs.into_array();
+            Some(first.trunk_add(last))
+        }
+        MicroOp::Nop => None,
+    }
+}
+
+/// Micro-operation executor with 4 registers
+#[synthesize]
+pub fn micro_op_exec(input: Valid<(Array<U<8>, 4>, MicroOp)>) -> Valid<HOption<U<8>>> {
+    input.map(|(regs, uop)| execute(regs, uop))
+}
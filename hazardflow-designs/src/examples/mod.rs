@@ -2,3 +2,9 @@
 
 pub mod custom_fifo;
 pub mod fir_filter;
+pub mod gemmini_arithmetic;
+pub mod lookup;
+pub mod micro_op;
+pub mod native_int;
+pub mod signed;
+pub mod statistics;
@@ -0,0 +1,74 @@
+//! Arithmetic on primitive integers
+
+use crate::prelude::*;
+use crate::std::*;
+
+/// Offset added to the scaled delta.
+const BIAS: i64 = -5;
+
+/// Results of the arithmetic.
+#[derive(Debug, Clone, Copy)]
+pub struct Results {
+    /// `addr + stride * 4`, which wraps around.
+    pub addr: u64,
+    /// `addr` rotated left by a byte.
+    pub rotated: u64,
+    /// Lower byte of `addr`.
+    pub low: u8,
+    /// `stride` with its bytes swapped.
+    pub swapped: u16,
+    /// Negated delta.
+    pub neg: i32,
+    /// Delta shifted right by 2, which rounds toward negative infinity.
+    pub quarter: i32,
+    /// Whether the delta is negative.
+    pub is_neg: bool,
+    /// Sum of the delta shifted right by 3, 2, 1 and 0 bits.
+    pub shifted_sum: i32,
+    /// Delta saturated to `i16`.
+    pub clamped: i16,
+    /// `delta * 1000 + BIAS`, which does not overflow.
+    pub scaled: i64,
+}
+
+/// Rotates `x` left by `K` bits.
+fn rotate_left<const K: u8>(x: u64) -> u64 {
+    (x << K) | (x >> (64 - K))
+}
+
+/// Returns the results of the arithmetic on the address, the stride and the delta.
+fn compute(addr: u64, stride: u16, delta: i32) -> Results {
+    let mut shifted_sum = 0;
+    let mut shamt = 3;
+    while shamt >= 0 {
+        shifted_sum += delta >> shamt;
+        shamt -= 1;
+    }
+
+    let clamped = if delta > i16::MAX as i32 {
+        i16::MAX
+    } else if delta < i16::MIN as i32 {
+        i16::MIN
+    } else {
+        delta as i16
+    };
+
+    Results {
+        addr: addr + stride as u64 * 4,
+        rotated: rotate_left::<8>(addr),
+        low: addr as u8,
+        swapped: (stride << 8) | (stride >> 8),
+        neg: -delta,
+        quarter: delta >> 2,
+        is_neg: delta < 0,
+        shifted_sum,
+        clamped,
+        scaled: delta as i64 * 1000 + BIAS,
+    }
+}
+
+/// Arithmetic on primitive integers
+#[synthesize]
+pub fn native_int_ops(input: Valid<(u64, u16, i32)>) -> Valid<Results> {
+    input.map(|(addr, stride, delta)| compute(addr, stride, delta))
+}
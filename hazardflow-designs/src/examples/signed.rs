@@ -0,0 +1,42 @@
+//! Arithmetic on signed integers
+
+use crate::prelude::*;
+use crate::std::*;
+
+/// Results of the arithmetic.
+#[derive(Debug, Clone, Copy)]
+pub struct Results {
+    /// `a + b`, which does not overflow.
+    pub sum: S<13>,
+    /// `a - b`, which wraps around.
+    pub diff: S<12>,
+    /// `a * b`, which does not overflow.
+    pub product: S<24>,
+    /// Negated `a`.
+    pub neg: S<12>,
+    /// `a` shifted right by 3, which rounds toward negative infinity.
+    pub quarter: S<12>,
+    /// Minimum of `a` and `b`.
+    pub min: S<12>,
+    /// Whether `a` is at least `b`.
+    pub ge: bool,
+}
+
+/// Returns the results of the arithmetic on `a` and `b`.
+fn compute(a: S<12>, b: S<12>) -> Results {
+    Results {
+        sum: a + b,
+        diff: a - b,
+        product: a * b,
+        neg: -a,
+        quarter: a >> 3,
+        min: if a < b { a } else { b },
+        ge: a >= b,
+    }
+}
+
+/// Arithmetic on signed integers
+#[synthesize]
+pub fn signed_ops(input: Valid<(S<12>, S<12>)>) -> Valid<Results> {
+    input.map(|(a, b)| compute(a, b))
+}
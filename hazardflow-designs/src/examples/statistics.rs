@@ -0,0 +1,56 @@
+//! Statistics of samples
+
+use crate::prelude::*;
+use crate::std::*;
+
+/// Statistics of the valid samples.
+#[derive(Debug, Clone, Copy)]
+pub struct Stats {
+    /// Number of the samples.
+    count: U<3>,
+    /// Sum of the samples.
+    sum: U<10>,
+    /// Maximum of the samples.
+    max: U<8>,
+}
+
+/// Returns the statistics of the valid samples.
+fn stats(samples: Array<HOption<U<8>>, 4>) -> Stats {
+    let mut stats = Stats { count: U::from(0), sum: U::from(0), max: U::from(0) };
+    for i in 0..4 {
+        if let Some(s) = samples[i] {
+            stats.count += U::from(1);
+            stats.sum += s.resize();
+            if s > stats.max {
+                stats.max = s;
+            }
+        }
+    }
+    stats
+}
+
+/// Returns the number of the samples in each bin.
+///
+/// The samples after the first invalid one are not counted.
+fn histogram(samples: Array<HOption<U<2>>, 4>) -> Array<U<3>, 4> {
+    let mut counts = U::from(0).repeat::<4>();
+    let mut i = 0;
+    while i < 4 {
+        let Some(bin) = samples[i] else { break };
+        counts[bin] += U::from(1);
+        i += 1;
+    }
+    counts
+}
+
+/// Statistics of 4 samples
+#[synthesize]
+pub fn sample_stats(input: Valid<Array<HOption<U<8>>, 4>>) -> Valid<Stats> {
+    input.map(stats)
+}
+
+/// Histogram of 4 samples over 4 bins
+#[synthesize]
+pub fn sample_histogram(input: Valid<Array<HOption<U<2>>, 4>>) -> Valid<Array<U<3>, 4>> {
+    input.map(histogram)
+}
@@ -6,20 +6,18 @@ use super::*;
 ///
 /// It preserves the signedness of operands.
 pub fn mac(a: S<8>, b: S<8>, c: S<32>) -> S<OUTPUT_BITS> {
-    let a = u32::from(U::from(a.sext::<32>())) as i32;
-    let b = u32::from(U::from(b.sext::<32>())) as i32;
-    let c = u32::from(U::from(c)) as i32;
-    S::from((a * b + c).into_u())
+    let product = (a * b).sext::<32>();
+    (product + c).resize()
 }
 
 /// Rounding shift (round-to-nearest-even)
 /// <https://github.com/ucb-bar/gemmini/blob/be2e9f26181658895ebc7ca7f7d6be6210f5cdef/src/main/scala/gemmini/Arithmetic.scala#L97C7-L97C22>
 /// <https://github.com/riscv/riscv-v-spec/blob/master/v-spec.adoc#38-vector-fixed-point-rounding-mode-register-vxrm>
 pub fn rounding_shift(val: S<32>, shamt: U<5>) -> S<32> {
+    let round_down_shifted = val >> shamt;
+
     let val = U::from(val);
-    let val_i32 = u32::from(val) as i32; // $signed(c1)
     let shamt_usize = u32::from(shamt) as usize;
-    let round_down_shifted = val_i32 >> u32::from(shamt);
 
     // d != 0
     let nonzero_shamt = shamt.any(|x| x);
@@ -28,14 +26,14 @@ pub fn rounding_shift(val: S<32>, shamt: U<5>) -> S<32> {
     let zeros = if shamt_usize < 2 {
         false
     } else {
-        let mask = (1 << (shamt_usize - 1)) - 1;
-        (val_i32 & mask) != 0
+        let mask = (U::<32>::from(1) << (shamt_usize - 1)) - U::from(1);
+        (val & mask).any(|x| x)
     };
 
     // d != 0 && v[d-1] && (v[d-2:0]!=0 | v[d])
-    let r = (nonzero_shamt & val[shamt_usize - 1] & (zeros | val[shamt_usize])) as i32;
+    let r = nonzero_shamt & val[shamt_usize - 1] & (zeros | val[shamt_usize]);
 
-    S::from((round_down_shifted + r).into_u())
+    (round_down_shifted + S::from(U::from(r).resize())).resize()
 }
 
 /// Same as `clippedToWidthOf` function.
@@ -45,18 +43,14 @@ where
     [(); M - 1]:,
     [(); (M - 1) + 1]:,
 {
-    let val = u32::from(U::from(val)) as i32;
-
-    // TODO: Fix `sat_min` logic.
-    let sat_max = u32::from(U::from(S::<M>::signed_max())) as i32;
-    let sat_min = u32::from(U::from(S::<M>::signed_min().resize::<20>().sext::<32>())) as i32;
-    let clipped = if val > sat_max {
-        sat_max
-    } else if val < sat_min {
-        sat_min
-    } else {
-        val
-    };
+    // `val` fits in `M` bits if and only if the bits from `M - 1` are all equal to its sign bit, i.e., `high` is 0 or -1.
+    let high = val >> (M - 1);
 
-    S::from(clipped.into_u())
+    if high > S::from(U::from(0)) {
+        S::<M>::signed_max()
+    } else if high < S::from(U::unsigned_max()) {
+        S::<M>::signed_min()
+    } else {
+        val.resize()
+    }
 }
@@ -104,7 +104,7 @@ enum OpBank {
 }
 
 /// Extended ComputeControlSignals.
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Default)]
 struct ControlSignals {
     perform_single_mul: bool, // TODO: Delete this.
 
@@ -9,38 +9,42 @@ use super::*;
 
 /// Chisel MeshWithDelays Wrapper.
 #[magic(ffi::MeshWithDelaysWrapper())]
+#[ffi_source("MeshWithDelaysWrapper.v")]
 pub fn mesh_with_delays_ffi(
     a: Vr<A>,
     b: Vr<B>,
     d: Vr<D>,
     req: I<VrH<MeshReq, TagsInProgress>, { Dep::Helpful }>,
 ) -> Valid<MeshResp> {
-    ffi!("MeshWithDelaysWrapper.v")
+    ffi!()
 }
 
 /// Chisel Mesh Wrapper.
 ///
 /// This module allows students to proceed with future assignments even if they have not completed assignment 5.
 #[magic(ffi::MeshWrapper())]
+#[ffi_source("MeshWrapper.v")]
 pub fn mesh_ffi(in_left: MeshRowData, in_top: MeshColData) -> (MeshRowData, MeshColData) {
-    ffi!("MeshWrapper.v")
+    ffi!()
 }
 
 /// Chisel Transposer Wrapper.
 ///
 /// This module allows students to proceed with future assignments even if they have not completed assignment 5.
 #[magic(ffi::TransposerWrapper())]
+#[ffi_source("TransposerWrapper.v")]
 pub fn transposer_ffi(in_row: Valid<Array<S<INPUT_BITS>, 16>>) -> Valid<Array<S<INPUT_BITS>, 16>> {
-    ffi!("TransposerWrapper.v")
+    ffi!()
 }
 
 /// Chisel PE Wrapper.
 ///
 /// This module allows students to proceed with future assignments even if they have not completed assignment 4.
 #[magic(ffi::PE256Wrapper())]
+#[ffi_source("PE256Wrapper.v")]
 pub fn pe_ffi(
     in_left: Valid<PeRowData>,
     (in_top_data, in_top_control): (Valid<PeColData>, Valid<PeColControl>),
 ) -> (Valid<PeRowData>, (Valid<PeColData>, Valid<PeColControl>)) {
-    ffi!("PE256Wrapper.v")
+    ffi!()
 }
@@ -13,8 +13,9 @@ use crate::gemmini::*;
 pub mod rocc;
 
 /// Funct values.
-#[derive(Debug, Clone, Copy, HEq)]
+#[derive(Debug, Clone, Copy, HEq, Default)]
 pub enum Funct {
+    #[default]
     ConfigCmd,
     Load2Cmd,
     LoadCmd,
@@ -97,7 +98,7 @@ pub struct RoCCInstruction {
     pub rd: U<5>,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Default)]
 pub struct GemminiCmd {
     pub cmd: RoCCCommand<64>,
     pub rob_id: HOption<U<{ clog2(ROB_ENTRIES) }>>,
@@ -3,7 +3,7 @@
 use super::*;
 
 /// <https://github.com/chipsalliance/rocket-chip/blob/master/src/main/scala/rocket/CSR.scala#L18>
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Default)]
 pub struct MStatus {
     pub debug: bool,
     pub cease: bool,
@@ -49,7 +49,7 @@ pub struct MStatus {
 /// RoCC Instruction
 ///
 /// <https://github.com/chipsalliance/rocket-chip/blob/master/src/main/scala/tile/LazyRoCC.scala#L18>
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Default)]
 pub struct RoCCInstruction {
     pub funct: Funct,
     pub rs2: U<5>,
@@ -65,7 +65,7 @@ pub struct RoCCInstruction {
 ///
 /// <https://github.com/chipsalliance/rocket-chip/blob/master/src/main/scala/tile/LazyRoCC.scala#L29>
 /// TODO: Add fields which are inherent in `CoreBundle` in Chisel
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Default)]
 pub struct RoCCCommand<const X_LEN: usize> {
     pub inst: RoCCInstruction,
     pub rs1: U<X_LEN>,
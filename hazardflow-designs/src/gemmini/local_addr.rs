@@ -34,7 +34,7 @@ const METADATA_WIDTH: usize = 1 + 1 + 1 + clog2(8);
 const GARBAGE_BITS: usize = if LOCAL_ADDR_BITS - MAX_ADDR_BITS >= METADATA_WIDTH + 1 { 1 } else { 0 };
 
 /// Local address. The total number of bits for all fields is 32.
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Default)]
 pub struct LocalAddr {
     /// Is Accumulator Address?
     pub is_acc_addr: bool,
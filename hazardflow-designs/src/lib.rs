@@ -36,6 +36,7 @@
 #![allow(incomplete_features)]
 #![feature(adt_const_params)]
 #![feature(generic_const_exprs)]
+#![feature(if_let_guard)]
 #![feature(macro_metavar_expr)]
 // TODO: This is here to suppress clippy complaining about #[synthesize] macro.
 // Later should be removed after build system is matured
@@ -2,5 +2,6 @@
 
 pub use hazardflow_macro::*;
 
+pub use crate::std::sim_bounds::*;
 pub use crate::std::value::*;
 pub use crate::{compiler_magic, display, ffi, hassert, hpanic};
@@ -19,7 +19,7 @@ where
     pub len: U<{ clog2(N + 1) }>,
 }
 
-impl<P: Copy, const N: usize> Default for FifoS<P, N>
+impl<P: Copy + SimDefault, const N: usize> Default for FifoS<P, N>
 where
     [(); clog2(N)]:,
     [(); clog2(N + 1)]:,
@@ -56,7 +56,7 @@ where
     }
 }
 
-impl<P: Copy, R: Copy, const D: Dep> I<VrH<P, R>, D> {
+impl<P: Copy + SimDefault, R: Copy, const D: Dep> I<VrH<P, R>, D> {
     /// FIFO queue with `N` entries.
     ///
     /// This queue is fully pipelined, which means it can accept a new element every cycle.
@@ -79,7 +79,7 @@ impl<P: Copy, R: Copy, const D: Dep> I<VrH<P, R>, D> {
     }
 }
 
-impl<const D: Dep, const N: usize, P: Copy, R: Copy> I<VrH<P, (R, FifoS<P, N>)>, D>
+impl<const D: Dep, const N: usize, P: Copy + SimDefault, R: Copy> I<VrH<P, (R, FifoS<P, N>)>, D>
 where
     [(); clog2(N)]:,
     [(); clog2(N + 1)]:,
@@ -162,7 +162,7 @@ where
     }
 }
 
-impl<const D: Dep, const N: usize, P: Copy> I<VrH<P, FifoS<P, N>>, D>
+impl<const D: Dep, const N: usize, P: Copy + SimDefault> I<VrH<P, FifoS<P, N>>, D>
 where
     [(); clog2(N)]:,
     [(); clog2(N + 1)]:,
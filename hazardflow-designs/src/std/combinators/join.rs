@@ -11,7 +11,9 @@ pub trait JoinExt: Interface {
     fn join(self) -> Self::E;
 }
 
-impl<P1: Copy, P2: Copy, R1: Copy, R2: Copy, const D: Dep> JoinExt for (I<ValidH<P1, R1>, D>, I<ValidH<P2, R2>, D>) {
+impl<P1: Copy, P2: Copy, R1: Copy + SimDefault, R2: Copy + SimDefault, const D: Dep> JoinExt
+    for (I<ValidH<P1, R1>, D>, I<ValidH<P2, R2>, D>)
+{
     type E = I<ValidH<(P1, P2), (R1, R2)>, D>;
 
     /// Joins two `ValidH` hazard interfaces.
@@ -36,7 +38,7 @@ impl<P1: Copy, P2: Copy, R1: Copy, R2: Copy, const D: Dep> JoinExt for (I<ValidH
 
 macro_rules! impl_i_valid_h_join {
     ($($P:ident),+; $($R:ident),+) => {
-        impl<$($P: Copy,)+ $($R: Copy,)+ const D: Dep> JoinExt for ($(I<ValidH<$P, $R>, D>,)+) {
+        impl<$($P: Copy,)+ $($R: Copy + SimDefault,)+ const D: Dep> JoinExt for ($(I<ValidH<$P, $R>, D>,)+) {
             type E = I<ValidH<($($P,)+), ($($R,)+)>, D>;
 
             /// A variation of [`join`] for 3-12 `ValidH` hazard interfaces. See the 2-tuple version for more
@@ -70,7 +72,7 @@ impl_i_valid_h_join! { P1, P2, P3, P4, P5, P6, P7, P8, P9, P10; R1, R2, R3, R4,
 impl_i_valid_h_join! { P1, P2, P3, P4, P5, P6, P7, P8, P9, P10, P11; R1, R2, R3, R4, R5, R6, R7, R8, R9, R10, R11 }
 impl_i_valid_h_join! { P1, P2, P3, P4, P5, P6, P7, P8, P9, P10, P11, P12; R1, R2, R3, R4, R5, R6, R7, R8, R9, R10, R11, R12 }
 
-impl<P: Copy, R: Copy, const D: Dep, const N: usize> JoinExt for [I<ValidH<P, R>, D>; N] {
+impl<P: Copy + SimDefault, R: Copy, const D: Dep, const N: usize> JoinExt for [I<ValidH<P, R>, D>; N] {
     type E = I<ValidH<Array<P, N>, Array<R, N>>, D>;
 
     /// Joins `N` `ValidH` hazard interfaces.
@@ -146,7 +148,7 @@ impl_valid_join_valid! { P1, P2, P3, P4, P5, P6, P7, P8, P9, P10, P11 }
 impl_valid_join_valid! { P1, P2, P3, P4, P5, P6, P7, P8, P9, P10, P11, P12 }
 
 // Joins N valid interfaces.
-impl<P: Copy, const N: usize> JoinValidExt for [Valid<P>; N] {
+impl<P: Copy + SimDefault, const N: usize> JoinValidExt for [Valid<P>; N] {
     type E = Valid<Array<P, N>>;
 
     /// A variation of [`join`] for valid interfaces, that has the correct resolver type.
@@ -164,7 +166,7 @@ impl<P: Copy, const N: usize> JoinValidExt for [Valid<P>; N] {
     }
 }
 
-impl<P1: Copy, P2: Copy, R1: Copy, R2: Copy> JoinExt
+impl<P1: Copy, P2: Copy, R1: Copy + SimDefault, R2: Copy + SimDefault> JoinExt
     for (I<VrH<P1, R1>, { Dep::Helpful }>, I<VrH<P2, R2>, { Dep::Helpful }>)
 {
     type E = I<VrH<(P1, P2), (R1, R2)>, { Dep::Helpful }>;
@@ -204,7 +206,7 @@ impl<P1: Copy, P2: Copy, R1: Copy, R2: Copy> JoinExt
 
 macro_rules! impl_i_vr_h_join {
     ($($P:ident),+; $($R:ident),+; $($index:tt),+) => {
-        impl<$($P: Copy,)+ $($R: Copy,)+> JoinExt for ($(I<VrH<$P, $R>, { Dep::Helpful }>,)+) {
+        impl<$($P: Copy,)+ $($R: Copy + SimDefault,)+> JoinExt for ($(I<VrH<$P, $R>, { Dep::Helpful }>,)+) {
             type E = I<VrH<($($P,)+), ($($R,)+)>, { Dep::Helpful }>;
 
             /// A variation of [`join`] for 3-12 `VrH` hazard interfaces. See the 2-tuple version for more information.
@@ -277,7 +279,7 @@ impl<P1: Copy, P2: Copy, P3: Copy, R: Copy, const D: Dep> JoinExt for (I<VrH<P1,
     }
 }
 
-impl<P: Copy, R: Copy, const N: usize> JoinExt for [I<VrH<P, R>, { Dep::Helpful }>; N] {
+impl<P: Copy + SimDefault, R: Copy + SimDefault, const N: usize> JoinExt for [I<VrH<P, R>, { Dep::Helpful }>; N] {
     type E = I<VrH<Array<P, N>, Array<R, N>>, { Dep::Helpful }>;
 
     /// Joins `N` `VrH` hazard interfaces.
@@ -363,7 +365,7 @@ impl_vr_join_vr! { P1, P2, P3, P4, P5, P6, P7, P8, P9, P10 }
 impl_vr_join_vr! { P1, P2, P3, P4, P5, P6, P7, P8, P9, P10, P11 }
 impl_vr_join_vr! { P1, P2, P3, P4, P5, P6, P7, P8, P9, P10, P11, P12 }
 
-impl<P: Copy, const N: usize> JoinVrExt for [Vr<P>; N] {
+impl<P: Copy + SimDefault, const N: usize> JoinVrExt for [Vr<P>; N] {
     type E = Vr<Array<P, N>>;
 
     /// A variation of [`join`] for valid-ready interfaces, that has the correct resolver type.
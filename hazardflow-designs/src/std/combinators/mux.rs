@@ -13,7 +13,7 @@ where [(); clog2(N)]:
     fn mux(self, cntl: Valid<U<{ clog2(N) }>>) -> Self::E;
 }
 
-impl<P: Copy, R: Copy, const N: usize, const D: Dep> MuxExt<N> for [I<ValidH<P, R>, D>; N]
+impl<P: Copy, R: Copy + SimDefault, const N: usize, const D: Dep> MuxExt<N> for [I<ValidH<P, R>, D>; N]
 where [(); clog2(N)]:
 {
     type E = I<ValidH<P, R>, D>;
@@ -41,7 +41,7 @@ where [(); clog2(N)]:
     }
 }
 
-impl<P: Copy, R: Copy, const N: usize, const D: Dep> MuxExt<N> for [I<VrH<P, R>, D>; N]
+impl<P: Copy, R: Copy + SimDefault, const N: usize, const D: Dep> MuxExt<N> for [I<VrH<P, R>, D>; N]
 where [(); clog2(N)]:
 {
     type E = I<VrH<P, R>, D>;
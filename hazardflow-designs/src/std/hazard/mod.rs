@@ -46,6 +46,9 @@ pub enum Dep {
 #[must_use]
 pub struct I<H: Hazard, const D: Dep> {
     _marker: PhantomData<H>,
+    /// Wire in the simulator.
+    #[cfg(feature = "sim")]
+    wire: usize,
 }
 
 impl<H: Hazard, const D: Dep> Interface for I<H, D> {
@@ -55,10 +58,40 @@ impl<H: Hazard, const D: Dep> Interface for I<H, D> {
     ///
     /// `Some(p)` means a valid payload with data `p`, and `None` means an invalid payload.
     type Fwd = HOption<H::P>;
+
+    #[cfg(feature = "sim")]
+    fn sim_from_wires(wires: &mut dyn Iterator<Item = usize>) -> Self {
+        I { _marker: PhantomData, wire: sim::next_wire(wires) }
+    }
+
+    #[cfg(feature = "sim")]
+    fn sim_wires(&self, wires: &mut Vec<usize>) {
+        wires.push(self.wire);
+    }
+
+    #[cfg(feature = "sim")]
+    fn sim_fwd(&self) -> Self::Fwd {
+        sim::read_fwd(self.wire).unwrap_or(None)
+    }
+
+    #[cfg(feature = "sim")]
+    fn sim_bwd(&self) -> Option<Self::Bwd> {
+        sim::read_bwd(self.wire)
+    }
+
+    #[cfg(feature = "sim")]
+    fn sim_set_fwd(&self, fwd: Self::Fwd) {
+        sim::write_fwd(self.wire, fwd)
+    }
+
+    #[cfg(feature = "sim")]
+    fn sim_set_bwd(&self, bwd: Self::Bwd) {
+        sim::write_bwd(self.wire, bwd)
+    }
 }
 
 /// Wrapping resolver type for `AndH`.
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Default, Clone, Copy)]
 pub struct Ready<R> {
     /// Whether the receiver of the payload is ready to accept a new payload.
     pub ready: bool,
@@ -77,7 +110,8 @@ impl<R: Copy> Ready<R> {
     // TODO: We should add `inner` as parameter to set the inner hazard value when creating invalid signal.
     //       This is needed because the inner hazard value should be allowed as don't-care value only when explicit `unsafe` reasoning by user is given.
     #[allow(unreachable_code)]
-    pub fn invalid() -> Self {
+    pub fn invalid() -> Self
+    where R: SimDefault {
         Self { ready: false, inner: unsafe { x::<R>() } }
     }
 
@@ -62,9 +62,33 @@ pub trait Interface: Sized {
         _init_state: S,
         _f: impl Fn(Self::Fwd, E::Bwd, S) -> (E::Fwd, Self::Bwd, S),
     ) -> E {
-        compiler_magic!()
+        compiler_magic!(sim: super::sim::fsm(self, _init_state, _f))
     }
 
+    /// Builds the interface from the wires of the simulator.
+    #[cfg(feature = "sim")]
+    fn sim_from_wires(wires: &mut dyn Iterator<Item = usize>) -> Self;
+
+    /// Appends the wires of the interface to `wires`.
+    #[cfg(feature = "sim")]
+    fn sim_wires(&self, wires: &mut Vec<usize>);
+
+    /// Returns the forward signal in the simulator.
+    #[cfg(feature = "sim")]
+    fn sim_fwd(&self) -> Self::Fwd;
+
+    /// Returns the backward signal in the simulator, or `None` if it is not driven yet.
+    #[cfg(feature = "sim")]
+    fn sim_bwd(&self) -> Option<Self::Bwd>;
+
+    /// Drives the forward signal in the simulator.
+    #[cfg(feature = "sim")]
+    fn sim_set_fwd(&self, fwd: Self::Fwd);
+
+    /// Drives the backward signal in the simulator.
+    #[cfg(feature = "sim")]
+    fn sim_set_bwd(&self, bwd: Self::Bwd);
+
     /// Combines the module to the given interface and returns the egress interface.
     fn comb<E: Interface>(self, m: impl FnOnce(Self) -> E) -> E {
         m(self)
@@ -74,11 +98,61 @@ pub trait Interface: Sized {
 impl Interface for () {
     type Bwd = ();
     type Fwd = ();
+
+    #[cfg(feature = "sim")]
+    fn sim_from_wires(_wires: &mut dyn Iterator<Item = usize>) -> Self {}
+
+    #[cfg(feature = "sim")]
+    fn sim_wires(&self, _wires: &mut Vec<usize>) {}
+
+    #[cfg(feature = "sim")]
+    fn sim_fwd(&self) {}
+
+    #[cfg(feature = "sim")]
+    fn sim_bwd(&self) -> Option<()> {
+        core::option::Option::Some(())
+    }
+
+    #[cfg(feature = "sim")]
+    fn sim_set_fwd(&self, _fwd: ()) {}
+
+    #[cfg(feature = "sim")]
+    fn sim_set_bwd(&self, _bwd: ()) {}
 }
 
 impl<If: Interface, const N: usize> Interface for [If; N] {
     type Bwd = Array<If::Bwd, N>;
     type Fwd = Array<If::Fwd, N>;
+
+    #[cfg(feature = "sim")]
+    fn sim_from_wires(wires: &mut dyn Iterator<Item = usize>) -> Self {
+        core::array::from_fn(|_| If::sim_from_wires(wires))
+    }
+
+    #[cfg(feature = "sim")]
+    fn sim_wires(&self, wires: &mut Vec<usize>) {
+        self.iter().for_each(|i| i.sim_wires(wires));
+    }
+
+    #[cfg(feature = "sim")]
+    fn sim_fwd(&self) -> Self::Fwd {
+        Array::from(core::array::from_fn(|idx| self[idx].sim_fwd()))
+    }
+
+    #[cfg(feature = "sim")]
+    fn sim_bwd(&self) -> Option<Self::Bwd> {
+        self.iter().map(If::sim_bwd).collect::<Option<Vec<_>>>().map(Array::sim_from_vec)
+    }
+
+    #[cfg(feature = "sim")]
+    fn sim_set_fwd(&self, fwd: Self::Fwd) {
+        self.iter().enumerate().for_each(|(idx, i)| i.sim_set_fwd(fwd[idx]));
+    }
+
+    #[cfg(feature = "sim")]
+    fn sim_set_bwd(&self, bwd: Self::Bwd) {
+        self.iter().enumerate().for_each(|(idx, i)| i.sim_set_bwd(bwd[idx]));
+    }
 }
 
 macro_rules! impl_interface_tuple {
@@ -86,6 +160,36 @@ macro_rules! impl_interface_tuple {
         impl<$($a: Interface,)+> Interface for ($($a,)+) {
             type Fwd = ($($a::Fwd,)+);
             type Bwd = ($($a::Bwd,)+);
+
+            #[cfg(feature = "sim")]
+            fn sim_from_wires(wires: &mut dyn Iterator<Item = usize>) -> Self {
+                ($($a::sim_from_wires(wires),)+)
+            }
+
+            #[cfg(feature = "sim")]
+            fn sim_wires(&self, wires: &mut Vec<usize>) {
+                $(${ignore($a)} self.${index()}.sim_wires(wires);)+
+            }
+
+            #[cfg(feature = "sim")]
+            fn sim_fwd(&self) -> Self::Fwd {
+                ($(${ignore($a)} self.${index()}.sim_fwd(),)+)
+            }
+
+            #[cfg(feature = "sim")]
+            fn sim_bwd(&self) -> Option<Self::Bwd> {
+                core::option::Option::Some(($(${ignore($a)} self.${index()}.sim_bwd()?,)+))
+            }
+
+            #[cfg(feature = "sim")]
+            fn sim_set_fwd(&self, fwd: Self::Fwd) {
+                $(${ignore($a)} self.${index()}.sim_set_fwd(fwd.${index()});)+
+            }
+
+            #[cfg(feature = "sim")]
+            fn sim_set_bwd(&self, bwd: Self::Bwd) {
+                $(${ignore($a)} self.${index()}.sim_set_bwd(bwd.${index()});)+
+            }
         }
     }
 }
@@ -55,6 +55,9 @@ pub mod combinators;
 pub mod hazard;
 pub mod interface;
 pub mod module;
+#[cfg(feature = "sim")]
+pub mod sim;
+pub mod sim_bounds;
 pub mod utils;
 pub mod valid;
 pub mod valid_ready;
@@ -67,14 +70,37 @@ pub use combinators::*;
 pub use hazard::*;
 pub use interface::*;
 pub use module::*;
+pub use sim_bounds::*;
 pub use utils::*;
 pub use valid::*;
 pub use valid_ready::*;
 pub use value::*;
 
 /// Indicates that the function is implemented as a compiler magic.
+///
+/// With the `sim` feature, `compiler_magic!(sim: expr)` evaluates `expr` instead, so that the function can be executed
+/// in Rust. See `std::sim` for more information.
+#[cfg(not(feature = "sim"))]
 #[macro_export]
 macro_rules! compiler_magic {
+    (sim: $sim:expr) => {
+        todo!()
+    };
+    ($($msg:expr)?) => {
+        todo!($($msg)?)
+    };
+}
+
+/// Indicates that the function is implemented as a compiler magic.
+///
+/// With the `sim` feature, `compiler_magic!(sim: expr)` evaluates `expr` instead, so that the function can be executed
+/// in Rust. See [`sim`] for more information.
+#[cfg(feature = "sim")]
+#[macro_export]
+macro_rules! compiler_magic {
+    (sim: $sim:expr) => {
+        $sim
+    };
     ($($msg:expr)?) => {
         todo!($($msg)?)
     };
@@ -14,7 +14,7 @@ use crate::prelude::*;
 pub fn module_split<I1: Interface, I2: Interface, O1: Interface, O2: Interface>(
     _m: impl FnOnce(I1, I2) -> (O1, O2),
 ) -> (fn(I1) -> O1, fn(I2) -> O2) {
-    compiler_magic!()
+    compiler_magic!(sim: super::sim::module_split(_m))
 }
 
 /// Splits a module into three modules.
@@ -35,8 +35,8 @@ pub fn module_split3<I1: Interface, I2: Interface, I3: Interface, O1: Interface,
 // TODO: Modify `f` to be `f: impl FnOnce(n: usize) -> T`.
 #[magic(module::from_fn)]
 pub fn from_fn<I: Interface, O: Interface, J: Interface, T, const N: usize>(f: T) -> [fn(I, J) -> (O, J); N]
-where T: FnOnce(I, J) -> (O, J) {
-    compiler_magic!()
+where T: FnOnce(I, J) -> (O, J) + SimCopy {
+    compiler_magic!(sim: super::sim::from_fn(f))
 }
 
 /// Generates a 1D systolic array from an array of modules.
@@ -51,15 +51,15 @@ where T: FnOnce(I, J) -> (O, J) {
 #[magic(module::seq)]
 pub fn seq<I: Interface, O: Interface, J: Interface, const N: usize>(
     ms: [fn(I, J) -> (O, J); N],
-) -> impl FnOnce([I; N], J) -> ([O; N], J) {
+) -> impl FnOnce([I; N], J) -> ([O; N], J) + SimCopy {
     // This should be primitive?
-    |is, j| compiler_magic!()
+    move |is, j| compiler_magic!(sim: super::sim::seq(ms, is, j))
 }
 
 /// Flips a module's input and output.
 pub fn flip<I1: Interface, I2: Interface, O1: Interface, O2: Interface>(
-    f: impl FnOnce(I1, I2) -> (O1, O2),
-) -> impl FnOnce(I2, I1) -> (O2, O1) {
+    f: impl FnOnce(I1, I2) -> (O1, O2) + SimCopy,
+) -> impl FnOnce(I2, I1) -> (O2, O1) + SimCopy {
     move |i2, i1| {
         let (o1, o2) = f(i1, i2);
         (o2, o1)
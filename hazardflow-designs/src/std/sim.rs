@@ -0,0 +1,671 @@
+//! Software simulation of modules.
+//!
+//! With the `sim` feature, the compiler magics ([`Interface::fsm`], [`module_split`](super::module_split), [`from_fn`](super::from_fn), [`seq`](super::seq), and the
+//! magics of the builtin value types) are executable in Rust, so that a module can be tested with plain Rust values
+//! before it is compiled.
+//!
+//! # Semantics
+//!
+//! [`Sim`] simulates a module function `m: Fn(I) -> E` cycle by cycle. Each `fsm` call is a node which keeps its state
+//! across cycles, and each hazard interface is a wire which holds its payload and resolver.
+//!
+//! In each cycle, the scheduler evaluates the module in passes. The closures given to `fsm` may borrow the locals of the
+//! module function (e.g., `map` borrows its `f`), so they cannot outlive the module function. Instead of storing them,
+//! the scheduler runs the module function again in every pass, and each `fsm` call applies its closure to the ingress
+//! payload and egress resolver which are most recently written. Nodes and wires are identified by the order of the
+//! calls, so the module function must build the same structure in every pass, which the compiler already requires.
+//!
+//! The values are not compared, so the scheduler runs one more pass than there are payloads and resolvers, which is
+//! enough for a value to propagate through the longest combinational path. A payload which is not driven yet is
+//! invalid, and a node whose egress resolver is not driven yet is skipped in the pass.
+//!
+//! After the last pass, its `display!`s and `hassert!`s are reported, and every node moves to its next state. The
+//! outputs of `display!` are collected in [`Sim::displays`].
+//!
+//! # Limitations
+//!
+//! - Combinational loops are not detected.
+//! - The payloads, resolvers and states persist across passes, so they should not borrow the locals of the module
+//!     function. They are checked by their types, but the lifetimes are not distinguished.
+//! - The modules returned by [`module_split`](super::module_split) with the same closure type are matched in their call order.
+//! - The modules generated by [`from_fn`](super::from_fn) call the closure of the same type given most recently.
+//! - FFI modules cannot be simulated.
+//!
+//! # Example
+//!
+//! ```
+//! # #![allow(incomplete_features)]
+//! # #![feature(generic_const_exprs)]
+//! use hazardflow_designs::std::sim::Sim;
+//! use hazardflow_designs::std::*;
+//!
+//! let mut sim = Sim::new(|i: Vr<U<8>>| i.fifo::<2>());
+//!
+//! // The FIFO is empty, so it accepts the payload but does not output anything yet.
+//! let (ep, ir) = sim.step(Some(U::from(3)), Ready::valid(()));
+//! assert!(ep.is_none() && ir.ready);
+//!
+//! let (ep, ir) = sim.step(Some(U::from(5)), Ready::invalid());
+//! assert_eq!(ep.map(u32::from), Some(3));
+//! assert!(ir.ready);
+//!
+//! // The FIFO is full.
+//! let (ep, ir) = sim.step(None, Ready::valid(()));
+//! assert_eq!(ep.map(u32::from), Some(3));
+//! assert!(!ir.ready);
+//!
+//! let (ep, _) = sim.step(None, Ready::valid(()));
+//! assert_eq!(ep.map(u32::from), Some(5));
+//! ```
+
+#![allow(clippy::type_complexity)]
+
+use core::any::{type_name, TypeId};
+use core::cell::RefCell;
+use core::fmt;
+use core::marker::PhantomData;
+use core::mem::{align_of, size_of, MaybeUninit};
+use std::panic::{catch_unwind, resume_unwind, AssertUnwindSafe};
+
+use super::{Array, Interface, SimArg, SimArgs, S, U};
+
+thread_local! {
+    static CONTEXT: RefCell<Option<Context>> = const { RefCell::new(None) };
+}
+
+/// Runs `f` with the context of the module being simulated.
+fn with<T>(f: impl FnOnce(&mut Context) -> T) -> T {
+    CONTEXT.with(|cx| {
+        let mut cx = cx.borrow_mut();
+        f(cx.as_mut().expect("no module is being simulated; run it with `hazardflow_designs::std::sim::Sim`"))
+    })
+}
+
+/// Simulator of a module.
+pub struct Sim<I: Interface, E: Interface, M: Fn(I) -> E> {
+    module: M,
+    context: Option<Context>,
+    cycle: u64,
+    displays: Vec<(u64, String)>,
+    _marker: PhantomData<fn(I) -> E>,
+}
+
+impl<I: Interface, E: Interface, M: Fn(I) -> E> fmt::Debug for Sim<I, E, M> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Sim").field("cycle", &self.cycle).finish_non_exhaustive()
+    }
+}
+
+impl<I: Interface, E: Interface, M: Fn(I) -> E> Sim<I, E, M> {
+    /// Creates a new simulator of the module `module`.
+    ///
+    /// The states of the module are initialized when they are first evaluated.
+    pub fn new(module: M) -> Self {
+        Self { module, context: Some(Context::default()), cycle: 0, displays: vec![], _marker: PhantomData }
+    }
+
+    /// Returns the number of elapsed cycles.
+    pub fn cycle(&self) -> u64 {
+        self.cycle
+    }
+
+    /// Simulates one cycle.
+    ///
+    /// The module is given the ingress payload `ip` and the egress resolver `er`, and the egress payload and the
+    /// ingress resolver of this cycle are returned. Then, the states of the module are updated.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a `hassert!` fails or if a resolver is never driven.
+    pub fn step(&mut self, ip: I::Fwd, er: E::Bwd) -> (E::Fwd, I::Bwd) {
+        let (ep, ir, report) = self.enter(|module| Self::settle(module, ip, er));
+
+        self.displays.extend(report.displays.into_iter().map(|line| (self.cycle, line)));
+        if let Some(msg) = report.failure {
+            panic!("assertion failed at cycle {}: {msg}", self.cycle);
+        }
+
+        let cx = self.context.as_mut().unwrap();
+        for node in &mut cx.nodes {
+            node.state = node.next.clone();
+        }
+        self.cycle += 1;
+
+        (ep, ir)
+    }
+
+    /// Returns the `display!` outputs so far, with the cycles they were printed at.
+    pub fn displays(&self) -> &[(u64, String)] {
+        &self.displays
+    }
+
+    /// Takes the `display!` outputs so far.
+    pub fn take_displays(&mut self) -> Vec<(u64, String)> {
+        core::mem::take(&mut self.displays)
+    }
+
+    /// Resets the states of the module to their initial values.
+    pub fn reset(&mut self) {
+        for node in &mut self.context.as_mut().unwrap().nodes {
+            node.state = node.init.clone();
+        }
+    }
+
+    /// Runs `f` with the context of this simulator.
+    fn enter<T>(&mut self, f: impl FnOnce(&M) -> T) -> T {
+        /// Moves the context back to the simulator even if `f` panics.
+        struct Guard<'a>(&'a mut Option<Context>);
+
+        impl Drop for Guard<'_> {
+            fn drop(&mut self) {
+                *self.0 = CONTEXT.with(|cx| cx.borrow_mut().take());
+            }
+        }
+
+        CONTEXT.with(|cx| {
+            let mut cx = cx.borrow_mut();
+            assert!(cx.is_none(), "a module is already being simulated");
+            *cx = self.context.take();
+        });
+        let _guard = Guard(&mut self.context);
+        f(&self.module)
+    }
+
+    /// Evaluates the module until the values propagate through every combinational path.
+    fn settle(module: &M, ip: I::Fwd, er: E::Bwd) -> (E::Fwd, I::Bwd, Report) {
+        let mut passes = 0;
+        loop {
+            with(Context::begin_pass);
+
+            let ingress = alloc::<I>();
+            let ingress_wires = wires(&ingress);
+            ingress.sim_set_fwd(ip);
+            let egress = module(ingress);
+            egress.sim_set_bwd(er);
+
+            // A combinational path visits each payload and resolver at most once.
+            passes += 1;
+            if passes > 2 * with(|cx| cx.wires.len()) {
+                let (skipped, report) = with(|cx| (cx.skipped, core::mem::take(&mut cx.report)));
+                if let Some(node) = skipped {
+                    panic!("the egress resolver of the `fsm` node #{node} is never driven");
+                }
+                let ir = I::sim_from_wires(&mut ingress_wires.into_iter()).sim_bwd();
+                return (egress.sim_fwd(), ir.expect("the ingress resolver is never driven"), report);
+            }
+        }
+    }
+}
+
+/// Messages reported in a pass.
+#[derive(Debug, Default)]
+struct Report {
+    displays: Vec<String>,
+    failure: Option<String>,
+}
+
+/// Simulation context.
+#[derive(Debug, Default)]
+struct Context {
+    /// Wires, indexed in their allocation order.
+    wires: Vec<Wire>,
+    /// Next wire to allocate in this pass.
+    next_wire: usize,
+    /// States of `fsm` nodes, indexed in their call order.
+    nodes: Vec<Node>,
+    /// Next node to evaluate in this pass.
+    next_node: usize,
+    /// First node skipped in this pass because its egress resolver is not driven yet.
+    skipped: Option<usize>,
+    /// Modules split by `module_split` in this pass.
+    splits: Vec<Split>,
+    /// Closures given to `from_fn` in this pass.
+    fns: Vec<Slot>,
+    /// Messages reported in this pass.
+    report: Report,
+}
+
+impl Context {
+    fn begin_pass(&mut self) {
+        self.next_wire = 0;
+        self.next_node = 0;
+        self.skipped = None;
+        self.splits.clear();
+        self.fns.clear();
+        self.report = Report::default();
+    }
+
+    fn slot(&mut self, id: usize, fwd: bool) -> &mut Option<Slot> {
+        if self.wires.len() <= id {
+            self.wires.resize_with(id + 1, Wire::default);
+        }
+        let wire = &mut self.wires[id];
+        if fwd {
+            &mut wire.fwd
+        } else {
+            &mut wire.bwd
+        }
+    }
+
+    fn write<T: Copy>(&mut self, id: usize, fwd: bool, value: T) {
+        match self.slot(id, fwd) {
+            Some(slot) => slot.write(value),
+            slot @ None => *slot = Some(Slot::new(value)),
+        }
+    }
+
+    /// Returns the value of the wire, or `None` if it is not driven yet.
+    fn read<T: Copy>(&mut self, id: usize, fwd: bool) -> Option<T> {
+        // SAFETY: The values of the wires do not borrow the locals of the module function.
+        self.slot(id, fwd).as_ref().map(|slot| unsafe { slot.read::<T>() })
+    }
+}
+
+/// Wire of a hazard interface.
+#[derive(Debug, Default)]
+struct Wire {
+    fwd: Option<Slot>,
+    bwd: Option<Slot>,
+}
+
+/// State of an `fsm` node.
+#[derive(Debug)]
+struct Node {
+    init: Slot,
+    state: Slot,
+    next: Slot,
+}
+
+/// Module split by `module_split`.
+#[derive(Debug)]
+struct Split {
+    /// Type of the closure and the interfaces.
+    key: TypeId,
+    /// Wires of the inner ingress interface and the egress interface of each half, and whether it is taken.
+    halves: [(Vec<usize>, Vec<usize>, bool); 2],
+}
+
+/// Returns the `TypeId` of `T`, whose lifetimes are erased.
+fn type_id<T: ?Sized>() -> TypeId {
+    trait NonStaticAny {
+        fn get_type_id(&self) -> TypeId
+        where Self: 'static;
+    }
+
+    impl<T: ?Sized> NonStaticAny for PhantomData<T> {
+        fn get_type_id(&self) -> TypeId
+        where Self: 'static {
+            TypeId::of::<T>()
+        }
+    }
+
+    let phantom = PhantomData::<T>;
+    // SAFETY: `TypeId::of` does not depend on the lifetimes, and `PhantomData` does not hold any value.
+    let phantom = unsafe { core::mem::transmute::<&dyn NonStaticAny, &(dyn NonStaticAny + 'static)>(&phantom) };
+    phantom.get_type_id()
+}
+
+/// Chunk of [`Slot`], which is aligned enough for the builtin value types.
+#[derive(Debug, Clone, Copy)]
+#[repr(align(16))]
+struct Chunk(MaybeUninit<[u8; 16]>);
+
+/// Type-erased storage of a `Copy` value.
+///
+/// The values stored in the simulator are not necessarily `'static`, so `Any` cannot be used. Instead, the type is
+/// checked by its `TypeId` with the lifetimes erased.
+#[derive(Debug, Clone)]
+struct Slot {
+    ty: TypeId,
+    data: Vec<Chunk>,
+}
+
+impl Slot {
+    fn new<T: Copy>(value: T) -> Self {
+        assert!(align_of::<T>() <= align_of::<Chunk>(), "`{}` is aligned too much", type_name::<T>());
+        let chunks = size_of::<T>().div_ceil(size_of::<Chunk>());
+        let mut slot = Self { ty: type_id::<T>(), data: vec![Chunk(MaybeUninit::uninit()); chunks] };
+        slot.write(value);
+        slot
+    }
+
+    /// Reads the value.
+    ///
+    /// # Safety
+    ///
+    /// The stored value should be valid for the lifetimes of `T`, which are not checked.
+    unsafe fn read<T: Copy>(&self) -> T {
+        assert!(self.ty == type_id::<T>(), "the module is built differently across passes");
+        // SAFETY: `data` holds a value of `T`, which is aligned and large enough.
+        unsafe { self.data.as_ptr().cast::<T>().read() }
+    }
+
+    /// Writes the value.
+    fn write<T: Copy>(&mut self, value: T) {
+        assert!(self.ty == type_id::<T>(), "the module is built differently across passes");
+        // SAFETY: `data` is allocated for `T`, which is aligned and large enough.
+        unsafe { self.data.as_mut_ptr().cast::<T>().write(value) }
+    }
+}
+
+/// Allocates an interface with fresh wires.
+fn alloc<If: Interface>() -> If {
+    with(|cx| {
+        let mut ids = cx.next_wire..;
+        let interface = If::sim_from_wires(&mut ids);
+        cx.next_wire = ids.start;
+        interface
+    })
+}
+
+/// Returns the wires of the interface.
+fn wires<If: Interface>(interface: &If) -> Vec<usize> {
+    let mut wires = vec![];
+    interface.sim_wires(&mut wires);
+    wires
+}
+
+/// Connects the interface `from` to `to`.
+fn connect<If: Interface>(from: If, to: If) {
+    to.sim_set_fwd(from.sim_fwd());
+    if let Some(bwd) = to.sim_bwd() {
+        from.sim_set_bwd(bwd);
+    }
+}
+
+/// Returns the payload of the wire, or `None` if it is not driven yet.
+pub(crate) fn read_fwd<T: Copy>(wire: usize) -> Option<T> {
+    with(|cx| cx.read(wire, true))
+}
+
+/// Returns the resolver of the wire, or `None` if it is not driven yet.
+pub(crate) fn read_bwd<T: Copy>(wire: usize) -> Option<T> {
+    with(|cx| cx.read(wire, false))
+}
+
+/// Drives the payload of the wire.
+pub(crate) fn write_fwd<T: Copy>(wire: usize, value: T) {
+    with(|cx| cx.write(wire, true, value))
+}
+
+/// Drives the resolver of the wire.
+pub(crate) fn write_bwd<T: Copy>(wire: usize, value: T) {
+    with(|cx| cx.write(wire, false, value))
+}
+
+/// Allocates the wires of an interface built in [`Interface::sim_from_wires`].
+pub(crate) fn next_wire(wires: &mut dyn Iterator<Item = usize>) -> usize {
+    wires.next().expect("not enough wires for the interface")
+}
+
+/// Simulates [`Interface::fsm`].
+pub(crate) fn fsm<If: Interface, E: Interface, S: Copy>(
+    ingress: If,
+    init_state: S,
+    f: impl Fn(If::Fwd, E::Bwd, S) -> (E::Fwd, If::Bwd, S),
+) -> E {
+    let egress = alloc::<E>();
+    let (node, state) = with(|cx| {
+        let node = cx.next_node;
+        cx.next_node += 1;
+        if node == cx.nodes.len() {
+            let init = Slot::new(init_state);
+            cx.nodes.push(Node { init: init.clone(), state: init.clone(), next: init });
+        }
+        // SAFETY: The states do not borrow the locals of the module function.
+        (node, unsafe { cx.nodes[node].state.read::<S>() })
+    });
+
+    let Some(er) = egress.sim_bwd() else {
+        with(|cx| {
+            cx.skipped.get_or_insert(node);
+        });
+        return egress;
+    };
+    let (ep, ir, s_next) = match catch_unwind(AssertUnwindSafe(|| f(ingress.sim_fwd(), er, state))) {
+        Ok(out) => out,
+        // The failure of `hpanic!` is already reported, and it remains only if it is reached in the last pass.
+        Err(payload) if payload.is::<Abort>() => return egress,
+        Err(payload) => resume_unwind(payload),
+    };
+    egress.sim_set_fwd(ep);
+    ingress.sim_set_bwd(ir);
+
+    with(|cx| cx.nodes[node].next.write(s_next));
+    egress
+}
+
+/// Unwinding payload which stops evaluating an `fsm` node.
+struct Abort;
+
+/// Stops evaluating the current `fsm` node after `hpanic!`, which has no value to return.
+pub(crate) fn abort_node<T>() -> T {
+    resume_unwind(Box::new(Abort))
+}
+
+/// Simulates [`module_split`](super::module_split).
+pub(crate) fn module_split<I1: Interface, I2: Interface, O1: Interface, O2: Interface, T>(
+    m: T,
+) -> (fn(I1) -> O1, fn(I2) -> O2)
+where T: FnOnce(I1, I2) -> (O1, O2) {
+    let (i1, i2) = (alloc::<I1>(), alloc::<I2>());
+    let (w1, w2) = (wires(&i1), wires(&i2));
+    let (o1, o2) = m(i1, i2);
+    let split =
+        Split { key: type_id::<(T, I1, I2, O1, O2)>(), halves: [(w1, wires(&o1), false), (w2, wires(&o2), false)] };
+    with(|cx| cx.splits.push(split));
+
+    (split_half::<I1, O1, (T, I1, I2, O1, O2), 0>, split_half::<I2, O2, (T, I1, I2, O1, O2), 1>)
+}
+
+/// The `K`-th half of a module split by `module_split`.
+fn split_half<If: Interface, O: Interface, Key, const K: usize>(i: If) -> O {
+    let (inner, o) = with(|cx| {
+        let key = type_id::<Key>();
+        let split = cx
+            .splits
+            .iter_mut()
+            .find(|split| split.key == key && !split.halves[K].2)
+            .expect("the module is used more times than it is split");
+        split.halves[K].2 = true;
+        (split.halves[K].0.clone(), split.halves[K].1.clone())
+    });
+
+    connect(i, If::sim_from_wires(&mut inner.into_iter()));
+    O::sim_from_wires(&mut o.into_iter())
+}
+
+/// Simulates [`from_fn`](super::from_fn).
+pub(crate) fn from_fn<I: Interface, O: Interface, J: Interface, T, const N: usize>(f: T) -> [fn(I, J) -> (O, J); N]
+where T: FnOnce(I, J) -> (O, J) + Copy {
+    with(|cx| cx.fns.push(Slot::new(f)));
+    [from_fn_call::<I, O, J, T>; N]
+}
+
+/// Module generated by `from_fn`.
+fn from_fn_call<I: Interface, O: Interface, J: Interface, T>(i: I, j: J) -> (O, J)
+where T: FnOnce(I, J) -> (O, J) + Copy {
+    let f = with(|cx| {
+        let key = type_id::<T>();
+        let closure = cx.fns.iter().rev().find(|closure| closure.ty == key).expect("the module is not generated");
+        // SAFETY: The closure is given in this pass, so its captures are still alive.
+        unsafe { closure.read::<T>() }
+    });
+    f(i, j)
+}
+
+/// Simulates [`seq`](super::seq).
+pub(crate) fn seq<I: Interface, O: Interface, J: Interface, const N: usize>(
+    ms: [fn(I, J) -> (O, J); N],
+    is: [I; N],
+    j: J,
+) -> ([O; N], J) {
+    let mut j = Some(j);
+    let os = ms
+        .into_iter()
+        .zip(is)
+        .map(|(m, i)| {
+            let (o, j_next) = m(i, j.take().unwrap());
+            j = Some(j_next);
+            o
+        })
+        .collect::<Vec<_>>();
+
+    let Ok(os) = os.try_into() else { unreachable!() };
+    (os, j.unwrap())
+}
+
+/// Simulates `display!`.
+pub(crate) fn display<V: Copy + SimArgs>(fstring: &str, args: V) {
+    let line = format(fstring, &args.sim_args());
+    with(|cx| cx.report.displays.push(line));
+}
+
+/// Simulates `hassert!`.
+pub(crate) fn assert<V: Copy + SimArgs>(cond: bool, fstring: &str, args: V) {
+    if !cond {
+        let msg = format(fstring, &args.sim_args());
+        with(|cx| {
+            cx.report.displays.push(msg.clone());
+            cx.report.failure.get_or_insert(msg);
+        });
+    }
+}
+
+impl SimArg for bool {
+    fn sim_arg(&self) -> Vec<bool> {
+        vec![*self]
+    }
+}
+
+impl<V: Copy + SimArg, const N: usize> SimArg for Array<V, N> {
+    fn sim_arg(&self) -> Vec<bool> {
+        self.sim_elts().iter().flat_map(SimArg::sim_arg).collect()
+    }
+}
+
+impl<const N: usize> SimArg for S<N> {
+    fn sim_arg(&self) -> Vec<bool> {
+        U::from(*self).sim_arg()
+    }
+}
+
+macro_rules! impl_sim_arg_int {
+    ($($ty:ty)+) => {
+        $(
+            impl SimArg for $ty {
+                fn sim_arg(&self) -> Vec<bool> {
+                    (0..<$ty>::BITS).map(|i| (self >> i) & 1 == 1).collect()
+                }
+            }
+        )+
+    };
+}
+
+impl_sim_arg_int! { u8 u16 u32 u64 u128 usize }
+
+impl SimArgs for () {
+    fn sim_args(&self) -> Vec<Vec<bool>> {
+        vec![]
+    }
+}
+
+macro_rules! impl_sim_args_tuple {
+    ($($a:ident)+) => {
+        impl<$($a: SimArg,)+> SimArgs for ($($a,)+) {
+            fn sim_args(&self) -> Vec<Vec<bool>> {
+                vec![$(${ignore($a)} self.${index()}.sim_arg(),)+]
+            }
+        }
+    };
+}
+
+impl_sim_args_tuple! { A1 }
+impl_sim_args_tuple! { A1 A2 }
+impl_sim_args_tuple! { A1 A2 A3 }
+impl_sim_args_tuple! { A1 A2 A3 A4 }
+impl_sim_args_tuple! { A1 A2 A3 A4 A5 }
+impl_sim_args_tuple! { A1 A2 A3 A4 A5 A6 }
+impl_sim_args_tuple! { A1 A2 A3 A4 A5 A6 A7 }
+impl_sim_args_tuple! { A1 A2 A3 A4 A5 A6 A7 A8 }
+impl_sim_args_tuple! { A1 A2 A3 A4 A5 A6 A7 A8 A9 }
+impl_sim_args_tuple! { A1 A2 A3 A4 A5 A6 A7 A8 A9 A10 }
+impl_sim_args_tuple! { A1 A2 A3 A4 A5 A6 A7 A8 A9 A10 A11 }
+impl_sim_args_tuple! { A1 A2 A3 A4 A5 A6 A7 A8 A9 A10 A11 A12 }
+
+/// Formats the arguments with the format string of Verilog's `$display`.
+fn format(fstring: &str, args: &[Vec<bool>]) -> String {
+    let mut out = String::new();
+    let mut args = args.iter();
+    let mut chars = fstring.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            out.push(c);
+            continue;
+        }
+
+        // Width is ignored.
+        while chars.peek().is_some_and(char::is_ascii_digit) {
+            chars.next();
+        }
+
+        let radix = match chars.next() {
+            Some('%') => {
+                out.push('%');
+                continue;
+            }
+            Some('b' | 'B') => 2,
+            Some('o' | 'O') => 8,
+            Some('d' | 'D') => 10,
+            Some('h' | 'H' | 'x' | 'X') => 16,
+            Some('c' | 'C') => 0,
+            Some(c) => {
+                out.push('%');
+                out.push(c);
+                continue;
+            }
+            None => {
+                out.push('%');
+                break;
+            }
+        };
+
+        match args.next() {
+            Some(bits) if radix == 0 => {
+                out.push(char::from((0..8).fold(0, |acc, i| acc | (u8::from(bits.get(i) == Some(&true)) << i))))
+            }
+            Some(bits) => out.push_str(&to_str_radix(bits, radix)),
+            None => out.push_str("<missing>"),
+        }
+    }
+
+    out
+}
+
+/// Converts the bits into a string in the given radix.
+fn to_str_radix(bits: &[bool], radix: u32) -> String {
+    if radix == 10 && bits.len() > 128 {
+        return format!("'h{}", to_str_radix(bits, 16));
+    }
+
+    if radix == 10 {
+        let value = bits.iter().enumerate().fold(0u128, |acc, (i, b)| acc | (u128::from(*b) << i));
+        return value.to_string();
+    }
+
+    let digit_width = radix.trailing_zeros() as usize;
+    let digits = bits
+        .chunks(digit_width)
+        .map(|chunk| {
+            let digit = chunk.iter().enumerate().fold(0, |acc, (i, b)| acc | (u32::from(*b) << i));
+            char::from_digit(digit, radix).unwrap()
+        })
+        .collect::<Vec<_>>();
+    let s = digits.iter().rev().skip_while(|c| **c == '0').collect::<String>();
+    if s.is_empty() {
+        "0".to_string()
+    } else {
+        s
+    }
+}
@@ -0,0 +1,83 @@
+//! Bounds required by the simulator.
+//!
+//! Without the `sim` feature, every type implements these traits, so they do not constrain the hardware. With it, they
+//! require what the simulator in `std::sim` needs to execute the magics in Rust, e.g., [`x`](super::x) requires
+//! [`SimDefault`] because the simulator has to return an actual value.
+
+#[cfg(feature = "sim")]
+use core::ops::*;
+
+/// Declares a bound which is `$($bound)+` with the `sim` feature, and is implemented for every type without it.
+macro_rules! sim_bound {
+    ($(#[$attr:meta])* $name:ident: $($bound:tt)+) => {
+        $(#[$attr])*
+        #[cfg(feature = "sim")]
+        pub trait $name: $($bound)+ {}
+
+        #[cfg(feature = "sim")]
+        impl<T: ?Sized + $($bound)+> $name for T {}
+
+        $(#[$attr])*
+        #[cfg(not(feature = "sim"))]
+        pub trait $name {}
+
+        #[cfg(not(feature = "sim"))]
+        impl<T: ?Sized> $name for T {}
+    };
+}
+
+sim_bound! {
+    /// Values which have a don't care value in the simulator, which is `Default::default()`.
+    SimDefault: Default
+}
+
+sim_bound! {
+    /// Closures which the simulator calls more than once, e.g., for each element of an array.
+    SimCopy: Copy
+}
+
+sim_bound! {
+    /// Values which are compared in the simulator.
+    SimEq: PartialEq
+}
+
+sim_bound! {
+    /// Values which bitwise operations are applied to in the simulator.
+    SimBits: BitOr<Output = Self> + BitAnd<Output = Self> + BitXor<Output = Self> + Sized
+}
+
+/// Arguments of `display!` and `hassert!`.
+///
+/// With the `sim` feature, it is implemented for `()` and the tuples of [`SimArg`]s.
+#[cfg(feature = "sim")]
+pub trait SimArgs {
+    /// Returns the bits of each argument, from the least significant one.
+    fn sim_args(&self) -> Vec<Vec<bool>>;
+}
+
+/// Arguments of `display!` and `hassert!`.
+///
+/// With the `sim` feature, it is implemented for `()` and the tuples of [`SimArg`]s.
+#[cfg(not(feature = "sim"))]
+pub trait SimArgs {}
+
+#[cfg(not(feature = "sim"))]
+impl<T: ?Sized> SimArgs for T {}
+
+/// Argument of `display!` and `hassert!`.
+///
+/// With the `sim` feature, it is implemented for the integers and the arrays of them.
+#[cfg(feature = "sim")]
+pub trait SimArg {
+    /// Returns the bits, from the least significant one.
+    fn sim_arg(&self) -> Vec<bool>;
+}
+
+/// Argument of `display!` and `hassert!`.
+///
+/// With the `sim` feature, it is implemented for the integers and the arrays of them.
+#[cfg(not(feature = "sim"))]
+pub trait SimArg {}
+
+#[cfg(not(feature = "sim"))]
+impl<T: ?Sized> SimArg for T {}
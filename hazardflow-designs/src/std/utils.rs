@@ -35,8 +35,8 @@ pub const fn max(lhs: usize, rhs: usize) -> usize {
 
 /// Display function
 #[magic(system::display)]
-pub fn display<V: Copy>(_fstring: &str, _args: V) {
-    compiler_magic!()
+pub fn display<V: Copy + SimArgs>(_fstring: &str, _args: V) {
+    compiler_magic!(sim: super::sim::display(_fstring, _args))
 }
 
 /// Display macro
@@ -54,8 +54,8 @@ macro_rules! display {
 
 /// Assertion function
 #[magic(system::assert)]
-pub fn assert<V: Copy>(_cond: bool, _fstring: &str, _args: V) {
-    compiler_magic!()
+pub fn assert<V: Copy + SimArgs>(_cond: bool, _fstring: &str, _args: V) {
+    compiler_magic!(sim: super::sim::assert(_cond, _fstring, _args))
 }
 
 /// Assert macro
@@ -111,13 +111,13 @@ macro_rules! hpanic {
     ($fstring: expr, $($arg:expr),+) => {
         unsafe {
             $crate::std::utils::assert(false, $fstring, ($($arg,)*));
-            $crate::std::value::x()
+            $crate::std::value::panicked()
         }
     };
     ($string: expr) => {
         unsafe {
             $crate::std::utils::assert(false, $string, ());
-            $crate::std::value::x()
+            $crate::std::value::panicked()
         }
     };
 }
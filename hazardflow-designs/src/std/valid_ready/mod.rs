@@ -68,7 +68,7 @@ pub fn attach_ready<P1: Copy, R1: Copy, P2: Copy, R2: Copy>(
 ///               +-----+
 ///          R <------------ R
 /// ```
-pub fn attach_resolver<P: Copy, EP: Copy, R: Copy>(
+pub fn attach_resolver<P: Copy, EP: Copy + SimDefault, R: Copy>(
     m: impl FnOnce(Vr<P>) -> Vr<EP>,
 ) -> impl FnOnce(I<VrH<P, R>, { Dep::Helpful }>) -> I<VrH<EP, R>, { Dep::Helpful }> {
     |i: I<VrH<P, R>, { Dep::Helpful }>| -> I<VrH<EP, R>, { Dep::Helpful }> {
@@ -12,6 +12,54 @@ use crate::std::clog2;
 #[magic(array::array)]
 pub struct Array<V: Copy, const N: usize> {
     _marker: core::marker::PhantomData<V>,
+    /// Elements in the simulator.
+    #[cfg(feature = "sim")]
+    inner: SimElts<V>,
+}
+
+/// Elements of an array in the simulator.
+///
+/// They are stored out of line so that the layout of `Array` does not depend on `N`, which rustc cannot evaluate when it
+/// is a nested const expression such as `{ 1 + (N - 1) }`. The allocations are never freed nor mutated after they are
+/// shared, so the copies of an array can share them.
+#[cfg(feature = "sim")]
+#[derive(Clone, Copy)]
+struct SimElts<V>(*const [V]);
+
+// SAFETY: `SimElts` is a shared reference to the elements which lives forever.
+#[cfg(feature = "sim")]
+unsafe impl<V: Sync> Send for SimElts<V> {}
+
+// SAFETY: `SimElts` is a shared reference to the elements which lives forever.
+#[cfg(feature = "sim")]
+unsafe impl<V: Sync> Sync for SimElts<V> {}
+
+#[cfg(feature = "sim")]
+impl<V: Copy> SimElts<V> {
+    fn new(elts: Vec<V>) -> Self {
+        Self(Box::into_raw(elts.into_boxed_slice()))
+    }
+
+    fn get(&self) -> &[V] {
+        // SAFETY: The allocation is never freed, and it is not mutated while it is shared.
+        unsafe { &*self.0 }
+    }
+
+    /// Returns the elements mutably, after moving them to a new allocation which is not shared by the copies.
+    fn make_mut(&mut self) -> &mut [V] {
+        let elts = Box::into_raw(self.get().to_vec().into_boxed_slice());
+        self.0 = elts;
+        // SAFETY: The allocation is new, and it is not read through `self` while it is borrowed mutably.
+        unsafe { &mut *elts }
+    }
+}
+
+#[cfg(feature = "sim")]
+impl<V: core::fmt::Debug> core::fmt::Debug for SimElts<V> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        // SAFETY: The allocation is never freed, and it is not mutated while it is shared.
+        unsafe { &*self.0 }.fmt(f)
+    }
 }
 
 impl<V: Copy + Default, const N: usize> Default for Array<V, N> {
@@ -55,7 +103,7 @@ impl<V: Copy + Default, const N: usize> Array<V, N> {
     // You should manually resize to use this api for arrays that does not satisfy the constraint
     //
     // #[magic(array::tree_fold)]
-    pub fn fold_assoc<F: FnOnce(V, V) -> V>(self, f: F) -> V {
+    pub fn fold_assoc<F: FnOnce(V, V) -> V + SimCopy>(self, f: F) -> V {
         self.fold(V::default(), f)
     }
 
@@ -69,7 +117,7 @@ impl<V: Copy, const N: usize> Array<V, N> {
     /// Returns a new array with the `idx`-th element set to `elt`.
     #[magic(array::set)]
     pub fn set<Idx: Into<U<{ clog2(N) }>>>(self, _idx: Idx, _elt: V) -> Array<V, N> {
-        compiler_magic!()
+        compiler_magic!(sim: self.set_range(_idx.into().sim_to_usize(), Array::<V, 1>::from([_elt])))
     }
 
     /// Returns a new array with the `idx`-th element set to `elt` if `cond` is true.
@@ -84,13 +132,13 @@ impl<V: Copy, const N: usize> Array<V, N> {
     /// Returns a new clipped array of size `M` starting from `index`.
     #[magic(array::clip_const)]
     pub fn clip_const<const M: usize>(self, _index: usize) -> Array<V, M> {
-        compiler_magic!()
+        compiler_magic!(sim: Array::sim_from_vec((0..M).map(|i| self[_index.saturating_add(i)]).collect()))
     }
 
     /// Returns a new array that has tuples from the two given arrays as elements.
     #[magic(array::zip)]
     pub fn zip<W: Copy>(self, _other: Array<W, N>) -> Array<(V, W), N> {
-        compiler_magic!()
+        compiler_magic!(sim: Array::sim_from_vec(self.sim_elts().iter().copied().zip(_other.sim_elts().iter().copied()).collect()))
     }
 
     /// Returns a new array whose elements are enumerated with their indices.
@@ -100,16 +148,16 @@ impl<V: Copy, const N: usize> Array<V, N> {
 
     /// Transforms elements of `self` using `f`.
     #[magic(array::map)]
-    pub fn map<W: Copy, F: FnOnce(V) -> W>(self, _f: F) -> Array<W, N> {
-        compiler_magic!()
+    pub fn map<W: Copy, F: FnOnce(V) -> W + SimCopy>(self, _f: F) -> Array<W, N> {
+        compiler_magic!(sim: Array::sim_from_vec(self.sim_elts().iter().map(|elt| _f(*elt)).collect()))
     }
 
     /// Folds the array into a single value.
     ///
     /// The fold order is from left to right. (i.e. `foldl`)
     #[magic(array::fold)]
-    pub fn fold<B: Copy, F: FnOnce(B, V) -> B>(self, _init: B, _f: F) -> B {
-        compiler_magic!()
+    pub fn fold<B: Copy, F: FnOnce(B, V) -> B + SimCopy>(self, _init: B, _f: F) -> B {
+        compiler_magic!(sim: self.sim_elts().iter().fold(_init, |acc, elt| _f(acc, *elt)))
     }
 
     /// Tests if any element matches a predicate.
@@ -125,27 +173,38 @@ impl<V: Copy, const N: usize> Array<V, N> {
     }
 
     /// Resizes the given array.
+    ///
+    /// In the simulator, the new elements are `Default::default()`, which is zero for the integers.
     #[magic(array::resize)]
-    pub fn resize<const M: usize>(self) -> Array<V, M> {
-        compiler_magic!()
+    pub fn resize<const M: usize>(self) -> Array<V, M>
+    where V: SimDefault {
+        compiler_magic!(sim: Array::sim_from_vec(self.sim_elts().iter().copied().chain(core::iter::repeat_with(V::default)).take(M).collect()))
     }
 
     /// Chunks the array into an array of arrays.
     #[magic(array::chunk)]
     pub fn chunk<const M: usize>(self) -> Array<Array<V, M>, { N / M }> {
-        compiler_magic!()
+        compiler_magic!(sim: self.sim_chunk())
     }
 
     /// Returns a new array with the two given arrays appended.
     #[magic(array::append)]
     pub fn append<const M: usize>(self, _other: Array<V, M>) -> Array<V, { N + M }> {
-        compiler_magic!()
+        compiler_magic!(sim: Array::sim_from_vec(self.sim_elts().iter().chain(_other.sim_elts()).copied().collect()))
     }
 
     /// Returns a new array with the `M` elements starting from `index` set to the elements of `other`.
     #[magic(array::set_range)]
     pub fn set_range<const M: usize>(self, _index: usize, _other: Array<V, M>) -> Array<V, N> {
-        compiler_magic!()
+        compiler_magic!(sim: {
+            let mut elts = self.sim_elts().to_vec();
+            for (i, elt) in _other.sim_elts().iter().enumerate() {
+                if let core::option::Option::Some(dst) = _index.checked_add(i).and_then(|i| elts.get_mut(i)) {
+                    *dst = *elt;
+                }
+            }
+            Array::sim_from_vec(elts)
+        })
     }
 
     /// Returns a Cartesian product of the two arrays.
@@ -160,11 +219,30 @@ impl<V: Copy, const N: usize> Array<V, N> {
     }
 }
 
+#[cfg(feature = "sim")]
+impl<V: Copy, const N: usize> Array<V, N> {
+    /// Returns the elements.
+    pub(crate) fn sim_elts(&self) -> &[V] {
+        self.inner.get()
+    }
+
+    /// Creates an array from the elements, whose number should be `N`.
+    ///
+    /// The number is not checked, as `N` may not be evaluated (see [`SimElts`]).
+    pub(crate) fn sim_from_vec(elts: Vec<V>) -> Self {
+        Array { _marker: core::marker::PhantomData, inner: SimElts::new(elts) }
+    }
+
+    fn sim_chunk<const M: usize, const L: usize>(self) -> Array<Array<V, M>, L> {
+        Array::sim_from_vec((0..self.sim_elts().len() / M).map(|i| self.clip_const::<M>(i * M)).collect())
+    }
+}
+
 impl<V: Copy, const N: usize, const M: usize> Array<Array<V, N>, M> {
     /// Concatenates the array of arrays into a 1D array.
     #[magic(array::concat)]
     pub fn concat(self) -> Array<V, { M * N }> {
-        compiler_magic!()
+        compiler_magic!(sim: Array::sim_from_vec(self.sim_elts().iter().flat_map(Array::sim_elts).copied().collect()))
     }
 }
 
@@ -173,13 +251,21 @@ impl<V: Copy, const N: usize, const M: usize> Array<Array<V, N>, M> {
 // TODO: allow different starting point (FROM..START)
 #[magic(array::range)]
 pub fn range<const N: usize>() -> Array<U<{ clog2(N) }>, N> {
-    compiler_magic!()
+    compiler_magic!(sim: Array::sim_from_vec((0..N).map(U::from).collect()))
 }
 
 impl<V: Copy, const N: usize> From<[V; N]> for Array<V, N> {
     #[magic(array::from)]
     fn from(_value: [V; N]) -> Self {
-        compiler_magic!()
+        compiler_magic!(sim: Array::sim_from_vec(_value.to_vec()))
+    }
+}
+
+impl<V: Copy, const N: usize> Array<V, N> {
+    /// Converts the array into a Rust array, e.g., to destructure it by `let [first, .., last] = arr.into_array()`.
+    #[magic(array::from)]
+    pub fn into_array(self) -> [V; N] {
+        compiler_magic!(sim: core::array::from_fn(|i| self.sim_elts()[i]))
     }
 }
 
@@ -188,20 +274,20 @@ impl<V: Copy, const N: usize, const M: usize> Index<U<N>> for Array<V, M> {
 
     #[magic(array::index)]
     fn index(&self, _idx: U<N>) -> &V {
-        compiler_magic!()
+        compiler_magic!(sim: &self[_idx.sim_to_usize()])
     }
 }
 
-impl<V: Copy, const N: usize> PartialEq for Array<V, N> {
+impl<V: Copy + SimEq, const N: usize> PartialEq for Array<V, N> {
     #[magic(array::eq)]
     fn eq(&self, _other: &Self) -> bool {
-        compiler_magic!()
+        compiler_magic!(sim: self.sim_elts() == _other.sim_elts())
     }
 
     #[allow(clippy::partialeq_ne_impl)]
     #[magic(array::ne)]
     fn ne(&self, _other: &Self) -> bool {
-        compiler_magic!()
+        compiler_magic!(sim: !self.eq(_other))
     }
 }
 
@@ -210,34 +296,71 @@ impl<V: Copy, const M: usize> Index<usize> for Array<V, M> {
 
     #[magic(array::index)]
     fn index(&self, _idx: usize) -> &V {
-        compiler_magic!()
+        // In the simulator, an out-of-bounds index returns the last element as a don't care value.
+        compiler_magic!(sim: match M.checked_sub(1) {
+            core::option::Option::Some(last) => &self.sim_elts()[_idx.min(last)],
+            core::option::Option::None => panic!("indexed an empty array, which has no element to return as a don't care value"),
+        })
+    }
+}
+
+/// Only supported as the place of an assignment, e.g., `arr[idx] = elt` is `arr = arr.set(idx, elt)`.
+impl<V: Copy, const N: usize, const M: usize> IndexMut<U<N>> for Array<V, M> {
+    fn index_mut(&mut self, _idx: U<N>) -> &mut V {
+        compiler_magic!(sim: &mut self[_idx.sim_to_usize()])
+    }
+}
+
+/// Only supported as the place of an assignment, e.g., `arr[idx] = elt` is `arr = arr.set(idx, elt)`.
+impl<V: Copy, const M: usize> IndexMut<usize> for Array<V, M> {
+    fn index_mut(&mut self, _idx: usize) -> &mut V {
+        // In the simulator, an out-of-bounds index panics as there is no element to assign.
+        compiler_magic!(sim: &mut self.inner.make_mut()[_idx])
     }
 }
 
-impl<V: Copy, const N: usize> BitOr for Array<V, N> {
+impl<V: Copy + SimBits, const N: usize> BitOr for Array<V, N> {
     type Output = Self;
 
     #[magic(array::bitor)]
     fn bitor(self, _rhs: Self) -> Self::Output {
-        compiler_magic!()
+        compiler_magic!(sim: Array::sim_from_vec(self.sim_elts().iter().zip(_rhs.sim_elts()).map(|(lhs, rhs)| *lhs | *rhs).collect()))
     }
 }
 
-impl<V: Copy, const N: usize> BitAnd for Array<V, N> {
+impl<V: Copy + SimBits, const N: usize> BitAnd for Array<V, N> {
     type Output = Self;
 
     #[magic(array::bitand)]
     fn bitand(self, _rhs: Self) -> Self::Output {
-        compiler_magic!()
+        compiler_magic!(sim: Array::sim_from_vec(self.sim_elts().iter().zip(_rhs.sim_elts()).map(|(lhs, rhs)| *lhs & *rhs).collect()))
     }
 }
 
-impl<V: Copy, const N: usize> BitXor for Array<V, N> {
+impl<V: Copy + SimBits, const N: usize> BitXor for Array<V, N> {
     type Output = Self;
 
     #[magic(array::bitxor)]
     fn bitxor(self, _rhs: Self) -> Self {
-        compiler_magic!()
+        compiler_magic!(sim: Array::sim_from_vec(self.sim_elts().iter().zip(_rhs.sim_elts()).map(|(lhs, rhs)| *lhs ^ *rhs).collect()))
+    }
+}
+
+impl<V: Copy + SimBits, const N: usize> BitOrAssign for Array<V, N> {
+    fn bitor_assign(&mut self, rhs: Self) {
+        *self = *self | rhs;
+    }
+}
+
+impl<V: Copy + SimBits, const N: usize> BitAndAssign for Array<V, N> {
+    fn bitand_assign(&mut self, rhs: Self) {
+        *self = *self & rhs;
+    }
+}
+
+impl<V: Copy + SimBits, const N: usize> BitXorAssign for Array<V, N> {
+    fn bitxor_assign(&mut self, rhs: Self) {
+        *self = *self ^ rhs;
     }
 }
 
@@ -250,6 +373,6 @@ pub trait RepeatExt: Copy {
 impl<T: Copy> RepeatExt for T {
     #[magic(array::repeat)]
     fn repeat<const N: usize>(self) -> Array<Self, N> {
-        compiler_magic!()
+        compiler_magic!(sim: Array::sim_from_vec(core::iter::repeat(self).take(N).collect()))
     }
 }
@@ -22,6 +22,19 @@ pub use uint::*;
 ///
 /// TODO: Write safety condition
 #[magic(x)]
-pub unsafe fn x<T: Copy>() -> T {
-    compiler_magic!()
+pub unsafe fn x<T: Copy + SimDefault>() -> T {
+    // In the simulator, the don't care value is `Default::default()`.
+    compiler_magic!(sim: T::default())
+}
+
+/// Don't care value after `hpanic!`.
+///
+/// In the simulator, it stops evaluating the `fsm` node, whose assertion failure is already reported.
+///
+/// # Safety
+///
+/// TODO: Write safety condition
+#[magic(x)]
+pub unsafe fn panicked<T: Copy>() -> T {
+    compiler_magic!(sim: crate::std::sim::abort_node())
 }
@@ -1,10 +1,28 @@
 //! Signed integer.
 
+use core::cmp::Ordering;
+use core::ops::*;
+
+use hazardflow_macro::magic;
+
 use super::*;
 
 /// An signed integer with bitwidth `N`.
-#[derive(Debug, Default, Clone, Copy)]
-pub struct S<const N: usize>(U<N>);
+///
+/// It is represented in two's complement, and its arithmetic operations are signed.
+#[derive(Debug, Clone, Copy)]
+#[magic(int::sint)]
+pub struct S<const N: usize>(
+    /// Bits in the simulator.
+    #[cfg(feature = "sim")]
+    U<N>,
+);
+
+impl<const N: usize> Default for S<N> {
+    fn default() -> Self {
+        S::from(U::default())
+    }
+}
 
 impl<const N: usize> S<N> {
     /// Sign extends `S<N>` to `S<M>`.
@@ -14,8 +32,9 @@ impl<const N: usize> S<N> {
     pub fn sext<const M: usize>(self) -> S<M>
     where [(); N + M]: {
         if M >= N {
-            let msb = self.0[N - 1];
-            let inner = self.0.append(msb.repeat::<M>());
+            let bits = U::from(self);
+            let msb = bits[N - 1];
+            let inner = bits.append(msb.repeat::<M>());
             S::from(inner.resize::<M>())
         } else {
             panic!("M should be larger than N")
@@ -49,13 +68,172 @@ impl<const N: usize> S<N> {
 }
 
 impl<const N: usize> From<U<N>> for S<N> {
-    fn from(value: U<N>) -> S<N> {
-        S(value)
+    #[magic(int::convert)]
+    fn from(_value: U<N>) -> S<N> {
+        compiler_magic!(sim: S(_value))
     }
 }
 
 impl<const N: usize> From<S<N>> for U<N> {
-    fn from(value: S<N>) -> U<N> {
-        value.0
+    #[magic(int::convert)]
+    fn from(_value: S<N>) -> U<N> {
+        compiler_magic!(sim: _value.0)
+    }
+}
+
+impl<const N: usize> Add<S<N>> for S<N>
+where [(); N + 1]:
+{
+    type Output = S<{ N + 1 }>;
+
+    /// Adds two `S<N>`s, whose sum does not overflow.
+    #[magic(int::add)]
+    fn add(self, _rhs: S<N>) -> S<{ N + 1 }> {
+        compiler_magic!(sim: {
+            let width = N + 1;
+            let mut sum = sim_add(&self.sim_sext(width), &_rhs.sim_sext(width), false);
+            sum.pop();
+            S::sim_from_vec(sum)
+        })
+    }
+}
+
+impl<const N: usize> Sub<S<N>> for S<N> {
+    type Output = S<N>;
+
+    /// Subtracts `rhs`, and the difference wraps around.
+    #[magic(int::sub)]
+    fn sub(self, _rhs: S<N>) -> S<N> {
+        compiler_magic!(sim: {
+            let negated = _rhs.sim_sext(N).into_iter().map(|b| !b).collect::<Vec<_>>();
+            let mut diff = sim_add(&self.sim_sext(N), &negated, true);
+            diff.pop();
+            S::sim_from_vec(diff)
+        })
+    }
+}
+
+impl<const N: usize, const M: usize> Mul<S<M>> for S<N>
+where [(); N + M]:
+{
+    type Output = S<{ N + M }>;
+
+    /// Multiplies two signed integers, whose product does not overflow.
+    #[magic(int::mul)]
+    fn mul(self, _rhs: S<M>) -> Self::Output {
+        compiler_magic!(sim: self.sim_product(_rhs))
+    }
+}
+
+impl<const N: usize> Neg for S<N> {
+    type Output = S<N>;
+
+    /// Negates the integer, and `-S::signed_min()` wraps around to itself.
+    #[magic(int::neg)]
+    fn neg(self) -> S<N> {
+        compiler_magic!(sim: S::from(U::from(0)) - self)
+    }
+}
+
+impl<const N: usize> Shr<usize> for S<N> {
+    type Output = Self;
+
+    /// Shifts right arithmetically, filling the upper bits with the sign bit.
+    #[magic(int::shr)]
+    fn shr(self, _rhs: usize) -> Self::Output {
+        compiler_magic!(sim: self.sim_shr(_rhs))
+    }
+}
+
+impl<const N: usize, const M: usize> Shr<U<M>> for S<N> {
+    type Output = Self;
+
+    /// Shifts right arithmetically, filling the upper bits with the sign bit.
+    #[magic(int::shr)]
+    fn shr(self, _rhs: U<M>) -> Self::Output {
+        compiler_magic!(sim: self >> _rhs.sim_to_usize())
+    }
+}
+
+impl<const N: usize> Shl<usize> for S<N> {
+    type Output = Self;
+
+    #[magic(int::shl)]
+    fn shl(self, _rhs: usize) -> Self::Output {
+        compiler_magic!(sim: S::from(U::from(self) << _rhs))
+    }
+}
+
+impl<const N: usize, const M: usize> Shl<U<M>> for S<N> {
+    type Output = Self;
+
+    #[magic(int::shl)]
+    fn shl(self, _rhs: U<M>) -> Self::Output {
+        compiler_magic!(sim: self << _rhs.sim_to_usize())
+    }
+}
+
+impl<const N: usize> PartialEq for S<N> {
+    fn eq(&self, other: &Self) -> bool {
+        U::from(*self) == U::from(*other)
+    }
+}
+
+impl<const N: usize> PartialOrd for S<N> {
+    fn partial_cmp(&self, _other: &Self) -> Option<Ordering> {
+        panic!("placeholder for rust's type system")
+    }
+
+    #[magic(int::lt)]
+    fn lt(&self, _other: &Self) -> bool {
+        compiler_magic!(sim: self.sim_cmp(_other).is_lt())
+    }
+
+    #[magic(int::le)]
+    fn le(&self, _other: &Self) -> bool {
+        compiler_magic!(sim: self.sim_cmp(_other).is_le())
+    }
+
+    #[magic(int::gt)]
+    fn gt(&self, _other: &Self) -> bool {
+        compiler_magic!(sim: self.sim_cmp(_other).is_gt())
+    }
+
+    #[magic(int::ge)]
+    fn ge(&self, _other: &Self) -> bool {
+        compiler_magic!(sim: self.sim_cmp(_other).is_ge())
+    }
+}
+
+#[cfg(feature = "sim")]
+impl<const N: usize> S<N> {
+    /// Returns the bits sign-extended or truncated to `width`, from the least significant one.
+    fn sim_sext(self, width: usize) -> Vec<bool> {
+        let bits = self.0.sim_elts();
+        let msb = bits.last().copied().unwrap_or(false);
+        bits.iter().copied().chain(core::iter::repeat(msb)).take(width).collect()
+    }
+
+    fn sim_from_vec(bits: Vec<bool>) -> Self {
+        S(U::sim_from_vec(bits))
+    }
+
+    /// Returns the product, whose width is `W`.
+    fn sim_product<const M: usize, const W: usize>(self, rhs: S<M>) -> S<W> {
+        let product = sim_mul(&self.sim_sext(W), &rhs.sim_sext(W));
+        S::sim_from_vec(product.into_iter().take(W).collect())
+    }
+
+    /// Shifts right arithmetically.
+    fn sim_shr(self, shamt: usize) -> Self {
+        let shamt = shamt.min(N);
+        S::sim_from_vec(self.sim_sext(N + shamt).into_iter().skip(shamt).collect())
+    }
+
+    fn sim_cmp(&self, other: &Self) -> Ordering {
+        // Flipping the sign bits turns the signed order into the unsigned one.
+        let [lhs, rhs] = [self, other]
+            .map(|value| value.0.sim_elts().iter().rev().enumerate().map(|(i, b)| (i == 0) ^ b).collect::<Vec<_>>());
+        lhs.cmp(&rhs)
     }
 }
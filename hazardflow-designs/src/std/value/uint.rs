@@ -18,56 +18,107 @@ pub type U<const N: usize> = Array<bool, N>;
 impl<const N: usize> From<U<N>> for u32 {
     #[magic(int::convert)]
     fn from(_value: U<N>) -> Self {
-        compiler_magic!()
+        compiler_magic!(sim: _value.sim_to_u128() as u32)
     }
 }
 
 impl<const N: usize> From<U<N>> for u8 {
     #[magic(int::convert)]
     fn from(_value: U<N>) -> Self {
-        compiler_magic!()
+        compiler_magic!(sim: _value.sim_to_u128() as u8)
     }
 }
 
+impl<const N: usize> From<U<N>> for u16 {
+    #[magic(int::convert)]
+    fn from(_value: U<N>) -> Self {
+        compiler_magic!(sim: _value.sim_to_u128() as u16)
+    }
+}
+
+impl<const N: usize> From<U<N>> for u64 {
+    #[magic(int::convert)]
+    fn from(_value: U<N>) -> Self {
+        compiler_magic!(sim: _value.sim_to_u128() as u64)
+    }
+}
+
+impl<const N: usize> From<U<N>> for u128 {
+    #[magic(int::convert)]
+    fn from(_value: U<N>) -> Self {
+        compiler_magic!(sim: _value.sim_to_u128())
+    }
+}
+
+/// The value is sign-extended if `N` is larger than 32.
 impl<const N: usize> From<i32> for U<N> {
     #[magic(int::convert)]
     fn from(_value: i32) -> U<N> {
-        compiler_magic!()
+        compiler_magic!(sim: U::sim_from_u128(_value as u128))
+    }
+}
+
+/// The value is sign-extended if `N` is larger than 64.
+impl<const N: usize> From<i64> for U<N> {
+    #[magic(int::convert)]
+    fn from(_value: i64) -> U<N> {
+        compiler_magic!(sim: U::sim_from_u128(_value as u128))
+    }
+}
+
+impl<const N: usize> From<u8> for U<N> {
+    #[magic(int::convert)]
+    fn from(_value: u8) -> U<N> {
+        compiler_magic!(sim: U::sim_from_u128(u128::from(_value)))
+    }
+}
+
+impl<const N: usize> From<u16> for U<N> {
+    #[magic(int::convert)]
+    fn from(_value: u16) -> U<N> {
+        compiler_magic!(sim: U::sim_from_u128(u128::from(_value)))
     }
 }
 
 impl<const N: usize> From<u32> for U<N> {
     #[magic(int::convert)]
     fn from(_value: u32) -> U<N> {
-        compiler_magic!()
+        compiler_magic!(sim: U::sim_from_u128(u128::from(_value)))
+    }
+}
+
+impl<const N: usize> From<u64> for U<N> {
+    #[magic(int::convert)]
+    fn from(_value: u64) -> U<N> {
+        compiler_magic!(sim: U::sim_from_u128(u128::from(_value)))
     }
 }
 
 impl<const N: usize> From<usize> for U<N> {
     #[magic(int::convert)]
     fn from(_value: usize) -> U<N> {
-        compiler_magic!()
+        compiler_magic!(sim: U::sim_from_u128(_value as u128))
     }
 }
 
 impl<const N: usize> From<u128> for U<N> {
     #[magic(int::convert)]
     fn from(_value: u128) -> U<N> {
-        compiler_magic!()
+        compiler_magic!(sim: U::sim_from_u128(_value))
     }
 }
 
 impl From<bool> for U<1> {
     #[magic(int::convert)]
     fn from(_value: bool) -> U<1> {
-        compiler_magic!()
+        compiler_magic!(sim: Array::from([_value]))
     }
 }
 
 impl<const N: usize> From<U<N>> for bool {
     #[magic(int::convert)]
     fn from(_value: U<N>) -> bool {
-        compiler_magic!()
+        compiler_magic!(sim: N > 0 && _value[0])
     }
 }
 
@@ -76,7 +127,7 @@ impl<const N: usize> Not for U<N> {
 
     #[magic(int::not)]
     fn not(self) -> Self::Output {
-        compiler_magic!()
+        compiler_magic!(sim: U::sim_from_vec(self.sim_bits().into_iter().map(|b| !b).collect()))
     }
 }
 
@@ -85,7 +136,7 @@ impl<const N: usize, const M: usize> Shr<U<M>> for U<N> {
 
     #[magic(int::shr)]
     fn shr(self, _rhs: U<M>) -> Self::Output {
-        compiler_magic!()
+        compiler_magic!(sim: self >> _rhs.sim_to_usize())
     }
 }
 
@@ -94,7 +145,16 @@ impl<const N: usize> Shr<usize> for U<N> {
 
     #[magic(int::shr)]
     fn shr(self, _rhs: usize) -> Self::Output {
-        compiler_magic!()
+        compiler_magic!(sim: {
+            let bits = self.sim_bits();
+            U::sim_from_vec(bits.iter().copied().skip(_rhs).chain(core::iter::repeat(false)).take(bits.len()).collect())
+        })
+    }
+}
+
+impl<const N: usize> ShrAssign<usize> for U<N> {
+    fn shr_assign(&mut self, rhs: usize) {
+        *self = *self >> rhs;
     }
 }
 
@@ -103,7 +163,7 @@ impl<const N: usize, const M: usize> Shl<U<M>> for U<N> {
 
     #[magic(int::shl)]
     fn shl(self, _lhs: U<M>) -> Self::Output {
-        compiler_magic!()
+        compiler_magic!(sim: self << _lhs.sim_to_usize())
     }
 }
 
@@ -112,7 +172,16 @@ impl<const N: usize> Shl<usize> for U<N> {
 
     #[magic(int::shl)]
     fn shl(self, _lhs: usize) -> Self::Output {
-        compiler_magic!()
+        compiler_magic!(sim: {
+            let bits = self.sim_bits();
+            U::sim_from_vec(core::iter::repeat(false).take(_lhs).chain(bits.iter().copied()).take(bits.len()).collect())
+        })
+    }
+}
+
+impl<const N: usize> ShlAssign<usize> for U<N> {
+    fn shl_assign(&mut self, rhs: usize) {
+        *self = *self << rhs;
     }
 }
 
@@ -123,7 +192,7 @@ where [(); N + 1]:
 
     #[magic(int::add)]
     fn add(self, _rhs: U<N>) -> U<{ N + 1 }> {
-        compiler_magic!()
+        compiler_magic!(sim: U::sim_from_vec(sim_add(&self.sim_bits(), &_rhs.sim_bits(), false)))
     }
 }
 
@@ -137,6 +206,15 @@ where [(); N + 1]:
     }
 }
 
+impl<const N: usize> AddAssign<U<N>> for U<N>
+where [(); N + 1]:
+{
+    /// Adds `rhs` and truncates the result to `U<N>`.
+    fn add_assign(&mut self, rhs: U<N>) {
+        *self = self.trunk_add(rhs);
+    }
+}
+
 impl<const N: usize> U<N> {
     /// Returns the maximum value of an `N` bit unsigned value. (i.e., 2^`N` - 1)
     pub fn unsigned_max() -> U<N> {
@@ -149,7 +227,17 @@ impl<const N: usize> Sub<U<N>> for U<N> {
 
     #[magic(int::sub)]
     fn sub(self, _other: U<N>) -> U<N> {
-        compiler_magic!()
+        compiler_magic!(sim: {
+            let mut diff = sim_add(&self.sim_bits(), &(!_other).sim_bits(), true);
+            diff.pop();
+            U::sim_from_vec(diff)
+        })
+    }
+}
+
+impl<const N: usize> SubAssign<U<N>> for U<N> {
+    fn sub_assign(&mut self, rhs: U<N>) {
+        *self = *self - rhs;
     }
 }
 
@@ -160,7 +248,7 @@ where [(); N + M]:
 
     #[magic(int::mul)]
     fn mul(self, _other: U<M>) -> Self::Output {
-        compiler_magic!()
+        compiler_magic!(sim: U::sim_from_vec(sim_mul(&self.sim_bits(), &_other.sim_bits())))
     }
 }
 
@@ -171,25 +259,87 @@ impl<const N: usize> PartialOrd for U<N> {
 
     #[magic(int::lt)]
     fn lt(&self, _other: &Self) -> bool {
-        compiler_magic!()
+        compiler_magic!(sim: self.sim_cmp(_other).is_lt())
     }
 
     #[magic(int::le)]
     fn le(&self, _other: &Self) -> bool {
-        compiler_magic!()
+        compiler_magic!(sim: self.sim_cmp(_other).is_le())
     }
 
     #[magic(int::gt)]
     fn gt(&self, _other: &Self) -> bool {
-        compiler_magic!()
+        compiler_magic!(sim: self.sim_cmp(_other).is_gt())
     }
 
     #[magic(int::ge)]
     fn ge(&self, _other: &Self) -> bool {
-        compiler_magic!()
+        compiler_magic!(sim: self.sim_cmp(_other).is_ge())
+    }
+}
+
+#[cfg(feature = "sim")]
+impl<const N: usize> U<N> {
+    /// Returns the bits, from the least significant one.
+    fn sim_bits(self) -> Vec<bool> {
+        self.sim_elts().to_vec()
+    }
+
+    fn sim_from_u128(value: u128) -> Self {
+        U::sim_from_vec((0..N).map(|i| i < 128 && (value >> i) & 1 == 1).collect())
+    }
+
+    fn sim_to_u128(self) -> u128 {
+        self.sim_bits().into_iter().take(128).enumerate().fold(0, |acc, (i, b)| acc | (u128::from(b) << i))
+    }
+
+    /// Converts the integer into `usize`, saturating at `usize::MAX`.
+    pub(crate) fn sim_to_usize(self) -> usize {
+        if self.sim_bits().into_iter().skip(usize::BITS as usize).any(|b| b) {
+            usize::MAX
+        } else {
+            self.sim_to_u128() as usize
+        }
+    }
+
+    fn sim_cmp(&self, other: &Self) -> Ordering {
+        self.sim_bits().into_iter().rev().cmp(other.sim_bits().into_iter().rev())
     }
 }
 
+/// Adds the two bit vectors with the carry, and returns the sum with the carry out.
+#[cfg(feature = "sim")]
+pub(super) fn sim_add(lhs: &[bool], rhs: &[bool], carry: bool) -> Vec<bool> {
+    let mut carry = carry;
+    let mut sum = lhs
+        .iter()
+        .zip(rhs)
+        .map(|(l, r)| {
+            let s = l ^ r ^ carry;
+            carry = (l & r) | (carry & (l ^ r));
+            s
+        })
+        .collect::<Vec<_>>();
+    sum.push(carry);
+    sum
+}
+
+/// Multiplies the two bit vectors, and returns the product whose width is the sum of their widths.
+#[cfg(feature = "sim")]
+pub(super) fn sim_mul(lhs: &[bool], rhs: &[bool]) -> Vec<bool> {
+    let width = lhs.len() + rhs.len();
+    rhs.iter().enumerate().fold(vec![false; width], |acc, (i, b)| {
+        if *b {
+            let shifted = core::iter::repeat(false).take(i).chain(lhs.iter().copied()).chain(core::iter::repeat(false));
+            let mut sum = sim_add(&acc, &shifted.take(width).collect::<Vec<_>>(), false);
+            sum.pop();
+            sum
+        } else {
+            acc
+        }
+    })
+}
+
 /// Trait for converting a type into `U<N>`.
 pub trait IntoU {
     /// Converts `self` into `U<N>`.
@@ -211,6 +361,11 @@ impl IntoU for u32 {
         U::from(self)
     }
 }
+impl IntoU for u64 {
+    fn into_u<const N: usize>(self) -> U<N> {
+        U::from(self)
+    }
+}
 
 impl IntoU for bool {
     fn into_u<const N: usize>(self) -> U<N> {
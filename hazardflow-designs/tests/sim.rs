@@ -0,0 +1,121 @@
+//! Tests of the software simulation in `std::sim`.
+
+#![allow(incomplete_features)]
+#![feature(generic_const_exprs)]
+
+use hazardflow_designs::examples::fir_filter::fir_filter;
+use hazardflow_designs::std::sim::Sim;
+use hazardflow_designs::std::*;
+use hazardflow_designs::{display, hassert};
+
+#[test]
+fn sim_map() {
+    let mut sim = Sim::new(|i: Valid<U<8>>| i.map(|p| (p + U::from(1)).resize::<8>()));
+
+    let (ep, ()) = sim.step(Some(U::from(3)), ());
+    assert_eq!(ep.map(u32::from), Some(4));
+
+    let (ep, ()) = sim.step(None, ());
+    assert!(ep.is_none());
+    assert_eq!(sim.cycle(), 2);
+}
+
+#[test]
+fn sim_reg_latency() {
+    let mut sim = Sim::new(|i: Valid<U<8>>| i.reg_fwd_always().reg_fwd_always());
+
+    let outputs = [Some(1), Some(2), None, Some(4), None, None]
+        .into_iter()
+        .map(|ip| sim.step(ip.map(U::from), ()).0.map(u32::from))
+        .collect::<Vec<_>>();
+    assert_eq!(outputs, [None, None, Some(1), Some(2), None, Some(4)]);
+}
+
+#[test]
+fn sim_state_and_reset() {
+    let mut sim = Sim::new(|i: Valid<U<8>>| {
+        i.fsm_map(U::<8>::from(0), |p, s| {
+            let s = (s + p).resize();
+            (s, s)
+        })
+    });
+
+    assert_eq!(sim.step(Some(U::from(2)), ()).0.map(u32::from), Some(2));
+    assert_eq!(sim.step(Some(U::from(3)), ()).0.map(u32::from), Some(5));
+    // The state is not updated without an ingress transfer.
+    assert_eq!(sim.step(None, ()).0.map(u32::from), None);
+    assert_eq!(sim.step(Some(U::from(1)), ()).0.map(u32::from), Some(6));
+
+    sim.reset();
+    assert_eq!(sim.step(Some(U::from(1)), ()).0.map(u32::from), Some(1));
+}
+
+#[test]
+fn sim_displays() {
+    let mut sim = Sim::new(|i: Valid<U<8>>| {
+        i.map(|p| {
+            display!("p = %d, %b, %h", p, p, p);
+            p
+        })
+    });
+
+    sim.step(Some(U::from(10)), ());
+    sim.step(Some(U::from(255)), ());
+    assert_eq!(sim.displays(), [(0, "p = 10, 1010, a".to_string()), (1, "p = 255, 11111111, ff".to_string())]);
+    assert_eq!(sim.take_displays().len(), 2);
+    assert!(sim.displays().is_empty());
+}
+
+#[test]
+#[should_panic(expected = "assertion failed at cycle 1: too large: 200")]
+fn sim_hassert() {
+    let mut sim = Sim::new(|i: Valid<U<8>>| {
+        i.map(|p| {
+            hassert!(p < U::from(100), "too large: %d", p);
+            p
+        })
+    });
+
+    sim.step(Some(U::from(50)), ());
+    sim.step(Some(U::from(200)), ());
+}
+
+#[test]
+fn sim_back_pressure() {
+    let mut sim = Sim::new(|i: Vr<U<8>>| i.reg_fwd(true));
+
+    // The register is empty, so the payload is accepted even if the egress is not ready.
+    let (ep, ir) = sim.step(Some(U::from(7)), Ready::invalid());
+    assert!(ep.is_none() && ir.ready);
+
+    // The register holds the payload until the egress is ready.
+    let (ep, ir) = sim.step(Some(U::from(8)), Ready::invalid());
+    assert_eq!(ep.map(u32::from), Some(7));
+    assert!(!ir.ready);
+
+    let (ep, ir) = sim.step(Some(U::from(8)), Ready::valid(()));
+    assert_eq!(ep.map(u32::from), Some(7));
+    assert!(ir.ready);
+
+    let (ep, _) = sim.step(None, Ready::valid(()));
+    assert_eq!(ep.map(u32::from), Some(8));
+}
+
+/// Resolver which does not implement `Default`.
+#[derive(Debug, Clone, Copy)]
+struct NoDefault;
+
+#[test]
+fn sim_resolver_without_default() {
+    let mut sim = Sim::new(|i: I<ValidH<U<8>, NoDefault>, { Dep::Helpful }>| i.map(|p| p));
+    let (ep, _) = sim.step(Some(U::from(1)), NoDefault);
+    assert_eq!(ep.map(u32::from), Some(1));
+}
+
+#[test]
+fn sim_fir_filter() {
+    let mut sim = Sim::new(fir_filter);
+    let outputs =
+        [Some(1), Some(0), None, Some(0), Some(2), None].into_iter().map(|ip| sim.step(ip, ()).0).collect::<Vec<_>>();
+    assert_eq!(outputs, [Some(4), Some(2), None, Some(3), Some(8), None]);
+}
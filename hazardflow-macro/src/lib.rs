@@ -2,7 +2,7 @@
 
 use proc_macro::{self, TokenStream};
 use quote::{quote, ToTokens};
-use syn::{parse_macro_input, parse_quote, DeriveInput, Item, ItemFn};
+use syn::{parse_macro_input, parse_quote, DeriveInput, Item, ItemFn, LitStr};
 
 #[proc_macro_attribute]
 pub fn synthesize(_attr: TokenStream, item: TokenStream) -> TokenStream {
@@ -11,6 +11,34 @@ pub fn synthesize(_attr: TokenStream, item: TokenStream) -> TokenStream {
     f.into_token_stream().into()
 }
 
+/// Turns a function taking `&mut hazardflow::testing::Testbench` of the given top-level module into a test.
+///
+/// ```ignore
+/// #[hazardflow::test("custom_fifo")]
+/// fn custom_fifo_forwards(tb: &mut Testbench) -> VirgenResult<()> { .. }
+/// ```
+#[proc_macro_attribute]
+pub fn test(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let target = parse_macro_input!(attr as LitStr);
+    let mut f = parse_macro_input!(item as ItemFn);
+
+    // Attributes such as `#[should_panic]` go to the test.
+    let attrs = std::mem::take(&mut f.attrs);
+    let (vis, name, output) = (&f.vis, &f.sig.ident, &f.sig.output);
+    quote! {
+        #[::core::prelude::v1::test]
+        #(#attrs)*
+        #vis fn #name() #output {
+            #f
+
+            let mut tb = ::hazardflow::testing::Testbench::new(#target)
+                .unwrap_or_else(|err| panic!("failed to build the testbench of `{}`: {}", #target, err));
+            #name(&mut tb)
+        }
+    }
+    .into()
+}
+
 #[proc_macro_attribute]
 pub fn magic(args: TokenStream, item: TokenStream) -> TokenStream {
     let args = args.to_string();
@@ -46,6 +74,15 @@ pub fn magic(args: TokenStream, item: TokenStream) -> TokenStream {
     }
 }
 
+/// Records the Verilog source file of an FFI module, e.g., `#[ffi_source("MeshWrapper.v")]`.
+#[proc_macro_attribute]
+pub fn ffi_source(args: TokenStream, item: TokenStream) -> TokenStream {
+    let source = parse_macro_input!(args as LitStr);
+    let mut f = parse_macro_input!(item as ItemFn);
+    f.attrs.push(parse_quote!(#[hazardflow::ffi_source(#source)]));
+    f.into_token_stream().into()
+}
+
 #[proc_macro_derive(HEq)]
 pub fn heq(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
     let ast = parse_macro_input!(input as DeriveInput);
@@ -77,23 +114,57 @@ pub fn heq(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
             }
             .into()
         }
-        syn::Data::Enum(syn::DataEnum { .. }) => quote! {
-            impl #impl_generics ::core::cmp::PartialEq for #name #ty_generics #where_clause {
-                #[magic(adt::enum_eq)]
-                fn eq(&self, other: &Self) -> bool {
-                    crate::prelude::compiler_magic!()
+        syn::Data::Enum(syn::DataEnum { ref variants, .. }) => {
+            // The payloads are compared in the simulator, where `SimEq` requires `PartialEq`.
+            let mut generics = ast.generics.clone();
+            let predicates = &mut generics.make_where_clause().predicates;
+            for ty in variants.iter().flat_map(|v| v.fields.iter().map(|f| &f.ty)) {
+                predicates.push(parse_quote!(#ty: crate::std::SimEq));
+            }
+            let where_clause = generics.where_clause.as_ref();
+
+            // Compares the discriminants and the payloads in the simulator.
+            let arms = variants.iter().map(|v| {
+                let variant = &v.ident;
+                let lhs = v.fields.iter().enumerate().map(|(i, _)| quote::format_ident!("lhs{i}")).collect::<Vec<_>>();
+                let rhs = v.fields.iter().enumerate().map(|(i, _)| quote::format_ident!("rhs{i}")).collect::<Vec<_>>();
+                let (lhs_pat, rhs_pat) = match &v.fields {
+                    syn::Fields::Named(fields) => {
+                        let names = fields.named.iter().map(|f| f.ident.as_ref().unwrap()).collect::<Vec<_>>();
+                        (quote! { { #(#names: #lhs),* } }, quote! { { #(#names: #rhs),* } })
+                    }
+                    syn::Fields::Unnamed(_) => (quote! { (#(#lhs),*) }, quote! { (#(#rhs),*) }),
+                    syn::Fields::Unit => (quote! {}, quote! {}),
+                };
+                quote! {
+                    (Self::#variant #lhs_pat, Self::#variant #rhs_pat) => true #(&& #lhs == #rhs)*
                 }
-                #[allow(clippy::partialeq_ne_impl)]
-                #[magic(adt::enum_ne)]
-                fn ne(&self, other: &Self) -> bool {
-                    crate::prelude::compiler_magic!()
+            });
+
+            quote! {
+                impl #impl_generics ::core::cmp::PartialEq for #name #ty_generics #where_clause {
+                    #[magic(adt::enum_eq)]
+                    fn eq(&self, other: &Self) -> bool {
+                        crate::prelude::compiler_magic!(sim: {
+                            #[allow(unreachable_patterns)]
+                            match (self, other) {
+                                #(#arms,)*
+                                _ => false,
+                            }
+                        })
+                    }
+                    #[allow(clippy::partialeq_ne_impl)]
+                    #[magic(adt::enum_ne)]
+                    fn ne(&self, other: &Self) -> bool {
+                        crate::prelude::compiler_magic!(sim: !self.eq(other))
+                    }
+                }
+                impl #impl_generics ::core::cmp::Eq for #name #ty_generics #where_clause {
+                    fn assert_receiver_is_total_eq(&self) {}
                 }
             }
-            impl #impl_generics ::core::cmp::Eq for #name #ty_generics #where_clause {
-                fn assert_receiver_is_total_eq(&self) {}
-            }
+            .into()
         }
-        .into(),
         _ => todo!("HEq macro is not implemented for union type"),
     }
 }
@@ -132,6 +203,9 @@ pub fn interface(input: TokenStream) -> TokenStream {
         quote! { #vis #name: <#ty as Interface>::Bwd }
     });
 
+    let names = fields.iter().map(|f| &f.ident).collect::<Vec<_>>();
+    let tys = fields.iter().map(|f| &f.ty).collect::<Vec<_>>();
+
     let expanded = quote! {
         #[allow(unused_braces, missing_docs)]
         #[derive(Debug, Clone, Copy)]
@@ -149,6 +223,36 @@ pub fn interface(input: TokenStream) -> TokenStream {
         impl #impl_generics Interface for #name #ty_generics #where_clause {
             type Fwd = #fident #ty_generics;
             type Bwd = #bident #ty_generics;
+
+            #[cfg(feature = "sim")]
+            fn sim_from_wires(wires: &mut dyn ::core::iter::Iterator<Item = usize>) -> Self {
+                Self { #(#names: <#tys as Interface>::sim_from_wires(wires),)* }
+            }
+
+            #[cfg(feature = "sim")]
+            fn sim_wires(&self, wires: &mut ::std::vec::Vec<usize>) {
+                #(self.#names.sim_wires(wires);)*
+            }
+
+            #[cfg(feature = "sim")]
+            fn sim_fwd(&self) -> Self::Fwd {
+                #fident { #(#names: self.#names.sim_fwd(),)* }
+            }
+
+            #[cfg(feature = "sim")]
+            fn sim_bwd(&self) -> ::core::option::Option<Self::Bwd> {
+                ::core::option::Option::Some(#bident { #(#names: self.#names.sim_bwd()?,)* })
+            }
+
+            #[cfg(feature = "sim")]
+            fn sim_set_fwd(&self, fwd: Self::Fwd) {
+                #(self.#names.sim_set_fwd(fwd.#names);)*
+            }
+
+            #[cfg(feature = "sim")]
+            fn sim_set_bwd(&self, bwd: Self::Bwd) {
+                #(self.#names.sim_set_bwd(bwd.#names);)*
+            }
         }
     };
     expanded.into()
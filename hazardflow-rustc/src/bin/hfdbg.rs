@@ -0,0 +1,55 @@
+//! Decodes the channels in a VCD waveform into Rust values with the debug information of the compiler.
+//!
+//! ```text
+//! $ cargo run --bin hfdbg -- build/cpu/cpu.hfdbg.json waves.vcd
+//! ```
+
+#![feature(rustc_private)]
+extern crate clap;
+extern crate hazardflow;
+extern crate rustc_driver;
+
+use std::path::PathBuf;
+use std::process::exit;
+
+use clap::Parser;
+use hazardflow::compiler::debug_info::read_debug_info;
+
+/// HazardFlow waveform decoder
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None)]
+struct Args {
+    /// Debug information generated by the compiler (`build/<top>/<top>.hfdbg.json`)
+    debug_info: PathBuf,
+
+    /// Waveform in the VCD format
+    vcd: PathBuf,
+
+    /// Shows only the values whose paths contain the pattern
+    #[arg(short, long)]
+    filter: Option<String>,
+}
+
+fn main() {
+    let args = Args::parse();
+
+    let decoded = read_debug_info(&args.debug_info).and_then(|debug_info| {
+        let vcd =
+            std::fs::read_to_string(&args.vcd).map_err(|err| hazardflow::compiler::error::VirgenError::Fs { err })?;
+        debug_info.decode_vcd(&vcd)
+    });
+
+    match decoded {
+        Ok(decoded) => {
+            for value in decoded {
+                if args.filter.as_ref().map_or(true, |filter| value.path.contains(filter.as_str())) {
+                    println!("#{} {} = {}", value.time, value.path, value.value);
+                }
+            }
+        }
+        Err(err) => {
+            eprintln!("error: {}", err);
+            exit(1);
+        }
+    }
+}
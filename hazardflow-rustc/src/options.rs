@@ -39,6 +39,22 @@ pub struct HazardflowArgs {
     /// Merge all modules into a single file
     #[clap(long = "merge")]
     pub(crate) merge: bool,
+
+    /// Output format
+    #[clap(long = "emit", value_enum, default_value = "verilog")]
+    pub(crate) emit: EmitKind,
+
+    /// Annotates the statements in generated Verilog with their source locations
+    #[clap(long = "src-loc", value_enum, default_value = "none")]
+    pub(crate) src_loc: SrcLoc,
+
+    /// Turns the const generics affecting only widths and array sizes into Verilog parameters
+    #[clap(long = "parameterize")]
+    pub(crate) parameterize: bool,
+
+    /// Merges structurally identical modules
+    #[clap(long = "dedup")]
+    pub(crate) dedup: bool,
 }
 
 impl HazardflowArgs {
@@ -59,6 +75,10 @@ impl HazardflowArgs {
             detect_comb_loop: self.detect_comb_loop,
             target: if self.target.is_empty() { CompileTarget::All } else { CompileTarget::FilterBy(self.target) },
             merge: self.merge,
+            emit: self.emit,
+            src_loc: self.src_loc,
+            parameterize: self.parameterize,
+            dedup: self.dedup,
         }
     }
 }
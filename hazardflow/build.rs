@@ -0,0 +1,34 @@
+//! Builds `hazardflow-macro` for the designs compiled by `testing::compile`, and exports the path of the library as
+//! `HAZARDFLOW_MACRO_PATH`.
+
+use std::env;
+use std::path::PathBuf;
+use std::process::Command;
+
+fn main() {
+    let macro_dir = PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap()).join("../hazardflow-macro");
+    println!("cargo:rerun-if-changed={}", macro_dir.display());
+
+    // The target directory of the running build is locked, so the library is built in a separate one.
+    let output = Command::new(env::var("CARGO").unwrap())
+        .args(["build", "--offline", "--message-format=json-render-diagnostics", "--manifest-path"])
+        .arg(macro_dir.join("Cargo.toml"))
+        .arg("--target-dir")
+        .arg(PathBuf::from(env::var("OUT_DIR").unwrap()).join("target"))
+        .env_remove("RUSTC_WORKSPACE_WRAPPER")
+        .output()
+        .expect("failed to run cargo");
+    assert!(
+        output.status.success(),
+        "failed to build `hazardflow-macro`:\n{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let path = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| serde_json::from_str::<serde_json::Value>(line).ok())
+        .filter(|message| message["reason"] == "compiler-artifact" && message["target"]["name"] == "hazardflow-macro")
+        .find_map(|message| message["filenames"][0].as_str().map(str::to_string))
+        .expect("no library is built for `hazardflow-macro`");
+    println!("cargo:rustc-env=HAZARDFLOW_MACRO_PATH={}", path);
+}
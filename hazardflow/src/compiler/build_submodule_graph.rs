@@ -51,7 +51,7 @@ enum FunctionTyp<'tcx> {
     InterfaceFsm(ModuleSig<'tcx>),
 
     /// Foregin function interface
-    Ffi { sig: ModuleSig<'tcx>, module_name: String, params: Vec<(String, usize)> },
+    Ffi { sig: ModuleSig<'tcx>, module_name: String, params: Vec<(String, usize)>, source: Option<String> },
 
     /// Submodule
     Submodule(ModuleSig<'tcx>, Instance<'tcx>),
@@ -185,7 +185,12 @@ impl<'tcx> ModuleGraphConstructor<'tcx, '_> {
                         }
                     }
 
-                    return FunctionTyp::Ffi { sig, module_name: *module_name, params: instantiated_params };
+                    return FunctionTyp::Ffi {
+                        sig,
+                        module_name: *module_name,
+                        params: instantiated_params,
+                        source: get_ffi_source(self.tcx, instance.def_id()),
+                    };
                 }
                 HazardFlowAttr::ModuleMagic(module_magic) => match module_magic {
                     ModuleMagic::ModuleSplit => match sig.ret_ty.as_ref() {
@@ -240,7 +245,10 @@ impl<'tcx> ModuleGraphConstructor<'tcx, '_> {
         args: &[ExprId],
         force_construction: Option<String>,
     ) -> VirgenResult<ModuleGraphValue<'tcx>> {
-        let args = args.iter().map(|arg| self.get_module_arg(*arg, force_construction.clone())).collect::<Vec<_>>();
+        let args = args
+            .iter()
+            .map(|arg| self.get_module_arg(*arg, force_construction.clone()))
+            .collect::<VirgenResult<Vec<_>>>()?;
 
         let (unwired_input_interface, module_arg) = self.get_wired_input_interface(&sig, &args, None);
 
@@ -507,10 +515,14 @@ impl<'tcx> ModuleGraphConstructor<'tcx, '_> {
         )
     }
 
-    fn construct_module_arg(&mut self, id: ExprId, force_construction: Option<String>) -> ModuleGraphValue<'tcx> {
+    fn construct_module_arg(
+        &mut self,
+        id: ExprId,
+        force_construction: Option<String>,
+    ) -> VirgenResult<ModuleGraphValue<'tcx>> {
         let expr = &self.thir_body.borrow().exprs[id];
-        match &expr.kind {
-            ExprKind::Scope { value, .. } => self.get_module_arg(*value, force_construction),
+        let module_arg = match &expr.kind {
+            ExprKind::Scope { value, .. } => self.get_module_arg(*value, force_construction)?,
             ExprKind::Call { fun, args, .. } => {
                 let function_id = self.skip_exprs(*fun).unwrap();
 
@@ -525,7 +537,7 @@ impl<'tcx> ModuleGraphConstructor<'tcx, '_> {
                     let args = args
                         .iter()
                         .map(|arg| self.get_module_arg(*arg, force_construction.clone()))
-                        .collect::<Vec<_>>();
+                        .collect::<VirgenResult<Vec<_>>>()?;
 
                     let ty = PortDecls::from_ty(self.monomorphise(expr.ty), self.tcx);
                     assert!(ty.is_some());
@@ -546,7 +558,7 @@ impl<'tcx> ModuleGraphConstructor<'tcx, '_> {
                                 self.tcx,
                                 args.into_iter().map(|arg| arg.function_arg().unwrap()).collect(),
                                 &mut FsmCache::default(),
-                            );
+                            )?;
 
                             assert!(displays.is_empty(), "trying to display outside of fsm");
 
@@ -558,11 +570,11 @@ impl<'tcx> ModuleGraphConstructor<'tcx, '_> {
             }
             ExprKind::Block { block } => {
                 let block = &self.thir_body.borrow()[*block];
-                block.expr.map(|expr| self.get_module_arg(expr, force_construction)).unwrap()
+                self.get_module_arg(block.expr.unwrap(), force_construction)?
             }
             ExprKind::Field { lhs, variant_index, name } => {
                 assert!(variant_index.index() == 0, "relax when needed");
-                match self.get_module_arg(*lhs, force_construction) {
+                match self.get_module_arg(*lhs, force_construction)? {
                     ModuleGraphValue::Interface(interface_arg) => match interface_arg {
                         InterfaceValue::ExternalInterface(path) => {
                             self.output_interface.wire(path.clone(), Interface::Unit);
@@ -633,11 +645,14 @@ impl<'tcx> ModuleGraphConstructor<'tcx, '_> {
                                 ModuleGraphValue::Unit => todo!(),
                             },
                             PatAccessNode::Variant { .. } => todo!(),
-                            PatAccessNode::Index(_) => todo!(),
+                            PatAccessNode::Index { .. } => todo!(),
+                            PatAccessNode::Subslice { .. } | PatAccessNode::Alternative(_) => {
+                                unreachable!("rejected by `check_patterns`")
+                            }
                         })
                     }
                     LocalVar::Stmt { expr_id, accessor, .. } => {
-                        let bounded_arg = self.get_module_arg(expr_id, force_construction);
+                        let bounded_arg = self.get_module_arg(expr_id, force_construction)?;
                         accessor.iter().fold(bounded_arg, |acc, elt| match elt {
                             PatAccessNode::Field { name, .. } => match acc {
                                 ModuleGraphValue::Interface(interface_arg) => match interface_arg {
@@ -683,7 +698,7 @@ impl<'tcx> ModuleGraphConstructor<'tcx, '_> {
                                 ModuleGraphValue::ConstantFunctionArgs(_) => todo!(),
                                 ModuleGraphValue::Unit => todo!(),
                             },
-                            PatAccessNode::Index(index) => match acc {
+                            PatAccessNode::Index { idx: index, .. } => match acc {
                                 ModuleGraphValue::Interface(interface_arg) => match interface_arg {
                                     InterfaceValue::CallResultInterface(interface) => match interface {
                                         Interface::Array(inner) => {
@@ -709,6 +724,9 @@ impl<'tcx> ModuleGraphConstructor<'tcx, '_> {
                                 ModuleGraphValue::Unit => todo!(),
                             },
                             PatAccessNode::Variant { .. } => panic!(),
+                            PatAccessNode::Subslice { .. } | PatAccessNode::Alternative(_) => {
+                                unreachable!("rejected by `check_patterns`")
+                            }
                         })
                     }
                     LocalVar::PatBinding { .. } => panic!(),
@@ -719,7 +737,7 @@ impl<'tcx> ModuleGraphConstructor<'tcx, '_> {
                     match id {
                         Id::Local(id) => {
                             if id == var_hir_id {
-                                return upvar.clone();
+                                return Ok(upvar.clone());
                             }
                         }
                         Id::Upvar(_) => todo!(),
@@ -733,23 +751,25 @@ impl<'tcx> ModuleGraphConstructor<'tcx, '_> {
                 {
                     let args = fields
                         .iter()
-                        .map(|arg| match self.get_module_arg(*arg, force_construction.clone()) {
-                            ModuleGraphValue::Interface(interface_arg) => match interface_arg {
-                                InterfaceValue::ExternalInterface(path) => {
-                                    self.output_interface.wire(path.clone(), Interface::Unit);
-                                    self.input_interface.get_subinterface(path)
-                                }
-                                InterfaceValue::CallResultInterface(i) => i,
-                            },
-                            _ => todo!(),
+                        .map(|arg| {
+                            Ok(match self.get_module_arg(*arg, force_construction.clone())? {
+                                ModuleGraphValue::Interface(interface_arg) => match interface_arg {
+                                    InterfaceValue::ExternalInterface(path) => {
+                                        self.output_interface.wire(path.clone(), Interface::Unit);
+                                        self.input_interface.get_subinterface(path)
+                                    }
+                                    InterfaceValue::CallResultInterface(i) => i,
+                                },
+                                _ => todo!(),
+                            })
                         })
-                        .collect::<Vec<_>>();
+                        .collect::<VirgenResult<Vec<_>>>()?;
                     InterfaceValue::call_result_interface(Interface::Array(args)).into()
                 } else {
                     let field_args = fields
                         .iter()
                         .map(|id| self.get_module_arg(*id, force_construction.clone()))
-                        .collect::<Vec<_>>();
+                        .collect::<VirgenResult<Vec<_>>>()?;
                     let arr_len = field_args.len();
                     if field_args.iter().all(|arg| matches!(arg, ModuleGraphValue::Module(_))) {
                         ModuleGraphValue::Module(ModuleValue::Composite(CompositeModuleArg::Array(
@@ -772,8 +792,10 @@ impl<'tcx> ModuleGraphConstructor<'tcx, '_> {
                 }
             }
             ExprKind::Tuple { fields } => {
-                let field_args =
-                    fields.iter().map(|id| self.get_module_arg(*id, force_construction.clone())).collect::<Vec<_>>();
+                let field_args = fields
+                    .iter()
+                    .map(|id| self.get_module_arg(*id, force_construction.clone()))
+                    .collect::<VirgenResult<Vec<_>>>()?;
                 if field_args.is_empty() {
                     ModuleGraphValue::Unit
                 } else if field_args
@@ -825,7 +847,7 @@ impl<'tcx> ModuleGraphConstructor<'tcx, '_> {
                                     let field_name =
                                         adt_def.variant(e.variant_index).fields[field_expr.name].name.to_ident_string();
                                     let x = match self
-                                        .get_module_arg(field_expr.expr, force_construction.clone())
+                                        .get_module_arg(field_expr.expr, force_construction.clone())?
                                         .interface_arg()
                                         .expect("we currenty expect composition of interfaces")
                                     {
@@ -835,9 +857,9 @@ impl<'tcx> ModuleGraphConstructor<'tcx, '_> {
                                             self.input_interface.get_subinterface(path.clone())
                                         }
                                     };
-                                    (field_name, (None, x))
+                                    Ok((field_name, (None, x)))
                                 })
-                                .collect();
+                                .collect::<VirgenResult<_>>()?;
 
                             let struct_interface = Interface::Struct(fields);
                             InterfaceValue::call_result_interface(struct_interface).into()
@@ -847,24 +869,26 @@ impl<'tcx> ModuleGraphConstructor<'tcx, '_> {
                     _ => panic!(),
                 }
             }
-            ExprKind::Closure(closure_expr) => self.closure_to_module_arg(closure_expr, expr.ty, force_construction),
+            ExprKind::Closure(closure_expr) => self.closure_to_module_arg(closure_expr, expr.ty, force_construction)?,
             ExprKind::Literal { lit, neg } => {
                 ModuleGraphValue::ConstantFunctionArgs(PureValue::Expr(build_literal(neg, lit, expr.ty, self.tcx)))
             }
             ExprKind::ZstLiteral { .. } => self.zst_lit_to_module_arg(expr.ty),
             ExprKind::PointerCoercion { cast, source } => match cast {
                 rustc_middle::ty::adjustment::PointerCoercion::ClosureFnPointer(unsafety) => match unsafety {
-                    rustc_hir::Unsafety::Normal => self.get_module_arg(*source, force_construction),
+                    rustc_hir::Unsafety::Normal => self.get_module_arg(*source, force_construction)?,
                     rustc_hir::Unsafety::Unsafe => panic!(),
                 },
                 // Go from a fn-item type to a fn-pointer type.
                 rustc_middle::ty::adjustment::PointerCoercion::ReifyFnPointer => {
-                    self.get_module_arg(*source, force_construction)
+                    self.get_module_arg(*source, force_construction)?
                 }
                 _ => panic!("{cast:#?}"),
             },
             _ => todo!("{expr:?}"),
-        }
+        };
+
+        Ok(module_arg)
     }
 
     // Returns the ModuleArg that corresponds to the given `ExprId`
@@ -876,20 +900,24 @@ impl<'tcx> ModuleGraphConstructor<'tcx, '_> {
     //    - This function will not search and store to the cache(`self.module_args`)
     //    - This function will construct(instantiate) a new module.
     // Currently, the `force_construction` is used when the module is constructed from the `from_fn` combinator.
-    fn get_module_arg(&mut self, id: ExprId, force_construction: Option<String>) -> ModuleGraphValue<'tcx> {
+    fn get_module_arg(
+        &mut self,
+        id: ExprId,
+        force_construction: Option<String>,
+    ) -> VirgenResult<ModuleGraphValue<'tcx>> {
         let expr = &self.thir_body.borrow().exprs[id];
         log::debug!("Get Arg: {expr:#?}\nspan: {:#?}", expr.span);
 
         // 1. If the module arg is already calculated, return it
         if force_construction.is_none() {
             if let Some(arg) = self.module_args.get(&id) {
-                return arg.clone();
+                return Ok(arg.clone());
             }
         }
 
         if let ExprKind::Tuple { fields } = &expr.kind {
             if fields.is_empty() {
-                return ModuleGraphValue::Unit;
+                return Ok(ModuleGraphValue::Unit);
             }
         }
 
@@ -901,14 +929,14 @@ impl<'tcx> ModuleGraphConstructor<'tcx, '_> {
                     upvars.iter().map(|(id, arg)| (*id, arg.function_arg().unwrap_or(PureValue::Misc))).collect_vec()
                 });
 
-                return ModuleGraphValue::ConstantFunctionArgs(PureValue::Expr(build_const_expr(
+                return Ok(ModuleGraphValue::ConstantFunctionArgs(PureValue::Expr(build_const_expr(
                     self.tcx,
                     id,
                     self.thir_body,
                     self.instance.args,
                     &self.args.iter().map(|arg| arg.function_arg().unwrap_or(PureValue::Misc)).collect::<Vec<_>>(),
                     upvars.as_deref(),
-                )));
+                )?)));
             }
         }
 
@@ -917,14 +945,14 @@ impl<'tcx> ModuleGraphConstructor<'tcx, '_> {
         }
 
         // 2. If the expression id doesn't exist, then construct module_arg
-        let module_arg = self.construct_module_arg(id, force_construction.clone());
+        let module_arg = self.construct_module_arg(id, force_construction.clone())?;
 
         // 3. Store the calculated module_arg to the expression id
         if force_construction.is_none() {
             self.insert_module_arg(id, module_arg.clone());
         }
 
-        module_arg
+        Ok(module_arg)
     }
 
     fn ty_to_instance(&self, ty: Ty<'tcx>) -> Option<Instance<'tcx>> {
@@ -948,7 +976,9 @@ impl<'tcx> ModuleGraphConstructor<'tcx, '_> {
 
     fn zst_lit_to_module_arg(&mut self, ty: Ty<'tcx>) -> ModuleGraphValue<'tcx> {
         match self.function_typ(ty) {
-            FunctionTyp::Ffi { sig, module_name, params } => self.construct_ffi(sig, module_name, params),
+            FunctionTyp::Ffi { sig, module_name, params, source } => {
+                self.construct_ffi(sig, module_name, params, source)
+            }
             FunctionTyp::Submodule(sig, instance) => {
                 let mut input_interface = Interface::Unwired(sig.input_interface_typ());
 
@@ -1028,6 +1058,7 @@ impl<'tcx> ModuleGraphConstructor<'tcx, '_> {
         sig: ModuleSig<'tcx>,
         module_name: String,
         params: Vec<(String, usize)>,
+        source: Option<String>,
     ) -> ModuleGraphValue<'tcx> {
         let mut input_interface = Interface::Unwired(sig.input_interface_typ());
 
@@ -1046,7 +1077,8 @@ impl<'tcx> ModuleGraphConstructor<'tcx, '_> {
 
         let submodule_index = self.submodules.len();
         let output_interface = submodule_output_interface(sig.output_interface_typ(), submodule_index);
-        let module = Ffi { sig, inst_name: format!("ffi_{module_name}_{}", submodule_index), module_name, params };
+        let module =
+            Ffi { sig, inst_name: format!("ffi_{module_name}_{}", submodule_index), module_name, params, source };
         // XXX: insert to interfaces..?
         // Maybe not since this path can only be reached when a function is being passed to
         // another function
@@ -1089,8 +1121,57 @@ impl<'tcx> ModuleGraphConstructor<'tcx, '_> {
         }
     }
 
+    /// Returns whether the values of `ty` are pure values, which are built as expressions instead of being resolved
+    /// into interfaces and modules.
+    fn is_pure_ty(&self, ty: Ty<'tcx>) -> bool {
+        let ty = self.monomorphise(ty);
+        InterfaceTyp::from_ty(ty, self.meta.interface_did(), self.tcx).is_err()
+            && PortDecls::from_ty(ty, self.tcx).is_some()
+    }
+
+    /// Rejects the patterns binding a subarray (`rest @ ..`) or the alternatives of an or-pattern of interfaces or
+    /// modules, which are only supported for pure values.
+    fn check_patterns(&self) -> VirgenResult<()> {
+        let thir_body = self.thir_body.borrow();
+        let lets = thir_body.stmts.iter().filter_map(|stmt| match &stmt.kind {
+            thir::StmtKind::Let { pattern, .. } => Some(pattern.as_ref()),
+            _ => None,
+        });
+        let params = thir_body.params.iter().filter_map(|param| param.pat.as_deref());
+
+        let binds = |pat: &thir::Pat<'tcx>| {
+            let mut binds = false;
+            pat.walk_always(|pat| binds |= matches!(pat.kind, thir::PatKind::Binding { .. }));
+            binds
+        };
+
+        for pat in lets.chain(params) {
+            let mut unsupported = None;
+            pat.walk_always(|pat| match &pat.kind {
+                thir::PatKind::Array { slice: Some(slice), .. } if binds(slice) && !self.is_pure_ty(pat.ty) => {
+                    unsupported.get_or_insert(("a subarray", pat.span));
+                }
+                thir::PatKind::Or { pats } if pats.iter().any(|pat| binds(pat)) && !self.is_pure_ty(pat.ty) => {
+                    unsupported.get_or_insert(("an or-pattern", pat.span));
+                }
+                _ => {}
+            });
+
+            if let Some((kind, span)) = unsupported {
+                return Err(VirgenError::collect_fsm_error(format!(
+                    "binding {kind} of interfaces or modules is not supported: {span:?}"
+                )));
+            }
+        }
+
+        Ok(())
+    }
+
     /// Construct module topology graph.
     fn construct_graph(mut self) -> VirgenResult<(Vec<ModuleGraphEdge<'tcx>>, Interface)> {
+        // 0. Reject the patterns which cannot be resolved into interfaces or modules
+        self.check_patterns()?;
+
         // 1. Traverse module function calls, and construct node/egde for each submodule
         self.traverse_function_calls()?;
 
@@ -1160,15 +1241,17 @@ impl<'tcx> ModuleGraphConstructor<'tcx, '_> {
             FunctionTyp::Seq { sig } => self.construct_module_seq(sig, args.as_ref(), force_construction)?,
             FunctionTyp::FromFn { n, .. } => self.construct_from_fn(n, args.as_ref(), force_construction)?,
             FunctionTyp::FnPtr => self.construct_fn_ptr(*fun, args, force_construction)?,
-            FunctionTyp::Ffi { sig, module_name, params } => {
-                let ffi = self.construct_ffi(sig, module_name, params);
+            FunctionTyp::Ffi { sig, module_name, params, source } => {
+                let ffi = self.construct_ffi(sig, module_name, params, source);
 
                 let ModuleGraphValue::Module(ModuleValue::Function { submodule_index, output_interface }) = ffi else {
                     panic!()
                 };
 
-                let args =
-                    args.iter().map(|arg| self.get_module_arg(*arg, force_construction.clone())).collect::<Vec<_>>();
+                let args = args
+                    .iter()
+                    .map(|arg| self.get_module_arg(*arg, force_construction.clone()))
+                    .collect::<VirgenResult<Vec<_>>>()?;
 
                 for (idx, arg) in args.into_iter().enumerate() {
                     match arg {
@@ -1306,7 +1389,7 @@ impl<'tcx> ModuleGraphConstructor<'tcx, '_> {
             }
         }
 
-        let arg = self.get_module_arg(final_expr_id, None);
+        let arg = self.get_module_arg(final_expr_id, None)?;
 
         if let Some(interface_arg) = arg.interface_arg() {
             match interface_arg {
@@ -1387,12 +1470,13 @@ impl<'tcx> ModuleGraphConstructor<'tcx, '_> {
         args: &[ExprId],
         force_construction: Option<String>,
     ) -> Result<ModuleGraphValue<'tcx>, VirgenError> {
-        let closure_arg = self.get_module_arg(args[0], force_construction.clone());
+        let closure_arg = self.get_module_arg(args[0], force_construction.clone())?;
         if let Some(module) = closure_arg.module_arg() {
             let args: Vec<ModuleGraphValue<'tcx>> = match &self.thir_body.borrow()[self.skip_exprs(args[1])?].kind {
-                ExprKind::Tuple { fields } => {
-                    fields.iter().map(|arg| self.get_module_arg(*arg, force_construction.clone())).collect()
-                }
+                ExprKind::Tuple { fields } => fields
+                    .iter()
+                    .map(|arg| self.get_module_arg(*arg, force_construction.clone()))
+                    .collect::<VirgenResult<_>>()?,
                 _ => panic!(),
             };
             match module {
@@ -1473,7 +1557,7 @@ impl<'tcx> ModuleGraphConstructor<'tcx, '_> {
     ) -> VirgenResult<ModuleGraphValue<'tcx>> {
         let [input_interface_id, init_value_id, fsm_logic_id] = args else { unreachable!() };
 
-        let input_interface = match self.get_module_arg(*input_interface_id, force_construction.clone()) {
+        let input_interface = match self.get_module_arg(*input_interface_id, force_construction.clone())? {
             ModuleGraphValue::Interface(interface_arg) => match interface_arg {
                 InterfaceValue::ExternalInterface(path) => {
                     self.output_interface.wire(path.clone(), Interface::Unit);
@@ -1496,7 +1580,7 @@ impl<'tcx> ModuleGraphConstructor<'tcx, '_> {
 
         let init_span = self.thir_body.borrow()[self.skip_exprs(*init_value_id)?].span;
 
-        let init_value = self.get_module_arg(*init_value_id, force_construction.clone());
+        let init_value = self.get_module_arg(*init_value_id, force_construction.clone())?;
 
         let fsm = Fsm {
             sig,
@@ -1509,7 +1593,7 @@ impl<'tcx> ModuleGraphConstructor<'tcx, '_> {
                 init_value.function_arg().unwrap().expr().unwrap()
             },
             fsm_logic: self
-                .get_module_arg(*fsm_logic_id, force_construction.clone())
+                .get_module_arg(*fsm_logic_id, force_construction.clone())?
                 .function_arg()
                 .unwrap()
                 .function()
@@ -1527,7 +1611,7 @@ impl<'tcx> ModuleGraphConstructor<'tcx, '_> {
         closure_expr: &ClosureExpr<'tcx>,
         ty: Ty<'tcx>,
         force_construction: Option<String>,
-    ) -> ModuleGraphValue<'tcx> {
+    ) -> VirgenResult<ModuleGraphValue<'tcx>> {
         let instance = self.ty_to_instance(ty).expect("TODO: take care when None");
         if let Some(sig) = ModuleSig::from_instance(self.tcx, self.meta, instance, self.sig.generic_map.clone().into())
         {
@@ -1539,7 +1623,7 @@ impl<'tcx> ModuleGraphConstructor<'tcx, '_> {
                 .upvars
                 .iter()
                 .map(|upvar| self.get_upvar(*upvar, force_construction.clone()))
-                .collect::<Vec<_>>();
+                .collect::<VirgenResult<Vec<_>>>()?;
             let submodule_index = self.submodules.len(); // index of the module we are constructing
             let constructed_output_interface = submodule_output_interface(sig.output_interface_typ(), submodule_index);
             for (upvar_idx, (_, upvar_arg)) in upvars.iter().enumerate() {
@@ -1642,11 +1726,11 @@ impl<'tcx> ModuleGraphConstructor<'tcx, '_> {
 
             self.submodules.push((module.into(), input_interface));
 
-            ModuleValue::closure_module(
+            Ok(ModuleValue::closure_module(
                 submodule_index,
                 constructed_output_interface.swap_field("captured", Interface::Unit),
             )
-            .into()
+            .into())
         } else {
             let instance = Instance::resolve(
                 self.tcx,
@@ -1666,26 +1750,30 @@ impl<'tcx> ModuleGraphConstructor<'tcx, '_> {
                 .upvars
                 .iter()
                 .map(|upvar| {
-                    let (id, arg) = self.get_upvar(*upvar, force_construction.clone());
-                    (id, arg.function_arg().unwrap())
+                    let (id, arg) = self.get_upvar(*upvar, force_construction.clone())?;
+                    Ok((id, arg.function_arg().unwrap()))
                 })
-                .collect();
+                .collect::<VirgenResult<_>>()?;
 
-            ModuleGraphValue::ConstantFunctionArgs(PureValue::Function(FunctionBuilder::new_closure(
+            Ok(ModuleGraphValue::ConstantFunctionArgs(PureValue::Function(FunctionBuilder::new_closure(
                 instance, upvars, self.tcx,
-            )))
+            ))))
         }
     }
 
-    fn get_upvar(&mut self, arg: ExprId, force_construction: Option<String>) -> (Id, ModuleGraphValue<'tcx>) {
+    fn get_upvar(
+        &mut self,
+        arg: ExprId,
+        force_construction: Option<String>,
+    ) -> VirgenResult<(Id, ModuleGraphValue<'tcx>)> {
         match &self.thir_body.borrow().exprs[arg].kind {
             ExprKind::Scope { lint_level, .. } => match lint_level {
                 thir::LintLevel::Inherited => todo!(),
-                thir::LintLevel::Explicit(id) => (Id::Upvar(*id), self.get_module_arg(arg, force_construction)),
+                thir::LintLevel::Explicit(id) => Ok((Id::Upvar(*id), self.get_module_arg(arg, force_construction)?)),
             },
-            ExprKind::VarRef { id, .. } => (Id::Local(*id), self.get_module_arg(arg, force_construction)),
+            ExprKind::VarRef { id, .. } => Ok((Id::Local(*id), self.get_module_arg(arg, force_construction)?)),
             ExprKind::UpvarRef { var_hir_id, .. } => {
-                (Id::Local(*var_hir_id), self.get_module_arg(arg, force_construction))
+                Ok((Id::Local(*var_hir_id), self.get_module_arg(arg, force_construction)?))
             }
             ExprKind::Borrow { borrow_kind, arg } => {
                 assert_eq!(*borrow_kind, BorrowKind::Shared);
@@ -1701,7 +1789,10 @@ impl<'tcx> ModuleGraphConstructor<'tcx, '_> {
         args: &[ExprId],
         force_construction: Option<String>,
     ) -> VirgenResult<ModuleGraphValue<'tcx>> {
-        let args = args.iter().map(|arg| self.get_module_arg(*arg, force_construction.clone())).collect::<Vec<_>>();
+        let args = args
+            .iter()
+            .map(|arg| self.get_module_arg(*arg, force_construction.clone()))
+            .collect::<VirgenResult<Vec<_>>>()?;
         let (unwired_input_interface, module_arg) = self.get_wired_input_interface(&sig, &args, None);
 
         let module_split = ModuleSplit { sig, module_name: "module_split".to_string() };
@@ -1721,7 +1812,7 @@ impl<'tcx> ModuleGraphConstructor<'tcx, '_> {
         // Create inner modules
         // This will instantiate the unwired inner modules
         assert!(args.len() == 1);
-        let seq_inner_modules = self.get_module_arg(args[0], force_construction);
+        let seq_inner_modules = self.get_module_arg(args[0], force_construction)?;
         assert!(matches!(
             seq_inner_modules,
             ModuleGraphValue::Module(ModuleValue::Composite(CompositeModuleArg::Array(..)))
@@ -1753,13 +1844,13 @@ impl<'tcx> ModuleGraphConstructor<'tcx, '_> {
         // When calling `get_module_arg`, if `force_construction` is not None, it will instantiate the module.
         // The first module is already instantiated, so we don't need to call `get_module_arg` with `Some` type of `force_construction`.
         // For n-1 modules, we call `get_module_arg` with `Some` type of `force_construction`.
-        let first_module_arg = self.get_module_arg(args[0], force_construction.clone());
+        let first_module_arg = self.get_module_arg(args[0], force_construction.clone())?;
         modules.push(first_module_arg.module_arg().unwrap().clone());
         for idx in 1..n {
             let module = self.get_module_arg(
                 args[0],
                 join_options("_", [force_construction.clone(), Some("from_fn".to_string()), Some(idx.to_string())]),
-            );
+            )?;
             modules.push(module.module_arg().unwrap().clone());
         }
 
@@ -1774,7 +1865,7 @@ impl<'tcx> ModuleGraphConstructor<'tcx, '_> {
         args: &[ExprId],
         force_construction: Option<String>,
     ) -> VirgenResult<ModuleGraphValue<'tcx>> {
-        let module_arg = self.get_module_arg(fn_ptr_id, force_construction.clone());
+        let module_arg = self.get_module_arg(fn_ptr_id, force_construction.clone())?;
 
         if let Some(module) = module_arg.module_arg() {
             match module {
@@ -1784,7 +1875,7 @@ impl<'tcx> ModuleGraphConstructor<'tcx, '_> {
                     let args = args
                         .iter()
                         .map(|arg| self.get_module_arg(*arg, force_construction.clone()))
-                        .collect::<Vec<_>>();
+                        .collect::<VirgenResult<Vec<_>>>()?;
 
                     let (node, edge) = self.submodules.get_mut(submodule_index).unwrap();
                     for (i, arg) in args.into_iter().enumerate() {
@@ -1824,7 +1915,7 @@ impl<'tcx> ModuleGraphConstructor<'tcx, '_> {
                     let args = args
                         .iter()
                         .map(|arg| self.get_module_arg(*arg, force_construction.clone()))
-                        .collect::<Vec<_>>();
+                        .collect::<VirgenResult<Vec<_>>>()?;
 
                     for (i, arg) in args.into_iter().enumerate() {
                         match arg {
@@ -3,8 +3,9 @@
 use std::collections::VecDeque;
 use std::ops::*;
 
-use itertools::izip;
+use itertools::{izip, Itertools};
 
+use super::debug_info::*;
 use super::*;
 use crate::some_or;
 use crate::utils::*;
@@ -220,6 +221,13 @@ impl Context {
         join_options("_", [self.get_prefix(), Some(self.alloc_temp_id())]).unwrap()
     }
 
+    /// Allocates net or reg named after a local variable.
+    ///
+    /// The name is suffixed with a temporary id, as the same name can be bound multiple times.
+    pub fn alloc_named_id_with_prefix(&mut self, name: &str) -> String {
+        join_options("_", [self.get_prefix(), Some(name.to_string()), Some(self.alloc_temp_id())]).unwrap()
+    }
+
     /// Refreshes fsm cache.
     ///
     /// XXX: This is a bad design
@@ -302,6 +310,9 @@ struct Accessor {
 
     /// Trace of array sizes.
     arr_trace: Vec<Option<usize>>,
+
+    /// Names of the enclosing struct fields, outermost first.
+    path: Vec<String>,
 }
 
 /// Logic value.
@@ -382,6 +393,7 @@ fn gen_ports(interface_typ: &InterfaceTyp) -> Vec<(Port, Accessor)> {
             .into_iter()
             .flat_map(|(name, (sep, interface_typ))| {
                 gen_ports(interface_typ).into_iter().map(|(port, mut accessor)| {
+                    accessor.path.insert(0, name.clone());
                     match accessor.prefix {
                         Some(prefix) => {
                             let sep = sep.clone().unwrap_or_else(|| "_".to_string());
@@ -528,6 +540,53 @@ pub(super) fn gen_port_decls<'tcx>(module: &Virgen<'tcx>) -> VirgenResult<Vec<(D
     Ok(port_decls)
 }
 
+/// Returns the debug information of the ports in the module.
+///
+/// The names of the ports are the same as in [`gen_port_decls`]. Channels whose types are unknown are skipped.
+#[allow(clippy::needless_lifetimes)]
+pub(super) fn gen_module_info<'tcx>(module: &Virgen<'tcx>) -> ModuleInfo {
+    let mut channels = vec![];
+
+    for (interface_typ, dir, path) in
+        [(module.input_interface_typ(), "in", "ingress"), (module.output_interface_typ(), "out", "egress")]
+    {
+        for (port, accessor) in gen_ports(&interface_typ) {
+            let Some((fwd_ty, bwd_ty)) = &port.channel_typ.tys else {
+                continue;
+            };
+            let path_sep = accessor.sep.unwrap_or_else(|| "_".to_string());
+            let port_prefix = join_options("_", [Some(dir.to_string()), accessor.prefix]);
+
+            for (kind, ty, port_decls) in
+                [("payload", fwd_ty, &port.channel_typ.fwd), ("resolver", bwd_ty, &port.channel_typ.bwd)]
+            {
+                let leaves = ty.leaves();
+                let port_decls = port_decls.iter().collect::<Vec<_>>();
+                if leaves.len() != port_decls.len() {
+                    log::warn!("Skipped debug information of {}: unexpected layout of `{:?}`", module.name(), ty);
+                    continue;
+                }
+
+                let signals = izip!(port_decls, leaves)
+                    .map(|((name, shape), (leaf_path, _))| SignalInfo {
+                        name: join_options(&path_sep, [port_prefix.clone(), Some(kind.to_string()), name]).unwrap(),
+                        path: leaf_path,
+                        width: shape.width() * port.size,
+                    })
+                    .collect();
+                channels.push(ChannelInfo {
+                    path: [path].into_iter().chain(accessor.path.iter().map(String::as_str)).chain([kind]).join("."),
+                    count: port.size,
+                    ty: ty.clone(),
+                    signals,
+                });
+            }
+        }
+    }
+
+    ModuleInfo { channels, instances: Default::default() }
+}
+
 /// Returns input/output wires for submodules in the module.
 ///
 /// # Returns
@@ -0,0 +1,752 @@
+//! Debug information of the generated modules.
+//!
+//! For each top module, the compiler writes `build/<top>/<top>.hfdbg.json`. It records the channels in the interfaces
+//! of the top module and its submodules: the Rust path and type of each payload and resolver, and the ports the value
+//! is flattened into, in the order of [`PortDecls::iter`]. With it, [`DebugInfo::decode_vcd`] shows the ports in a
+//! waveform as Rust values such as `Some(MemReq { addr: 0x80000004, fcn: Load, .. })`.
+//!
+//! The layout follows [`PortDecls::from_ty`]:
+//!
+//! - An enum is flattened into `discriminant` followed by the fields of every variant. A variant is encoded as its
+//!   discriminant, such as `i` for the `i`-th variant without explicit discriminants.
+//! - An array is flattened into the ports of its element, each of which packs the elements with element 0 in the least
+//!   significant bits. An array of interfaces packs the channels in the same way.
+//! - Zero-width ports are omitted.
+//!
+//! [`PortDecls::iter`]: super::prelude::PortDecls::iter
+//! [`PortDecls::from_ty`]: super::prelude::PortDecls::from_ty
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+
+use itertools::Itertools;
+use rustc_middle::ty::{AdtDef, GenericArgKind, GenericArgsRef, ParamEnv, Ty, TyCtxt, VariantDef};
+use rustc_type_ir::TyKind;
+use serde::{Deserialize, Serialize};
+
+use super::error::{VirgenError, VirgenResult};
+use crate::utils::*;
+use crate::vir::sim::{read_vcd, Bits};
+
+/// Debug information of a top module.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DebugInfo {
+    /// Name of the top module.
+    pub top: String,
+
+    /// Modules by their names.
+    pub modules: BTreeMap<String, ModuleInfo>,
+}
+
+/// Debug information of a module.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ModuleInfo {
+    /// Payloads and resolvers of the channels in the interfaces.
+    pub channels: Vec<ChannelInfo>,
+
+    /// Names of the instantiated modules by the instance names.
+    #[serde(default)]
+    pub instances: BTreeMap<String, String>,
+}
+
+/// Payload or resolver of a channel.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChannelInfo {
+    /// Rust path of the value, such as `ingress.input.0.payload`.
+    pub path: String,
+
+    /// Number of channels packed into the ports, if the channel is in arrays of interfaces.
+    pub count: usize,
+
+    /// Rust type of the value.
+    pub ty: TypeInfo,
+
+    /// Ports of the value.
+    pub signals: Vec<SignalInfo>,
+}
+
+/// Port of a value.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignalInfo {
+    /// Name of the port.
+    pub name: String,
+
+    /// Rust path of the bits in the value, such as `Some.0.addr`.
+    pub path: String,
+
+    /// Width of the port.
+    pub width: usize,
+}
+
+/// Rust type of a value.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum TypeInfo {
+    /// Boolean or integer, including `U<N>` and `S<N>`.
+    Bits {
+        /// Rust type.
+        ty: String,
+
+        /// Width.
+        width: usize,
+
+        /// Signedness.
+        signed: bool,
+    },
+
+    /// Array.
+    Array {
+        /// Rust type.
+        ty: String,
+
+        /// Element type.
+        elt: Box<TypeInfo>,
+
+        /// Length.
+        len: usize,
+    },
+
+    /// Struct or tuple.
+    Struct {
+        /// Rust type.
+        ty: String,
+
+        /// Name of the struct, or `None` for tuples.
+        name: Option<String>,
+
+        /// Fields.
+        fields: Vec<FieldInfo>,
+    },
+
+    /// Enum.
+    Enum {
+        /// Rust type.
+        ty: String,
+
+        /// Name of the enum.
+        name: String,
+
+        /// Width of the discriminant.
+        discriminant_width: usize,
+
+        /// Variants.
+        variants: Vec<VariantInfo>,
+    },
+}
+
+/// Field of a struct or an enum variant.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FieldInfo {
+    /// Name, or the index for tuple fields.
+    pub name: String,
+
+    /// Type.
+    pub ty: TypeInfo,
+}
+
+/// Enum variant.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VariantInfo {
+    /// Name.
+    pub name: String,
+
+    /// Encoded discriminant.
+    pub discriminant: usize,
+
+    /// Fields.
+    pub fields: Vec<FieldInfo>,
+}
+
+impl TypeInfo {
+    /// Returns the type information of `ty`, if it can be a signal type. See [`PortDecls::from_ty`](super::prelude::PortDecls::from_ty).
+    pub fn from_ty<'tcx>(ty: Ty<'tcx>, tcx: TyCtxt<'tcx>) -> Option<Self> {
+        match ty.kind() {
+            TyKind::Bool => Some(Self::Bits { ty: ty.to_string(), width: 1, signed: false }),
+            TyKind::Int(int_ty) => {
+                Some(Self::Bits { ty: ty.to_string(), width: int_ty.bit_width()?.try_into().ok()?, signed: true })
+            }
+            TyKind::Uint(uint_ty) => Some(Self::Bits {
+                ty: ty.to_string(),
+                width: uint_ty.bit_width().unwrap_or(32).try_into().ok()?,
+                signed: false,
+            }),
+            TyKind::Adt(def, args) => Self::from_adt(ty, def, args, tcx),
+            TyKind::Array(elt, len) => Some(Self::Array {
+                ty: ty.to_string(),
+                elt: Box::new(Self::from_ty(*elt, tcx)?),
+                len: len.eval_target_usize(tcx, ParamEnv::empty()) as usize,
+            }),
+            TyKind::Tuple(tys) => Some(Self::Struct {
+                ty: ty.to_string(),
+                name: None,
+                fields: tys
+                    .iter()
+                    .enumerate()
+                    .map(|(i, ty)| Some(FieldInfo { name: i.to_string(), ty: Self::from_ty(ty, tcx)? }))
+                    .collect::<Option<_>>()?,
+            }),
+            _ => None,
+        }
+    }
+
+    fn from_adt<'tcx>(ty: Ty<'tcx>, def: &AdtDef<'tcx>, args: GenericArgsRef<'tcx>, tcx: TyCtxt<'tcx>) -> Option<Self> {
+        let fields = |variant: &VariantDef| {
+            variant
+                .fields
+                .iter()
+                .map(|field| {
+                    let ty = normalize_alias_ty(tcx, tcx.type_of(field.did).instantiate(tcx, args));
+                    Some(FieldInfo { name: field.ident(tcx).to_string(), ty: Self::from_ty(ty, tcx)? })
+                })
+                .collect::<Option<Vec<_>>>()
+        };
+
+        let attr =
+            def.did().as_local().and_then(|local| get_hazardflow_attribute(tcx, tcx.local_def_id_to_hir_id(local)));
+        match def.adt_kind() {
+            rustc_middle::ty::AdtKind::Struct => {
+                if let Some(HazardFlowAttr::ExprMagic(ExprMagic::ArrayMagic(ArrayMagic::Array))) = attr {
+                    let GenericArgKind::Type(elt) = args.first()?.unpack() else {
+                        return None;
+                    };
+                    let len = evaluate_const_generic_arg(tcx, args.get(1)?)?;
+
+                    // `U<N>` is shown as an integer.
+                    if elt.is_bool() {
+                        return Some(Self::Bits { ty: ty.to_string(), width: len, signed: false });
+                    }
+                    return Some(Self::Array { ty: ty.to_string(), elt: Box::new(Self::from_ty(elt, tcx)?), len });
+                }
+
+                // `S<N>` is shown as a signed integer.
+                if let Some(HazardFlowAttr::ExprMagic(ExprMagic::IntMagic(IntMagic::Sint))) = attr {
+                    let width = evaluate_const_generic_arg(tcx, args.first()?)?;
+                    return Some(Self::Bits { ty: ty.to_string(), width, signed: true });
+                }
+
+                let fields = fields(def.non_enum_variant())?;
+
+                Some(Self::Struct { ty: ty.to_string(), name: Some(tcx.item_name(def.did()).to_string()), fields })
+            }
+            rustc_middle::ty::AdtKind::Enum => {
+                let discriminant_width = clog2(def.variants().len());
+                let variants = def
+                    .variants()
+                    .iter()
+                    .map(|variant| {
+                        let discriminant = get_variant_discriminator(tcx, variant) as usize;
+                        Some(VariantInfo {
+                            name: variant.ident(tcx).to_string(),
+                            discriminant: discriminant & ((1 << discriminant_width) - 1),
+                            fields: fields(variant)?,
+                        })
+                    })
+                    .collect::<Option<Vec<_>>>()?;
+                Some(Self::Enum {
+                    ty: ty.to_string(),
+                    name: tcx.item_name(def.did()).to_string(),
+                    discriminant_width,
+                    variants,
+                })
+            }
+            rustc_middle::ty::AdtKind::Union => None,
+        }
+    }
+
+    /// Returns the Rust path and width of each port the value is flattened into.
+    pub fn leaves(&self) -> Vec<(String, usize)> {
+        let mut leaves = vec![];
+        self.collect_leaves(String::new(), 1, &mut leaves);
+        leaves
+    }
+
+    fn collect_leaves(&self, path: String, count: usize, leaves: &mut Vec<(String, usize)>) {
+        let join = |name: &str| if path.is_empty() { name.to_string() } else { format!("{}.{}", path, name) };
+        match self {
+            Self::Bits { width, .. } => {
+                if width * count > 0 {
+                    leaves.push((path, width * count));
+                }
+            }
+            Self::Array { elt, len, .. } => elt.collect_leaves(format!("{}[]", path), count * len, leaves),
+            Self::Struct { fields, .. } => {
+                for field in fields {
+                    field.ty.collect_leaves(join(&field.name), count, leaves);
+                }
+            }
+            Self::Enum { discriminant_width, variants, .. } => {
+                if discriminant_width * count > 0 {
+                    leaves.push((join("discriminant"), discriminant_width * count));
+                }
+                for variant in variants {
+                    for field in &variant.fields {
+                        field.ty.collect_leaves(join(&format!("{}.{}", variant.name, field.name)), count, leaves);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Returns the all-zero value of the type, which is the value of unconnected ports.
+    pub fn zero(&self) -> Value {
+        self.decode(&[], &mut 0, &[])
+    }
+
+    /// Decodes a value from the values of the ports in [`TypeInfo::leaves`].
+    ///
+    /// `select` is the indices and lengths of the arrays enclosing the value, outermost first.
+    fn decode(&self, ports: &[Bits], next: &mut usize, select: &[(usize, usize)]) -> Value {
+        let index = select.iter().fold(0, |acc, (index, len)| acc * len + index);
+        let mut read = |width: usize| {
+            let count = select.iter().map(|(_, len)| len).product::<usize>();
+            if width * count == 0 {
+                return Bits::zero(width);
+            }
+            *next += 1;
+            ports.get(*next - 1).map_or(Bits::zero(width), |port| port.slice(index * width, width))
+        };
+
+        match self {
+            Self::Bits { ty, width, signed } => {
+                let value = read(*width);
+                if ty == "bool" {
+                    Value::Bool(!value.is_zero())
+                } else if *signed {
+                    Value::Int(value)
+                } else {
+                    Value::Uint(value)
+                }
+            }
+            Self::Array { elt, len, .. } => {
+                let start = *next;
+                Value::Array(
+                    (0..*len)
+                        .map(|i| {
+                            *next = start;
+                            elt.decode(ports, next, &[select, &[(i, *len)]].concat())
+                        })
+                        .collect(),
+                )
+            }
+            Self::Struct { name, fields, .. } => Value::Struct(
+                name.clone(),
+                fields.iter().map(|field| (field.name.clone(), field.ty.decode(ports, next, select))).collect(),
+            ),
+            Self::Enum { discriminant_width, variants, .. } => {
+                let discriminant = read(*discriminant_width).to_usize();
+                let variants = variants
+                    .iter()
+                    .map(|variant| {
+                        let fields = variant
+                            .fields
+                            .iter()
+                            .map(|field| (field.name.clone(), field.ty.decode(ports, next, select)))
+                            .collect::<Vec<_>>();
+                        (variant.discriminant, Value::Variant(variant.name.clone(), fields))
+                    })
+                    .collect::<Vec<_>>();
+                variants.into_iter().find(|(d, _)| *d == discriminant).map_or(
+                    Value::Variant(format!("<invalid discriminant {}>", discriminant), vec![]),
+                    |(_, variant)| variant,
+                )
+            }
+        }
+    }
+
+    /// Encodes a value into the values of the ports in [`TypeInfo::leaves`]. It is the inverse of `decode`.
+    fn encode(
+        &self,
+        value: &Value,
+        ports: &mut [Bits],
+        next: &mut usize,
+        select: &[(usize, usize)],
+    ) -> VirgenResult<()> {
+        let index = select.iter().fold(0, |acc, (index, len)| acc * len + index);
+        let count = select.iter().map(|(_, len)| len).product::<usize>();
+        let mut write = |bits: Bits| {
+            if bits.width() * count > 0 {
+                ports[*next].set_slice(index * bits.width(), &bits);
+                *next += 1;
+            }
+        };
+        let mismatch = || VirgenError::Misc { msg: format!("`{}` is not a value of `{}`", value, self.ty()) };
+
+        match (self, value) {
+            (Self::Bits { ty, width, .. }, Value::Bool(b)) if ty == "bool" || *width == 1 => {
+                write(Bits::from_bool(*b).resize(*width, false))
+            }
+            (Self::Bits { ty, width, signed }, Value::Uint(bits) | Value::Int(bits)) if ty != "bool" || *width == 1 => {
+                // Checks that the value is representable in the type, by comparing them in a wider width.
+                let wide = bits.resize(bits.width().max(*width) + 1, matches!(value, Value::Int(_)));
+                let resized = wide.resize(*width, false);
+                if resized.resize(wide.width(), *signed) != wide {
+                    return Err(VirgenError::Misc { msg: format!("`{}` does not fit in `{}`", value, ty) });
+                }
+                write(resized)
+            }
+            (Self::Array { elt, len, .. }, Value::Array(elts)) if elts.len() == *len => {
+                let start = *next;
+                for (i, elt_value) in elts.iter().enumerate() {
+                    *next = start;
+                    elt.encode(elt_value, ports, next, &[select, &[(i, *len)]].concat())?;
+                }
+                if *len == 0 {
+                    *next = start;
+                }
+            }
+            (Self::Struct { fields, .. }, Value::Struct(_, values)) => {
+                for field in fields {
+                    let value = values.iter().find(|(name, _)| *name == field.name).ok_or_else(mismatch)?;
+                    field.ty.encode(&value.1, ports, next, select)?;
+                }
+            }
+            (Self::Enum { discriminant_width, variants, .. }, Value::Variant(name, values)) => {
+                let variant = variants.iter().find(|variant| variant.name == *name).ok_or_else(mismatch)?;
+                write(Bits::from_u64(variant.discriminant as u64, *discriminant_width));
+                for other in variants {
+                    for field in &other.fields {
+                        if other.name != *name {
+                            field.ty.encode(&field.ty.zero(), ports, next, select)?;
+                            continue;
+                        }
+                        let value = values.iter().find(|(name, _)| *name == field.name).ok_or_else(mismatch)?;
+                        field.ty.encode(&value.1, ports, next, select)?;
+                    }
+                }
+            }
+            _ => return Err(mismatch()),
+        }
+
+        Ok(())
+    }
+
+    /// Returns the Rust type.
+    pub fn ty(&self) -> &str {
+        match self {
+            Self::Bits { ty, .. } | Self::Array { ty, .. } | Self::Struct { ty, .. } | Self::Enum { ty, .. } => ty,
+        }
+    }
+}
+
+/// Value of a signal type.
+///
+/// Integers are compared by their values regardless of their widths, and struct names are not compared, so that
+/// expected values can be written without the exact types:
+///
+/// ```ignore
+/// assert_eq!(payload, Value::some((Value::from(3u32), Value::uint(1, 3))));
+/// ```
+#[derive(Debug, Clone)]
+pub enum Value {
+    /// Boolean.
+    Bool(bool),
+
+    /// Unsigned integer, including `U<N>`.
+    Uint(Bits),
+
+    /// Signed integer, including `S<N>`.
+    Int(Bits),
+
+    /// Array.
+    Array(Vec<Value>),
+
+    /// Struct or tuple, with the name of the struct and the fields. Tuple fields are named by their indices.
+    Struct(Option<String>, Vec<(String, Value)>),
+
+    /// Enum variant, with the name of the variant and the fields.
+    Variant(String, Vec<(String, Value)>),
+}
+
+impl Value {
+    /// Returns an unsigned integer of the given width.
+    pub fn uint(value: u128, width: usize) -> Self {
+        Self::Uint(Bits::from_u128(value, width))
+    }
+
+    /// Returns `()`.
+    pub fn unit() -> Self {
+        Self::Struct(None, vec![])
+    }
+
+    /// Returns a tuple.
+    pub fn tuple(values: impl IntoIterator<Item = Value>) -> Self {
+        Self::Struct(None, values.into_iter().enumerate().map(|(i, value)| (i.to_string(), value)).collect())
+    }
+
+    /// Returns `Some(value)` of `HOption` or `Option`.
+    pub fn some(value: impl Into<Value>) -> Self {
+        Self::Variant("Some".to_string(), vec![("0".to_string(), value.into())])
+    }
+
+    /// Returns `None` of `HOption` or `Option`.
+    pub fn none() -> Self {
+        Self::Variant("None".to_string(), vec![])
+    }
+
+    /// Returns the boolean, if the value is a boolean.
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Self::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    /// Returns the integer, if the value is an integer which fits in `u64`.
+    pub fn as_u64(&self) -> Option<u64> {
+        match self {
+            Self::Uint(bits) if bits.resize(64, false).resize(bits.width(), false) == *bits => Some(bits.to_u64()),
+            Self::Int(bits) if !bits.msb() && bits.resize(64, false).resize(bits.width(), false) == *bits => {
+                Some(bits.to_u64())
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the integer, if the value is an integer which fits in `i64`.
+    pub fn as_i64(&self) -> Option<i64> {
+        let (bits, signed) = match self {
+            Self::Uint(bits) => (bits, false),
+            Self::Int(bits) => (bits, true),
+            _ => return None,
+        };
+        let wide = bits.width().max(64) + 1;
+        let value = bits.resize(64, signed);
+        (value.resize(wide, true) == bits.resize(wide, signed)).then(|| value.to_u64() as i64)
+    }
+
+    /// Returns the field of a struct or an enum variant.
+    pub fn field(&self, name: &str) -> Option<&Value> {
+        match self {
+            Self::Struct(_, fields) | Self::Variant(_, fields) => {
+                fields.iter().find(|(field, _)| field == name).map(|(_, value)| value)
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the name of the variant, if the value is an enum variant.
+    pub fn variant(&self) -> Option<&str> {
+        match self {
+            Self::Variant(name, _) => Some(name),
+            _ => None,
+        }
+    }
+
+    /// Returns the value in `Some`, if the value is `Some` of `HOption` or `Option`.
+    pub fn as_some(&self) -> Option<&Value> {
+        match self {
+            Self::Variant(name, fields) if name == "Some" && fields.len() == 1 => Some(&fields[0].1),
+            _ => None,
+        }
+    }
+
+    /// Returns the value with the field of a struct replaced.
+    pub fn with_field(mut self, name: &str, value: impl Into<Value>) -> Self {
+        if let Self::Struct(_, fields) | Self::Variant(_, fields) = &mut self {
+            if let Some((_, field)) = fields.iter_mut().find(|(field, _)| field == name) {
+                *field = value.into();
+            }
+        }
+        self
+    }
+}
+
+impl PartialEq for Value {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Bool(lhs), Self::Bool(rhs)) => lhs == rhs,
+            (Self::Uint(lhs) | Self::Int(lhs), Self::Uint(rhs) | Self::Int(rhs)) => {
+                let width = lhs.width().max(rhs.width()) + 1;
+                lhs.resize(width, matches!(self, Self::Int(_))) == rhs.resize(width, matches!(other, Self::Int(_)))
+            }
+            (Self::Array(lhs), Self::Array(rhs)) => lhs == rhs,
+            (Self::Struct(_, lhs), Self::Struct(_, rhs)) => lhs == rhs,
+            (Self::Variant(lhs_name, lhs), Self::Variant(rhs_name, rhs)) => lhs_name == rhs_name && lhs == rhs,
+            _ => false,
+        }
+    }
+}
+
+impl Eq for Value {}
+
+impl From<bool> for Value {
+    fn from(value: bool) -> Self {
+        Self::Bool(value)
+    }
+}
+
+macro_rules! impl_from_int {
+    ($($variant:ident: $($ty:ty),*;)*) => {
+        $($(
+            impl From<$ty> for Value {
+                #[allow(trivial_numeric_casts)]
+                fn from(value: $ty) -> Self {
+                    Self::$variant(Bits::from_u128(value as u128, <$ty>::BITS as usize))
+                }
+            }
+        )*)*
+    };
+}
+
+impl_from_int! {
+    Uint: u8, u16, u32, u64, u128;
+    Int: i8, i16, i32, i64, i128;
+}
+
+impl From<()> for Value {
+    fn from(_: ()) -> Self {
+        Self::unit()
+    }
+}
+
+impl<A: Into<Value>, B: Into<Value>> From<(A, B)> for Value {
+    fn from((a, b): (A, B)) -> Self {
+        Self::tuple([a.into(), b.into()])
+    }
+}
+
+impl<T: Into<Value>, const N: usize> From<[T; N]> for Value {
+    fn from(values: [T; N]) -> Self {
+        Self::Array(values.into_iter().map(Into::into).collect())
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Bool(b) => write!(f, "{}", b),
+            Self::Uint(bits) => write!(f, "0x{}", bits.to_str_radix(16)),
+            Self::Int(bits) if bits.width() > 0 && bits.msb() => write!(f, "-{}", bits.neg().to_str_radix(10)),
+            Self::Int(bits) => write!(f, "{}", bits.to_str_radix(10)),
+            Self::Array(elts) => write!(f, "[{}]", elts.iter().join(", ")),
+            Self::Struct(name, fields) => show_fields(f, name.as_deref().unwrap_or_default(), fields),
+            Self::Variant(name, fields) => show_fields(f, name, fields),
+        }
+    }
+}
+
+/// Shows a struct, a tuple, or an enum variant.
+fn show_fields(f: &mut fmt::Formatter<'_>, name: &str, fields: &[(String, Value)]) -> fmt::Result {
+    if fields.is_empty() {
+        return write!(f, "{}", if name.is_empty() { "()" } else { name });
+    }
+
+    let is_tuple = fields.iter().enumerate().all(|(i, (field, _))| *field == i.to_string());
+    if is_tuple {
+        let trailing = if name.is_empty() && fields.len() == 1 { "," } else { "" };
+        write!(f, "{}({}{})", name, fields.iter().map(|(_, value)| value).join(", "), trailing)
+    } else {
+        write!(f, "{} {{ {} }}", name, fields.iter().map(|(field, value)| format!("{}: {}", field, value)).join(", "))
+    }
+}
+
+impl ChannelInfo {
+    /// Decodes the value from the values of the ports.
+    ///
+    /// If the channel is in arrays of interfaces, returns the array of the values of the channels.
+    pub fn decode(&self, ports: &[Bits]) -> Value {
+        if self.count == 1 {
+            return self.ty.decode(ports, &mut 0, &[]);
+        }
+
+        Value::Array((0..self.count).map(|i| self.decode_at(ports, i)).collect())
+    }
+
+    /// Decodes the value of the `index`-th channel from the values of the ports.
+    pub fn decode_at(&self, ports: &[Bits], index: usize) -> Value {
+        self.ty.decode(ports, &mut 0, &[(index, self.count)])
+    }
+
+    /// Encodes the value of the `index`-th channel into the values of the ports, leaving the other channels.
+    pub fn encode_at(&self, ports: &mut [Bits], index: usize, value: &Value) -> VirgenResult<()> {
+        self.ty.encode(value, ports, &mut 0, &[(index, self.count)])
+    }
+}
+
+/// Value of a channel in a waveform.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecodedValue {
+    /// Time of the change.
+    pub time: u64,
+
+    /// Path of the value, which is the scope in the waveform followed by [`ChannelInfo::path`].
+    pub path: String,
+
+    /// Value.
+    pub value: Value,
+}
+
+impl DebugInfo {
+    /// Decodes the channels in a VCD file into Rust values, whenever they change.
+    ///
+    /// A scope in the waveform is matched to the module instantiated with the same instance name in the enclosing
+    /// module. Otherwise, it is matched to the module with the same name, or the name prefixed with the name of the
+    /// enclosing module. The outermost scope is matched to the top module.
+    pub fn decode_vcd(&self, vcd: &str) -> VirgenResult<Vec<DecodedValue>> {
+        let dump = read_vcd(vcd)?;
+
+        // Identifier codes of the variables in each scope.
+        let mut scopes = BTreeMap::<Vec<String>, HashMap<&str, &str>>::new();
+        for var in &dump.vars {
+            scopes.entry(var.scope.clone()).or_default().insert(&var.name, &var.code);
+        }
+
+        let mut channels = vec![];
+        for (scope, vars) in &scopes {
+            let Some(module) = self.module_of(scope) else {
+                continue;
+            };
+            for channel in &module.channels {
+                let codes = channel.signals.iter().map(|signal| vars.get(signal.name.as_str()).copied());
+                if let Some(codes) = codes.collect::<Option<Vec<_>>>() {
+                    channels.push((format!("{}.{}", scope.join("."), channel.path), channel, codes));
+                }
+            }
+        }
+
+        let mut values = HashMap::<&str, Bits>::new();
+        let mut shown = HashMap::<String, Value>::new();
+        let mut decoded = vec![];
+        for (time, changes) in &dump.changes {
+            for (code, value) in changes {
+                values.insert(code, value.clone());
+            }
+            for (path, channel, codes) in &channels {
+                if !codes.iter().any(|code| changes.iter().any(|(changed, _)| changed == code)) {
+                    continue;
+                }
+                let ports =
+                    codes.iter().map(|code| values.get(code).cloned().unwrap_or(Bits::zero(0))).collect::<Vec<_>>();
+                let value = channel.decode(&ports);
+                if shown.get(path) != Some(&value) {
+                    shown.insert(path.clone(), value.clone());
+                    decoded.push(DecodedValue { time: *time, path: path.clone(), value });
+                }
+            }
+        }
+
+        Ok(decoded)
+    }
+
+    /// Returns the module of a scope in the waveform.
+    fn module_of(&self, scope: &[String]) -> Option<&ModuleInfo> {
+        let mut name = None::<String>;
+        for (depth, scope) in scope.iter().enumerate() {
+            let parent = name.as_ref().and_then(|parent| self.modules.get(parent));
+            let instantiated = parent.and_then(|parent| parent.instances.get(scope)).cloned();
+            let prefixed = name.map(|parent| format!("{}_{}", parent, scope));
+            name = match instantiated.or(prefixed.filter(|name| self.modules.contains_key(name))) {
+                Some(module) => Some(module),
+                None if depth == 0 && !self.modules.contains_key(scope) => Some(self.top.clone()),
+                None => Some(scope.clone()),
+            };
+        }
+        self.modules.get(&name?)
+    }
+}
+
+/// Reads debug information from a file.
+pub fn read_debug_info(path: impl AsRef<std::path::Path>) -> VirgenResult<DebugInfo> {
+    let json = std::fs::read_to_string(path).map_err(|err| VirgenError::Fs { err })?;
+    serde_json::from_str(&json).map_err(|err| VirgenError::Misc { msg: format!("invalid debug information: {}", err) })
+}
@@ -49,6 +49,21 @@ pub enum VirgenError {
         /// Error message
         msg: String,
     },
+
+    /// Simulation error
+    #[error("Virgen Error Simulation: {msg:?}")]
+    SimulationError {
+        /// Error message
+        msg: String,
+    },
+
+    /// IR serialization error
+    #[cfg(feature = "vir-json")]
+    #[error("Virgen Error VIR JSON: {msg:?}")]
+    VirJsonError {
+        /// Error message
+        msg: String,
+    },
 }
 
 impl VirgenError {
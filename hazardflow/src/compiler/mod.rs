@@ -6,6 +6,7 @@ use rustc_interface::Queries;
 
 pub mod build_submodule_graph;
 pub mod codegen;
+pub mod debug_info;
 pub mod error;
 pub mod module;
 pub mod package;
@@ -53,6 +54,77 @@ pub struct Options {
 
     /// Merge all modules into a single file
     pub merge: bool,
+
+    /// Output format
+    pub emit: EmitKind,
+
+    /// Source location annotations in generated Verilog
+    pub src_loc: SrcLoc,
+
+    /// Generates the instances of a function differing only in widths and array sizes as a parameterized module
+    pub parameterize: bool,
+
+    /// Merges structurally identical modules
+    pub dedup: bool,
+}
+
+/// Output Format
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum EmitKind {
+    /// Verilog modules
+    Verilog,
+
+    /// Standalone Rust simulator of the integrated top module
+    RustSim,
+
+    /// SystemVerilog modules with the Rust types declared in a package
+    Sv,
+
+    /// CIRCT `hw`, `comb` and `seq` dialects of all modules in a single MLIR file
+    Circt,
+
+    /// VHDL-2008 entities and architectures
+    Vhdl,
+
+    /// Yosys RTLIL of all modules in a single file
+    Rtlil,
+
+    /// Yosys JSON netlist of all modules in a single file
+    YosysJson,
+
+    /// IR of all modules in JSON after optimization
+    #[cfg(feature = "vir-json")]
+    VirJson,
+}
+
+impl EmitKind {
+    /// Returns `true` if the output format needs the submodules integrated into the top module.
+    pub fn needs_integrate(&self) -> bool {
+        match self {
+            EmitKind::Verilog => false,
+            EmitKind::RustSim => true,
+            EmitKind::Sv => false,
+            EmitKind::Circt => false,
+            EmitKind::Vhdl => false,
+            EmitKind::Rtlil => false,
+            EmitKind::YosysJson => false,
+            #[cfg(feature = "vir-json")]
+            EmitKind::VirJson => false,
+        }
+    }
+}
+
+/// Source Location Annotation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum SrcLoc {
+    /// `// {span:?}` comments with the spans of rustc
+    None,
+
+    /// `// path/to/file.rs:line:col` comments
+    Comment,
+
+    /// `(* src = "path/to/file.rs:line:col" *)` attributes
+    Attribute,
 }
 
 /// Compile Target Specifier
@@ -75,6 +147,15 @@ impl CompileTarget {
     }
 }
 
+/// Keeps a copy of the `Thir` of each function, which is stolen while building MIR.
+pub(crate) fn override_queries(_session: &rustc_session::Session, providers: &mut rustc_middle::util::Providers) {
+    providers.mir_built = |tcx, def| {
+        let thir = thir_body(tcx, def).borrow().clone();
+        copy_thir_before_steal(def, thir.clone());
+        (rustc_interface::DEFAULT_QUERY_PROVIDERS.mir_built)(tcx, def) as _
+    };
+}
+
 /// Hazardflow Compiler
 #[derive(Debug)]
 pub struct Compiler {
@@ -92,13 +173,7 @@ impl Compiler {
 impl rustc_driver::Callbacks for Compiler {
     fn config(&mut self, config: &mut rustc_interface::Config) {
         assert!(config.override_queries.is_none());
-        config.override_queries = Some(|_session, providers| {
-            providers.mir_built = |tcx, def| {
-                let thir = thir_body(tcx, def).borrow().clone();
-                copy_thir_before_steal(def, thir.clone());
-                (rustc_interface::DEFAULT_QUERY_PROVIDERS.mir_built)(tcx, def) as _
-            };
-        });
+        config.override_queries = Some(override_queries);
     }
 
     fn after_expansion<'tcx>(
@@ -15,6 +15,8 @@ pub(crate) struct Ffi<'tcx> {
     pub(crate) inst_name: String,
     /// Module parameters.
     pub(crate) params: Vec<(String, usize)>,
+    /// Verilog source file.
+    pub(crate) source: Option<String>,
 }
 
 impl<'tcx> PrimitiveModule for Ffi<'tcx> {
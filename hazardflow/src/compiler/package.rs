@@ -1,6 +1,6 @@
 //! Package management for the Virgen build system.
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::fs;
 use std::io::Write;
 
@@ -8,9 +8,41 @@ use hir::def_id::DefId;
 use rustc_hir::{self as hir, ItemId};
 use rustc_middle::ty::TyCtxt;
 
+use super::debug_info::DebugInfo;
 use super::*;
+use crate::vir::sim::{instance_scopes, Scopes};
+use crate::vir::src_map::{gen_src_map, SrcLocAnnotator, SrcLocation, SrcMapping};
+use crate::vir::utils::instances;
 use crate::*;
 
+/// Number of lines of the header of a Verilog file.
+const VERILOG_HEADER_LINES: usize = 3;
+
+/// Number of cycles the generated Verilator `main` steps the top module.
+const HARNESS_CYCLES: usize = 1000;
+
+/// Modules generated from a top-level module.
+#[derive(Debug)]
+struct Design {
+    /// Name of the top module.
+    top_name: String,
+
+    /// Name of the build directory.
+    top_module_name: String,
+
+    /// Modules by their names.
+    modules: HashMap<String, vir::Module>,
+
+    /// Debug information.
+    debug_info: DebugInfo,
+
+    /// Const generic arguments of the modules.
+    generics: HashMap<String, vir::param::ConstGenerics>,
+
+    /// Verilog source files of the FFI modules.
+    ffi_sources: Vec<String>,
+}
+
 /// Traits that are reserved for the compiler
 #[derive(Debug, Clone)]
 pub enum LangTrait {
@@ -142,23 +174,85 @@ impl<'tcx> Package<'tcx> {
     pub(crate) fn build(&self) -> VirgenResult<()> {
         let top_modules = self.collect_top_level_synthesizables();
 
-        for top_module in top_modules {
-            self.build_top_module(top_module)?;
+        let mut designs = top_modules
+            .into_iter()
+            .map(|top_module| self.build_design(top_module))
+            .collect::<VirgenResult<Vec<_>>>()?;
+
+        if self.options.dedup {
+            self.dedup(&mut designs);
+        }
+
+        for design in designs {
+            self.build_top_module(design)?;
         }
 
         Ok(())
     }
 
-    fn build_top_module(&self, top_module: Virgen<'tcx>) -> Result<(), VirgenError> {
-        let (top_name, top_module_name, mut vir_modules) = self.virgen_modules(top_module)?;
+    /// Builds the package into integrated and optimized modules with their debug information, without writing them.
+    ///
+    /// Returns the name of each top-level module, which is the name of its build directory, with the outputs and the
+    /// scopes of the signals in the waveform.
+    pub(crate) fn build_integrated(&self) -> VirgenResult<Vec<(String, vir::Module, DebugInfo, Scopes)>> {
+        self.collect_top_level_synthesizables()
+            .into_iter()
+            .map(|top_module| {
+                let Design { top_name, top_module_name, modules, debug_info, .. } = self.virgen_modules(top_module)?;
+                let scopes = instance_scopes(&modules, &top_name);
+                let top = self.optimize(vir::integrate(modules, top_name));
+                self.analyze(&top)?;
+                Ok((top_module_name, top, debug_info, scopes))
+            })
+            .collect()
+    }
+
+    /// Generates, integrates and optimizes the modules of the top-level module.
+    fn build_design(&self, top_module: Virgen<'tcx>) -> VirgenResult<Design> {
+        let mut design = self.virgen_modules(top_module)?;
 
-        if self.options.integrate {
-            let top = vir::integrate(vir_modules, top_name.clone());
-            vir_modules = HashMap::new();
-            vir_modules.insert(top_name.clone(), top);
+        if self.options.integrate || self.options.emit.needs_integrate() {
+            let top = vir::integrate(std::mem::take(&mut design.modules), design.top_name.clone());
+            design.modules.insert(design.top_name.clone(), top);
         }
 
-        let dirpath = self.options.build_dir.join(top_module_name);
+        design.modules = design
+            .modules
+            .into_iter()
+            .map(|(name, vir_module)| {
+                let vir_module = self.optimize(vir_module);
+                self.analyze(&vir_module)?;
+                Ok((name, vir_module))
+            })
+            .collect::<VirgenResult<_>>()?;
+
+        Ok(design)
+    }
+
+    /// Merges the structurally identical modules of the designs, with their debug information and const generic
+    /// arguments.
+    fn dedup(&self, designs: &mut [Design]) {
+        let tops = designs.iter().map(|design| design.top_name.clone()).collect::<Vec<_>>();
+        let mut modules = designs.iter_mut().map(|design| std::mem::take(&mut design.modules)).collect::<Vec<_>>();
+        let canonical_names = vir::dedup(&mut modules, &tops);
+
+        for (design, modules) in designs.iter_mut().zip(modules) {
+            design.modules = modules;
+            for (name, canonical) in &canonical_names {
+                if let Some(module_info) = design.debug_info.modules.remove(name) {
+                    design.debug_info.modules.entry(canonical.clone()).or_insert(module_info);
+                }
+                if let Some(generics) = design.generics.remove(name) {
+                    design.generics.entry(canonical.clone()).or_insert(generics);
+                }
+            }
+        }
+    }
+
+    fn build_top_module(&self, design: Design) -> Result<(), VirgenError> {
+        let Design { top_name, top_module_name, modules: vir_modules, debug_info, mut generics, ffi_sources } = design;
+
+        let dirpath = self.options.build_dir.join(&top_module_name);
         // Creates a directory for module.
         if !dirpath.exists() {
             fs::create_dir(&dirpath).map_err(|err| VirgenError::Fs { err })?;
@@ -167,42 +261,190 @@ impl<'tcx> Package<'tcx> {
             fs::create_dir(&dirpath).map_err(|err| VirgenError::Fs { err })?;
         }
 
-        let mut merged_file = if self.options.merge {
-            let mut file =
-                fs::File::create(dirpath.join(format!("{}.v", top_name))).map_err(|err| VirgenError::Fs { err })?;
-            writeln!(file, "`timescale 1ns / 1ps\n\n").map_err(|err| VirgenError::Fs { err })?;
+        let debug_json =
+            serde_json::to_string_pretty(&debug_info).map_err(|err| VirgenError::Misc { msg: err.to_string() })?;
+        fs::write(dirpath.join(format!("{}.hfdbg.json", top_module_name)), debug_json)
+            .map_err(|err| VirgenError::Fs { err })?;
+
+        // The files of the modules are listed after the files of their submodules.
+        let order = vir::verilator::dependency_order(&vir_modules);
+        let top_module = vir_modules[&top_name].clone();
+
+        // The SystemVerilog modules import the types from a package.
+        let sv_types = vir::sv::SvTypes::new(&debug_info);
+        let sv_package = format!("{}_pkg", top_name);
+
+        let mut merged_file = match (self.options.merge, self.options.emit) {
+            (true, EmitKind::Verilog) => {
+                let mut file =
+                    fs::File::create(dirpath.join(format!("{}.v", top_name))).map_err(|err| VirgenError::Fs { err })?;
+                writeln!(file, "`timescale 1ns / 1ps\n\n").map_err(|err| VirgenError::Fs { err })?;
+
+                Some(file)
+            }
+            (true, EmitKind::Sv) => {
+                let mut file = fs::File::create(dirpath.join(format!("{}.sv", top_name)))
+                    .map_err(|err| VirgenError::Fs { err })?;
+                writeln!(file, "`timescale 1ns / 1ps\n\n{}\n", sv_types.gen_package(&sv_package))
+                    .map_err(|err| VirgenError::Fs { err })?;
 
-            Some(file)
-        } else {
-            None
+                Some(file)
+            }
+            _ => None,
         };
 
+        if !self.options.merge && self.options.emit == EmitKind::Sv {
+            fs::write(dirpath.join(format!("{}.sv", sv_package)), format!("{}\n", sv_types.gen_package(&sv_package)))
+                .map_err(|err| VirgenError::Fs { err })?;
+        }
+
+        // Lines of each Verilog file mapped to the Rust source, and the number of lines written in the merged file.
+        let mut src_map = BTreeMap::new();
+        let mut merged_lines = VERILOG_HEADER_LINES;
+
+        // The CIRCT, VHDL and Yosys modules are generated together, as instantiated modules are referred by their ports.
+        let mut modules = vec![];
+
+        // The Verilog modules, which are generated together when parameterized.
+        let locate = |span| self.locate(span);
+        let annotator = SrcLocAnnotator::new(self.options.src_loc, &locate);
+        let mut verilogs = vec![];
+        let mut parameterized = vec![];
+
         for (name, vir_module) in vir_modules {
-            let vir_module = self.optimize(vir_module);
+            match self.options.emit {
+                EmitKind::Verilog if self.options.parameterize => {
+                    parameterized.push((vir_module, generics.remove(&name).unwrap()))
+                }
+                EmitKind::Verilog => verilogs.push((name, vir_module.gen_verilog(&annotator))),
+                EmitKind::RustSim => {
+                    let mut file = fs::File::create(dirpath.join(format!("{}.rs", name)))
+                        .map_err(|err| VirgenError::Fs { err })?;
+                    self.dump_rust_sim(&mut file, vir_module)?;
+                }
+                EmitKind::Sv => {
+                    let sv_module = vir::sv::gen_sv_module(&vir_module, &debug_info, &sv_types, &sv_package);
+                    if let Some(merged_file) = &mut merged_file {
+                        writeln!(merged_file, "{}\n", sv_module).map_err(|err| VirgenError::Fs { err })?;
+                    } else {
+                        let mut file = fs::File::create(dirpath.join(format!("{}.sv", name)))
+                            .map_err(|err| VirgenError::Fs { err })?;
+                        writeln!(file, "`timescale 1ns / 1ps\n\n{}", sv_module)
+                            .map_err(|err| VirgenError::Fs { err })?;
+                    }
+                }
+                EmitKind::Circt | EmitKind::Vhdl | EmitKind::Rtlil | EmitKind::YosysJson => modules.push(vir_module),
+                #[cfg(feature = "vir-json")]
+                EmitKind::VirJson => modules.push(vir_module),
+            }
+        }
 
-            self.analyze(&vir_module)?;
+        if self.options.parameterize {
+            verilogs = vir::param::gen_parameterized_verilog(parameterized, &annotator);
+        }
+
+        let files = match (self.options.merge, self.options.emit) {
+            (true, EmitKind::Verilog) => vec![format!("{}.v", top_name)],
+            (true, EmitKind::Sv) => vec![format!("{}.sv", top_name)],
+            (false, EmitKind::Verilog) => order
+                .iter()
+                .filter(|name| verilogs.iter().any(|(verilog, _)| verilog == *name))
+                .map(|name| format!("{}.v", name))
+                .collect(),
+            (false, EmitKind::Sv) => {
+                std::iter::once(&sv_package).chain(&order).map(|name| format!("{}.sv", name)).collect()
+            }
+            _ => vec![],
+        };
+        let filelist = format!("{}.f", top_module_name);
+        if !files.is_empty() {
+            fs::write(dirpath.join(&filelist), vir::verilator::gen_filelist(&ffi_sources, &files))
+                .map_err(|err| VirgenError::Fs { err })?;
+        }
+
+        // The SystemVerilog ports are typed, so the harness accessing the flattened ports is generated for Verilog.
+        if self.options.emit == EmitKind::Verilog {
+            fs::write(
+                dirpath.join(format!("{}_harness.h", top_name)),
+                vir::verilator::gen_harness(&top_module, &debug_info),
+            )
+            .map_err(|err| VirgenError::Fs { err })?;
+            fs::write(
+                dirpath.join(format!("{}_main.cpp", top_name)),
+                vir::verilator::gen_main(&top_name, &filelist, HARNESS_CYCLES),
+            )
+            .map_err(|err| VirgenError::Fs { err })?;
+        }
 
+        for (name, verilog) in verilogs {
             if let Some(merged_file) = &mut merged_file {
-                self.dump_verilog(merged_file, vir_module)?;
+                let mappings = src_map.entry(format!("{}.v", top_name)).or_default();
+                merged_lines += self.dump_verilog(merged_file, &verilog, merged_lines, mappings)?;
             } else {
                 let mut file =
                     fs::File::create(dirpath.join(format!("{}.v", name))).map_err(|err| VirgenError::Fs { err })?;
                 writeln!(file, "`timescale 1ns / 1ps\n\n").map_err(|err| VirgenError::Fs { err })?;
-                self.dump_verilog(&mut file, vir_module)?;
+                let mappings = src_map.entry(format!("{}.v", name)).or_default();
+                self.dump_verilog(&mut file, &verilog, VERILOG_HEADER_LINES, mappings)?;
             }
         }
 
+        modules.sort_by(|lhs, rhs| lhs.name.cmp(&rhs.name));
+        match self.options.emit {
+            EmitKind::Circt => {
+                let circt = vir::circt::gen_circt(&modules)?;
+                fs::write(dirpath.join(format!("{}.mlir", top_name)), format!("{}\n", circt))
+                    .map_err(|err| VirgenError::Fs { err })?;
+            }
+            EmitKind::Vhdl => {
+                let vhdl = vir::vhdl::gen_vhdl(&modules)?;
+                if self.options.merge {
+                    fs::write(dirpath.join(format!("{}.vhd", top_name)), format!("{}\n", vhdl.join("\n\n")))
+                        .map_err(|err| VirgenError::Fs { err })?;
+                } else {
+                    for (module, vhdl) in modules.iter().zip(vhdl) {
+                        fs::write(dirpath.join(format!("{}.vhd", module.name)), format!("{}\n", vhdl))
+                            .map_err(|err| VirgenError::Fs { err })?;
+                    }
+                }
+            }
+            EmitKind::Rtlil => {
+                let rtlil = vir::yosys::gen_rtlil(&modules, &top_name)?;
+                fs::write(dirpath.join(format!("{}.il", top_name)), format!("{}\n", rtlil))
+                    .map_err(|err| VirgenError::Fs { err })?;
+            }
+            EmitKind::YosysJson => {
+                let json = vir::yosys::gen_yosys_json(&modules, &top_name)?;
+                fs::write(dirpath.join(format!("{}.yosys.json", top_name)), format!("{}\n", json))
+                    .map_err(|err| VirgenError::Fs { err })?;
+            }
+            #[cfg(feature = "vir-json")]
+            EmitKind::VirJson => {
+                let json = vir::json::gen_vir_json(&modules, &locate)?;
+                fs::write(dirpath.join(format!("{}.vir.json", top_name)), format!("{}\n", json))
+                    .map_err(|err| VirgenError::Fs { err })?;
+            }
+            _ => {}
+        }
+
+        if self.options.emit == EmitKind::Verilog && self.options.src_loc != SrcLoc::None {
+            let src_map_json =
+                serde_json::to_string_pretty(&src_map).map_err(|err| VirgenError::Misc { msg: err.to_string() })?;
+            fs::write(dirpath.join(format!("{}.srcmap.json", top_module_name)), src_map_json)
+                .map_err(|err| VirgenError::Fs { err })?;
+        }
+
         Ok(())
     }
 
-    fn virgen_modules(
-        &self,
-        top_module: Virgen<'tcx>,
-    ) -> Result<(String, String, HashMap<String, vir::Module>), VirgenError> {
+    fn virgen_modules(&self, top_module: Virgen<'tcx>) -> Result<Design, VirgenError> {
         let top_name = top_module.name();
         let top_module_name = top_module.top_module_name();
         let mut modules = vec![top_module];
         let mut vir_modules = HashMap::new();
+        let mut debug_info = DebugInfo { top: top_name.clone(), modules: Default::default() };
+        let mut generics = HashMap::new();
+        let mut ffi_sources = vec![];
 
         while let Some(mut module) = modules.pop() {
             let submodules = module.preprocess()?;
@@ -217,7 +459,15 @@ impl<'tcx> Package<'tcx> {
             match module.virgen() {
                 Ok(vir_module) => {
                     log::info!("Synthesized {}/{}.v", self.options.build_dir.to_string_lossy(), module.name());
+                    let mut module_info = module.debug_info();
+                    module_info.instances = instances(&vir_module.module_items)
+                        .into_iter()
+                        .map(|inst| (inst.inst_name.clone(), inst.module_name.clone()))
+                        .collect();
                     vir_modules.insert(module.name(), vir_module);
+                    debug_info.modules.insert(module.name(), module_info);
+                    generics.insert(module.name(), module.const_generics());
+                    ffi_sources.extend(module.ffi_sources());
                 }
                 Err(e) => {
                     log::error!("Failed to synthesize {}\n{}", module.name(), e);
@@ -225,12 +475,40 @@ impl<'tcx> Package<'tcx> {
             };
         }
 
-        Ok((top_name, top_module_name, vir_modules))
+        ffi_sources.sort();
+        ffi_sources.dedup();
+
+        Ok(Design { top_name, top_module_name, modules: vir_modules, debug_info, generics, ffi_sources })
     }
 
     // Dumps Verilog code.
-    fn dump_verilog(&self, file: &mut std::fs::File, vir_module: vir::Module) -> Result<(), VirgenError> {
-        writeln!(file, "{}", vir_module.to_string()).map_err(|err| VirgenError::Fs { err })?;
+    //
+    // The lines annotated with source locations are appended to `src_map`, offset by the `lines` already written in
+    // the file. Returns the number of lines written.
+    fn dump_verilog(
+        &self,
+        file: &mut std::fs::File,
+        verilog: &str,
+        lines: usize,
+        src_map: &mut Vec<SrcMapping>,
+    ) -> Result<usize, VirgenError> {
+        writeln!(file, "{}", verilog).map_err(|err| VirgenError::Fs { err })?;
+
+        src_map.extend(
+            gen_src_map(verilog).into_iter().map(|mapping| SrcMapping { line: lines + mapping.line, ..mapping }),
+        );
+        Ok(verilog.lines().count())
+    }
+
+    /// Returns the location of the source code of `span`, or of the macro invocation expanded into it.
+    fn locate(&self, span: rustc_span::Span) -> Option<SrcLocation> {
+        let (file, line, column, ..) = self.tcx.sess.source_map().span_to_location_info(span.source_callsite());
+        Some(SrcLocation { file: file?.name.prefer_local().to_string(), line, column })
+    }
+
+    // Dumps Rust simulator.
+    fn dump_rust_sim(&self, file: &mut std::fs::File, vir_module: vir::Module) -> Result<(), VirgenError> {
+        writeln!(file, "{}", vir::sim::gen_rust_sim(&vir_module)?).map_err(|err| VirgenError::Fs { err })?;
 
         Ok(())
     }
@@ -258,7 +536,7 @@ impl<'tcx> Package<'tcx> {
         let mut analysis: Vec<(&str, fn(&vir::Module) -> Result<(), VirgenError>)> = vec![];
 
         if self.options.detect_comb_loop {
-            assert!(self.options.integrate);
+            assert!(self.options.integrate || self.options.emit.needs_integrate());
 
             analysis.push(("detect_comb_loop", vir::analysis::detect_comb_loop))
         }
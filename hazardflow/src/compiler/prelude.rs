@@ -12,11 +12,13 @@ use rustc_middle::ty::{
 };
 use rustc_type_ir::TyKind;
 
+use super::debug_info::TypeInfo;
 use super::error::{VirgenError, VirgenResult};
 use crate::utils::*;
 
 /// Shape of an array.
 #[derive(Default, Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "vir-json", derive(serde::Serialize, serde::Deserialize))]
 pub struct Shape {
     inner: VecDeque<usize>,
 
@@ -46,6 +48,11 @@ impl Shape {
         *self.inner.get(index).unwrap()
     }
 
+    /// Returns the sizes of the dimensions.
+    pub(crate) fn dims_mut(&mut self) -> impl Iterator<Item = &mut usize> {
+        self.inner.iter_mut()
+    }
+
     /// TODO: Documentation
     #[must_use]
     pub fn multiple(&self, n: usize) -> Self {
@@ -142,6 +149,12 @@ pub enum AdtLayout {
         /// Length of array
         len: usize,
     },
+
+    /// Primitive signed integer type
+    Sint {
+        /// Bitwidth
+        width: usize,
+    },
 }
 
 impl AdtLayout {
@@ -161,6 +174,11 @@ impl AdtLayout {
                     return Self::Array { elt_ty, len };
                 }
 
+                if let Some(HazardFlowAttr::ExprMagic(ExprMagic::IntMagic(IntMagic::Sint))) = attr {
+                    let width = evaluate_const_generic_arg(tcx, generic_args.first().unwrap()).unwrap();
+                    return Self::Sint { width };
+                }
+
                 assert!(def.variants().len() == 1);
 
                 let struct_def = def.variant(0u32.into());
@@ -215,7 +233,7 @@ impl AdtLayout {
                 EnumEncodingTy::Grey => todo!(),
             },
             AdtLayout::Struct { .. } => panic!(),
-            AdtLayout::Array { .. } => panic!(),
+            AdtLayout::Array { .. } | AdtLayout::Sint { .. } => panic!(),
         }
     }
 
@@ -233,6 +251,7 @@ impl AdtLayout {
                 PortDecls::Struct(fields.iter().map(|(name, field)| (Some(name.clone()), field.clone())).collect())
             }
             AdtLayout::Array { elt_ty, len } => elt_ty.multiple(*len),
+            AdtLayout::Sint { width } => PortDecls::signed_bits(*width),
         }
     }
 }
@@ -343,7 +362,8 @@ impl PortDecls {
                     .collect::<Option<Vec<_>>>()?;
                 Some(Self::Struct(inner))
             }
-            TyKind::Ref(r, t, m) => todo!("ref type {:#?} {:#?} {:#?}", r, t, m),
+            // References are transparent, e.g., `&mut self` of the methods assigning `self`.
+            TyKind::Ref(_, ty, _) => Self::from_ty(*ty, tcx),
             unsupported_ty => {
                 log::debug!(
                     "unsupported type conversion from rust Type {:#?} to PortDecls. You might need to normalize the type before the type conversion.",
@@ -469,22 +489,34 @@ impl Iterator for ValueTypIterator {
 }
 
 /// Channel's type.
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone)]
 pub struct ChannelTyp {
     /// Forward value.
     pub fwd: PortDecls,
 
     /// Backward value.
     pub bwd: PortDecls,
+
+    /// Rust types of the forward and backward values, for the debug information.
+    pub tys: Option<(TypeInfo, TypeInfo)>,
 }
 
 impl ChannelTyp {
     /// Creates a new channel type.
     pub const fn new(fwd: PortDecls, bwd: PortDecls) -> Self {
-        Self { fwd, bwd }
+        Self { fwd, bwd, tys: None }
+    }
+}
+
+// NOTE: The Rust types are not compared because they do not affect the generated ports.
+impl PartialEq for ChannelTyp {
+    fn eq(&self, other: &Self) -> bool {
+        self.fwd == other.fwd && self.bwd == other.bwd
     }
 }
 
+impl Eq for ChannelTyp {}
+
 /// Interface's type.
 #[allow(variant_size_differences)]
 #[derive(Debug, Clone, Eq)]
@@ -618,20 +650,23 @@ impl InterfaceTyp {
                     })
                     // .map(|(name, ty)| (name, tcx.type_of(ty.hir_id.owner.def_id).subst(tcx, substs)))
                     .collect::<Vec<_>>();
-                let fwd = assoc_items
+                let fwd_ty = assoc_items
                     .iter()
                     .find(|(name, _)| name == "Fwd")
                     .map(|(_, x)| normalize_alias_ty(tcx, *x))
-                    .and_then(|ty| PortDecls::from_ty(ty, tcx))
                     .ok_or_else(|| VirgenError::Misc { msg: "Interface impl does not have `Fwd`".to_string() })?;
-                let bwd = assoc_items
+                let fwd = PortDecls::from_ty(fwd_ty, tcx)
+                    .ok_or_else(|| VirgenError::Misc { msg: "Interface impl does not have `Fwd`".to_string() })?;
+                let bwd_ty = assoc_items
                     .iter()
                     .find(|(name, _)| name == "Bwd")
                     .map(|(_, x)| normalize_alias_ty(tcx, *x))
-                    .and_then(|ty| PortDecls::from_ty(ty, tcx))
                     .ok_or_else(|| VirgenError::Misc { msg: "Interface impl does not have `Bwd`".to_string() })?;
+                let bwd = PortDecls::from_ty(bwd_ty, tcx)
+                    .ok_or_else(|| VirgenError::Misc { msg: "Interface impl does not have `Bwd`".to_string() })?;
+                let tys = TypeInfo::from_ty(fwd_ty, tcx).zip(TypeInfo::from_ty(bwd_ty, tcx));
 
-                Ok(InterfaceTyp::Channel(ChannelTyp { fwd, bwd }))
+                Ok(InterfaceTyp::Channel(ChannelTyp { fwd, bwd, tys }))
             }
             rustc_type_ir::TyKind::Tuple(ty_list) => {
                 let mut interface_map = LinkedHashMap::new();
@@ -1118,6 +1153,7 @@ impl Endpoint {
 /// Unary operators.
 // TODO: Add more cases
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "vir-json", derive(serde::Serialize, serde::Deserialize))]
 pub enum UnaryOp {
     /// Negation
     Negation,
@@ -1134,6 +1170,7 @@ impl ToString for UnaryOp {
 
 /// Binary operators.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "vir-json", derive(serde::Serialize, serde::Deserialize))]
 pub enum BinaryOp {
     /// Addition
     Add,
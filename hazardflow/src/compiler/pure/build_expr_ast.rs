@@ -38,10 +38,19 @@ pub(super) struct ExprBuilder<'tcx, 'function_builder> {
 
     pub(super) path_ctx: PathCtx,
 
+    /// State of building the function in program order.
+    pub(super) seq_ctx: SeqCtx,
+
     pub(super) upvars: Option<&'function_builder [(Id, PureValue<'tcx>)]>,
 
     pub(super) pat_bindings: &'function_builder [PatBinding<'tcx>],
     pub(super) tasks_inner: Vec<SystemTask>,
+
+    /// First error in building the expression, e.g., an unsupported form in a callee built in program order.
+    ///
+    /// The expression is built on with an undefined value in place of the erroneous one, and the error is returned
+    /// after it is built.
+    pub(super) error: Option<VirgenError>,
 }
 
 impl<'tcx> std::fmt::Debug for ExprBuilder<'tcx, '_> {
@@ -55,21 +64,32 @@ impl<'tcx> std::fmt::Debug for ExprBuilder<'tcx, '_> {
 
 impl<'tcx, 'function_builder> ExprBuilder<'tcx, 'function_builder> {
     /// Builds expr from a `ExprId`.
-    pub(super) fn build(mut self) -> (ExprId, Vec<SystemTask>) {
-        (self.build_impl(self.expr_id), self.tasks_inner)
+    pub(super) fn build(mut self) -> VirgenResult<(ExprId, Vec<SystemTask>)> {
+        let expr = self.build_impl(self.expr_id);
+        match self.error {
+            Some(err) => Err(err),
+            None => Ok((expr, self.tasks_inner)),
+        }
+    }
+
+    /// Records the error, which is returned after the expression is built, and returns an undefined value of the type in
+    /// place of the erroneous expression.
+    pub(super) fn record_error(&mut self, err: VirgenError, typ: PortDecls, span: Span) -> ExprId {
+        self.error.get_or_insert(err);
+        Expr::X { typ, span }.alloc_with_fsm_cache(self.fsm_cache)
     }
 
-    fn is_closure(&self) -> bool {
+    pub(super) fn is_closure(&self) -> bool {
         self.upvars.is_some()
     }
 
-    fn monomorphise<T>(&mut self, t: T) -> T
+    pub(super) fn monomorphise<T>(&mut self, t: T) -> T
     where T: TypeFoldable<TyCtxt<'tcx>> {
         normalize_alias_ty(self.tcx, EarlyBinder::bind(t).instantiate(self.tcx, self.substs))
     }
 
     /// Helper function to build exprs recursively.
-    fn build_impl(&mut self, expr_id: thir::ExprId) -> ExprId {
+    pub(super) fn build_impl(&mut self, expr_id: thir::ExprId) -> ExprId {
         let expr_id = skip_exprs(&self.thir_body.borrow(), expr_id);
         let expr = &self.thir_body.borrow()[expr_id];
         let typ_expected = PortDecls::from_ty(self.monomorphise(expr.ty), self.tcx).unwrap();
@@ -91,7 +111,8 @@ impl<'tcx, 'function_builder> ExprBuilder<'tcx, 'function_builder> {
             ExprKind::If { cond, then, else_opt, .. } => self.build_conditional(cond, then, else_opt, span),
             ExprKind::Scope { .. } => todo!(),
             ExprKind::Box { .. } => todo!(),
-            ExprKind::Call { fun, args, .. } => self.build_call(*fun, args, span),
+            ExprKind::Loop { .. } => unreachable!("the value of a loop is cached when the loop is unrolled"),
+            ExprKind::Call { fun, args, .. } => self.build_call(*fun, args, typ_expected.clone(), span),
             ExprKind::Deref { arg } => {
                 let arg_skipped = skip_exprs(&self.thir_body.borrow(), *arg);
                 let skipped_expr = &self.thir_body.borrow()[arg_skipped];
@@ -103,10 +124,10 @@ impl<'tcx, 'function_builder> ExprBuilder<'tcx, 'function_builder> {
                     let name = self.tcx.item_name(*id);
                     // HACK: Only this is allowed
                     assert!(name.to_string().as_str() == "index" && parent_name.to_string().as_str() == "Index");
-                    return self.build_call(*fun, args.as_ref(), span);
+                    return self.build_call(*fun, args.as_ref(), typ_expected, span);
                 } else {
-                    let _ = self.build_impl(*arg);
-                    todo!();
+                    // References are transparent, e.g., `*self` for `&mut self`.
+                    return self.build_impl(*arg);
                 }
             }
             ExprKind::Binary { op, lhs, rhs } => {
@@ -137,7 +158,12 @@ impl<'tcx, 'function_builder> ExprBuilder<'tcx, 'function_builder> {
                 rustc_middle::mir::UnOp::Not => {
                     Expr::Not { inner: self.build_impl(*arg), span }.alloc_with_fsm_cache(self.fsm_cache)
                 }
-                rustc_middle::mir::UnOp::Neg => todo!(),
+                rustc_middle::mir::UnOp::Neg => {
+                    // `-x` is `0 - x`, which wraps around.
+                    let zero = Expr::int_bits(typ_expected.clone(), 0, span).alloc_with_fsm_cache(self.fsm_cache);
+                    Expr::BinaryOp { op: BinaryOp::Sub, lhs: zero, rhs: self.build_impl(*arg), span }
+                        .alloc_with_fsm_cache(self.fsm_cache)
+                }
             },
             ExprKind::Cast { source } => {
                 let inner = self.build_impl(*source);
@@ -214,12 +240,10 @@ impl<'tcx, 'function_builder> ExprBuilder<'tcx, 'function_builder> {
                 match self.tcx.const_eval_resolve(ParamEnv::empty(), uneval, None) {
                     Ok(v) => match v {
                         rustc_middle::mir::ConstValue::Scalar(scalar) => {
-                            let value = scalar_to_usize(scalar).expect("scalar value should be resolved");
-                            let size = match self.monomorphise(expr.ty).kind() {
-                                rustc_type_ir::TyKind::Uint(uint_ty) => uint_ty.bit_width().unwrap_or(32),
-                                _ => todo!(),
-                            };
-                            Expr::unsigned_bits(size.try_into().unwrap(), value, span)
+                            let value = scalar_to_u128(scalar).expect("scalar value should be resolved");
+                            let ty = self.monomorphise(expr.ty);
+                            assert!(ty.is_integral() || ty.is_bool(), "{ty:?}");
+                            Expr::int_bits(PortDecls::from_ty(ty, self.tcx).unwrap(), value, span)
                                 .alloc_with_fsm_cache(self.fsm_cache)
                         }
                         rustc_middle::mir::ConstValue::Indirect { alloc_id, offset } => {
@@ -234,15 +258,10 @@ impl<'tcx, 'function_builder> ExprBuilder<'tcx, 'function_builder> {
                 let c = self.substs.get(param.index as usize).unwrap();
                 let c = evaluate_const_generic_arg(self.tcx, c).unwrap();
                 match self.monomorphise(expr.ty).kind() {
-                    rustc_type_ir::TyKind::Uint(uint_ty) => match uint_ty {
-                        rustc_type_ir::UintTy::Usize | rustc_type_ir::UintTy::U32 => {
-                            Expr::unsigned_bits(32, c, span).alloc_with_fsm_cache(self.fsm_cache)
-                        }
-                        rustc_type_ir::UintTy::U8 => todo!(),
-                        rustc_type_ir::UintTy::U16 => todo!(),
-                        rustc_type_ir::UintTy::U64 => todo!(),
-                        rustc_type_ir::UintTy::U128 => todo!(),
-                    },
+                    rustc_type_ir::TyKind::Bool | rustc_type_ir::TyKind::Int(_) | rustc_type_ir::TyKind::Uint(_) => {
+                        let typ = PortDecls::from_ty(self.monomorphise(expr.ty), self.tcx).unwrap();
+                        Expr::int_bits(typ, c as u128, span).alloc_with_fsm_cache(self.fsm_cache)
+                    }
                     rustc_type_ir::TyKind::Adt(i, x) => {
                         assert!(x.is_empty(),);
 
@@ -287,11 +306,11 @@ impl<'tcx, 'function_builder> ExprBuilder<'tcx, 'function_builder> {
         None
     }
 
-    fn push_path_ctx(&mut self, expr_id: ExprId) {
+    pub(super) fn push_path_ctx(&mut self, expr_id: ExprId) {
         self.path_ctx.inner.push_back(expr_id)
     }
 
-    fn pop_path_ctx(&mut self) {
+    pub(super) fn pop_path_ctx(&mut self) {
         self.path_ctx.inner.pop_back().unwrap();
     }
 
@@ -467,11 +486,16 @@ impl<'tcx, 'function_builder> ExprBuilder<'tcx, 'function_builder> {
         }
     }
 
-    fn build_var_ref(&mut self, id: &thir::LocalVarId, span: Span) -> ExprId {
+    pub(super) fn build_var_ref(&mut self, id: &thir::LocalVarId, span: Span) -> ExprId {
         log::debug!("building var ref");
+        if let Some(value) = self.seq_ctx.locals.get(id) {
+            return *value;
+        }
+
         let local_var_resolved = resolve_var_ref(self.tcx, self.thir_body, *id, Some(self.pat_bindings));
         assert_ne!(local_var_resolved.len(), 0);
 
+        let is_let = matches!(local_var_resolved[..], [LocalVar::Stmt { .. }, ..]);
         let mut local_vars = vec![];
 
         for local_var in local_var_resolved {
@@ -498,23 +522,46 @@ impl<'tcx, 'function_builder> ExprBuilder<'tcx, 'function_builder> {
             local_vars.push((match_condition, bounded_expr))
         }
 
-        match local_vars.len() {
+        let expr = match local_vars.len() {
             0 => panic!(),
             1 => local_vars[0].1,
             _ => {
                 let typ = local_vars[0].1.into_expr().port_decls();
 
+                // The variable bound by an irrefutable alternative of an or-pattern is the default.
+                let matched = local_vars.iter().position(|(cond, _)| cond.is_none()).unwrap_or(local_vars.len());
+                let default = match local_vars.get(matched) {
+                    Some((_, expr)) => *expr,
+                    None => Expr::X { typ, span }.alloc_with_fsm_cache(self.fsm_cache),
+                };
+
                 Expr::Cond {
-                    cond_expr_pair: local_vars.into_iter().map(|(cond, expr)| (cond.unwrap(), expr)).collect(),
-                    default: Expr::X { typ, span }.alloc_with_fsm_cache(self.fsm_cache),
+                    cond_expr_pair: local_vars
+                        .into_iter()
+                        .take(matched)
+                        .map(|(cond, expr)| (cond.unwrap(), expr))
+                        .collect(),
+                    default,
                     span,
                 }
                 .alloc_with_fsm_cache(self.fsm_cache)
             }
+        };
+
+        // Names the wire of the expression after the variable bound by `let`.
+        if is_let {
+            self.fsm_cache.set_name(expr, self.tcx.hir().name(id.0).to_string());
         }
+
+        expr
     }
 
-    fn build_pattern_access(&mut self, expr_id: ExprId, accessor: PatAccessor, span: Span) -> (Option<ExprId>, ExprId) {
+    fn build_pattern_access(
+        &mut self,
+        expr_id: ExprId,
+        accessor: PatAccessor<'tcx>,
+        span: Span,
+    ) -> (Option<ExprId>, ExprId) {
         let (accesed_expr, access_cond) =
             accessor.iter().fold((expr_id, vec![]), |(acc_expr, mut access_conds), elt| match elt {
                 PatAccessNode::Field { idx, .. } => {
@@ -538,7 +585,31 @@ impl<'tcx, 'function_builder> ExprBuilder<'tcx, 'function_builder> {
 
                     (acc_expr.member((*idx + 1).into(), span).alloc_with_fsm_cache(self.fsm_cache), access_conds)
                 }
-                PatAccessNode::Index(_) => todo!(),
+                PatAccessNode::Index { idx, len } => {
+                    let typ_elt = acc_expr.into_expr().port_decls().divide(*len);
+                    let index = Expr::unsigned_bits(clog2(*len), *idx, span).alloc_with_fsm_cache(self.fsm_cache);
+
+                    (
+                        Expr::Get { inner: acc_expr, typ_elt, index, span }.alloc_with_fsm_cache(self.fsm_cache),
+                        access_conds,
+                    )
+                }
+                PatAccessNode::Subslice { from, size, len } => {
+                    let typ_elt = acc_expr.into_expr().port_decls().divide(*len);
+                    let from = Expr::unsigned_bits(clog2(*len + 1), *from, span).alloc_with_fsm_cache(self.fsm_cache);
+
+                    (
+                        Expr::Clip { inner: acc_expr, typ_elt, from, size: *size, span }
+                            .alloc_with_fsm_cache(self.fsm_cache),
+                        access_conds,
+                    )
+                }
+                PatAccessNode::Alternative(pat) => {
+                    // The variable is bound by the alternative only if it matches.
+                    access_conds.extend(gen_match_cond(self.tcx, pat, acc_expr, self.fsm_cache));
+
+                    (acc_expr, access_conds)
+                }
             });
 
         match access_cond.len() {
@@ -561,7 +632,7 @@ impl<'tcx, 'function_builder> ExprBuilder<'tcx, 'function_builder> {
     fn build_field_expr(
         &mut self,
         lhs: &thir::ExprId,
-        _variant_index: &VariantIdx,
+        variant_index: &VariantIdx,
         name: &FieldIdx,
         span: Span,
     ) -> ExprId {
@@ -573,7 +644,14 @@ impl<'tcx, 'function_builder> ExprBuilder<'tcx, 'function_builder> {
                 lhs_expr.member(usize::from(*name), span).alloc_with_fsm_cache(self.fsm_cache)
             }
             rustc_type_ir::TyKind::Adt(def, _) => match def.adt_kind() {
-                AdtKind::Enum => todo!(),
+                AdtKind::Enum => {
+                    let lhs_expr = self.build_impl(*lhs);
+
+                    // The fields of the variant come after the discriminant.
+                    let variant_expr =
+                        lhs_expr.member(1 + variant_index.as_usize(), span).alloc_with_fsm_cache(self.fsm_cache);
+                    variant_expr.member(usize::from(*name), span).alloc_with_fsm_cache(self.fsm_cache)
+                }
                 AdtKind::Struct => {
                     let lhs_expr = self.build_impl(*lhs);
 
@@ -661,11 +739,17 @@ impl<'tcx, 'function_builder> ExprBuilder<'tcx, 'function_builder> {
     }
 
     /// Builds the condition for an arm
-    fn build_arm_cond(&mut self, scrutinee_expr: ExprId, arm: &thir::Arm<'tcx>) -> Option<ExprId> {
+    pub(super) fn build_arm_cond(&mut self, scrutinee_expr: ExprId, arm: &thir::Arm<'tcx>) -> Option<ExprId> {
         let pattern_cond = gen_match_cond(self.tcx, arm.pattern.as_ref(), scrutinee_expr, self.fsm_cache);
         let guard_cond = arm.guard.as_ref().map(|guard| match guard {
             thir::Guard::If(guard_expr_id) => self.build_impl(*guard_expr_id),
-            thir::Guard::IfLet(..) => todo!(),
+            thir::Guard::IfLet(pat, expr_id) => {
+                let expr = self.build_impl(*expr_id);
+                match gen_match_cond(self.tcx, pat, expr, self.fsm_cache) {
+                    Some(cond) => cond,
+                    None => Expr::unsigned_bits(1, 1, arm.span).alloc_with_fsm_cache(self.fsm_cache),
+                }
+            }
         });
         match (pattern_cond, guard_cond) {
             (None, None) => None,
@@ -677,7 +761,7 @@ impl<'tcx, 'function_builder> ExprBuilder<'tcx, 'function_builder> {
         }
     }
 
-    fn get_current_path(&mut self, span: Span) -> Option<ExprId> {
+    pub(super) fn get_current_path(&mut self, span: Span) -> Option<ExprId> {
         let path_cloned = self.path_ctx.inner.clone();
 
         path_cloned.into_iter().reduce(|l, r| {
@@ -685,8 +769,8 @@ impl<'tcx, 'function_builder> ExprBuilder<'tcx, 'function_builder> {
         })
     }
 
-    /// Builds ast of a call expression
-    fn build_call(&mut self, fun_id: thir::ExprId, args: &[thir::ExprId], span: Span) -> ExprId {
+    /// Builds ast of a call expression, whose value has type `typ`.
+    fn build_call(&mut self, fun_id: thir::ExprId, args: &[thir::ExprId], typ: PortDecls, span: Span) -> ExprId {
         if is_closure_call_with_id(self.tcx, self.thir_body, fun_id, args) {
             assert_eq!(args.len(), 2);
             let builder_args = if let ExprKind::Tuple { fields } = &self.thir_body.borrow()[args[1]].kind {
@@ -696,7 +780,10 @@ impl<'tcx, 'function_builder> ExprBuilder<'tcx, 'function_builder> {
             };
             let PureValue::Function(ref builder) = self.collect_arg(&args[0]) else { panic!() };
 
-            let (expr, tasks) = builder.build(self.tcx, builder_args, self.fsm_cache);
+            let (expr, tasks) = match builder.build(self.tcx, builder_args, self.fsm_cache) {
+                Ok(built) => built,
+                Err(err) => return self.record_error(err, typ, span),
+            };
 
             self.add_tasks(tasks, span);
 
@@ -720,8 +807,14 @@ impl<'tcx, 'function_builder> ExprBuilder<'tcx, 'function_builder> {
                 let instance_id = instance.def_id();
 
                 if instance_id.is_local() {
-                    let (expr, tasks) =
-                        FunctionBuilder::new_local(instance, self.tcx).build(self.tcx, build_args, self.fsm_cache);
+                    let (expr, tasks) = match FunctionBuilder::new_local(instance, self.tcx).build(
+                        self.tcx,
+                        build_args,
+                        self.fsm_cache,
+                    ) {
+                        Ok(built) => built,
+                        Err(err) => return self.record_error(err, typ, span),
+                    };
 
                     self.add_tasks(tasks, span);
 
@@ -746,18 +839,9 @@ impl<'tcx, 'function_builder> ExprBuilder<'tcx, 'function_builder> {
                             rustc_type_ir::TyKind::Bool => {
                                 Expr::unsigned_bits(1, 0, span).alloc_with_fsm_cache(self.fsm_cache)
                             }
-                            rustc_type_ir::TyKind::Uint(uint_ty) => {
-                                Expr::unsigned_bits(
-                                    uint_ty
-                                    .bit_width()
-                                    // NOTE: This fails when type is `usize`, and falls back to 32 bits
-                                    .unwrap_or(32)
-                                    .try_into()
-                                    .unwrap(),
-                                    0,
-                                    span,
-                                )
-                                .alloc_with_fsm_cache(self.fsm_cache)
+                            rustc_type_ir::TyKind::Int(_) | rustc_type_ir::TyKind::Uint(_) => {
+                                Expr::int_bits(PortDecls::from_ty(ty, self.tcx).unwrap(), 0, span)
+                                    .alloc_with_fsm_cache(self.fsm_cache)
                             }
                             tykind => todo!("{tykind:?}"),
                         }
@@ -775,8 +859,8 @@ impl<'tcx, 'function_builder> ExprBuilder<'tcx, 'function_builder> {
         }
     }
 
-    fn add_tasks(&mut self, mut displays: Vec<SystemTask>, span: Span) {
-        let current_path_cond = self.get_current_path(span);
+    pub(super) fn add_tasks(&mut self, mut displays: Vec<SystemTask>, span: Span) {
+        let current_path_cond = self.get_guard(span);
 
         if let Some(current_path_cond) = current_path_cond {
             for display in displays.iter_mut() {
@@ -793,7 +877,7 @@ impl<'tcx, 'function_builder> ExprBuilder<'tcx, 'function_builder> {
     }
 
     /// Recursively collect an `FunctionArg` given `ExprId`
-    fn collect_arg(&mut self, arg: &thir::ExprId) -> PureValue<'tcx> {
+    pub(super) fn collect_arg(&mut self, arg: &thir::ExprId) -> PureValue<'tcx> {
         let arg = skip_exprs(&self.thir_body.borrow(), *arg);
 
         let expr = &self.thir_body.borrow()[arg];
@@ -902,7 +986,6 @@ pub fn build_literal<'tcx>(
     ty: Ty<'tcx>,
     tcx: TyCtxt<'tcx>,
 ) -> ExprId {
-    assert!(!neg);
     let typ = PortDecls::from_ty(ty, tcx).unwrap();
     let expr = match lit.node {
         rustc_ast::LitKind::Str(..) => todo!(),
@@ -911,12 +994,9 @@ pub fn build_literal<'tcx>(
         rustc_ast::LitKind::Byte(_) => todo!(),
         rustc_ast::LitKind::Char(_) => todo!(),
         rustc_ast::LitKind::Int(value, _) => {
-            log::debug!("ty: {:?}, value: {:?}", typ, value);
-            if typ.is_signed() {
-                Expr::signed_bits(typ.width(), value.try_into().unwrap(), lit.span)
-            } else {
-                Expr::unsigned_bits(typ.width(), value.try_into().unwrap(), lit.span)
-            }
+            log::debug!("ty: {:?}, value: {:?}, neg: {:?}", typ, value, neg);
+            // Negative literals are in two's complement.
+            Expr::int_bits(typ, if *neg { value.wrapping_neg() } else { value }, lit.span)
         }
         rustc_ast::LitKind::Float(..) => todo!(),
         rustc_ast::LitKind::Bool(b) => Expr::unsigned_bits(1, b as usize, lit.span),
@@ -933,7 +1013,7 @@ pub fn build_const_expr<'tcx>(
     substs: GenericArgsRef<'tcx>,
     args: &[PureValue<'tcx>],
     upvars: Option<&[(Id, PureValue<'tcx>)]>,
-) -> ExprId {
+) -> VirgenResult<ExprId> {
     let (expr, displays) = ExprBuilder {
         tcx,
         expr_id,
@@ -947,10 +1027,12 @@ pub fn build_const_expr<'tcx>(
         pat_bindings: &[],
         tasks_inner: vec![],
         path_ctx: PathCtx::default(),
+        seq_ctx: SeqCtx::default(),
+        error: None,
     }
-    .build();
+    .build()?;
     assert!(displays.is_empty());
-    expr
+    Ok(expr)
 }
 
 /// Generate match condition for a pattern and expr pair
@@ -967,8 +1049,8 @@ pub fn gen_match_cond<'tcx>(
             gen_match_cond(tcx, subpattern.as_ref(), match_arg, fsm_cache)
         }
         thir::PatKind::Binding { subpattern, .. } => {
-            assert!(subpattern.is_none());
-            None
+            // `var @ subpattern`
+            subpattern.as_ref().and_then(|subpattern| gen_match_cond(tcx, subpattern, match_arg, fsm_cache))
         }
         thir::PatKind::Variant { adt_def, variant_index, subpatterns, .. } => match adt_def.adt_kind() {
             rustc_middle::ty::AdtKind::Struct => todo!(),
@@ -1065,11 +1147,42 @@ pub fn gen_match_cond<'tcx>(
         },
         thir::PatKind::Range(_) => todo!(),
         thir::PatKind::Slice { .. } => todo!(),
-        thir::PatKind::Array { .. } => todo!(),
+        thir::PatKind::Array { prefix, slice, suffix } => {
+            let rustc_type_ir::TyKind::Array(elt_ty, len) = pattern.ty.kind() else { panic!() };
+            let typ_elt = PortDecls::from_ty(*elt_ty, tcx).unwrap();
+            let len = len.eval_target_usize(tcx, ParamEnv::empty()) as usize;
+
+            let mut conds = vec![];
+            let elts = prefix
+                .iter()
+                .enumerate()
+                .chain(suffix.iter().enumerate().map(|(i, pat)| (len - suffix.len() + i, pat)));
+            for (idx, pat) in elts {
+                let index = Expr::unsigned_bits(clog2(len), idx, pattern.span).alloc_with_fsm_cache(fsm_cache);
+                let elt = Expr::Get { inner: match_arg, typ_elt: typ_elt.clone(), index, span: pattern.span }
+                    .alloc_with_fsm_cache(fsm_cache);
+                conds.extend(gen_match_cond(tcx, pat, elt, fsm_cache));
+            }
+
+            if let Some(slice) = slice {
+                let from =
+                    Expr::unsigned_bits(clog2(len + 1), prefix.len(), pattern.span).alloc_with_fsm_cache(fsm_cache);
+                let size = len - prefix.len() - suffix.len();
+                let subarray = Expr::Clip { inner: match_arg, typ_elt, from, size, span: pattern.span }
+                    .alloc_with_fsm_cache(fsm_cache);
+                conds.extend(gen_match_cond(tcx, slice, subarray, fsm_cache));
+            }
+
+            conds.into_iter().reduce(|acc, elt| {
+                Expr::BinaryOp { op: BinaryOp::And, lhs: acc, rhs: elt, span: pattern.span }
+                    .alloc_with_fsm_cache(fsm_cache)
+            })
+        }
         thir::PatKind::Or { pats } => {
             let mut conds = vec![];
             for pat in pats.iter() {
-                conds.push(gen_match_cond(tcx, pat.as_ref(), match_arg, fsm_cache).unwrap())
+                // An irrefutable alternative always matches.
+                conds.push(gen_match_cond(tcx, pat.as_ref(), match_arg, fsm_cache)?)
             }
             match conds.len() {
                 0 => panic!(),
@@ -678,6 +678,12 @@ impl Expr {
         Self::Constant { bits, typ: PortDecls::signed_bits(len), span }
     }
 
+    /// Constructs a constant of the bits type, whose value is truncated to the width.
+    pub fn int_bits(typ: PortDecls, value: u128, span: Span) -> Self {
+        let bits = u128_to_bitvec(typ.width(), value);
+        Self::Constant { bits, typ, span }
+    }
+
     /// Allocate an expr with a cache.
     pub fn alloc_with_fsm_cache(self, cache: &mut FsmCache) -> ExprId {
         cache.alloc(self)
@@ -1,12 +1,12 @@
 //! Pure Function.
 
-use std::borrow::Borrow;
 use std::cell::RefCell;
 use std::collections::HashMap;
 use std::fmt::Formatter;
 
 use itertools::Itertools;
 use rustc_hir::def::DefKind;
+use rustc_middle::mir::BorrowKind;
 use rustc_middle::thir::{self, ExprKind, Thir};
 use rustc_middle::ty::{EarlyBinder, FnSig, GenericArgKind, GenericArgsRef, Instance, ParamEnv, TyCtxt};
 use rustc_span::Span;
@@ -168,6 +168,9 @@ pub struct FunctionBuilder<'tcx> {
     /// Explicit Returns
     explicit_returns: Vec<Return<'tcx>>,
 
+    /// Whether the function has loops or assignments, and is built in program order.
+    in_order: bool,
+
     /// System Tasks
     pub system_tasks: Vec<SystemTaskInfo<'tcx>>,
 }
@@ -206,6 +209,7 @@ impl<'tcx> FunctionBuilder<'tcx> {
             span: get_span(tcx, instance.def_id()),
             pat_bindings: vec![],
             explicit_returns: vec![],
+            in_order: false,
             system_tasks: vec![],
         }
     }
@@ -217,6 +221,7 @@ impl<'tcx> FunctionBuilder<'tcx> {
             span: get_span(tcx, instance.def_id()),
             pat_bindings: vec![],
             explicit_returns: vec![],
+            in_order: false,
             system_tasks: vec![],
         }
     }
@@ -227,8 +232,14 @@ impl<'tcx> FunctionBuilder<'tcx> {
 
         let span = get_span(tcx, instance.def_id());
 
-        let mut new_builder =
-            Self { ast: f, span, pat_bindings: vec![], explicit_returns: vec![], system_tasks: vec![] };
+        let mut new_builder = Self {
+            ast: f,
+            span,
+            pat_bindings: vec![],
+            explicit_returns: vec![],
+            in_order: false,
+            system_tasks: vec![],
+        };
 
         if let Function::Fn(_) = &new_builder.ast {
             new_builder.preprocess(tcx);
@@ -252,14 +263,33 @@ impl<'tcx> FunctionBuilder<'tcx> {
         tcx: TyCtxt<'tcx>,
         args: Vec<PureValue<'tcx>>,
         fsm_cache: &mut FsmCache,
-    ) -> (ExprId, Vec<SystemTask>) {
+    ) -> VirgenResult<(ExprId, Vec<SystemTask>)> {
         match &self.ast {
-            Function::Ctor { instance } => (self.build_ctor(tcx, *instance, fsm_cache, args), vec![]),
+            Function::Ctor { instance } => Ok((self.build_ctor(tcx, *instance, fsm_cache, args), vec![])),
             Function::Fn { .. } => self.build_fn(tcx, fsm_cache, args),
             Function::Magic { instance, magic } => {
-                (self.build_magic(tcx, *instance, magic.clone(), fsm_cache, args), vec![])
+                Ok((self.build_magic(tcx, *instance, magic.clone(), fsm_cache, args), vec![]))
+            }
+        }
+    }
+
+    /// Builds the function with given args, and returns the values of the args at the end of the function together.
+    ///
+    /// The values differ from the given args if they are `&mut` references assigned in the function.
+    pub fn build_mut(
+        &self,
+        tcx: TyCtxt<'tcx>,
+        args: Vec<PureValue<'tcx>>,
+        fsm_cache: &mut FsmCache,
+    ) -> VirgenResult<(ExprId, Vec<PureValue<'tcx>>, Vec<SystemTask>)> {
+        if let Function::Fn(_) = &self.ast {
+            if self.in_order {
+                return self.build_fn_in_order(tcx, fsm_cache, &args);
             }
         }
+
+        let (expr, tasks) = self.build(tcx, args.clone(), fsm_cache)?;
+        Ok((expr, args, tasks))
     }
 
     fn build_ctor(
@@ -362,18 +392,22 @@ impl<'tcx> FunctionBuilder<'tcx> {
         tcx: TyCtxt<'tcx>,
         fsm_cache: &mut FsmCache,
         args: Vec<PureValue<'tcx>>,
-    ) -> (ExprId, Vec<SystemTask>) {
+    ) -> VirgenResult<(ExprId, Vec<SystemTask>)> {
+        if self.in_order {
+            let (expr, _, tasks) = self.build_fn_in_order(tcx, fsm_cache, &args)?;
+            return Ok((expr, tasks));
+        }
+
         let mut thir_cache = ThirCache::default();
 
-        let mut displays = self
-            .system_tasks
-            .iter()
-            .flat_map(|display| self.build_system_task(display, tcx, &mut thir_cache, fsm_cache, &args))
-            .collect::<Vec<_>>();
+        let mut displays = vec![];
+        for display in self.system_tasks.iter() {
+            displays.append(&mut self.build_system_task(display, tcx, &mut thir_cache, fsm_cache, &args)?);
+        }
 
         let return_expr_id = (self.expect_fn().thir_body.borrow().exprs.len() - 1).into();
         let (default_return, mut displays_inner) =
-            self.build_expr(tcx, return_expr_id, &mut thir_cache, fsm_cache, &args);
+            self.build_expr(tcx, return_expr_id, &mut thir_cache, fsm_cache, &args)?;
 
         displays.append(&mut displays_inner);
 
@@ -383,6 +417,8 @@ impl<'tcx> FunctionBuilder<'tcx> {
             .explicit_returns
             .iter()
             .map(|ret| self.build_return(ret, tcx, &mut thir_cache, fsm_cache, &args))
+            .collect::<VirgenResult<Vec<_>>>()?
+            .into_iter()
             .unzip();
         displays.append(&mut displays_from_returns.concat());
 
@@ -393,7 +429,7 @@ impl<'tcx> FunctionBuilder<'tcx> {
                 .alloc_with_fsm_cache(fsm_cache)
         };
 
-        (return_selected, displays)
+        Ok((return_selected, displays))
     }
 
     fn build_magic(
@@ -499,7 +535,8 @@ impl<'tcx> FunctionBuilder<'tcx> {
         fsm_cache: &mut FsmCache,
     ) -> ExprId {
         match magic {
-            IntMagic::Convert => {
+            // The constructor of `S<N>` reinterprets the bits of `U<N>` as signed, which is a conversion.
+            IntMagic::Convert | IntMagic::Sint => {
                 assert_eq!(build_args.len(), 1);
                 assert!(monomorphized.args.len() <= 1);
                 let from_expr = build_args[0].expr().unwrap();
@@ -510,6 +547,12 @@ impl<'tcx> FunctionBuilder<'tcx> {
                 Expr::Cast { from: from_expr, to, span }.alloc_with_fsm_cache(fsm_cache)
             }
             IntMagic::Not => Expr::Not { inner: build_args[0].expr().unwrap(), span }.alloc_with_fsm_cache(fsm_cache),
+            IntMagic::Neg => {
+                // `-x` is `0 - x`, which wraps around.
+                let inner = build_args[0].expr().unwrap();
+                let zero = Expr::int_bits(inner.into_expr().port_decls(), 0, span).alloc_with_fsm_cache(fsm_cache);
+                Expr::BinaryOp { op: BinaryOp::Sub, lhs: zero, rhs: inner, span }.alloc_with_fsm_cache(fsm_cache)
+            }
             magic => {
                 let op = magic.bin_op();
 
@@ -735,13 +778,25 @@ impl<'tcx> FunctionBuilder<'tcx> {
         fsm_cache: &mut FsmCache,
         args: &[PureValue<'tcx>],
         // acc: ExprId,
-    ) -> ((ExprId, ExprId), Vec<SystemTask>) {
+    ) -> VirgenResult<((ExprId, ExprId), Vec<SystemTask>)> {
         let path_cond = &ret.path_cond;
-        let (cond, displays_in_path) = self.build_path_cond(path_cond, tcx, thir_cache, fsm_cache, args);
+        let (cond, displays_in_path) = self.build_path_cond(path_cond, tcx, thir_cache, fsm_cache, args)?;
         let cond = cond.unwrap_or(Expr::unsigned_bits(1, 1, self.span).alloc_with_fsm_cache(fsm_cache));
 
-        let (value, displays_in_value) = self.build_expr(tcx, ret.value, thir_cache, fsm_cache, args);
-        ((cond, value), [displays_in_path, displays_in_value].concat())
+        let (value, displays_in_value) = self.build_expr(tcx, ret.value, thir_cache, fsm_cache, args)?;
+        Ok(((cond, value), [displays_in_path, displays_in_value].concat()))
+    }
+
+    /// Builds the function in program order, and returns the values of the args at the end of the function together.
+    fn build_fn_in_order(
+        &self,
+        tcx: TyCtxt<'tcx>,
+        fsm_cache: &mut FsmCache,
+        args: &[PureValue<'tcx>],
+    ) -> VirgenResult<(ExprId, Vec<PureValue<'tcx>>, Vec<SystemTask>)> {
+        let mut thir_cache = ThirCache::default();
+        let return_expr_id = (self.expect_fn().thir_body.borrow().exprs.len() - 1).into();
+        self.expr_builder(tcx, return_expr_id, &mut thir_cache, fsm_cache, args).build_in_order()
     }
 
     fn build_system_task(
@@ -751,17 +806,17 @@ impl<'tcx> FunctionBuilder<'tcx> {
         thir_cache: &mut ThirCache,
         fsm_cache: &mut FsmCache,
         args: &[PureValue<'tcx>],
-    ) -> Vec<SystemTask> {
+    ) -> VirgenResult<Vec<SystemTask>> {
         let mut result = vec![];
 
         let path_cond = &task.path_cond;
-        let (path_cond, mut tasks_in_path) = self.build_path_cond(path_cond, tcx, thir_cache, fsm_cache, args);
+        let (path_cond, mut tasks_in_path) = self.build_path_cond(path_cond, tcx, thir_cache, fsm_cache, args)?;
         result.append(&mut tasks_in_path);
 
         let (kind, mut tasks) = match task.kind {
             SystemTaskInfoKind::Display => (SystemTaskKind::Display, vec![]),
             SystemTaskInfoKind::Assert { cond } => {
-                let (cond, mut tasks) = self.build_expr(tcx, cond, thir_cache, fsm_cache, args);
+                let (cond, mut tasks) = self.build_expr(tcx, cond, thir_cache, fsm_cache, args)?;
 
                 for task in tasks.iter_mut() {
                     let zipped = match (task.path_cond, path_cond) {
@@ -781,7 +836,7 @@ impl<'tcx> FunctionBuilder<'tcx> {
         };
         result.append(&mut tasks);
 
-        let (args, mut tasks) = self.build_expr(tcx, task.arg, thir_cache, fsm_cache, args);
+        let (args, mut tasks) = self.build_expr(tcx, task.arg, thir_cache, fsm_cache, args)?;
 
         for task in tasks.iter_mut() {
             let zipped = match (task.path_cond, path_cond) {
@@ -813,7 +868,7 @@ impl<'tcx> FunctionBuilder<'tcx> {
         result.push(task);
 
         // tasks.into_iter().chain(tasks_in_path).chain(std::iter::once(task)).collect()
-        result
+        Ok(result)
     }
 
     fn build_path_cond(
@@ -823,24 +878,24 @@ impl<'tcx> FunctionBuilder<'tcx> {
         thir_cache: &mut ThirCache,
         fsm_cache: &mut FsmCache,
         args: &[PureValue<'tcx>],
-    ) -> (Option<ExprId>, Vec<SystemTask>) {
+    ) -> VirgenResult<(Option<ExprId>, Vec<SystemTask>)> {
         let mut condition_exprs = vec![];
         let mut displays = vec![];
 
         for condition in path_cond.iter() {
             let (cond_expr, mut displays_in_path) =
-                self.build_condition_expr(condition, tcx, thir_cache, fsm_cache, args);
+                self.build_condition_expr(condition, tcx, thir_cache, fsm_cache, args)?;
             displays.append(&mut displays_in_path);
             assert_eq!(cond_expr.into_expr().width(), 1);
             condition_exprs.push(cond_expr);
         }
         // TODO: use reduction operator
-        (
+        Ok((
             condition_exprs.into_iter().reduce(|l, r| {
                 Expr::BinaryOp { op: BinaryOp::And, lhs: l, rhs: r, span: self.span }.alloc_with_fsm_cache(fsm_cache)
             }),
             displays,
-        )
+        ))
     }
 
     fn build_condition_expr(
@@ -850,20 +905,24 @@ impl<'tcx> FunctionBuilder<'tcx> {
         thir_cache: &mut ThirCache,
         fsm_cache: &mut FsmCache,
         args: &[PureValue<'tcx>],
-    ) -> (ExprId, Vec<SystemTask>) {
+    ) -> VirgenResult<(ExprId, Vec<SystemTask>)> {
         match condition {
             Condition::Expr(cond_expr_id) => self.build_expr(tcx, *cond_expr_id, thir_cache, fsm_cache, args),
             Condition::Matches(pat, cond_expr_id) => {
-                let (match_arg, displays) = self.build_expr(tcx, *cond_expr_id, thir_cache, fsm_cache, args);
-                (
+                let (match_arg, displays) = self.build_expr(tcx, *cond_expr_id, thir_cache, fsm_cache, args)?;
+                Ok((
                     gen_match_cond(tcx, pat.as_ref(), match_arg, fsm_cache)
                         .unwrap_or(Expr::unsigned_bits(1, 1, self.span).alloc_with_fsm_cache(fsm_cache)),
                     displays,
-                )
+                ))
             }
             Condition::Not(cond) => {
-                let (inner, displays) = self.build_condition_expr(cond, tcx, thir_cache, fsm_cache, args);
-                (Expr::Not { inner, span: self.span }.alloc_with_fsm_cache(fsm_cache), displays)
+                let (inner, displays) = self.build_condition_expr(cond, tcx, thir_cache, fsm_cache, args)?;
+                Ok((Expr::Not { inner, span: self.span }.alloc_with_fsm_cache(fsm_cache), displays))
+            }
+            Condition::All(conds) => {
+                let (cond, displays) = self.build_path_cond(conds, tcx, thir_cache, fsm_cache, args)?;
+                Ok((cond.unwrap_or(Expr::unsigned_bits(1, 1, self.span).alloc_with_fsm_cache(fsm_cache)), displays))
             }
         }
     }
@@ -890,7 +949,18 @@ impl<'tcx> FunctionBuilder<'tcx> {
         thir_cache: &mut ThirCache,
         fsm_cache: &mut FsmCache,
         args: &[PureValue<'tcx>],
-    ) -> (ExprId, Vec<SystemTask>) {
+    ) -> VirgenResult<(ExprId, Vec<SystemTask>)> {
+        self.expr_builder(tcx, expr_id, thir_cache, fsm_cache, args).build()
+    }
+
+    fn expr_builder<'a>(
+        &'a self,
+        tcx: TyCtxt<'tcx>,
+        expr_id: thir::ExprId,
+        thir_cache: &'a mut ThirCache,
+        fsm_cache: &'a mut FsmCache,
+        args: &'a [PureValue<'tcx>],
+    ) -> ExprBuilder<'tcx, 'a> {
         ExprBuilder {
             tcx,
             expr_id,
@@ -902,9 +972,10 @@ impl<'tcx> FunctionBuilder<'tcx> {
             upvars: self.expect_fn().upvars.as_deref(),
             pat_bindings: &self.pat_bindings,
             path_ctx: Default::default(),
+            seq_ctx: Default::default(),
             tasks_inner: vec![],
+            error: None,
         }
-        .build()
     }
 
     fn preprocess(&mut self, tcx: TyCtxt<'tcx>) {
@@ -955,59 +1026,9 @@ impl<'tcx> FunctionBuilder<'tcx> {
                     self.preprocess_expr(&body[*arg], ctx)
                 }
 
-                let (func_def_id, _substs) = match self.expect_fn().thir_body.borrow()[*fun].ty.kind() {
-                    rustc_type_ir::TyKind::FnDef(id, args) => (*id, *args),
-                    _ => panic!(),
-                };
-
-                let Some(local) = func_def_id.as_local() else {
-                    return;
-                };
-
-                let Some(attr) = get_hazardflow_attribute(ctx.tcx, ctx.tcx.local_def_id_to_hir_id(local)) else {
-                    return;
-                };
-
-                let HazardFlowAttr::SystemTask(task) = attr else {
-                    return;
-                };
-
-                let task = match task {
-                    SystemTaskMagic::Display => {
-                        let (fstring, span) = get_string_from_thir_id(body.borrow(), args[0]);
-
-                        let arg_id = skip_exprs(body, args[1]);
-                        log::debug!("{:#?}", &body[arg_id]);
-
-                        assert!(matches!(&body[arg_id].ty.kind(), rustc_type_ir::TyKind::Tuple(_)));
-
-                        SystemTaskInfo {
-                            kind: SystemTaskInfoKind::Display,
-                            path_cond: ctx.path_conds(),
-                            // TODO: revisit. maybe we need to use `to_string`
-                            fstring,
-                            arg: arg_id,
-                            span,
-                        }
-                    }
-                    SystemTaskMagic::Assert => {
-                        let cond_id = skip_exprs(body, args[0]);
-                        let (fstring, span) = get_string_from_thir_id(body.borrow(), args[1]);
-                        let arg_id = skip_exprs(body, args[2]);
-                        log::debug!("{:#?}", &body[arg_id]);
-                        assert!(matches!(&body[arg_id].ty.kind(), rustc_type_ir::TyKind::Tuple(_)));
-
-                        SystemTaskInfo {
-                            kind: SystemTaskInfoKind::Assert { cond: cond_id },
-                            path_cond: ctx.path_conds(),
-                            fstring,
-                            arg: arg_id,
-                            span,
-                        }
-                    }
-                };
-
-                self.system_tasks.push(task)
+                if let Some(task) = system_task_info(ctx.tcx, body, *fun, args) {
+                    self.system_tasks.push(SystemTaskInfo { path_cond: ctx.path_conds(), ..task })
+                }
             }
             ExprKind::Deref { arg } => {
                 self.preprocess_expr(&body[*arg], ctx);
@@ -1026,7 +1047,10 @@ impl<'tcx> FunctionBuilder<'tcx> {
             ExprKind::NeverToAny { source } => self.preprocess_expr(&body[*source], ctx),
             // XXX: We come here when panic. ignore for now
             ExprKind::PointerCoercion { .. } => {}
-            ExprKind::Loop { .. } => todo!(),
+            ExprKind::Loop { body: loop_body } => {
+                self.in_order = true;
+                self.preprocess_expr(&body[*loop_body], ctx)
+            }
             ExprKind::Let { expr, pat } => {
                 self.preprocess_expr(&body[*expr], ctx);
 
@@ -1034,37 +1058,55 @@ impl<'tcx> FunctionBuilder<'tcx> {
             }
             ExprKind::Match { scrutinee, arms, .. } => {
                 let mut patterns = vec![];
+
+                // The arm is taken only if the previous arms are not, as their patterns or guards may overlap.
+                let mut prev_arm_conds: Vec<Condition<'tcx>> = vec![];
                 for arm in arms.iter() {
                     let arm: &thir::Arm<'_> = &body[*arm];
+                    let mut arm_conds = vec![];
+                    for prev_arm_cond in prev_arm_conds.iter() {
+                        ctx.push_cond(prev_arm_cond.clone().not());
+                    }
+
                     if let Some(guard) = &arm.guard {
                         match guard {
                             thir::Guard::If(expr) => {
                                 self.preprocess_expr(&body[*expr], ctx);
 
+                                arm_conds.push(Condition::expr(*expr));
                                 ctx.push_cond(Condition::expr(*expr));
                             }
-                            thir::Guard::IfLet(..) => todo!(),
+                            thir::Guard::IfLet(pat, expr) => {
+                                self.preprocess_expr(&body[*expr], ctx);
+
+                                self.pat_bindings.push(PatBinding { id: *expr, patterns: vec![pat.as_ref().clone()] });
+                                arm_conds.push(Condition::matches(*expr, pat.as_ref().clone()));
+                                ctx.push_cond(Condition::matches(*expr, pat.as_ref().clone()));
+                            }
                         }
                     }
 
                     patterns.push(arm.pattern.as_ref().clone());
 
+                    arm_conds.push(Condition::matches(*scrutinee, arm.pattern.as_ref().clone()));
                     ctx.push_cond(Condition::matches(*scrutinee, arm.pattern.as_ref().clone()));
 
                     self.preprocess_expr(&body[arm.body], ctx);
 
-                    ctx.pop_cond();
-
-                    if arm.guard.is_some() {
+                    for _ in 0..prev_arm_conds.len() + arm_conds.len() {
                         ctx.pop_cond();
                     }
+                    prev_arm_conds.push(Condition::all(arm_conds));
                 }
 
                 self.pat_bindings.push(PatBinding { id: *scrutinee, patterns })
             }
             ExprKind::Block { block } => self.preprocess_block(&body[*block], ctx),
-            ExprKind::Assign { .. } => todo!(),
-            ExprKind::AssignOp { .. } => todo!(),
+            ExprKind::Assign { lhs, rhs } | ExprKind::AssignOp { lhs, rhs, .. } => {
+                self.in_order = true;
+                self.preprocess_expr(&body[*lhs], ctx);
+                self.preprocess_expr(&body[*rhs], ctx);
+            }
             ExprKind::Field { lhs, .. } => self.preprocess_expr(&body[*lhs], ctx),
             ExprKind::Index { lhs, index } => {
                 self.preprocess_expr(&body[*lhs], ctx);
@@ -1072,10 +1114,20 @@ impl<'tcx> FunctionBuilder<'tcx> {
             }
             ExprKind::VarRef { .. } => {}
             ExprKind::UpvarRef { .. } => {}
-            ExprKind::Borrow { arg, .. } => self.preprocess_expr(&body[*arg], ctx),
+            ExprKind::Borrow { borrow_kind, arg } => {
+                // The `&mut` arguments are assigned by the callees.
+                if matches!(borrow_kind, BorrowKind::Mut { .. }) {
+                    self.in_order = true;
+                }
+                self.preprocess_expr(&body[*arg], ctx)
+            }
             ExprKind::AddressOf { .. } => todo!(),
-            ExprKind::Break { .. } => todo!(),
-            ExprKind::Continue { .. } => todo!(),
+            ExprKind::Break { value, .. } => {
+                if let Some(value) = value {
+                    self.preprocess_expr(&body[*value], ctx)
+                }
+            }
+            ExprKind::Continue { .. } => {}
             ExprKind::Return { value } => {
                 let value = value.unwrap();
                 self.explicit_returns.push(Return { value, path_cond: ctx.path_conds() });
@@ -1128,10 +1180,12 @@ impl<'tcx> FunctionBuilder<'tcx> {
         match &stmt.kind {
             thir::StmtKind::Expr { expr, .. } => self.preprocess_expr(&self.expect_fn().thir_body.borrow()[*expr], ctx),
             thir::StmtKind::Let { pattern, initializer, else_block, .. } => {
-                self.preprocess_expr(&self.expect_fn().thir_body.borrow()[initializer.unwrap()], ctx);
+                // A variable declared without an initializer is assigned later.
+                let Some(initializer) = initializer else { return };
+                self.preprocess_expr(&self.expect_fn().thir_body.borrow()[*initializer], ctx);
 
                 if let Some(block_id) = &else_block {
-                    let else_cond = Condition::matches(initializer.unwrap(), pattern.as_ref().clone()).not();
+                    let else_cond = Condition::matches(*initializer, pattern.as_ref().clone()).not();
                     ctx.push_cond(else_cond);
                     self.preprocess_block(&self.expect_fn().thir_body.borrow()[*block_id], ctx);
                     ctx.pop_cond()
@@ -1148,6 +1202,9 @@ impl<'tcx> FunctionBuilder<'tcx> {
 pub struct FsmCache {
     inner: HashMap<Expr, ExprId>,
     hit: usize,
+
+    /// Names of the local variables bound to the expressions.
+    names: HashMap<Expr, String>,
 }
 
 impl FsmCache {
@@ -1168,6 +1225,18 @@ impl FsmCache {
         id
     }
 
+    /// Names the expression after the local variable bound to it.
+    ///
+    /// If the expression is bound to multiple variables, e.g., returned from a function, the last one is kept.
+    pub fn set_name(&mut self, id: ExprId, name: String) {
+        self.names.insert((*id.into_expr()).clone(), name);
+    }
+
+    /// Returns the name of the local variable bound to the expression.
+    pub fn name(&self, expr: &Expr) -> Option<&str> {
+        self.names.get(expr).map(String::as_str)
+    }
+
     /// Print statistics.
     pub fn stats(&self) -> String {
         format!(
@@ -1182,6 +1251,7 @@ impl FsmCache {
     pub fn clear(&mut self) {
         self.inner.clear();
         self.hit = 0;
+        self.names.clear();
     }
 }
 
@@ -1210,6 +1280,11 @@ impl ThirCache {
         self.inner.insert(thir_id, expr_id)
     }
 
+    /// Removes the mappings from the `thir::ExprId`s satisfying the predicate.
+    pub fn remove_if(&mut self, f: impl std::ops::Fn(thir::ExprId) -> bool) {
+        self.inner.retain(|thir_id, _| !f(*thir_id))
+    }
+
     /// Print statistics.
     pub fn stats(&self) -> String {
         format!(
@@ -1228,6 +1303,8 @@ enum Condition<'tcx> {
     Matches(Box<thir::Pat<'tcx>>, thir::ExprId),
 
     Not(Box<Condition<'tcx>>),
+
+    All(Vec<Condition<'tcx>>),
 }
 
 impl<'tcx> Condition<'tcx> {
@@ -1242,6 +1319,10 @@ impl<'tcx> Condition<'tcx> {
     fn matches(id: thir::ExprId, pat: thir::Pat<'tcx>) -> Self {
         Condition::Matches(Box::new(pat), id)
     }
+
+    fn all(conds: Vec<Condition<'tcx>>) -> Self {
+        Condition::All(conds)
+    }
 }
 
 struct PreprocessCtx<'tcx> {
@@ -1287,19 +1368,74 @@ struct Return<'tcx> {
 #[allow(missing_docs)]
 #[derive(Debug, Clone)]
 pub struct SystemTaskInfo<'tcx> {
-    kind: SystemTaskInfoKind,
+    pub(super) kind: SystemTaskInfoKind,
     path_cond: Vec<Condition<'tcx>>,
-    fstring: String,
-    arg: thir::ExprId,
-    span: Span,
+    pub(super) fstring: String,
+    pub(super) arg: thir::ExprId,
+    pub(super) span: Span,
 }
 
 #[derive(Debug, Clone)]
-enum SystemTaskInfoKind {
+pub(super) enum SystemTaskInfoKind {
     Display,
     Assert { cond: thir::ExprId },
 }
 
+/// Returns the system task called by `fun` with `args`, if `fun` is a system task.
+///
+/// The path condition of the returned task is empty.
+pub(super) fn system_task_info<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    body: &Thir<'tcx>,
+    fun: thir::ExprId,
+    args: &[thir::ExprId],
+) -> Option<SystemTaskInfo<'tcx>> {
+    let rustc_type_ir::TyKind::FnDef(func_def_id, _) = body[fun].ty.kind() else { panic!() };
+
+    let local = func_def_id.as_local()?;
+    let Some(HazardFlowAttr::SystemTask(task)) = get_hazardflow_attribute(tcx, tcx.local_def_id_to_hir_id(local))
+    else {
+        return None;
+    };
+
+    let task = match task {
+        SystemTaskMagic::Display => {
+            let (fstring, span) = get_string_from_thir_id(body, args[0]);
+
+            let arg_id = skip_exprs(body, args[1]);
+            log::debug!("{:#?}", &body[arg_id]);
+
+            assert!(matches!(&body[arg_id].ty.kind(), rustc_type_ir::TyKind::Tuple(_)));
+
+            SystemTaskInfo {
+                kind: SystemTaskInfoKind::Display,
+                path_cond: vec![],
+                // TODO: revisit. maybe we need to use `to_string`
+                fstring,
+                arg: arg_id,
+                span,
+            }
+        }
+        SystemTaskMagic::Assert => {
+            let cond_id = skip_exprs(body, args[0]);
+            let (fstring, span) = get_string_from_thir_id(body, args[1]);
+            let arg_id = skip_exprs(body, args[2]);
+            log::debug!("{:#?}", &body[arg_id]);
+            assert!(matches!(&body[arg_id].ty.kind(), rustc_type_ir::TyKind::Tuple(_)));
+
+            SystemTaskInfo {
+                kind: SystemTaskInfoKind::Assert { cond: cond_id },
+                path_cond: vec![],
+                fstring,
+                arg: arg_id,
+                span,
+            }
+        }
+    };
+
+    Some(task)
+}
+
 /// A task to synthesize a display function.
 #[derive(Debug, Clone)]
 pub struct SystemTask {
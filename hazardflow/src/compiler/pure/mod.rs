@@ -5,9 +5,12 @@
 mod build_expr_ast;
 mod expr;
 mod function;
+mod sequential;
 
 pub use build_expr_ast::*;
 pub use expr::*;
 pub use function::*;
+use sequential::*;
 
+use crate::compiler::error::{VirgenError, VirgenResult};
 use crate::compiler::prelude::*;
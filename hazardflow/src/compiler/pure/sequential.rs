@@ -0,0 +1,961 @@
+//! Building of the functions with loops or assignments in program order.
+
+use std::collections::HashMap;
+
+use rustc_hir::LangItem;
+use rustc_middle::middle::region;
+use rustc_middle::mir::{BorrowKind, Mutability};
+use rustc_middle::thir::{self, ExprKind, Thir};
+use rustc_middle::ty::{Instance, ParamEnv, Ty};
+use rustc_span::{DesugaringKind, Span};
+
+use super::*;
+use crate::utils::*;
+
+/// Maximum number of iterations of an unrolled loop.
+const MAX_ITERATIONS: usize = 1024;
+
+/// Loop being unrolled.
+#[derive(Debug)]
+struct LoopCtx {
+    /// Region scope of the loop, targeted by its `break`s and `continue`s.
+    label: region::Scope,
+
+    /// Length of the path context at the loop.
+    path_depth: usize,
+
+    /// Condition on which the loop has been exited by `break`.
+    broken: ExprId,
+
+    /// Condition on which the current iteration has been ended by `continue`.
+    continued: ExprId,
+
+    /// Values given to `break`, with the conditions on which they are given.
+    values: Vec<(ExprId, ExprId)>,
+
+    /// Values of the local variables at the `break`s, with the conditions on which they are taken.
+    breaks: Vec<(ExprId, Locals)>,
+
+    /// Values of the local variables at the `continue`s in the current iteration, with the conditions on which they
+    /// are taken.
+    continues: Vec<(ExprId, Locals)>,
+}
+
+/// Values of the local variables.
+type Locals = HashMap<thir::LocalVarId, ExprId>;
+
+/// State of building a function in program order.
+#[derive(Debug, Default)]
+pub(super) struct SeqCtx {
+    /// Values of the local variables at the current point, which are mutable or bound by the loops, e.g., the loop
+    /// variable of `for`.
+    pub(super) locals: Locals,
+
+    /// Loops being unrolled, from the outermost one.
+    loops: Vec<LoopCtx>,
+
+    /// Values of `return`s and the local variables at them, with the conditions on which they are taken.
+    returns: Vec<(ExprId, ExprId, Locals)>,
+}
+
+/// Projection from a local variable to the place assigned.
+#[derive(Debug, Clone)]
+enum Projection {
+    /// Field of a struct or a tuple.
+    Field(usize),
+
+    /// Element of an array.
+    Index { index: ExprId, typ_elt: PortDecls },
+}
+
+/// Place assigned, which is a local variable with the projections from it.
+type Place = (thir::LocalVarId, Vec<Projection>);
+
+/// Body of a branch.
+#[derive(Debug, Clone, Copy)]
+enum Body {
+    Expr(thir::ExprId),
+    Block(thir::BlockId),
+}
+
+impl<'tcx> ExprBuilder<'tcx, '_> {
+    /// Builds the function of `self.expr_id`, which is its body, in program order.
+    ///
+    /// Unlike `build_impl`, which builds each THIR expression once into a pure tree cached by its id, this keeps the
+    /// values of the mutable local variables at the current point, and builds the expressions in a loop again in each
+    /// iteration. The pure subexpressions, e.g., the operands of an assignment, are still built by `build_impl`.
+    ///
+    /// Returns the return value, the values of the arguments at the end of the function, and the system tasks.
+    pub(super) fn build_in_order(mut self) -> VirgenResult<(ExprId, Vec<PureValue<'tcx>>, Vec<SystemTask>)> {
+        let span = self.thir_body.borrow()[self.expr_id].span;
+
+        // NOTE: closure silently adds itself as the first parameter
+        let params =
+            self.thir_body.borrow().params.iter().skip(usize::from(self.is_closure())).cloned().collect::<Vec<_>>();
+        for param in params.iter() {
+            if let Some(pat) = &param.pat {
+                self.bind_mut_vars(pat);
+            }
+        }
+
+        let value = self.eval(self.expr_id)?;
+        if let Some(err) = self.error {
+            return Err(err);
+        }
+        let returns = std::mem::take(&mut self.seq_ctx.returns);
+        let (values, locals): (Vec<_>, Vec<_>) =
+            returns.into_iter().map(|(cond, value, locals)| ((cond, value), (cond, locals))).unzip();
+        self.merge_locals(locals, span);
+        let value = if values.is_empty() || value.into_expr().width() == 0 {
+            value
+        } else {
+            Expr::Cond { cond_expr_pair: values, default: value, span }.alloc_with_fsm_cache(self.fsm_cache)
+        };
+
+        let args = params
+            .iter()
+            .zip(self.args.iter())
+            .map(|(param, arg)| match param.pat.as_deref().map(|pat| &pat.kind) {
+                Some(thir::PatKind::Binding { var, .. }) => {
+                    self.seq_ctx.locals.get(var).map_or_else(|| arg.clone(), |value| PureValue::Expr(*value))
+                }
+                _ => arg.clone(),
+            })
+            .collect();
+
+        Ok((value, args, self.tasks_inner))
+    }
+
+    /// Returns the condition on which the current expression is evaluated, i.e., the path to it is taken and no
+    /// `break`, `continue` or `return` has been taken before it.
+    ///
+    /// `break`, `continue` and `return` are lowered to the conditions on which they are taken, so the expressions after
+    /// them are guarded by the negations of the conditions.
+    pub(super) fn get_guard(&mut self, span: Span) -> Option<ExprId> {
+        let path = self.get_current_path(span);
+        if self.seq_ctx.loops.is_empty() && self.seq_ctx.returns.is_empty() {
+            return path;
+        }
+
+        let exited = self.exited(span);
+        let guard = self.not(exited, span);
+        match path {
+            Some(path) => Some(self.and(path, guard, span)),
+            None => Some(guard),
+        }
+    }
+
+    /// Returns the condition on which a loop has been exited, an iteration has been ended, or a `return` has been taken.
+    fn exited(&mut self, span: Span) -> ExprId {
+        let conds = self
+            .seq_ctx
+            .loops
+            .iter()
+            .flat_map(|lp| [lp.broken, lp.continued])
+            .chain(self.seq_ctx.returns.iter().map(|(cond, ..)| *cond))
+            .collect::<Vec<_>>();
+        let exited = self.bool(false, span);
+        conds.into_iter().fold(exited, |acc, cond| self.or(acc, cond, span))
+    }
+
+    /// Builds the expression in program order.
+    ///
+    /// The subexpressions without assignments, loops, `break`s, `continue`s, `return`s and system tasks are built by
+    /// `build_impl`, with the current values of the local variables.
+    fn eval(&mut self, expr_id: thir::ExprId) -> VirgenResult<ExprId> {
+        let expr = &self.thir_body.borrow()[expr_id];
+        let span = expr.span;
+
+        let value = match &expr.kind {
+            ExprKind::Scope { region_scope, value, .. } => {
+                if let ExprKind::Loop { .. } = self.thir_body.borrow()[*value].kind {
+                    self.unroll_loop(*region_scope, *value)?
+                } else {
+                    self.eval(*value)?
+                }
+            }
+            ExprKind::Use { source } => self.eval(*source)?,
+            ExprKind::NeverToAny { source } => {
+                self.eval(*source)?;
+                return Ok(self.undefined(expr.ty, span));
+            }
+            ExprKind::Block { block } => self.eval_block(*block)?,
+            ExprKind::If { cond, then, else_opt, .. } => {
+                let cond = self.eval(*cond)?;
+                let mut branches = vec![(cond, None, Body::Expr(*then))];
+                if let Some(els) = else_opt {
+                    let taken = self.bool(true, span);
+                    branches.push((taken, None, Body::Expr(*els)));
+                }
+
+                self.eval_branches(branches, else_opt.is_some(), expr.ty, span)?
+            }
+            // The iterator bound by the desugaring of `for` is not built, as the loop is unrolled over its range.
+            ExprKind::Match { arms, .. } if span.is_desugaring(DesugaringKind::ForLoop) => {
+                let [arm] = &arms[..] else { panic!() };
+                self.eval(self.thir_body.borrow()[*arm].body)?
+            }
+            ExprKind::Match { scrutinee, arms, .. } => {
+                let scrutinee = self.eval(*scrutinee)?;
+
+                let mut branches = vec![];
+                for arm_id in arms.iter() {
+                    let arm = &self.thir_body.borrow()[*arm_id];
+                    let arm_cond = match self.build_arm_cond(scrutinee, arm) {
+                        Some(arm_cond) => arm_cond,
+                        None => self.bool(true, span),
+                    };
+                    branches.push((arm_cond, Some(arm.pattern.clone()), Body::Expr(arm.body)));
+                }
+
+                self.eval_branches(branches, true, expr.ty, span)?
+            }
+            ExprKind::Assign { lhs, rhs } => {
+                let value = self.eval(*rhs)?;
+                let place = self.place(*lhs)?;
+                self.assign(place, value, span);
+                self.undefined(expr.ty, span)
+            }
+            ExprKind::AssignOp { op, lhs, rhs } => {
+                let rhs = self.eval(*rhs)?;
+                let place = self.place(*lhs)?;
+                let lhs = self.read(&place, span);
+
+                let value =
+                    Expr::BinaryOp { op: BinaryOp::from(*op), lhs, rhs, span }.alloc_with_fsm_cache(self.fsm_cache);
+                let (from, to) = (value.into_expr().width(), lhs.into_expr().width());
+                let value = if from == to { value } else { Expr::resize(value, from, to, self.fsm_cache, span) };
+
+                self.assign(place, value, span);
+                self.undefined(expr.ty, span)
+            }
+            ExprKind::Break { label, value } => {
+                let cond = self.get_target_guard(*label, span)?;
+                let value = value.map(|value| self.eval(value)).transpose()?;
+
+                let idx = self.loop_idx(*label, span)?;
+                let broken = self.or(self.seq_ctx.loops[idx].broken, cond, span);
+                let lp = &mut self.seq_ctx.loops[idx];
+                lp.broken = broken;
+                lp.breaks.push((cond, self.seq_ctx.locals.clone()));
+                if let Some(value) = value {
+                    lp.values.push((cond, value));
+                }
+                self.undefined(expr.ty, span)
+            }
+            ExprKind::Continue { label } => {
+                let cond = self.get_target_guard(*label, span)?;
+
+                let idx = self.loop_idx(*label, span)?;
+                let continued = self.or(self.seq_ctx.loops[idx].continued, cond, span);
+                let lp = &mut self.seq_ctx.loops[idx];
+                lp.continued = continued;
+                lp.continues.push((cond, self.seq_ctx.locals.clone()));
+                self.undefined(expr.ty, span)
+            }
+            ExprKind::Return { value } => {
+                let value = self.eval(value.unwrap())?;
+                let cond = match self.get_guard(span) {
+                    Some(cond) => cond,
+                    None => self.bool(true, span),
+                };
+                self.seq_ctx.returns.push((cond, value, self.seq_ctx.locals.clone()));
+                self.undefined(expr.ty, span)
+            }
+            ExprKind::Call { fun, args, .. } => self.eval_call(expr_id, *fun, args, span)?,
+            ExprKind::Loop { .. } => unreachable!("a loop is in a scope"),
+            _ => self.build_impl(expr_id),
+        };
+
+        // The value is used by the variables bound to the expression.
+        if value.into_expr().width() > 0 {
+            let expr_id = skip_exprs(&self.thir_body.borrow(), expr_id);
+            self.thir_cache.insert(expr_id, value);
+        }
+
+        Ok(value)
+    }
+
+    fn eval_block(&mut self, block_id: thir::BlockId) -> VirgenResult<ExprId> {
+        let block = &self.thir_body.borrow()[block_id];
+
+        for stmt in block.stmts.iter() {
+            match &self.thir_body.borrow()[*stmt].kind {
+                thir::StmtKind::Expr { expr, .. } => {
+                    self.eval(*expr)?;
+                }
+                thir::StmtKind::Let { pattern, initializer: None, .. } => {
+                    // The variables declared without an initializer are undefined until they are assigned.
+                    let mut vars = vec![];
+                    pattern.walk_always(|pat| {
+                        if let thir::PatKind::Binding { var, ty, .. } = pat.kind {
+                            vars.push((var, ty, pat.span));
+                        }
+                    });
+                    for (var, ty, span) in vars {
+                        let value = self.undefined(ty, span);
+                        self.seq_ctx.locals.insert(var, value);
+                    }
+                }
+                thir::StmtKind::Let { pattern, initializer: Some(initializer), else_block, .. } => {
+                    let value = self.eval(*initializer)?;
+
+                    if let Some(else_block) = else_block {
+                        let span = pattern.span;
+                        let matched = match gen_match_cond(self.tcx, pattern, value, self.fsm_cache) {
+                            Some(matched) => matched,
+                            None => self.bool(true, span),
+                        };
+                        let not_matched = self.not(matched, span);
+                        self.eval_branches(
+                            vec![(not_matched, None, Body::Block(*else_block))],
+                            false,
+                            self.tcx.types.unit,
+                            span,
+                        )?;
+                    }
+
+                    self.bind_mut_vars(pattern);
+                }
+            }
+        }
+
+        match block.expr {
+            Some(expr) => self.eval(expr),
+            None => Ok(self.undefined(self.tcx.types.unit, block.span)),
+        }
+    }
+
+    /// Builds the branches, the first of which whose condition holds is taken, and merges the values of the local
+    /// variables assigned in them. `exhaustive` tells whether one of the branches is always taken.
+    fn eval_branches(
+        &mut self,
+        branches: Vec<(ExprId, Option<Box<thir::Pat<'tcx>>>, Body)>,
+        exhaustive: bool,
+        ty: Ty<'tcx>,
+        span: Span,
+    ) -> VirgenResult<ExprId> {
+        let before = self.seq_ctx.locals.clone();
+
+        let mut matched = self.bool(false, span);
+        let mut taken_branches = vec![];
+        for (cond, pattern, body) in branches {
+            let not_matched = self.not(matched, span);
+            let taken = self.and(cond, not_matched, span);
+            matched = self.or(matched, cond, span);
+            if eval_const(taken) == Some(0) {
+                continue;
+            }
+
+            self.push_path_ctx(taken);
+            if let Some(pattern) = pattern {
+                self.bind_mut_vars(&pattern);
+            }
+            let value = match body {
+                Body::Expr(expr_id) => self.eval(expr_id)?,
+                Body::Block(block_id) => self.eval_block(block_id)?,
+            };
+            self.pop_path_ctx();
+
+            let locals = std::mem::replace(&mut self.seq_ctx.locals, before.clone());
+            taken_branches.push((taken, value, locals));
+        }
+
+        let (default, default_locals) = match taken_branches.pop() {
+            Some((_, value, locals)) if exhaustive => (value, locals),
+            branch => {
+                taken_branches.extend(branch);
+                (self.undefined(ty, span), before.clone())
+            }
+        };
+
+        let mut vars = before.into_keys().collect::<Vec<_>>();
+        vars.sort_by_key(|var| var.0);
+        for var in vars {
+            let default = default_locals[&var];
+            let cond_expr_pair = taken_branches
+                .iter()
+                .map(|(taken, _, locals)| (*taken, locals[&var]))
+                .filter(|(_, value)| *value != default)
+                .collect::<Vec<_>>();
+
+            let merged = if cond_expr_pair.is_empty() {
+                default
+            } else {
+                Expr::Cond { cond_expr_pair, default, span }.alloc_with_fsm_cache(self.fsm_cache)
+            };
+            self.seq_ctx.locals.insert(var, merged);
+        }
+
+        if taken_branches.is_empty() || default.into_expr().width() == 0 {
+            return Ok(default);
+        }
+        let cond_expr_pair = taken_branches.into_iter().map(|(taken, value, _)| (taken, value)).collect();
+        Ok(Expr::Cond { cond_expr_pair, default, span }.alloc_with_fsm_cache(self.fsm_cache))
+    }
+
+    /// Builds the call. The `&mut` arguments are assigned with their values at the end of the callee.
+    fn eval_call(
+        &mut self,
+        expr_id: thir::ExprId,
+        fun: thir::ExprId,
+        args: &[thir::ExprId],
+        span: Span,
+    ) -> VirgenResult<ExprId> {
+        let expr = &self.thir_body.borrow()[expr_id];
+
+        if let Some(task) = system_task_info(self.tcx, &self.thir_body.borrow(), fun, args) {
+            self.eval_system_task(task);
+            return Ok(self.undefined(expr.ty, span));
+        }
+
+        // XXX: We come here when panic. ignore for now
+        if expr.ty.is_never() {
+            return Ok(self.undefined(expr.ty, span));
+        }
+
+        let mut places = vec![];
+        for (idx, arg) in args.iter().enumerate() {
+            let mut arg = *arg;
+            while let ExprKind::Scope { value, .. } = self.thir_body.borrow()[arg].kind {
+                arg = value;
+            }
+            if let ExprKind::Borrow { borrow_kind: BorrowKind::Mut { .. }, .. } = self.thir_body.borrow()[arg].kind {
+                places.push((idx, self.place(arg)?));
+            }
+        }
+
+        if places.is_empty() {
+            return Ok(self.build_impl(expr_id));
+        }
+
+        let rustc_type_ir::TyKind::FnDef(id, substs) = self.thir_body.borrow()[fun].ty.kind() else {
+            return Err(unsupported("calling a closure with `&mut` arguments", span));
+        };
+        let instance =
+            Instance::resolve(self.tcx, ParamEnv::reveal_all(), *id, self.monomorphise(substs)).unwrap().unwrap();
+        if !instance.def_id().is_local() {
+            return Err(unsupported("calling a function of another crate with `&mut` arguments", span));
+        }
+
+        let mut build_args = vec![];
+        for (idx, arg) in args.iter().enumerate() {
+            match places.iter().find(|(place_idx, _)| *place_idx == idx) {
+                Some((_, place)) => build_args.push(PureValue::Expr(self.read(place, span))),
+                None => build_args.push(self.collect_arg(arg)),
+            }
+        }
+
+        let (value, args_after, tasks) =
+            FunctionBuilder::new_local(instance, self.tcx).build_mut(self.tcx, build_args, self.fsm_cache)?;
+        self.add_tasks(tasks, span);
+
+        for (idx, place) in places {
+            self.assign(place, args_after[idx].expr().unwrap(), span);
+        }
+
+        Ok(value)
+    }
+
+    /// Returns the place of the expression, which is assigned.
+    fn place(&mut self, expr_id: thir::ExprId) -> VirgenResult<Place> {
+        let expr = &self.thir_body.borrow()[expr_id];
+        let span = expr.span;
+
+        match &expr.kind {
+            ExprKind::Scope { value, .. } | ExprKind::Use { source: value } | ExprKind::Borrow { arg: value, .. } => {
+                self.place(*value)
+            }
+            ExprKind::VarRef { id } => Ok((*id, vec![])),
+            ExprKind::Field { lhs, name, .. } => {
+                let (var, mut projections) = self.place(*lhs)?;
+                projections.push(Projection::Field(usize::from(*name)));
+                Ok((var, projections))
+            }
+            ExprKind::Deref { arg } => {
+                let arg = &self.thir_body.borrow()[skip_wrappers(&self.thir_body.borrow(), *arg)];
+                match &arg.kind {
+                    // `*r` for the `&mut` reference `r`, e.g., `*self`.
+                    ExprKind::VarRef { id } => Ok((*id, vec![])),
+                    // `a[i]` for `*IndexMut::index_mut(&mut a, i)`.
+                    ExprKind::Call { fun, args, .. } if self.is_index_mut(*fun) => {
+                        let index = self.eval(args[1])?;
+                        let typ_elt = PortDecls::from_ty(self.monomorphise(expr.ty), self.tcx).unwrap();
+
+                        let (var, mut projections) = self.place(args[0])?;
+                        projections.push(Projection::Index { index, typ_elt });
+                        Ok((var, projections))
+                    }
+                    _ => Err(unsupported("assigning to the dereference", span)),
+                }
+            }
+            ExprKind::UpvarRef { .. } => Err(unsupported("assigning to a variable captured by a closure", span)),
+            _ => Err(unsupported("assigning to the expression", span)),
+        }
+    }
+
+    fn is_index_mut(&self, fun: thir::ExprId) -> bool {
+        let rustc_type_ir::TyKind::FnDef(id, _) = self.thir_body.borrow()[fun].ty.kind() else { return false };
+        self.tcx.trait_of_item(*id).is_some()
+            && self.tcx.trait_of_item(*id) == self.tcx.lang_items().get(LangItem::IndexMut)
+    }
+
+    /// Returns the current value of the place.
+    fn read(&mut self, (var, projections): &Place, span: Span) -> ExprId {
+        let mut value = self.build_var_ref(var, span);
+        for projection in projections.iter() {
+            value = match projection {
+                Projection::Field(idx) => value.member(*idx, span).alloc_with_fsm_cache(self.fsm_cache),
+                Projection::Index { index, typ_elt } => {
+                    let index = self.element_index(value, *index, typ_elt, span);
+                    Expr::Get { inner: value, typ_elt: typ_elt.clone(), index, span }
+                        .alloc_with_fsm_cache(self.fsm_cache)
+                }
+            };
+        }
+        value
+    }
+
+    /// Assigns the value to the place.
+    fn assign(&mut self, (var, projections): Place, value: ExprId, span: Span) {
+        let before = self.build_var_ref(&var, span);
+        let after = self.update(before, &projections, value, span);
+
+        // Names the wire of the value after the variable.
+        self.fsm_cache.set_name(after, self.tcx.hir().name(var.0).to_string());
+        self.seq_ctx.locals.insert(var, after);
+    }
+
+    /// Returns `inner` with the place projected by `projections` replaced by `value`.
+    fn update(&mut self, inner: ExprId, projections: &[Projection], value: ExprId, span: Span) -> ExprId {
+        let Some((projection, rest)) = projections.split_first() else {
+            return value;
+        };
+
+        match projection {
+            Projection::Field(idx) => {
+                let PortDecls::Struct(fields) = inner.into_expr().port_decls() else { panic!() };
+                let fields = fields
+                    .into_iter()
+                    .enumerate()
+                    .map(|(i, (name, _))| {
+                        let member = inner.member(i, span).alloc_with_fsm_cache(self.fsm_cache);
+                        (name, if i == *idx { self.update(member, rest, value, span) } else { member })
+                    })
+                    .collect();
+                Expr::Struct { inner: fields, span }.alloc_with_fsm_cache(self.fsm_cache)
+            }
+            Projection::Index { index, typ_elt } => {
+                let index = self.element_index(inner, *index, typ_elt, span);
+                let elt =
+                    Expr::Get { inner, typ_elt: typ_elt.clone(), index, span }.alloc_with_fsm_cache(self.fsm_cache);
+                let elt = self.update(elt, rest, value, span);
+                Expr::Set { inner, index, elt, span }.alloc_with_fsm_cache(self.fsm_cache)
+            }
+        }
+    }
+
+    /// Resizes the index to the width indexing the elements of the array.
+    fn element_index(&mut self, array: ExprId, index: ExprId, typ_elt: &PortDecls, span: Span) -> ExprId {
+        let len = array.into_expr().width() / typ_elt.width();
+        Expr::resize(index, index.into_expr().width(), clog2(len), self.fsm_cache, span)
+    }
+
+    /// Binds the mutable variables in the pattern to their current values, which are replaced by assignments.
+    fn bind_mut_vars(&mut self, pattern: &thir::Pat<'tcx>) {
+        let mut vars = vec![];
+        pattern.walk_always(|pat| {
+            if let thir::PatKind::Binding { mutability, var, ty, .. } = pat.kind {
+                if mutability == Mutability::Mut || ty.is_mutable_ptr() {
+                    vars.push((var, pat.span));
+                }
+            }
+        });
+
+        for (var, span) in vars {
+            self.seq_ctx.locals.remove(&var);
+            let value = self.build_var_ref(&var, span);
+            self.seq_ctx.locals.insert(var, value);
+        }
+    }
+
+    /// Returns an undefined value of the type, which is `()` for `!`.
+    fn undefined(&mut self, ty: Ty<'tcx>, span: Span) -> ExprId {
+        let ty = self.monomorphise(ty);
+        let typ = if ty.is_never() { PortDecls::Struct(vec![]) } else { PortDecls::from_ty(ty, self.tcx).unwrap() };
+        Expr::X { typ, span }.alloc_with_fsm_cache(self.fsm_cache)
+    }
+
+    /// Unrolls the loop targeted by `label`, and returns its value.
+    ///
+    /// `for` over a range with constant bounds, e.g., `for i in 0..N`, builds its body for each value of the range,
+    /// with the loop variable bound to the value. `loop` and `while` build their bodies until the condition on which
+    /// the loop is exited folds to `true`. Either is not supported beyond [`MAX_ITERATIONS`] iterations. The value of
+    /// the loop is selected from the values given to its `break`s by their conditions.
+    fn unroll_loop(&mut self, label: region::Scope, loop_id: thir::ExprId) -> VirgenResult<ExprId> {
+        let expr = &self.thir_body.borrow()[loop_id];
+        let ExprKind::Loop { body } = expr.kind else { panic!() };
+        let span = expr.span;
+
+        let not_taken = self.bool(false, span);
+        self.seq_ctx.loops.push(LoopCtx {
+            label,
+            path_depth: self.path_ctx.inner.len(),
+            broken: not_taken,
+            continued: not_taken,
+            values: vec![],
+            breaks: vec![],
+            continues: vec![],
+        });
+
+        if span.is_desugaring(DesugaringKind::ForLoop) {
+            let (var, values, body) = self.for_loop(body, span)?;
+            for value in values {
+                if !self.begin_iteration(span) {
+                    break;
+                }
+                if let Some(var) = var {
+                    self.seq_ctx.locals.insert(var, value);
+                }
+                self.eval(body)?;
+                self.end_iteration(span);
+            }
+            if let Some(var) = var {
+                self.seq_ctx.locals.remove(&var);
+            }
+        } else {
+            let mut iterations = 0;
+            while self.begin_iteration(span) {
+                if iterations == MAX_ITERATIONS {
+                    return Err(unsupported(
+                        &format!("a loop not exited in {MAX_ITERATIONS} iterations, whose trip count is not known at compile time"),
+                        span,
+                    ));
+                }
+                iterations += 1;
+                self.eval(body)?;
+                self.end_iteration(span);
+            }
+        }
+
+        let lp = self.seq_ctx.loops.pop().unwrap();
+        self.merge_locals(lp.breaks, span);
+        let default = self.undefined(expr.ty, span);
+        if lp.values.is_empty() || default.into_expr().width() == 0 {
+            Ok(default)
+        } else {
+            Ok(Expr::Cond { cond_expr_pair: lp.values, default, span }.alloc_with_fsm_cache(self.fsm_cache))
+        }
+    }
+
+    /// Begins an iteration of the innermost loop. Returns `false` if the loop has been exited.
+    fn begin_iteration(&mut self, loop_span: Span) -> bool {
+        let not_taken = self.bool(false, loop_span);
+        self.seq_ctx.loops.last_mut().unwrap().continued = not_taken;
+
+        let exited = self.exited(loop_span);
+        if eval_const(exited) == Some(1) {
+            return false;
+        }
+
+        // The expressions in the loop are built again in each iteration.
+        let thir_body = self.thir_body.borrow();
+        self.thir_cache.remove_if(|thir_id| loop_span.contains(thir_body[thir_id].span.source_callsite()));
+        true
+    }
+
+    /// Ends an iteration of the innermost loop, where the `continue`s in the iteration join.
+    fn end_iteration(&mut self, loop_span: Span) {
+        let continues = std::mem::take(&mut self.seq_ctx.loops.last_mut().unwrap().continues);
+        self.merge_locals(continues, loop_span);
+    }
+
+    /// Merges the values of the local variables at the points joining the current one, e.g., the `break`s of a loop
+    /// after it. The variables take the values at the first point whose condition holds, or the current values if none.
+    fn merge_locals(&mut self, joins: Vec<(ExprId, Locals)>, span: Span) {
+        if joins.is_empty() {
+            return;
+        }
+
+        let mut vars = self.seq_ctx.locals.keys().copied().collect::<Vec<_>>();
+        vars.sort_by_key(|var| var.0);
+        for var in vars {
+            let default = self.seq_ctx.locals[&var];
+            let cond_expr_pair = joins
+                .iter()
+                .filter_map(|(cond, locals)| Some((*cond, *locals.get(&var)?)))
+                .filter(|(_, value)| *value != default)
+                .collect::<Vec<_>>();
+
+            if !cond_expr_pair.is_empty() {
+                let merged = Expr::Cond { cond_expr_pair, default, span }.alloc_with_fsm_cache(self.fsm_cache);
+                self.seq_ctx.locals.insert(var, merged);
+            }
+        }
+    }
+
+    /// Returns the loop variable, its values, and the body of the `for` loop.
+    ///
+    /// The range should have bounds known at compile time, and at most [`MAX_ITERATIONS`] values.
+    ///
+    /// `for <pat> in <range> { <body> }` is desugared into
+    /// `match IntoIterator::into_iter(<range>) { mut iter => loop { match Iterator::next(&mut iter) { None => break,
+    /// Some(<pat>) => <body> } } }`, whose `loop` has `body`.
+    fn for_loop(
+        &mut self,
+        body: thir::ExprId,
+        span: Span,
+    ) -> VirgenResult<(Option<thir::LocalVarId>, Vec<ExprId>, thir::ExprId)> {
+        let thir_body = self.thir_body.borrow();
+
+        let ExprKind::Block { block } = thir_body[skip_wrappers(&thir_body, body)].kind else { panic!() };
+        let [stmt] = &thir_body[block].stmts[..] else { panic!() };
+        let thir::StmtKind::Expr { expr, .. } = thir_body[*stmt].kind else { panic!() };
+        let ExprKind::Match { scrutinee, ref arms, .. } = thir_body[skip_wrappers(&thir_body, expr)].kind else {
+            panic!()
+        };
+
+        // The iterator is bound by the arm of the outer `match`, whose scrutinee is `IntoIterator::into_iter(<range>)`.
+        let ExprKind::Call { ref args, .. } = thir_body[skip_wrappers(&thir_body, scrutinee)].kind else { panic!() };
+        let ExprKind::VarRef { id: iter } = thir_body[skip_wrappers(&thir_body, args[0])].kind else { panic!() };
+        let [LocalVar::PatBinding { expr_id: into_iter, .. }] =
+            resolve_var_ref(self.tcx, self.thir_body, iter, Some(self.pat_bindings))[..]
+        else {
+            panic!()
+        };
+        let ExprKind::Call { ref args, .. } = thir_body[skip_wrappers(&thir_body, into_iter)].kind else { panic!() };
+        let range = &thir_body[skip_wrappers(&thir_body, args[0])];
+
+        let (start, end, inclusive) = match &range.kind {
+            ExprKind::Adt(adt) if self.tcx.lang_items().get(LangItem::Range) == Some(adt.adt_def.did()) => {
+                let field = |idx: usize| adt.fields.iter().find(|field| usize::from(field.name) == idx).unwrap().expr;
+                (field(0), field(1), false)
+            }
+            ExprKind::Call { fun, args, .. } if matches!(thir_body[*fun].ty.kind(), rustc_type_ir::TyKind::FnDef(id, _) if self.tcx.lang_items().get(LangItem::RangeInclusiveNew) == Some(*id)) => {
+                (args[0], args[1], true)
+            }
+            _ => return Err(unsupported("`for` over an iterator other than a range", span)),
+        };
+
+        let arm = arms
+            .iter()
+            .map(|arm| &thir_body[*arm])
+            .find(|arm| matches!(arm.pattern.kind, thir::PatKind::Variant { variant_index, .. } if variant_index.as_usize() == 1))
+            .unwrap();
+        let thir::PatKind::Variant { subpatterns, .. } = &arm.pattern.kind else { unreachable!() };
+        let pattern = &subpatterns[0].pattern;
+        let var = match &pattern.kind {
+            thir::PatKind::Binding { var, subpattern: None, .. } => Some(*var),
+            thir::PatKind::Wild => None,
+            _ => return Err(unsupported("destructuring the loop variable of `for`", pattern.span)),
+        };
+        let typ = PortDecls::from_ty(self.monomorphise(pattern.ty), self.tcx).unwrap();
+
+        let mut bounds = [start, end].into_iter().map(|bound| {
+            let value = eval_const(self.build_impl(bound)).ok_or_else(|| {
+                unsupported("`for` over a range whose bounds are not known at compile time", thir_body[bound].span)
+            })?;
+            Ok(if typ.is_signed() { sext(value, typ.width()) } else { value.try_into().unwrap() })
+        });
+        let (start, end) = (bounds.next().unwrap()?, bounds.next().unwrap()?);
+        let end = if inclusive {
+            end.checked_add(1).ok_or_else(|| unsupported("`for` over a range ending at `i128::MAX`", range.span))?
+        } else {
+            end
+        };
+        if end.saturating_sub(start) > MAX_ITERATIONS as i128 {
+            return Err(unsupported(&format!("`for` over a range of more than {MAX_ITERATIONS} values"), range.span));
+        }
+        let values = (start..end)
+            .map(|value| Expr::int_bits(typ.clone(), value as u128, span).alloc_with_fsm_cache(self.fsm_cache))
+            .collect();
+
+        Ok((var, values, arm.body))
+    }
+
+    fn eval_system_task(&mut self, task: SystemTaskInfo<'tcx>) {
+        let path_cond = self.get_guard(task.span);
+
+        let kind = match task.kind {
+            SystemTaskInfoKind::Display => SystemTaskKind::Display,
+            SystemTaskInfoKind::Assert { cond } => SystemTaskKind::Assert { cond: self.build_impl(cond) },
+        };
+
+        let args = self.build_impl(task.arg);
+        let PortDecls::Struct(inner) = args.into_expr().port_decls() else { panic!() };
+        let args = (0..inner.len()).map(|i| args.member(i, task.span).alloc_with_fsm_cache(self.fsm_cache)).collect();
+
+        self.tasks_inner.push(SystemTask { kind, fstring: task.fstring, path_cond, args, span: task.span });
+    }
+
+    /// Returns the index of the loop targeted by `label`.
+    fn loop_idx(&self, label: region::Scope, span: Span) -> VirgenResult<usize> {
+        self.seq_ctx
+            .loops
+            .iter()
+            .rposition(|lp| lp.label == label)
+            .ok_or_else(|| unsupported("`break` from a labeled block", span))
+    }
+
+    /// Returns the condition on which the current expression is evaluated, relative to the path to the loop targeted
+    /// by `label`.
+    fn get_target_guard(&mut self, label: region::Scope, span: Span) -> VirgenResult<ExprId> {
+        let idx = self.loop_idx(label, span)?;
+        let path_depth = self.seq_ctx.loops[idx].path_depth;
+        let path = self.path_ctx.inner.iter().skip(path_depth).copied().collect::<Vec<_>>();
+
+        let exited = self.exited(span);
+        let guard = self.not(exited, span);
+        Ok(path.into_iter().fold(guard, |acc, cond| self.and(acc, cond, span)))
+    }
+
+    fn bool(&mut self, value: bool, span: Span) -> ExprId {
+        Expr::unsigned_bits(1, value as usize, span).alloc_with_fsm_cache(self.fsm_cache)
+    }
+
+    fn not(&mut self, inner: ExprId, span: Span) -> ExprId {
+        match eval_const(inner) {
+            Some(value) => self.bool(value == 0, span),
+            None => Expr::Not { inner, span }.alloc_with_fsm_cache(self.fsm_cache),
+        }
+    }
+
+    fn and(&mut self, lhs: ExprId, rhs: ExprId, span: Span) -> ExprId {
+        match (eval_const(lhs), eval_const(rhs)) {
+            (Some(0), _) => lhs,
+            (_, Some(0)) => rhs,
+            (Some(_), _) => rhs,
+            (_, Some(_)) => lhs,
+            _ => Expr::BinaryOp { op: BinaryOp::And, lhs, rhs, span }.alloc_with_fsm_cache(self.fsm_cache),
+        }
+    }
+
+    fn or(&mut self, lhs: ExprId, rhs: ExprId, span: Span) -> ExprId {
+        match (eval_const(lhs), eval_const(rhs)) {
+            (Some(0), _) => rhs,
+            (_, Some(0)) => lhs,
+            (Some(_), _) => lhs,
+            (_, Some(_)) => rhs,
+            // `x || !x`, e.g., the `break` taken unless the loop has been exited.
+            _ if matches!(*rhs.into_expr(), Expr::Not { inner, .. } if inner == lhs) => self.bool(true, span),
+            _ => Expr::BinaryOp { op: BinaryOp::Or, lhs, rhs, span }.alloc_with_fsm_cache(self.fsm_cache),
+        }
+    }
+}
+
+/// Returns the error for a form which is not supported in a function built in program order, e.g., `for` over a range
+/// with non-constant bounds.
+fn unsupported(form: &str, span: Span) -> VirgenError {
+    VirgenError::collect_fsm_error(format!("{form} is not supported: {span:?}"))
+}
+
+/// Skips the scopes, uses, borrows and dereferences, which the desugaring of `for` wraps around its parts.
+fn skip_wrappers(body: &Thir<'_>, expr_id: thir::ExprId) -> thir::ExprId {
+    match body[expr_id].kind {
+        ExprKind::Scope { value, .. }
+        | ExprKind::Use { source: value }
+        | ExprKind::Borrow { arg: value, .. }
+        | ExprKind::Deref { arg: value } => skip_wrappers(body, value),
+        _ => expr_id,
+    }
+}
+
+/// Sign-extends the lower `width` bits of the value.
+fn sext(value: u128, width: usize) -> i128 {
+    let shift = 128 - width.clamp(1, 128);
+    ((value << shift) as i128) >> shift
+}
+
+/// Evaluates the expression, if it is a constant of at most 128 bits. Signed constants are in two's complement.
+fn eval_const(expr_id: ExprId) -> Option<u128> {
+    let expr = expr_id.into_expr();
+    let width = expr.width();
+    if width > 128 {
+        return None;
+    }
+    let mask = |value: u128| if width == 128 { value } else { value & ((1 << width) - 1) };
+
+    let value = match &*expr {
+        Expr::Constant { bits, .. } => bits.iter().rev().fold(0, |acc, bit| (acc << 1) | u128::from(*bit)),
+        Expr::Not { inner, .. } => !eval_const(*inner)?,
+        Expr::BinaryOp { op, lhs: lhs_id, rhs: rhs_id, .. } => {
+            let [lhs_typ, rhs_typ] = [lhs_id, rhs_id].map(|operand| operand.into_expr().port_decls());
+            // As in Verilog, the operations are signed if both operands are signed, except for the shifts.
+            let signed = lhs_typ.is_signed() && rhs_typ.is_signed();
+            let (lhs, rhs) = match (op, eval_const(*lhs_id), eval_const(*rhs_id)) {
+                (BinaryOp::And, Some(0), _) | (BinaryOp::And, _, Some(0)) => return Some(0),
+                (BinaryOp::Or, Some(value), _) | (BinaryOp::Or, _, Some(value)) if value == mask(u128::MAX) => {
+                    return Some(value)
+                }
+                (_, lhs, rhs) => (lhs?, rhs?),
+            };
+            let [lhs_int, rhs_int] =
+                [(lhs, &lhs_typ), (rhs, &rhs_typ)]
+                    .map(|(value, typ)| if signed { sext(value, typ.width()) } else { value as i128 });
+            let ordering = if signed { lhs_int.cmp(&rhs_int) } else { lhs.cmp(&rhs) };
+            match op {
+                BinaryOp::Add => lhs_int.wrapping_add(rhs_int) as u128,
+                BinaryOp::Sub => lhs.wrapping_sub(rhs),
+                BinaryOp::Mul => lhs_int.wrapping_mul(rhs_int) as u128,
+                BinaryOp::Div if signed => lhs_int.checked_div(rhs_int)? as u128,
+                BinaryOp::Mod if signed => lhs_int.checked_rem(rhs_int)? as u128,
+                BinaryOp::Div => lhs.checked_div(rhs)?,
+                BinaryOp::Mod => lhs.checked_rem(rhs)?,
+                BinaryOp::Or => lhs | rhs,
+                BinaryOp::And => lhs & rhs,
+                BinaryOp::Xor => lhs ^ rhs,
+                BinaryOp::Eq | BinaryOp::EqArithmetic => u128::from(lhs == rhs),
+                BinaryOp::NeStrict | BinaryOp::NeArithmetic => u128::from(lhs != rhs),
+                BinaryOp::Less => u128::from(ordering.is_lt()),
+                BinaryOp::Greater => u128::from(ordering.is_gt()),
+                BinaryOp::LessEq => u128::from(ordering.is_le()),
+                BinaryOp::GreaterEq => u128::from(ordering.is_ge()),
+                BinaryOp::ShiftLeft => lhs.checked_shl(rhs.try_into().ok()?).unwrap_or(0),
+                BinaryOp::ShiftRight if lhs_typ.is_signed() => {
+                    (sext(lhs, lhs_typ.width()) >> u32::try_from(rhs).unwrap_or(127).min(127)) as u128
+                }
+                BinaryOp::ShiftRight => lhs.checked_shr(rhs.try_into().ok()?).unwrap_or(0),
+            }
+        }
+        Expr::Cast { from, .. } => {
+            let from_typ = from.into_expr().port_decls();
+            if from_typ.is_signed() {
+                sext(eval_const(*from)?, from_typ.width()) as u128
+            } else {
+                eval_const(*from)?
+            }
+        }
+        Expr::Repeat { inner, count, .. } => {
+            let inner_width = inner.into_expr().width();
+            let inner = eval_const(*inner)?;
+            (0..*count).fold(0, |acc, i| {
+                acc | u32::try_from(i * inner_width).ok().and_then(|shift| inner.checked_shl(shift)).unwrap_or(0)
+            })
+        }
+        Expr::Append { lhs, rhs, .. } => {
+            let lhs_width = lhs.into_expr().width();
+            eval_const(*lhs)? | eval_const(*rhs)?.checked_shl(lhs_width.try_into().ok()?).unwrap_or(0)
+        }
+        Expr::Clip { inner, typ_elt, from, .. } => {
+            let from = eval_const(*from)? * u128::try_from(typ_elt.width()).ok()?;
+            eval_const(*inner)?.checked_shr(from.try_into().ok()?).unwrap_or(0)
+        }
+        Expr::Member { inner, index, .. } => match &*inner.into_expr() {
+            Expr::Struct { inner, .. } => eval_const(inner[*index].1)?,
+            _ => return None,
+        },
+        Expr::Cond { cond_expr_pair, default, .. } => {
+            let mut selected = *default;
+            for (cond, expr) in cond_expr_pair.iter() {
+                if eval_const(*cond)? != 0 {
+                    selected = *expr;
+                    break;
+                }
+            }
+            eval_const(selected)?
+        }
+        _ => return None,
+    };
+
+    Some(mask(value))
+}
@@ -199,6 +199,46 @@ impl<'tcx> Virgen<'tcx> {
         Ok(module)
     }
 
+    /// Returns the debug information of the module.
+    ///
+    /// NOTE: This function should only be called after `preprocess`
+    pub(crate) fn debug_info(&self) -> debug_info::ModuleInfo {
+        gen_module_info(self)
+    }
+
+    /// Returns the const generic arguments of the module.
+    pub(crate) fn const_generics(&self) -> vir::param::ConstGenerics {
+        let def_id = self.instance.def_id();
+        let generics = self.tcx.generics_of(def_id);
+
+        let args = self
+            .instance
+            .args
+            .iter()
+            .enumerate()
+            .take(generics.count())
+            .filter_map(|(idx, arg)| {
+                let value = arg.as_const()?.try_to_scalar()?.try_to_int().ok()?;
+                Some((generics.param_at(idx, self.tcx).name.to_string(), value.assert_bits(value.size())))
+            })
+            .collect();
+
+        vir::param::ConstGenerics { item: self.tcx.def_path_str(def_id), args }
+    }
+
+    /// Returns the Verilog source files of the FFI submodules.
+    ///
+    /// NOTE: This function should only be called after `preprocess`
+    pub(crate) fn ffi_sources(&self) -> Vec<String> {
+        self.submodules
+            .iter()
+            .filter_map(|(module, _)| match &*module.inner {
+                ModuleInner::Ffi(ffi) => ffi.source.clone(),
+                _ => None,
+            })
+            .collect()
+    }
+
     /// TODO: need to refactor. Don't do string spliting
     pub(crate) fn top_module_name(&self) -> String {
         if self.prefix.is_empty() { self.name() } else { self.prefix[0].clone() }
@@ -325,7 +365,11 @@ impl<'tcx> Virgen<'tcx> {
         let module_inst = vir::ModuleInstantiation::new(
             module.get_module_name(),
             module.inst_name.clone(),
-            module.params.clone(),
+            module
+                .params
+                .iter()
+                .map(|(name, value)| (name.clone(), vir::Expression::number(value.to_string())))
+                .collect(),
             connections,
         );
 
@@ -349,7 +393,11 @@ impl<'tcx> Virgen<'tcx> {
             } else {
                 format!("{}_{}", module.prefix.join("_"), module.inst_name)
             },
-            module.params.clone(),
+            module
+                .params
+                .iter()
+                .map(|(name, value)| (name.clone(), vir::Expression::number(value.to_string())))
+                .collect(),
             connections,
         );
 
@@ -438,7 +486,7 @@ impl<'tcx> Virgen<'tcx> {
         // XXX: This is a bad design
         ctx.clear_fsm_ctx();
 
-        let (fsm_ast, displays) = fsm_function_builder.build(self.tcx, fsm_inputs, &mut ctx.fsm_cache);
+        let (fsm_ast, displays) = fsm_function_builder.build(self.tcx, fsm_inputs, &mut ctx.fsm_cache)?;
 
         let fsm_ast = &fsm_ast.into_expr();
         // NOTE: This should come before translating exprs for displays
@@ -648,6 +696,9 @@ impl<'tcx> Virgen<'tcx> {
                 let literal = gen_expr_literal(expr).map(|s| {
                     if s.is_empty() {
                         vir::Expression::number("0".to_string())
+                    } else if expr.port_decls().is_signed() {
+                        // Signed constants keep the operations with them signed.
+                        vir::Expression::number(format!("{}'sb{}", s.len(), s.to_string()))
                     } else if s.iter().all(|x| *x == LogicValue::False) {
                         vir::Expression::number(format!("{}'b0", s.len()))
                     } else if s.iter().all(|x| *x == LogicValue::X) {
@@ -1014,6 +1065,17 @@ impl<'tcx> Virgen<'tcx> {
                 let exprs_for_output_inner = exprs_for_output.clone().into_expr();
 
                 let exprs_for_elts = match from_typ.width().cmp(&to.width()) {
+                    // Signed integers are sign-extended, as in Rust.
+                    std::cmp::Ordering::Less if from_typ.is_signed() => {
+                        let msb = if from_typ.width() == 1 {
+                            exprs_for_from.clone()
+                        } else {
+                            exprs_for_from.clone().with_range(Range::new_index(vir::Expression::number(
+                                (from_typ.width() - 1).to_string(),
+                            )))
+                        };
+                        msb.multiple_concat(to.width() - from_typ.width()).concat(exprs_for_from)
+                    }
                     std::cmp::Ordering::Less => vir::Expression::number("1'b0".to_string())
                         .multiple_concat(to.width() - from_typ.width())
                         .concat(exprs_for_from),
@@ -1250,7 +1312,7 @@ impl<'tcx> Virgen<'tcx> {
                 PureValue::Expr(inner_indexed.alloc_with_fsm_cache(&mut ctx.fsm_cache)),
             ],
             &mut ctx.fsm_cache,
-        );
+        )?;
         ctx.displays.append(&mut displays);
 
         let expr_folded = &expr_folded.into_expr();
@@ -1381,7 +1443,7 @@ impl<'tcx> Virgen<'tcx> {
             self.tcx,
             vec![PureValue::Expr(inner_indexed.alloc_with_fsm_cache(&mut ctx.fsm_cache))],
             &mut ctx.fsm_cache,
-        );
+        )?;
         ctx.displays.append(&mut displays);
         let expr_mapped = &expr_mapped.into_expr();
         let (decls_for_loop_body, stmts_for_loop_body, exprs_for_loop_body) = self.gen_expr(expr_mapped, ctx, cache)?;
@@ -1493,7 +1555,10 @@ impl<'tcx> Virgen<'tcx> {
         cache: &mut HashMap<Expr, String>,
     ) -> VirgenResult<(Vec<Declaration>, CompositeExpr<Expression>)> {
         let typ = expr.port_decls();
-        let prefix = ctx.alloc_temp_id_with_prefix();
+        let prefix = match ctx.fsm_cache.name(&expr).map(str::to_string) {
+            Some(name) => ctx.alloc_named_id_with_prefix(&name),
+            None => ctx.alloc_temp_id_with_prefix(),
+        };
         let exprs = CompositeExpr::from_typ(typ, prefix.clone());
 
         let decls = exprs.iter().map(|(ident, shape)| vir::Declaration::reg(shape, ident)).collect::<Vec<_>>();
@@ -37,14 +37,17 @@ extern crate rustc_hir;
 extern crate rustc_infer;
 extern crate rustc_interface;
 extern crate rustc_middle;
+extern crate rustc_session;
 extern crate rustc_span;
 extern crate rustc_target;
 extern crate rustc_trait_selection;
 extern crate rustc_type_ir;
 
 pub mod compiler;
+pub mod testing;
 pub mod utils;
 pub mod vir;
 
-pub use compiler::{CompileTarget, Compiler, Options};
+pub use compiler::{CompileTarget, Compiler, EmitKind, Options, SrcLoc};
+pub use hazardflow_macro::test;
 use utils::*;
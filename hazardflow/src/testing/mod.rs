@@ -0,0 +1,853 @@
+//! Transaction-level tests of synthesizable modules.
+//!
+//! [`Testbench`] compiles a `#[synthesize]` function of `hazardflow-designs` in-process with [`compile`], and simulates
+//! the integrated module with [`Simulator`]. A channel in the interfaces of the module is driven and monitored through
+//! a [`Channel`] handle, which is typed with the Rust types of its payloads and resolvers (see [`payload`]). The handles
+//! are created by [`Testbench::ingress`] and [`Testbench::egress`] from the paths of the channels in the debug
+//! information, such as `input.0` and `output`, which fail if the channel does not have the given types. A channel in
+//! arrays of interfaces is addressed with its index, such as `input.0[2]`. [`Testbench::channel`] is the fallback for
+//! the types without [`Payload`], whose payloads and resolvers are [`Value`]s.
+//!
+//! - An ingress channel is driven by a queue of payloads. If the payload type is `HOption<P>`, [`Testbench::push`]
+//!   queues a `P`, which is driven as `Some` until it is transferred. `None` is driven while the queue is empty, or
+//!   in the gaps given by [`Testbench::set_valid`].
+//! - An egress channel is resolved by a [`ResolverPolicy`], which is always ready by default.
+//! - Every channel is monitored, and [`Testbench::transfers`] returns the payloads transferred so far.
+//!   [`Testbench::check_protocol`] additionally checks the valid-ready protocol on a channel, and
+//!   [`Testbench::scoreboard`] compares the transfers against a reference model.
+//!
+//! The random gaps, back-pressure, and payloads (see [`stimulus`]) are drawn from a generator seeded with
+//! [`Testbench::seed`].
+//!
+//! A payload is transferred when it is `Some` and the resolver is ready, i.e., the `ready` field is `true` if the
+//! resolver is a struct with one such as `Ready<R>`, or always otherwise. [`Testbench::set_transfer`] overrides it for
+//! custom hazards.
+//!
+//! The `#[hazardflow::test]` attribute turns a function taking a [`Testbench`] of the given module into a test:
+//!
+//! ```ignore
+//! #[hazardflow::test("custom_fifo")]
+//! fn custom_fifo_forwards(tb: &mut Testbench) -> VirgenResult<()> {
+//!     let input = tb.ingress::<u32, Ready<()>>("input.0[0]")?;
+//!     let output = tb.egress::<u32, Ready<()>>("output")?;
+//!     tb.push(input, 42u32)?;
+//!     tb.run_until(16, |tb| tb.num_transfers(output) == 1)?;
+//!     assert_eq!(tb.transfers(output)[0].1, 42);
+//!     Ok(())
+//! }
+//! ```
+//!
+//! Compiling the designs loads `hazardflow-macro`, which the build script of `hazardflow` builds in its output directory.
+
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::marker::PhantomData;
+use std::path::PathBuf;
+use std::rc::Rc;
+use std::sync::{Mutex, PoisonError};
+
+use itertools::Itertools;
+use once_cell::sync::Lazy;
+use rustc_interface::Queries;
+
+pub mod payload;
+pub mod protocol;
+pub mod riscv;
+pub mod scoreboard;
+pub mod stimulus;
+
+use payload::some_of;
+pub use payload::{Payload, Ready};
+use protocol::*;
+use scoreboard::*;
+use stimulus::*;
+
+use crate::compiler::debug_info::{ChannelInfo, DebugInfo, Value};
+use crate::compiler::error::{VirgenError, VirgenResult};
+use crate::compiler::override_queries;
+use crate::compiler::package::Package;
+use crate::utils::clear_stolen_thirs;
+use crate::vir::sim::{Bits, Scopes, Simulator};
+use crate::vir::Module;
+use crate::*;
+
+/// Compiled top-level module.
+#[derive(Debug, Clone)]
+pub struct Compiled {
+    /// Module with the submodules integrated.
+    pub module: Module,
+
+    /// Debug information.
+    pub debug_info: DebugInfo,
+
+    /// Scopes of the signals in the waveform.
+    pub(crate) scopes: Scopes,
+}
+
+/// Compiled modules by their names and whether the system tasks are compiled. It also serializes the compilations,
+/// which share the stolen `Thir`s.
+static COMPILED: Lazy<Mutex<HashMap<(String, bool), Compiled>>> = Lazy::new(Default::default);
+
+/// Compiles the top-level module `target` of `hazardflow-designs`, such as `custom_fifo`.
+///
+/// The module is compiled with `--wire-cache --deadcode --integrate`, and `--system-task` if `system_task` is `true`.
+/// The result is cached for the process.
+pub fn compile(target: &str, system_task: bool) -> VirgenResult<Compiled> {
+    let mut compiled = COMPILED.lock().unwrap_or_else(PoisonError::into_inner);
+    if let Some(module) = compiled.get(&(target.to_string(), system_task)) {
+        return Ok(module.clone());
+    }
+
+    let options = Options {
+        // Concurrent test binaries compile in separate processes, so they must not share a directory.
+        build_dir: std::env::temp_dir().join(format!("hazardflow-testing-{}", std::process::id())),
+        system_task,
+        wire_cache: true,
+        deadcode: true,
+        inline_always: false,
+        integrate: true,
+        detect_comb_loop: false,
+        target: CompileTarget::FilterBy(vec![target.to_string()]),
+        merge: false,
+        emit: EmitKind::Verilog,
+        src_loc: SrcLoc::None,
+        parameterize: false,
+        dedup: false,
+    };
+
+    let root = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("..");
+    let args = vec![
+        "hazardflow".to_string(),
+        "--crate-name=hazardflow".to_string(),
+        "--edition=2021".to_string(),
+        root.join("hazardflow-designs/src/lib.rs").to_string_lossy().to_string(),
+        "--crate-type=lib".to_string(),
+        "--extern".to_string(),
+        format!("hazardflow_macro={}", env!("HAZARDFLOW_MACRO_PATH")),
+    ];
+
+    let mut collector = Collector { options, outputs: None };
+    rustc_driver::RunCompiler::new(&args, &mut collector)
+        .run()
+        .map_err(|_| VirgenError::Misc { msg: "failed to compile `hazardflow-designs`".to_string() })?;
+    let outputs = collector.outputs.unwrap_or_else(|| Ok(vec![]))?;
+
+    for (name, output) in outputs {
+        let _unused = compiled.insert((name, system_task), output);
+    }
+    compiled
+        .get(&(target.to_string(), system_task))
+        .cloned()
+        .ok_or_else(|| VirgenError::Misc { msg: format!("`{}` is not a top-level module", target) })
+}
+
+/// Compiler callbacks collecting the outputs instead of writing them.
+struct Collector {
+    options: Options,
+    outputs: Option<VirgenResult<Vec<(String, Compiled)>>>,
+}
+
+impl rustc_driver::Callbacks for Collector {
+    fn config(&mut self, config: &mut rustc_interface::Config) {
+        clear_stolen_thirs();
+        config.override_queries = Some(override_queries);
+    }
+
+    fn after_expansion<'tcx>(
+        &mut self,
+        _compiler: &rustc_interface::interface::Compiler,
+        queries: &'tcx Queries<'tcx>,
+    ) -> rustc_driver::Compilation {
+        queries.global_ctxt().unwrap().enter(|tcx| {
+            let outputs =
+                Package::new(tcx, Rc::new(self.options.clone())).and_then(|package| package.build_integrated());
+            self.outputs = Some(outputs.map(|outputs| {
+                outputs
+                    .into_iter()
+                    .map(|(name, module, debug_info, scopes)| (name, Compiled { module, debug_info, scopes }))
+                    .collect()
+            }));
+        });
+
+        rustc_driver::Compilation::Continue
+    }
+
+    fn after_analysis<'tcx>(
+        &mut self,
+        _compiler: &rustc_interface::interface::Compiler,
+        _queries: &'tcx Queries<'tcx>,
+    ) -> rustc_driver::Compilation {
+        clear_stolen_thirs();
+        rustc_driver::Compilation::Stop
+    }
+}
+
+/// Policy to compute the resolver of an egress channel in each cycle.
+#[derive(Debug, Clone)]
+pub enum ResolverPolicy<P = Value, R = Value> {
+    /// Ready following the pattern. The other fields are zero, or computed from the cycle and the payload by the
+    /// function given to [`Testbench::set_resolver_inner`], e.g., the `R` of `VrH<P, R>`.
+    Ready(Pattern),
+
+    /// The given resolver in every cycle.
+    Fixed(R),
+
+    /// The resolver computed from the cycle and the valid payload, if any.
+    Custom(fn(u64, Option<&P>) -> R),
+}
+
+/// Handle of a channel in the interfaces of the module, with the types of its payloads and resolvers.
+///
+/// `P` is the type in `Some` if the payload type is `HOption<P>`, or the payload type otherwise. [`Value`]s represent
+/// the types without [`Payload`].
+pub struct Channel<P = Value, R = Value> {
+    id: ChannelId,
+    _marker: PhantomData<fn() -> (P, R)>,
+}
+
+impl<P, R> Clone for Channel<P, R> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<P, R> Copy for Channel<P, R> {}
+
+impl<P, R> fmt::Debug for Channel<P, R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Channel").field("interface", &self.id.interface).field("index", &self.id.index).finish()
+    }
+}
+
+/// Channel in the interfaces of the module, regardless of the types of its handles.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+struct ChannelId {
+    /// Index in `Testbench::interfaces`.
+    interface: usize,
+
+    /// Index in the arrays of interfaces.
+    index: usize,
+}
+
+/// Channel in the interfaces of the module.
+#[derive(Debug, Clone)]
+struct Interface {
+    /// Path without the `.payload` and `.resolver` suffixes.
+    path: String,
+
+    /// Whether the channel is in the ingress interface.
+    ingress: bool,
+
+    payload: ChannelInfo,
+    resolver: ChannelInfo,
+}
+
+/// Function on the values of a channel, converted from a function on the types of its handle.
+struct Erased<F: ?Sized>(Rc<F>);
+
+impl<F: ?Sized> Clone for Erased<F> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+impl<F: ?Sized> fmt::Debug for Erased<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("<function>")
+    }
+}
+
+/// Function computing a resolver from the cycle and the valid payload, if any.
+type ResolverFn = Erased<dyn Fn(u64, Option<&Value>) -> Value>;
+
+/// Function returning whether the valid payload is transferred with the resolver.
+type TransferFn = Erased<dyn Fn(&Value, &Value) -> bool>;
+
+/// [`ResolverPolicy`] on the values of a channel.
+#[derive(Debug, Clone)]
+enum Policy {
+    Ready(Pattern),
+    Fixed(Value),
+    Custom(ResolverFn),
+}
+
+/// State of a channel.
+#[derive(Debug, Clone)]
+struct ChannelState {
+    /// Payloads to be driven, for ingress channels.
+    queue: VecDeque<Value>,
+
+    /// Pattern of the valid payloads, for ingress channels.
+    valid: Pattern,
+
+    /// State of `valid`.
+    valid_state: PatternState,
+
+    /// Whether a payload was driven and not transferred in the previous cycle, for ingress channels.
+    presenting: bool,
+
+    /// Resolver policy, for egress channels.
+    policy: Policy,
+
+    /// State of the pattern of `policy`.
+    policy_state: PatternState,
+
+    /// Computes the fields of the resolver other than `ready`.
+    inner: Option<ResolverFn>,
+
+    /// Checker of the valid-ready protocol, if enabled.
+    checker: Option<Checker>,
+
+    /// Returns whether the valid payload is transferred with the resolver.
+    transfer: TransferFn,
+
+    /// Transferred payloads with the cycles.
+    transfers: Vec<(u64, Value)>,
+}
+
+/// Testbench of a top-level module. See the module documentation.
+#[derive(Debug, Clone)]
+pub struct Testbench {
+    sim: Simulator,
+    interfaces: Vec<Interface>,
+    states: HashMap<ChannelId, ChannelState>,
+    rng: Rng,
+    violations: Vec<Violation>,
+}
+
+impl Testbench {
+    /// Compiles the top-level module `target` without the system tasks and creates a testbench for it.
+    ///
+    /// The system tasks are not compiled since the assertions are checked regardless of the path conditions, e.g.,
+    /// `unwrap`s of the arguments evaluated eagerly. Use [`compile`] and [`Testbench::from_compiled`] to check them.
+    pub fn new(target: &str) -> VirgenResult<Self> {
+        Self::from_compiled(&compile(target, false)?)
+    }
+
+    /// Creates a testbench for a compiled module, and resets it for a cycle.
+    pub fn from_compiled(compiled: &Compiled) -> VirgenResult<Self> {
+        let debug_info = &compiled.debug_info;
+        let top = debug_info.modules.get(&debug_info.top).ok_or_else(|| VirgenError::Misc {
+            msg: format!("no debug information of the top module `{}`", debug_info.top),
+        })?;
+
+        let mut interfaces = vec![];
+        for payload in &top.channels {
+            let Some(path) = payload.path.strip_suffix(".payload") else {
+                continue;
+            };
+            let resolver = top.channels.iter().find(|channel| channel.path == format!("{}.resolver", path));
+            if let Some(resolver) = resolver {
+                interfaces.push(Interface {
+                    path: path.to_string(),
+                    ingress: path.starts_with("ingress."),
+                    payload: payload.clone(),
+                    resolver: resolver.clone(),
+                });
+            }
+        }
+
+        let states = interfaces
+            .iter()
+            .enumerate()
+            .flat_map(|(interface, info)| (0..info.payload.count).map(move |index| ChannelId { interface, index }))
+            .map(|id| {
+                let state = ChannelState {
+                    queue: VecDeque::new(),
+                    valid: Pattern::Always,
+                    valid_state: PatternState::default(),
+                    presenting: false,
+                    policy: Policy::Ready(Pattern::Always),
+                    policy_state: PatternState::default(),
+                    inner: None,
+                    checker: None,
+                    transfer: Erased(Rc::new(|_, resolver| is_ready(resolver))),
+                    transfers: vec![],
+                };
+                (id, state)
+            })
+            .collect();
+
+        let mut sim = Simulator::new(&compiled.module)?;
+        sim.set_scopes(&compiled.scopes);
+        let mut tb = Self { sim, interfaces, states, rng: Rng::new(0), violations: vec![] };
+        tb.sim.reset(1)?;
+        Ok(tb)
+    }
+
+    /// Returns the simulator, e.g., to record the waveform or to poke ports directly.
+    pub fn simulator(&mut self) -> &mut Simulator {
+        &mut self.sim
+    }
+
+    /// Returns the number of cycles simulated so far.
+    pub fn cycle(&self) -> u64 {
+        self.sim.cycle()
+    }
+
+    /// Seeds the random number generator of the stimulus.
+    pub fn seed(&mut self, seed: u64) {
+        self.rng = Rng::new(seed);
+    }
+
+    /// Returns the random number generator of the stimulus.
+    pub fn rng(&mut self) -> &mut Rng {
+        &mut self.rng
+    }
+
+    /// Returns the handle of the channel at `path` in the ingress interface, such as `input.0` or `input.0[2]`.
+    ///
+    /// It fails if the payloads and the resolvers of the channel are not of `P` and `R`.
+    pub fn ingress<P: Payload, R: Payload>(&self, path: &str) -> VirgenResult<Channel<P, R>> {
+        self.handle(&format!("ingress.{}", path))
+    }
+
+    /// Returns the handle of the channel at `path` in the egress interface, such as `output`. See
+    /// [`Testbench::ingress`].
+    pub fn egress<P: Payload, R: Payload>(&self, path: &str) -> VirgenResult<Channel<P, R>> {
+        self.handle(&format!("egress.{}", path))
+    }
+
+    /// Returns the handle of the channel at the full `path`, such as `ingress.input.0[2]`, with the payloads and the
+    /// resolvers as [`Value`]s.
+    ///
+    /// It is the fallback for the channels of types without [`Payload`], such as the structs of the designs.
+    pub fn channel(&self, path: &str) -> VirgenResult<Channel> {
+        self.handle(path)
+    }
+
+    /// Queues a payload to be driven to an ingress channel.
+    ///
+    /// If the payload type is `HOption<P>`, `payload` is driven as `Some`. Otherwise, it is driven as is.
+    pub fn push<P: Payload, R>(&mut self, channel: Channel<P, R>, payload: impl Into<P>) -> VirgenResult<()> {
+        self.push_value(channel.id, payload.into().to_value())
+    }
+
+    /// Queues `count` random payloads satisfying `constraint` to an ingress channel. See [`Testbench::push`].
+    ///
+    /// The payloads are drawn by rejection sampling, and it fails if no payload satisfies `constraint` in 1000 draws.
+    pub fn push_random<P: Payload, R>(
+        &mut self,
+        channel: Channel<P, R>,
+        count: usize,
+        constraint: fn(&P) -> bool,
+    ) -> VirgenResult<()> {
+        let payload = &self.interfaces[channel.id.interface].payload;
+        let ty = some_of(&payload.ty).unwrap_or(&payload.ty).clone();
+
+        for _ in 0..count {
+            let payload = (0..1000)
+                .map(|_| self.rng.value(&ty))
+                .find(|payload| constraint(&typed(payload)))
+                .ok_or_else(|| VirgenError::SimulationError {
+                    msg: format!("no random `{}` satisfies the constraint", ty.ty()),
+                })?;
+            self.push_value(channel.id, payload)?;
+        }
+        Ok(())
+    }
+
+    /// Sets the pattern of the cycles in which an ingress channel may start driving a queued payload.
+    ///
+    /// Once a payload is driven, it is driven until it is transferred as the valid-ready protocol requires.
+    pub fn set_valid<P, R>(&mut self, channel: Channel<P, R>, valid: Pattern) -> VirgenResult<()> {
+        let interface = &self.interfaces[channel.id.interface];
+        if !interface.ingress {
+            return Err(VirgenError::SimulationError {
+                msg: format!("cannot drive the payload of egress channel `{}`", interface.path),
+            });
+        }
+
+        self.state(channel.id).valid = valid;
+        Ok(())
+    }
+
+    /// Returns the number of payloads queued to an ingress channel, which are not transferred yet.
+    pub fn pending<P, R>(&self, channel: Channel<P, R>) -> usize {
+        self.states[&channel.id].queue.len()
+    }
+
+    /// Sets the resolver policy of an egress channel.
+    pub fn set_policy<P: Payload, R: Payload>(
+        &mut self,
+        channel: Channel<P, R>,
+        policy: ResolverPolicy<P, R>,
+    ) -> VirgenResult<()> {
+        let interface = &self.interfaces[channel.id.interface];
+        if interface.ingress {
+            return Err(VirgenError::SimulationError {
+                msg: format!("cannot drive the resolver of ingress channel `{}`", interface.path),
+            });
+        }
+
+        self.state(channel.id).policy = match policy {
+            ResolverPolicy::Ready(pattern) => Policy::Ready(pattern),
+            ResolverPolicy::Fixed(resolver) => Policy::Fixed(resolver.to_value()),
+            ResolverPolicy::Custom(f) => Policy::Custom(Erased(Rc::new(move |cycle, payload: Option<&Value>| {
+                f(cycle, payload.map(typed).as_ref()).to_value()
+            }))),
+        };
+        Ok(())
+    }
+
+    /// Sets the function computing the resolvers for [`ResolverPolicy::Ready`] from the cycle and the valid payload,
+    /// if any. The `ready` field of the resolvers is overwritten by the pattern.
+    pub fn set_resolver_inner<P: Payload, R: Payload>(
+        &mut self,
+        channel: Channel<P, R>,
+        inner: fn(u64, Option<&P>) -> R,
+    ) {
+        self.state(channel.id).inner = Some(Erased(Rc::new(move |cycle, payload: Option<&Value>| {
+            inner(cycle, payload.map(typed).as_ref()).to_value()
+        })));
+    }
+
+    /// Checks the valid-ready protocol on a channel from the next cycle. See [`protocol`].
+    pub fn check_protocol<P, R>(&mut self, channel: Channel<P, R>) -> VirgenResult<()> {
+        let interface = &self.interfaces[channel.id.interface];
+        if !has_some(&interface.payload) {
+            return Err(VirgenError::SimulationError {
+                msg: format!("the payload of `{}` is not `HOption<P>`", interface.path),
+            });
+        }
+
+        self.state(channel.id).checker = Some(Checker::default());
+        Ok(())
+    }
+
+    /// Returns the violations of the valid-ready protocol so far.
+    pub fn violations(&self) -> &[Violation] {
+        &self.violations
+    }
+
+    /// Returns an error listing the violations of the valid-ready protocol so far, if any.
+    pub fn check_violations(&self) -> VirgenResult<()> {
+        if self.violations.is_empty() {
+            return Ok(());
+        }
+
+        Err(VirgenError::SimulationError {
+            msg: format!("valid-ready protocol violated:\n{}", self.violations.iter().join("\n")),
+        })
+    }
+
+    /// Sets the condition on the valid payload and the resolver for a transfer on a channel.
+    pub fn set_transfer<P: Payload, R: Payload>(&mut self, channel: Channel<P, R>, transfer: fn(&P, &R) -> bool) {
+        self.state(channel.id).transfer =
+            Erased(Rc::new(move |payload, resolver| transfer(&typed(payload), &typed(resolver))));
+    }
+
+    /// Returns the payloads transferred on a channel so far, with the cycles.
+    pub fn transfers<P: Payload, R>(&self, channel: Channel<P, R>) -> Vec<(u64, P)> {
+        self.states[&channel.id].transfers.iter().map(|(cycle, payload)| (*cycle, typed(payload))).collect()
+    }
+
+    /// Returns the number of payloads transferred on a channel so far.
+    pub fn num_transfers<P, R>(&self, channel: Channel<P, R>) -> usize {
+        self.states[&channel.id].transfers.len()
+    }
+
+    /// Takes the payloads transferred on a channel so far.
+    pub fn take_transfers<P: Payload, R>(&mut self, channel: Channel<P, R>) -> Vec<(u64, P)> {
+        let transfers = std::mem::take(&mut self.state(channel.id).transfers);
+        transfers.iter().map(|(cycle, payload)| (*cycle, typed(payload))).collect()
+    }
+
+    /// Returns a scoreboard comparing the transfers of `egress` so far against `model` applied to each transfer of
+    /// `ingress`, in the order of the cycles and then of `ingress`. `model` returns the payloads expected from a
+    /// transfer, e.g., an `Option<Q>`. See [`scoreboard`].
+    pub fn scoreboard<P: Payload, R, Q: Payload, S, I: IntoIterator<Item = Q>>(
+        &self,
+        ingress: &[Channel<P, R>],
+        egress: Channel<Q, S>,
+        matching: Matching,
+        mut model: impl FnMut(&P) -> I,
+    ) -> Scoreboard {
+        let mut scoreboard = Scoreboard::new(self.path(egress.id), matching);
+        let transfers = ingress.iter().enumerate().flat_map(|(order, channel)| {
+            self.states[&channel.id].transfers.iter().map(move |(cycle, payload)| ((*cycle, order), payload))
+        });
+        for ((cycle, _), payload) in transfers.sorted_by_key(|(key, _)| *key) {
+            for expected in model(&typed(payload)) {
+                scoreboard.expect(cycle, expected.to_value());
+            }
+        }
+        for (cycle, payload) in &self.states[&egress.id].transfers {
+            scoreboard.observe(*cycle, payload.clone());
+        }
+        scoreboard
+    }
+
+    /// Returns the current payload of a channel if it is valid, i.e., `Some` if the payload type is `HOption<P>`.
+    pub fn payload<P: Payload, R>(&mut self, channel: Channel<P, R>) -> VirgenResult<Option<P>> {
+        let payload = self.read(channel.id, true)?;
+        Ok(valid(&self.interfaces[channel.id.interface].payload, &payload).map(typed))
+    }
+
+    /// Returns the current resolver of a channel.
+    pub fn resolver<P, R: Payload>(&mut self, channel: Channel<P, R>) -> VirgenResult<R> {
+        Ok(typed(&self.read(channel.id, false)?))
+    }
+
+    /// Drives the payload of a channel until the next [`Testbench::step`], which drives it again. `None` drives the
+    /// payload which is not valid, if the payload type is `HOption<P>`.
+    ///
+    /// It is for models driving the channels themselves, such as a memory responding in the same cycle as the request.
+    /// Such models simulate the cycles with [`Simulator::step`] instead.
+    pub fn set_payload<P: Payload, R>(&mut self, channel: Channel<P, R>, payload: Option<&P>) -> VirgenResult<()> {
+        let info = &self.interfaces[channel.id.interface].payload;
+        let payload = match payload {
+            Some(payload) => self.wrap(channel.id, payload.to_value()),
+            None if has_some(info) => none_of(info),
+            None => {
+                return Err(VirgenError::SimulationError {
+                    msg: format!("the payload of `{}` is not `HOption<P>`", self.path(channel.id)),
+                })
+            }
+        };
+        self.drive(channel.id, &payload, true)
+    }
+
+    /// Drives the resolver of a channel until the next [`Testbench::step`]. See [`Testbench::set_payload`].
+    pub fn set_resolver<P, R: Payload>(&mut self, channel: Channel<P, R>, resolver: &R) -> VirgenResult<()> {
+        self.drive(channel.id, &resolver.to_value(), false)
+    }
+
+    /// Drives the channels and simulates a cycle.
+    pub fn step(&mut self) -> VirgenResult<()> {
+        let mut channels = self.states.keys().copied().collect::<Vec<_>>();
+        channels.sort();
+        let (ingress, egress): (Vec<_>, Vec<_>) =
+            channels.iter().partition(|channel| self.interfaces[channel.interface].ingress);
+
+        // Drives the payloads first, since the resolver policies may depend on them.
+        for channel in &ingress {
+            let state = self.states.get_mut(channel).unwrap();
+            let present =
+                !state.queue.is_empty() && (state.presenting || state.valid_state.next(&state.valid, &mut self.rng));
+            let payload = match self.states[channel].queue.front() {
+                Some(payload) if present => self.wrap(*channel, payload.clone()),
+                _ => none_of(&self.interfaces[channel.interface].payload),
+            };
+            self.state(*channel).presenting = present;
+            self.drive(*channel, &payload, true)?;
+        }
+
+        for channel in &egress {
+            let payload = self.read(*channel, true)?;
+            let info = &self.interfaces[channel.interface];
+            let payload = valid(&info.payload, &payload);
+            let zero = info.resolver.ty.zero();
+            let cycle = self.cycle();
+            let state = self.states.get_mut(channel).unwrap();
+            let resolver = match &state.policy {
+                Policy::Ready(pattern) => {
+                    let ready = state.policy_state.next(pattern, &mut self.rng);
+                    with_ready(state.inner.as_ref().map_or(zero, |inner| (inner.0)(cycle, payload)), ready)
+                }
+                Policy::Fixed(resolver) => resolver.clone(),
+                Policy::Custom(f) => (f.0)(cycle, payload),
+            };
+            self.drive(*channel, &resolver, false)?;
+        }
+
+        // Observes the transfers before the clock edge.
+        let cycle = self.cycle();
+        for channel in &channels {
+            let payload = self.read(*channel, true)?;
+            let resolver = self.read(*channel, false)?;
+            let ingress = self.interfaces[channel.interface].ingress;
+            let valid = valid(&self.interfaces[channel.interface].payload, &payload);
+            let path = self.path(*channel);
+            let state = self.states.get_mut(channel).unwrap();
+            let transferred = valid.map_or(false, |valid| (state.transfer.0)(valid, &resolver));
+            if let Some(kind) = state.checker.as_mut().and_then(|checker| checker.check(payload.as_some(), transferred))
+            {
+                self.violations.push(Violation { cycle, channel: path, kind });
+            }
+            if !transferred {
+                continue;
+            }
+            if ingress {
+                let _unused = state.queue.pop_front();
+                state.presenting = false;
+            }
+            state.transfers.push((cycle, valid.cloned().unwrap()));
+        }
+
+        self.sim.step()
+    }
+
+    /// Simulates `cycles` cycles.
+    pub fn run(&mut self, cycles: usize) -> VirgenResult<()> {
+        for _ in 0..cycles {
+            self.step()?;
+        }
+        Ok(())
+    }
+
+    /// Simulates until `done` holds, for at most `max_cycles` cycles. Returns the number of simulated cycles.
+    pub fn run_until(&mut self, max_cycles: usize, mut done: impl FnMut(&Self) -> bool) -> VirgenResult<usize> {
+        for cycles in 0..max_cycles {
+            if done(self) {
+                return Ok(cycles);
+            }
+            self.step()?;
+        }
+
+        if done(self) {
+            return Ok(max_cycles);
+        }
+        Err(VirgenError::SimulationError { msg: format!("timed out after {} cycles", max_cycles) })
+    }
+
+    /// Simulates until every payload queued to the ingress channels is transferred, for at most `max_cycles` cycles.
+    pub fn run_until_drained(&mut self, max_cycles: usize) -> VirgenResult<usize> {
+        self.run_until(max_cycles, |tb| tb.states.values().all(|state| state.queue.is_empty()))
+    }
+
+    /// Returns the handle of the channel at `path`, checking the types of its payloads and resolvers.
+    fn handle<P: Payload, R: Payload>(&self, path: &str) -> VirgenResult<Channel<P, R>> {
+        let (interface_path, index) = match path.strip_suffix(']').and_then(|path| path.rsplit_once('[')) {
+            Some((path, index)) => (path, index.parse().ok()),
+            None => (path, Some(0)),
+        };
+
+        let interface = self.interfaces.iter().position(|interface| interface.path == interface_path);
+        let id = match (interface, index) {
+            (Some(interface), Some(index)) if index < self.interfaces[interface].payload.count => {
+                ChannelId { interface, index }
+            }
+            _ => {
+                return Err(VirgenError::SimulationError {
+                    msg: format!(
+                        "channel `{}` does not exist; the channels are {}",
+                        interface_path,
+                        self.interfaces.iter().map(|interface| format!("`{}`", interface.path)).join(", ")
+                    ),
+                })
+            }
+        };
+
+        let interface = &self.interfaces[id.interface];
+        let payload = some_of(&interface.payload.ty).unwrap_or(&interface.payload.ty);
+        if !P::is_of(payload) || !R::is_of(&interface.resolver.ty) {
+            return Err(VirgenError::SimulationError {
+                msg: format!(
+                    "channel `{}` has payloads of `{}` and resolvers of `{}`, which are not `{}` and `{}`",
+                    path,
+                    payload.ty(),
+                    interface.resolver.ty.ty(),
+                    std::any::type_name::<P>(),
+                    std::any::type_name::<R>()
+                ),
+            });
+        }
+
+        Ok(Channel { id, _marker: PhantomData })
+    }
+
+    /// Queues a payload to an ingress channel. See [`Testbench::push`].
+    fn push_value(&mut self, id: ChannelId, payload: Value) -> VirgenResult<()> {
+        let interface = &self.interfaces[id.interface];
+        if !interface.ingress {
+            return Err(VirgenError::SimulationError {
+                msg: format!("cannot drive the payload of egress channel `{}`", interface.path),
+            });
+        }
+
+        // Checks the type of the payload, which may be a `Value` of any type.
+        let mut ports = ports_of(&interface.payload, &mut self.sim)?;
+        interface.payload.encode_at(&mut ports, id.index, &self.wrap(id, payload.clone()))?;
+
+        self.state(id).queue.push_back(payload);
+        Ok(())
+    }
+
+    /// Returns the path of a channel, with the index if it is in arrays of interfaces.
+    fn path(&self, id: ChannelId) -> String {
+        let interface = &self.interfaces[id.interface];
+        if interface.payload.count == 1 {
+            interface.path.clone()
+        } else {
+            format!("{}[{}]", interface.path, id.index)
+        }
+    }
+
+    fn state(&mut self, id: ChannelId) -> &mut ChannelState {
+        self.states.get_mut(&id).unwrap()
+    }
+
+    /// Wraps a payload queued to a channel into `Some` if the payload type is `HOption<P>`.
+    fn wrap(&self, id: ChannelId, payload: Value) -> Value {
+        if has_some(&self.interfaces[id.interface].payload) {
+            Value::some(payload)
+        } else {
+            payload
+        }
+    }
+
+    /// Returns the current payload or resolver of a channel.
+    fn read(&mut self, id: ChannelId, payload: bool) -> VirgenResult<Value> {
+        let interface = &self.interfaces[id.interface];
+        let info = if payload { &interface.payload } else { &interface.resolver };
+        Ok(info.decode_at(&ports_of(info, &mut self.sim)?, id.index))
+    }
+
+    /// Drives the payload or the resolver of a channel.
+    fn drive(&mut self, id: ChannelId, value: &Value, payload: bool) -> VirgenResult<()> {
+        let interface = &self.interfaces[id.interface];
+        let info = if payload { &interface.payload } else { &interface.resolver };
+
+        let mut ports = ports_of(info, &mut self.sim)?;
+        info.encode_at(&mut ports, id.index, value).map_err(|err| VirgenError::SimulationError {
+            msg: format!("cannot drive `{}` to `{}`: {}", value, info.path, err),
+        })?;
+        for (signal, value) in info.signals.iter().zip(ports) {
+            self.sim.poke(&signal.name, value)?;
+        }
+        Ok(())
+    }
+}
+
+/// Converts a value of a channel to a type of its handle, which is checked when the handle is created.
+fn typed<T: Payload>(value: &Value) -> T {
+    T::from_value(value).expect("the types of a channel are checked when its handle is created")
+}
+
+/// Returns the current values of the ports of a channel.
+fn ports_of(info: &ChannelInfo, sim: &mut Simulator) -> VirgenResult<Vec<Bits>> {
+    info.signals.iter().map(|signal| sim.peek(&signal.name)).collect()
+}
+
+/// Returns whether the payload type is `HOption<P>` or `Option<P>`.
+fn has_some(info: &ChannelInfo) -> bool {
+    some_of(&info.ty).is_some()
+}
+
+/// Returns the valid payload, i.e., the value in `Some` if the payload type is `HOption<P>`.
+fn valid<'a>(info: &ChannelInfo, payload: &'a Value) -> Option<&'a Value> {
+    if has_some(info) {
+        payload.as_some()
+    } else {
+        Some(payload)
+    }
+}
+
+/// Returns the payload which is not valid.
+fn none_of(info: &ChannelInfo) -> Value {
+    info.ty.zero()
+}
+
+/// Returns whether the resolver is ready.
+fn is_ready(resolver: &Value) -> bool {
+    resolver.field("ready").or(Some(resolver)).and_then(Value::as_bool).unwrap_or(true)
+}
+
+/// Returns the resolver with the `ready` field set, if any.
+fn with_ready(resolver: Value, ready: bool) -> Value {
+    match resolver {
+        Value::Bool(_) => Value::Bool(ready),
+        resolver => resolver.with_field("ready", ready),
+    }
+}
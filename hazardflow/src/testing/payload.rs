@@ -0,0 +1,215 @@
+//! Rust types of the payloads and the resolvers driven and monitored by a testbench.
+//!
+//! A typed [`Channel`](super::Channel) converts the payloads and the resolvers from and to [`Value`]s with
+//! [`Payload`]. The types of the interface are checked against the type parameters when the handle is created, so a
+//! test with the wrong types fails there instead of in the middle of the simulation. A type without an implementation,
+//! such as a struct of the designs, is driven and monitored as a [`Value`].
+
+use crate::compiler::debug_info::{TypeInfo, Value};
+
+/// Rust type of a payload or a resolver.
+pub trait Payload: Sized + 'static {
+    /// Returns whether the values of `ty` are represented by the type.
+    fn is_of(ty: &TypeInfo) -> bool;
+
+    /// Converts into a value.
+    fn to_value(&self) -> Value;
+
+    /// Converts from a value of a type for which [`Payload::is_of`] holds.
+    fn from_value(value: &Value) -> Option<Self>;
+}
+
+impl Payload for Value {
+    fn is_of(_: &TypeInfo) -> bool {
+        true
+    }
+
+    fn to_value(&self) -> Value {
+        self.clone()
+    }
+
+    fn from_value(value: &Value) -> Option<Self> {
+        Some(value.clone())
+    }
+}
+
+impl Payload for bool {
+    fn is_of(ty: &TypeInfo) -> bool {
+        matches!(ty, TypeInfo::Bits { ty, .. } if ty == "bool")
+    }
+
+    fn to_value(&self) -> Value {
+        Value::Bool(*self)
+    }
+
+    fn from_value(value: &Value) -> Option<Self> {
+        value.as_bool()
+    }
+}
+
+macro_rules! impl_payload_int {
+    ($($variant:ident($signed:literal): $($ty:ty),*;)*) => {
+        $($(
+            impl Payload for $ty {
+                fn is_of(ty: &TypeInfo) -> bool {
+                    matches!(
+                        ty,
+                        TypeInfo::Bits { ty, width, signed }
+                            if ty != "bool" && *signed == $signed && *width <= <$ty>::BITS as usize
+                    )
+                }
+
+                fn to_value(&self) -> Value {
+                    Value::from(*self)
+                }
+
+                #[allow(trivial_numeric_casts)]
+                fn from_value(value: &Value) -> Option<Self> {
+                    match value {
+                        Value::$variant(bits) => Some(bits.resize(128, $signed).to_u128() as $ty),
+                        _ => None,
+                    }
+                }
+            }
+        )*)*
+    };
+}
+
+impl_payload_int! {
+    Uint(false): u8, u16, u32, u64, u128;
+    Int(true): i8, i16, i32, i64, i128;
+}
+
+impl Payload for () {
+    fn is_of(ty: &TypeInfo) -> bool {
+        matches!(ty, TypeInfo::Struct { fields, .. } if fields.is_empty())
+    }
+
+    fn to_value(&self) -> Value {
+        Value::unit()
+    }
+
+    fn from_value(value: &Value) -> Option<Self> {
+        matches!(value, Value::Struct(_, fields) if fields.is_empty()).then_some(())
+    }
+}
+
+macro_rules! impl_payload_tuple {
+    ($(($($name:ident: $index:tt),*);)*) => {
+        $(
+            impl<$($name: Payload),*> Payload for ($($name,)*) {
+                fn is_of(ty: &TypeInfo) -> bool {
+                    match ty {
+                        TypeInfo::Struct { name: None, fields, .. } => {
+                            fields.len() == [$($index),*].len() && $($name::is_of(&fields[$index].ty))&&*
+                        }
+                        _ => false,
+                    }
+                }
+
+                fn to_value(&self) -> Value {
+                    Value::tuple([$(self.$index.to_value()),*])
+                }
+
+                fn from_value(value: &Value) -> Option<Self> {
+                    Some(($($name::from_value(value.field(stringify!($index))?)?,)*))
+                }
+            }
+        )*
+    };
+}
+
+impl_payload_tuple! {
+    (A: 0, B: 1);
+    (A: 0, B: 1, C: 2);
+    (A: 0, B: 1, C: 2, D: 3);
+}
+
+impl<T: Payload, const N: usize> Payload for [T; N] {
+    fn is_of(ty: &TypeInfo) -> bool {
+        matches!(ty, TypeInfo::Array { elt, len, .. } if *len == N && T::is_of(elt))
+    }
+
+    fn to_value(&self) -> Value {
+        Value::Array(self.iter().map(T::to_value).collect())
+    }
+
+    fn from_value(value: &Value) -> Option<Self> {
+        match value {
+            Value::Array(elts) => elts.iter().map(T::from_value).collect::<Option<Vec<_>>>()?.try_into().ok(),
+            _ => None,
+        }
+    }
+}
+
+/// `HOption<T>` or `Option<T>`.
+impl<T: Payload> Payload for Option<T> {
+    fn is_of(ty: &TypeInfo) -> bool {
+        some_of(ty).map_or(false, T::is_of)
+    }
+
+    fn to_value(&self) -> Value {
+        self.as_ref().map_or_else(Value::none, |value| Value::some(value.to_value()))
+    }
+
+    fn from_value(value: &Value) -> Option<Self> {
+        match value.variant()? {
+            "None" => Some(None),
+            _ => Some(Some(T::from_value(value.as_some()?)?)),
+        }
+    }
+}
+
+/// Resolver with a ready signal, i.e., `Ready<R>` of the designs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Ready<R> {
+    /// Ready signal.
+    pub ready: bool,
+
+    /// Inner value.
+    pub inner: R,
+}
+
+impl<R> Ready<R> {
+    /// Creates a new ready signal.
+    pub fn new(ready: bool, inner: R) -> Self {
+        Self { ready, inner }
+    }
+}
+
+impl<R: Payload> Payload for Ready<R> {
+    fn is_of(ty: &TypeInfo) -> bool {
+        match ty {
+            TypeInfo::Struct { name: Some(_), fields, .. } => match fields.as_slice() {
+                [ready, inner] => {
+                    ready.name == "ready" && bool::is_of(&ready.ty) && inner.name == "inner" && R::is_of(&inner.ty)
+                }
+                _ => false,
+            },
+            _ => false,
+        }
+    }
+
+    fn to_value(&self) -> Value {
+        Value::Struct(Some("Ready".to_string()), vec![
+            ("ready".to_string(), Value::Bool(self.ready)),
+            ("inner".to_string(), self.inner.to_value()),
+        ])
+    }
+
+    fn from_value(value: &Value) -> Option<Self> {
+        Some(Self { ready: value.field("ready")?.as_bool()?, inner: R::from_value(value.field("inner")?)? })
+    }
+}
+
+/// Returns the type in `Some` if `ty` is `HOption<T>` or `Option<T>`.
+pub(super) fn some_of(ty: &TypeInfo) -> Option<&TypeInfo> {
+    match ty {
+        TypeInfo::Enum { variants, .. } if variants.len() == 2 => {
+            let none = variants.iter().find(|variant| variant.name == "None" && variant.fields.is_empty());
+            let some = variants.iter().find(|variant| variant.name == "Some" && variant.fields.len() == 1);
+            none.and(some).map(|some| &some.fields[0].ty)
+        }
+        _ => None,
+    }
+}
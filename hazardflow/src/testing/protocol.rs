@@ -0,0 +1,84 @@
+//! Checker of the valid-ready protocol.
+//!
+//! Once a payload is valid, it should stay valid and unchanged until it is transferred. Modules such as `reg_skid` and
+//! `transparent_fifo` rely on it for their ingress, and should guarantee it for their egress.
+
+use std::fmt;
+
+use crate::compiler::debug_info::Value;
+
+/// Violation of the valid-ready protocol.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Violation {
+    /// Cycle of the violation.
+    pub cycle: u64,
+
+    /// Path of the channel.
+    pub channel: String,
+
+    /// Kind of the violation.
+    pub kind: ViolationKind,
+}
+
+/// Kind of a violation of the valid-ready protocol.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ViolationKind {
+    /// The payload changed while it was valid and not transferred.
+    PayloadChanged {
+        /// Payload in the previous cycle.
+        before: Value,
+
+        /// Payload in the cycle.
+        after: Value,
+    },
+
+    /// The payload became invalid before it was transferred.
+    ValidDropped {
+        /// Payload in the previous cycle.
+        before: Value,
+    },
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            ViolationKind::PayloadChanged { before, after } => write!(
+                f,
+                "cycle {}: `{}` changed from `{}` to `{}` while valid and not ready",
+                self.cycle, self.channel, before, after
+            ),
+            ViolationKind::ValidDropped { before } => {
+                write!(
+                    f,
+                    "cycle {}: `{}` dropped valid `{}` before it was transferred",
+                    self.cycle, self.channel, before
+                )
+            }
+        }
+    }
+}
+
+/// Checker of the protocol on a channel.
+#[derive(Debug, Clone, Default)]
+pub(super) struct Checker {
+    /// Payload which was valid and not transferred in the previous cycle.
+    pending: Option<Value>,
+}
+
+impl Checker {
+    /// Checks the payload in a cycle, which is `None` if it is not valid.
+    pub(super) fn check(&mut self, payload: Option<&Value>, transferred: bool) -> Option<ViolationKind> {
+        let violation = match (self.pending.take(), payload) {
+            (Some(before), None) => Some(ViolationKind::ValidDropped { before }),
+            (Some(before), Some(after)) if before != *after => {
+                Some(ViolationKind::PayloadChanged { before, after: after.clone() })
+            }
+            _ => None,
+        };
+
+        if !transferred {
+            self.pending = payload.cloned();
+        }
+        violation
+    }
+}
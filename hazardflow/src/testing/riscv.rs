@@ -0,0 +1,558 @@
+//! Memory model and ELF loader for running RISC-V programs on `cpu::core`.
+//!
+//! [`CoreTestbench`] simulates `cpu::riscv32_5stage::core` with a [`Memory`] behind its `imem` and `dmem` interfaces,
+//! which correspond to `ingress.input.{0,1}.output` and `egress.input.{0,1}.input.0` in the debug information.
+//!
+//! Only the memory of Sodor is supported: each port accepts a request whenever the core accepts its response, which is
+//! valid in the same cycle as the request, as in the asynchronous scratchpad memory. `core` relies on it, since
+//! `attach_payload` in the memory stage does not support responses in later cycles, and the bypass from the memory
+//! stage is lost while `dmem` back-pressures a load.
+//!
+//! A program loaded from an ELF file finishes by storing to the `tohost` symbol as the riscv-tests and the benchmarks
+//! in `scripts/cpu/program` do: `1` means that it passed, and `(code << 1) | 1` means that it failed with `code`, e.g.,
+//! the number of the failed test case.
+//!
+//! Every retired instruction is recorded in the commit log from the `$display`s of the writeback stage, and can be
+//! compared with a golden model in lockstep with [`CoreTestbench::run_lockstep`].
+
+use std::collections::HashMap;
+use std::fmt::{self, Write};
+use std::path::Path;
+
+use super::*;
+
+/// Size of a page of [`Memory`].
+const PAGE_SIZE: usize = 1 << 12;
+
+/// Number of combinational iterations to settle the responses.
+const SETTLE_LIMIT: usize = 8;
+
+/// Number of commits preceding a divergence in the report of [`CoreTestbench::run_lockstep`].
+const LOCKSTEP_CONTEXT: usize = 8;
+
+/// Program loaded from an ELF file.
+#[derive(Debug, Clone, Default)]
+pub struct Elf {
+    /// Entry point.
+    pub entry: u32,
+
+    /// Loadable segments with their addresses. The bytes not in the file are zero.
+    pub segments: Vec<(u32, Vec<u8>)>,
+
+    /// Addresses of the symbols.
+    pub symbols: HashMap<String, u32>,
+}
+
+impl Elf {
+    /// Reads a 32-bit little-endian ELF file.
+    pub fn read(path: impl AsRef<Path>) -> VirgenResult<Self> {
+        let bytes = std::fs::read(path.as_ref()).map_err(|err| VirgenError::Fs { err })?;
+        Self::parse(&bytes).map_err(|msg| VirgenError::Misc { msg: format!("{}: {}", path.as_ref().display(), msg) })
+    }
+
+    /// Parses a 32-bit little-endian ELF file.
+    pub fn parse(bytes: &[u8]) -> Result<Self, String> {
+        let u16_at = |offset: usize| -> Result<u16, String> {
+            let bytes = bytes.get(offset..offset + 2).ok_or("truncated ELF file")?;
+            Ok(u16::from_le_bytes([bytes[0], bytes[1]]))
+        };
+        let u32_at = |offset: usize| -> Result<u32, String> {
+            let bytes = bytes.get(offset..offset + 4).ok_or("truncated ELF file")?;
+            Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+        };
+        let slice = |offset: u32, size: u32| -> Result<&[u8], String> {
+            bytes.get(offset as usize..offset as usize + size as usize).ok_or_else(|| "truncated ELF file".to_string())
+        };
+
+        if bytes.get(..6) != Some(b"\x7fELF\x01\x01") {
+            return Err("not a 32-bit little-endian ELF file".to_string());
+        }
+
+        let mut elf = Self { entry: u32_at(0x18)?, ..Self::default() };
+
+        // Program headers.
+        let (phoff, phentsize, phnum) = (u32_at(0x1c)? as usize, u16_at(0x2a)? as usize, u16_at(0x2c)? as usize);
+        for i in 0..phnum {
+            let header = phoff + i * phentsize;
+            if u32_at(header)? != PT_LOAD {
+                continue;
+            }
+            let (offset, addr, filesz, memsz) =
+                (u32_at(header + 4)?, u32_at(header + 12)?, u32_at(header + 16)?, u32_at(header + 20)?);
+            let mut data = slice(offset, filesz)?.to_vec();
+            data.resize(memsz.max(filesz) as usize, 0);
+            elf.segments.push((addr, data));
+        }
+
+        // Symbols.
+        let (shoff, shentsize, shnum) = (u32_at(0x20)? as usize, u16_at(0x2e)? as usize, u16_at(0x30)? as usize);
+        let section = |i: usize| shoff + i * shentsize;
+        for i in 0..shnum {
+            if u32_at(section(i) + 4)? != SHT_SYMTAB {
+                continue;
+            }
+            let (offset, size, entsize) =
+                (u32_at(section(i) + 16)?, u32_at(section(i) + 20)?, u32_at(section(i) + 36)?.max(1));
+            let strtab = section(u32_at(section(i) + 24)? as usize);
+            let names = slice(u32_at(strtab + 16)?, u32_at(strtab + 20)?)?;
+
+            for symbol in (0..size / entsize).map(|j| (offset + j * entsize) as usize) {
+                let name = &names.get(u32_at(symbol)? as usize..).ok_or("invalid symbol name")?;
+                let name = String::from_utf8_lossy(name.split(|b| *b == 0).next().unwrap_or_default());
+                if !name.is_empty() {
+                    let _unused = elf.symbols.insert(name.to_string(), u32_at(symbol + 4)?);
+                }
+            }
+        }
+
+        Ok(elf)
+    }
+
+    /// Returns the address of a symbol.
+    pub fn symbol(&self, name: &str) -> Option<u32> {
+        self.symbols.get(name).copied()
+    }
+}
+
+/// `PT_LOAD` segment type.
+const PT_LOAD: u32 = 1;
+
+/// `SHT_SYMTAB` section type.
+const SHT_SYMTAB: u32 = 2;
+
+/// Byte-addressable little-endian memory. Bytes not written are zero.
+#[derive(Debug, Clone, Default)]
+pub struct Memory {
+    pages: HashMap<u32, Box<[u8; PAGE_SIZE]>>,
+}
+
+impl Memory {
+    /// Creates a new memory.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Loads the segments of a program.
+    pub fn load_elf(&mut self, elf: &Elf) {
+        for (addr, data) in &elf.segments {
+            for (i, byte) in data.iter().enumerate() {
+                self.write_u8(addr.wrapping_add(i as u32), *byte);
+            }
+        }
+    }
+
+    /// Reads a byte.
+    pub fn read_u8(&self, addr: u32) -> u8 {
+        self.pages.get(&(addr / PAGE_SIZE as u32)).map_or(0, |page| page[addr as usize % PAGE_SIZE])
+    }
+
+    /// Writes a byte.
+    pub fn write_u8(&mut self, addr: u32, value: u8) {
+        let page = self.pages.entry(addr / PAGE_SIZE as u32).or_insert_with(|| Box::new([0; PAGE_SIZE]));
+        page[addr as usize % PAGE_SIZE] = value;
+    }
+
+    /// Reads `size` bytes as an unsigned integer.
+    pub fn read(&self, addr: u32, size: u32) -> u32 {
+        (0..size).rev().fold(0, |acc, i| (acc << 8) | u32::from(self.read_u8(addr.wrapping_add(i))))
+    }
+
+    /// Writes the lower `size` bytes of an integer.
+    pub fn write(&mut self, addr: u32, size: u32, value: u32) {
+        for i in 0..size {
+            self.write_u8(addr.wrapping_add(i), (value >> (8 * i)) as u8);
+        }
+    }
+
+    /// Returns the data of the `MemRespWithAddr` to a `MemReq`, without performing it.
+    ///
+    /// Loads are sign- or zero-extended following `typ`, and stores return zero.
+    pub fn respond(&self, req: &Value) -> VirgenResult<u32> {
+        let access = Access::new(req)?;
+        if access.store {
+            return Ok(0);
+        }
+
+        let value = self.read(access.addr, access.size);
+        let shift = 32 - 8 * access.size;
+        Ok(if access.signed { (((value << shift) as i32) >> shift) as u32 } else { value })
+    }
+
+    /// Performs a `MemReq`, and returns the data of the `MemRespWithAddr`. See [`Memory::respond`].
+    ///
+    /// Stores write the lower bytes of `data` following `typ`.
+    pub fn access(&mut self, req: &Value) -> VirgenResult<u32> {
+        let access = Access::new(req)?;
+        if access.store {
+            self.write(access.addr, access.size, access.data);
+        }
+        self.respond(req)
+    }
+}
+
+/// Decoded `MemReq`.
+#[derive(Debug, Clone, Copy)]
+struct Access {
+    addr: u32,
+    data: u32,
+    store: bool,
+
+    /// Number of bytes.
+    size: u32,
+
+    /// Whether a load is sign-extended.
+    signed: bool,
+}
+
+impl Access {
+    fn new(req: &Value) -> VirgenResult<Self> {
+        let invalid = || VirgenError::SimulationError { msg: format!("`{}` is not a valid `MemReq`", req) };
+        let field = |name: &str| req.field(name).ok_or_else(invalid);
+
+        let (size, signed) = match field("typ")?.variant() {
+            Some("B") => (1, true),
+            Some("H") => (2, true),
+            Some("W" | "D" | "WU") => (4, false),
+            Some("BU") => (1, false),
+            Some("HU") => (2, false),
+            _ => return Err(invalid()),
+        };
+
+        Ok(Self {
+            addr: field("addr")?.as_u64().ok_or_else(invalid)? as u32,
+            data: field("data")?.as_u64().ok_or_else(invalid)? as u32,
+            store: field("fcn")?.variant() == Some("Store"),
+            size,
+            signed,
+        })
+    }
+}
+
+/// Result of a program.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Exit {
+    /// The program passed.
+    Pass,
+
+    /// The program failed with the code, e.g., the number of the failed test case of riscv-tests.
+    Fail(u32),
+}
+
+/// Retired instruction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Commit {
+    /// Cycle of the retirement.
+    pub cycle: u64,
+
+    /// PC.
+    pub pc: u32,
+
+    /// Instruction.
+    pub inst: u32,
+
+    /// Written register and data, if any.
+    pub write: Option<(u32, u32)>,
+
+    /// Effects on the CSRs, if the instruction is a CSR instruction or raises an exception.
+    pub csr: Option<CsrCommit>,
+}
+
+/// Effects of a retired instruction on the CSRs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CsrCommit {
+    /// Written CSR and data, if any. The data is before the unimplemented fields are masked.
+    pub write: Option<(u32, u32)>,
+
+    /// `mstatus` after the instruction.
+    pub mstatus: u32,
+
+    /// `mepc` after the instruction.
+    pub mepc: u32,
+
+    /// `mcause` after the instruction.
+    pub mcause: u32,
+}
+
+impl fmt::Display for Commit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:>8} 0x{:08x} (0x{:08x})", self.cycle, self.pc, self.inst)?;
+        if let Some((rd, data)) = self.write {
+            write!(f, " x{:<2} 0x{:08x}", rd, data)?;
+        }
+        if let Some(csr) = self.csr {
+            if let Some((addr, data)) = csr.write {
+                write!(f, " c{:03x} 0x{:08x}", addr, data)?;
+            }
+            write!(f, " mstatus=0x{:08x} mepc=0x{:08x} mcause=0x{:08x}", csr.mstatus, csr.mepc, csr.mcause)?;
+        }
+        Ok(())
+    }
+}
+
+impl Commit {
+    /// Parses the `$display` of the writeback stage, such as `retire=[1] pc=[80000000] inst=[00000297]
+    /// write=[r 5=80000000]`. The fields may be padded with spaces.
+    fn parse(cycle: u64, display: &str) -> Option<Self> {
+        let field = |name: &str| {
+            let (_, rest) = display.split_once(&format!("{}=[", name))?;
+            rest.split_once(']').map(|(value, _)| value.trim())
+        };
+        let hex = |value: &str| u32::from_str_radix(value.trim(), 16).ok();
+
+        if field("retire")? != "1" {
+            return None;
+        }
+        let write = match field("write") {
+            Some(write) => {
+                let (rd, data) = write.strip_prefix('r')?.split_once('=')?;
+                Some((rd.trim().parse().ok()?, hex(data)?))
+            }
+            None => None,
+        };
+
+        Some(Self { cycle, pc: hex(field("pc")?)?, inst: hex(field("inst")?)?, write, csr: None })
+    }
+}
+
+impl CsrCommit {
+    /// Parses the `$display` of the writeback stage following a retired CSR instruction, such as `csr=[300=8]
+    /// mstatus=[1808] mepc=[0] mcause=[0]`, or `csr=[]` if no CSR is written.
+    fn parse(display: &str) -> Option<Self> {
+        let field = |name: &str| {
+            let (_, rest) = display.split_once(&format!("{}=[", name))?;
+            rest.split_once(']').map(|(value, _)| value.trim())
+        };
+        let hex = |value: &str| u32::from_str_radix(value.trim(), 16).ok();
+
+        let write = match field("csr")? {
+            "" => None,
+            write => {
+                let (addr, data) = write.split_once('=')?;
+                Some((hex(addr)?, hex(data)?))
+            }
+        };
+
+        Some(Self {
+            write,
+            mstatus: hex(field("mstatus")?)?,
+            mepc: hex(field("mepc")?)?,
+            mcause: hex(field("mcause")?)?,
+        })
+    }
+}
+
+/// Memory port of the core.
+#[derive(Debug, Clone)]
+struct Port {
+    /// Request channel, which is an egress channel of the core.
+    req: Channel,
+
+    /// Response channel, which is an ingress channel of the core.
+    resp: Channel,
+}
+
+/// Testbench of `cpu::core` with a memory. See the module documentation.
+#[derive(Debug, Clone)]
+pub struct CoreTestbench {
+    tb: Testbench,
+    memory: Memory,
+
+    /// `imem` and `dmem` ports.
+    ports: [Port; 2],
+
+    tohost: Option<u32>,
+    commits: Vec<Commit>,
+    exit: Option<Exit>,
+}
+
+impl CoreTestbench {
+    /// Compiles `core` with the system tasks, which print the retired instructions, and creates a testbench for it.
+    pub fn new() -> VirgenResult<Self> {
+        let tb = Testbench::from_compiled(&compile("core", true)?)?;
+        let port = |i: usize| -> VirgenResult<Port> {
+            Ok(Port {
+                req: tb.channel(&format!("egress.input.{}.input.0", i))?,
+                resp: tb.channel(&format!("ingress.input.{}.output", i))?,
+            })
+        };
+        let ports = [port(0)?, port(1)?];
+
+        Ok(Self { tb, memory: Memory::new(), ports, tohost: None, commits: vec![], exit: None })
+    }
+
+    /// Loads a program into the memory, and watches the stores to its `tohost` symbol.
+    pub fn load(&mut self, elf: &Elf) {
+        self.memory.load_elf(elf);
+        self.tohost = elf.symbol("tohost");
+    }
+
+    /// Returns the underlying testbench, e.g., to record the waveform.
+    pub fn testbench(&mut self) -> &mut Testbench {
+        &mut self.tb
+    }
+
+    /// Returns the memory.
+    pub fn memory(&mut self) -> &mut Memory {
+        &mut self.memory
+    }
+
+    /// Returns the instructions retired so far.
+    pub fn commits(&self) -> &[Commit] {
+        &self.commits
+    }
+
+    /// Writes the commit log, one retired instruction per line.
+    pub fn write_commit_log(&self, path: impl AsRef<Path>) -> VirgenResult<()> {
+        let log = self.commits.iter().fold(String::new(), |mut log, commit| {
+            writeln!(log, "{}", commit).unwrap();
+            log
+        });
+        std::fs::write(path, log).map_err(|err| VirgenError::Fs { err })
+    }
+
+    /// Returns the result of the program, if it has finished.
+    pub fn exit(&self) -> Option<Exit> {
+        self.exit
+    }
+
+    /// Simulates a cycle.
+    pub fn step(&mut self) -> VirgenResult<()> {
+        // Drives the responses and the readies until they settle, since a response depends on the request in the same
+        // cycle.
+        let mut driven = vec![None; self.ports.len()];
+        for _ in 0..SETTLE_LIMIT {
+            let mut next = vec![];
+            for port in &self.ports {
+                let req = self.tb.payload(port.req)?;
+                let resp = match &req {
+                    Some(req) => Some(resp_of(req, self.memory.respond(req)?)),
+                    None => None,
+                };
+                self.tb.set_payload(port.resp, resp.as_ref())?;
+
+                // A request is accepted together with its response.
+                let ready = is_ready(&self.tb.resolver(port.resp)?);
+                let resolver = self.tb.resolver(port.req)?;
+                self.tb.set_resolver(port.req, &with_ready(resolver, ready))?;
+
+                next.push(Some((req, ready)));
+            }
+
+            if next == driven {
+                break;
+            }
+            driven = next;
+        }
+
+        // Performs the accesses transferred in this cycle.
+        for (req, ready) in driven.into_iter().flatten() {
+            let Some(req) = req.filter(|_| ready) else {
+                continue;
+            };
+
+            let _unused = self.memory.access(&req)?;
+            self.check_tohost(&req)?;
+        }
+
+        self.tb.simulator().step()?;
+        // The effects on the CSRs belong to the instruction retired in the same cycle.
+        let displays = self.tb.simulator().take_displays();
+        let checked = self.commits.len();
+        self.commits.extend(displays.iter().filter_map(|(cycle, display)| Commit::parse(*cycle, display)));
+        for (cycle, display) in &displays {
+            let Some(csr) = CsrCommit::parse(display) else {
+                continue;
+            };
+            let commit = self.commits[checked..].iter_mut().find(|commit| commit.cycle == *cycle);
+            commit
+                .ok_or_else(|| VirgenError::SimulationError {
+                    msg: format!("no instruction retired with `{}` at cycle {}", display, cycle),
+                })?
+                .csr = Some(csr);
+        }
+        Ok(())
+    }
+
+    /// Simulates until the program finishes, for at most `max_cycles` cycles.
+    pub fn run(&mut self, max_cycles: usize) -> VirgenResult<Exit> {
+        for _ in 0..max_cycles {
+            if let Some(exit) = self.exit {
+                return Ok(exit);
+            }
+            self.step()?;
+        }
+
+        self.exit.ok_or_else(|| VirgenError::SimulationError {
+            msg: format!("the program did not finish in {} cycles", max_cycles),
+        })
+    }
+
+    /// Simulates until the program finishes as [`CoreTestbench::run`], comparing each retired instruction with a
+    /// golden model, e.g., `cpu::iss::Iss` of `hazardflow-designs`.
+    ///
+    /// `model` executes the next instruction and returns its commit, whose `cycle` is ignored. Besides the PC, the
+    /// instruction, and the written register, the written CSR and the resulting `mstatus`, `mepc` and `mcause` of
+    /// CSR instructions, traps and `mret` are compared. Returns an error with the preceding commits on the first
+    /// divergence.
+    pub fn run_lockstep(&mut self, max_cycles: usize, mut model: impl FnMut() -> Commit) -> VirgenResult<Exit> {
+        let mut checked = self.commits.len();
+        for _ in 0..max_cycles {
+            if let Some(exit) = self.exit {
+                return Ok(exit);
+            }
+            self.step()?;
+
+            for (index, actual) in self.commits.iter().enumerate().skip(checked) {
+                let expected = Commit { cycle: actual.cycle, ..model() };
+                if expected != *actual {
+                    let preceding = &self.commits[index.saturating_sub(LOCKSTEP_CONTEXT)..index];
+                    return Err(VirgenError::SimulationError {
+                        msg: format!(
+                            "diverged from the model at commit {}\n- {} (model)\n+ {} (core)\npreceding commits:\n{}",
+                            index,
+                            expected,
+                            actual,
+                            preceding.iter().join("\n")
+                        ),
+                    });
+                }
+            }
+            checked = self.commits.len();
+        }
+
+        self.exit.ok_or_else(|| VirgenError::SimulationError {
+            msg: format!("the program did not finish in {} cycles", max_cycles),
+        })
+    }
+
+    /// Checks whether a request is a store to `tohost` finishing the program.
+    fn check_tohost(&mut self, req: &Value) -> VirgenResult<()> {
+        let (Some(tohost), Some("Store")) = (self.tohost, req.field("fcn").and_then(Value::variant)) else {
+            return Ok(());
+        };
+        if req.field("addr").and_then(Value::as_u64) != Some(u64::from(tohost)) {
+            return Ok(());
+        }
+
+        match req.field("data").and_then(Value::as_u64).unwrap_or_default() as u32 {
+            0 => {}
+            1 => self.exit = Some(Exit::Pass),
+            data if data & 1 == 1 => self.exit = Some(Exit::Fail(data >> 1)),
+            data => {
+                return Err(VirgenError::SimulationError {
+                    msg: format!("unsupported system call at 0x{:08x} through `tohost`", data),
+                })
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Returns the `MemRespWithAddr` to a request.
+fn resp_of(req: &Value, data: u32) -> Value {
+    let addr = req.field("addr").cloned().unwrap_or_else(|| Value::from(0u32));
+    Value::Struct(Some("MemRespWithAddr".to_string()), vec![
+        ("data".to_string(), data.into()),
+        ("addr".to_string(), addr),
+    ])
+}
@@ -0,0 +1,184 @@
+//! Scoreboard comparing the transfers of a module against a reference model.
+//!
+//! The expected payloads are computed by a pure Rust function from the ingress transfers, and matched with the egress
+//! transfers either in order or by a key, e.g., the address of a `MemRespWithAddr` for responses which may be
+//! reordered. Payloads with the same key are matched in order.
+
+use std::collections::VecDeque;
+use std::fmt;
+
+use itertools::Itertools;
+
+use crate::compiler::debug_info::Value;
+use crate::compiler::error::{VirgenError, VirgenResult};
+
+/// How the expected and actual payloads are matched.
+#[derive(Debug, Clone, Copy)]
+pub enum Matching {
+    /// The `i`-th actual payload is matched with the `i`-th expected payload.
+    InOrder,
+
+    /// Payloads are matched with the first unmatched payload with the same key.
+    ByKey(fn(&Value) -> Value),
+}
+
+/// Payload with the cycle it was transferred.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Transaction {
+    /// Cycle of the transfer. For expected payloads, the cycle of the ingress transfer it was computed from.
+    pub cycle: u64,
+
+    /// Payload.
+    pub payload: Value,
+}
+
+/// Mismatch between the expected and actual payloads.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Mismatch {
+    /// The actual payload differs from the matched expected payload.
+    Differs {
+        /// Expected payload.
+        expected: Transaction,
+
+        /// Actual payload.
+        actual: Transaction,
+    },
+
+    /// No actual payload is matched with the expected payload.
+    Missing {
+        /// Expected payload.
+        expected: Transaction,
+    },
+
+    /// No expected payload is matched with the actual payload.
+    Unexpected {
+        /// Actual payload.
+        actual: Transaction,
+    },
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Differs { expected, actual } => write!(
+                f,
+                "- {} (expected from cycle {})\n+ {} (actual at cycle {})",
+                expected.payload, expected.cycle, actual.payload, actual.cycle
+            ),
+            Self::Missing { expected } => {
+                write!(f, "- {} (expected from cycle {})\n+ (missing)", expected.payload, expected.cycle)
+            }
+            Self::Unexpected { actual } => {
+                write!(f, "- (not expected)\n+ {} (actual at cycle {})", actual.payload, actual.cycle)
+            }
+        }
+    }
+}
+
+/// Scoreboard of a channel.
+#[derive(Debug, Clone)]
+pub struct Scoreboard {
+    name: String,
+    matching: Matching,
+    expected: Vec<Transaction>,
+    actual: Vec<Transaction>,
+}
+
+impl Scoreboard {
+    /// Creates a new scoreboard. `name` is shown in the report.
+    pub fn new(name: impl Into<String>, matching: Matching) -> Self {
+        Self { name: name.into(), matching, expected: vec![], actual: vec![] }
+    }
+
+    /// Adds an expected payload computed from an ingress transfer at `cycle`.
+    pub fn expect(&mut self, cycle: u64, payload: impl Into<Value>) {
+        self.expected.push(Transaction { cycle, payload: payload.into() });
+    }
+
+    /// Adds an actual payload transferred at `cycle`.
+    pub fn observe(&mut self, cycle: u64, payload: impl Into<Value>) {
+        self.actual.push(Transaction { cycle, payload: payload.into() });
+    }
+
+    /// Returns the expected payloads.
+    pub fn expected(&self) -> &[Transaction] {
+        &self.expected
+    }
+
+    /// Returns the actual payloads.
+    pub fn actual(&self) -> &[Transaction] {
+        &self.actual
+    }
+
+    /// Returns the mismatches, ordered by the cycles.
+    pub fn mismatches(&self) -> Vec<Mismatch> {
+        let pairs: Vec<(Option<&Transaction>, Option<&Transaction>)> = match self.matching {
+            Matching::InOrder => self
+                .expected
+                .iter()
+                .map(Some)
+                .pad_using(self.actual.len(), |_| None)
+                .zip(self.actual.iter().map(Some).pad_using(self.expected.len(), |_| None))
+                .collect(),
+            Matching::ByKey(key) => {
+                let mut unmatched = self.actual.iter().collect::<VecDeque<_>>();
+                let mut pairs = self
+                    .expected
+                    .iter()
+                    .map(|expected| {
+                        let expected_key = key(&expected.payload);
+                        let actual = unmatched
+                            .iter()
+                            .position(|actual| key(&actual.payload) == expected_key)
+                            .and_then(|index| unmatched.remove(index));
+                        (Some(expected), actual)
+                    })
+                    .collect::<Vec<_>>();
+                pairs.extend(unmatched.into_iter().map(|actual| (None, Some(actual))));
+                pairs
+            }
+        };
+
+        pairs
+            .into_iter()
+            .filter_map(|pair| match pair {
+                (Some(expected), Some(actual)) if expected.payload == actual.payload => None,
+                (Some(expected), Some(actual)) => {
+                    Some(Mismatch::Differs { expected: expected.clone(), actual: actual.clone() })
+                }
+                (Some(expected), None) => Some(Mismatch::Missing { expected: expected.clone() }),
+                (None, Some(actual)) => Some(Mismatch::Unexpected { actual: actual.clone() }),
+                (None, None) => unreachable!(),
+            })
+            .sorted_by_key(|mismatch| match mismatch {
+                Mismatch::Differs { actual, .. } | Mismatch::Unexpected { actual } => actual.cycle,
+                Mismatch::Missing { expected } => expected.cycle,
+            })
+            .collect()
+    }
+
+    /// Returns the report of the mismatches, or `None` if there is no mismatch.
+    pub fn report(&self) -> Option<String> {
+        let mismatches = self.mismatches();
+        if mismatches.is_empty() {
+            return None;
+        }
+
+        Some(format!(
+            "scoreboard `{}`: {} mismatches in {} expected and {} actual payloads\n{}",
+            self.name,
+            mismatches.len(),
+            self.expected.len(),
+            self.actual.len(),
+            mismatches.iter().join("\n")
+        ))
+    }
+
+    /// Returns an error with the report if there is a mismatch.
+    pub fn check(&self) -> VirgenResult<()> {
+        match self.report() {
+            Some(msg) => Err(VirgenError::SimulationError { msg }),
+            None => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,123 @@
+//! Constrained-random stimulus.
+//!
+//! Every random choice of a testbench is drawn from its [`Rng`] in a fixed order, so a test is reproduced by its seed.
+
+use std::ops::RangeInclusive;
+
+use crate::compiler::debug_info::{TypeInfo, Value};
+use crate::vir::sim::Bits;
+
+/// Random number generator (xorshift64*), which is deterministic for a seed.
+#[derive(Debug, Clone)]
+pub struct Rng(u64);
+
+impl Rng {
+    /// Creates a new generator.
+    pub fn new(seed: u64) -> Self {
+        // The state should not be zero.
+        Self((seed ^ 0x9e37_79b9_7f4a_7c15).max(1))
+    }
+
+    /// Returns a random number.
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    /// Returns a number in `0..n`.
+    pub fn below(&mut self, n: u64) -> u64 {
+        self.next_u64() % n.max(1)
+    }
+
+    /// Returns a number in `range`.
+    pub fn range(&mut self, range: RangeInclusive<u32>) -> u32 {
+        let (lo, hi) = range.into_inner();
+        if hi <= lo {
+            return lo;
+        }
+        lo + self.below(u64::from(hi - lo) + 1) as u32
+    }
+
+    /// Returns `true` with the given probability in percent.
+    pub fn chance(&mut self, percent: u8) -> bool {
+        self.below(100) < u64::from(percent)
+    }
+
+    /// Returns a random value of the type. The discriminants of enums are always valid.
+    pub fn value(&mut self, ty: &TypeInfo) -> Value {
+        match ty {
+            TypeInfo::Bits { ty, width, signed } => {
+                let words = (0..(width + 63) / 64).map(|_| self.next_u64()).collect::<Vec<_>>();
+                let bits = Bits::from_words(&words, *width);
+                match (ty.as_str(), signed) {
+                    ("bool", _) => Value::Bool(!bits.is_zero()),
+                    (_, true) => Value::Int(bits),
+                    (_, false) => Value::Uint(bits),
+                }
+            }
+            TypeInfo::Array { elt, len, .. } => Value::Array((0..*len).map(|_| self.value(elt)).collect()),
+            TypeInfo::Struct { name, fields, .. } => Value::Struct(
+                name.clone(),
+                fields.iter().map(|field| (field.name.clone(), self.value(&field.ty))).collect(),
+            ),
+            TypeInfo::Enum { variants, .. } => {
+                let variant = &variants[self.below(variants.len() as u64) as usize];
+                Value::Variant(
+                    variant.name.clone(),
+                    variant.fields.iter().map(|field| (field.name.clone(), self.value(&field.ty))).collect(),
+                )
+            }
+        }
+    }
+}
+
+/// Pattern of a condition in each cycle, such as whether a payload is valid or a resolver is ready.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Pattern {
+    /// Always holds.
+    Always,
+
+    /// Never holds.
+    Never,
+
+    /// Holds with the given probability in percent, independently in each cycle.
+    Random(u8),
+
+    /// Alternates between bursts where it holds and gaps where it does not, starting with a burst. The lengths of
+    /// the bursts and the gaps are drawn from the ranges, and are at least one cycle.
+    Bursts {
+        /// Lengths of the bursts.
+        on: RangeInclusive<u32>,
+
+        /// Lengths of the gaps.
+        off: RangeInclusive<u32>,
+    },
+}
+
+/// Current burst or gap of a [`Pattern::Bursts`].
+#[derive(Debug, Clone, Default)]
+pub(super) struct PatternState {
+    on: bool,
+    remaining: u32,
+}
+
+impl PatternState {
+    /// Returns whether the pattern holds in the next cycle.
+    pub(super) fn next(&mut self, pattern: &Pattern, rng: &mut Rng) -> bool {
+        match pattern {
+            Pattern::Always => true,
+            Pattern::Never => false,
+            Pattern::Random(percent) => rng.chance(*percent),
+            Pattern::Bursts { on, off } => {
+                if self.remaining == 0 {
+                    self.on = !self.on;
+                    self.remaining = rng.range(if self.on { on.clone() } else { off.clone() }).max(1);
+                }
+                self.remaining -= 1;
+                self.on
+            }
+        }
+    }
+}
@@ -98,6 +98,8 @@ impl HazardFlowAttr {
             "shr" => IntMagic::Shr,
             "not" => IntMagic::Not,
             "mul" => IntMagic::Mul,
+            "neg" => IntMagic::Neg,
+            "sint" => IntMagic::Sint,
             _ => panic!("Invalid Magic, register it. {:?}", s),
         };
 
@@ -260,6 +262,12 @@ pub enum IntMagic {
 
     /// Mult
     Mul,
+
+    /// Neg
+    Neg,
+
+    /// Signed integer type, represented as signed bits
+    Sint,
 }
 
 impl IntMagic {
@@ -277,8 +285,10 @@ impl IntMagic {
             IntMagic::Gt => BinaryOp::Greater,
             IntMagic::Ge => BinaryOp::GreaterEq,
             IntMagic::Mul => BinaryOp::Mul,
-            IntMagic::Not => todo!(),
-            IntMagic::Convert => todo!(),
+            IntMagic::Not | IntMagic::Neg => {
+                unreachable!("`{:?}` is a unary operation, built on its own operand", self)
+            }
+            IntMagic::Convert | IntMagic::Sint => unreachable!("`{:?}` is a conversion, built as a cast", self),
         }
     }
 }
@@ -330,6 +340,8 @@ pub fn get_hazardflow_attribute(tcx: TyCtxt<'_>, hir_id: hir::HirId) -> Option<H
                     if segments.len() >= 2 && segments[0].ident.as_str() == "hazardflow" {
                         match segments[1].ident.as_str() {
                             "synthesize" => Some(HazardFlowAttr::Synthesize),
+                            // Read by `get_ffi_source`.
+                            "ffi_source" => None,
                             "magic" => match args {
                                 rustc_ast::AttrArgs::Delimited(inner) => {
                                     let magic_name = inner.tokens.trees().next().unwrap();
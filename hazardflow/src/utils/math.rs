@@ -39,6 +39,11 @@ pub fn usize_to_bitvec(n: usize, value: usize) -> Vec<bool> {
     (0..n).map(|i| if i >= size_of_usize * 8 { false } else { (value & (1 << i)) != 0 }).collect::<Vec<_>>()
 }
 
+/// Returns the lower `n` bits of an integer.
+pub fn u128_to_bitvec(n: usize, value: u128) -> Vec<bool> {
+    (0..n).map(|i| i < 128 && (value >> i) & 1 != 0).collect()
+}
+
 /// Returns bit-represented value of an integer.
 // TODO: Make this function `const fn`.
 pub fn usize_to_bits<const N: usize>(value: usize) -> [bool; N] {
@@ -15,7 +15,7 @@ use rustc_middle::ty::{
     Const, EarlyBinder, GenericArg, GenericPredicates, Instance, InstantiatedPredicates, ParamEnv, Ty, TyCtxt,
     UnevaluatedConst, ValTree, VariantDef,
 };
-use rustc_span::Span;
+use rustc_span::{Span, Symbol};
 use rustc_target::abi::{FieldIdx, VariantIdx};
 use rustc_trait_selection::traits::{ObligationCause, ObligationCtxt};
 
@@ -45,6 +45,13 @@ pub fn copy_thir_before_steal(def: LocalDefId, thir: Thir<'_>) {
     }
 }
 
+/// Clears the cache for stolen `Thir`, which refers to the `TyCtxt` of the previous compilation.
+pub fn clear_stolen_thirs() {
+    unsafe {
+        STOLEN_THIRS.clear();
+    }
+}
+
 /// Retreive `Thir` given `LocalDefId
 pub fn thir_body<'tcx>(tcx: TyCtxt<'tcx>, id: LocalDefId) -> &rustc_data_structures::steal::Steal<Thir<'tcx>> {
     assert!(!tcx.is_constructor(id.to_def_id()));
@@ -91,6 +98,7 @@ pub fn skip_exprs<'tcx>(body: &Thir<'tcx>, expr_id: ExprId) -> ExprId {
         | ExprKind::Match { .. }
         | ExprKind::Literal { .. }
         | ExprKind::If { .. }
+        | ExprKind::Loop { .. }
         | ExprKind::Binary { .. }
         | ExprKind::Unary { .. }
         | ExprKind::Repeat { .. }
@@ -128,7 +136,7 @@ pub fn skip_exprs<'tcx>(body: &Thir<'tcx>, expr_id: ExprId) -> ExprId {
 
 /// Pattern Accessor Node
 #[derive(Debug)]
-pub enum PatAccessNode {
+pub enum PatAccessNode<'tcx> {
     /// Field Access
     Field {
         /// Field Index.
@@ -151,21 +159,42 @@ pub enum PatAccessNode {
     },
 
     /// Array Access
-    Index(usize),
+    Index {
+        /// Element Index
+        idx: usize,
+
+        /// Array Length
+        len: usize,
+    },
+
+    /// Subarray Access, e.g., `rest` of `[first, rest @ ..]`
+    Subslice {
+        /// Index of the first element
+        from: usize,
+
+        /// Subarray Length
+        size: usize,
+
+        /// Array Length
+        len: usize,
+    },
+
+    /// Alternative of an or-pattern, which should match for the access
+    Alternative(Box<thir::Pat<'tcx>>),
 }
 
 /// Pattern Accessor
 #[derive(Debug)]
-pub struct PatAccessor {
-    inner: VecDeque<PatAccessNode>,
+pub struct PatAccessor<'tcx> {
+    inner: VecDeque<PatAccessNode<'tcx>>,
 }
 
-impl PatAccessor {
+impl<'tcx> PatAccessor<'tcx> {
     fn empty() -> Self {
         Self { inner: VecDeque::new() }
     }
 
-    fn prepend(mut self, node: PatAccessNode) -> Self {
+    fn prepend(mut self, node: PatAccessNode<'tcx>) -> Self {
         self.inner.push_front(node);
         self
     }
@@ -181,20 +210,27 @@ impl PatAccessor {
     }
 
     /// Iterate on the from innermost access
-    pub fn iter(&self) -> impl Iterator<Item = &PatAccessNode> {
+    pub fn iter(&self) -> impl Iterator<Item = &PatAccessNode<'tcx>> {
         self.inner.iter()
     }
 }
 
-fn find_localvar_from_pat(tcx: TyCtxt<'_>, pat: &thir::Pat<'_>, local_var_id: thir::LocalVarId) -> Vec<PatAccessor> {
+fn find_localvar_from_pat<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    pat: &thir::Pat<'tcx>,
+    local_var_id: thir::LocalVarId,
+) -> Vec<PatAccessor<'tcx>> {
     let mut accessors = vec![];
 
     match &pat.kind {
         thir::PatKind::Binding { var, subpattern, .. } => {
-            assert!(subpattern.is_none());
             if *var == local_var_id {
                 accessors.push(PatAccessor::empty())
             }
+            // `var @ subpattern`
+            if let Some(subpattern) = subpattern {
+                accessors.append(&mut find_localvar_from_pat(tcx, subpattern.as_ref(), local_var_id))
+            }
         }
         thir::PatKind::AscribeUserType { subpattern, .. } => {
             accessors.append(&mut find_localvar_from_pat(tcx, subpattern.as_ref(), local_var_id))
@@ -254,19 +290,30 @@ fn find_localvar_from_pat(tcx: TyCtxt<'_>, pat: &thir::Pat<'_>, local_var_id: th
         thir::PatKind::Range(_) => todo!(),
         thir::PatKind::Slice { .. } => todo!(),
         thir::PatKind::Array { prefix, slice, suffix } => {
-            assert!(slice.is_none());
-            assert!(suffix.is_empty());
-            for (i, pat) in prefix.iter().enumerate() {
+            let rustc_type_ir::TyKind::Array(_, len) = pat.ty.kind() else { panic!() };
+            let len = len.eval_target_usize(tcx, ParamEnv::empty()) as usize;
+
+            let elts = prefix
+                .iter()
+                .enumerate()
+                .chain(suffix.iter().enumerate().map(|(i, pat)| (len - suffix.len() + i, pat)));
+            for (idx, pat) in elts {
                 for accessor in find_localvar_from_pat(tcx, pat.as_ref(), local_var_id) {
-                    let accessor = accessor.prepend(PatAccessNode::Index(i));
-                    accessors.push(accessor)
+                    accessors.push(accessor.prepend(PatAccessNode::Index { idx, len }))
+                }
+            }
+
+            if let Some(slice) = slice {
+                let (from, size) = (prefix.len(), len - prefix.len() - suffix.len());
+                for accessor in find_localvar_from_pat(tcx, slice.as_ref(), local_var_id) {
+                    accessors.push(accessor.prepend(PatAccessNode::Subslice { from, size, len }))
                 }
             }
         }
         thir::PatKind::Or { pats } => {
             for pat in pats.iter() {
                 for accessor in find_localvar_from_pat(tcx, pat, local_var_id) {
-                    accessors.push(accessor)
+                    accessors.push(accessor.prepend(PatAccessNode::Alternative(pat.clone())))
                 }
             }
         }
@@ -283,7 +330,7 @@ fn find_localvar_from_stmt<'tcx>(
     tcx: TyCtxt<'tcx>,
     stmt: &thir::Stmt<'tcx>,
     local_var_id: thir::LocalVarId,
-) -> Vec<(thir::Pat<'tcx>, PatAccessor, ExprId)> {
+) -> Vec<(thir::Pat<'tcx>, PatAccessor<'tcx>, ExprId)> {
     match &stmt.kind {
         thir::StmtKind::Let { pattern, initializer, .. } => find_localvar_from_pat(tcx, pattern, local_var_id)
             .into_iter()
@@ -304,7 +351,7 @@ pub enum LocalVar<'tcx> {
         arg_idx: usize,
 
         /// Accessor
-        accessor: PatAccessor,
+        accessor: PatAccessor<'tcx>,
 
         /// Pattern
         pat: thir::Pat<'tcx>,
@@ -319,7 +366,7 @@ pub enum LocalVar<'tcx> {
         expr_id: ExprId,
 
         /// Accessor
-        accessor: PatAccessor,
+        accessor: PatAccessor<'tcx>,
 
         /// Pattern
         pat: thir::Pat<'tcx>,
@@ -331,7 +378,7 @@ pub enum LocalVar<'tcx> {
         expr_id: ExprId,
 
         /// Accessor
-        accessor: PatAccessor,
+        accessor: PatAccessor<'tcx>,
 
         /// Pattern
         pat: thir::Pat<'tcx>,
@@ -472,12 +519,10 @@ fn is_closure_call(fun_expr: &thir::Expr<'_>, tcx: TyCtxt<'_>) -> bool {
     }
 }
 
-/// Unwrap `Scalar` type into `usize`
-pub fn scalar_to_usize(scalar: Scalar) -> Option<usize> {
+/// Unwrap `Scalar` type into `u128`
+pub fn scalar_to_u128(scalar: Scalar) -> Option<u128> {
     match scalar {
-        rustc_const_eval::interpret::Scalar::Int(scalar_int) => {
-            scalar.to_bits(scalar_int.size()).ok().map(|value| value.try_into().unwrap())
-        }
+        rustc_const_eval::interpret::Scalar::Int(scalar_int) => scalar.to_bits(scalar_int.size()).ok(),
         rustc_const_eval::interpret::Scalar::Ptr(..) => todo!(),
     }
 }
@@ -602,6 +647,18 @@ pub fn get_span(tcx: TyCtxt<'_>, id: DefId) -> Span {
     tcx.hir().span_if_local(id).unwrap()
 }
 
+/// Returns the Verilog source file of the FFI module, which is given by the `#[ffi_source(..)]` attribute of the
+/// function, e.g., `#[ffi_source("MeshWrapper.v")]`.
+pub fn get_ffi_source(tcx: TyCtxt<'_>, id: DefId) -> Option<String> {
+    tcx.get_attrs_unchecked(id).iter().find_map(|attr| {
+        let path = [Symbol::intern("hazardflow"), Symbol::intern("ffi_source")];
+        if !attr.path_matches(&path) {
+            return None;
+        }
+        Some(attr.meta_item_list()?.first()?.lit()?.symbol.to_string())
+    })
+}
+
 /// Mapping from generic parameters to their bounds
 #[derive(Debug, Clone)]
 pub struct GenericMap<'tcx> {
@@ -0,0 +1,369 @@
+//! CIRCT core dialects output.
+//!
+//! [`gen_circt`] lowers modules into the `hw`, `comb` and `seq` dialects of [CIRCT](https://circt.llvm.org) in the
+//! MLIR textual format. Designs from other frontends, such as the Chisel wrappers of Gemmini lowered by `firtool`, can
+//! be linked with them and optimized together, and `circt-opt --export-verilog` prints them as Verilog.
+//!
+//! The modules are lowered from the same netlist as the [`Simulator`](super::sim::Simulator), so the widths and
+//! signedness of the operations follow the Verilog rules:
+//!
+//! - Every port, net, and register is an integer of its total width, named after it with `hw.wire`. An array is
+//!   flattened with the element 0 in the least significant bits. The `clk` port is a `!seq.clock`.
+//! - Continuous assignments and `always @*` blocks are executed symbolically into `comb` operations, where the branches
+//!   of `if` and `case` statements become `comb.mux`. `for` loops are unrolled, so their bounds should be constant.
+//! - Signals assigned in `always @(posedge clk)` blocks become `seq.compreg`, whose next value includes the
+//!   synchronous reset.
+//! - Module instantiations become `hw.instance`, so the instantiated modules should be lowered together.
+//! - `initial` blocks, `$display` and `$fatal` are dropped.
+
+use std::collections::HashMap;
+
+use itertools::Itertools;
+
+use super::lower::{self, misc, Lower, Op, Predicate};
+use super::sim::netlist::*;
+use super::sim::Bits;
+use super::*;
+use crate::compiler::error::VirgenResult;
+use crate::utils::indent;
+
+const INDENT: usize = 2;
+
+/// Lowered value, where the nodes are SSA values. Constants are materialized with `hw.constant` when used as operands.
+type Value = lower::Value<String>;
+
+type Write = lower::Write<String>;
+
+type Writes = lower::Writes<String>;
+
+type State = lower::State<String>;
+
+/// Generates the modules in the CIRCT core dialects.
+///
+/// An `hw.instance` spells out the types of the ports of the instantiated module, so every instantiated module should
+/// also be in `modules`.
+pub fn gen_circt(modules: &[Module]) -> VirgenResult<String> {
+    let ports = modules.iter().map(|module| (module.name.as_str(), module.port_decls.as_slice())).collect();
+    let modules =
+        modules.iter().map(|module| Lowering::new(module, &ports)?.gen(module)).collect::<VirgenResult<Vec<_>>>()?;
+    Ok(modules.join("\n\n"))
+}
+
+/// Lowering of a module.
+struct Lowering<'a> {
+    netlist: Netlist,
+
+    /// Port declarations of the given modules, which type the operands and the results of `hw.instance`.
+    ports: &'a HashMap<&'a str, &'a [PortDeclaration]>,
+
+    /// The `clk` port.
+    clock: Option<SignalId>,
+
+    /// Operations of the body.
+    ops: Vec<String>,
+
+    /// Number of the next SSA value.
+    next: usize,
+
+    /// Materialized constants.
+    consts: HashMap<Bits, String>,
+
+    /// `clk` as an `i1`, if it is read.
+    clock_value: Option<Value>,
+}
+
+fn is_clock(port_decl: &PortDeclaration) -> bool {
+    matches!(port_decl, PortDeclaration::Input(1, name) if name == "clk")
+}
+
+impl<'a> Lowering<'a> {
+    fn new(module: &Module, ports: &'a HashMap<&'a str, &'a [PortDeclaration]>) -> VirgenResult<Self> {
+        let netlist = Netlist::with_instances(module)?;
+        let clock = module.port_decls.iter().find(|port_decl| is_clock(port_decl)).map(|_| netlist.ids["clk"]);
+        Ok(Self { netlist, ports, clock, ops: vec![], next: 0, consts: HashMap::new(), clock_value: None })
+    }
+
+    fn gen(mut self, module: &Module) -> VirgenResult<String> {
+        if !self.netlist.inits.is_empty() {
+            return Err(misc(format!("register initializers in `{}` are not supported", module.name)));
+        }
+
+        let mut comb_writes = vec![vec![]; self.netlist.signals.len()];
+        let mut seq_writes = vec![vec![]; self.netlist.signals.len()];
+
+        for process in std::mem::take(&mut self.netlist.comb) {
+            let mut state = State::comb();
+            self.exec(&process.stmts, &mut state)?;
+            for (id, write) in state.blocking {
+                comb_writes[id].push(write);
+            }
+        }
+
+        for stmts in std::mem::take(&mut self.netlist.seq) {
+            let mut state = State::seq();
+            self.exec(&stmts, &mut state)?;
+            let mut writes = state.blocking;
+            for (id, write) in state.nonblocking.unwrap() {
+                let merged = match writes.remove(&id) {
+                    Some(blocking) => self.overlay(&blocking, &write),
+                    None => write,
+                };
+                let _unused = writes.insert(id, merged);
+            }
+            for (id, write) in writes {
+                // Loop variables are not registers.
+                if self.netlist.signals[id].kind != SignalKind::Integer {
+                    seq_writes[id].push(write);
+                }
+            }
+        }
+
+        for inst in std::mem::take(&mut self.netlist.instances) {
+            self.instance(&inst, &mut comb_writes)?;
+        }
+
+        for (id, signal) in self.netlist.signals.clone().iter().enumerate() {
+            let width = signal.total_width();
+            if signal.kind == SignalKind::Input || width == 0 {
+                continue;
+            }
+
+            // Bits which are not assigned are zero.
+            let (value, _) = self.merge(&comb_writes[id], width);
+            if seq_writes[id].is_empty() {
+                let value = self.operand(&value);
+                self.ops.push(format!("%{} = hw.wire {} : i{}", signal.name, value, width));
+                continue;
+            }
+            if !comb_writes[id].is_empty() {
+                return Err(misc(format!("`{}` is assigned in both combinational and sequential blocks", signal.name)));
+            }
+            let Some(clock) = self.clock else {
+                return Err(misc(format!("`{}` is assigned in a sequential block without `clk`", signal.name)));
+            };
+
+            // Bits which are not assigned keep their values.
+            let (value, mask) = self.merge(&seq_writes[id], width);
+            let current = Value::Node(format!("%{}", signal.name), width);
+            let next = self.select(&mask, &value, &current);
+            let next = self.operand(&next);
+            self.ops.push(format!(
+                "%{} = seq.compreg {}, %{} : i{}",
+                signal.name, next, self.netlist.signals[clock].name, width
+            ));
+        }
+
+        let ports = module
+            .port_decls
+            .iter()
+            .map(|port_decl| match port_decl {
+                PortDeclaration::Input(..) if is_clock(port_decl) => "in %clk : !seq.clock".to_string(),
+                PortDeclaration::Input(width, name) => format!("in %{} : i{}", name, width),
+                PortDeclaration::Output(width, name) => format!("out {} : i{}", name, width),
+            })
+            .join(", ");
+        let outputs = module
+            .port_decls
+            .iter()
+            .filter_map(|port_decl| match port_decl {
+                PortDeclaration::Output(width, name) => Some((format!("%{}", name), format!("i{}", width))),
+                PortDeclaration::Input(..) => None,
+            })
+            .collect::<Vec<_>>();
+        let output = if outputs.is_empty() {
+            "hw.output".to_string()
+        } else {
+            format!(
+                "hw.output {} : {}",
+                outputs.iter().map(|(value, _)| value).join(", "),
+                outputs.iter().map(|(_, ty)| ty).join(", ")
+            )
+        };
+
+        self.ops.push(output);
+        Ok(format!("hw.module @{}({}) {{\n{}\n}}", module.name, ports, indent(self.ops.join("\n"), INDENT)))
+    }
+
+    /// Lowers a module instantiation, adding the writes of its outputs.
+    fn instance(&mut self, inst: &ModuleInstantiation, comb_writes: &mut [Vec<Write>]) -> VirgenResult<()> {
+        let port_decls = *self.ports.get(inst.module_name.as_str()).ok_or_else(|| {
+            misc(format!("module `{}` instantiated as `{}` is not given", inst.module_name, inst.inst_name))
+        })?;
+        let connections =
+            inst.port_connections.iter().map(|(port, expr)| (port.as_str(), expr)).collect::<HashMap<_, _>>();
+
+        let mut inputs = vec![];
+        let mut outputs = vec![];
+        for port_decl in port_decls {
+            match port_decl {
+                PortDeclaration::Input(width, name) => {
+                    let expr = connections.get(name.as_str()).map(|expr| self.netlist.expr(expr)).transpose()?;
+                    let (operand, ty) = match expr {
+                        Some(expr) if is_clock(port_decl) => (self.clock_operand(&expr)?, "!seq.clock".to_string()),
+                        Some(expr) => {
+                            let value = self.expr(&expr, (*width).max(expr.width), expr.signed, &Writes::new())?;
+                            let value = self.extract(&value, 0, *width);
+                            (self.operand(&value), format!("i{}", width))
+                        }
+                        None => (self.operand(&Value::zero(*width)), format!("i{}", width)),
+                    };
+                    inputs.push(format!("{}: {}: {}", name, operand, ty));
+                }
+                PortDeclaration::Output(width, name) => outputs.push((name, *width)),
+            }
+        }
+
+        let result = self.fresh();
+        let results = match outputs.len() {
+            0 => String::new(),
+            1 => format!("{} = ", result),
+            n => format!("{}:{} = ", result, n),
+        };
+        self.ops.push(format!(
+            "{}hw.instance \"{}\" @{}({}) -> ({})",
+            results,
+            inst.inst_name,
+            inst.module_name,
+            inputs.join(", "),
+            outputs.iter().map(|(name, width)| format!("{}: i{}", name, width)).join(", ")
+        ));
+
+        for (i, (name, width)) in outputs.iter().enumerate() {
+            let Some(expr) = connections.get(name.as_str()) else {
+                continue;
+            };
+            let lvalue = self.netlist.lvalue(expr)?;
+            let value =
+                Value::Node(if outputs.len() == 1 { result.clone() } else { format!("{}#{}", result, i) }, *width);
+            let value = self.resize(&value, lvalue.width(&self.netlist.signals), false);
+            let mut state = State::comb();
+            self.assign(&lvalue, value, &mut state)?;
+            for (id, write) in state.blocking {
+                comb_writes[id].push(write);
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the operand for a `clk` port of an instantiated module.
+    fn clock_operand(&mut self, expr: &Expr) -> VirgenResult<String> {
+        if let (ExprKind::Signal(id), Some(clock)) = (&expr.kind, self.clock) {
+            if *id == clock {
+                return Ok(format!("%{}", self.netlist.signals[clock].name));
+            }
+        }
+        let value = self.expr_self(expr, &Writes::new())?;
+        let value = self.truthy(&value);
+        let value = self.operand(&value);
+        Ok(self.emit(1, format!("seq.to_clock {}", value)).node())
+    }
+
+    fn fresh(&mut self) -> String {
+        self.next += 1;
+        format!("%{}", self.next - 1)
+    }
+
+    /// Adds an operation with a result of the given width.
+    fn emit(&mut self, width: usize, op: String) -> Value {
+        let name = self.fresh();
+        self.ops.push(format!("{} = {}", name, op));
+        Value::Node(name, width)
+    }
+
+    /// Returns the SSA value of a value, materializing constants.
+    fn operand(&mut self, value: &Value) -> String {
+        match value {
+            Value::Node(name, _) => name.clone(),
+            Value::Const(bits) => {
+                if let Some(name) = self.consts.get(bits) {
+                    return name.clone();
+                }
+                let constant = format!("hw.constant {} : i{}", bits.to_str_radix(10), bits.width());
+                let name = self.emit(bits.width(), constant).node();
+                let _unused = self.consts.insert(bits.clone(), name.clone());
+                name
+            }
+        }
+    }
+}
+
+impl Lower for Lowering<'_> {
+    type Node = String;
+
+    fn netlist(&self) -> &Netlist {
+        &self.netlist
+    }
+
+    fn signal(&mut self, id: SignalId) -> Value {
+        let signal = &self.netlist.signals[id];
+        if Some(id) != self.clock {
+            return Value::Node(format!("%{}", signal.name), signal.total_width());
+        }
+        if self.clock_value.is_none() {
+            let clock = format!("seq.from_clock %{}", signal.name);
+            self.clock_value = Some(self.emit(1, clock));
+        }
+        self.clock_value.clone().unwrap()
+    }
+
+    fn extract_node(&mut self, node: &String, from: usize, lo: usize, width: usize) -> Value {
+        self.emit(width, format!("comb.extract {} from {} : (i{}) -> i{}", node, lo, from, width))
+    }
+
+    fn concat_nodes(&mut self, parts: &[Value]) -> Value {
+        let width = parts.iter().map(|part| part.width()).sum();
+        let operands = parts.iter().map(|part| self.operand(part)).collect::<Vec<_>>();
+        let types = parts.iter().map(|part| format!("i{}", part.width())).join(", ");
+        self.emit(width, format!("comb.concat {} : {}", operands.join(", "), types))
+    }
+
+    fn replicate(&mut self, bit: &Value, count: usize) -> Value {
+        let bit = self.operand(bit);
+        self.emit(count, format!("comb.replicate {} : (i1) -> i{}", bit, count))
+    }
+
+    fn op_node(&mut self, op: Op, lhs: &Value, rhs: &Value) -> Value {
+        let op = match op {
+            Op::Add => "add",
+            Op::Sub => "sub",
+            Op::Mul => "mul",
+            Op::DivU => "divu",
+            Op::DivS => "divs",
+            Op::ModU => "modu",
+            Op::ModS => "mods",
+            Op::And => "and",
+            Op::Or => "or",
+            Op::Xor => "xor",
+            Op::Shl => "shl",
+            Op::ShrU => "shru",
+            Op::ShrS => "shrs",
+        };
+        let width = lhs.width();
+        let (l, r) = (self.operand(lhs), self.operand(rhs));
+        self.emit(width, format!("comb.{} {}, {} : i{}", op, l, r, width))
+    }
+
+    fn compare_node(&mut self, predicate: Predicate, lhs: &Value, rhs: &Value) -> Value {
+        let predicate = match predicate {
+            Predicate::Eq => "eq",
+            Predicate::Ne => "ne",
+            Predicate::Slt => "slt",
+            Predicate::Ult => "ult",
+            Predicate::Sgt => "sgt",
+            Predicate::Ugt => "ugt",
+            Predicate::Sle => "sle",
+            Predicate::Ule => "ule",
+            Predicate::Sge => "sge",
+            Predicate::Uge => "uge",
+        };
+        let (l, r) = (self.operand(lhs), self.operand(rhs));
+        self.emit(1, format!("comb.icmp {} {}, {} : i{}", predicate, l, r, lhs.width()))
+    }
+
+    fn mux_node(&mut self, cond: &Value, then_value: &Value, else_value: &Value) -> Value {
+        let (cond, then_operand, else_operand) =
+            (self.operand(cond), self.operand(then_value), self.operand(else_value));
+        let width = then_value.width();
+        self.emit(width, format!("comb.mux {}, {}, {} : i{}", cond, then_operand, else_operand, width))
+    }
+}
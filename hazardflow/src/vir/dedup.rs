@@ -0,0 +1,69 @@
+//! Deduplicates structurally identical modules.
+
+use std::collections::{BTreeMap, HashMap};
+
+use crate::vir::utils::*;
+use crate::vir::*;
+
+/// Merges the structurally identical modules of the designs under a canonical name, which is the smallest name among
+/// them, and rewrites the module instantiations to match.
+///
+/// Each design maps the names of its modules to the modules, and the names are unique across the designs. The top
+/// modules, given by `tops`, keep their names. A design may instantiate a canonical module from another design, in
+/// which case the module is added to the design.
+///
+/// Returns the canonical name of each merged module.
+pub fn dedup(designs: &mut [HashMap<String, Module>], tops: &[String]) -> HashMap<String, String> {
+    let mut canonical_names = HashMap::new();
+
+    // Merging the modules may make their parents identical, so it is repeated until no more modules are merged.
+    loop {
+        // Modules by their Verilog code, where the names of the modules are erased. A canonical module may be in
+        // several designs.
+        let mut modules = BTreeMap::<_, BTreeMap<_, _>>::new();
+        for design in designs.iter() {
+            for (name, module) in design {
+                if !tops.contains(name) {
+                    modules.entry(renamed(module, "").to_string()).or_default().insert(name, module);
+                }
+            }
+        }
+
+        let mut renames = HashMap::new();
+        let mut canonicals = HashMap::new();
+        for modules in modules.into_values().filter(|modules| modules.len() > 1) {
+            let mut modules = modules.into_values();
+            let canonical = modules.next().unwrap();
+            for module in modules {
+                renames.insert(module.name.clone(), canonical.name.clone());
+            }
+            canonicals.insert(canonical.name.clone(), canonical.clone());
+        }
+
+        if renames.is_empty() {
+            return canonical_names;
+        }
+
+        for design in designs.iter_mut() {
+            for name in design.keys().filter(|name| renames.contains_key(*name)).cloned().collect::<Vec<_>>() {
+                design.remove(&name);
+                let canonical = &renames[&name];
+                design.entry(canonical.clone()).or_insert_with(|| canonicals[canonical].clone());
+            }
+            for module in design.values_mut() {
+                for_each_inst(&mut module.module_items, &mut |inst| {
+                    if let Some(canonical) = renames.get(&inst.module_name) {
+                        inst.module_name = canonical.clone();
+                    }
+                });
+            }
+        }
+
+        for canonical in canonical_names.values_mut() {
+            if let Some(renamed) = renames.get(canonical) {
+                *canonical = renamed.clone();
+            }
+        }
+        canonical_names.extend(renames);
+    }
+}
@@ -2,6 +2,7 @@
 
 use itertools::Itertools;
 
+use super::src_map::SrcLocAnnotator;
 use crate::compiler::prelude::Shape;
 use crate::compiler::{BinaryOp, PortDecls, UnaryOp};
 use crate::utils::{indent, join_options};
@@ -10,6 +11,7 @@ const INDENT: usize = 4;
 
 /// Module.
 #[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "vir-json", derive(serde::Serialize, serde::Deserialize))]
 pub struct Module {
     /// Module name.
     pub name: String,
@@ -23,6 +25,13 @@ pub struct Module {
 
 impl ToString for Module {
     fn to_string(&self) -> String {
+        self.gen_verilog(&SrcLocAnnotator::default())
+    }
+}
+
+impl Module {
+    /// Generates Verilog code, annotating the statements with their source locations.
+    pub fn gen_verilog(&self, annotator: &SrcLocAnnotator<'_>) -> String {
         format!(
             "module {}\n(\n{}\n);\n\ngenerate\n{}\nendgenerate\nendmodule",
             self.name,
@@ -30,13 +39,14 @@ impl ToString for Module {
                 self.port_decls.iter().map(|port_decl| port_decl.to_string()).collect::<Vec<_>>().join(",\n"),
                 INDENT
             ),
-            gen_verilog_module(&self.module_items)
+            self.module_items.iter().map(|item| item.gen_verilog(annotator)).collect::<Vec<_>>().join("\n\n")
         )
     }
 }
 
 /// Module item.
 #[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "vir-json", derive(serde::Serialize, serde::Deserialize))]
 pub enum ModuleItem {
     /// Declarations.
     Declarations(Vec<Declaration>),
@@ -63,6 +73,13 @@ impl ModuleItem {
 
 impl ToString for ModuleItem {
     fn to_string(&self) -> String {
+        self.gen_verilog(&SrcLocAnnotator::default())
+    }
+}
+
+impl ModuleItem {
+    /// Generates Verilog code, annotating the statements with their source locations.
+    pub fn gen_verilog(&self, annotator: &SrcLocAnnotator<'_>) -> String {
         match self {
             ModuleItem::Declarations(decls) => decls.iter().map(|decl| decl.to_string()).collect::<Vec<_>>().join("\n"),
             ModuleItem::ContinuousAssigns(conts) => gen_verilog_conts(conts),
@@ -71,14 +88,14 @@ impl ToString for ModuleItem {
                 format!(
                     "{} begin\n{}\nend",
                     event,
-                    indent(stmts.iter().map(|stmt| stmt.to_string()).collect::<Vec<_>>().join("\n"), INDENT)
+                    indent(stmts.iter().map(|stmt| stmt.gen_verilog(annotator)).collect::<Vec<_>>().join("\n"), INDENT)
                 )
             }
             ModuleItem::Commented(comment_before, comment_after, items) => {
                 format!(
                     "/*\n{}\n*/\n{}{}",
                     indent(comment_before.clone(), INDENT),
-                    items.iter().map(|item| item.to_string()).collect::<Vec<_>>().join("\n\n"),
+                    items.iter().map(|item| item.gen_verilog(annotator)).collect::<Vec<_>>().join("\n\n"),
                     comment_after.as_ref().map_or("".to_string(), |c| format!("\n/* {} */", c))
                 )
             }
@@ -93,6 +110,7 @@ pub fn gen_verilog_module(module: &[ModuleItem]) -> String {
 
 /// Port declaration.
 #[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "vir-json", derive(serde::Serialize, serde::Deserialize))]
 pub enum PortDeclaration {
     /// Input declaration.
     Input(usize, String),
@@ -151,6 +169,7 @@ impl PortDeclaration {
 
 /// Declaration.
 #[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "vir-json", derive(serde::Serialize, serde::Deserialize))]
 pub enum Declaration {
     /// Net declaration.
     Net(Shape, String),
@@ -298,6 +317,7 @@ impl ToString for Declaration {
 
 /// Continuous assign.
 #[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "vir-json", derive(serde::Serialize, serde::Deserialize))]
 pub struct ContinuousAssign(pub Expression, pub Expression);
 
 /// Generates verilog code for continuous assigns.
@@ -320,6 +340,7 @@ impl ContinuousAssign {
 
 /// Module instantiation.
 #[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "vir-json", derive(serde::Serialize, serde::Deserialize))]
 pub struct ModuleInstantiation {
     /// Module name.
     pub module_name: String,
@@ -327,8 +348,8 @@ pub struct ModuleInstantiation {
     /// Inst name.
     pub inst_name: String,
 
-    /// Params.
-    pub params: Vec<(String, usize)>,
+    /// Parameter overrides.
+    pub params: Vec<(String, Expression)>,
 
     /// Port connections.
     pub port_connections: Vec<(String, Expression)>,
@@ -341,7 +362,7 @@ impl ToString for ModuleInstantiation {
             self.module_name,
             self.params
                 .iter()
-                .map(|(name, value)| { format!("    .{}({})", name, value) })
+                .map(|(name, value)| { format!("    .{}({})", name, value.to_string()) })
                 .collect::<Vec<_>>()
                 .join(",\n"),
             self.inst_name,
@@ -359,7 +380,7 @@ impl ModuleInstantiation {
     pub fn new(
         module_name: String,
         inst_name: String,
-        params: Vec<(String, usize)>,
+        params: Vec<(String, Expression)>,
         port_connections: Vec<(String, Expression)>,
     ) -> Self {
         Self { module_name, inst_name, params, port_connections }
@@ -368,24 +389,51 @@ impl ModuleInstantiation {
 
 /// Statement.
 #[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "vir-json", derive(serde::Serialize, serde::Deserialize))]
 pub enum Statement {
     /// Blocking assignment.
-    BlockingAssignment(Expression, Expression, rustc_span::Span),
+    BlockingAssignment(
+        Expression,
+        Expression,
+        #[cfg_attr(feature = "vir-json", serde(with = "super::json::span"))] rustc_span::Span,
+    ),
 
     /// Conditional statement.
-    Conditional(Vec<(Expression, Vec<Statement>)>, Vec<Statement>, rustc_span::Span),
+    Conditional(
+        Vec<(Expression, Vec<Statement>)>,
+        Vec<Statement>,
+        #[cfg_attr(feature = "vir-json", serde(with = "super::json::span"))] rustc_span::Span,
+    ),
 
     /// Loop statement.
-    Loop(String, Expression, Vec<Statement>, rustc_span::Span),
+    Loop(
+        String,
+        Expression,
+        Vec<Statement>,
+        #[cfg_attr(feature = "vir-json", serde(with = "super::json::span"))] rustc_span::Span,
+    ),
 
     /// Nonblocking assignment.
-    NonblockingAssignment(Expression, Expression, rustc_span::Span),
+    NonblockingAssignment(
+        Expression,
+        Expression,
+        #[cfg_attr(feature = "vir-json", serde(with = "super::json::span"))] rustc_span::Span,
+    ),
 
     /// Case statement.
-    Case(Expression, Vec<(Expression, Vec<Statement>)>, Vec<Statement>, rustc_span::Span),
+    Case(
+        Expression,
+        Vec<(Expression, Vec<Statement>)>,
+        Vec<Statement>,
+        #[cfg_attr(feature = "vir-json", serde(with = "super::json::span"))] rustc_span::Span,
+    ),
 
     /// Display
-    Display(String, Vec<Expression>, rustc_span::Span),
+    Display(
+        String,
+        Vec<Expression>,
+        #[cfg_attr(feature = "vir-json", serde(with = "super::json::span"))] rustc_span::Span,
+    ),
 
     /// Fatal
     Fatal,
@@ -415,9 +463,16 @@ impl Statement {
 
 impl ToString for Statement {
     fn to_string(&self) -> String {
+        self.gen_verilog(&SrcLocAnnotator::default())
+    }
+}
+
+impl Statement {
+    /// Generates Verilog code, annotating the statement with its source location.
+    pub fn gen_verilog(&self, annotator: &SrcLocAnnotator<'_>) -> String {
         match self {
             Self::BlockingAssignment(lvalue, expr, span) => {
-                format!("{} = {}; // {span:?}", lvalue.to_string(), expr.to_string(),)
+                annotator.stmt(*span, format!("{} = {};", lvalue.to_string(), expr.to_string()))
             }
             Self::Conditional(cond_expr_pairs, else_stmt, span) if else_stmt.is_empty() => {
                 let conditional = cond_expr_pairs
@@ -426,12 +481,15 @@ impl ToString for Statement {
                         format!(
                             "if ({}) begin\n{}\nend",
                             cond.to_string(),
-                            indent(expr.iter().map(|stmt| stmt.to_string()).collect::<Vec<_>>().join("\n"), INDENT),
+                            indent(
+                                expr.iter().map(|stmt| stmt.gen_verilog(annotator)).collect::<Vec<_>>().join("\n"),
+                                INDENT
+                            ),
                         )
                     })
                     .join("\nelse ");
 
-                format!("// {span:?}\n{conditional}")
+                annotator.block(*span, conditional)
             }
             Self::Conditional(cond_expr_pairs, else_stmt, span) => {
                 assert!(!cond_expr_pairs.is_empty());
@@ -441,73 +499,90 @@ impl ToString for Statement {
                         format!(
                             "if ({}) begin\n{}\nend",
                             cond.to_string(),
-                            indent(expr.iter().map(|stmt| stmt.to_string()).collect::<Vec<_>>().join("\n"), INDENT),
+                            indent(
+                                expr.iter().map(|stmt| stmt.gen_verilog(annotator)).collect::<Vec<_>>().join("\n"),
+                                INDENT
+                            ),
                         )
                     })
                     .join("\nelse ");
-                let else_stmt =
-                    indent(else_stmt.iter().map(|stmt| stmt.to_string()).collect::<Vec<_>>().join("\n"), INDENT);
-                format!("// {span:?}\n{conditional}\nelse begin\n{else_stmt}\nend",)
+                let else_stmt = indent(
+                    else_stmt.iter().map(|stmt| stmt.gen_verilog(annotator)).collect::<Vec<_>>().join("\n"),
+                    INDENT,
+                );
+                annotator.block(*span, format!("{conditional}\nelse begin\n{else_stmt}\nend"))
             }
-            Self::Loop(ident, count, stmt, span) => {
+            Self::Loop(ident, count, stmt, span) => annotator.block(
+                *span,
                 format!(
-                    "// {span:?}\nfor ({} = 0; {} < {}; {} = {} + 1) begin\n{}\nend",
+                    "for ({} = 0; {} < {}; {} = {} + 1) begin\n{}\nend",
                     ident,
                     ident,
                     count.to_string(),
                     ident,
                     ident,
-                    indent(stmt.iter().map(|stmt| stmt.to_string()).collect::<Vec<_>>().join("\n"), INDENT),
-                )
-            }
+                    indent(stmt.iter().map(|stmt| stmt.gen_verilog(annotator)).collect::<Vec<_>>().join("\n"), INDENT),
+                ),
+            ),
             Self::NonblockingAssignment(lvalue, expr, span) => {
-                format!("{} <= {}; // {span:?}", lvalue.to_string(), expr.to_string(),)
+                annotator.stmt(*span, format!("{} <= {};", lvalue.to_string(), expr.to_string()))
             }
             Self::Case(case_expr, case_items, default, span) => {
                 let case_items_code = case_items.iter().map(|(cond, stmt)| {
                     format!(
                         "{}: begin\n{}\nend",
                         cond.to_string(),
-                        indent(stmt.iter().map(|stmt| stmt.to_string()).collect::<Vec<_>>().join("\n"), INDENT)
+                        indent(
+                            stmt.iter().map(|stmt| stmt.gen_verilog(annotator)).collect::<Vec<_>>().join("\n"),
+                            INDENT
+                        )
                     )
                 });
 
-                format!(
-                    "// {span:?}\ncase ({})\n{}{}\nendcase",
-                    case_expr.to_string(),
-                    indent(case_items_code.collect::<Vec<_>>().join("\n"), INDENT),
-                    if default.is_empty() {
-                        "".to_string()
-                    } else {
-                        indent(
-                            format!(
-                                "\ndefault: begin\n{}\nend",
-                                indent(
-                                    default.iter().map(|stmt| stmt.to_string()).collect::<Vec<_>>().join("\n"),
-                                    INDENT
+                annotator.block(
+                    *span,
+                    format!(
+                        "case ({})\n{}{}\nendcase",
+                        case_expr.to_string(),
+                        indent(case_items_code.collect::<Vec<_>>().join("\n"), INDENT),
+                        if default.is_empty() {
+                            "".to_string()
+                        } else {
+                            indent(
+                                format!(
+                                    "\ndefault: begin\n{}\nend",
+                                    indent(
+                                        default
+                                            .iter()
+                                            .map(|stmt| stmt.gen_verilog(annotator))
+                                            .collect::<Vec<_>>()
+                                            .join("\n"),
+                                        INDENT
+                                    ),
                                 ),
-                            ),
-                            INDENT,
-                        )
-                    }
+                                INDENT,
+                            )
+                        }
+                    ),
                 )
             }
-            Self::Display(fstring, args, span) => {
+            Self::Display(fstring, args, span) => annotator.stmt(
+                *span,
                 if args.is_empty() {
                     format!(
                         // NOTE: 32'h80000001 is `stdout`
-                        "$fdisplay(32'h80000002,\"[%0t] {}\", $time); // {span:?}",
+                        "$fdisplay(32'h80000002,\"[%0t] {}\", $time);",
                         fstring
                     )
                 } else {
                     format!(
                         // NOTE: 32'h80000001 is `stdout`
-                        "$fdisplay(32'h80000002,\"[%0t] {}\", $time, {}); // {span:?}",
+                        "$fdisplay(32'h80000002,\"[%0t] {}\", $time, {});",
                         fstring,
                         args.iter().map(|arg| arg.to_string()).join(", ")
                     )
-                }
-            }
+                },
+            ),
             Statement::Fatal => "$fatal;".to_string(),
         }
     }
@@ -515,6 +590,7 @@ impl ToString for Statement {
 
 /// Expression.
 #[derive(Debug, PartialEq, Eq, Hash, Clone)]
+#[cfg_attr(feature = "vir-json", derive(serde::Serialize, serde::Deserialize))]
 pub enum Expression {
     /// Primary.
     Primary(Primary),
@@ -531,6 +607,7 @@ pub enum Expression {
 
 /// Range.
 #[derive(Debug, PartialEq, Eq, Hash, Clone)]
+#[cfg_attr(feature = "vir-json", derive(serde::Serialize, serde::Deserialize))]
 pub enum Range {
     /// Index: `[index]`
     Index(Box<Expression>),
@@ -541,6 +618,7 @@ pub enum Range {
 
 /// Primary.
 #[derive(Debug, PartialEq, Eq, Hash, Clone)]
+#[cfg_attr(feature = "vir-json", derive(serde::Serialize, serde::Deserialize))]
 pub enum Primary {
     /// Number.
     Number(String),
@@ -562,6 +640,7 @@ pub enum Primary {
 
 /// Concatenation.
 #[derive(Debug, PartialEq, Eq, Hash, Clone)]
+#[cfg_attr(feature = "vir-json", derive(serde::Serialize, serde::Deserialize))]
 pub struct Concatenation {
     /// Expressions.
     pub exprs: Vec<Expression>,
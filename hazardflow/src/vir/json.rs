@@ -0,0 +1,106 @@
+//! JSON serialization of the IR.
+//!
+//! [`gen_vir_json`] writes the modules as they are after optimization, so that external tools, lint scripts and
+//! passes consume them without parsing Verilog, and [`load_vir_json`] reads them back. The modules are wrapped in an
+//! object with the version of the format:
+//!
+//! ```json
+//! { "version": 1, "modules": [ { "name": "...", "port_decls": [...], "module_items": [...] } ] }
+//! ```
+//!
+//! Each enum variant is an object with the name of the variant as its key, e.g., `{ "Input": [1, "clk"] }` for
+//! `PortDeclaration::Input(1, "clk")`, and unit variants are strings. The span of a statement is written as the
+//! location of its Rust code, e.g., `"hazardflow-designs/src/cpu/exe.rs:113:13"`, or `null` if it has none. As a span
+//! is only meaningful within the compilation, the loaded statements have dummy spans.
+
+use rustc_span::Span;
+use serde::{Deserialize, Serialize};
+
+use super::src_map::SrcLocation;
+use super::*;
+use crate::compiler::error::{VirgenError, VirgenResult};
+
+/// Version of the format, which is bumped when the IR changes incompatibly.
+pub const VIR_JSON_VERSION: u32 = 1;
+
+#[derive(Serialize)]
+struct VirJson<'a> {
+    version: u32,
+    modules: &'a [Module],
+}
+
+#[derive(Deserialize)]
+struct VirJsonOwned {
+    version: u32,
+    modules: Vec<Module>,
+}
+
+fn vir_json_error(msg: String) -> VirgenError {
+    VirgenError::VirJsonError { msg }
+}
+
+/// Generates the modules in JSON, where the spans are written as their locations found with `locate`.
+pub fn gen_vir_json(modules: &[Module], locate: &dyn Fn(Span) -> Option<SrcLocation>) -> VirgenResult<String> {
+    let mut json = serde_json::to_value(VirJson { version: VIR_JSON_VERSION, modules })
+        .map_err(|err| vir_json_error(err.to_string()))?;
+    span::locate(&mut json, locate);
+    serde_json::to_string_pretty(&json).map_err(|err| vir_json_error(err.to_string()))
+}
+
+/// Loads the modules from JSON generated by [`gen_vir_json`].
+pub fn load_vir_json(json: &str) -> VirgenResult<Vec<Module>> {
+    let vir_json: VirJsonOwned = serde_json::from_str(json).map_err(|err| vir_json_error(err.to_string()))?;
+    if vir_json.version != VIR_JSON_VERSION {
+        return Err(vir_json_error(format!(
+            "unsupported IR version {} (expected {})",
+            vir_json.version, VIR_JSON_VERSION
+        )));
+    }
+    Ok(vir_json.modules)
+}
+
+/// Serializes a span as its location.
+///
+/// The serializer has no access to the source map, so a span is first serialized as an object with its byte offsets
+/// under [`span::KEY`], which is not a name in the IR, and [`span::locate`] replaces the objects with the locations.
+pub(crate) mod span {
+    use rustc_span::{BytePos, Span, DUMMY_SP};
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    use super::SrcLocation;
+
+    /// Key of the byte offsets of a span before it is located.
+    pub(super) const KEY: &str = "$span";
+
+    #[derive(Serialize)]
+    struct Offsets {
+        #[serde(rename = "$span")]
+        offsets: (u32, u32),
+    }
+
+    pub(crate) fn serialize<S: Serializer>(span: &Span, serializer: S) -> Result<S::Ok, S::Error> {
+        Offsets { offsets: (span.lo().0, span.hi().0) }.serialize(serializer)
+    }
+
+    pub(crate) fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Span, D::Error> {
+        Option::<String>::deserialize(deserializer)?;
+        Ok(DUMMY_SP)
+    }
+
+    /// Replaces the spans in `json` with their locations, or `null` for the dummy spans and the unknown locations.
+    pub(super) fn locate(json: &mut serde_json::Value, locate: &dyn Fn(Span) -> Option<SrcLocation>) {
+        if let Some(offsets) = json.as_object().filter(|object| object.len() == 1).and_then(|object| object.get(KEY)) {
+            let (lo, hi) = <(u32, u32)>::deserialize(offsets).expect("offsets of a span");
+            let span = Span::with_root_ctxt(BytePos(lo), BytePos(hi));
+            let loc = if span.is_dummy() { None } else { locate(span) };
+            *json = loc.map_or(serde_json::Value::Null, |loc| serde_json::Value::String(loc.to_string()));
+            return;
+        }
+
+        match json {
+            serde_json::Value::Object(object) => object.values_mut().for_each(|value| self::locate(value, locate)),
+            serde_json::Value::Array(values) => values.iter_mut().for_each(|value| self::locate(value, locate)),
+            _ => {}
+        }
+    }
+}
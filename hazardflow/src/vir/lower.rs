@@ -0,0 +1,726 @@
+//! Symbolic execution of netlists, shared by the backends lowering modules into dataflow graphs.
+//!
+//! The statements of a process are executed with the values of the signals as nodes of a graph, where the branches of
+//! `if` and `case` statements become multiplexers and `for` loops are unrolled. Each backend provides the nodes of its
+//! graph with [`Lower`], and the constants are folded here.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use super::sim::netlist::*;
+use super::sim::Bits;
+use crate::compiler::error::{VirgenError, VirgenResult};
+use crate::compiler::{BinaryOp, UnaryOp};
+
+/// Upper bound on the iterations of an unrolled `for` loop.
+const UNROLL_LIMIT: usize = 1 << 16;
+
+/// Lowered value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Value<N> {
+    /// Constant.
+    Const(Bits),
+
+    /// Node of the graph with its width.
+    Node(N, usize),
+}
+
+impl<N> Value<N> {
+    pub(crate) fn zero(width: usize) -> Self {
+        Self::Const(Bits::zero(width))
+    }
+
+    pub(crate) fn ones(width: usize) -> Self {
+        Self::Const(Bits::ones(width))
+    }
+
+    pub(crate) fn width(&self) -> usize {
+        match self {
+            Self::Const(bits) => bits.width(),
+            Self::Node(_, width) => *width,
+        }
+    }
+
+    pub(crate) fn is_zero(&self) -> bool {
+        matches!(self, Self::Const(bits) if bits.is_zero())
+    }
+
+    pub(crate) fn is_ones(&self) -> bool {
+        matches!(self, Self::Const(bits) if *bits == Bits::ones(bits.width()))
+    }
+
+    /// Returns the node of a value.
+    pub(crate) fn node(self) -> N {
+        match self {
+            Self::Node(node, _) => node,
+            Self::Const(_) => panic!("`node` of a constant"),
+        }
+    }
+}
+
+/// Operation on operands of the same width.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Op {
+    Add,
+    Sub,
+    Mul,
+    DivU,
+    DivS,
+    ModU,
+    ModS,
+    And,
+    Or,
+    Xor,
+    Shl,
+    ShrU,
+    ShrS,
+}
+
+/// Comparison of operands of the same width.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Predicate {
+    Eq,
+    Ne,
+    Slt,
+    Ult,
+    Sgt,
+    Ugt,
+    Sle,
+    Ule,
+    Sge,
+    Uge,
+}
+
+impl Predicate {
+    fn is_signed(self) -> bool {
+        matches!(self, Self::Slt | Self::Sgt | Self::Sle | Self::Sge)
+    }
+}
+
+/// Value and mask of the bits written to a signal.
+pub(crate) type Write<N> = (Value<N>, Value<N>);
+
+/// Writes to each signal.
+pub(crate) type Writes<N> = BTreeMap<SignalId, Write<N>>;
+
+/// Writes of a process being executed.
+#[derive(Debug, Clone)]
+pub(crate) struct State<N> {
+    /// Blocking assignments, which are visible to the following statements.
+    pub(crate) blocking: Writes<N>,
+
+    /// Nonblocking assignments, or `None` in combinational processes where they are treated as blocking ones.
+    pub(crate) nonblocking: Option<Writes<N>>,
+}
+
+impl<N> State<N> {
+    /// Returns the state of a combinational process.
+    pub(crate) fn comb() -> Self {
+        Self { blocking: Writes::new(), nonblocking: None }
+    }
+
+    /// Returns the state of an `always @(posedge clk)` block.
+    pub(crate) fn seq() -> Self {
+        Self { blocking: Writes::new(), nonblocking: Some(Writes::new()) }
+    }
+}
+
+pub(crate) fn misc(msg: String) -> VirgenError {
+    VirgenError::Misc { msg }
+}
+
+/// Lowering of a netlist into a dataflow graph.
+///
+/// The required methods add nodes for operations with at least one operand which is not constant.
+pub(crate) trait Lower {
+    /// Node of the graph.
+    type Node: Clone + PartialEq;
+
+    fn netlist(&self) -> &Netlist;
+
+    /// Returns the current value of a signal of nonzero width.
+    fn signal(&mut self, id: SignalId) -> Value<Self::Node>;
+
+    /// Returns `width` bits of a node of width `from`, starting from `lo`.
+    fn extract_node(&mut self, node: &Self::Node, from: usize, lo: usize, width: usize) -> Value<Self::Node>;
+
+    /// Concatenates values of nonzero width, most significant first.
+    fn concat_nodes(&mut self, parts: &[Value<Self::Node>]) -> Value<Self::Node>;
+
+    /// Replicates a bit.
+    fn replicate(&mut self, bit: &Value<Self::Node>, count: usize) -> Value<Self::Node>;
+
+    fn op_node(&mut self, op: Op, lhs: &Value<Self::Node>, rhs: &Value<Self::Node>) -> Value<Self::Node>;
+
+    fn compare_node(
+        &mut self,
+        predicate: Predicate,
+        lhs: &Value<Self::Node>,
+        rhs: &Value<Self::Node>,
+    ) -> Value<Self::Node>;
+
+    fn mux_node(
+        &mut self,
+        cond: &Value<Self::Node>,
+        then_value: &Value<Self::Node>,
+        else_value: &Value<Self::Node>,
+    ) -> Value<Self::Node>;
+
+    /// Executes statements symbolically.
+    fn exec(&mut self, stmts: &[Stmt], state: &mut State<Self::Node>) -> VirgenResult<()> {
+        for stmt in stmts {
+            match stmt {
+                Stmt::Assign { lhs, rhs, blocking } => {
+                    let width = lhs.width(&self.netlist().signals);
+                    let value = self.expr(rhs, width.max(rhs.width), rhs.signed, &state.blocking)?;
+                    let value = self.extract(&value, 0, width);
+                    if *blocking || state.nonblocking.is_none() {
+                        self.assign(lhs, value, state)?;
+                    } else {
+                        // Targets of nonblocking assignments are resolved with the blocking assignments so far.
+                        let mut nonblocking = State { blocking: state.nonblocking.take().unwrap(), nonblocking: None };
+                        let visible = std::mem::take(&mut state.blocking);
+                        let result = self.assign_visible(lhs, value, &mut nonblocking, &visible);
+                        state.blocking = visible;
+                        state.nonblocking = Some(nonblocking.blocking);
+                        result?;
+                    }
+                }
+                Stmt::If(branches, default) => self.exec_if(branches, default, state)?,
+                Stmt::Case(sel, items, default) => {
+                    let branches = items
+                        .iter()
+                        .map(|(item, stmts)| {
+                            let (width, signed) = (sel.width.max(item.width), sel.signed && item.signed);
+                            let lhs = self.expr(sel, width, signed, &state.blocking)?;
+                            let rhs = self.expr(item, width, signed, &state.blocking)?;
+                            Ok((self.compare(Predicate::Eq, &lhs, &rhs), stmts.clone()))
+                        })
+                        .collect::<VirgenResult<Vec<_>>>()?;
+                    self.exec_branches(&branches, default, state)?;
+                }
+                Stmt::Loop(var, count, body) => {
+                    let width = self.netlist().signals[*var].total_width();
+                    let count = match self.expr(count, width.max(count.width), count.signed, &state.blocking)? {
+                        Value::Const(count) => count,
+                        Value::Node(..) => {
+                            return Err(misc(format!(
+                                "the bound of the loop on `{}` is not constant",
+                                self.netlist().signals[*var].name
+                            )))
+                        }
+                    };
+                    let mut iter = 0;
+                    loop {
+                        let index = Bits::from_u64(iter as u64, width);
+                        self.assign(&LValue::Signal(*var), Value::Const(index.clone()), state)?;
+                        if index.resize(count.width(), true).scmp(&count).is_ge() {
+                            break;
+                        }
+                        if iter == UNROLL_LIMIT {
+                            return Err(misc(format!(
+                                "the loop on `{}` has more than {} iterations",
+                                self.netlist().signals[*var].name,
+                                UNROLL_LIMIT
+                            )));
+                        }
+                        self.exec(body, state)?;
+                        iter += 1;
+                    }
+                }
+                Stmt::Display(..) | Stmt::Fatal => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn exec_if(
+        &mut self,
+        branches: &[(Expr, Vec<Stmt>)],
+        default: &[Stmt],
+        state: &mut State<Self::Node>,
+    ) -> VirgenResult<()> {
+        let Some(((cond, stmts), rest)) = branches.split_first() else {
+            return self.exec(default, state);
+        };
+        let cond = self.expr_self(cond, &state.blocking)?;
+        let cond = self.truthy(&cond);
+
+        let mut then_state = state.clone();
+        self.exec(stmts, &mut then_state)?;
+        self.exec_if(rest, default, state)?;
+        let else_state = std::mem::replace(state, State::comb());
+        *state = self.merge_states(&cond, then_state, else_state);
+        Ok(())
+    }
+
+    fn exec_branches(
+        &mut self,
+        branches: &[(Value<Self::Node>, Vec<Stmt>)],
+        default: &[Stmt],
+        state: &mut State<Self::Node>,
+    ) -> VirgenResult<()> {
+        let Some(((cond, stmts), rest)) = branches.split_first() else {
+            return self.exec(default, state);
+        };
+
+        let mut then_state = state.clone();
+        self.exec(stmts, &mut then_state)?;
+        self.exec_branches(rest, default, state)?;
+        let else_state = std::mem::replace(state, State::comb());
+        *state = self.merge_states(cond, then_state, else_state);
+        Ok(())
+    }
+
+    /// Merges the states after the branches of a condition.
+    fn merge_states(
+        &mut self,
+        cond: &Value<Self::Node>,
+        then_state: State<Self::Node>,
+        else_state: State<Self::Node>,
+    ) -> State<Self::Node> {
+        let blocking = self.merge_writes(cond, &then_state.blocking, &else_state.blocking);
+        let nonblocking = match (then_state.nonblocking, else_state.nonblocking) {
+            (Some(then_writes), Some(else_writes)) => Some(self.merge_writes(cond, &then_writes, &else_writes)),
+            _ => None,
+        };
+        State { blocking, nonblocking }
+    }
+
+    fn merge_writes(
+        &mut self,
+        cond: &Value<Self::Node>,
+        then_writes: &Writes<Self::Node>,
+        else_writes: &Writes<Self::Node>,
+    ) -> Writes<Self::Node> {
+        let ids = then_writes.keys().chain(else_writes.keys()).copied().collect::<BTreeSet<_>>();
+        ids.into_iter()
+            .map(|id| {
+                let width = self.netlist().signals[id].total_width();
+                let none = (Value::zero(width), Value::zero(width));
+                let (then_value, then_mask) = then_writes.get(&id).unwrap_or(&none);
+                let (else_value, else_mask) = else_writes.get(&id).unwrap_or(&none);
+                let value = self.mux(cond, then_value, else_value);
+                let mask = self.mux(cond, then_mask, else_mask);
+                (id, (value, mask))
+            })
+            .collect()
+    }
+
+    /// Assigns a value to the target, resolving it with the writes of the state.
+    fn assign(&mut self, lhs: &LValue, value: Value<Self::Node>, state: &mut State<Self::Node>) -> VirgenResult<()> {
+        let visible = state.blocking.clone();
+        self.assign_visible(lhs, value, state, &visible)
+    }
+
+    /// Assigns a value to the target, resolving it with the visible writes.
+    fn assign_visible(
+        &mut self,
+        lhs: &LValue,
+        value: Value<Self::Node>,
+        state: &mut State<Self::Node>,
+        visible: &Writes<Self::Node>,
+    ) -> VirgenResult<()> {
+        match lhs {
+            LValue::Signal(id) => self.write(*id, &Value::zero(1), value, &mut state.blocking),
+            LValue::Index(id, index) => {
+                let signal = &self.netlist().signals[*id];
+                let elt_width = if signal.len.is_some() { signal.width } else { 1 };
+                let index = self.expr_self(index, visible)?;
+                let lo = self.scale(&index, elt_width);
+                self.write(*id, &lo, value, &mut state.blocking);
+            }
+            LValue::Range(id, base, _) => {
+                let lo = self.expr_self(base, visible)?;
+                self.write(*id, &lo, value, &mut state.blocking);
+            }
+            LValue::Concat(lvalues) => {
+                let mut lo = value.width();
+                for lvalue in lvalues {
+                    let width = lvalue.width(&self.netlist().signals);
+                    lo -= width;
+                    let part = self.extract(&value, lo, width);
+                    self.assign_visible(lvalue, part, state, visible)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes a value into the bits of a signal starting from `lo`.
+    fn write(
+        &mut self,
+        id: SignalId,
+        lo: &Value<Self::Node>,
+        value: Value<Self::Node>,
+        writes: &mut Writes<Self::Node>,
+    ) {
+        let width = self.netlist().signals[id].total_width();
+        let none = (Value::zero(width), Value::zero(width));
+        let (old_value, old_mask) = writes.get(&id).unwrap_or(&none).clone();
+
+        let (value, mask) = match lo {
+            Value::Const(lo) => {
+                let lo = lo.to_usize();
+                if lo >= width {
+                    return;
+                }
+                let part_width = value.width().min(width - lo);
+                let part = self.extract(&value, 0, part_width);
+                let high = width - lo - part_width;
+                let parts = [self.extract(&old_value, lo + part_width, high), part, self.extract(&old_value, 0, lo)];
+                let new_value = self.concat(&parts);
+                let parts = [
+                    self.extract(&old_mask, lo + part_width, high),
+                    Value::ones(part_width),
+                    self.extract(&old_mask, 0, lo),
+                ];
+                (new_value, self.concat(&parts))
+            }
+            Value::Node(..) => {
+                let wide = width.max(lo.width()).max(value.width());
+                let amount = self.resize(lo, wide, false);
+                let ones = self.resize(&Value::ones(value.width()), wide, false);
+                let mask = self.op(Op::Shl, &ones, &amount);
+                let mask = self.extract(&mask, 0, width);
+                let value = self.resize(&value, wide, false);
+                let value = self.op(Op::Shl, &value, &amount);
+                let value = self.extract(&value, 0, width);
+                let new_value = self.select(&mask, &value, &old_value);
+                (new_value, self.op(Op::Or, &old_mask, &mask))
+            }
+        };
+        let _unused = writes.insert(id, (value, mask));
+    }
+
+    /// Returns the writes of `upper` over the writes of `lower`.
+    fn overlay(&mut self, lower: &Write<Self::Node>, upper: &Write<Self::Node>) -> Write<Self::Node> {
+        if lower.1.is_zero() {
+            return upper.clone();
+        }
+        let value = self.select(&upper.1, &upper.0, &lower.0);
+        let mask = self.op(Op::Or, &lower.1, &upper.1);
+        (value, mask)
+    }
+
+    /// Merges the writes of the processes assigning to a signal.
+    fn merge(&mut self, writes: &[Write<Self::Node>], width: usize) -> Write<Self::Node> {
+        writes.iter().fold((Value::zero(width), Value::zero(width)), |lower, upper| self.overlay(&lower, upper))
+    }
+
+    /// Returns the value of a signal, with the visible writes.
+    fn read(&mut self, id: SignalId, visible: &Writes<Self::Node>) -> Value<Self::Node> {
+        let current = match self.netlist().signals[id].total_width() {
+            0 => Value::zero(0),
+            _ => self.signal(id),
+        };
+        match visible.get(&id) {
+            Some((value, mask)) => self.select(mask, value, &current),
+            None => current,
+        }
+    }
+
+    /// Returns `width` bits of `value` starting from `lo`, where bits out of range are zero.
+    fn select_range(&mut self, value: &Value<Self::Node>, lo: &Value<Self::Node>, width: usize) -> Value<Self::Node> {
+        match lo {
+            Value::Const(lo) => {
+                let lo = lo.to_usize();
+                if lo >= value.width() {
+                    return Value::zero(width);
+                }
+                let bits = self.extract(value, lo, width.min(value.width() - lo));
+                self.resize(&bits, width, false)
+            }
+            Value::Node(..) => {
+                let wide = value.width().max(lo.width()).max(width);
+                let value = self.resize(value, wide, false);
+                let amount = self.resize(lo, wide, false);
+                let shifted = self.op(Op::ShrU, &value, &amount);
+                self.extract(&shifted, 0, width)
+            }
+        }
+    }
+
+    /// Returns `index * elt_width`.
+    fn scale(&mut self, index: &Value<Self::Node>, elt_width: usize) -> Value<Self::Node> {
+        match index {
+            _ if elt_width == 1 => index.clone(),
+            Value::Const(index) => {
+                let lo = index.to_usize().saturating_mul(elt_width);
+                Value::Const(Bits::from_u64(lo.try_into().unwrap_or(u64::MAX), 64))
+            }
+            Value::Node(..) => {
+                let width = index.width() + (usize::BITS - elt_width.leading_zeros()) as usize;
+                let index = self.resize(index, width, false);
+                self.op(Op::Mul, &index, &Value::Const(Bits::from_u64(elt_width as u64, width)))
+            }
+        }
+    }
+
+    fn expr_self(&mut self, expr: &Expr, visible: &Writes<Self::Node>) -> VirgenResult<Value<Self::Node>> {
+        self.expr(expr, expr.width, expr.signed, visible)
+    }
+
+    /// Lowers an expression in a context of the given width and signedness. See [`Expr::eval`].
+    fn expr(
+        &mut self,
+        expr: &Expr,
+        width: usize,
+        signed: bool,
+        visible: &Writes<Self::Node>,
+    ) -> VirgenResult<Value<Self::Node>> {
+        Ok(match &expr.kind {
+            ExprKind::Const(bits) => Value::Const(bits.resize(width, signed)),
+            ExprKind::Signal(id) => {
+                let value = self.read(*id, visible);
+                self.resize(&value, width, signed)
+            }
+            ExprKind::Index(id, index) => {
+                let signal = &self.netlist().signals[*id];
+                let elt_width = if signal.len.is_some() { signal.width } else { 1 };
+                let index = self.expr_self(index, visible)?;
+                let lo = self.scale(&index, elt_width);
+                let value = self.read(*id, visible);
+                let value = self.select_range(&value, &lo, expr.width);
+                self.resize(&value, width, signed)
+            }
+            ExprKind::Range(id, base, range_width) => {
+                let lo = self.expr_self(base, visible)?;
+                let value = self.read(*id, visible);
+                let value = self.select_range(&value, &lo, *range_width);
+                self.resize(&value, width, signed)
+            }
+            ExprKind::Concat(exprs) => {
+                let parts = exprs.iter().map(|expr| self.expr_self(expr, visible)).collect::<VirgenResult<Vec<_>>>()?;
+                let value = self.concat(&parts);
+                self.resize(&value, width, signed)
+            }
+            ExprKind::Repeat(count, exprs) => {
+                let parts = exprs.iter().map(|expr| self.expr_self(expr, visible)).collect::<VirgenResult<Vec<_>>>()?;
+                let parts = (0..*count).flat_map(|_| parts.clone()).collect::<Vec<_>>();
+                let value = self.concat(&parts);
+                self.resize(&value, width, signed)
+            }
+            ExprKind::Unary(UnaryOp::Negation, expr) => {
+                let value = self.expr(expr, width, signed, visible)?;
+                self.not(&value)
+            }
+            ExprKind::Binary(lhs, op, rhs) => match op {
+                BinaryOp::EqArithmetic
+                | BinaryOp::NeStrict
+                | BinaryOp::NeArithmetic
+                | BinaryOp::Less
+                | BinaryOp::Greater
+                | BinaryOp::LessEq
+                | BinaryOp::GreaterEq => {
+                    let (w, s) = (lhs.width.max(rhs.width), lhs.signed && rhs.signed);
+                    let (l, r) = (self.expr(lhs, w, s, visible)?, self.expr(rhs, w, s, visible)?);
+                    let predicate = match (op, s) {
+                        (BinaryOp::EqArithmetic, _) => Predicate::Eq,
+                        (BinaryOp::NeStrict | BinaryOp::NeArithmetic, _) => Predicate::Ne,
+                        (BinaryOp::Less, true) => Predicate::Slt,
+                        (BinaryOp::Less, false) => Predicate::Ult,
+                        (BinaryOp::Greater, true) => Predicate::Sgt,
+                        (BinaryOp::Greater, false) => Predicate::Ugt,
+                        (BinaryOp::LessEq, true) => Predicate::Sle,
+                        (BinaryOp::LessEq, false) => Predicate::Ule,
+                        (BinaryOp::GreaterEq, true) => Predicate::Sge,
+                        (BinaryOp::GreaterEq, false) => Predicate::Uge,
+                        _ => unreachable!(),
+                    };
+                    let result = self.compare(predicate, &l, &r);
+                    self.resize(&result, width, false)
+                }
+                BinaryOp::ShiftLeft | BinaryOp::ShiftRight => {
+                    let value = self.expr(lhs, width, signed, visible)?;
+                    let amount = self.expr_self(rhs, visible)?;
+                    // Shifts in a wider width, so that the amount is not truncated.
+                    let wide = width.max(amount.width());
+                    let value = self.resize(&value, wide, signed);
+                    let amount = self.resize(&amount, wide, false);
+                    let op = match op {
+                        BinaryOp::ShiftLeft => Op::Shl,
+                        _ if signed => Op::ShrS,
+                        _ => Op::ShrU,
+                    };
+                    let shifted = self.op(op, &value, &amount);
+                    self.extract(&shifted, 0, width)
+                }
+                _ => {
+                    let (l, r) = (self.expr(lhs, width, signed, visible)?, self.expr(rhs, width, signed, visible)?);
+                    let op = match op {
+                        BinaryOp::Add => Op::Add,
+                        BinaryOp::Sub => Op::Sub,
+                        BinaryOp::Mul => Op::Mul,
+                        BinaryOp::Div if signed => Op::DivS,
+                        BinaryOp::Div => Op::DivU,
+                        BinaryOp::Mod if signed => Op::ModS,
+                        BinaryOp::Mod => Op::ModU,
+                        BinaryOp::Or => Op::Or,
+                        BinaryOp::And => Op::And,
+                        BinaryOp::Xor => Op::Xor,
+                        BinaryOp::Eq => {
+                            let value = self.op(Op::Xor, &l, &r);
+                            return Ok(self.not(&value));
+                        }
+                        _ => unreachable!(),
+                    };
+                    self.op(op, &l, &r)
+                }
+            },
+            ExprKind::Cond(cond, then_expr, else_expr) => {
+                let cond = self.expr_self(cond, visible)?;
+                let cond = self.truthy(&cond);
+                let then_value = self.expr(then_expr, width, signed, visible)?;
+                let else_value = self.expr(else_expr, width, signed, visible)?;
+                self.mux(&cond, &then_value, &else_value)
+            }
+        })
+    }
+
+    fn extract(&mut self, value: &Value<Self::Node>, lo: usize, width: usize) -> Value<Self::Node> {
+        assert!(lo + width <= value.width());
+        match value {
+            _ if width == 0 => Value::zero(0),
+            _ if lo == 0 && width == value.width() => value.clone(),
+            Value::Const(bits) => Value::Const(bits.slice(lo, width)),
+            Value::Node(node, from) => self.extract_node(node, *from, lo, width),
+        }
+    }
+
+    /// Concatenates values, most significant first.
+    fn concat(&mut self, parts: &[Value<Self::Node>]) -> Value<Self::Node> {
+        let parts = parts.iter().filter(|part| part.width() > 0).cloned().collect::<Vec<_>>();
+        match parts.as_slice() {
+            [] => Value::zero(0),
+            [part] => part.clone(),
+            _ if parts.iter().all(|part| matches!(part, Value::Const(_))) => {
+                Value::Const(parts.iter().fold(Bits::zero(0), |acc, part| {
+                    let Value::Const(bits) = part else { unreachable!() };
+                    acc.concat(bits)
+                }))
+            }
+            _ => self.concat_nodes(&parts),
+        }
+    }
+
+    fn resize(&mut self, value: &Value<Self::Node>, width: usize, signed: bool) -> Value<Self::Node> {
+        let from = value.width();
+        match value {
+            _ if from == width => value.clone(),
+            _ if from > width => self.extract(value, 0, width),
+            _ if from == 0 => Value::zero(width),
+            Value::Const(bits) => Value::Const(bits.resize(width, signed)),
+            Value::Node(..) => {
+                let high = if signed {
+                    let msb = self.extract(value, from - 1, 1);
+                    self.replicate(&msb, width - from)
+                } else {
+                    Value::zero(width - from)
+                };
+                self.concat(&[high, value.clone()])
+            }
+        }
+    }
+
+    fn not(&mut self, value: &Value<Self::Node>) -> Value<Self::Node> {
+        match value {
+            Value::Const(bits) => Value::Const(bits.not()),
+            Value::Node(..) => self.op(Op::Xor, value, &Value::ones(value.width())),
+        }
+    }
+
+    /// Returns the bits of `value` where `mask` is set, and the bits of `other` elsewhere.
+    fn select(
+        &mut self,
+        mask: &Value<Self::Node>,
+        value: &Value<Self::Node>,
+        other: &Value<Self::Node>,
+    ) -> Value<Self::Node> {
+        if mask.is_ones() {
+            return value.clone();
+        }
+        if mask.is_zero() {
+            return other.clone();
+        }
+        let value = self.op(Op::And, value, mask);
+        if other.is_zero() {
+            return value;
+        }
+        let inverted = self.not(mask);
+        let other = self.op(Op::And, other, &inverted);
+        self.op(Op::Or, &value, &other)
+    }
+
+    /// Returns whether the value is nonzero.
+    fn truthy(&mut self, value: &Value<Self::Node>) -> Value<Self::Node> {
+        match value.width() {
+            1 => value.clone(),
+            width => self.compare(Predicate::Ne, value, &Value::zero(width)),
+        }
+    }
+
+    fn mux(
+        &mut self,
+        cond: &Value<Self::Node>,
+        then_value: &Value<Self::Node>,
+        else_value: &Value<Self::Node>,
+    ) -> Value<Self::Node> {
+        match cond {
+            _ if then_value == else_value => then_value.clone(),
+            Value::Const(cond) if cond.is_zero() => else_value.clone(),
+            Value::Const(_) => then_value.clone(),
+            Value::Node(..) if then_value.width() == 1 && then_value.is_ones() && else_value.is_zero() => cond.clone(),
+            Value::Node(..) => self.mux_node(cond, then_value, else_value),
+        }
+    }
+
+    fn compare(&mut self, predicate: Predicate, lhs: &Value<Self::Node>, rhs: &Value<Self::Node>) -> Value<Self::Node> {
+        if let (Value::Const(l), Value::Const(r)) = (lhs, rhs) {
+            let ord = if predicate.is_signed() { l.scmp(r) } else { l.ucmp(r) };
+            let result = match predicate {
+                Predicate::Eq => ord.is_eq(),
+                Predicate::Ne => ord.is_ne(),
+                Predicate::Slt | Predicate::Ult => ord.is_lt(),
+                Predicate::Sgt | Predicate::Ugt => ord.is_gt(),
+                Predicate::Sle | Predicate::Ule => ord.is_le(),
+                Predicate::Sge | Predicate::Uge => ord.is_ge(),
+            };
+            return Value::Const(Bits::from_bool(result));
+        }
+        self.compare_node(predicate, lhs, rhs)
+    }
+
+    /// Applies an operation on operands of the same width.
+    fn op(&mut self, op: Op, lhs: &Value<Self::Node>, rhs: &Value<Self::Node>) -> Value<Self::Node> {
+        assert_eq!(lhs.width(), rhs.width());
+        match (op, lhs, rhs) {
+            (_, Value::Const(l), Value::Const(r)) => {
+                return Value::Const(match op {
+                    Op::Add => l.add(r),
+                    Op::Sub => l.sub(r),
+                    Op::Mul => l.mul(r),
+                    Op::DivU => l.udiv(r),
+                    Op::DivS => l.sdiv(r),
+                    Op::ModU => l.urem(r),
+                    Op::ModS => l.srem(r),
+                    Op::And => l.and(r),
+                    Op::Or => l.or(r),
+                    Op::Xor => l.xor(r),
+                    Op::Shl => l.shl(r.to_usize()),
+                    Op::ShrU => l.lshr(r.to_usize()),
+                    Op::ShrS => l.ashr(r.to_usize()),
+                })
+            }
+            (Op::And, ..) if lhs.is_zero() || rhs.is_ones() => return lhs.clone(),
+            (Op::And, ..) if rhs.is_zero() || lhs.is_ones() => return rhs.clone(),
+            (Op::Or | Op::Xor, ..) if lhs.is_zero() => return rhs.clone(),
+            (Op::Or | Op::Xor | Op::Add | Op::Sub | Op::Shl | Op::ShrU | Op::ShrS, ..) if rhs.is_zero() => {
+                return lhs.clone()
+            }
+            (Op::Or, ..) if lhs.is_ones() => return lhs.clone(),
+            (Op::Or, ..) if rhs.is_ones() => return rhs.clone(),
+            _ => {}
+        }
+        self.op_node(op, lhs, rhs)
+    }
+}
@@ -1,12 +1,25 @@
 //! Verilog IR.
 
 pub mod analysis;
+pub mod circt;
+mod dedup;
 mod integrate;
 /// TODO: make this pub(crate)
 mod ir;
+#[cfg(feature = "vir-json")]
+pub mod json;
+mod lower;
 /// TODO: make this pub(crate)
 pub mod opt;
-mod utils;
+pub mod param;
+pub mod sim;
+pub mod src_map;
+pub mod sv;
+pub(crate) mod utils;
+pub mod verilator;
+pub mod vhdl;
+pub mod yosys;
 
+pub use dedup::*;
 pub use integrate::*;
 pub use ir::*;
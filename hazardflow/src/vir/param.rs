@@ -0,0 +1,465 @@
+//! Verilog parameters for the const generics.
+//!
+//! Each instance of a function, e.g., `fifo::<4>` and `fifo::<8>`, is compiled into its own module. With
+//! `--parameterize`, [`gen_parameterized_verilog`] prints the instances of a function whose IR differs only in widths,
+//! i.e., the widths of the signals and the sizes of the arrays, and the widths of the sized numbers, as a single module,
+//! where the widths become `parameter`s overridden by the instantiations:
+//!
+//! ```verilog
+//! module fifo #(
+//!     parameter N = 4,
+//!     parameter PARAM_0 = 2'd1
+//! )
+//! (
+//!     ...
+//!
+//! fifo #(
+//!     .N(8),
+//!     .PARAM_0(3'd1)
+//! )
+//! fifo_inst (
+//!     ...
+//! ```
+//!
+//! The instances are compared on their IR, so the instances whose const generics also affect a value, e.g., a reset
+//! value or the number of entries compared with a counter, are not merged. A width is named after the const generic
+//! whose values it takes in all the instances. Other widths, such as the width of a counter of `N` entries, become
+//! `PARAM_<n>`, as do the sized numbers, which keep their values but not their widths: a parameter without a range
+//! takes the width of the value it is overridden with, e.g., `2'd3` and `3'd3`.
+//!
+//! The modules are parameterized from the leaves, so that the instances of a function instantiating the parameterized
+//! modules are parameterized by the overrides they pass.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use super::src_map::SrcLocAnnotator;
+use super::utils::*;
+use super::*;
+use crate::utils::indent;
+
+const INDENT: usize = 4;
+
+/// Width standing for a parameter until the code is generated, which is larger than any width in a design.
+const PLACEHOLDER: usize = 1 << 40;
+
+/// Const generic arguments of the instance of a function which a module is generated from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConstGenerics {
+    /// Path of the function.
+    pub item: String,
+
+    /// Names and values of the const arguments.
+    pub args: Vec<(String, u128)>,
+}
+
+/// Number in a module which may differ between the instances of a function without changing the hardware they
+/// describe, except for its size.
+#[derive(Debug)]
+enum Hole<'a> {
+    /// Width of a port or a signal, or size of an array.
+    Width(&'a mut usize),
+
+    /// Unsized number giving a width, i.e., the width of a part-select or the override of a parameter.
+    WidthNumber(&'a mut String),
+
+    /// Sized number, e.g., `8'd1`, which takes the width of the signal it is used with.
+    Sized(&'a mut String),
+}
+
+impl Hole<'_> {
+    /// Returns the number as it is written in Verilog.
+    fn value(&self) -> String {
+        match self {
+            Hole::Width(width) => width.to_string(),
+            Hole::WidthNumber(num) | Hole::Sized(num) => num.to_string(),
+        }
+    }
+
+    /// Erases the width, so that the instances which differ only in the widths become equal.
+    ///
+    /// A 1-bit signal is declared without a range, so a width of 1 is kept.
+    fn erase(self) {
+        match self {
+            Hole::Width(width) => {
+                if *width != 1 {
+                    *width = 2;
+                }
+            }
+            Hole::WidthNumber(num) => *num = "0".to_string(),
+            Hole::Sized(num) => *num = sized_value(num).unwrap(),
+        }
+    }
+}
+
+/// Returns the value of the sized number without its width, e.g., `'d1` for `8'd1` and `16'd0001`.
+///
+/// The leading zeros are dropped and a number of all `x`s or all `z`s is shortened to a single digit, as the number
+/// is extended to its width.
+fn sized_value(num: &str) -> Option<String> {
+    let (width, value) = num.split_once('\'')?;
+    if width.is_empty() || !width.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+
+    let signed = value.starts_with(['s', 'S']);
+    let value = value.trim_start_matches(['s', 'S']);
+    let base = value.chars().next().filter(|c| matches!(c.to_ascii_lowercase(), 'b' | 'o' | 'd' | 'h'))?;
+    let digits = value[1..].to_ascii_lowercase().replace('_', "");
+    let digits = match digits.trim_start_matches('0') {
+        "" => "0".to_string(),
+        digits if digits.bytes().all(|b| b == b'x') => "x".to_string(),
+        digits if digits.bytes().all(|b| b == b'z') => "z".to_string(),
+        digits => digits.to_string(),
+    };
+    Some(format!("'{}{}{}", if signed { "s" } else { "" }, base.to_ascii_lowercase(), digits))
+}
+
+/// Returns the hole of the number, where `width` says that the number gives a width.
+fn number_hole(num: &mut String, width: bool) -> Option<Hole<'_>> {
+    if width && num.parse::<usize>().is_ok() {
+        Some(Hole::WidthNumber(num))
+    } else if sized_value(num).is_some() {
+        Some(Hole::Sized(num))
+    } else {
+        None
+    }
+}
+
+/// Collects the holes of the IR, in the order of the code.
+trait Holes {
+    fn holes<'a>(&'a mut self, holes: &mut Vec<Hole<'a>>);
+}
+
+impl<T: Holes> Holes for Vec<T> {
+    fn holes<'a>(&'a mut self, holes: &mut Vec<Hole<'a>>) {
+        self.iter_mut().for_each(|e| e.holes(holes));
+    }
+}
+
+impl<T: Holes> Holes for Option<T> {
+    fn holes<'a>(&'a mut self, holes: &mut Vec<Hole<'a>>) {
+        if let Some(e) = self {
+            e.holes(holes);
+        }
+    }
+}
+
+impl<T: Holes> Holes for Box<T> {
+    fn holes<'a>(&'a mut self, holes: &mut Vec<Hole<'a>>) {
+        self.as_mut().holes(holes);
+    }
+}
+
+impl Holes for Module {
+    fn holes<'a>(&'a mut self, holes: &mut Vec<Hole<'a>>) {
+        self.port_decls.holes(holes);
+        self.module_items.holes(holes);
+    }
+}
+
+impl Holes for PortDeclaration {
+    fn holes<'a>(&'a mut self, holes: &mut Vec<Hole<'a>>) {
+        match self {
+            PortDeclaration::Input(width, _) | PortDeclaration::Output(width, _) => holes.push(Hole::Width(width)),
+        }
+    }
+}
+
+impl Holes for ModuleItem {
+    fn holes<'a>(&'a mut self, holes: &mut Vec<Hole<'a>>) {
+        match self {
+            ModuleItem::Declarations(decls) => decls.holes(holes),
+            ModuleItem::ContinuousAssigns(conts) => conts.holes(holes),
+            ModuleItem::ModuleInstantiation(module_inst) => module_inst.holes(holes),
+            ModuleItem::AlwaysConstruct(_, stmts) => stmts.holes(holes),
+            ModuleItem::Commented(_, _, items) => items.holes(holes),
+        }
+    }
+}
+
+impl Holes for Declaration {
+    fn holes<'a>(&'a mut self, holes: &mut Vec<Hole<'a>>) {
+        match self {
+            Declaration::Net(shape, _) => holes.extend(shape.dims_mut().map(Hole::Width)),
+            Declaration::Reg(shape, _, init) => {
+                holes.extend(shape.dims_mut().map(Hole::Width));
+                init.holes(holes);
+            }
+            Declaration::Integer(_) => {}
+        }
+    }
+}
+
+impl Holes for ContinuousAssign {
+    fn holes<'a>(&'a mut self, holes: &mut Vec<Hole<'a>>) {
+        self.0.holes(holes);
+        self.1.holes(holes);
+    }
+}
+
+impl Holes for ModuleInstantiation {
+    fn holes<'a>(&'a mut self, holes: &mut Vec<Hole<'a>>) {
+        // The parameters of the parameterized modules are widths or sized numbers.
+        for (_, param) in &mut self.params {
+            match param {
+                Expression::Primary(Primary::Number(num)) => holes.extend(number_hole(num, true)),
+                _ => param.holes(holes),
+            }
+        }
+        for (_, expr) in &mut self.port_connections {
+            expr.holes(holes);
+        }
+    }
+}
+
+impl Holes for Statement {
+    fn holes<'a>(&'a mut self, holes: &mut Vec<Hole<'a>>) {
+        match self {
+            Statement::BlockingAssignment(lvalue, expr, _) | Statement::NonblockingAssignment(lvalue, expr, _) => {
+                lvalue.holes(holes);
+                expr.holes(holes);
+            }
+            Statement::Conditional(cond_expr_pairs, else_stmt, _) => {
+                for (cond, stmts) in cond_expr_pairs {
+                    cond.holes(holes);
+                    stmts.holes(holes);
+                }
+                else_stmt.holes(holes);
+            }
+            Statement::Loop(_, count, stmts, _) => {
+                count.holes(holes);
+                stmts.holes(holes);
+            }
+            Statement::Case(case_expr, case_items, default, _) => {
+                case_expr.holes(holes);
+                for (cond, stmts) in case_items {
+                    cond.holes(holes);
+                    stmts.holes(holes);
+                }
+                default.holes(holes);
+            }
+            Statement::Display(_, args, _) => args.holes(holes),
+            Statement::Fatal => {}
+        }
+    }
+}
+
+impl Holes for Expression {
+    fn holes<'a>(&'a mut self, holes: &mut Vec<Hole<'a>>) {
+        match self {
+            Expression::Primary(prim) | Expression::Unary(_, prim) => prim.holes(holes),
+            Expression::Binary(lhs, _, rhs) => {
+                lhs.holes(holes);
+                rhs.holes(holes);
+            }
+            Expression::Conditional(cond, then_expr, else_expr) => {
+                cond.holes(holes);
+                then_expr.holes(holes);
+                else_expr.holes(holes);
+            }
+        }
+    }
+}
+
+impl Holes for Primary {
+    fn holes<'a>(&'a mut self, holes: &mut Vec<Hole<'a>>) {
+        match self {
+            Primary::Number(num) => holes.extend(number_hole(num, false)),
+            Primary::HierarchicalIdentifier(_, range) => match range {
+                Some(Range::Index(index)) => index.holes(holes),
+                Some(Range::Range(base, offset)) => {
+                    base.holes(holes);
+                    match offset.as_mut() {
+                        Expression::Primary(Primary::Number(num)) => holes.extend(number_hole(num, true)),
+                        offset => offset.holes(holes),
+                    }
+                }
+                None => {}
+            },
+            Primary::Concatenation(concat) => concat.exprs.holes(holes),
+            Primary::MultipleConcatenation(count, concat) => {
+                holes.push(Hole::Width(count));
+                concat.exprs.holes(holes);
+            }
+            Primary::MintypmaxExpression(expr) => expr.holes(holes),
+        }
+    }
+}
+
+/// Returns the holes of the module.
+fn holes(module: &mut Module) -> Vec<Hole<'_>> {
+    let mut holes = vec![];
+    module.holes(&mut holes);
+    holes
+}
+
+/// Returns the height of the module in the hierarchy, which is 0 if it does not instantiate the given modules.
+fn height(
+    name: &str,
+    modules: &BTreeMap<String, (Module, ConstGenerics)>,
+    heights: &mut HashMap<String, usize>,
+) -> usize {
+    if let Some(height) = heights.get(name) {
+        return *height;
+    }
+
+    let height = submodules(&modules[name].0.module_items)
+        .into_iter()
+        .filter(|submodule| modules.contains_key(*submodule))
+        .map(|submodule| height(submodule, modules, heights) + 1)
+        .max()
+        .unwrap_or(0);
+    heights.insert(name.to_string(), height);
+    height
+}
+
+/// Parameterized module.
+#[derive(Debug)]
+struct Parameterized {
+    /// Verilog code.
+    verilog: String,
+
+    /// Name of the module, and the parameters to instantiate each instance with.
+    insts: Vec<(String, Vec<(String, Expression)>)>,
+}
+
+/// Parameterizes the modules, whose IR differs only in the holes, into the first one.
+fn parameterize(modules: &[&(Module, ConstGenerics)], annotator: &SrcLocAnnotator<'_>) -> Parameterized {
+    let (canonical, generics) = modules[0];
+    let values = modules
+        .iter()
+        .map(|(module, _)| holes(&mut module.clone()).iter().map(Hole::value).collect::<Vec<_>>())
+        .collect::<Vec<_>>();
+    let decls = extract_decls(canonical).into_iter().collect::<HashSet<_>>();
+
+    // Names and values of the parameters, for each different sequence of the numbers in the instances.
+    let mut params: Vec<(String, Vec<String>)> = vec![];
+    let mut params_of_holes = HashMap::new();
+    for i in 0..values[0].len() {
+        let values = values.iter().map(|values| values[i].clone()).collect::<Vec<_>>();
+        if values.iter().all(|value| *value == values[0]) {
+            continue;
+        }
+
+        let param = match params.iter().position(|(_, param_values)| *param_values == values) {
+            Some(param) => param,
+            None => {
+                // A sized number is never equal to the value of a const generic, so only widths are named after them.
+                let const_generic = generics.args.iter().map(|(name, _)| name).find(|name| {
+                    !decls.contains(name.as_str())
+                        && modules.iter().zip(&values).all(|((_, generics), value)| {
+                            generics.args.iter().any(|(arg, arg_value)| arg == *name && arg_value.to_string() == *value)
+                        })
+                });
+                let name = match const_generic {
+                    Some(name) => name.clone(),
+                    None => (0..)
+                        .map(|n| format!("PARAM_{}", n))
+                        .find(|name| !decls.contains(name.as_str()) && params.iter().all(|(param, _)| param != name))
+                        .unwrap(),
+                };
+                params.push((name, values));
+                params.len() - 1
+            }
+        };
+        params_of_holes.insert(i, param);
+    }
+
+    // The widths are `usize`s in the IR, so they are filled with placeholders replaced by the names after the code is
+    // generated.
+    let placeholder = |param: usize| PLACEHOLDER + param;
+    let mut module = canonical.clone();
+    for (i, hole) in holes(&mut module).into_iter().enumerate() {
+        if let Some(param) = params_of_holes.get(&i) {
+            match hole {
+                Hole::Width(width) => *width = placeholder(*param),
+                Hole::WidthNumber(num) | Hole::Sized(num) => *num = params[*param].0.clone(),
+            }
+        }
+    }
+    let mut verilog = module.gen_verilog(annotator);
+    for (param, (name, _)) in params.iter().enumerate() {
+        verilog = verilog.replace(&placeholder(param).to_string(), name);
+    }
+
+    if !params.is_empty() {
+        let decls =
+            params.iter().map(|(name, values)| format!("parameter {} = {}", name, values[0])).collect::<Vec<_>>();
+        verilog = verilog.replacen(
+            &format!("module {}\n", canonical.name),
+            &format!("module {} #(\n{}\n)\n", canonical.name, indent(decls.join(",\n"), INDENT)),
+            1,
+        );
+    }
+
+    let insts = modules
+        .iter()
+        .enumerate()
+        .map(|(i, (module, _))| {
+            let params =
+                params.iter().map(|(name, values)| (name.clone(), Expression::number(values[i].clone()))).collect();
+            (module.name.clone(), params)
+        })
+        .collect();
+
+    Parameterized { verilog, insts }
+}
+
+/// Generates Verilog code of the modules, where the instances of each function that differ only in widths are
+/// parameterized into a single module.
+///
+/// Returns the name and Verilog code of each module, sorted by the name.
+pub fn gen_parameterized_verilog(
+    modules: Vec<(Module, ConstGenerics)>,
+    annotator: &SrcLocAnnotator<'_>,
+) -> Vec<(String, String)> {
+    let mut modules = modules
+        .into_iter()
+        .map(|(module, generics)| (module.name.clone(), (module, generics)))
+        .collect::<BTreeMap<_, _>>();
+
+    let mut heights = HashMap::new();
+    for name in modules.keys() {
+        height(name, &modules, &mut heights);
+    }
+
+    let mut verilogs = BTreeMap::new();
+    for level in 0..=heights.values().copied().max().unwrap_or(0) {
+        // Instances of the same function with the same IR, except for the holes.
+        let mut classes = BTreeMap::<_, Vec<_>>::new();
+        for (name, module) in &modules {
+            if heights[name] == level {
+                let mut skeleton = renamed(&module.0, "");
+                holes(&mut skeleton).into_iter().for_each(Hole::erase);
+                classes.entry((module.1.item.clone(), skeleton.to_string())).or_default().push(module);
+            }
+        }
+
+        // Module names and the parameters of the instances, which are instantiated with the parameterized modules.
+        let mut insts = HashMap::new();
+        for class in classes.values() {
+            if let [(module, _)] = class[..] {
+                verilogs.insert(module.name.clone(), module.gen_verilog(annotator));
+                continue;
+            }
+
+            let parameterized = parameterize(class, annotator);
+            let name = class[0].0.name.clone();
+            verilogs.insert(name.clone(), parameterized.verilog);
+            for (inst, params) in parameterized.insts {
+                insts.insert(inst, (name.clone(), params));
+            }
+        }
+
+        for (module, _) in modules.values_mut() {
+            for_each_inst(&mut module.module_items, &mut |inst| {
+                if let Some((name, params)) = insts.get(&inst.module_name) {
+                    inst.module_name = name.clone();
+                    inst.params = params.clone();
+                }
+            });
+        }
+    }
+
+    verilogs.into_iter().collect()
+}
@@ -0,0 +1,462 @@
+//! Two-state bit vectors.
+
+use std::cmp::Ordering;
+use std::fmt;
+
+const WORD: usize = u64::BITS as usize;
+
+/// Fixed-width two-state bit vector.
+///
+/// Bits are stored in little-endian 64-bit words. Bits above `width` are always kept zero.
+#[derive(Default, Clone, PartialEq, Eq, Hash)]
+pub struct Bits {
+    width: usize,
+    words: Vec<u64>,
+}
+
+impl Bits {
+    /// Creates a zero vector of the given width.
+    pub fn zero(width: usize) -> Self {
+        Self { width, words: vec![0; words_for(width)] }
+    }
+
+    /// Creates a vector of the given width with all bits set.
+    pub fn ones(width: usize) -> Self {
+        let mut bits = Self { width, words: vec![u64::MAX; words_for(width)] };
+        bits.mask();
+        bits
+    }
+
+    /// Creates a vector from `value`, truncated or zero-extended to `width`.
+    pub fn from_u64(value: u64, width: usize) -> Self {
+        Self::from_u128(value as u128, width)
+    }
+
+    /// Creates a vector from `value`, truncated or zero-extended to `width`.
+    pub fn from_u128(value: u128, width: usize) -> Self {
+        Self::from_words(&[value as u64, (value >> WORD) as u64], width)
+    }
+
+    /// Creates a 1-bit vector.
+    pub fn from_bool(value: bool) -> Self {
+        Self::from_u64(value as u64, 1)
+    }
+
+    /// Creates a vector from little-endian words, truncated or zero-extended to `width`.
+    pub fn from_words(words: &[u64], width: usize) -> Self {
+        let mut bits = Self::zero(width);
+        for (dst, src) in bits.words.iter_mut().zip(words) {
+            *dst = *src;
+        }
+        bits.mask();
+        bits
+    }
+
+    /// Creates a vector from a binary string, most significant bit first.
+    ///
+    /// `x`, `z` and `?` digits are read as `0`. Underscores are ignored.
+    pub fn from_binary_str(digits: &str, width: usize) -> Option<Self> {
+        let mut bits = Self::zero(width);
+        for (i, c) in digits.chars().rev().filter(|c| *c != '_').enumerate() {
+            match c {
+                '0' | 'x' | 'X' | 'z' | 'Z' | '?' => {}
+                '1' => {
+                    if i < width {
+                        bits.set_bit(i, true)
+                    }
+                }
+                _ => return None,
+            }
+        }
+        Some(bits)
+    }
+
+    /// Creates a vector from digits in the given radix (2, 8, 10 or 16), most significant digit first.
+    ///
+    /// `x`, `z` and `?` digits are read as `0`. Underscores are ignored.
+    pub fn from_str_radix(digits: &str, radix: u32, width: usize) -> Option<Self> {
+        let mut bits = Self::zero(width);
+        for c in digits.chars().filter(|c| *c != '_') {
+            let digit = if matches!(c, 'x' | 'X' | 'z' | 'Z' | '?') { 0 } else { c.to_digit(radix)? };
+            bits = bits.mul_small(radix as u64).add(&Self::from_u64(digit as u64, width));
+        }
+        Some(bits)
+    }
+
+    /// Returns the width.
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Returns the little-endian words.
+    pub fn words(&self) -> &[u64] {
+        &self.words
+    }
+
+    /// Returns the lower 64 bits.
+    pub fn to_u64(&self) -> u64 {
+        self.words.first().copied().unwrap_or(0)
+    }
+
+    /// Returns the lower 128 bits.
+    pub fn to_u128(&self) -> u128 {
+        self.to_u64() as u128 | (self.words.get(1).copied().unwrap_or(0) as u128) << WORD
+    }
+
+    /// Returns the value as `usize`, saturating at `usize::MAX`.
+    pub fn to_usize(&self) -> usize {
+        if self.words.iter().skip(1).any(|w| *w != 0) {
+            usize::MAX
+        } else {
+            usize::try_from(self.to_u64()).unwrap_or(usize::MAX)
+        }
+    }
+
+    /// Returns the value interpreted as a signed integer, saturating at the bounds of `i64`.
+    pub fn to_i64(&self) -> i64 {
+        if !self.msb() {
+            return i64::try_from(self.to_usize()).unwrap_or(i64::MAX);
+        }
+        let magnitude = self.neg();
+        if magnitude.words.iter().skip(1).any(|w| *w != 0) || magnitude.to_u64() > i64::MAX as u64 + 1 {
+            i64::MIN
+        } else {
+            (magnitude.to_u64() as i64).wrapping_neg()
+        }
+    }
+
+    /// Returns `true` if every bit is zero.
+    pub fn is_zero(&self) -> bool {
+        self.words.iter().all(|w| *w == 0)
+    }
+
+    /// Returns the bit at `index`. Bits beyond the width read as zero.
+    pub fn bit(&self, index: usize) -> bool {
+        index < self.width && (self.words[index / WORD] >> (index % WORD)) & 1 == 1
+    }
+
+    /// Sets the bit at `index`. Writes beyond the width are ignored.
+    pub fn set_bit(&mut self, index: usize, value: bool) {
+        if index < self.width {
+            let mask = 1 << (index % WORD);
+            if value {
+                self.words[index / WORD] |= mask;
+            } else {
+                self.words[index / WORD] &= !mask;
+            }
+        }
+    }
+
+    /// Returns the most significant bit.
+    pub fn msb(&self) -> bool {
+        self.width > 0 && self.bit(self.width - 1)
+    }
+
+    /// Returns `width` bits starting from `lo`. Bits beyond the width read as zero.
+    pub fn slice(&self, lo: usize, width: usize) -> Self {
+        let mut out = Self::zero(width);
+        if lo >= self.width {
+            return out;
+        }
+        let (shift, bit) = (lo / WORD, lo % WORD);
+        for (i, dst) in out.words.iter_mut().enumerate() {
+            let low = self.words.get(i + shift).copied().unwrap_or(0);
+            let high = self.words.get(i + shift + 1).copied().unwrap_or(0);
+            *dst = if bit == 0 { low } else { (low >> bit) | (high << (WORD - bit)) };
+        }
+        out.mask();
+        out
+    }
+
+    /// Overwrites the bits starting from `lo` with `value`. Writes beyond the width are ignored.
+    pub fn set_slice(&mut self, lo: usize, value: &Bits) {
+        if lo >= self.width {
+            return;
+        }
+        let width = value.width.min(self.width - lo);
+        if lo % WORD == 0 && width % WORD == 0 {
+            let start = lo / WORD;
+            self.words[start..start + width / WORD].copy_from_slice(&value.words[..width / WORD]);
+            return;
+        }
+        for i in 0..width {
+            self.set_bit(lo + i, value.bit(i));
+        }
+    }
+
+    /// Truncates or extends to `width`. Extension copies the sign bit if `signed`.
+    pub fn resize(&self, width: usize, signed: bool) -> Self {
+        if width == self.width {
+            return self.clone();
+        }
+        let mut out = Self::from_words(&self.words, width);
+        if signed && width > self.width && self.msb() {
+            for i in self.width..width {
+                out.set_bit(i, true);
+            }
+        }
+        out
+    }
+
+    /// Concatenates `self` (upper bits) with `lower`.
+    pub fn concat(&self, lower: &Bits) -> Self {
+        let mut out = lower.resize(lower.width + self.width, false);
+        out.set_slice(lower.width, self);
+        out
+    }
+
+    /// Bitwise negation.
+    pub fn not(&self) -> Self {
+        let mut out = Self { width: self.width, words: self.words.iter().map(|w| !w).collect() };
+        out.mask();
+        out
+    }
+
+    /// Bitwise and.
+    pub fn and(&self, rhs: &Bits) -> Self {
+        self.zip_words(rhs, |l, r| l & r)
+    }
+
+    /// Bitwise or.
+    pub fn or(&self, rhs: &Bits) -> Self {
+        self.zip_words(rhs, |l, r| l | r)
+    }
+
+    /// Bitwise xor.
+    pub fn xor(&self, rhs: &Bits) -> Self {
+        self.zip_words(rhs, |l, r| l ^ r)
+    }
+
+    /// Bitwise xnor.
+    pub fn xnor(&self, rhs: &Bits) -> Self {
+        self.xor(rhs).not()
+    }
+
+    /// Wrapping addition.
+    pub fn add(&self, rhs: &Bits) -> Self {
+        let mut out = Self::zero(self.width);
+        let mut carry = false;
+        for (i, dst) in out.words.iter_mut().enumerate() {
+            let (sum, c1) = self.words[i].overflowing_add(rhs.words.get(i).copied().unwrap_or(0));
+            let (sum, c2) = sum.overflowing_add(carry as u64);
+            *dst = sum;
+            carry = c1 || c2;
+        }
+        out.mask();
+        out
+    }
+
+    /// Wrapping subtraction.
+    pub fn sub(&self, rhs: &Bits) -> Self {
+        self.add(&rhs.resize(self.width, false).neg())
+    }
+
+    /// Wrapping two's complement negation.
+    pub fn neg(&self) -> Self {
+        self.not().add(&Self::from_u64(1, self.width))
+    }
+
+    /// Wrapping multiplication.
+    pub fn mul(&self, rhs: &Bits) -> Self {
+        let n = self.words.len();
+        let mut acc = vec![0u64; n];
+        for (i, l) in self.words.iter().enumerate() {
+            if *l == 0 {
+                continue;
+            }
+            let mut carry = 0u128;
+            for j in 0..(n - i) {
+                let r = rhs.words.get(j).copied().unwrap_or(0);
+                let t = (*l as u128) * (r as u128) + acc[i + j] as u128 + carry;
+                acc[i + j] = t as u64;
+                carry = t >> WORD;
+            }
+        }
+        Self::from_words(&acc, self.width)
+    }
+
+    /// Unsigned division. Division by zero yields zero.
+    pub fn udiv(&self, rhs: &Bits) -> Self {
+        self.udivrem(rhs).0
+    }
+
+    /// Unsigned remainder. Division by zero yields zero.
+    pub fn urem(&self, rhs: &Bits) -> Self {
+        self.udivrem(rhs).1
+    }
+
+    /// Signed division, truncating towards zero. Division by zero yields zero.
+    pub fn sdiv(&self, rhs: &Bits) -> Self {
+        let (q, _) = self.abs().udivrem(&rhs.abs());
+        if self.msb() != rhs.msb() {
+            q.neg()
+        } else {
+            q
+        }
+    }
+
+    /// Signed remainder, with the sign of the dividend. Division by zero yields zero.
+    pub fn srem(&self, rhs: &Bits) -> Self {
+        let (_, r) = self.abs().udivrem(&rhs.abs());
+        if self.msb() {
+            r.neg()
+        } else {
+            r
+        }
+    }
+
+    /// Logical shift left.
+    pub fn shl(&self, amount: usize) -> Self {
+        if amount >= self.width {
+            return Self::zero(self.width);
+        }
+        let mut out = Self::zero(self.width);
+        out.set_slice(amount, &self.slice(0, self.width - amount));
+        out
+    }
+
+    /// Logical shift right.
+    pub fn lshr(&self, amount: usize) -> Self {
+        self.slice(amount, self.width)
+    }
+
+    /// Arithmetic shift right.
+    pub fn ashr(&self, amount: usize) -> Self {
+        if !self.msb() {
+            return self.lshr(amount);
+        }
+        let amount = amount.min(self.width);
+        self.lshr(amount).or(&Self::ones(amount).resize(self.width, false).shl(self.width - amount))
+    }
+
+    /// Unsigned comparison.
+    pub fn ucmp(&self, rhs: &Bits) -> Ordering {
+        let n = self.words.len().max(rhs.words.len());
+        for i in (0..n).rev() {
+            let (l, r) = (self.words.get(i).copied().unwrap_or(0), rhs.words.get(i).copied().unwrap_or(0));
+            match l.cmp(&r) {
+                Ordering::Equal => continue,
+                ord => return ord,
+            }
+        }
+        Ordering::Equal
+    }
+
+    /// Signed comparison of two vectors of the same width.
+    pub fn scmp(&self, rhs: &Bits) -> Ordering {
+        match (self.msb(), rhs.msb()) {
+            (true, false) => Ordering::Less,
+            (false, true) => Ordering::Greater,
+            _ => self.ucmp(rhs),
+        }
+    }
+
+    /// Formats the value in the given radix (2, 8, 10 or 16) without padding.
+    pub fn to_str_radix(&self, radix: u32) -> String {
+        if self.is_zero() {
+            return "0".to_string();
+        }
+        let mut digits = vec![];
+        let mut rest = self.clone();
+        while !rest.is_zero() {
+            let (q, r) = rest.divrem_small(radix as u64);
+            digits.push(std::char::from_digit(r as u32, radix).unwrap());
+            rest = q;
+        }
+        digits.iter().rev().collect()
+    }
+
+    fn abs(&self) -> Self {
+        if self.msb() {
+            self.neg()
+        } else {
+            self.clone()
+        }
+    }
+
+    fn udivrem(&self, rhs: &Bits) -> (Self, Self) {
+        if rhs.is_zero() {
+            return (Self::zero(self.width), Self::zero(self.width));
+        }
+        if self.width <= 2 * WORD && rhs.width <= 2 * WORD {
+            let (l, r) = (self.to_u128(), rhs.to_u128());
+            return (Self::from_u128(l / r, self.width), Self::from_u128(l % r, self.width));
+        }
+        let divisor = rhs.resize(self.width.max(rhs.width) + 1, false);
+        let mut quotient = Self::zero(self.width);
+        let mut remainder = Self::zero(divisor.width);
+        for i in (0..self.width).rev() {
+            remainder = remainder.shl(1);
+            remainder.set_bit(0, self.bit(i));
+            if remainder.ucmp(&divisor) != Ordering::Less {
+                remainder = remainder.sub(&divisor);
+                quotient.set_bit(i, true);
+            }
+        }
+        (quotient, remainder.resize(self.width, false))
+    }
+
+    fn divrem_small(&self, divisor: u64) -> (Self, u64) {
+        let mut quotient = Self::zero(self.width);
+        let mut rem = 0u128;
+        for i in (0..self.words.len()).rev() {
+            let cur = (rem << WORD) | self.words[i] as u128;
+            quotient.words[i] = (cur / divisor as u128) as u64;
+            rem = cur % divisor as u128;
+        }
+        (quotient, rem as u64)
+    }
+
+    fn mul_small(&self, factor: u64) -> Self {
+        self.mul(&Self::from_u64(factor, self.width))
+    }
+
+    fn zip_words(&self, rhs: &Bits, f: impl Fn(u64, u64) -> u64) -> Self {
+        let words = self.words.iter().enumerate().map(|(i, l)| f(*l, rhs.words.get(i).copied().unwrap_or(0))).collect();
+        let mut out = Self { width: self.width, words };
+        out.mask();
+        out
+    }
+
+    fn mask(&mut self) {
+        if self.width % WORD != 0 {
+            if let Some(last) = self.words.last_mut() {
+                *last &= (1 << (self.width % WORD)) - 1;
+            }
+        }
+    }
+}
+
+fn words_for(width: usize) -> usize {
+    (width + WORD - 1) / WORD
+}
+
+impl fmt::Debug for Bits {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}'h{}", self.width, self.to_str_radix(16))
+    }
+}
+
+impl fmt::Display for Bits {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad_integral(true, "", &self.to_str_radix(10))
+    }
+}
+
+impl fmt::LowerHex for Bits {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad_integral(true, "0x", &self.to_str_radix(16))
+    }
+}
+
+impl fmt::Binary for Bits {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad_integral(true, "0b", &self.to_str_radix(2))
+    }
+}
+
+impl From<bool> for Bits {
+    fn from(value: bool) -> Self {
+        Self::from_bool(value)
+    }
+}
@@ -0,0 +1,428 @@
+//! Cycle-accurate simulator for Verilog IR.
+//!
+//! The simulator interprets a flattened [`Module`] (see [`integrate()`]) without going through an external Verilog
+//! simulator. Values are two-state: `x` and `z` literals read as zero, and out-of-range selects read as zero.
+//!
+//! Every rising edge of the implicit `clk` is one call to [`Simulator::step`]. Combinational logic (continuous
+//! assignments and `always @*` blocks) is settled lazily before values are sampled and before each edge.
+//! `$display` output is collected with the current cycle, and `$fatal` aborts the step with an error. System tasks in
+//! combinational processes are executed once per cycle, after the logic settles and before the edge.
+//!
+//! The waveform of every signal can be recorded with [`Simulator::trace`] and written as a VCD file with
+//! [`Simulator::write_vcd`]. Tools such as GTKWave's `vcd2fst` convert it into FST.
+
+mod bits;
+pub(crate) mod netlist;
+mod rust;
+mod vcd;
+
+use std::collections::{BTreeSet, HashMap};
+use std::path::Path;
+
+pub use bits::Bits;
+use netlist::*;
+pub use rust::gen_rust_sim;
+use vcd::*;
+pub(crate) use vcd::{instance_scopes, Scopes};
+pub use vcd::{read_vcd, VcdDump, VcdVar};
+
+use crate::compiler::error::{VirgenError, VirgenResult};
+use crate::vir::*;
+
+/// Upper bound on the iterations of one `for` loop.
+const LOOP_LIMIT: usize = 1 << 24;
+
+/// Simulator for a flattened module.
+#[derive(Debug, Clone)]
+pub struct Simulator {
+    name: String,
+    netlist: Netlist,
+    port_decls: Vec<PortDeclaration>,
+    values: Vec<Bits>,
+
+    /// Combinational processes that read each signal.
+    readers: Vec<Vec<usize>>,
+
+    /// Combinational processes to be evaluated.
+    dirty: BTreeSet<usize>,
+
+    /// Combinational processes that contain system tasks.
+    tasks: Vec<usize>,
+
+    /// Whether system tasks in combinational processes take effect.
+    strobe: bool,
+
+    cycle: u64,
+    displays: Vec<(u64, String)>,
+
+    /// Waveform being recorded, if any.
+    waveform: Option<Waveform>,
+}
+
+/// Pending write of `value` into the bits of a signal starting from `lo`.
+type Write = (SignalId, usize, Bits);
+
+impl Simulator {
+    /// Creates a new simulator for the module.
+    ///
+    /// The module should not contain module instantiations; integrate submodules first, or use
+    /// [`Simulator::from_modules`]. Register initializers and `initial` blocks are executed here.
+    pub fn new(module: &Module) -> VirgenResult<Self> {
+        let netlist = Netlist::new(module)?;
+
+        let mut readers = vec![vec![]; netlist.signals.len()];
+        for (pos, process) in netlist.comb.iter().enumerate() {
+            for id in &process.reads {
+                readers[*id].push(pos);
+            }
+        }
+
+        let tasks = (0..netlist.comb.len()).filter(|pos| has_task(&netlist.comb[*pos].stmts)).collect();
+
+        let mut sim = Self {
+            values: netlist.signals.iter().map(|signal| Bits::zero(signal.total_width())).collect(),
+            dirty: (0..netlist.comb.len()).collect(),
+            name: module.name.clone(),
+            port_decls: module.port_decls.clone(),
+            netlist,
+            readers,
+            tasks,
+            strobe: false,
+            cycle: 0,
+            displays: vec![],
+            waveform: None,
+        };
+
+        for (id, init) in sim.netlist.inits.clone() {
+            let value = init.eval(&sim.values, sim.values[id].width(), init.signed);
+            sim.write(None, (id, 0, value));
+        }
+        for stmts in sim.netlist.initials.clone() {
+            let mut nbas = vec![];
+            sim.exec(&stmts, None, &mut nbas)?;
+            sim.commit(nbas);
+        }
+        sim.settle()?;
+
+        Ok(sim)
+    }
+
+    /// Integrates `modules` into `top` and creates a new simulator for it.
+    ///
+    /// The scopes of the waveform follow the module instantiations in `modules`.
+    pub fn from_modules(modules: HashMap<String, Module>, top: &str) -> VirgenResult<Self> {
+        if !modules.contains_key(top) {
+            return Err(sim_error(format!("top module `{}` does not exist", top)));
+        }
+        let scopes = instance_scopes(&modules, top);
+        let mut sim = Self::new(&integrate(modules, top.to_string()))?;
+        sim.set_scopes(&scopes);
+        Ok(sim)
+    }
+
+    /// Puts the signals in the waveform into the scopes of the submodules declaring them. The other signals are in
+    /// the scope of the module.
+    pub(crate) fn set_scopes(&mut self, scopes: &Scopes) {
+        for signal in &mut self.netlist.signals {
+            signal.scope = scopes.get(&signal.name).cloned().unwrap_or_default();
+        }
+    }
+
+    /// Returns the port declarations of the simulated module.
+    pub fn port_decls(&self) -> &[PortDeclaration] {
+        &self.port_decls
+    }
+
+    /// Returns the number of rising edges simulated so far.
+    pub fn cycle(&self) -> u64 {
+        self.cycle
+    }
+
+    /// Drives an input port. The value is truncated or zero-extended to the port width.
+    pub fn poke(&mut self, port: &str, value: Bits) -> VirgenResult<()> {
+        let id = self.netlist.ids.get(port).copied();
+        let Some(id) = id.filter(|id| self.netlist.signals[*id].kind == SignalKind::Input) else {
+            return Err(sim_error(format!("`{}` is not an input port", port)));
+        };
+        let value = value.resize(self.values[id].width(), false);
+        self.write(None, (id, 0, value));
+        Ok(())
+    }
+
+    /// Drives an input port with an integer.
+    pub fn poke_u64(&mut self, port: &str, value: u64) -> VirgenResult<()> {
+        self.poke(port, Bits::from_u64(value, u64::BITS as usize))
+    }
+
+    /// Samples a port, net, or register after settling combinational logic. Arrays are returned flattened, with
+    /// element `0` in the least significant bits.
+    pub fn peek(&mut self, name: &str) -> VirgenResult<Bits> {
+        let id = self.netlist.ids.get(name).copied().ok_or_else(|| sim_error(format!("unknown signal `{}`", name)))?;
+        self.settle()?;
+        Ok(self.values[id].clone())
+    }
+
+    /// Samples a signal as an integer. Bits above 64 are dropped.
+    pub fn peek_u64(&mut self, name: &str) -> VirgenResult<u64> {
+        self.peek(name).map(|bits| bits.to_u64())
+    }
+
+    /// Simulates one rising edge of the clock.
+    pub fn step(&mut self) -> VirgenResult<()> {
+        self.settle()?;
+        self.exec_comb_tasks()?;
+        self.sample(PERIOD * self.cycle + PERIOD / 2, false);
+
+        let mut nbas = vec![];
+        for stmts in self.netlist.seq.clone() {
+            self.exec(&stmts, None, &mut nbas)?;
+        }
+        self.commit(nbas);
+        self.cycle += 1;
+
+        self.settle()?;
+        self.sample(PERIOD * self.cycle, true);
+        Ok(())
+    }
+
+    /// Simulates `cycles` rising edges of the clock.
+    pub fn run(&mut self, cycles: usize) -> VirgenResult<()> {
+        for _ in 0..cycles {
+            self.step()?;
+        }
+        Ok(())
+    }
+
+    /// Holds `rst` high for `cycles` rising edges, then releases it.
+    pub fn reset(&mut self, cycles: usize) -> VirgenResult<()> {
+        self.poke_u64("rst", 1)?;
+        self.run(cycles)?;
+        self.poke_u64("rst", 0)?;
+        self.settle()
+    }
+
+    /// Returns the `$display` outputs so far, with the cycles they were printed at.
+    pub fn displays(&self) -> &[(u64, String)] {
+        &self.displays
+    }
+
+    /// Takes the `$display` outputs so far.
+    pub fn take_displays(&mut self) -> Vec<(u64, String)> {
+        std::mem::take(&mut self.displays)
+    }
+
+    /// Starts recording the waveform of every signal from the current cycle.
+    pub fn trace(&mut self) -> VirgenResult<()> {
+        self.settle()?;
+        self.waveform = Some(Waveform::new(&self.netlist, &self.values, PERIOD * self.cycle));
+        Ok(())
+    }
+
+    /// Writes the waveform recorded since [`Simulator::trace`] as a VCD file.
+    pub fn write_vcd(&self, path: impl AsRef<Path>) -> VirgenResult<()> {
+        let Some(waveform) = &self.waveform else {
+            return Err(sim_error("the waveform is not recorded; call `Simulator::trace` first".to_string()));
+        };
+        std::fs::write(path, waveform.to_vcd(&self.netlist, &self.name)).map_err(|err| VirgenError::Fs { err })
+    }
+
+    /// Records the values in the waveform, if any.
+    fn sample(&mut self, time: u64, clk: bool) {
+        if let Some(waveform) = &mut self.waveform {
+            waveform.sample(&self.netlist, &self.values, time, clk);
+        }
+    }
+
+    /// Evaluates combinational processes until no signal changes.
+    fn settle(&mut self) -> VirgenResult<()> {
+        let limit = 64 * self.netlist.comb.len() + 1024;
+        let mut count = 0;
+        while let Some(pos) = self.dirty.pop_first() {
+            count += 1;
+            if count > limit {
+                return Err(sim_error(format!("combinational logic did not converge at cycle {}", self.cycle)));
+            }
+            let stmts = std::mem::take(&mut self.netlist.comb[pos].stmts);
+            let result = self.exec(&stmts, Some(pos), &mut vec![]);
+            self.netlist.comb[pos].stmts = stmts;
+            result?;
+        }
+        Ok(())
+    }
+
+    /// Executes the system tasks of the settled combinational processes.
+    ///
+    /// The processes are executed again as a whole, which does not change any signal because the logic is settled.
+    fn exec_comb_tasks(&mut self) -> VirgenResult<()> {
+        self.strobe = true;
+        let result = self.tasks.clone().into_iter().try_for_each(|pos| {
+            let stmts = std::mem::take(&mut self.netlist.comb[pos].stmts);
+            let result = self.exec(&stmts, Some(pos), &mut vec![]);
+            self.netlist.comb[pos].stmts = stmts;
+            result
+        });
+        self.strobe = false;
+        result
+    }
+
+    /// Executes statements. `process` is the combinational process being executed, if any.
+    fn exec(&mut self, stmts: &[Stmt], process: Option<usize>, nbas: &mut Vec<Write>) -> VirgenResult<()> {
+        for stmt in stmts {
+            match stmt {
+                Stmt::Assign { lhs, rhs, blocking } => {
+                    let width = lhs.width(&self.netlist.signals);
+                    let value = rhs.eval(&self.values, width.max(rhs.width), rhs.signed).resize(width, false);
+                    let writes = self.resolve(lhs, value);
+                    if *blocking {
+                        writes.into_iter().for_each(|write| self.write(process, write));
+                    } else {
+                        nbas.extend(writes);
+                    }
+                }
+                Stmt::If(branches, default) => {
+                    let taken = branches.iter().find(|(cond, _)| !cond.eval_self(&self.values).is_zero());
+                    self.exec(taken.map_or(default, |(_, stmts)| stmts), process, nbas)?;
+                }
+                Stmt::Case(sel, items, default) => {
+                    let taken = items.iter().find(|(item, _)| {
+                        let (width, signed) = (sel.width.max(item.width), sel.signed && item.signed);
+                        sel.eval(&self.values, width, signed) == item.eval(&self.values, width, signed)
+                    });
+                    self.exec(taken.map_or(default, |(_, stmts)| stmts), process, nbas)?;
+                }
+                Stmt::Loop(var, count, body) => {
+                    let width = self.values[*var].width();
+                    self.write(process, (*var, 0, Bits::zero(width)));
+                    let mut iter = 0;
+                    loop {
+                        let count = count.eval(&self.values, width.max(count.width), count.signed);
+                        if self.values[*var].resize(count.width(), true).scmp(&count).is_ge() {
+                            break;
+                        }
+                        iter += 1;
+                        if iter > LOOP_LIMIT {
+                            return Err(sim_error(format!("loop did not terminate at cycle {}", self.cycle)));
+                        }
+                        self.exec(body, process, nbas)?;
+                        let next = self.values[*var].add(&Bits::from_u64(1, width));
+                        self.write(process, (*var, 0, next));
+                    }
+                }
+                // System tasks in combinational processes only take effect once the logic is settled.
+                Stmt::Display(..) | Stmt::Fatal if process.is_some() && !self.strobe => {}
+                Stmt::Display(fstring, args) => {
+                    let args = args.iter().map(|arg| (arg.eval_self(&self.values), arg.signed)).collect::<Vec<_>>();
+                    self.displays.push((self.cycle, format(fstring, &args)));
+                }
+                Stmt::Fatal => {
+                    let last = self.displays.last().map_or(String::new(), |(_, msg)| format!(": {}", msg));
+                    return Err(sim_error(format!("$fatal at cycle {}{}", self.cycle, last)));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Splits a value into writes to the signals selected by the lvalue.
+    fn resolve(&self, lhs: &LValue, value: Bits) -> Vec<Write> {
+        let signals = &self.netlist.signals;
+        match lhs {
+            LValue::Signal(id) => vec![(*id, 0, value)],
+            LValue::Index(id, index) => {
+                let index = index.eval_self(&self.values).to_usize();
+                index.checked_mul(value.width()).map_or(vec![], |lo| vec![(*id, lo, value)])
+            }
+            LValue::Range(id, base, _) => vec![(*id, base.eval_self(&self.values).to_usize(), value)],
+            LValue::Concat(lvalues) => {
+                let mut lo = value.width();
+                lvalues
+                    .iter()
+                    .flat_map(|lvalue| {
+                        let width = lvalue.width(signals);
+                        lo -= width;
+                        self.resolve(lvalue, value.slice(lo, width))
+                    })
+                    .collect()
+            }
+        }
+    }
+
+    /// Applies nonblocking assignments.
+    fn commit(&mut self, nbas: Vec<Write>) {
+        nbas.into_iter().for_each(|write| self.write(None, write));
+    }
+
+    /// Writes a value and schedules the readers of the signal if it changed.
+    fn write(&mut self, process: Option<usize>, (id, lo, value): Write) {
+        let old = &mut self.values[id];
+        if lo >= old.width() || old.slice(lo, value.width()) == value {
+            return;
+        }
+        old.set_slice(lo, &value);
+        for reader in &self.readers[id] {
+            if Some(*reader) != process {
+                self.dirty.insert(*reader);
+            }
+        }
+    }
+}
+
+/// Formats a `$display` string. Supports `%b`, `%o`, `%d`, `%h`/`%x`, `%c`, `%t` and `%%`, with an optional `0`
+/// flag that disables padding.
+fn format(fstring: &str, args: &[(Bits, bool)]) -> String {
+    let mut out = String::new();
+    let mut args = args.iter();
+    let mut chars = fstring.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some('n') => out.push('\n'),
+                Some('t') => out.push('\t'),
+                Some(c) => out.push(c),
+                None => out.push('\\'),
+            },
+            '%' => {
+                let mut spec = String::new();
+                while let Some(c) = chars.next_if(|c| c.is_ascii_digit()) {
+                    spec.push(c);
+                }
+                let Some(conv) = chars.next() else {
+                    out.push('%');
+                    break;
+                };
+                if conv == '%' {
+                    out.push('%');
+                    continue;
+                }
+                let Some((value, signed)) = args.next() else {
+                    continue;
+                };
+                let pad = spec.is_empty();
+                let (radix, digits) = match conv.to_ascii_lowercase() {
+                    'b' => (2, value.width()),
+                    'o' => (8, (value.width() + 2) / 3),
+                    'h' | 'x' => (16, (value.width() + 3) / 4),
+                    'c' => {
+                        out.push(char::from(value.to_u64() as u8));
+                        continue;
+                    }
+                    't' => (10, 0),
+                    _ => (10, Bits::ones(value.width()).to_str_radix(10).len()),
+                };
+                let text = if radix == 10 && *signed && value.msb() {
+                    format!("-{}", value.neg().to_str_radix(10))
+                } else {
+                    value.to_str_radix(radix)
+                };
+                let text = if conv == 'X' || conv == 'H' { text.to_uppercase() } else { text };
+                if pad {
+                    let fill = if radix == 10 { ' ' } else { '0' };
+                    out.extend(std::iter::repeat(fill).take(digits.saturating_sub(text.len())));
+                }
+                out.push_str(&text);
+            }
+            _ => out.push(c),
+        }
+    }
+    out
+}
@@ -0,0 +1,751 @@
+//! Lowers a flattened module into a scheduled netlist.
+//!
+//! Identifiers are resolved into signal indices and the width and signedness of every expression are computed
+//! once, following the Verilog rules for self-determined and context-determined operands.
+
+use std::collections::HashMap;
+
+use super::bits::Bits;
+use crate::compiler::error::{VirgenError, VirgenResult};
+use crate::compiler::{BinaryOp, UnaryOp};
+use crate::vir::*;
+
+/// Signal index.
+pub(crate) type SignalId = usize;
+
+/// Kind of signal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum SignalKind {
+    /// Input port.
+    Input,
+
+    /// Output port.
+    Output,
+
+    /// Net.
+    Wire,
+
+    /// Register.
+    Reg,
+
+    /// Integer.
+    Integer,
+}
+
+/// Signal.
+#[derive(Debug, Clone)]
+pub(crate) struct Signal {
+    /// Name.
+    pub(crate) name: String,
+
+    /// Width of one element.
+    pub(crate) width: usize,
+
+    /// Number of elements if the signal is an array.
+    pub(crate) len: Option<usize>,
+
+    /// Signedness.
+    pub(crate) signed: bool,
+
+    /// Kind.
+    pub(crate) kind: SignalKind,
+
+    /// Instance and module names of the integrated submodules enclosing the declaration, outermost first. See
+    /// [`instance_scopes`](super::vcd::instance_scopes).
+    pub(crate) scope: Vec<(String, String)>,
+}
+
+impl Signal {
+    /// Returns the total width of the signal.
+    pub(crate) fn total_width(&self) -> usize {
+        self.width * self.len.unwrap_or(1)
+    }
+}
+
+/// Expression with resolved width and signedness.
+#[derive(Debug, Clone)]
+pub(crate) struct Expr {
+    /// Self-determined width.
+    pub(crate) width: usize,
+
+    /// Signedness.
+    pub(crate) signed: bool,
+
+    /// Kind.
+    pub(crate) kind: ExprKind,
+}
+
+/// Kind of expression.
+#[derive(Debug, Clone)]
+pub(crate) enum ExprKind {
+    /// Constant.
+    Const(Bits),
+
+    /// Whole signal.
+    Signal(SignalId),
+
+    /// Bit select, or element select if the signal is an array.
+    Index(SignalId, Box<Expr>),
+
+    /// Part select `[base +: width]`.
+    Range(SignalId, Box<Expr>, usize),
+
+    /// Concatenation, most significant first.
+    Concat(Vec<Expr>),
+
+    /// Multiple concatenation.
+    Repeat(usize, Vec<Expr>),
+
+    /// Unary operation.
+    Unary(UnaryOp, Box<Expr>),
+
+    /// Binary operation.
+    Binary(Box<Expr>, BinaryOp, Box<Expr>),
+
+    /// Conditional operation.
+    Cond(Box<Expr>, Box<Expr>, Box<Expr>),
+}
+
+/// Assignment target.
+#[derive(Debug, Clone)]
+pub(crate) enum LValue {
+    /// Whole signal.
+    Signal(SignalId),
+
+    /// Bit select, or element select if the signal is an array.
+    Index(SignalId, Expr),
+
+    /// Part select `[base +: width]`.
+    Range(SignalId, Expr, usize),
+
+    /// Concatenation, most significant first.
+    Concat(Vec<LValue>),
+}
+
+/// Statement.
+#[derive(Debug, Clone)]
+pub(crate) enum Stmt {
+    /// Assignment.
+    Assign {
+        /// Target.
+        lhs: LValue,
+
+        /// Value.
+        rhs: Expr,
+
+        /// Whether the assignment is blocking.
+        blocking: bool,
+    },
+
+    /// If-else chain.
+    If(Vec<(Expr, Vec<Stmt>)>, Vec<Stmt>),
+
+    /// Case statement.
+    Case(Expr, Vec<(Expr, Vec<Stmt>)>, Vec<Stmt>),
+
+    /// `for (var = 0; var < count; var = var + 1)` loop.
+    Loop(SignalId, Expr, Vec<Stmt>),
+
+    /// `$display`.
+    Display(String, Vec<Expr>),
+
+    /// `$fatal`.
+    Fatal,
+}
+
+/// Combinational process. Either a continuous assignment or an `always @*` block.
+#[derive(Debug, Clone)]
+pub(crate) struct Process {
+    /// Statements.
+    pub(crate) stmts: Vec<Stmt>,
+
+    /// Signals read by the process.
+    pub(crate) reads: Vec<SignalId>,
+
+    /// Signals written by the process.
+    pub(crate) writes: Vec<SignalId>,
+}
+
+/// Netlist.
+#[derive(Debug, Clone)]
+pub(crate) struct Netlist {
+    /// Signals.
+    pub(crate) signals: Vec<Signal>,
+
+    /// Signal indices by name.
+    pub(crate) ids: HashMap<String, SignalId>,
+
+    /// Register initializers.
+    pub(crate) inits: Vec<(SignalId, Expr)>,
+
+    /// `initial` blocks.
+    pub(crate) initials: Vec<Vec<Stmt>>,
+
+    /// Combinational processes, in topological order.
+    pub(crate) comb: Vec<Process>,
+
+    /// `always @(posedge clk)` blocks.
+    pub(crate) seq: Vec<Vec<Stmt>>,
+
+    /// Module instantiations.
+    pub(crate) instances: Vec<ModuleInstantiation>,
+}
+
+impl Netlist {
+    /// Lowers the module. The module should not contain module instantiations.
+    pub(crate) fn new(module: &Module) -> VirgenResult<Self> {
+        let netlist = Self::with_instances(module)?;
+        match netlist.instances.first() {
+            Some(inst) => Err(sim_error(format!(
+                "cannot simulate instantiation `{}` of external module `{}`",
+                inst.inst_name, inst.module_name
+            ))),
+            None => Ok(netlist),
+        }
+    }
+
+    /// Lowers the module, keeping its module instantiations in `instances`.
+    pub(crate) fn with_instances(module: &Module) -> VirgenResult<Self> {
+        let mut netlist = Self {
+            signals: vec![],
+            ids: HashMap::new(),
+            inits: vec![],
+            initials: vec![],
+            comb: vec![],
+            seq: vec![],
+            instances: vec![],
+        };
+
+        for port_decl in &module.port_decls {
+            let (width, name, kind) = match port_decl {
+                PortDeclaration::Input(width, name) => (*width, name, SignalKind::Input),
+                PortDeclaration::Output(width, name) => (*width, name, SignalKind::Output),
+            };
+            netlist.add_signal(Signal { name: name.clone(), width, len: None, signed: false, kind, scope: vec![] });
+        }
+
+        netlist.collect_decls(&module.module_items)?;
+        let mut comb = vec![];
+        netlist.collect_processes(&module.module_items, &mut comb)?;
+        netlist.comb = schedule(comb, netlist.signals.len());
+
+        Ok(netlist)
+    }
+
+    fn add_signal(&mut self, signal: Signal) {
+        // Ports may be redeclared inside the module. The first declaration wins.
+        if !self.ids.contains_key(&signal.name) {
+            self.ids.insert(signal.name.clone(), self.signals.len());
+            self.signals.push(signal);
+        }
+    }
+
+    fn collect_decls(&mut self, items: &[ModuleItem]) -> VirgenResult<()> {
+        for item in items {
+            match item {
+                ModuleItem::Declarations(decls) => {
+                    for decl in decls {
+                        let (shape, kind) = match decl {
+                            Declaration::Net(shape, _) => (shape, SignalKind::Wire),
+                            Declaration::Reg(shape, ..) => (shape, SignalKind::Reg),
+                            Declaration::Integer(name) => {
+                                self.add_signal(Signal {
+                                    name: name.clone(),
+                                    width: 32,
+                                    len: None,
+                                    signed: true,
+                                    kind: SignalKind::Integer,
+                                    scope: vec![],
+                                });
+                                continue;
+                            }
+                        };
+                        let (width, len) = match shape.dim() {
+                            1 => (shape.width(), None),
+                            2 => (shape.get(1), Some(shape.get(0))),
+                            dim => return Err(sim_error(format!("{}-dimensional declaration `{}`", dim, decl.name()))),
+                        };
+                        self.add_signal(Signal {
+                            name: decl.name(),
+                            width,
+                            len,
+                            signed: shape.is_signed(),
+                            kind,
+                            scope: vec![],
+                        });
+                    }
+                }
+                ModuleItem::Commented(_, _, items) => self.collect_decls(items)?,
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn collect_processes(&mut self, items: &[ModuleItem], comb: &mut Vec<Process>) -> VirgenResult<()> {
+        for item in items {
+            match item {
+                ModuleItem::Declarations(decls) => {
+                    for decl in decls {
+                        if let Declaration::Reg(_, name, Some(init)) = decl {
+                            let init = self.expr(init)?;
+                            self.inits.push((self.ids[name], init));
+                        }
+                    }
+                }
+                ModuleItem::ContinuousAssigns(conts) => {
+                    for ContinuousAssign(lhs, rhs) in conts {
+                        let stmt = Stmt::Assign { lhs: self.lvalue(lhs)?, rhs: self.expr(rhs)?, blocking: true };
+                        comb.push(Process::new(vec![stmt]));
+                    }
+                }
+                ModuleItem::ModuleInstantiation(inst) => self.instances.push(inst.clone()),
+                ModuleItem::AlwaysConstruct(event, stmts) => {
+                    let stmts = self.stmts(stmts)?;
+                    match event.split_whitespace().collect::<String>().as_str() {
+                        "always@*" | "always@(*)" => comb.push(Process::new(stmts)),
+                        "always@(posedgeclk)" => self.seq.push(stmts),
+                        "initial" => self.initials.push(stmts),
+                        _ => return Err(sim_error(format!("unsupported event `{}`", event))),
+                    }
+                }
+                ModuleItem::Commented(_, _, items) => self.collect_processes(items, comb)?,
+            }
+        }
+        Ok(())
+    }
+
+    fn id(&self, ident: &str) -> VirgenResult<SignalId> {
+        self.ids.get(ident).copied().ok_or_else(|| sim_error(format!("unknown identifier `{}`", ident)))
+    }
+
+    fn stmts(&self, stmts: &[Statement]) -> VirgenResult<Vec<Stmt>> {
+        stmts.iter().map(|stmt| self.stmt(stmt)).collect()
+    }
+
+    fn stmt(&self, stmt: &Statement) -> VirgenResult<Stmt> {
+        Ok(match stmt {
+            Statement::BlockingAssignment(lhs, rhs, _) => {
+                Stmt::Assign { lhs: self.lvalue(lhs)?, rhs: self.expr(rhs)?, blocking: true }
+            }
+            Statement::NonblockingAssignment(lhs, rhs, _) => {
+                Stmt::Assign { lhs: self.lvalue(lhs)?, rhs: self.expr(rhs)?, blocking: false }
+            }
+            Statement::Conditional(branches, default, _) => Stmt::If(
+                branches
+                    .iter()
+                    .map(|(cond, stmts)| Ok((self.expr(cond)?, self.stmts(stmts)?)))
+                    .collect::<VirgenResult<_>>()?,
+                self.stmts(default)?,
+            ),
+            Statement::Case(sel, items, default, _) => Stmt::Case(
+                self.expr(sel)?,
+                items
+                    .iter()
+                    .map(|(item, stmts)| Ok((self.expr(item)?, self.stmts(stmts)?)))
+                    .collect::<VirgenResult<_>>()?,
+                self.stmts(default)?,
+            ),
+            Statement::Loop(var, count, stmts, _) => Stmt::Loop(self.id(var)?, self.expr(count)?, self.stmts(stmts)?),
+            Statement::Display(fstring, args, _) => {
+                Stmt::Display(fstring.clone(), args.iter().map(|arg| self.expr(arg)).collect::<VirgenResult<_>>()?)
+            }
+            Statement::Fatal => Stmt::Fatal,
+        })
+    }
+
+    /// Lowers an assignment target.
+    pub(crate) fn lvalue(&self, expr: &Expression) -> VirgenResult<LValue> {
+        match expr {
+            Expression::Primary(Primary::HierarchicalIdentifier(ident, range)) => {
+                let id = self.id(ident)?;
+                Ok(match range {
+                    None => LValue::Signal(id),
+                    Some(Range::Index(index)) => LValue::Index(id, self.expr(index)?),
+                    Some(Range::Range(base, offset)) => LValue::Range(id, self.expr(base)?, self.const_usize(offset)?),
+                })
+            }
+            Expression::Primary(Primary::Concatenation(concat)) => {
+                Ok(LValue::Concat(concat.exprs.iter().map(|expr| self.lvalue(expr)).collect::<VirgenResult<_>>()?))
+            }
+            Expression::Primary(Primary::MintypmaxExpression(expr)) => self.lvalue(expr),
+            _ => Err(sim_error(format!("invalid lvalue `{}`", expr.to_string()))),
+        }
+    }
+
+    /// Lowers an expression.
+    pub(crate) fn expr(&self, expr: &Expression) -> VirgenResult<Expr> {
+        match expr {
+            Expression::Primary(prim) => self.primary(prim),
+            Expression::Unary(op, prim) => {
+                let operand = self.primary(prim)?;
+                Ok(Expr { width: operand.width, signed: operand.signed, kind: ExprKind::Unary(*op, Box::new(operand)) })
+            }
+            Expression::Binary(lhs, op, rhs) => {
+                let (lhs, rhs) = (self.expr(lhs)?, self.expr(rhs)?);
+                let (width, signed) = match op {
+                    BinaryOp::EqArithmetic
+                    | BinaryOp::NeStrict
+                    | BinaryOp::NeArithmetic
+                    | BinaryOp::Less
+                    | BinaryOp::Greater
+                    | BinaryOp::LessEq
+                    | BinaryOp::GreaterEq => (1, false),
+                    BinaryOp::ShiftLeft | BinaryOp::ShiftRight => (lhs.width, lhs.signed),
+                    _ => (lhs.width.max(rhs.width), lhs.signed && rhs.signed),
+                };
+                Ok(Expr { width, signed, kind: ExprKind::Binary(Box::new(lhs), *op, Box::new(rhs)) })
+            }
+            Expression::Conditional(cond, then_expr, else_expr) => {
+                let (cond, then_expr, else_expr) = (self.expr(cond)?, self.expr(then_expr)?, self.expr(else_expr)?);
+                Ok(Expr {
+                    width: then_expr.width.max(else_expr.width),
+                    signed: then_expr.signed && else_expr.signed,
+                    kind: ExprKind::Cond(Box::new(cond), Box::new(then_expr), Box::new(else_expr)),
+                })
+            }
+        }
+    }
+
+    fn primary(&self, prim: &Primary) -> VirgenResult<Expr> {
+        match prim {
+            Primary::Number(num) => parse_number(num),
+            Primary::HierarchicalIdentifier(ident, range) => {
+                // Some generated code uses numbers as identifiers.
+                if ident.starts_with(|c: char| c.is_ascii_digit()) {
+                    return parse_number(ident);
+                }
+                let id = self.id(ident)?;
+                let signal = &self.signals[id];
+                Ok(match range {
+                    None => Expr { width: signal.total_width(), signed: signal.signed, kind: ExprKind::Signal(id) },
+                    Some(Range::Index(index)) => Expr {
+                        width: if signal.len.is_some() { signal.width } else { 1 },
+                        signed: signal.len.is_some() && signal.signed,
+                        kind: ExprKind::Index(id, Box::new(self.expr(index)?)),
+                    },
+                    Some(Range::Range(base, offset)) => {
+                        let width = self.const_usize(offset)?;
+                        Expr { width, signed: false, kind: ExprKind::Range(id, Box::new(self.expr(base)?), width) }
+                    }
+                })
+            }
+            Primary::Concatenation(concat) => {
+                let exprs = concat.exprs.iter().map(|expr| self.expr(expr)).collect::<VirgenResult<Vec<_>>>()?;
+                Ok(Expr { width: exprs.iter().map(|e| e.width).sum(), signed: false, kind: ExprKind::Concat(exprs) })
+            }
+            Primary::MultipleConcatenation(count, concat) => {
+                let exprs = concat.exprs.iter().map(|expr| self.expr(expr)).collect::<VirgenResult<Vec<_>>>()?;
+                Ok(Expr {
+                    width: count * exprs.iter().map(|e| e.width).sum::<usize>(),
+                    signed: false,
+                    kind: ExprKind::Repeat(*count, exprs),
+                })
+            }
+            Primary::MintypmaxExpression(expr) => self.expr(expr),
+        }
+    }
+
+    fn const_usize(&self, expr: &Expression) -> VirgenResult<usize> {
+        let expr = self.expr(expr)?;
+        if expr.reads().is_empty() {
+            Ok(expr.eval_self(&[]).to_usize())
+        } else {
+            Err(sim_error(format!("part select width is not constant: {:?}", expr)))
+        }
+    }
+}
+
+impl Process {
+    /// Creates a new process, collecting the signals it reads and writes.
+    pub(crate) fn new(stmts: Vec<Stmt>) -> Self {
+        let (mut reads, mut writes) = (vec![], vec![]);
+        for stmt in &stmts {
+            stmt.collect(&mut reads, &mut writes);
+        }
+        reads.sort_unstable();
+        reads.dedup();
+        writes.sort_unstable();
+        writes.dedup();
+        Self { stmts, reads, writes }
+    }
+}
+
+impl Stmt {
+    fn collect(&self, reads: &mut Vec<SignalId>, writes: &mut Vec<SignalId>) {
+        match self {
+            Stmt::Assign { lhs, rhs, .. } => {
+                lhs.collect(reads, writes);
+                reads.extend(rhs.reads());
+            }
+            Stmt::If(branches, default) => {
+                for (cond, stmts) in branches {
+                    reads.extend(cond.reads());
+                    stmts.iter().for_each(|stmt| stmt.collect(reads, writes));
+                }
+                default.iter().for_each(|stmt| stmt.collect(reads, writes));
+            }
+            Stmt::Case(sel, items, default) => {
+                reads.extend(sel.reads());
+                for (item, stmts) in items {
+                    reads.extend(item.reads());
+                    stmts.iter().for_each(|stmt| stmt.collect(reads, writes));
+                }
+                default.iter().for_each(|stmt| stmt.collect(reads, writes));
+            }
+            Stmt::Loop(var, count, stmts) => {
+                writes.push(*var);
+                reads.extend(count.reads());
+                stmts.iter().for_each(|stmt| stmt.collect(reads, writes));
+            }
+            // System tasks are not evaluated in combinational processes.
+            Stmt::Display(..) | Stmt::Fatal => {}
+        }
+    }
+}
+
+impl LValue {
+    /// Collects the signals read by the indices of the target, and the signals written.
+    pub(crate) fn collect(&self, reads: &mut Vec<SignalId>, writes: &mut Vec<SignalId>) {
+        match self {
+            LValue::Signal(id) => writes.push(*id),
+            LValue::Index(id, index) | LValue::Range(id, index, _) => {
+                writes.push(*id);
+                reads.extend(index.reads());
+            }
+            LValue::Concat(lvalues) => lvalues.iter().for_each(|lvalue| lvalue.collect(reads, writes)),
+        }
+    }
+
+    /// Returns the width of the target.
+    pub(crate) fn width(&self, signals: &[Signal]) -> usize {
+        match self {
+            LValue::Signal(id) => signals[*id].total_width(),
+            LValue::Index(id, _) => {
+                if signals[*id].len.is_some() {
+                    signals[*id].width
+                } else {
+                    1
+                }
+            }
+            LValue::Range(_, _, width) => *width,
+            LValue::Concat(lvalues) => lvalues.iter().map(|lvalue| lvalue.width(signals)).sum(),
+        }
+    }
+}
+
+impl Expr {
+    /// Returns the signals read by the expression.
+    pub(crate) fn reads(&self) -> Vec<SignalId> {
+        let mut reads = vec![];
+        self.collect_reads(&mut reads);
+        reads
+    }
+
+    fn collect_reads(&self, reads: &mut Vec<SignalId>) {
+        match &self.kind {
+            ExprKind::Const(_) => {}
+            ExprKind::Signal(id) => reads.push(*id),
+            ExprKind::Index(id, index) | ExprKind::Range(id, index, _) => {
+                reads.push(*id);
+                index.collect_reads(reads);
+            }
+            ExprKind::Concat(exprs) | ExprKind::Repeat(_, exprs) => exprs.iter().for_each(|e| e.collect_reads(reads)),
+            ExprKind::Unary(_, expr) => expr.collect_reads(reads),
+            ExprKind::Binary(lhs, _, rhs) => {
+                lhs.collect_reads(reads);
+                rhs.collect_reads(reads);
+            }
+            ExprKind::Cond(cond, then_expr, else_expr) => {
+                cond.collect_reads(reads);
+                then_expr.collect_reads(reads);
+                else_expr.collect_reads(reads);
+            }
+        }
+    }
+
+    /// Evaluates the expression in its own width and signedness.
+    pub(crate) fn eval_self(&self, values: &[Bits]) -> Bits {
+        self.eval(values, self.width, self.signed)
+    }
+
+    /// Evaluates the expression in a context of the given width and signedness.
+    ///
+    /// Operands are extended to the context width before the operation, so carries out of the self-determined
+    /// width are kept.
+    pub(crate) fn eval(&self, values: &[Bits], width: usize, signed: bool) -> Bits {
+        match &self.kind {
+            ExprKind::Const(bits) => bits.resize(width, signed),
+            ExprKind::Signal(id) => values[*id].resize(width, signed),
+            ExprKind::Index(id, index) => {
+                let index = index.eval_self(values).to_usize();
+                match index.checked_mul(self.width) {
+                    Some(lo) => values[*id].slice(lo, self.width).resize(width, signed),
+                    None => Bits::zero(width),
+                }
+            }
+            ExprKind::Range(id, base, w) => {
+                values[*id].slice(base.eval_self(values).to_usize(), *w).resize(width, signed)
+            }
+            ExprKind::Concat(exprs) => concat(values, exprs).resize(width, signed),
+            ExprKind::Repeat(count, exprs) => {
+                let part = concat(values, exprs);
+                (0..*count).fold(Bits::zero(0), |acc, _| acc.concat(&part)).resize(width, signed)
+            }
+            ExprKind::Unary(UnaryOp::Negation, expr) => expr.eval(values, width, signed).not(),
+            ExprKind::Binary(lhs, op, rhs) => match op {
+                BinaryOp::EqArithmetic
+                | BinaryOp::NeStrict
+                | BinaryOp::NeArithmetic
+                | BinaryOp::Less
+                | BinaryOp::Greater
+                | BinaryOp::LessEq
+                | BinaryOp::GreaterEq => {
+                    let (w, s) = (lhs.width.max(rhs.width), lhs.signed && rhs.signed);
+                    let (l, r) = (lhs.eval(values, w, s), rhs.eval(values, w, s));
+                    let ord = if s { l.scmp(&r) } else { l.ucmp(&r) };
+                    let result = match op {
+                        BinaryOp::EqArithmetic => ord.is_eq(),
+                        BinaryOp::NeStrict | BinaryOp::NeArithmetic => ord.is_ne(),
+                        BinaryOp::Less => ord.is_lt(),
+                        BinaryOp::Greater => ord.is_gt(),
+                        BinaryOp::LessEq => ord.is_le(),
+                        BinaryOp::GreaterEq => ord.is_ge(),
+                        _ => unreachable!(),
+                    };
+                    Bits::from_bool(result).resize(width, false)
+                }
+                BinaryOp::ShiftLeft | BinaryOp::ShiftRight => {
+                    let value = lhs.eval(values, width, signed);
+                    let amount = rhs.eval_self(values).to_usize();
+                    match op {
+                        BinaryOp::ShiftLeft => value.shl(amount),
+                        _ if signed => value.ashr(amount),
+                        _ => value.lshr(amount),
+                    }
+                }
+                _ => {
+                    let (l, r) = (lhs.eval(values, width, signed), rhs.eval(values, width, signed));
+                    match op {
+                        BinaryOp::Add => l.add(&r),
+                        BinaryOp::Sub => l.sub(&r),
+                        BinaryOp::Mul => l.mul(&r),
+                        BinaryOp::Div if signed => l.sdiv(&r),
+                        BinaryOp::Div => l.udiv(&r),
+                        BinaryOp::Mod if signed => l.srem(&r),
+                        BinaryOp::Mod => l.urem(&r),
+                        BinaryOp::Or => l.or(&r),
+                        BinaryOp::And => l.and(&r),
+                        BinaryOp::Xor => l.xor(&r),
+                        BinaryOp::Eq => l.xnor(&r),
+                        _ => unreachable!(),
+                    }
+                }
+            },
+            ExprKind::Cond(cond, then_expr, else_expr) => {
+                if cond.eval_self(values).is_zero() {
+                    else_expr.eval(values, width, signed)
+                } else {
+                    then_expr.eval(values, width, signed)
+                }
+            }
+        }
+    }
+}
+
+fn concat(values: &[Bits], exprs: &[Expr]) -> Bits {
+    exprs.iter().fold(Bits::zero(0), |acc, expr| acc.concat(&expr.eval_self(values)))
+}
+
+/// Parses a Verilog number literal such as `0`, `4'b10x1` or `32'h80000002`.
+fn parse_number(num: &str) -> VirgenResult<Expr> {
+    let invalid = || sim_error(format!("invalid number `{}`", num));
+    let num = num.trim();
+
+    let Some((size, value)) = num.split_once('\'') else {
+        // Unsized decimal numbers are signed and at least 32 bits wide.
+        let bits = Bits::from_str_radix(num, 10, 128).ok_or_else(invalid)?;
+        let width = (0..128).rev().find(|i| bits.bit(*i)).map_or(32, |msb| (msb + 2).max(32));
+        return Ok(Expr { width, signed: true, kind: ExprKind::Const(bits.resize(width, false)) });
+    };
+
+    let width = if size.is_empty() { 32 } else { size.trim().parse().map_err(|_| invalid())? };
+    let (signed, value) = match value.strip_prefix(['s', 'S']) {
+        Some(value) => (true, value),
+        None => (false, value),
+    };
+    let mut chars = value.chars();
+    let radix = match chars.next() {
+        Some('b' | 'B') => 2,
+        Some('o' | 'O') => 8,
+        Some('d' | 'D') => 10,
+        Some('h' | 'H') => 16,
+        _ => return Err(invalid()),
+    };
+    let digits = chars.as_str().trim();
+    let bits =
+        if radix == 2 { Bits::from_binary_str(digits, width) } else { Bits::from_str_radix(digits, radix, width) }
+            .ok_or_else(invalid)?;
+
+    Ok(Expr { width, signed, kind: ExprKind::Const(bits) })
+}
+
+/// Orders processes so that every process comes after the processes it reads from, except within cycles.
+fn schedule(processes: Vec<Process>, num_signals: usize) -> Vec<Process> {
+    let mut writers = vec![vec![]; num_signals];
+    for (idx, process) in processes.iter().enumerate() {
+        for id in &process.writes {
+            writers[*id].push(idx);
+        }
+    }
+
+    // Iterative post-order DFS on the reversed dependency graph yields a topological order.
+    let mut visited = vec![false; processes.len()];
+    let mut order = Vec::with_capacity(processes.len());
+    for root in 0..processes.len() {
+        if visited[root] {
+            continue;
+        }
+        visited[root] = true;
+        let mut stack = vec![(root, 0, 0)];
+        while let Some((idx, read_idx, writer_idx)) = stack.last_mut() {
+            let reads = &processes[*idx].reads;
+            if *read_idx == reads.len() {
+                order.push(*idx);
+                stack.pop();
+                continue;
+            }
+            let signal_writers = &writers[reads[*read_idx]];
+            if *writer_idx == signal_writers.len() {
+                *read_idx += 1;
+                *writer_idx = 0;
+                continue;
+            }
+            let writer = signal_writers[*writer_idx];
+            *writer_idx += 1;
+            if !visited[writer] {
+                visited[writer] = true;
+                stack.push((writer, 0, 0));
+            }
+        }
+    }
+
+    let mut processes = processes.into_iter().map(Some).collect::<Vec<_>>();
+    order.into_iter().map(|idx| processes[idx].take().unwrap()).collect()
+}
+
+/// Returns whether the statements contain a system task.
+pub(crate) fn has_task(stmts: &[Stmt]) -> bool {
+    stmts.iter().any(|stmt| match stmt {
+        Stmt::Assign { .. } => false,
+        Stmt::If(branches, default) | Stmt::Case(_, branches, default) => {
+            branches.iter().any(|(_, stmts)| has_task(stmts)) || has_task(default)
+        }
+        Stmt::Loop(_, _, body) => has_task(body),
+        Stmt::Display(..) | Stmt::Fatal => true,
+    })
+}
+
+pub(crate) fn sim_error(msg: String) -> VirgenError {
+    VirgenError::SimulationError { msg }
+}
@@ -0,0 +1,636 @@
+//! Compiles a flattened module into a standalone Rust simulator.
+//!
+//! The generated file defines one struct per module with a field for every port, net, and register, and the
+//! runtime in `runtime.rs`. Combinational processes are evaluated once in topological order by `eval_comb()`,
+//! iterating only if the module has combinational cycles, and the `always @(posedge clk)` blocks run in `tick()`.
+//! As in the interpreter, system tasks of combinational processes are executed once per cycle, at the start of `tick()`.
+//! The fields that the simulator keeps for itself are named so that they do not collide with any signal.
+//! The generated code follows the same width and signedness rules as [`Simulator`](super::Simulator).
+
+use std::collections::HashSet;
+use std::fmt::Write;
+
+use itertools::Itertools;
+
+use super::netlist::*;
+use crate::compiler::error::VirgenResult;
+use crate::compiler::{BinaryOp, UnaryOp};
+use crate::utils::indent;
+use crate::vir::*;
+
+// Compiled into the crate as well so that it is type-checked.
+mod runtime;
+
+const INDENT: usize = 4;
+
+/// Number of combinational processes per generated method.
+const CHUNK: usize = 256;
+
+/// Upper bound on the passes of `eval_comb()` for modules with combinational cycles.
+const PASS_LIMIT: usize = 1024;
+
+const RUNTIME: &str = include_str!("runtime.rs");
+
+/// Strict and reserved keywords of Rust 2021, which are escaped as raw identifiers.
+const KEYWORDS: [&str; 47] = [
+    "as", "async", "await", "break", "const", "continue", "dyn", "else", "enum", "extern", "false", "fn", "for", "if",
+    "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub", "ref", "return", "static", "struct", "trait",
+    "true", "type", "unsafe", "use", "where", "while", "abstract", "become", "box", "do", "final", "macro", "override",
+    "priv", "try", "typeof", "unsized", "virtual", "yield",
+];
+
+/// Generates a Rust simulator for the module.
+///
+/// The module should not contain module instantiations. See [`integrate()`].
+pub fn gen_rust_sim(module: &Module) -> VirgenResult<String> {
+    let netlist = Netlist::new(module)?;
+    let fields = netlist.signals.iter().map(|signal| field(&signal.name)).collect::<HashSet<_>>();
+    let fresh = |name: &str| {
+        let mut name = name.to_string();
+        while fields.contains(&name) {
+            name.push('_');
+        }
+        name
+    };
+    Ok(RustSim { netlist: &netlist, nba: fresh("__nba"), cycle: fresh("__cycle") }.gen(&module.name))
+}
+
+struct RustSim<'a> {
+    netlist: &'a Netlist,
+
+    /// Field holding the values of registers after the current rising edge.
+    nba: String,
+
+    /// Field holding the number of rising edges simulated so far.
+    cycle: String,
+}
+
+fn words(width: usize) -> usize {
+    (width + 63) / 64
+}
+
+fn field(name: &str) -> String {
+    match name {
+        "self" | "Self" | "super" | "crate" => format!("{}_", name),
+        _ if KEYWORDS.contains(&name) => format!("r#{}", name),
+        _ => name.to_string(),
+    }
+}
+
+fn struct_name(module: &str) -> String {
+    module
+        .split('_')
+        .filter(|s| !s.is_empty())
+        .map(|s| {
+            let mut chars = s.chars();
+            chars.next().map_or(String::new(), |c| c.to_uppercase().chain(chars).collect())
+        })
+        .collect()
+}
+
+/// Resizes a generated value of `from` bits into `to` bits.
+fn ext(value: String, from: usize, to: usize, signed: bool) -> String {
+    if from == to {
+        value
+    } else {
+        format!("rt::ext::<{}, {}>({}, {}, {}, {})", words(from), words(to), value, from, to, signed)
+    }
+}
+
+impl<'a> RustSim<'a> {
+    fn signal(&self, id: SignalId) -> &Signal {
+        &self.netlist.signals[id]
+    }
+
+    fn lvalue_signals(lvalue: &LValue, out: &mut Vec<SignalId>) {
+        match lvalue {
+            LValue::Signal(id) | LValue::Index(id, _) | LValue::Range(id, ..) => out.push(*id),
+            LValue::Concat(lvalues) => lvalues.iter().for_each(|lvalue| Self::lvalue_signals(lvalue, out)),
+        }
+    }
+
+    fn nba_targets(stmts: &[Stmt], out: &mut Vec<SignalId>) {
+        for stmt in stmts {
+            match stmt {
+                Stmt::Assign { lhs, blocking: false, .. } => Self::lvalue_signals(lhs, out),
+                Stmt::Assign { .. } | Stmt::Display(..) | Stmt::Fatal => {}
+                Stmt::If(branches, default) | Stmt::Case(_, branches, default) => {
+                    branches.iter().for_each(|(_, stmts)| Self::nba_targets(stmts, out));
+                    Self::nba_targets(default, out);
+                }
+                Stmt::Loop(_, _, body) => Self::nba_targets(body, out),
+            }
+        }
+    }
+
+    fn gen(&self, module_name: &str) -> String {
+        let name = struct_name(module_name);
+        let signals = &self.netlist.signals;
+
+        let mut nba = vec![];
+        self.netlist.seq.iter().for_each(|stmts| Self::nba_targets(stmts, &mut nba));
+        let nba = nba.into_iter().unique().sorted().collect::<Vec<_>>();
+
+        let fields = signals
+            .iter()
+            .map(|signal| {
+                let kind = match signal.kind {
+                    SignalKind::Input => "Input port",
+                    SignalKind::Output => "Output port",
+                    SignalKind::Wire => "Net",
+                    SignalKind::Reg => "Register",
+                    SignalKind::Integer => "Integer",
+                };
+                let shape = match signal.len {
+                    Some(len) => format!("{} x {} bits", len, signal.width),
+                    None => format!("{} bits", signal.width),
+                };
+                format!(
+                    "/// {} `{}` ({}{}).\npub {}: [u64; {}],",
+                    kind,
+                    signal.name,
+                    shape,
+                    if signal.signed { ", signed" } else { "" },
+                    field(&signal.name),
+                    words(signal.total_width())
+                )
+            })
+            .join("\n");
+        let nba_fields = nba
+            .iter()
+            .map(|id| format!("{}: [u64; {}],", field(&self.signal(*id).name), words(self.signal(*id).total_width())))
+            .join("\n");
+
+        let mut body = String::new();
+
+        // Constructor.
+        let mut ctor = vec![];
+        ctor.push(format!(
+            "let mut sim = Self {{\n{}\n    {}: Nba {{\n{}\n    }},\n    {}: 0,\n}};",
+            indent(
+                signals
+                    .iter()
+                    .map(|signal| format!("{}: [0; {}],", field(&signal.name), words(signal.total_width())))
+                    .join("\n"),
+                INDENT
+            ),
+            self.nba,
+            indent(
+                nba.iter()
+                    .map(|id| format!(
+                        "{}: [0; {}],",
+                        field(&self.signal(*id).name),
+                        words(self.signal(*id).total_width())
+                    ))
+                    .join("\n"),
+                2 * INDENT
+            ),
+            self.cycle
+        ));
+        for (id, init) in &self.netlist.inits {
+            let width = self.signal(*id).total_width();
+            ctor.push(format!(
+                "sim.{} = {};",
+                field(&self.signal(*id).name),
+                ext(self.expr(init, init.width.max(width), init.signed, "sim"), init.width.max(width), width, false)
+            ));
+        }
+        for stmts in &self.netlist.initials {
+            ctor.push(self.stmts(stmts, "sim", &[], true));
+        }
+        ctor.push("sim.eval_comb();\nsim".to_string());
+        writeln!(
+            body,
+            "/// Creates a simulator with registers initialized and combinational logic settled.\npub fn new() -> Self {{\n{}\n}}\n",
+            indent(ctor.join("\n"), INDENT)
+        )
+        .unwrap();
+
+        // Combinational logic.
+        let chunks = self.netlist.comb.chunks(CHUNK).collect::<Vec<_>>();
+        let calls = (0..chunks.len()).map(|i| format!("self.comb_{}();", i)).join("\n");
+        let feedback = self.feedback_signals();
+        let eval_comb = if feedback.is_empty() {
+            calls
+        } else {
+            let snapshot = feedback
+                .iter()
+                .map(|id| format!("let prev_{} = self.{};", id, field(&self.signal(*id).name)))
+                .join("\n");
+            let unchanged =
+                feedback.iter().map(|id| format!("prev_{} == self.{}", id, field(&self.signal(*id).name))).join(" && ");
+            format!(
+                "for _ in 0..{} {{\n{}\n}}\npanic!(\"combinational logic did not converge at cycle {{}}\", self.{});",
+                PASS_LIMIT,
+                indent(format!("{}\n{}\nif {} {{\n    return;\n}}", snapshot, calls, unchanged), INDENT),
+                self.cycle
+            )
+        };
+        writeln!(
+            body,
+            "/// Propagates inputs and registers through combinational logic.\npub fn eval_comb(&mut self) {{\n{}\n}}\n",
+            indent(eval_comb, INDENT)
+        )
+        .unwrap();
+        for (i, chunk) in chunks.iter().enumerate() {
+            let stmts = chunk.iter().map(|process| self.stmts(&process.stmts, "self", &[], false)).join("\n");
+            writeln!(body, "fn comb_{}(&mut self) {{\n{}\n}}\n", i, indent(stmts, INDENT)).unwrap();
+        }
+
+        // System tasks of combinational processes.
+        let tasks = self.netlist.comb.iter().filter(|process| has_task(&process.stmts)).collect::<Vec<_>>();
+        if !tasks.is_empty() {
+            let stmts = tasks.iter().map(|process| self.stmts(&process.stmts, "self", &[], true)).join("\n");
+            writeln!(body, "fn comb_tasks(&mut self) {{\n{}\n}}\n", indent(stmts, INDENT)).unwrap();
+        }
+
+        // Sequential logic.
+        let mut tick = vec![];
+        if !tasks.is_empty() {
+            tick.push("self.comb_tasks();".to_string());
+        }
+        tick.extend(nba.iter().map(|id| {
+            let f = field(&self.signal(*id).name);
+            format!("self.{}.{} = self.{};", self.nba, f, f)
+        }));
+        tick.extend((0..self.netlist.seq.len()).map(|i| format!("self.seq_{}();", i)));
+        tick.extend(nba.iter().map(|id| {
+            let f = field(&self.signal(*id).name);
+            format!("self.{} = self.{}.{};", f, self.nba, f)
+        }));
+        tick.push(format!("self.{} += 1;\nself.eval_comb();", self.cycle));
+        writeln!(
+            body,
+            "/// Simulates one rising edge of `clk`, then settles combinational logic.\n///\n/// Call `eval_comb()` first if inputs changed since the last call.\npub fn tick(&mut self) {{\n{}\n}}\n",
+            indent(tick.join("\n"), INDENT)
+        )
+        .unwrap();
+        for (i, stmts) in self.netlist.seq.iter().enumerate() {
+            writeln!(
+                body,
+                "fn seq_{}(&mut self) {{\n{}\n}}\n",
+                i,
+                indent(self.stmts(stmts, "self", &nba, true), INDENT)
+            )
+            .unwrap();
+        }
+
+        if let Some(rst) = self.netlist.ids.get("rst") {
+            writeln!(
+                body,
+                "/// Holds `rst` high for `cycles` rising edges, then releases it.\npub fn reset(&mut self, cycles: usize) {{\n    self.{rst} = [1];\n    self.eval_comb();\n    for _ in 0..cycles {{\n        self.tick();\n    }}\n    self.{rst} = [0];\n    self.eval_comb();\n}}\n",
+                rst = field(&self.signal(*rst).name)
+            )
+            .unwrap();
+        }
+        writeln!(
+            body,
+            "/// Returns the number of rising edges simulated so far.\npub fn cycle(&self) -> u64 {{\n    self.{}\n}}",
+            self.cycle
+        )
+        .unwrap();
+
+        format!(
+            r#"// Cycle-accurate simulator for `{module_name}`, generated by the HazardFlow compiler.
+//
+// Values are little-endian `u64` words. Drive inputs by writing their fields, call `eval_comb()` to propagate
+// them, and call `tick()` for each rising edge of `clk`. The file can be declared as a module or `include!`d.
+
+/// Runtime.
+#[allow(clippy::all)]
+pub mod rt {{
+{runtime}
+}}
+
+/// Simulator for `{module_name}`.
+#[derive(Debug, Clone)]
+#[allow(non_snake_case)]
+pub struct {name} {{
+{fields}
+
+    /// Values of registers after the current rising edge.
+    {nba}: Nba,
+
+    /// Number of rising edges simulated so far.
+    {cycle}: u64,
+}}
+
+#[derive(Debug, Clone)]
+#[allow(non_snake_case)]
+struct Nba {{
+{nba_fields}
+}}
+
+impl Default for {name} {{
+    fn default() -> Self {{
+        Self::new()
+    }}
+}}
+
+#[allow(non_snake_case, unused_mut, unused_variables, unused_parens, unreachable_code, clippy::all)]
+impl {name} {{
+{body}
+}}
+"#,
+            runtime = indent(RUNTIME.trim_end().to_string(), INDENT),
+            fields = indent(fields, INDENT),
+            nba_fields = indent(nba_fields, INDENT),
+            body = indent(body.trim_end().to_string(), INDENT),
+            nba = self.nba,
+            cycle = self.cycle,
+        )
+    }
+
+    /// Returns the signals that a combinational process reads before a later process writes them. Another pass
+    /// is needed if they change.
+    fn feedback_signals(&self) -> Vec<SignalId> {
+        let mut last_writer: Vec<Option<usize>> = vec![None; self.netlist.signals.len()];
+        for (pos, process) in self.netlist.comb.iter().enumerate() {
+            for id in &process.writes {
+                last_writer[*id] = Some(pos);
+            }
+        }
+        self.netlist
+            .comb
+            .iter()
+            .enumerate()
+            .flat_map(|(pos, process)| {
+                let last_writer = &last_writer;
+                process.reads.iter().filter(move |id| matches!(last_writer[**id], Some(writer) if writer > pos))
+            })
+            .copied()
+            .unique()
+            .sorted()
+            .collect()
+    }
+
+    fn stmts(&self, stmts: &[Stmt], this: &str, nba: &[SignalId], effects: bool) -> String {
+        stmts.iter().map(|stmt| self.stmt(stmt, this, nba, effects)).filter(|s| !s.is_empty()).join("\n")
+    }
+
+    fn stmt(&self, stmt: &Stmt, this: &str, nba: &[SignalId], effects: bool) -> String {
+        match stmt {
+            Stmt::Assign { lhs, rhs, blocking } => {
+                let width = lhs.width(&self.netlist.signals);
+                let ctx = width.max(rhs.width);
+                let value = ext(self.expr(rhs, ctx, rhs.signed, this), ctx, width, false);
+                let target = if *blocking { this.to_string() } else { format!("{}.{}", this, self.nba) };
+                self.assign(lhs, value, width, &target, this)
+            }
+            Stmt::If(branches, default) => {
+                let mut out = branches
+                    .iter()
+                    .map(|(cond, stmts)| {
+                        format!(
+                            "if !rt::is_zero(&{}) {{\n{}\n}}",
+                            self.expr(cond, cond.width, cond.signed, this),
+                            indent(self.stmts(stmts, this, nba, effects), INDENT)
+                        )
+                    })
+                    .join(" else ");
+                if !default.is_empty() {
+                    write!(out, " else {{\n{}\n}}", indent(self.stmts(default, this, nba, effects), INDENT)).unwrap();
+                }
+                out
+            }
+            Stmt::Case(sel, items, default) => {
+                let mut out = items
+                    .iter()
+                    .map(|(item, stmts)| {
+                        let (width, signed) = (sel.width.max(item.width), sel.signed && item.signed);
+                        format!(
+                            "if {} == {} {{\n{}\n}}",
+                            self.expr(sel, width, signed, this),
+                            self.expr(item, width, signed, this),
+                            indent(self.stmts(stmts, this, nba, effects), INDENT)
+                        )
+                    })
+                    .join(" else ");
+                if out.is_empty() {
+                    return self.stmts(default, this, nba, effects);
+                }
+                if !default.is_empty() {
+                    write!(out, " else {{\n{}\n}}", indent(self.stmts(default, this, nba, effects), INDENT)).unwrap();
+                }
+                out
+            }
+            Stmt::Loop(var, count, body) => {
+                let var_width = self.signal(*var).total_width();
+                let count_width = var_width.max(count.width);
+                let var = format!("{}.{}", this, field(&self.signal(*var).name));
+                format!(
+                    "{var} = [0; {w}];\nloop {{\n    if rt::cmp(&{cur}, &{count}, {cw}, true).is_ge() {{\n        break;\n    }}\n{body}\n    {var} = rt::add({var}, rt::from_bool(true), {vw});\n}}",
+                    w = words(var_width),
+                    cur = ext(var.clone(), var_width, count_width, true),
+                    count = self.expr(count, count_width, count.signed, this),
+                    cw = count_width,
+                    vw = var_width,
+                    body = indent(self.stmts(body, this, nba, effects), INDENT),
+                )
+            }
+            Stmt::Display(..) | Stmt::Fatal if !effects => String::new(),
+            Stmt::Display(fstring, args) => {
+                let args = args
+                    .iter()
+                    .map(|arg| {
+                        format!("(&{}[..], {}, {})", self.expr(arg, arg.width, arg.signed, this), arg.width, arg.signed)
+                    })
+                    .join(", ");
+                format!("eprintln!(\"[{{}}] {{}}\", {}.{}, rt::format({:?}, &[{}]));", this, self.cycle, fstring, args)
+            }
+            Stmt::Fatal => format!("panic!(\"$fatal at cycle {{}}\", {}.{});", this, self.cycle),
+        }
+    }
+
+    /// Writes `value`, a `width`-bit generated value, into the lvalue. Signals are fields of `target`, and
+    /// indices are evaluated on `this`.
+    fn assign(&self, lhs: &LValue, value: String, width: usize, target: &str, this: &str) -> String {
+        match lhs {
+            LValue::Signal(id) => format!("{}.{} = {};", target, field(&self.signal(*id).name), value),
+            LValue::Index(id, index) => {
+                let signal = self.signal(*id);
+                format!(
+                    "rt::set(&mut {}.{}, {}, rt::to_usize(&{}).saturating_mul({}), &{}, {});",
+                    target,
+                    field(&signal.name),
+                    signal.total_width(),
+                    self.expr(index, index.width, index.signed, this),
+                    width,
+                    value,
+                    width
+                )
+            }
+            LValue::Range(id, base, _) => {
+                let signal = self.signal(*id);
+                format!(
+                    "rt::set(&mut {}.{}, {}, rt::to_usize(&{}), &{}, {});",
+                    target,
+                    field(&signal.name),
+                    signal.total_width(),
+                    self.expr(base, base.width, base.signed, this),
+                    value,
+                    width
+                )
+            }
+            LValue::Concat(lvalues) => {
+                let mut lo = width;
+                let parts = lvalues
+                    .iter()
+                    .map(|lvalue| {
+                        let part_width = lvalue.width(&self.netlist.signals);
+                        lo -= part_width;
+                        let part =
+                            format!("rt::slice::<{}, {}>(&v, {}, {})", words(width), words(part_width), lo, part_width);
+                        self.assign(lvalue, part, part_width, target, this)
+                    })
+                    .join("\n");
+                format!("{{\n    let v = {};\n{}\n}}", value, indent(parts, INDENT))
+            }
+        }
+    }
+
+    /// Generates an expression evaluated in a context of the given width and signedness.
+    fn expr(&self, expr: &Expr, width: usize, signed: bool, this: &str) -> String {
+        let w = words(width);
+        match &expr.kind {
+            ExprKind::Const(bits) => {
+                let bits = bits.resize(width, signed);
+                format!("[{}]", bits.words().iter().map(|x| format!("{:#x}_u64", x)).join(", "))
+            }
+            ExprKind::Signal(id) => {
+                let signal = self.signal(*id);
+                ext(format!("{}.{}", this, field(&signal.name)), signal.total_width(), width, signed)
+            }
+            ExprKind::Index(id, index) => {
+                let signal = self.signal(*id);
+                let value = format!(
+                    "rt::slice::<{}, {}>(&{}.{}, rt::to_usize(&{}).saturating_mul({}), {})",
+                    words(signal.total_width()),
+                    words(expr.width),
+                    this,
+                    field(&signal.name),
+                    self.expr(index, index.width, index.signed, this),
+                    expr.width,
+                    expr.width
+                );
+                ext(value, expr.width, width, signed)
+            }
+            ExprKind::Range(id, base, range_width) => {
+                let signal = self.signal(*id);
+                let value = format!(
+                    "rt::slice::<{}, {}>(&{}.{}, rt::to_usize(&{}), {})",
+                    words(signal.total_width()),
+                    words(*range_width),
+                    this,
+                    field(&signal.name),
+                    self.expr(base, base.width, base.signed, this),
+                    range_width
+                );
+                ext(value, *range_width, width, signed)
+            }
+            ExprKind::Concat(exprs) => ext(self.concat(exprs, this), expr.width, width, signed),
+            ExprKind::Repeat(count, exprs) => {
+                let part_width = exprs.iter().map(|e| e.width).sum::<usize>();
+                let value = format!(
+                    "{{\n    let p = {};\n    let mut c = [0; {}];\n    for i in 0..{} {{\n        rt::set(&mut c, {}, i * {}, &p, {});\n    }}\n    c\n}}",
+                    self.concat(exprs, this),
+                    words(expr.width),
+                    count,
+                    expr.width,
+                    part_width,
+                    part_width
+                );
+                ext(value, expr.width, width, signed)
+            }
+            ExprKind::Unary(UnaryOp::Negation, operand) => {
+                format!("rt::not::<{}>({}, {})", w, self.expr(operand, width, signed, this), width)
+            }
+            ExprKind::Binary(lhs, op, rhs) => match op {
+                BinaryOp::EqArithmetic
+                | BinaryOp::NeStrict
+                | BinaryOp::NeArithmetic
+                | BinaryOp::Less
+                | BinaryOp::Greater
+                | BinaryOp::LessEq
+                | BinaryOp::GreaterEq => {
+                    let (cw, cs) = (lhs.width.max(rhs.width), lhs.signed && rhs.signed);
+                    let method = match op {
+                        BinaryOp::EqArithmetic => "is_eq",
+                        BinaryOp::NeStrict | BinaryOp::NeArithmetic => "is_ne",
+                        BinaryOp::Less => "is_lt",
+                        BinaryOp::Greater => "is_gt",
+                        BinaryOp::LessEq => "is_le",
+                        _ => "is_ge",
+                    };
+                    format!(
+                        "rt::from_bool::<{}>(rt::cmp::<{}>(&{}, &{}, {}, {}).{}())",
+                        w,
+                        words(cw),
+                        self.expr(lhs, cw, cs, this),
+                        self.expr(rhs, cw, cs, this),
+                        cw,
+                        cs,
+                        method
+                    )
+                }
+                BinaryOp::ShiftLeft | BinaryOp::ShiftRight => {
+                    let func = match op {
+                        BinaryOp::ShiftLeft => "shl",
+                        _ if signed => "ashr",
+                        _ => "lshr",
+                    };
+                    format!(
+                        "rt::{}::<{}>({}, rt::to_usize(&{}), {})",
+                        func,
+                        w,
+                        self.expr(lhs, width, signed, this),
+                        self.expr(rhs, rhs.width, rhs.signed, this),
+                        width
+                    )
+                }
+                _ => {
+                    let (l, r) = (self.expr(lhs, width, signed, this), self.expr(rhs, width, signed, this));
+                    match op {
+                        BinaryOp::Or => format!("rt::or::<{}>({}, {})", w, l, r),
+                        BinaryOp::And => format!("rt::and::<{}>({}, {})", w, l, r),
+                        BinaryOp::Xor => format!("rt::xor::<{}>({}, {})", w, l, r),
+                        _ => {
+                            let func = match op {
+                                BinaryOp::Add => "add",
+                                BinaryOp::Sub => "sub",
+                                BinaryOp::Mul => "mul",
+                                BinaryOp::Div if signed => "sdiv",
+                                BinaryOp::Div => "udiv",
+                                BinaryOp::Mod if signed => "srem",
+                                BinaryOp::Mod => "urem",
+                                BinaryOp::Eq => "xnor",
+                                _ => unreachable!(),
+                            };
+                            format!("rt::{}::<{}>({}, {}, {})", func, w, l, r, width)
+                        }
+                    }
+                }
+            },
+            ExprKind::Cond(cond, then_expr, else_expr) => format!(
+                "if !rt::is_zero(&{}) {{ {} }} else {{ {} }}",
+                self.expr(cond, cond.width, cond.signed, this),
+                self.expr(then_expr, width, signed, this),
+                self.expr(else_expr, width, signed, this)
+            ),
+        }
+    }
+
+    /// Generates a concatenation in its own width.
+    fn concat(&self, exprs: &[Expr], this: &str) -> String {
+        let width = exprs.iter().map(|e| e.width).sum::<usize>();
+        let mut lo = width;
+        let parts = exprs
+            .iter()
+            .map(|e| {
+                lo -= e.width;
+                format!("rt::set(&mut c, {}, {}, &{}, {});", width, lo, self.expr(e, e.width, e.signed, this), e.width)
+            })
+            .join("\n");
+        format!("{{\n    let mut c = [0; {}];\n{}\n    c\n}}", words(width), indent(parts, INDENT))
+    }
+}
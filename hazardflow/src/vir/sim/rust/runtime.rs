@@ -0,0 +1,420 @@
+//! Runtime of generated simulators.
+//!
+//! Values are little-endian `u64` words. Every function takes the width of its operands and keeps the bits above
+//! the width zero. This file is embedded verbatim into the generated code.
+
+#![allow(dead_code)]
+
+use std::cmp::Ordering;
+
+#[inline(always)]
+fn low_mask(n: usize) -> u64 {
+    if n >= 64 {
+        u64::MAX
+    } else {
+        (1 << n) - 1
+    }
+}
+
+#[inline(always)]
+fn word<const N: usize>(v: &[u64; N], i: usize) -> u64 {
+    if i < N {
+        v[i]
+    } else {
+        0
+    }
+}
+
+/// Clears the bits above `w`.
+#[inline(always)]
+pub(crate) fn mask<const N: usize>(mut v: [u64; N], w: usize) -> [u64; N] {
+    for (i, x) in v.iter_mut().enumerate() {
+        let lo = i * 64;
+        if lo >= w {
+            *x = 0;
+        } else {
+            *x &= low_mask(w - lo);
+        }
+    }
+    v
+}
+
+/// Returns the bit at `i`.
+#[inline(always)]
+pub(crate) fn bit<const N: usize>(v: &[u64; N], i: usize) -> bool {
+    (word(v, i / 64) >> (i % 64)) & 1 == 1
+}
+
+/// Truncates or extends a `from`-bit value to `to` bits. Extension copies the sign bit if `signed`.
+#[inline(always)]
+pub(crate) fn ext<const N: usize, const M: usize>(v: [u64; N], from: usize, to: usize, signed: bool) -> [u64; M] {
+    let fill = if signed && from > 0 && bit(&v, from - 1) { u64::MAX } else { 0 };
+    let mut out = [0; M];
+    for (i, x) in out.iter_mut().enumerate() {
+        let lo = i * 64;
+        *x = if lo + 64 <= from {
+            v[i]
+        } else if lo >= from {
+            fill
+        } else {
+            let k = from - lo;
+            (v[i] & low_mask(k)) | (fill << k)
+        };
+    }
+    mask(out, to)
+}
+
+/// Returns `w` bits starting from `lo`.
+#[inline(always)]
+pub(crate) fn slice<const N: usize, const M: usize>(v: &[u64; N], lo: usize, w: usize) -> [u64; M] {
+    let mut out = [0; M];
+    if lo >= N * 64 {
+        return out;
+    }
+    let (s, b) = (lo / 64, lo % 64);
+    for (i, x) in out.iter_mut().enumerate() {
+        let (l, h) = (word(v, i + s), word(v, i + s + 1));
+        *x = if b == 0 { l } else { (l >> b) | (h << (64 - b)) };
+    }
+    mask(out, w)
+}
+
+/// Overwrites `w` bits of the `dst_w`-bit value `dst` starting from `lo` with `val`.
+#[inline(always)]
+pub(crate) fn set<const N: usize, const M: usize>(
+    dst: &mut [u64; N],
+    dst_w: usize,
+    lo: usize,
+    val: &[u64; M],
+    w: usize,
+) {
+    if lo >= dst_w {
+        return;
+    }
+    let w = w.min(dst_w - lo);
+    let mut k = 0;
+    while k < w {
+        let n = (64 - (lo + k) % 64).min(64 - k % 64).min(w - k);
+        let bits = (val[k / 64] >> (k % 64)) & low_mask(n);
+        let (d, sh) = ((lo + k) / 64, (lo + k) % 64);
+        dst[d] = (dst[d] & !(low_mask(n) << sh)) | (bits << sh);
+        k += n;
+    }
+}
+
+/// Returns a 1-bit value.
+#[inline(always)]
+pub(crate) fn from_bool<const M: usize>(b: bool) -> [u64; M] {
+    let mut out = [0; M];
+    if let Some(x) = out.first_mut() {
+        *x = b as u64;
+    }
+    out
+}
+
+/// Returns `true` if every bit is zero.
+#[inline(always)]
+pub(crate) fn is_zero<const N: usize>(v: &[u64; N]) -> bool {
+    v.iter().all(|x| *x == 0)
+}
+
+/// Returns the value as `usize`, saturating at `usize::MAX`.
+#[inline(always)]
+pub(crate) fn to_usize<const N: usize>(v: &[u64; N]) -> usize {
+    if v.iter().skip(1).any(|x| *x != 0) {
+        usize::MAX
+    } else {
+        usize::try_from(word(v, 0)).unwrap_or(usize::MAX)
+    }
+}
+
+#[inline(always)]
+pub(crate) fn not<const N: usize>(v: [u64; N], w: usize) -> [u64; N] {
+    mask(v.map(|x| !x), w)
+}
+
+#[inline(always)]
+pub(crate) fn and<const N: usize>(mut l: [u64; N], r: [u64; N]) -> [u64; N] {
+    l.iter_mut().zip(r).for_each(|(l, r)| *l &= r);
+    l
+}
+
+#[inline(always)]
+pub(crate) fn or<const N: usize>(mut l: [u64; N], r: [u64; N]) -> [u64; N] {
+    l.iter_mut().zip(r).for_each(|(l, r)| *l |= r);
+    l
+}
+
+#[inline(always)]
+pub(crate) fn xor<const N: usize>(mut l: [u64; N], r: [u64; N]) -> [u64; N] {
+    l.iter_mut().zip(r).for_each(|(l, r)| *l ^= r);
+    l
+}
+
+#[inline(always)]
+pub(crate) fn xnor<const N: usize>(l: [u64; N], r: [u64; N], w: usize) -> [u64; N] {
+    not(xor(l, r), w)
+}
+
+#[inline(always)]
+pub(crate) fn add<const N: usize>(mut l: [u64; N], r: [u64; N], w: usize) -> [u64; N] {
+    let mut carry = false;
+    for (l, r) in l.iter_mut().zip(r) {
+        let (s, c1) = l.overflowing_add(r);
+        let (s, c2) = s.overflowing_add(carry as u64);
+        *l = s;
+        carry = c1 || c2;
+    }
+    mask(l, w)
+}
+
+#[inline(always)]
+pub(crate) fn sub<const N: usize>(mut l: [u64; N], r: [u64; N], w: usize) -> [u64; N] {
+    let mut borrow = false;
+    for (l, r) in l.iter_mut().zip(r) {
+        let (d, b1) = l.overflowing_sub(r);
+        let (d, b2) = d.overflowing_sub(borrow as u64);
+        *l = d;
+        borrow = b1 || b2;
+    }
+    mask(l, w)
+}
+
+#[inline(always)]
+pub(crate) fn neg<const N: usize>(v: [u64; N], w: usize) -> [u64; N] {
+    sub([0; N], v, w)
+}
+
+#[inline(always)]
+pub(crate) fn mul<const N: usize>(l: [u64; N], r: [u64; N], w: usize) -> [u64; N] {
+    if N == 1 {
+        return mask([l[0].wrapping_mul(r[0]); N], w);
+    }
+    let mut acc = [0; N];
+    for i in 0..N {
+        if l[i] == 0 {
+            continue;
+        }
+        let mut carry = 0u128;
+        for j in 0..(N - i) {
+            let t = (l[i] as u128) * (r[j] as u128) + acc[i + j] as u128 + carry;
+            acc[i + j] = t as u64;
+            carry = t >> 64;
+        }
+    }
+    mask(acc, w)
+}
+
+fn shl1<const N: usize>(v: &mut [u64; N], b: bool) -> bool {
+    let mut carry = b as u64;
+    for x in v.iter_mut() {
+        let next = *x >> 63;
+        *x = (*x << 1) | carry;
+        carry = next;
+    }
+    carry == 1
+}
+
+/// Unsigned division and remainder. Division by zero yields zeros.
+pub(crate) fn udivrem<const N: usize>(l: [u64; N], r: [u64; N], w: usize) -> ([u64; N], [u64; N]) {
+    if is_zero(&r) {
+        return ([0; N], [0; N]);
+    }
+    if N == 1 {
+        return ([l[0] / r[0]; N], [l[0] % r[0]; N]);
+    }
+    let (mut q, mut rem) = ([0; N], [0; N]);
+    for i in (0..w).rev() {
+        let carry = shl1(&mut rem, bit(&l, i));
+        if carry || ucmp(&rem, &r) != Ordering::Less {
+            rem = sub(rem, r, N * 64);
+            q[i / 64] |= 1 << (i % 64);
+        }
+    }
+    (q, rem)
+}
+
+#[inline(always)]
+pub(crate) fn udiv<const N: usize>(l: [u64; N], r: [u64; N], w: usize) -> [u64; N] {
+    udivrem(l, r, w).0
+}
+
+#[inline(always)]
+pub(crate) fn urem<const N: usize>(l: [u64; N], r: [u64; N], w: usize) -> [u64; N] {
+    udivrem(l, r, w).1
+}
+
+fn abs<const N: usize>(v: [u64; N], w: usize) -> [u64; N] {
+    if w > 0 && bit(&v, w - 1) {
+        neg(v, w)
+    } else {
+        v
+    }
+}
+
+/// Signed division, truncating towards zero.
+pub(crate) fn sdiv<const N: usize>(l: [u64; N], r: [u64; N], w: usize) -> [u64; N] {
+    let q = udiv(abs(l, w), abs(r, w), w);
+    if w > 0 && bit(&l, w - 1) != bit(&r, w - 1) {
+        neg(q, w)
+    } else {
+        q
+    }
+}
+
+/// Signed remainder, with the sign of the dividend.
+pub(crate) fn srem<const N: usize>(l: [u64; N], r: [u64; N], w: usize) -> [u64; N] {
+    let rem = urem(abs(l, w), abs(r, w), w);
+    if w > 0 && bit(&l, w - 1) {
+        neg(rem, w)
+    } else {
+        rem
+    }
+}
+
+#[inline(always)]
+pub(crate) fn shl<const N: usize>(v: [u64; N], amount: usize, w: usize) -> [u64; N] {
+    let mut out = [0; N];
+    if amount < w {
+        set(&mut out, w, amount, &v, w - amount);
+    }
+    out
+}
+
+#[inline(always)]
+pub(crate) fn lshr<const N: usize>(v: [u64; N], amount: usize, w: usize) -> [u64; N] {
+    slice(&v, amount, w)
+}
+
+#[inline(always)]
+pub(crate) fn ashr<const N: usize>(v: [u64; N], amount: usize, w: usize) -> [u64; N] {
+    if w == 0 || !bit(&v, w - 1) {
+        return lshr(v, amount, w);
+    }
+    let amount = amount.min(w);
+    let mut out = mask([u64::MAX; N], w);
+    set(&mut out, w, 0, &lshr(v, amount, w), w - amount);
+    out
+}
+
+#[inline(always)]
+pub(crate) fn ucmp<const N: usize>(l: &[u64; N], r: &[u64; N]) -> Ordering {
+    for i in (0..N).rev() {
+        match l[i].cmp(&r[i]) {
+            Ordering::Equal => continue,
+            ord => return ord,
+        }
+    }
+    Ordering::Equal
+}
+
+/// Compares two `w`-bit values.
+#[inline(always)]
+pub(crate) fn cmp<const N: usize>(l: &[u64; N], r: &[u64; N], w: usize, signed: bool) -> Ordering {
+    if signed && w > 0 {
+        match (bit(l, w - 1), bit(r, w - 1)) {
+            (true, false) => return Ordering::Less,
+            (false, true) => return Ordering::Greater,
+            _ => {}
+        }
+    }
+    ucmp(l, r)
+}
+
+fn to_str_radix(v: &[u64], radix: u64) -> String {
+    let mut rest = v.to_vec();
+    let mut digits = vec![];
+    while rest.iter().any(|x| *x != 0) {
+        let mut rem = 0u128;
+        for x in rest.iter_mut().rev() {
+            let cur = (rem << 64) | *x as u128;
+            *x = (cur / radix as u128) as u64;
+            rem = cur % radix as u128;
+        }
+        digits.push(std::char::from_digit(rem as u32, radix as u32).unwrap());
+    }
+    if digits.is_empty() {
+        digits.push('0');
+    }
+    digits.iter().rev().collect()
+}
+
+/// Formats a `$display` string. Each argument is its words, width and signedness.
+pub(crate) fn format(fstring: &str, args: &[(&[u64], usize, bool)]) -> String {
+    let mut out = String::new();
+    let mut args = args.iter();
+    let mut chars = fstring.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some('n') => out.push('\n'),
+                Some('t') => out.push('\t'),
+                Some(c) => out.push(c),
+                None => out.push('\\'),
+            },
+            '%' => {
+                let mut spec = String::new();
+                while let Some(c) = chars.next_if(|c| c.is_ascii_digit()) {
+                    spec.push(c);
+                }
+                let Some(conv) = chars.next() else {
+                    out.push('%');
+                    break;
+                };
+                if conv == '%' {
+                    out.push('%');
+                    continue;
+                }
+                let Some((value, width, signed)) = args.next() else {
+                    continue;
+                };
+                let (value, width, signed) = (value.to_vec(), *width, *signed);
+                let pad = spec.is_empty();
+                let (radix, digits) = match conv.to_ascii_lowercase() {
+                    'b' => (2, width),
+                    'o' => (8, (width + 2) / 3),
+                    'h' | 'x' => (16, (width + 3) / 4),
+                    'c' => {
+                        out.push(char::from(value.first().copied().unwrap_or(0) as u8));
+                        continue;
+                    }
+                    't' => (10, 0),
+                    _ => {
+                        let mut ones = vec![u64::MAX; value.len()];
+                        for (i, x) in ones.iter_mut().enumerate() {
+                            *x &= low_mask(width.saturating_sub(i * 64));
+                        }
+                        (10, to_str_radix(&ones, 10).len())
+                    }
+                };
+                let negative =
+                    radix == 10 && signed && width > 0 && (value[(width - 1) / 64] >> ((width - 1) % 64)) & 1 == 1;
+                let text = if negative {
+                    // Two's complement negation within `width` bits.
+                    let mut magnitude = value.iter().map(|x| !x).collect::<Vec<_>>();
+                    for (i, x) in magnitude.iter_mut().enumerate() {
+                        *x &= low_mask(width.saturating_sub(i * 64));
+                    }
+                    for x in magnitude.iter_mut() {
+                        let (s, c) = x.overflowing_add(1);
+                        *x = s;
+                        if !c {
+                            break;
+                        }
+                    }
+                    format!("-{}", to_str_radix(&magnitude, 10))
+                } else {
+                    to_str_radix(&value, radix)
+                };
+                let text = if conv == 'X' || conv == 'H' { text.to_uppercase() } else { text };
+                if pad {
+                    let fill = if radix == 10 { ' ' } else { '0' };
+                    out.extend(std::iter::repeat(fill).take(digits.saturating_sub(text.len())));
+                }
+                out.push_str(&text);
+            }
+            _ => out.push(c),
+        }
+    }
+    out
+}
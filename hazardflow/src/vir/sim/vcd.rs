@@ -0,0 +1,281 @@
+//! Waveform of the simulated signals in the value change dump (VCD) format of IEEE 1364.
+//!
+//! Every port, net, register, and integer is dumped, with arrays flattened as in [`Simulator::peek`]. The scopes
+//! follow the module instantiations that [`integrate`] inlined (see [`instance_scopes`]), and the prefixes it added to
+//! the names are stripped.
+//!
+//! The implicit `clk` rises at every multiple of [`PERIOD`] and falls in between. Values are sampled right after each
+//! rising edge and right before the next one, so that inputs poked between steps show up at the falling edge.
+//!
+//! Dumps from other simulators can be read back with [`read_vcd`].
+//!
+//! [`Simulator::peek`]: super::Simulator::peek
+//! [`integrate`]: crate::vir::integrate
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write;
+
+use super::bits::Bits;
+use super::netlist::*;
+use crate::compiler::error::VirgenResult;
+use crate::vir::utils::{extract_decls, instances};
+use crate::vir::*;
+
+/// Time units per clock cycle.
+pub(crate) const PERIOD: u64 = 10;
+
+/// Instance and module names of the submodules enclosing each signal of an integrated module, outermost first, by the
+/// name of the signal.
+pub(crate) type Scopes = HashMap<String, Vec<(String, String)>>;
+
+/// Returns the scopes of the signals that [`integrate`] declares for the module instantiations in `top`.
+///
+/// The instantiations are followed from `top` through `modules`, and the signals of an instance are named as
+/// [`integrate`] does, i.e., prefixed with the name of the instantiated module.
+pub(crate) fn instance_scopes(modules: &HashMap<String, Module>, top: &str) -> Scopes {
+    fn collect(module: &Module, scope: &[(String, String)], modules: &HashMap<String, Module>, scopes: &mut Scopes) {
+        for inst in instances(&module.module_items) {
+            let Some(submodule) = modules.get(&inst.module_name) else { continue };
+            let scope = [scope, &[(inst.inst_name.clone(), inst.module_name.clone())]].concat();
+            for ident in extract_decls(submodule).into_iter().filter(|ident| ident != "clk" && ident != "rst") {
+                scopes.insert(format!("{}_{}", inst.module_name, ident), scope.clone());
+            }
+            collect(submodule, &scope, modules, scopes);
+        }
+    }
+
+    let mut scopes = Scopes::new();
+    if let Some(module) = modules.get(top) {
+        collect(module, &[], modules, &mut scopes);
+    }
+    scopes
+}
+
+/// Recorded waveform.
+#[derive(Debug, Clone)]
+pub(crate) struct Waveform {
+    /// Values dumped most recently.
+    last: Vec<Bits>,
+
+    /// Level of `clk` dumped most recently.
+    clk: bool,
+
+    /// Value changes, after the header.
+    changes: String,
+}
+
+/// Scope in the dump.
+#[derive(Debug, Default)]
+struct Scope {
+    /// Name of the module instantiated in the scope.
+    module: String,
+
+    /// Signals declared directly in the scope.
+    signals: Vec<SignalId>,
+
+    /// Nested scopes by the instance names.
+    children: BTreeMap<String, Scope>,
+}
+
+impl Waveform {
+    /// Starts a waveform at `time` with the current values.
+    pub(crate) fn new(netlist: &Netlist, values: &[Bits], time: u64) -> Self {
+        let mut waveform = Self { last: values.to_vec(), clk: false, changes: String::new() };
+        writeln!(waveform.changes, "#{}\n$dumpvars", time).unwrap();
+        for (id, value) in values.iter().enumerate() {
+            let value = if Some(id) == clk_id(netlist) { Bits::from_bool(false) } else { value.clone() };
+            waveform.dump(id, &value);
+        }
+        writeln!(waveform.changes, "$end").unwrap();
+        waveform
+    }
+
+    /// Records the values that changed since the last sample.
+    pub(crate) fn sample(&mut self, netlist: &Netlist, values: &[Bits], time: u64, clk: bool) {
+        writeln!(self.changes, "#{}", time).unwrap();
+        if let Some(id) = clk_id(netlist).filter(|_| clk != self.clk) {
+            self.dump(id, &Bits::from_bool(clk));
+        }
+        self.clk = clk;
+        for (id, value) in values.iter().enumerate() {
+            if Some(id) != clk_id(netlist) && self.last[id] != *value {
+                self.dump(id, value);
+                self.last[id] = value.clone();
+            }
+        }
+    }
+
+    /// Returns the dump of the module named `top`.
+    pub(crate) fn to_vcd(&self, netlist: &Netlist, top: &str) -> String {
+        let mut root = Scope { module: top.to_string(), ..Default::default() };
+        for (id, signal) in netlist.signals.iter().enumerate() {
+            let scope = signal.scope.iter().fold(&mut root, |scope, (inst, module)| {
+                scope
+                    .children
+                    .entry(inst.clone())
+                    .or_insert_with(|| Scope { module: module.clone(), ..Default::default() })
+            });
+            scope.signals.push(id);
+        }
+
+        let mut vcd = String::new();
+        writeln!(vcd, "$version hazardflow $end").unwrap();
+        writeln!(vcd, "$timescale 1ns $end").unwrap();
+        root.write(netlist, top, true, &mut vcd);
+        writeln!(vcd, "$enddefinitions $end").unwrap();
+        vcd.push_str(&self.changes);
+        vcd
+    }
+
+    fn dump(&mut self, id: SignalId, value: &Bits) {
+        if value.width() == 1 {
+            writeln!(self.changes, "{}{}", u8::from(value.bit(0)), code(id)).unwrap();
+        } else {
+            writeln!(self.changes, "b{} {}", value.to_str_radix(2), code(id)).unwrap();
+        }
+    }
+}
+
+impl Scope {
+    /// Writes the declarations of the scope named `name`. The names of the signals are prefixed with the module
+    /// name unless the scope is the top.
+    fn write(&self, netlist: &Netlist, name: &str, top: bool, vcd: &mut String) {
+        writeln!(vcd, "$scope module {} $end", name).unwrap();
+        for id in &self.signals {
+            let signal = &netlist.signals[*id];
+            let kind = match signal.kind {
+                SignalKind::Reg => "reg",
+                SignalKind::Integer => "integer",
+                SignalKind::Input | SignalKind::Output | SignalKind::Wire => "wire",
+            };
+            let prefix = format!("{}_", self.module);
+            let name = if top { &signal.name } else { signal.name.strip_prefix(&prefix).unwrap_or(&signal.name) };
+            let width = signal.total_width();
+            let range = if width == 1 { String::new() } else { format!(" [{}:0]", width - 1) };
+            writeln!(vcd, "$var {} {} {} {}{} $end", kind, width, code(*id), name, range).unwrap();
+        }
+        for (child, scope) in &self.children {
+            scope.write(netlist, child, false, vcd);
+        }
+        writeln!(vcd, "$upscope $end").unwrap();
+    }
+}
+
+fn clk_id(netlist: &Netlist) -> Option<SignalId> {
+    netlist.ids.get("clk").copied().filter(|id| netlist.signals[*id].kind == SignalKind::Input)
+}
+
+/// Returns the identifier code of a signal, in base 94 with the printable ASCII characters.
+fn code(mut id: SignalId) -> String {
+    let mut code = String::new();
+    loop {
+        code.push(char::from(b'!' + (id % 94) as u8));
+        id /= 94;
+        if id == 0 {
+            return code;
+        }
+        id -= 1;
+    }
+}
+
+/// Waveform read from a VCD file.
+#[derive(Debug, Clone, Default)]
+pub struct VcdDump {
+    /// Declared variables.
+    pub vars: Vec<VcdVar>,
+
+    /// Value changes by the identifier codes, grouped by time in increasing order.
+    pub changes: Vec<(u64, Vec<(String, Bits)>)>,
+}
+
+/// Variable declared in a VCD file.
+#[derive(Debug, Clone)]
+pub struct VcdVar {
+    /// Names of the enclosing scopes, outermost first.
+    pub scope: Vec<String>,
+
+    /// Reference name, without the bit range.
+    pub name: String,
+
+    /// Identifier code. Variables with the same code share the value.
+    pub code: String,
+
+    /// Width.
+    pub width: usize,
+}
+
+/// Reads a VCD file.
+///
+/// Real values and unknown sections are skipped, and `x` and `z` bits are read as zero.
+pub fn read_vcd(vcd: &str) -> VirgenResult<VcdDump> {
+    let mut dump = VcdDump::default();
+    let mut widths = HashMap::<String, usize>::new();
+    let mut scope = vec![];
+    let mut tokens = vcd.split_whitespace();
+
+    while let Some(token) = tokens.next() {
+        match token {
+            "$scope" => match until_end(&mut tokens)[..] {
+                [_, name] => scope.push(name.to_string()),
+                _ => return Err(sim_error("malformed `$scope` in VCD".to_string())),
+            },
+            "$upscope" => {
+                until_end(&mut tokens);
+                scope.pop();
+            }
+            "$var" => match until_end(&mut tokens)[..] {
+                [_, width, code, name, ..] => {
+                    let width = width.parse().map_err(|_| sim_error(format!("invalid width of `{}` in VCD", name)))?;
+                    widths.insert(code.to_string(), width);
+                    dump.vars.push(VcdVar {
+                        scope: scope.clone(),
+                        name: name.to_string(),
+                        code: code.to_string(),
+                        width,
+                    });
+                }
+                _ => return Err(sim_error("malformed `$var` in VCD".to_string())),
+            },
+            // Value changes follow these keywords until `$end`.
+            "$dumpvars" | "$dumpall" | "$dumpon" | "$dumpoff" | "$end" => {}
+            _ if token.starts_with('$') => {
+                until_end(&mut tokens);
+            }
+            _ if token.starts_with('#') => {
+                let time = token[1..].parse().map_err(|_| sim_error(format!("invalid time `{}` in VCD", token)))?;
+                dump.changes.push((time, vec![]));
+            }
+            _ if token.starts_with(['b', 'B', 'r', 'R']) => {
+                let code = tokens.next().ok_or_else(|| sim_error("missing identifier code in VCD".to_string()))?;
+                if token.starts_with(['r', 'R']) {
+                    continue;
+                }
+                let width = widths.get(code).copied().unwrap_or(token.len() - 1);
+                let value = Bits::from_binary_str(&token[1..], width)
+                    .ok_or_else(|| sim_error(format!("invalid value `{}` in VCD", token)))?;
+                record(&mut dump, code, value);
+            }
+            _ => {
+                let (value, code) = token.split_at(1);
+                let width = widths.get(code).copied().unwrap_or(1);
+                let value = Bits::from_binary_str(value, width)
+                    .ok_or_else(|| sim_error(format!("invalid value `{}` in VCD", token)))?;
+                record(&mut dump, code, value);
+            }
+        }
+    }
+
+    Ok(dump)
+}
+
+/// Returns the tokens until the next `$end`.
+fn until_end<'a>(tokens: &mut impl Iterator<Item = &'a str>) -> Vec<&'a str> {
+    tokens.take_while(|token| *token != "$end").collect()
+}
+
+fn record(dump: &mut VcdDump, code: &str, value: Bits) {
+    if dump.changes.is_empty() {
+        dump.changes.push((0, vec![]));
+    }
+    dump.changes.last_mut().unwrap().1.push((code.to_string(), value));
+}
@@ -0,0 +1,152 @@
+//! Source locations of the generated Verilog.
+//!
+//! With `--src-loc comment` or `--src-loc attribute`, each statement is annotated with the location of the Rust code
+//! it is generated from, as a comment or a `src` attribute:
+//!
+//! ```verilog
+//! // hazardflow-designs/src/cpu/exe.rs:112:9
+//! if (cond) begin
+//!     x = y; // hazardflow-designs/src/cpu/exe.rs:113:13
+//! end
+//!
+//! (* src = "hazardflow-designs/src/cpu/exe.rs:113:13" *) x = y;
+//! ```
+//!
+//! Without them, the statements are commented with their spans as printed by rustc.
+//!
+//! [`gen_src_map`] reads the annotations back into the lines of the Verilog they annotate, which the compiler writes in
+//! `build/<top>/<top>.srcmap.json` so that tools can jump from the lines in lint, synthesis and timing reports to the
+//! Rust code.
+
+use std::fmt;
+
+use serde::Serialize;
+
+use crate::compiler::SrcLoc;
+
+/// Location in the Rust source.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SrcLocation {
+    /// Path of the file.
+    pub file: String,
+
+    /// Line, starting from 1.
+    pub line: usize,
+
+    /// Column, starting from 1.
+    pub column: usize,
+}
+
+impl fmt::Display for SrcLocation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}:{}", self.file, self.line, self.column)
+    }
+}
+
+impl SrcLocation {
+    /// Parses `file:line:column`.
+    fn parse(loc: &str) -> Option<Self> {
+        let mut parts = loc.rsplitn(3, ':');
+        let column = parts.next()?.parse().ok()?;
+        let line = parts.next()?.parse().ok()?;
+        let file = parts.next().filter(|file| !file.is_empty())?.to_string();
+        Some(Self { file, line, column })
+    }
+}
+
+/// Line of the generated Verilog, mapped to the Rust source.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SrcMapping {
+    /// Line in the Verilog, starting from 1.
+    pub line: usize,
+
+    /// Location in the Rust source.
+    pub src: SrcLocation,
+}
+
+fn no_location(_: rustc_span::Span) -> Option<SrcLocation> {
+    None
+}
+
+/// Annotates the statements with their source locations while generating Verilog.
+pub struct SrcLocAnnotator<'a> {
+    kind: SrcLoc,
+    locate: &'a dyn Fn(rustc_span::Span) -> Option<SrcLocation>,
+}
+
+impl fmt::Debug for SrcLocAnnotator<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SrcLocAnnotator").field("kind", &self.kind).finish()
+    }
+}
+
+impl Default for SrcLocAnnotator<'_> {
+    fn default() -> Self {
+        Self { kind: SrcLoc::None, locate: &no_location }
+    }
+}
+
+impl<'a> SrcLocAnnotator<'a> {
+    /// Creates a new annotator, which finds the locations of the spans with `locate`.
+    pub fn new(kind: SrcLoc, locate: &'a dyn Fn(rustc_span::Span) -> Option<SrcLocation>) -> Self {
+        Self { kind, locate }
+    }
+
+    fn locate(&self, span: rustc_span::Span) -> Option<SrcLocation> {
+        match self.kind {
+            SrcLoc::None => None,
+            SrcLoc::Comment | SrcLoc::Attribute => (self.locate)(span),
+        }
+    }
+
+    /// Annotates a statement in a single line.
+    pub(crate) fn stmt(&self, span: rustc_span::Span, stmt: String) -> String {
+        match (self.kind, self.locate(span)) {
+            (SrcLoc::Comment, Some(loc)) => format!("{} // {}", stmt, loc),
+            (SrcLoc::Attribute, Some(loc)) => format!("(* src = \"{}\" *) {}", loc, stmt),
+            (SrcLoc::None, _) => format!("{} // {:?}", stmt, span),
+            _ => stmt,
+        }
+    }
+
+    /// Annotates a statement spanning multiple lines, such as `if` and `case`, in the line before it.
+    pub(crate) fn block(&self, span: rustc_span::Span, block: String) -> String {
+        match (self.kind, self.locate(span)) {
+            (SrcLoc::Comment, Some(loc)) => format!("// {}\n{}", loc, block),
+            (SrcLoc::Attribute, Some(loc)) => format!("(* src = \"{}\" *)\n{}", loc, block),
+            (SrcLoc::None, _) => format!("// {:?}\n{}", span, block),
+            _ => block,
+        }
+    }
+}
+
+/// Returns the lines of `verilog` annotated by [`SrcLocAnnotator`].
+///
+/// A statement spanning multiple lines is mapped by its first line.
+pub fn gen_src_map(verilog: &str) -> Vec<SrcMapping> {
+    let mut src_map = vec![];
+    let mut pending = None;
+    for (i, line) in verilog.lines().enumerate() {
+        let line_no = i + 1;
+        if let Some(src) = pending.take() {
+            src_map.push(SrcMapping { line: line_no, src });
+            continue;
+        }
+
+        let line = line.trim();
+        if let Some(attr) = line.strip_prefix("(* src = \"") {
+            let Some((loc, stmt)) = attr.split_once("\" *)") else { continue };
+            let Some(src) = SrcLocation::parse(loc) else { continue };
+            if stmt.is_empty() {
+                pending = Some(src);
+            } else {
+                src_map.push(SrcMapping { line: line_no, src });
+            }
+        } else if let Some(src) = line.strip_prefix("// ").and_then(SrcLocation::parse) {
+            pending = Some(src);
+        } else if let Some(src) = line.rsplit_once("; // ").and_then(|(_, loc)| SrcLocation::parse(loc)) {
+            src_map.push(SrcMapping { line: line_no, src });
+        }
+    }
+    src_map
+}
@@ -0,0 +1,662 @@
+//! SystemVerilog output.
+//!
+//! The Verilog output flattens every Rust value into bit vectors, one port for each leaf of its type. The SystemVerilog
+//! output keeps the types: [`SvTypes`] declares the Rust types of the channels recorded in the [`DebugInfo`] in a
+//! package, and [`gen_sv_module`] declares each payload and resolver as a single port of its type named after its Rust
+//! path, e.g., `output wire HOption_MemReq_t egress_input_0_input_0_payload`. The flattened ports become nets connected
+//! to the fields of the typed ports, so the body of the module is the same as in the Verilog output.
+//!
+//! The types follow the layout of [`TypeInfo::leaves`]:
+//!
+//! - A struct or a tuple is a `typedef struct packed` named `<type>_t`, whose fields are named `_0`, `_1`, ... for
+//!   tuples. Zero-width fields are omitted.
+//! - The discriminant of an enum is a `typedef enum logic` named `<enum>_e`, with a literal `<enum>_<variant>` for each
+//!   variant. An enum without fields is the `typedef enum logic` itself, and an enum with fields is a struct of the
+//!   discriminant followed by a field for each variant with fields. A variant with a single field has the type of the
+//!   field, and a variant with multiple fields is a struct of them named `<type>_<variant>_t`.
+//! - An array of integers is a packed array of `logic`, and an array of other types is a packed array of their type.
+//!
+//! The flattened ports of discriminants are cast to the enum types when assigned to the typed ports. Submodule
+//! instantiations connect the typed ports through nets named `<instance>_<port>`. Channels whose layout cannot be
+//! expressed in the types, and instantiations connecting them to expressions which are not identifiers, keep the
+//! flattened ports.
+
+use std::collections::HashMap;
+
+use itertools::Itertools;
+
+use super::*;
+use crate::compiler::debug_info::{ChannelInfo, DebugInfo, TypeInfo, VariantInfo};
+use crate::compiler::prelude::Shape;
+use crate::utils::indent;
+
+const INDENT: usize = 4;
+
+/// Keywords of SystemVerilog which may collide with Rust names.
+const KEYWORDS: [&str; 69] = [
+    "alias",
+    "always",
+    "and",
+    "assign",
+    "begin",
+    "bit",
+    "break",
+    "buf",
+    "byte",
+    "case",
+    "class",
+    "const",
+    "continue",
+    "default",
+    "do",
+    "else",
+    "end",
+    "enum",
+    "event",
+    "final",
+    "for",
+    "force",
+    "fork",
+    "function",
+    "if",
+    "initial",
+    "inout",
+    "input",
+    "int",
+    "integer",
+    "interface",
+    "join",
+    "local",
+    "logic",
+    "longint",
+    "module",
+    "nand",
+    "new",
+    "nor",
+    "not",
+    "null",
+    "or",
+    "output",
+    "package",
+    "parameter",
+    "real",
+    "reg",
+    "return",
+    "shortint",
+    "signed",
+    "static",
+    "string",
+    "struct",
+    "super",
+    "table",
+    "task",
+    "this",
+    "time",
+    "type",
+    "typedef",
+    "union",
+    "unique",
+    "unsigned",
+    "var",
+    "void",
+    "wait",
+    "while",
+    "wire",
+    "xor",
+];
+
+/// Typedefs of the Rust types in a top module.
+#[derive(Debug, Clone, Default)]
+pub struct SvTypes {
+    /// Types with their names and declarations, where a type is declared after the types of its fields.
+    typedefs: Vec<(TypeInfo, String, String)>,
+}
+
+impl SvTypes {
+    /// Collects the types of the channels in the modules.
+    pub fn new(debug_info: &DebugInfo) -> Self {
+        let mut types = Self::default();
+        for module in debug_info.modules.values() {
+            for channel in &module.channels {
+                types.declare(&channel.ty);
+            }
+        }
+        types
+    }
+
+    /// Generates the package declaring the types.
+    pub fn gen_package(&self, name: &str) -> String {
+        format!(
+            "package {};\n\n{}\n\nendpackage",
+            name,
+            self.typedefs.iter().map(|(_, _, typedef)| typedef.as_str()).collect::<Vec<_>>().join("\n\n")
+        )
+    }
+
+    /// Returns the name of a declared type.
+    fn name(&self, ty: &TypeInfo) -> Option<&str> {
+        self.typedefs.iter().find(|(declared, ..)| declared == ty).map(|(_, name, _)| name.as_str())
+    }
+
+    /// Returns `<base><suffix>`, or `<base>_<n><suffix>` if the name is taken by another type.
+    fn fresh_name(&self, base: &str, suffix: &str) -> String {
+        let taken = |name: &str| self.typedefs.iter().any(|(_, declared, _)| declared == name);
+        (0..)
+            .map(|n| if n == 0 { format!("{}{}", base, suffix) } else { format!("{}_{}{}", base, n, suffix) })
+            .find(|name| !taken(name))
+            .unwrap()
+    }
+
+    /// Declares a type and the types in it.
+    fn declare(&mut self, ty: &TypeInfo) {
+        match ty {
+            TypeInfo::Bits { .. } => {}
+            TypeInfo::Array { elt, .. } => self.declare(elt),
+            TypeInfo::Struct { ty: rust_ty, name, fields } => {
+                for field in fields {
+                    self.declare(&field.ty);
+                }
+                if width(ty) == 0 || self.name(ty).is_some() {
+                    return;
+                }
+
+                let base = if name.is_some() { sanitize(rust_ty) } else { format!("tuple_{}", sanitize(rust_ty)) };
+                let name = self.fresh_name(&base, "_t");
+                let members = fields
+                    .iter()
+                    .filter(|field| width(&field.ty) > 0)
+                    .map(|field| format!("{} {};", self.sv_type(&field.ty, &[]), ident(&field.name)))
+                    .collect::<Vec<_>>();
+                let typedef = format!("typedef struct packed {{\n{}\n}} {};", indent(members.join("\n"), INDENT), name);
+                self.typedefs.push((ty.clone(), name, typedef));
+            }
+            TypeInfo::Enum { ty: rust_ty, discriminant_width, variants, .. } => {
+                let discriminant_ty = tag(ty);
+                if *discriminant_width > 0 && self.name(&discriminant_ty).is_none() {
+                    let TypeInfo::Enum { name, .. } = &discriminant_ty else { unreachable!() };
+                    let name = self.fresh_name(&ident(name), "_e");
+                    let prefix = name.strip_suffix("_e").unwrap();
+                    let literals = variants
+                        .iter()
+                        .map(|variant| {
+                            format!("{}_{} = {}'d{}", prefix, variant.name, discriminant_width, variant.discriminant)
+                        })
+                        .collect::<Vec<_>>();
+                    let typedef = format!(
+                        "typedef enum logic [{}-1:0] {{\n{}\n}} {};",
+                        discriminant_width,
+                        indent(literals.join(",\n"), INDENT),
+                        name
+                    );
+                    self.typedefs.push((discriminant_ty, name, typedef));
+                }
+
+                if is_fieldless(ty) {
+                    return;
+                }
+                for variant in variants {
+                    for field in &variant.fields {
+                        self.declare(&field.ty);
+                    }
+                    if variant.fields.len() > 1 {
+                        self.declare(&variant_struct(rust_ty, variant));
+                    }
+                }
+                if width(ty) == 0 || self.name(ty).is_some() {
+                    return;
+                }
+
+                let name = self.fresh_name(&sanitize(rust_ty), "_t");
+                let discriminant = (*discriminant_width > 0)
+                    .then(|| format!("{} discriminant;", self.name(&tag(ty)).unwrap()))
+                    .into_iter();
+                let members = discriminant
+                    .chain(variants.iter().filter_map(|variant| {
+                        let variant_ty = variant_ty(rust_ty, variant);
+                        (width(&variant_ty) > 0)
+                            .then(|| format!("{} {};", self.sv_type(&variant_ty, &[]), ident(&variant.name)))
+                    }))
+                    .collect::<Vec<_>>();
+                let typedef = format!("typedef struct packed {{\n{}\n}} {};", indent(members.join("\n"), INDENT), name);
+                self.typedefs.push((ty.clone(), name, typedef));
+            }
+        }
+    }
+
+    /// Returns the SystemVerilog type of a declared type, in packed arrays of the lengths in `outer`.
+    fn sv_type(&self, ty: &TypeInfo, outer: &[usize]) -> String {
+        let mut dims = outer.to_vec();
+        let mut ty = ty;
+        while let TypeInfo::Array { elt, len, .. } = ty {
+            dims.push(*len);
+            ty = elt;
+        }
+
+        let base = match ty {
+            TypeInfo::Bits { width, signed, .. } => {
+                if *width > 1 {
+                    dims.push(*width);
+                }
+                if *signed { "logic signed" } else { "logic" }.to_string()
+            }
+            TypeInfo::Enum { .. } if is_fieldless(ty) => self.name(&tag(ty)).unwrap().to_string(),
+            _ => self.name(ty).unwrap().to_string(),
+        };
+
+        if dims.is_empty() {
+            base
+        } else {
+            format!("{} {}", base, dims.iter().map(|dim| format!("[{}-1:0]", dim)).join(""))
+        }
+    }
+}
+
+/// Step from a value to a value in it.
+#[derive(Debug, Clone)]
+enum Step {
+    /// Field of a struct, or the discriminant or a field of a variant of an enum.
+    Field(String),
+
+    /// Element of an array enumerated in the flattened ports, with the length of the array.
+    Index(usize),
+}
+
+/// Collects the steps to each leaf of a type with its width and the type of the discriminant if it is the discriminant
+/// of an enum, in the order of [`TypeInfo::leaves`].
+///
+/// Arrays of integers are single leaves, and arrays of other types are enumerated.
+fn collect_leaves(ty: &TypeInfo, steps: Vec<Step>, leaves: &mut Vec<(Vec<Step>, usize, Option<TypeInfo>)>) {
+    let with = |step: Step| [steps.clone(), vec![step]].concat();
+    match ty {
+        TypeInfo::Bits { .. } => leaves.push((steps, width(ty), None)),
+        TypeInfo::Array { elt, len, .. } if matches!(bits_array(elt), TypeInfo::Bits { .. }) => {
+            leaves.push((steps, width(elt) * len, None))
+        }
+        TypeInfo::Array { elt, len, .. } => collect_leaves(elt, with(Step::Index(*len)), leaves),
+        TypeInfo::Struct { fields, .. } => {
+            for field in fields {
+                collect_leaves(&field.ty, with(Step::Field(ident(&field.name))), leaves);
+            }
+        }
+        TypeInfo::Enum { discriminant_width, variants, .. } => {
+            if *discriminant_width > 0 {
+                let discriminant =
+                    if is_fieldless(ty) { vec![] } else { vec![Step::Field("discriminant".to_string())] };
+                leaves.push(([steps.clone(), discriminant].concat(), *discriminant_width, Some(tag(ty))));
+            }
+            for variant in variants {
+                let variant_steps = with(Step::Field(ident(&variant.name)));
+                match variant.fields.as_slice() {
+                    [field] => collect_leaves(&field.ty, variant_steps, leaves),
+                    fields => {
+                        for field in fields {
+                            let field_steps = [variant_steps.clone(), vec![Step::Field(ident(&field.name))]].concat();
+                            collect_leaves(&field.ty, field_steps, leaves);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Returns the innermost element type of nested arrays.
+fn bits_array(ty: &TypeInfo) -> &TypeInfo {
+    match ty {
+        TypeInfo::Array { elt, .. } => bits_array(elt),
+        _ => ty,
+    }
+}
+
+/// Channel declared as a typed port.
+#[derive(Debug, Clone)]
+struct TypedChannel {
+    /// Name of the port, without the prefix for instantiations.
+    port: String,
+
+    /// SystemVerilog type of the port.
+    ty: String,
+
+    /// Whether the value flows into the module.
+    input: bool,
+
+    /// Flattened ports.
+    signals: Vec<Signal>,
+}
+
+/// Flattened port of a typed channel.
+#[derive(Debug, Clone)]
+struct Signal {
+    /// Name of the port.
+    name: String,
+
+    /// Enum type to which the bits are cast, if they are a discriminant.
+    cast: Option<String>,
+
+    /// Selects of the fields of the typed port, with the ranges of the port.
+    selects: Vec<(String, Option<Range>)>,
+}
+
+impl TypedChannel {
+    /// Returns the layout of a channel, or `None` if its flattened ports do not follow its type.
+    fn new(channel: &ChannelInfo, types: &SvTypes) -> Option<Self> {
+        let mut leaves = vec![];
+        collect_leaves(&channel.ty, vec![], &mut leaves);
+        let leaves = leaves.into_iter().filter(|(_, width, _)| *width > 0).collect::<Vec<_>>();
+        if leaves.len() != channel.signals.len() {
+            return None;
+        }
+
+        let signals = leaves
+            .into_iter()
+            .zip(&channel.signals)
+            .map(|((steps, width, discriminant_ty), signal)| {
+                let lens = (channel.count > 1)
+                    .then_some(channel.count)
+                    .into_iter()
+                    .chain(steps.iter().filter_map(|step| if let Step::Index(len) = step { Some(*len) } else { None }))
+                    .collect::<Vec<_>>();
+                let count = lens.iter().product::<usize>();
+                if signal.width != width * count {
+                    return None;
+                }
+
+                let selects = (0..count)
+                    .map(|flat| {
+                        // Indices of the enclosing arrays, outermost first, where the element 0 is in the LSB.
+                        let mut rest = flat;
+                        let mut indices = lens
+                            .iter()
+                            .rev()
+                            .map(|len| {
+                                let index = rest % len;
+                                rest /= len;
+                                index
+                            })
+                            .collect::<Vec<_>>();
+                        indices.reverse();
+                        let mut indices = indices.into_iter();
+
+                        let mut select = String::new();
+                        if channel.count > 1 {
+                            select.push_str(&format!("[{}]", indices.next().unwrap()));
+                        }
+                        for step in &steps {
+                            match step {
+                                Step::Field(name) => select.push_str(&format!(".{}", name)),
+                                Step::Index(_) => select.push_str(&format!("[{}]", indices.next().unwrap())),
+                            }
+                        }
+                        let range = (count > 1).then(|| {
+                            Range::new_range(
+                                Expression::number((flat * width).to_string()),
+                                Expression::number(width.to_string()),
+                            )
+                        });
+                        (select, range)
+                    })
+                    .collect::<Vec<_>>();
+                let cast = discriminant_ty.and_then(|ty| types.name(&ty)).map(|name| name.to_string());
+                Some(Signal { name: signal.name.clone(), cast, selects })
+            })
+            .collect::<Option<Vec<_>>>()?;
+
+        Some(Self {
+            port: ident(&channel.path.replace(|c: char| !c.is_ascii_alphanumeric(), "_")),
+            ty: types.sv_type(&channel.ty, &[channel.count].into_iter().filter(|count| *count > 1).collect::<Vec<_>>()),
+            input: channel.path.starts_with("ingress") == channel.path.ends_with("payload"),
+            signals,
+        })
+    }
+
+    /// Returns the assignments connecting the typed port `port` and the flattened ports, given by their expressions.
+    fn assigns(&self, port: &str, flattened: impl Fn(&str) -> Expression) -> Vec<ContinuousAssign> {
+        self.signals
+            .iter()
+            .flat_map(|signal| {
+                let flattened = flattened(&signal.name);
+                signal.selects.iter().map(move |(select, range)| {
+                    let field = Expression::ident(format!("{}{}", port, select));
+                    let bits = match range {
+                        Some(range) => flattened.clone().with_range(range.clone()),
+                        None => flattened.clone(),
+                    };
+                    if self.input {
+                        ContinuousAssign::new(bits, field)
+                    } else if let Some(cast) = &signal.cast {
+                        ContinuousAssign::new(field, Expression::ident(format!("{}'({})", cast, bits.to_string())))
+                    } else {
+                        ContinuousAssign::new(field, bits)
+                    }
+                })
+            })
+            .collect()
+    }
+}
+
+/// Returns the typed channels of a module.
+fn typed_channels(module_name: &str, debug_info: &DebugInfo, types: &SvTypes) -> Vec<TypedChannel> {
+    debug_info.modules.get(module_name).map_or(vec![], |module| {
+        module.channels.iter().filter_map(|channel| TypedChannel::new(channel, types)).collect()
+    })
+}
+
+/// Returns the index of the channel of each flattened port.
+fn channel_of(channels: &[TypedChannel]) -> HashMap<&str, usize> {
+    channels
+        .iter()
+        .enumerate()
+        .flat_map(|(i, channel)| channel.signals.iter().map(move |signal| (signal.name.as_str(), i)))
+        .collect()
+}
+
+/// Generates a SystemVerilog module importing the types from `package`.
+pub fn gen_sv_module(module: &Module, debug_info: &DebugInfo, types: &SvTypes, package: &str) -> String {
+    // Channels whose flattened ports have the same direction as the typed port.
+    let channels = typed_channels(&module.name, debug_info, types)
+        .into_iter()
+        .filter(|channel| {
+            channel.signals.iter().all(|signal| {
+                module.port_decls.iter().any(|port_decl| match port_decl {
+                    PortDeclaration::Input(_, name) => channel.input && *name == signal.name,
+                    PortDeclaration::Output(_, name) => !channel.input && *name == signal.name,
+                })
+            })
+        })
+        .collect::<Vec<_>>();
+    let channel_of = channel_of(&channels);
+
+    let mut ports = vec![];
+    let mut nets = vec![];
+    let mut declared = vec![false; channels.len()];
+    for port_decl in &module.port_decls {
+        let name = port_decl.name();
+        let Some(i) = channel_of.get(name.as_str()) else {
+            ports.push(port_decl.to_string());
+            continue;
+        };
+        let (PortDeclaration::Input(width, _) | PortDeclaration::Output(width, _)) = port_decl;
+        nets.push(Declaration::net(Shape::new([*width], false), name).to_string());
+        if !declared[*i] {
+            declared[*i] = true;
+            let channel = &channels[*i];
+            ports.push(format!(
+                "{} wire {} {}",
+                if channel.input { "input" } else { "output" },
+                channel.ty,
+                channel.port
+            ));
+        }
+    }
+    let assigns = channels
+        .iter()
+        .flat_map(|channel| channel.assigns(&channel.port, |signal| Expression::ident(signal.to_string())));
+
+    let header = [nets.join("\n"), gen_verilog_conts(&assigns.collect::<Vec<_>>())]
+        .into_iter()
+        .filter(|s| !s.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n\n");
+    let body =
+        module.module_items.iter().map(|item| gen_item(item, debug_info, types)).collect::<Vec<_>>().join("\n\n");
+
+    format!(
+        "module {}\n    import {}::*;\n(\n{}\n);\n\n{}{}generate\n{}\nendgenerate\nendmodule",
+        module.name,
+        package,
+        indent(ports.join(",\n"), INDENT),
+        header,
+        if header.is_empty() { "" } else { "\n\n" },
+        body
+    )
+}
+
+/// Generates a module item, connecting the typed ports of instantiated modules.
+fn gen_item(item: &ModuleItem, debug_info: &DebugInfo, types: &SvTypes) -> String {
+    match item {
+        ModuleItem::ModuleInstantiation(inst) => gen_instantiation(inst, debug_info, types),
+        ModuleItem::Commented(comment_before, comment_after, items) => {
+            format!(
+                "/*\n{}\n*/\n{}{}",
+                indent(comment_before.clone(), INDENT),
+                items.iter().map(|item| gen_item(item, debug_info, types)).collect::<Vec<_>>().join("\n\n"),
+                comment_after.as_ref().map_or("".to_string(), |c| format!("\n/* {} */", c))
+            )
+        }
+        _ => item.to_string(),
+    }
+}
+
+/// Generates a module instantiation, connecting the typed ports through nets.
+fn gen_instantiation(inst: &ModuleInstantiation, debug_info: &DebugInfo, types: &SvTypes) -> String {
+    let connections = inst.port_connections.iter().cloned().collect::<HashMap<_, _>>();
+
+    // Channels whose flattened ports are connected to identifiers, or to any expressions if they are not sliced.
+    let channels = typed_channels(&inst.module_name, debug_info, types)
+        .into_iter()
+        .filter(|channel| {
+            channel.signals.iter().all(|signal| match connections.get(&signal.name) {
+                Some(expr) => {
+                    expr.is_identifier()
+                        || (signal.selects.len() == 1 && signal.selects[0].1.is_none() && channel.input)
+                }
+                None => false,
+            })
+        })
+        .collect::<Vec<_>>();
+    let channel_of = channel_of(&channels);
+
+    let mut port_connections = vec![];
+    let mut connected = vec![false; channels.len()];
+    for (port, expr) in &inst.port_connections {
+        match channel_of.get(port.as_str()) {
+            None => port_connections.push((port.clone(), expr.clone())),
+            Some(i) if !connected[*i] => {
+                connected[*i] = true;
+                let net = format!("{}_{}", inst.inst_name, channels[*i].port);
+                port_connections.push((channels[*i].port.clone(), Expression::ident(net)));
+            }
+            Some(_) => {}
+        }
+    }
+
+    let nets = channels
+        .iter()
+        .map(|channel| format!("wire {} {}_{};", channel.ty, inst.inst_name, channel.port))
+        .collect::<Vec<_>>();
+    // The directions are of the instantiated module, so they are flipped.
+    let assigns = channels
+        .iter()
+        .flat_map(|channel| {
+            let flipped = TypedChannel { input: !channel.input, ..channel.clone() };
+            flipped.assigns(&format!("{}_{}", inst.inst_name, channel.port), |signal| connections[signal].clone())
+        })
+        .collect::<Vec<_>>();
+
+    let inst = ModuleInstantiation { port_connections, ..inst.clone() };
+    [nets.join("\n"), gen_verilog_conts(&assigns), inst.to_string()]
+        .into_iter()
+        .filter(|s| !s.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Returns the total width of a type.
+fn width(ty: &TypeInfo) -> usize {
+    ty.leaves().iter().map(|(_, width)| width).sum()
+}
+
+/// Returns `true` if the type is an enum whose variants have no fields.
+fn is_fieldless(ty: &TypeInfo) -> bool {
+    matches!(ty, TypeInfo::Enum { variants, .. } if variants.iter().all(|variant| width(&variant_ty("", variant)) == 0))
+}
+
+/// Returns the type of the discriminant of an enum, which does not depend on the fields and the generic arguments.
+fn tag(ty: &TypeInfo) -> TypeInfo {
+    let TypeInfo::Enum { ty, name, discriminant_width, variants } = ty else { panic!("`tag` of a non-enum type") };
+    let path = ty.split('<').next().unwrap();
+    TypeInfo::Enum {
+        ty: path.to_string(),
+        name: name.clone(),
+        discriminant_width: *discriminant_width,
+        variants: variants
+            .iter()
+            .map(|variant| VariantInfo {
+                name: variant.name.clone(),
+                discriminant: variant.discriminant,
+                fields: vec![],
+            })
+            .collect(),
+    }
+}
+
+/// Returns the struct of the fields of an enum variant.
+fn variant_struct(enum_ty: &str, variant: &VariantInfo) -> TypeInfo {
+    TypeInfo::Struct {
+        ty: format!("{}::{}", enum_ty, variant.name),
+        name: Some(variant.name.clone()),
+        fields: variant.fields.clone(),
+    }
+}
+
+/// Returns the type of the member for an enum variant.
+fn variant_ty(enum_ty: &str, variant: &VariantInfo) -> TypeInfo {
+    match variant.fields.as_slice() {
+        [field] => field.ty.clone(),
+        _ => variant_struct(enum_ty, variant),
+    }
+}
+
+/// Returns a SystemVerilog name for a Rust type, without the paths of the types, e.g., `HOption_MemReq` for
+/// `std::option::HOption<cpu::MemReq>`.
+fn sanitize(rust_ty: &str) -> String {
+    let mut words = vec![];
+    let mut word = String::new();
+    let mut chars = rust_ty.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c.is_ascii_alphanumeric() || c == '_' {
+            word.push(c);
+            continue;
+        }
+        let is_path = c == ':' && chars.peek() == Some(&':');
+        if !word.is_empty() && !is_path {
+            words.push(std::mem::take(&mut word));
+        }
+        word.clear();
+    }
+    if !word.is_empty() {
+        words.push(word);
+    }
+    ident(&words.join("_"))
+}
+
+/// Escapes a name which is a keyword or starts with a digit.
+fn ident(name: &str) -> String {
+    if name.starts_with(|c: char| c.is_ascii_digit()) {
+        format!("_{}", name)
+    } else if KEYWORDS.contains(&name) {
+        format!("{}_", name)
+    } else {
+        name.to_string()
+    }
+}
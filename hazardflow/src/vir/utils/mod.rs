@@ -40,3 +40,64 @@ fn extract_decls_module_item(module_item: &ModuleItem) -> Vec<String> {
         _ => vec![],
     }
 }
+
+/// Calls `f` on the module instantiations in the module items.
+pub(crate) fn for_each_inst(items: &mut [ModuleItem], f: &mut impl FnMut(&mut ModuleInstantiation)) {
+    for item in items {
+        match item {
+            ModuleItem::ModuleInstantiation(inst) => f(inst),
+            ModuleItem::Commented(_, _, items) => for_each_inst(items, f),
+            _ => {}
+        }
+    }
+}
+
+/// Returns the module instantiations in the module items.
+pub(crate) fn instances(items: &[ModuleItem]) -> Vec<&ModuleInstantiation> {
+    items
+        .iter()
+        .flat_map(|item| match item {
+            ModuleItem::ModuleInstantiation(inst) => vec![inst],
+            ModuleItem::Commented(_, _, items) => instances(items),
+            _ => vec![],
+        })
+        .collect()
+}
+
+/// Returns the names of the modules instantiated in the module items.
+pub(crate) fn submodules(items: &[ModuleItem]) -> Vec<&str> {
+    items
+        .iter()
+        .flat_map(|item| match item {
+            ModuleItem::ModuleInstantiation(inst) => vec![inst.module_name.as_str()],
+            ModuleItem::Commented(_, _, items) => submodules(items),
+            _ => vec![],
+        })
+        .collect()
+}
+
+/// Returns the module renamed to `name`, where the names of the instances and the comments follow the new name.
+pub(crate) fn renamed(module: &Module, name: &str) -> Module {
+    let mut module = module.clone();
+    for_each_inst(&mut module.module_items, &mut |inst| {
+        if let Some(inst_name) = inst.inst_name.strip_prefix(&format!("{}_", module.name)) {
+            inst.inst_name = format!("{}_{}", name, inst_name);
+        }
+    });
+    rename_comments(&mut module.module_items, &module.name, name);
+    module.name = name.to_string();
+    module
+}
+
+/// Renames `from` to `to` in the comments of the module items.
+fn rename_comments(items: &mut [ModuleItem], from: &str, to: &str) {
+    for item in items {
+        if let ModuleItem::Commented(comment_before, comment_after, items) = item {
+            *comment_before = comment_before.replace(from, to);
+            if let Some(comment_after) = comment_after {
+                *comment_after = comment_after.replace(from, to);
+            }
+            rename_comments(items, from, to);
+        }
+    }
+}
@@ -0,0 +1,264 @@
+//! Verilator harness.
+//!
+//! For each top module, the compiler writes a filelist of the sources to simulate, and a C++ harness around the model
+//! Verilator generates from them:
+//!
+//! - [`gen_filelist`] lists the Verilog sources of the FFI modules, given by the `ffi!` macros, followed by the
+//!   generated files, each after the files of the modules it instantiates.
+//! - [`gen_harness`] wraps `V<top>` in a class `<top>_harness`, which drives the clock and the reset, and has an
+//!   accessor for each port named after its channel, e.g., `in_payload_input_0_Some_0` for the port
+//!   `in_input_0_payload_Some_0` of `ingress.input.0.payload`. The ingress payloads and the egress resolvers are set,
+//!   and the ingress resolvers and the egress payloads are read.
+//! - [`gen_main`] is a minimal `main` stepping the harness, to be replaced by a testbench.
+//!
+//! The harness is regenerated with the ports, so a testbench written against the accessors follows the changes of the
+//! port names.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use super::utils::*;
+use super::*;
+use crate::compiler::debug_info::DebugInfo;
+use crate::utils::indent;
+
+const INDENT: usize = 4;
+
+/// Returns the names of the modules, where each module comes after the modules it instantiates.
+///
+/// The instantiated modules which are not in `modules`, such as the FFI modules, are skipped.
+pub fn dependency_order(modules: &HashMap<String, Module>) -> Vec<String> {
+    fn visit<'a>(
+        name: &'a str,
+        modules: &'a HashMap<String, Module>,
+        visited: &mut HashSet<&'a str>,
+        order: &mut Vec<String>,
+    ) {
+        if !visited.insert(name) {
+            return;
+        }
+        let Some(module) = modules.get(name) else {
+            return;
+        };
+        for submodule in submodules(&module.module_items) {
+            visit(submodule, modules, visited, order);
+        }
+        order.push(name.to_string());
+    }
+
+    let mut names = modules.keys().map(String::as_str).collect::<Vec<_>>();
+    names.sort();
+
+    let mut visited = HashSet::new();
+    let mut order = vec![];
+    for name in names {
+        visit(name, modules, &mut visited, &mut order);
+    }
+    order
+}
+
+/// Generates the filelist of the FFI sources and the generated files, one path per line.
+///
+/// The paths are relative to the directory of the filelist, as read by `verilator -F`. The FFI sources are listed as
+/// given by the `ffi!` macros, without duplicates.
+pub fn gen_filelist(ffi_sources: &[String], files: &[String]) -> String {
+    let mut seen = HashSet::new();
+    let mut filelist = String::new();
+    for path in ffi_sources.iter().filter(|source| seen.insert(source.as_str())).chain(files) {
+        filelist.push_str(path);
+        filelist.push('\n');
+    }
+    filelist
+}
+
+/// Returns the C++ type Verilator gives to a port of the width.
+fn port_type(width: usize) -> String {
+    match width {
+        0..=8 => "CData".to_string(),
+        9..=16 => "SData".to_string(),
+        17..=32 => "IData".to_string(),
+        33..=64 => "QData".to_string(),
+        _ => format!("VlWide<{}>", (width + 31) / 32),
+    }
+}
+
+/// Accessor of a port.
+#[derive(Debug)]
+struct Accessor {
+    /// Name of the accessor.
+    name: String,
+
+    /// Rust path and type of the channel, if the port belongs to one.
+    channel: Option<String>,
+}
+
+/// Returns the accessors of the ports, by the names of the ports.
+///
+/// The port `<side>_<interface>_<kind><rest>` of the channel `<ingress|egress>.<interface>.<kind>` is accessed by
+/// `<side>_<kind>_<interface><rest>`, where `.` in the path of the interface is replaced by `_`.
+fn accessors(module: &Module, debug_info: &DebugInfo) -> HashMap<String, Accessor> {
+    let mut accessors = HashMap::new();
+    let Some(module_info) = debug_info.modules.get(&module.name) else {
+        return accessors;
+    };
+
+    for channel in &module_info.channels {
+        let Some((dir, path)) = channel.path.split_once('.') else {
+            continue;
+        };
+        let Some((interface, kind)) = path.rsplit_once('.') else {
+            continue;
+        };
+        let side = match dir {
+            "ingress" => "in",
+            "egress" => "out",
+            _ => continue,
+        };
+        let interface = interface.replace('.', "_");
+        let prefix = format!("{}_{}_{}", side, interface, kind);
+
+        for signal in &channel.signals {
+            let Some(rest) = signal.name.strip_prefix(&prefix) else {
+                continue;
+            };
+            accessors.insert(signal.name.clone(), Accessor {
+                name: format!("{}_{}_{}{}", side, kind, interface, rest),
+                channel: Some(format!("{}: {}", channel.path, channel.ty.ty())),
+            });
+        }
+    }
+    accessors
+}
+
+/// Generates the C++ header of the harness of the top module.
+pub fn gen_harness(module: &Module, debug_info: &DebugInfo) -> String {
+    let top = &module.name;
+    let accessors = accessors(module, debug_info);
+
+    // Accessors grouped by the channels, in the order of the ports.
+    let mut channels = BTreeMap::<_, Vec<_>>::new();
+    let mut order = vec![];
+    for port in &module.port_decls {
+        let (is_input, width, name) = match port {
+            PortDeclaration::Input(width, name) => (true, *width, name),
+            PortDeclaration::Output(width, name) => (false, *width, name),
+        };
+        if name == "clk" || name == "rst" {
+            continue;
+        }
+
+        let (accessor, channel) = match accessors.get(name) {
+            Some(accessor) => (accessor.name.as_str(), accessor.channel.clone()),
+            None => (name.as_str(), None),
+        };
+        let ty = port_type(width);
+        let code = if is_input {
+            let param = if width > 64 { format!("const {} &value", ty) } else { format!("{} value", ty) };
+            format!("void {}({}) {{ top->{} = value; }}", accessor, param, name)
+        } else if width > 64 {
+            format!("const {} &{}() const {{ return top->{}; }}", ty, accessor, name)
+        } else {
+            format!("{} {}() const {{ return top->{}; }}", ty, accessor, name)
+        };
+
+        if !channels.contains_key(&channel) {
+            order.push(channel.clone());
+        }
+        channels.entry(channel).or_default().push(code);
+    }
+
+    let accessors = order
+        .into_iter()
+        .map(|channel| {
+            let codes = channels[&channel].join("\n");
+            match channel {
+                Some(channel) => format!("// {}\n{}", channel, codes),
+                None => codes,
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n");
+
+    let body = format!(
+        "VerilatedContext *context;
+V{top} *top;
+
+// Number of cycles stepped.
+uint64_t cycle = 0;
+
+explicit {top}_harness(VerilatedContext *context) : context(context), top(new V{top}{{context}}) {{}}
+
+~{top}_harness() {{
+    top->final();
+    delete top;
+}}
+
+// Evaluates the combinational logic with the current inputs.
+void eval() {{ top->eval(); }}
+
+// Steps a cycle with a rising edge of the clock.
+void tick() {{
+    top->clk = 0;
+    top->eval();
+    context->timeInc(1);
+    top->clk = 1;
+    top->eval();
+    context->timeInc(1);
+    cycle++;
+}}
+
+// Holds the reset for the cycles.
+void reset(uint64_t cycles = 1) {{
+    top->rst = 1;
+    for (uint64_t i = 0; i < cycles; i++) {{
+        tick();
+    }}
+    top->rst = 0;
+}}
+
+{accessors}"
+    );
+
+    format!(
+        "// Verilator harness of `{top}`.
+#pragma once
+
+#include <cstdint>
+
+#include <verilated.h>
+
+#include \"V{top}.h\"
+
+class {top}_harness {{
+  public:
+{body}
+}};
+",
+        body = indent(body, INDENT).lines().map(str::trim_end).collect::<Vec<_>>().join("\n")
+    )
+}
+
+/// Generates a minimal `main` of the harness of the top module, which resets the module and steps it until `$finish`
+/// or the given number of cycles.
+pub fn gen_main(top: &str, filelist: &str, cycles: usize) -> String {
+    format!(
+        "// Simulation of `{top}`, built with:
+//
+//     verilator --cc --exe --build --top-module {top} -F {filelist} {top}_main.cpp
+#include \"{top}_harness.h\"
+
+int main(int argc, char **argv) {{
+    VerilatedContext context;
+    context.commandArgs(argc, argv);
+    {top}_harness harness{{&context}};
+
+    harness.reset();
+    while (!context.gotFinish() && harness.cycle < {cycles}) {{
+        // Drive the ingress payloads and the egress resolvers here.
+        harness.tick();
+    }}
+
+    return 0;
+}}
+"
+    )
+}
@@ -0,0 +1,1109 @@
+//! VHDL-2008 output.
+//!
+//! [`gen_vhdl`] prints each module as an entity and its architecture, from the same netlist as the
+//! [`Simulator`], so the widths and signedness of the operations follow the Verilog rules:
+//!
+//! - Every port, net, and register is a `std_logic_vector` of its total width, and an array is flattened with the
+//!   element 0 in the least significant bits. The `clk` port is a `std_logic`. Registers start with the values given by
+//!   their initializers and `initial` blocks, and with zero otherwise.
+//! - Expressions are computed on `unsigned` of `ieee.numeric_std`, converted to `signed` for signed operations.
+//! - Continuous assignments with constant targets become concurrent signal assignments, and the other assignments and
+//!   `always @*` blocks become `process (all)`. Signals assigned in a process are read and written through variables,
+//!   so that the following statements see the blocking assignments.
+//! - `always @(posedge clk)` blocks become processes on `rising_edge(clk)`, where nonblocking assignments are signal
+//!   assignments. The synchronous reset is the `if (rst)` branch of the block.
+//! - Module instantiations become component instantiations, so the instantiated modules should be given together.
+//! - `$display` becomes `report`, and `$fatal` becomes `report` with `severity failure`.
+//!
+//! Names which are not basic identifiers of VHDL, such as reserved words or names with `__`, are printed as extended
+//! identifiers, e.g., `\in\`.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+
+use itertools::Itertools;
+
+use super::sim::netlist::*;
+use super::sim::{Bits, Simulator};
+use super::*;
+use crate::compiler::error::{VirgenError, VirgenResult};
+use crate::compiler::{BinaryOp, UnaryOp};
+use crate::utils::indent;
+
+const INDENT: usize = 4;
+
+/// Reserved words of VHDL-2008, and names of the libraries and subprograms used in the output.
+const KEYWORDS: [&str; 117] = [
+    "abs",
+    "access",
+    "after",
+    "alias",
+    "all",
+    "and",
+    "architecture",
+    "array",
+    "assert",
+    "assume",
+    "assume_guarantee",
+    "attribute",
+    "begin",
+    "block",
+    "body",
+    "buffer",
+    "bus",
+    "case",
+    "component",
+    "configuration",
+    "constant",
+    "context",
+    "cover",
+    "default",
+    "disconnect",
+    "downto",
+    "else",
+    "elsif",
+    "end",
+    "entity",
+    "exit",
+    "fairness",
+    "file",
+    "for",
+    "force",
+    "function",
+    "generate",
+    "generic",
+    "group",
+    "guarded",
+    "if",
+    "impure",
+    "in",
+    "inertial",
+    "inout",
+    "is",
+    "label",
+    "library",
+    "linkage",
+    "literal",
+    "loop",
+    "map",
+    "mod",
+    "nand",
+    "new",
+    "next",
+    "nor",
+    "not",
+    "null",
+    "of",
+    "on",
+    "open",
+    "or",
+    "others",
+    "out",
+    "package",
+    "parameter",
+    "port",
+    "postponed",
+    "procedure",
+    "process",
+    "property",
+    "protected",
+    "pure",
+    "range",
+    "record",
+    "register",
+    "reject",
+    "release",
+    "rem",
+    "report",
+    "restrict",
+    "restrict_guarantee",
+    "return",
+    "rol",
+    "ror",
+    "select",
+    "sequence",
+    "severity",
+    "shared",
+    "signal",
+    "sla",
+    "sll",
+    "sra",
+    "srl",
+    "strong",
+    "subtype",
+    "then",
+    "to",
+    "transport",
+    "type",
+    "unaffected",
+    "units",
+    "until",
+    "use",
+    "variable",
+    "vmode",
+    "vprop",
+    "vunit",
+    "wait",
+    "when",
+    "while",
+    "with",
+    "xnor",
+    "xor",
+    "ieee",
+    "std",
+];
+
+/// Names declared by the libraries which may collide with Verilog names.
+const PREDEFINED: [&str; 16] = [
+    "work",
+    "std_logic",
+    "std_logic_vector",
+    "unsigned",
+    "signed",
+    "resize",
+    "shift_left",
+    "shift_right",
+    "to_integer",
+    "to_string",
+    "to_hstring",
+    "to_ostring",
+    "rising_edge",
+    "character",
+    "failure",
+    "lf",
+];
+
+/// Subprograms declared in every architecture.
+const HELPERS: &str = "-- Converts a condition into a bit.
+function hf_bit(c : boolean) return unsigned is
+begin
+    if c then
+        return \"1\";
+    end if;
+    return \"0\";
+end function;
+
+-- Selects `a` if the condition holds, and `b` otherwise.
+function hf_mux(c : boolean; a : unsigned; b : unsigned) return unsigned is
+begin
+    if c then
+        return a;
+    end if;
+    return b;
+end function;
+
+-- Returns `n * scale` as an offset, saturated so that offsets out of range stay out of range.
+function hf_offset(n : unsigned; scale : positive) return natural is
+begin
+    if n >= 2**30 / scale then
+        return 2**30;
+    end if;
+    return to_integer(n) * scale;
+end function;";
+
+/// Generates the modules in VHDL, each as an entity and its architecture.
+///
+/// An architecture declares a component for each module it instantiates, so every instantiated module should also be in
+/// `modules`.
+pub fn gen_vhdl(modules: &[Module]) -> VirgenResult<Vec<String>> {
+    let ports = modules.iter().map(|module| (module.name.as_str(), module.port_decls.as_slice())).collect();
+    modules.iter().map(|module| Architecture::new(module, &ports)?.gen(module)).collect()
+}
+
+fn misc(msg: String) -> VirgenError {
+    VirgenError::Misc { msg }
+}
+
+fn is_clock(port_decl: &PortDeclaration) -> bool {
+    matches!(port_decl, PortDeclaration::Input(1, name) if name == "clk")
+}
+
+/// Returns whether the name is a basic identifier of VHDL which is not reserved.
+fn is_basic(name: &str) -> bool {
+    let lower = name.to_lowercase();
+    name.starts_with(|c: char| c.is_ascii_alphabetic())
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && !name.contains("__")
+        && !name.ends_with('_')
+        && !KEYWORDS.contains(&lower.as_str())
+        && !PREDEFINED.contains(&lower.as_str())
+        && !lower.starts_with("hf_")
+}
+
+/// Returns the identifier of the name, which is an extended identifier if the name is not a basic identifier.
+fn ident(name: &str) -> String {
+    if is_basic(name) {
+        name.to_string()
+    } else {
+        format!("\\{}\\", name.replace('\\', "\\\\"))
+    }
+}
+
+/// Returns the type of a vector of the width.
+fn vector(width: usize) -> String {
+    format!("std_logic_vector({}-1 downto 0)", width)
+}
+
+/// Returns the bit string literal of the bits.
+fn literal(bits: &Bits) -> String {
+    let (radix, prefix, digits) =
+        if bits.width() % 4 == 0 { (16, "x", bits.width() / 4) } else { (2, "", bits.width()) };
+    format!("{}\"{:0>digits$}\"", prefix, bits.to_str_radix(radix), digits = digits)
+}
+
+/// Returns the initial value of a vector.
+fn initial(bits: &Bits) -> String {
+    if bits.is_zero() {
+        "(others => '0')".to_string()
+    } else {
+        literal(bits)
+    }
+}
+
+/// Returns the `unsigned` constant.
+fn constant(bits: &Bits) -> String {
+    format!("unsigned'({})", literal(bits))
+}
+
+/// Returns `x` if the value is `unsigned(x)`.
+fn converted(value: &str) -> Option<&str> {
+    let inner = value.strip_prefix("unsigned(")?.strip_suffix(')')?;
+    let mut depth = 0;
+    let balanced = inner.chars().all(|c| {
+        match c {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            _ => {}
+        }
+        depth >= 0
+    });
+    balanced.then_some(inner)
+}
+
+/// Converts an `unsigned` into a `std_logic_vector`.
+fn slv(value: String) -> String {
+    // Constants are printed as literals.
+    if let Some(literal) = value.strip_prefix("unsigned'(").and_then(|value| value.strip_suffix(')')) {
+        if !literal.contains(['(', ')']) {
+            return literal.to_string();
+        }
+    }
+    match converted(&value) {
+        Some(inner) => inner.to_string(),
+        None => format!("std_logic_vector({})", value),
+    }
+}
+
+/// Converts an `unsigned` into a `signed`.
+fn to_signed(value: String) -> String {
+    format!("signed({})", converted(&value).unwrap_or(&value))
+}
+
+/// Resizes an `unsigned` of width `from` to width `to`, extending its sign if `signed` is `true`.
+fn resize(value: String, from: usize, to: usize, signed: bool) -> String {
+    if from == to {
+        value
+    } else if from == 0 {
+        constant(&Bits::zero(to))
+    } else if signed && from < to {
+        format!("unsigned(resize({}, {}))", to_signed(value), to)
+    } else {
+        format!("resize({}, {})", value, to)
+    }
+}
+
+/// Returns the port declarations of an entity or a component.
+fn gen_ports(port_decls: &[PortDeclaration], defaults: Option<&HashMap<String, Bits>>) -> Option<String> {
+    let ports = port_decls
+        .iter()
+        .filter_map(|port_decl| match port_decl {
+            _ if is_clock(port_decl) => Some("clk : in std_logic".to_string()),
+            PortDeclaration::Input(0, _) | PortDeclaration::Output(0, _) => None,
+            PortDeclaration::Input(width, name) => Some(format!("{} : in {}", ident(name), vector(*width))),
+            PortDeclaration::Output(width, name) => {
+                let default = defaults.and_then(|defaults| defaults.get(name));
+                let default = default.map(|bits| format!(" := {}", initial(bits))).unwrap_or_default();
+                Some(format!("{} : out {}{}", ident(name), vector(*width), default))
+            }
+        })
+        .collect::<Vec<_>>();
+    if ports.is_empty() {
+        None
+    } else {
+        Some(format!("port (\n{}\n);", indent(ports.join(";\n"), INDENT)))
+    }
+}
+
+/// Returns the initial values of the signals, which are set by the register initializers and `initial` blocks.
+fn initial_values(module: &Module) -> VirgenResult<HashMap<String, Bits>> {
+    fn filter(items: &[ModuleItem]) -> Vec<ModuleItem> {
+        items
+            .iter()
+            .filter_map(|item| match item {
+                ModuleItem::Declarations(_) => Some(item.clone()),
+                ModuleItem::AlwaysConstruct(event, _) if event == "initial" => Some(item.clone()),
+                ModuleItem::Commented(before, after, items) => {
+                    Some(ModuleItem::Commented(before.clone(), after.clone(), filter(items)))
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    let initials = Module {
+        name: module.name.clone(),
+        port_decls: module.port_decls.clone(),
+        module_items: filter(&module.module_items),
+    };
+    let netlist = Netlist::with_instances(&initials)?;
+    let mut sim = Simulator::new(&initials)?;
+    netlist
+        .signals
+        .iter()
+        .filter(|signal| signal.kind != SignalKind::Input && signal.kind != SignalKind::Integer)
+        .map(|signal| Ok((signal.name.clone(), sim.peek(&signal.name)?)))
+        .collect()
+}
+
+/// Architecture of a module being generated.
+struct Architecture<'a> {
+    netlist: Netlist,
+
+    /// Port declarations of the given modules, from which the component declarations are generated.
+    ports: &'a HashMap<&'a str, &'a [PortDeclaration]>,
+
+    /// Identifiers of the signals.
+    names: Vec<String>,
+
+    /// Lowercase names which are used, as VHDL is case-insensitive.
+    used: HashSet<String>,
+
+    /// The `clk` port.
+    clock: Option<SignalId>,
+
+    /// Declarations of the architecture.
+    decls: Vec<String>,
+
+    /// Concurrent statements of the architecture.
+    body: Vec<String>,
+
+    /// Variables standing for the signals in the process being generated.
+    variables: HashMap<SignalId, String>,
+
+    /// Declarations of the process being generated, or `None` outside processes.
+    process_decls: Option<Vec<String>>,
+}
+
+impl<'a> Architecture<'a> {
+    fn new(module: &Module, ports: &'a HashMap<&'a str, &'a [PortDeclaration]>) -> VirgenResult<Self> {
+        let netlist = Netlist::with_instances(module)?;
+
+        // Names differing only in case are printed as extended identifiers, which are case-sensitive.
+        let mut counts = HashMap::<String, usize>::new();
+        for signal in &netlist.signals {
+            *counts.entry(signal.name.to_lowercase()).or_default() += 1;
+        }
+        let names = netlist
+            .signals
+            .iter()
+            .map(|signal| {
+                if counts[&signal.name.to_lowercase()] > 1 {
+                    format!("\\{}\\", signal.name.replace('\\', "\\\\"))
+                } else {
+                    ident(&signal.name)
+                }
+            })
+            .collect();
+        let used = counts.into_keys().collect();
+
+        let clock = module.port_decls.iter().find(|port_decl| is_clock(port_decl)).map(|_| netlist.ids["clk"]);
+        Ok(Self {
+            netlist,
+            ports,
+            names,
+            used,
+            clock,
+            decls: vec![],
+            body: vec![],
+            variables: HashMap::new(),
+            process_decls: None,
+        })
+    }
+
+    fn gen(mut self, module: &Module) -> VirgenResult<String> {
+        let initials = initial_values(module)?;
+
+        for (id, signal) in self.netlist.signals.iter().enumerate() {
+            let width = signal.total_width();
+            if matches!(signal.kind, SignalKind::Input | SignalKind::Output | SignalKind::Integer) || width == 0 {
+                continue;
+            }
+            let default = initials.get(&signal.name).map(|bits| format!(" := {}", initial(bits))).unwrap_or_default();
+            self.decls.push(format!("signal {} : {}{};", self.names[id], vector(width), default));
+        }
+
+        for process in std::mem::take(&mut self.netlist.comb) {
+            match process.stmts.as_slice() {
+                [Stmt::Assign { lhs, rhs, .. }] if is_static(lhs) => {
+                    let value = self.rhs(lhs, rhs);
+                    let assigns = self.assign(lhs, value);
+                    self.body.extend(assigns);
+                }
+                _ => self.comb_process(&process),
+            }
+        }
+
+        for stmts in std::mem::take(&mut self.netlist.seq) {
+            self.seq_process(stmts)?;
+        }
+
+        let mut components = BTreeMap::new();
+        for inst in std::mem::take(&mut self.netlist.instances) {
+            let port_decls = *self.ports.get(inst.module_name.as_str()).ok_or_else(|| {
+                misc(format!("module `{}` instantiated as `{}` is not given", inst.module_name, inst.inst_name))
+            })?;
+            let _unused = components.insert(inst.module_name.clone(), port_decls);
+            self.instance(&inst, port_decls)?;
+        }
+
+        let name = ident(&module.name);
+        let components = components.into_iter().map(|(name, port_decls)| {
+            let name = ident(&name);
+            match gen_ports(port_decls, None) {
+                Some(ports) => format!("component {} is\n{}\nend component {};", name, indent(ports, INDENT), name),
+                None => format!("component {} is\nend component {};", name, name),
+            }
+        });
+        let decls = [HELPERS.to_string()].into_iter().chain(components).chain([self.decls.join("\n")]).join("\n\n");
+        let entity = match gen_ports(&module.port_decls, Some(&initials)) {
+            Some(ports) => format!("entity {} is\n{}\nend entity {};", name, indent(ports, INDENT), name),
+            None => format!("entity {} is\nend entity {};", name, name),
+        };
+
+        Ok(format!(
+            "library ieee;\nuse ieee.std_logic_1164.all;\nuse ieee.numeric_std.all;\n\n{}\n\narchitecture rtl of {} is\n{}\nbegin\n{}\nend architecture rtl;",
+            entity,
+            name,
+            indent(decls, INDENT),
+            indent(self.body.join("\n"), INDENT)
+        ))
+    }
+
+    /// Returns a fresh identifier starting with `base`.
+    fn fresh(&mut self, base: &str) -> String {
+        let name = (0..)
+            .map(|i| if i == 0 { base.to_string() } else { format!("{}_{}", base, i) })
+            .find(|name| !self.used.contains(&name.to_lowercase()))
+            .unwrap();
+        let _unused = self.used.insert(name.to_lowercase());
+        ident(&name)
+    }
+
+    /// Declares a temporary vector in the process being generated, or in the architecture.
+    fn temporary(&mut self, base: &str, width: usize) -> String {
+        let name = self.fresh(base);
+        match &mut self.process_decls {
+            Some(decls) => decls.push(format!("variable {} : {};", name, vector(width))),
+            None => self.decls.push(format!("signal {} : {};", name, vector(width))),
+        }
+        name
+    }
+
+    /// Returns the identifier of a signal, or the variable standing for it.
+    fn name(&self, id: SignalId) -> &str {
+        self.variables.get(&id).unwrap_or(&self.names[id])
+    }
+
+    /// Returns a signal as an `unsigned`.
+    fn whole(&self, id: SignalId) -> String {
+        if Some(id) == self.clock {
+            format!("unsigned'(0 => {})", self.names[id])
+        } else {
+            format!("unsigned({})", self.name(id))
+        }
+    }
+
+    /// Returns the value of an assignment, as an `unsigned` of the width of the target.
+    fn rhs(&self, lhs: &LValue, rhs: &Expr) -> String {
+        let width = lhs.width(&self.netlist.signals);
+        let value = self.expr(rhs, width.max(rhs.width), rhs.signed);
+        resize(value, width.max(rhs.width), width, false)
+    }
+
+    /// Returns the statements assigning `value`, an `unsigned` of the width of the target, to the target.
+    fn assign(&mut self, lhs: &LValue, value: String) -> Vec<String> {
+        let width = lhs.width(&self.netlist.signals);
+        if width == 0 {
+            return vec![];
+        }
+
+        match lhs {
+            LValue::Signal(id) => vec![format!("{} {} {};", self.name(*id), self.op(*id), slv(value))],
+            LValue::Index(id, index) | LValue::Range(id, index, _) => {
+                let signal = &self.netlist.signals[*id];
+                let total = signal.total_width();
+                let scale = if matches!(lhs, LValue::Index(..)) && signal.len.is_some() { signal.width } else { 1 };
+                let (name, op) = (self.name(*id).to_string(), self.op(*id));
+
+                if index.reads().is_empty() {
+                    let lo = index.eval_self(&[]).to_usize().saturating_mul(scale);
+                    if lo >= total {
+                        return vec![];
+                    }
+                    let part = width.min(total - lo);
+                    let value = resize(value, width, part, false);
+                    return vec![format!("{}({} downto {}) {} {};", name, lo + part - 1, lo, op, slv(value))];
+                }
+
+                if width > total {
+                    return vec![];
+                }
+                let offset = format!("hf_offset({}, {})", self.expr(index, index.width, index.signed), scale);
+                let hi = if width == 1 { offset.clone() } else { format!("{} + {}", offset, width - 1) };
+                vec![format!(
+                    "if {} <= {} then\n{}\nend if;",
+                    offset,
+                    total - width,
+                    indent(format!("{}({} downto {}) {} {};", name, hi, offset, op, slv(value)), INDENT)
+                )]
+            }
+            LValue::Concat(lvalues) => {
+                let temporary = self.temporary("concat", width);
+                let mut stmts = vec![format!("{} {} {};", temporary, self.temporary_op(), slv(value))];
+                let mut lo = width;
+                for lvalue in lvalues {
+                    let part = lvalue.width(&self.netlist.signals);
+                    lo -= part;
+                    if part > 0 {
+                        let value = format!("unsigned({}({} downto {}))", temporary, lo + part - 1, lo);
+                        stmts.extend(self.assign(lvalue, value));
+                    }
+                }
+                stmts
+            }
+        }
+    }
+
+    /// Returns the assignment operator for a signal.
+    fn op(&self, id: SignalId) -> &'static str {
+        if self.variables.contains_key(&id) {
+            ":="
+        } else {
+            "<="
+        }
+    }
+
+    /// Returns the assignment operator for a temporary.
+    fn temporary_op(&self) -> &'static str {
+        if self.process_decls.is_some() {
+            ":="
+        } else {
+            "<="
+        }
+    }
+
+    /// Generates a combinational process, where every signal is assigned through a variable.
+    fn comb_process(&mut self, process: &Process) {
+        let mut decls = vec![];
+        let (mut enter, mut exit) = (vec![], vec![]);
+        for id in process.reads.iter().chain(&process.writes).copied().collect::<BTreeSet<_>>() {
+            let signal = &self.netlist.signals[id];
+            let (width, kind, name) = (signal.total_width(), signal.kind, signal.name.clone());
+            if width == 0 {
+                continue;
+            }
+            if kind == SignalKind::Integer {
+                decls.push(format!("variable {} : {};", self.names[id], vector(width)));
+                let _unused = self.variables.insert(id, self.names[id].clone());
+            } else if process.writes.contains(&id) {
+                let variable = self.fresh(&format!("{}_v", name));
+                decls.push(format!("variable {} : {};", variable, vector(width)));
+                enter.push(format!("{} := {};", variable, self.names[id]));
+                exit.push(format!("{} <= {};", self.names[id], variable));
+                let _unused = self.variables.insert(id, variable);
+            }
+        }
+
+        self.process_decls = Some(decls);
+        let stmts = self.stmts(&process.stmts, true);
+        let decls = self.process_decls.take().unwrap();
+        self.variables.clear();
+
+        let stmts = enter.into_iter().chain(stmts).chain(exit).join("\n");
+        self.body.push(gen_process("all", &decls, stmts));
+    }
+
+    /// Generates a process on the rising edges of `clk`, where the blocking assignments are through variables.
+    fn seq_process(&mut self, stmts: Vec<Stmt>) -> VirgenResult<()> {
+        let Some(clock) = self.clock else {
+            return Err(misc("`always @(posedge clk)` block without `clk`".to_string()));
+        };
+
+        let mut blocking = BTreeSet::new();
+        collect_blocking_writes(&stmts, &mut blocking);
+        let process = Process::new(stmts);
+
+        let mut decls = vec![];
+        let (mut enter, mut exit) = (vec![], vec![]);
+        for id in process.reads.iter().chain(&process.writes).copied().collect::<BTreeSet<_>>() {
+            let signal = &self.netlist.signals[id];
+            let (width, kind, name) = (signal.total_width(), signal.kind, signal.name.clone());
+            if width == 0 {
+                continue;
+            }
+            if kind == SignalKind::Integer {
+                decls.push(format!("variable {} : {};", self.names[id], vector(width)));
+                let _unused = self.variables.insert(id, self.names[id].clone());
+            } else if blocking.contains(&id) {
+                let variable = self.fresh(&format!("{}_v", name));
+                decls.push(format!("variable {} : {};", variable, vector(width)));
+                enter.push(format!("{} := {};", variable, self.names[id]));
+                exit.push(format!("{} <= {};", self.names[id], variable));
+                let _unused = self.variables.insert(id, variable);
+            }
+        }
+
+        self.process_decls = Some(decls);
+        let stmts = self.stmts(&process.stmts, false);
+        let decls = self.process_decls.take().unwrap();
+        self.variables.clear();
+
+        let stmts = enter.into_iter().chain(stmts).chain(exit).join("\n");
+        let clock = self.names[clock].clone();
+        let stmts = format!("if rising_edge({}) then\n{}\nend if;", clock, indent(stmts, INDENT));
+        self.body.push(gen_process(&clock, &decls, stmts));
+        Ok(())
+    }
+
+    /// Generates sequential statements.
+    ///
+    /// In combinational processes, nonblocking assignments are treated as blocking ones.
+    fn stmts(&mut self, stmts: &[Stmt], comb: bool) -> Vec<String> {
+        stmts.iter().flat_map(|stmt| self.stmt(stmt, comb)).collect()
+    }
+
+    fn block(&mut self, stmts: &[Stmt], comb: bool) -> String {
+        let stmts = self.stmts(stmts, comb);
+        if stmts.is_empty() {
+            indent("null;".to_string(), INDENT)
+        } else {
+            indent(stmts.join("\n"), INDENT)
+        }
+    }
+
+    fn stmt(&mut self, stmt: &Stmt, comb: bool) -> Vec<String> {
+        match stmt {
+            Stmt::Assign { lhs, rhs, blocking } => {
+                let value = self.rhs(lhs, rhs);
+                if *blocking || comb {
+                    self.assign(lhs, value)
+                } else {
+                    // The targets of nonblocking assignments are the signals, even if blocking assignments also write
+                    // them.
+                    let variables = std::mem::take(&mut self.variables);
+                    let integers = variables
+                        .iter()
+                        .filter(|(id, _)| self.netlist.signals[**id].kind == SignalKind::Integer)
+                        .map(|(id, name)| (*id, name.clone()))
+                        .collect();
+                    self.variables = integers;
+                    let stmts = self.assign(lhs, value);
+                    self.variables = variables;
+                    stmts
+                }
+            }
+            Stmt::If(branches, default) => {
+                let branches = branches
+                    .iter()
+                    .map(|(cond, stmts)| (self.truthy(cond), self.block(stmts, comb)))
+                    .collect::<Vec<_>>();
+                vec![self.gen_if(branches, default, comb)]
+            }
+            Stmt::Case(sel, items, default) => {
+                let branches = items
+                    .iter()
+                    .map(|(item, stmts)| {
+                        let (width, signed) = (sel.width.max(item.width), sel.signed && item.signed);
+                        let cond = if width == 0 {
+                            "true".to_string()
+                        } else {
+                            format!("{} = {}", self.expr(sel, width, signed), self.expr(item, width, signed))
+                        };
+                        (cond, self.block(stmts, comb))
+                    })
+                    .collect::<Vec<_>>();
+                vec![self.gen_if(branches, default, comb)]
+            }
+            Stmt::Loop(var, count, body) => {
+                let signal = &self.netlist.signals[*var];
+                let var_expr =
+                    Expr { width: signal.total_width(), signed: signal.signed, kind: ExprKind::Signal(*var) };
+                let cond = Expr {
+                    width: 1,
+                    signed: false,
+                    kind: ExprKind::Binary(Box::new(var_expr.clone()), BinaryOp::Less, Box::new(count.clone())),
+                };
+                let one = Expr { width: 32, signed: true, kind: ExprKind::Const(Bits::from_u64(1, 32)) };
+                let next = Expr {
+                    width: var_expr.width.max(32),
+                    signed: var_expr.signed,
+                    kind: ExprKind::Binary(Box::new(var_expr), BinaryOp::Add, Box::new(one)),
+                };
+
+                let lhs = LValue::Signal(*var);
+                let mut stmts = self.assign(&lhs, constant(&Bits::zero(lhs.width(&self.netlist.signals))));
+                let cond = self.truthy(&cond);
+                let mut body = self.stmts(body, comb);
+                let next = self.rhs(&lhs, &next);
+                body.extend(self.assign(&lhs, next));
+                stmts.push(format!("while {} loop\n{}\nend loop;", cond, indent(body.join("\n"), INDENT)));
+                stmts
+            }
+            Stmt::Display(fstring, args) => vec![format!("report {};", self.message(fstring, args))],
+            Stmt::Fatal => vec!["report \"$fatal\" severity failure;".to_string()],
+        }
+    }
+
+    fn gen_if(&mut self, branches: Vec<(String, String)>, default: &[Stmt], comb: bool) -> String {
+        let mut out = String::new();
+        for (i, (cond, stmts)) in branches.iter().enumerate() {
+            out.push_str(&format!("{} {} then\n{}\n", if i == 0 { "if" } else { "elsif" }, cond, stmts));
+        }
+        if branches.is_empty() {
+            return self.stmts(default, comb).join("\n");
+        }
+        if !default.is_empty() {
+            out.push_str(&format!("else\n{}\n", self.block(default, comb)));
+        }
+        out.push_str("end if;");
+        out
+    }
+
+    /// Returns the message of a `report`, following the format of `$display`.
+    fn message(&self, fstring: &str, args: &[Expr]) -> String {
+        let mut parts = vec![];
+        let mut text = String::new();
+        let mut args = args.iter();
+        let mut chars = fstring.chars().peekable();
+        let flush = |text: &mut String, parts: &mut Vec<String>| {
+            if !text.is_empty() {
+                parts.push(format!("\"{}\"", text.replace('"', "\"\"")));
+                text.clear();
+            }
+        };
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => match chars.next() {
+                    Some('n') => {
+                        flush(&mut text, &mut parts);
+                        parts.push("LF".to_string());
+                    }
+                    Some('t') => text.push(' '),
+                    Some(c) => text.push(c),
+                    None => text.push('\\'),
+                },
+                '%' => {
+                    while chars.next_if(|c| c.is_ascii_digit()).is_some() {}
+                    let Some(conv) = chars.next() else {
+                        text.push('%');
+                        break;
+                    };
+                    if conv == '%' {
+                        text.push('%');
+                        continue;
+                    }
+                    let Some(arg) = args.next() else {
+                        continue;
+                    };
+                    if arg.width == 0 {
+                        continue;
+                    }
+                    flush(&mut text, &mut parts);
+                    let value = self.expr(arg, arg.width, arg.signed);
+                    parts.push(match conv.to_ascii_lowercase() {
+                        'b' => format!("to_string({})", value),
+                        'o' => format!("to_ostring({})", value),
+                        'h' | 'x' => format!("to_hstring({})", value),
+                        'c' => format!("character'val(to_integer({}))", resize(value, arg.width, 8, false)),
+                        _ if arg.signed && arg.width <= 32 => format!("to_string(to_integer({}))", to_signed(value)),
+                        _ if arg.width <= 31 => format!("to_string(to_integer({}))", value),
+                        _ => format!("to_hstring({})", value),
+                    });
+                }
+                _ => text.push(c),
+            }
+        }
+        // The trailing newline is added by `report`.
+        if parts.last().map(String::as_str) == Some("LF") && text.is_empty() {
+            let _unused = parts.pop();
+        }
+        flush(&mut text, &mut parts);
+        if parts.is_empty() {
+            "\"\"".to_string()
+        } else {
+            parts.join(" & ")
+        }
+    }
+
+    /// Generates a component instantiation. The ports are connected through signals named after the instance,
+    /// unless they are connected to whole signals.
+    fn instance(&mut self, inst: &ModuleInstantiation, port_decls: &[PortDeclaration]) -> VirgenResult<()> {
+        if !inst.params.is_empty() {
+            return Err(misc(format!("parameters of `{}` are not supported", inst.inst_name)));
+        }
+        let connections =
+            inst.port_connections.iter().map(|(port, expr)| (port.as_str(), expr)).collect::<HashMap<_, _>>();
+
+        let mut port_map = vec![];
+        for port_decl in port_decls {
+            let (width, name) = match port_decl {
+                PortDeclaration::Input(width, name) | PortDeclaration::Output(width, name) => (*width, name),
+            };
+            if width == 0 {
+                continue;
+            }
+            let base = format!("{}_{}", inst.inst_name, name);
+            let actual = match (port_decl, connections.get(name.as_str())) {
+                (_, Some(expr)) if is_clock(port_decl) => match self.netlist.expr(expr)?.kind {
+                    ExprKind::Signal(id) if Some(id) == self.clock => self.names[id].clone(),
+                    _ => return Err(misc(format!("`clk` of `{}` is not connected to `clk`", inst.inst_name))),
+                },
+                (PortDeclaration::Input(..), Some(expr)) => {
+                    let expr = self.netlist.expr(expr)?;
+                    match expr.kind {
+                        ExprKind::Signal(id)
+                            if self.netlist.signals[id].total_width() == width && Some(id) != self.clock =>
+                        {
+                            self.names[id].clone()
+                        }
+                        _ => {
+                            let temporary = self.temporary(&base, width);
+                            let value = self.expr(&expr, width.max(expr.width), expr.signed);
+                            let value = resize(value, width.max(expr.width), width, false);
+                            self.body.push(format!("{} <= {};", temporary, slv(value)));
+                            temporary
+                        }
+                    }
+                }
+                (PortDeclaration::Input(..), None) => {
+                    let temporary = self.fresh(&base);
+                    self.decls.push(format!("signal {} : {} := (others => '0');", temporary, vector(width)));
+                    temporary
+                }
+                (PortDeclaration::Output(..), Some(expr)) => {
+                    let lhs = self.netlist.lvalue(expr)?;
+                    match lhs {
+                        LValue::Signal(id) if self.netlist.signals[id].total_width() == width => self.names[id].clone(),
+                        _ => {
+                            let temporary = self.temporary(&base, width);
+                            let lhs_width = lhs.width(&self.netlist.signals);
+                            let value = resize(format!("unsigned({})", temporary), width, lhs_width, false);
+                            let assigns = self.assign(&lhs, value);
+                            self.body.extend(assigns);
+                            temporary
+                        }
+                    }
+                }
+                (PortDeclaration::Output(..), None) => "open".to_string(),
+            };
+            port_map.push(format!("{} => {}", ident(name), actual));
+        }
+
+        let label = self.fresh(&inst.inst_name);
+        let port_map = if port_map.is_empty() {
+            String::new()
+        } else {
+            format!("\n{}", indent(format!("port map (\n{}\n);", indent(port_map.join(",\n"), INDENT)), INDENT))
+        };
+        self.body.push(format!(
+            "{} : {}{}",
+            label,
+            ident(&inst.module_name),
+            if port_map.is_empty() { ";".to_string() } else { port_map }
+        ));
+        Ok(())
+    }
+
+    /// Returns the condition that the expression is nonzero.
+    fn truthy(&self, cond: &Expr) -> String {
+        if cond.width == 0 {
+            return "false".to_string();
+        }
+        if cond.reads().is_empty() {
+            return (!cond.eval_self(&[]).is_zero()).to_string();
+        }
+        self.comparison(cond).unwrap_or_else(|| format!("{} /= 0", self.expr(cond, cond.width, cond.signed)))
+    }
+
+    /// Returns the condition of a comparison, or `None` if the expression is not a comparison.
+    fn comparison(&self, expr: &Expr) -> Option<String> {
+        let ExprKind::Binary(lhs, op, rhs) = &expr.kind else {
+            return None;
+        };
+        let operator = match op {
+            BinaryOp::EqArithmetic => "=",
+            BinaryOp::NeStrict | BinaryOp::NeArithmetic => "/=",
+            BinaryOp::Less => "<",
+            BinaryOp::Greater => ">",
+            BinaryOp::LessEq => "<=",
+            BinaryOp::GreaterEq => ">=",
+            _ => return None,
+        };
+        let (w, s) = (lhs.width.max(rhs.width), lhs.signed && rhs.signed);
+        Some(if w == 0 {
+            matches!(op, BinaryOp::EqArithmetic | BinaryOp::LessEq | BinaryOp::GreaterEq).to_string()
+        } else if s {
+            format!("{} {} {}", to_signed(self.expr(lhs, w, s)), operator, to_signed(self.expr(rhs, w, s)))
+        } else {
+            format!("{} {} {}", self.expr(lhs, w, s), operator, self.expr(rhs, w, s))
+        })
+    }
+
+    /// Returns `width` bits of a signal starting from `index * scale`, where bits out of range are zero.
+    fn slice(&self, id: SignalId, index: &Expr, scale: usize, width: usize) -> String {
+        let total = self.netlist.signals[id].total_width();
+        if total == 0 || width == 0 {
+            return constant(&Bits::zero(width));
+        }
+        if index.reads().is_empty() {
+            let lo = index.eval_self(&[]).to_usize().saturating_mul(scale);
+            if lo >= total {
+                return constant(&Bits::zero(width));
+            }
+            let hi = (lo + width).min(total);
+            let value = if lo == 0 && hi == total {
+                self.whole(id)
+            } else if Some(id) == self.clock {
+                unreachable!("`clk` is a single bit")
+            } else {
+                format!("unsigned({}({} downto {}))", self.name(id), hi - 1, lo)
+            };
+            return resize(value, hi - lo, width, false);
+        }
+        let offset = format!("hf_offset({}, {})", self.expr(index, index.width, index.signed), scale);
+        resize(format!("shift_right({}, {})", self.whole(id), offset), total, width, false)
+    }
+
+    /// Returns the expression in a context of the given width and signedness, as an `unsigned` of the width. See
+    /// [`Expr::eval`].
+    fn expr(&self, expr: &Expr, width: usize, signed: bool) -> String {
+        if width == 0 {
+            return String::new();
+        }
+        if expr.reads().is_empty() {
+            return constant(&expr.eval(&[], width, signed));
+        }
+        if expr.width == 0 {
+            return constant(&Bits::zero(width));
+        }
+        if let Some(cond) = self.comparison(expr) {
+            return resize(format!("hf_bit({})", cond), 1, width, false);
+        }
+
+        match &expr.kind {
+            ExprKind::Const(bits) => constant(&bits.resize(width, signed)),
+            ExprKind::Signal(id) => resize(self.whole(*id), expr.width, width, signed),
+            ExprKind::Index(id, index) => {
+                let signal = &self.netlist.signals[*id];
+                let scale = if signal.len.is_some() { signal.width } else { 1 };
+                resize(self.slice(*id, index, scale, expr.width), expr.width, width, signed)
+            }
+            ExprKind::Range(id, base, range_width) => {
+                resize(self.slice(*id, base, 1, *range_width), *range_width, width, signed)
+            }
+            ExprKind::Concat(exprs) => {
+                let parts = exprs.iter().filter(|expr| expr.width > 0).collect::<Vec<_>>();
+                resize(self.concat(&parts), expr.width, width, signed)
+            }
+            ExprKind::Repeat(count, exprs) => {
+                let parts = exprs.iter().filter(|expr| expr.width > 0).collect::<Vec<_>>();
+                let parts = (0..*count).flat_map(|_| parts.clone()).collect::<Vec<_>>();
+                resize(self.concat(&parts), expr.width, width, signed)
+            }
+            ExprKind::Unary(UnaryOp::Negation, expr) => format!("(not {})", self.expr(expr, width, signed)),
+            ExprKind::Binary(lhs, op, rhs) => match op {
+                BinaryOp::ShiftLeft | BinaryOp::ShiftRight => {
+                    let value = self.expr(lhs, width, signed);
+                    let amount = if rhs.reads().is_empty() {
+                        rhs.eval_self(&[]).to_usize().min(width).to_string()
+                    } else {
+                        format!("hf_offset({}, 1)", self.expr(rhs, rhs.width, rhs.signed))
+                    };
+                    match op {
+                        BinaryOp::ShiftLeft => format!("shift_left({}, {})", value, amount),
+                        _ if signed => format!("unsigned(shift_right({}, {}))", to_signed(value), amount),
+                        _ => format!("shift_right({}, {})", value, amount),
+                    }
+                }
+                _ => {
+                    let (l, r) = (self.expr(lhs, width, signed), self.expr(rhs, width, signed));
+                    match op {
+                        BinaryOp::Add => format!("({} + {})", l, r),
+                        BinaryOp::Sub => format!("({} - {})", l, r),
+                        BinaryOp::Mul => format!("resize({} * {}, {})", l, r, width),
+                        BinaryOp::Div if signed => format!("unsigned({} / {})", to_signed(l), to_signed(r)),
+                        BinaryOp::Div => format!("({} / {})", l, r),
+                        BinaryOp::Mod if signed => format!("unsigned({} rem {})", to_signed(l), to_signed(r)),
+                        BinaryOp::Mod => format!("({} rem {})", l, r),
+                        BinaryOp::Or => format!("({} or {})", l, r),
+                        BinaryOp::And => format!("({} and {})", l, r),
+                        BinaryOp::Xor => format!("({} xor {})", l, r),
+                        BinaryOp::Eq => format!("({} xnor {})", l, r),
+                        _ => unreachable!(),
+                    }
+                }
+            },
+            ExprKind::Cond(cond, then_expr, else_expr) => format!(
+                "hf_mux({}, {}, {})",
+                self.truthy(cond),
+                self.expr(then_expr, width, signed),
+                self.expr(else_expr, width, signed)
+            ),
+        }
+    }
+
+    /// Concatenates expressions of nonzero widths, most significant first.
+    fn concat(&self, parts: &[&Expr]) -> String {
+        match parts {
+            [part] => self.expr(part, part.width, part.signed),
+            _ => {
+                format!("unsigned'({})", parts.iter().map(|part| self.expr(part, part.width, part.signed)).join(" & "))
+            }
+        }
+    }
+}
+
+/// Returns whether the target is a constant part of signals.
+fn is_static(lhs: &LValue) -> bool {
+    match lhs {
+        LValue::Signal(_) => true,
+        LValue::Index(_, index) | LValue::Range(_, index, _) => index.reads().is_empty(),
+        LValue::Concat(lvalues) => lvalues.iter().all(is_static),
+    }
+}
+
+/// Collects the signals written by blocking assignments.
+fn collect_blocking_writes(stmts: &[Stmt], writes: &mut BTreeSet<SignalId>) {
+    for stmt in stmts {
+        match stmt {
+            Stmt::Assign { lhs, blocking: true, .. } => {
+                let mut ids = vec![];
+                lhs.collect(&mut vec![], &mut ids);
+                writes.extend(ids);
+            }
+            Stmt::Assign { .. } | Stmt::Display(..) | Stmt::Fatal => {}
+            Stmt::If(branches, default) | Stmt::Case(_, branches, default) => {
+                branches.iter().for_each(|(_, stmts)| collect_blocking_writes(stmts, writes));
+                collect_blocking_writes(default, writes);
+            }
+            Stmt::Loop(var, _, body) => {
+                let _unused = writes.insert(*var);
+                collect_blocking_writes(body, writes);
+            }
+        }
+    }
+}
+
+fn gen_process(sensitivity: &str, decls: &[String], stmts: String) -> String {
+    let decls = if decls.is_empty() { String::new() } else { format!("{}\n", indent(decls.join("\n"), INDENT)) };
+    format!("process ({})\n{}begin\n{}\nend process;", sensitivity, decls, indent(stmts, INDENT))
+}
@@ -0,0 +1,774 @@
+//! Yosys RTLIL and JSON output.
+//!
+//! [`gen_rtlil`] and [`gen_yosys_json`] lower modules into netlists of the internal cells of
+//! [Yosys](https://yosyshq.net/yosys), so that they are read by `yosys`, `nextpnr` or `netlistsvg` without parsing
+//! Verilog. For example, `netlistsvg` draws the structural diagram of a module from the JSON netlist.
+//!
+//! The modules are lowered from the same netlist as the [`Simulator`](super::sim::Simulator), so the widths and
+//! signedness of the cells follow the Verilog rules:
+//!
+//! - Every port, net, and register is a wire of its total width, named after it. An array is flattened with the element
+//!   0 in the least significant bits. Register initializers become the `init` attribute of the wire.
+//! - Continuous assignments and `always @*` blocks are executed symbolically into cells such as `$add`, `$eq` and
+//!   `$shl`, where the branches of `if` and `case` statements become `$mux`. Bit selects with constant indices and
+//!   concatenations are slices of the wires, without cells. `for` loops are unrolled, so their bounds should be
+//!   constant.
+//! - Signals assigned in `always @(posedge clk)` blocks become `$dff`. The synchronous reset is the `$mux` in front of
+//!   its `D` input, which `opt_dff` of Yosys merges into `$sdff`.
+//! - Module instantiations become cells of the instantiated modules, so the instantiated modules should be lowered
+//!   together.
+//! - `initial` blocks, `$display` and `$fatal` are dropped.
+
+use std::collections::HashMap;
+
+use itertools::Itertools;
+use serde_json::json;
+
+use super::lower::{self, misc, Lower, Op, Predicate};
+use super::sim::netlist::*;
+use super::sim::Bits;
+use super::*;
+use crate::compiler::error::VirgenResult;
+use crate::utils::indent;
+
+const INDENT: usize = 2;
+
+type Value = lower::Value<SigSpec>;
+
+type Write = lower::Write<SigSpec>;
+
+type Writes = lower::Writes<SigSpec>;
+
+type State = lower::State<SigSpec>;
+
+/// Generates the modules in Yosys RTLIL, marking the module named `top` as the top module.
+///
+/// The connections of a cell instantiating a module are split into inputs and outputs by the port declarations of the
+/// module, so every instantiated module should also be in `modules`.
+pub fn gen_rtlil(modules: &[Module], top: &str) -> VirgenResult<String> {
+    let modules = lower_modules(modules, top)?;
+    Ok(modules.iter().map(CellModule::rtlil).join("\n\n"))
+}
+
+/// Generates the modules in the JSON format of Yosys `write_json`, marking the module named `top` as the top module.
+///
+/// As with [`gen_rtlil`], every instantiated module should also be in `modules`.
+pub fn gen_yosys_json(modules: &[Module], top: &str) -> VirgenResult<String> {
+    let modules = lower_modules(modules, top)?;
+    let modules = modules.iter().map(|module| (module.name.clone(), module.json())).collect::<serde_json::Map<_, _>>();
+    let design = json!({ "creator": "hazardflow", "modules": modules });
+    serde_json::to_string_pretty(&design).map_err(|err| misc(err.to_string()))
+}
+
+fn lower_modules(modules: &[Module], top: &str) -> VirgenResult<Vec<CellModule>> {
+    let ports = modules.iter().map(|module| (module.name.as_str(), module.port_decls.as_slice())).collect();
+    modules.iter().map(|module| Lowering::new(module, &ports)?.gen(module, module.name == top)).collect()
+}
+
+/// Bits of wires and constants.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct SigSpec(
+    /// Chunks, least significant first.
+    Vec<Chunk>,
+);
+
+/// Consecutive bits of a wire or a constant.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Chunk {
+    /// Constant.
+    Const(Bits),
+
+    /// `width` bits of a wire starting from `lo`.
+    Wire(usize, usize, usize),
+}
+
+impl Chunk {
+    fn width(&self) -> usize {
+        match self {
+            Chunk::Const(bits) => bits.width(),
+            Chunk::Wire(_, _, width) => *width,
+        }
+    }
+
+    fn slice(&self, lo: usize, width: usize) -> Chunk {
+        match self {
+            Chunk::Const(bits) => Chunk::Const(bits.slice(lo, width)),
+            Chunk::Wire(wire, base, _) => Chunk::Wire(*wire, base + lo, width),
+        }
+    }
+}
+
+impl SigSpec {
+    fn of(value: &Value) -> Self {
+        match value {
+            Value::Const(bits) => SigSpec(vec![Chunk::Const(bits.clone())]),
+            Value::Node(spec, _) => spec.clone(),
+        }
+    }
+
+    fn wire(wire: usize, width: usize) -> Self {
+        SigSpec(vec![Chunk::Wire(wire, 0, width)])
+    }
+
+    fn width(&self) -> usize {
+        self.0.iter().map(Chunk::width).sum()
+    }
+
+    fn slice(&self, lo: usize, width: usize) -> Self {
+        let mut chunks = vec![];
+        let mut offset = 0;
+        for chunk in &self.0 {
+            let (chunk_lo, chunk_hi) = (offset, offset + chunk.width());
+            offset = chunk_hi;
+            let (from, to) = (lo.max(chunk_lo), (lo + width).min(chunk_hi));
+            if from < to {
+                chunks.push(chunk.slice(from - chunk_lo, to - from));
+            }
+        }
+        SigSpec(chunks)
+    }
+
+    /// Returns the value of the bits, merging adjacent chunks.
+    fn value(self) -> Value {
+        let mut chunks: Vec<Chunk> = vec![];
+        for chunk in self.0.into_iter().filter(|chunk| chunk.width() > 0) {
+            match (chunks.last_mut(), chunk) {
+                (Some(Chunk::Const(lower)), Chunk::Const(upper)) => *lower = upper.concat(lower),
+                (Some(Chunk::Wire(wire, lo, width)), Chunk::Wire(upper_wire, upper_lo, upper_width))
+                    if *wire == upper_wire && *lo + *width == upper_lo =>
+                {
+                    *width += upper_width
+                }
+                (_, chunk) => chunks.push(chunk),
+            }
+        }
+        match chunks.as_slice() {
+            [] => Value::zero(0),
+            [Chunk::Const(bits)] => Value::Const(bits.clone()),
+            _ => {
+                let spec = SigSpec(chunks);
+                let width = spec.width();
+                Value::Node(spec, width)
+            }
+        }
+    }
+}
+
+/// Wire.
+#[derive(Debug, Clone)]
+struct Wire {
+    /// Name, which starts with `\` if it is public and `$` otherwise.
+    name: String,
+
+    width: usize,
+
+    /// Direction and position of the port, starting from 1.
+    port: Option<(Direction, usize)>,
+
+    /// Initial value.
+    init: Option<Bits>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Direction {
+    Input,
+    Output,
+}
+
+impl Direction {
+    fn as_str(self) -> &'static str {
+        match self {
+            Direction::Input => "input",
+            Direction::Output => "output",
+        }
+    }
+}
+
+/// Cell.
+#[derive(Debug, Clone)]
+struct Cell {
+    /// Name, which starts with `\` if it is public and `$` otherwise.
+    name: String,
+
+    /// Type, which is an internal cell such as `$add`, or the name of an instantiated module.
+    ty: String,
+
+    parameters: Vec<(String, usize)>,
+
+    /// Connections of the ports with their directions.
+    connections: Vec<(String, Direction, SigSpec)>,
+}
+
+/// Module lowered into cells.
+#[derive(Debug, Clone)]
+struct CellModule {
+    name: String,
+
+    /// Whether the module is the top module.
+    top: bool,
+
+    wires: Vec<Wire>,
+
+    cells: Vec<Cell>,
+
+    /// Wires connected to the signals driving them.
+    connections: Vec<(SigSpec, SigSpec)>,
+}
+
+/// Bit of a JSON netlist.
+#[allow(variant_size_differences)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Bit {
+    Const(bool),
+
+    /// Net, numbered from 2.
+    Net(usize),
+}
+
+impl CellModule {
+    fn rtlil(&self) -> String {
+        let mut lines = vec![];
+        for wire in &self.wires {
+            if let Some(init) = &wire.init {
+                lines.push(format!("attribute \\init {}", rtlil_const(init)));
+            }
+            let mut decl = "wire".to_string();
+            if wire.width != 1 {
+                decl.push_str(&format!(" width {}", wire.width));
+            }
+            if let Some((direction, position)) = wire.port {
+                decl.push_str(&format!(" {} {}", direction.as_str(), position));
+            }
+            lines.push(format!("{} {}", decl, wire.name));
+        }
+        for cell in &self.cells {
+            let body = cell
+                .parameters
+                .iter()
+                .map(|(name, value)| format!("parameter \\{} {}", name, value))
+                .chain(
+                    cell.connections
+                        .iter()
+                        .map(|(port, _, spec)| format!("connect \\{} {}", port, self.rtlil_spec(spec))),
+                )
+                .join("\n");
+            lines.push(format!("cell {} {}\n{}\nend", cell.ty, cell.name, indent(body, INDENT)));
+        }
+        for (lhs, rhs) in &self.connections {
+            lines.push(format!("connect {} {}", self.rtlil_spec(lhs), self.rtlil_spec(rhs)));
+        }
+
+        let attribute = if self.top { "attribute \\top 1\n" } else { "" };
+        format!("{}module \\{}\n{}\nend", attribute, self.name, indent(lines.join("\n"), INDENT))
+    }
+
+    fn rtlil_spec(&self, spec: &SigSpec) -> String {
+        let chunks = spec
+            .0
+            .iter()
+            .rev()
+            .map(|chunk| match chunk {
+                Chunk::Const(bits) => rtlil_const(bits),
+                Chunk::Wire(wire, lo, width) => {
+                    let wire = &self.wires[*wire];
+                    match *width {
+                        _ if *width == wire.width => wire.name.clone(),
+                        1 => format!("{} [{}]", wire.name, lo),
+                        _ => format!("{} [{}:{}]", wire.name, lo + width - 1, lo),
+                    }
+                }
+            })
+            .collect::<Vec<_>>();
+        match chunks.as_slice() {
+            [] => "{ }".to_string(),
+            [chunk] => chunk.clone(),
+            _ => format!("{{ {} }}", chunks.join(" ")),
+        }
+    }
+
+    fn json(&self) -> serde_json::Value {
+        // Numbers the bits of the wires, and resolves the connections into the bits driving them.
+        let mut offsets = vec![];
+        let mut next = 2;
+        for wire in &self.wires {
+            offsets.push(next);
+            next += wire.width;
+        }
+        let mut drivers = HashMap::new();
+        for (lhs, rhs) in &self.connections {
+            for (lhs, rhs) in self.json_bits(&offsets, lhs).into_iter().zip(self.json_bits(&offsets, rhs)) {
+                if let Bit::Net(net) = lhs {
+                    let _unused = drivers.insert(net, rhs);
+                }
+            }
+        }
+        let resolve = |mut bit: Bit| {
+            // Bounded, in case of connections in a loop.
+            for _ in 0..=drivers.len() {
+                match bit {
+                    Bit::Net(net) if drivers.contains_key(&net) => bit = drivers[&net],
+                    _ => break,
+                }
+            }
+            match bit {
+                Bit::Const(value) => json!(if value { "1" } else { "0" }),
+                Bit::Net(net) => json!(net),
+            }
+        };
+        let bits = |spec: &SigSpec| self.json_bits(&offsets, spec).into_iter().map(resolve).collect::<Vec<_>>();
+
+        let ports = self
+            .wires
+            .iter()
+            .enumerate()
+            .filter_map(|(i, wire)| {
+                let (direction, _) = wire.port?;
+                let bits = bits(&SigSpec::wire(i, wire.width));
+                Some((json_name(&wire.name), json!({ "direction": direction.as_str(), "bits": bits })))
+            })
+            .collect::<serde_json::Map<_, _>>();
+        let cells = self
+            .cells
+            .iter()
+            .map(|cell| {
+                let parameters = cell
+                    .parameters
+                    .iter()
+                    .map(|(name, value)| (name.clone(), json!(format!("{:032b}", value))))
+                    .collect::<serde_json::Map<_, _>>();
+                let port_directions = cell
+                    .connections
+                    .iter()
+                    .map(|(port, direction, _)| (port.clone(), json!(direction.as_str())))
+                    .collect::<serde_json::Map<_, _>>();
+                let connections = cell
+                    .connections
+                    .iter()
+                    .map(|(port, _, spec)| (port.clone(), json!(bits(spec))))
+                    .collect::<serde_json::Map<_, _>>();
+                let value = json!({
+                    "hide_name": u8::from(cell.name.starts_with('$')),
+                    "type": json_name(&cell.ty),
+                    "parameters": parameters,
+                    "attributes": {},
+                    "port_directions": port_directions,
+                    "connections": connections,
+                });
+                (json_name(&cell.name), value)
+            })
+            .collect::<serde_json::Map<_, _>>();
+        let netnames = self
+            .wires
+            .iter()
+            .enumerate()
+            .map(|(i, wire)| {
+                let mut attributes = serde_json::Map::new();
+                if let Some(init) = &wire.init {
+                    let _unused = attributes.insert("init".to_string(), json!(binary(init)));
+                }
+                let net = json!({
+                    "hide_name": u8::from(wire.name.starts_with('$')),
+                    "bits": bits(&SigSpec::wire(i, wire.width)),
+                    "attributes": attributes,
+                });
+                (json_name(&wire.name), net)
+            })
+            .collect::<serde_json::Map<_, _>>();
+
+        let mut attributes = serde_json::Map::new();
+        if self.top {
+            let _unused = attributes.insert("top".to_string(), json!(format!("{:032b}", 1)));
+        }
+        json!({ "attributes": attributes, "ports": ports, "cells": cells, "netnames": netnames })
+    }
+
+    /// Returns the bits of a signal, least significant first, before resolving the connections.
+    fn json_bits(&self, offsets: &[usize], spec: &SigSpec) -> Vec<Bit> {
+        spec.0
+            .iter()
+            .flat_map(|chunk| match chunk {
+                Chunk::Const(bits) => (0..bits.width()).map(|i| Bit::Const(bits.bit(i))).collect::<Vec<_>>(),
+                Chunk::Wire(wire, lo, width) => (*lo..lo + width).map(|i| Bit::Net(offsets[*wire] + i)).collect(),
+            })
+            .collect()
+    }
+}
+
+/// Returns the binary digits of a constant, most significant first.
+fn binary(bits: &Bits) -> String {
+    (0..bits.width()).rev().map(|i| if bits.bit(i) { '1' } else { '0' }).collect()
+}
+
+fn rtlil_const(bits: &Bits) -> String {
+    format!("{}'{}", bits.width(), binary(bits))
+}
+
+/// Returns the name in JSON, where public names are not escaped.
+fn json_name(name: &str) -> String {
+    name.strip_prefix('\\').unwrap_or(name).to_string()
+}
+
+/// Lowering of a module.
+struct Lowering<'a> {
+    netlist: Netlist,
+
+    /// Port declarations of the given modules, which give the directions of the connections of the instance cells.
+    ports: &'a HashMap<&'a str, &'a [PortDeclaration]>,
+
+    /// The `clk` port.
+    clock: Option<SignalId>,
+
+    wires: Vec<Wire>,
+
+    /// Wire of each signal, if its width is nonzero.
+    signal_wires: Vec<Option<usize>>,
+
+    cells: Vec<Cell>,
+
+    /// Number of the next internal name.
+    next: usize,
+}
+
+fn is_clock(port_decl: &PortDeclaration) -> bool {
+    matches!(port_decl, PortDeclaration::Input(1, name) if name == "clk")
+}
+
+impl<'a> Lowering<'a> {
+    fn new(module: &Module, ports: &'a HashMap<&'a str, &'a [PortDeclaration]>) -> VirgenResult<Self> {
+        let netlist = Netlist::with_instances(module)?;
+        let clock = module.port_decls.iter().find(|port_decl| is_clock(port_decl)).map(|_| netlist.ids["clk"]);
+
+        let mut wires = vec![];
+        let mut signal_wires = vec![];
+        let mut position = 0;
+        for signal in &netlist.signals {
+            let width = signal.total_width();
+            if width == 0 {
+                signal_wires.push(None);
+                continue;
+            }
+            let port = match signal.kind {
+                SignalKind::Input => Some(Direction::Input),
+                SignalKind::Output => Some(Direction::Output),
+                SignalKind::Wire | SignalKind::Reg | SignalKind::Integer => None,
+            }
+            .map(|direction| {
+                position += 1;
+                (direction, position)
+            });
+            signal_wires.push(Some(wires.len()));
+            wires.push(Wire { name: format!("\\{}", signal.name), width, port, init: None });
+        }
+
+        Ok(Self { netlist, ports, clock, wires, signal_wires, cells: vec![], next: 0 })
+    }
+
+    fn gen(mut self, module: &Module, top: bool) -> VirgenResult<CellModule> {
+        for (id, init) in std::mem::take(&mut self.netlist.inits) {
+            let signal = &self.netlist.signals[id];
+            if !init.reads().is_empty() {
+                return Err(misc(format!("the initializer of `{}` is not constant", signal.name)));
+            }
+            let value = init.eval(&[], signal.total_width(), signal.signed);
+            if let Some(wire) = self.signal_wires[id] {
+                self.wires[wire].init = Some(value);
+            }
+        }
+
+        let mut comb_writes = vec![vec![]; self.netlist.signals.len()];
+        let mut seq_writes = vec![vec![]; self.netlist.signals.len()];
+
+        for process in std::mem::take(&mut self.netlist.comb) {
+            let mut state = State::comb();
+            self.exec(&process.stmts, &mut state)?;
+            for (id, write) in state.blocking {
+                comb_writes[id].push(write);
+            }
+        }
+
+        for stmts in std::mem::take(&mut self.netlist.seq) {
+            let mut state = State::seq();
+            self.exec(&stmts, &mut state)?;
+            let mut writes = state.blocking;
+            for (id, write) in state.nonblocking.unwrap() {
+                let merged = match writes.remove(&id) {
+                    Some(blocking) => self.overlay(&blocking, &write),
+                    None => write,
+                };
+                let _unused = writes.insert(id, merged);
+            }
+            for (id, write) in writes {
+                // Loop variables are not registers.
+                if self.netlist.signals[id].kind != SignalKind::Integer {
+                    seq_writes[id].push(write);
+                }
+            }
+        }
+
+        for inst in std::mem::take(&mut self.netlist.instances) {
+            self.instance(&inst, &mut comb_writes)?;
+        }
+
+        let mut connections = vec![];
+        for (id, signal) in self.netlist.signals.clone().iter().enumerate() {
+            let Some(wire) = self.signal_wires[id] else {
+                continue;
+            };
+            let width = signal.total_width();
+            if signal.kind == SignalKind::Input {
+                continue;
+            }
+
+            // Bits which are not assigned are zero.
+            let (value, _) = self.merge(&comb_writes[id], width);
+            if seq_writes[id].is_empty() {
+                connections.push((SigSpec::wire(wire, width), SigSpec::of(&value)));
+                continue;
+            }
+            if !comb_writes[id].is_empty() {
+                return Err(misc(format!("`{}` is assigned in both combinational and sequential blocks", signal.name)));
+            }
+            let Some(clock) = self.clock else {
+                return Err(misc(format!("`{}` is assigned in a sequential block without `clk`", signal.name)));
+            };
+
+            // Bits which are not assigned keep their values.
+            let (value, mask) = self.merge(&seq_writes[id], width);
+            let current = self.signal(id);
+            let next = self.select(&mask, &value, &current);
+            let name = self.fresh();
+            let clock = self.signal(clock);
+            self.cells.push(Cell {
+                name,
+                ty: "$dff".to_string(),
+                parameters: vec![("CLK_POLARITY".to_string(), 1), ("WIDTH".to_string(), width)],
+                connections: vec![
+                    ("CLK".to_string(), Direction::Input, SigSpec::of(&clock)),
+                    ("D".to_string(), Direction::Input, SigSpec::of(&next)),
+                    ("Q".to_string(), Direction::Output, SigSpec::wire(wire, width)),
+                ],
+            });
+        }
+
+        Ok(CellModule { name: module.name.clone(), top, wires: self.wires, cells: self.cells, connections })
+    }
+
+    /// Lowers a module instantiation, adding the writes of its outputs.
+    fn instance(&mut self, inst: &ModuleInstantiation, comb_writes: &mut [Vec<Write>]) -> VirgenResult<()> {
+        let port_decls = *self.ports.get(inst.module_name.as_str()).ok_or_else(|| {
+            misc(format!("module `{}` instantiated as `{}` is not given", inst.module_name, inst.inst_name))
+        })?;
+        let connections =
+            inst.port_connections.iter().map(|(port, expr)| (port.as_str(), expr)).collect::<HashMap<_, _>>();
+
+        let mut cell_connections = vec![];
+        let mut outputs = vec![];
+        for port_decl in port_decls {
+            match port_decl {
+                PortDeclaration::Input(0, _) | PortDeclaration::Output(0, _) => {}
+                PortDeclaration::Input(width, name) => {
+                    let value = match connections.get(name.as_str()) {
+                        Some(expr) => {
+                            let expr = self.netlist.expr(expr)?;
+                            let value = self.expr(&expr, (*width).max(expr.width), expr.signed, &Writes::new())?;
+                            self.extract(&value, 0, *width)
+                        }
+                        None => Value::zero(*width),
+                    };
+                    cell_connections.push((name.clone(), Direction::Input, SigSpec::of(&value)));
+                }
+                PortDeclaration::Output(width, name) => {
+                    let Some(expr) = connections.get(name.as_str()) else {
+                        continue;
+                    };
+                    let wire = format!("{}_{}", self.fresh(), name);
+                    let wire = self.wire(wire, *width);
+                    cell_connections.push((name.clone(), Direction::Output, SigSpec::wire(wire, *width)));
+                    outputs.push((expr, wire, *width));
+                }
+            }
+        }
+
+        self.cells.push(Cell {
+            name: format!("\\{}", inst.inst_name),
+            ty: format!("\\{}", inst.module_name),
+            parameters: inst
+                .params
+                .iter()
+                .map(|(name, value)| match value {
+                    Expression::Primary(Primary::Number(num)) if num.parse::<usize>().is_ok() => {
+                        Ok((name.clone(), num.parse().unwrap()))
+                    }
+                    _ => Err(misc(format!("parameter `{}` of `{}` is not a number", name, inst.inst_name))),
+                })
+                .collect::<VirgenResult<_>>()?,
+            connections: cell_connections,
+        });
+
+        for (expr, wire, width) in outputs {
+            let lvalue = self.netlist.lvalue(expr)?;
+            let value = SigSpec::wire(wire, width).value();
+            let value = self.resize(&value, lvalue.width(&self.netlist.signals), false);
+            let mut state = State::comb();
+            self.assign(&lvalue, value, &mut state)?;
+            for (id, write) in state.blocking {
+                comb_writes[id].push(write);
+            }
+        }
+        Ok(())
+    }
+
+    fn fresh(&mut self) -> String {
+        self.next += 1;
+        format!("${}", self.next - 1)
+    }
+
+    /// Adds an internal wire.
+    fn wire(&mut self, name: String, width: usize) -> usize {
+        self.wires.push(Wire { name, width, port: None, init: None });
+        self.wires.len() - 1
+    }
+
+    /// Adds an internal cell with the given inputs, and returns its output `Y` of the given width.
+    fn cell(&mut self, ty: &str, parameters: &[(&str, usize)], inputs: &[(&str, &Value)], width: usize) -> Value {
+        let name = self.fresh();
+        let output = self.wire(format!("{}_Y", name), width);
+        let connections = inputs
+            .iter()
+            .map(|(port, value)| (port.to_string(), Direction::Input, SigSpec::of(value)))
+            .chain([("Y".to_string(), Direction::Output, SigSpec::wire(output, width))])
+            .collect();
+        let parameters = parameters.iter().map(|(name, value)| (name.to_string(), *value)).collect();
+        self.cells.push(Cell { name, ty: ty.to_string(), parameters, connections });
+        Value::Node(SigSpec::wire(output, width), width)
+    }
+
+    /// Adds a cell with the ports `A`, `B` and `Y`.
+    fn binary_cell(&mut self, ty: &str, signed: bool, lhs: &Value, rhs: &Value, width: usize) -> Value {
+        let parameters = [
+            ("A_SIGNED", usize::from(signed)),
+            ("A_WIDTH", lhs.width()),
+            ("B_SIGNED", usize::from(signed)),
+            ("B_WIDTH", rhs.width()),
+            ("Y_WIDTH", width),
+        ];
+        self.cell(ty, &parameters, &[("A", lhs), ("B", rhs)], width)
+    }
+}
+
+impl Lower for Lowering<'_> {
+    type Node = SigSpec;
+
+    fn netlist(&self) -> &Netlist {
+        &self.netlist
+    }
+
+    fn signal(&mut self, id: SignalId) -> Value {
+        let wire = self.signal_wires[id].unwrap();
+        let width = self.wires[wire].width;
+        Value::Node(SigSpec::wire(wire, width), width)
+    }
+
+    fn extract_node(&mut self, node: &SigSpec, _from: usize, lo: usize, width: usize) -> Value {
+        node.slice(lo, width).value()
+    }
+
+    fn concat_nodes(&mut self, parts: &[Value]) -> Value {
+        SigSpec(parts.iter().rev().flat_map(|part| SigSpec::of(part).0).collect()).value()
+    }
+
+    fn replicate(&mut self, bit: &Value, count: usize) -> Value {
+        let bit = SigSpec::of(bit);
+        SigSpec((0..count).flat_map(|_| bit.0.clone()).collect()).value()
+    }
+
+    fn op_node(&mut self, op: Op, lhs: &Value, rhs: &Value) -> Value {
+        let (ty, signed) = match op {
+            Op::Add => ("$add", false),
+            Op::Sub => ("$sub", false),
+            Op::Mul => ("$mul", false),
+            Op::DivU => ("$div", false),
+            Op::DivS => ("$div", true),
+            Op::ModU => ("$mod", false),
+            Op::ModS => ("$mod", true),
+            Op::And => ("$and", false),
+            Op::Or => ("$or", false),
+            Op::Xor => ("$xor", false),
+            Op::Shl => ("$shl", false),
+            Op::ShrU => ("$shr", false),
+            Op::ShrS => ("$sshr", true),
+        };
+        self.binary_cell(ty, signed, lhs, rhs, lhs.width())
+    }
+
+    fn compare_node(&mut self, predicate: Predicate, lhs: &Value, rhs: &Value) -> Value {
+        let (ty, signed) = match predicate {
+            Predicate::Eq => ("$eq", false),
+            Predicate::Ne => ("$ne", false),
+            Predicate::Slt => ("$lt", true),
+            Predicate::Ult => ("$lt", false),
+            Predicate::Sgt => ("$gt", true),
+            Predicate::Ugt => ("$gt", false),
+            Predicate::Sle => ("$le", true),
+            Predicate::Ule => ("$le", false),
+            Predicate::Sge => ("$ge", true),
+            Predicate::Uge => ("$ge", false),
+        };
+        self.binary_cell(ty, signed, lhs, rhs, 1)
+    }
+
+    fn mux_node(&mut self, cond: &Value, then_value: &Value, else_value: &Value) -> Value {
+        let width = then_value.width();
+        self.cell("$mux", &[("WIDTH", width)], &[("A", else_value), ("B", then_value), ("S", cond)], width)
+    }
+
+    fn not(&mut self, value: &Value) -> Value {
+        match value {
+            Value::Const(bits) => Value::Const(bits.not()),
+            Value::Node(..) => {
+                let width = value.width();
+                let parameters = [("A_SIGNED", 0), ("A_WIDTH", width), ("Y_WIDTH", width)];
+                self.cell("$not", &parameters, &[("A", value)], width)
+            }
+        }
+    }
+
+    fn truthy(&mut self, value: &Value) -> Value {
+        match value {
+            _ if value.width() == 1 => value.clone(),
+            Value::Const(bits) => Value::Const(Bits::from_bool(!bits.is_zero())),
+            Value::Node(..) => {
+                let parameters = [("A_SIGNED", 0), ("A_WIDTH", value.width()), ("Y_WIDTH", 1)];
+                self.cell("$reduce_bool", &parameters, &[("A", value)], 1)
+            }
+        }
+    }
+
+    /// Returns the bits of `value` where `mask` is set, and the bits of `other` elsewhere. The bits are sliced if the
+    /// mask is constant.
+    fn select(&mut self, mask: &Value, value: &Value, other: &Value) -> Value {
+        match mask {
+            Value::Const(bits) => {
+                let mut parts = vec![];
+                let mut lo = 0;
+                while lo < bits.width() {
+                    let set = bits.bit(lo);
+                    let hi = (lo..bits.width()).find(|i| bits.bit(*i) != set).unwrap_or(bits.width());
+                    parts.push(self.extract(if set { value } else { other }, lo, hi - lo));
+                    lo = hi;
+                }
+                parts.reverse();
+                self.concat(&parts)
+            }
+            Value::Node(..) => {
+                let value = self.op(Op::And, value, mask);
+                if other.is_zero() {
+                    return value;
+                }
+                let inverted = self.not(mask);
+                let other = self.op(Op::And, other, &inverted);
+                self.op(Op::Or, &value, &other)
+            }
+        }
+    }
+}
@@ -0,0 +1,66 @@
+//! Transaction-level tests of `gemmini::arithmetic`, through the tops in `examples::gemmini_arithmetic`.
+
+#![feature(rustc_private)]
+extern crate rustc_driver;
+extern crate rustc_span;
+
+mod common;
+
+use common::*;
+use hazardflow::compiler::error::VirgenResult;
+use hazardflow::testing::scoreboard::Matching;
+use hazardflow::testing::*;
+
+#[hazardflow::test("mac_default")]
+fn mac_matches_integer_math(tb: &mut Testbench) -> VirgenResult<()> {
+    let input = tb.ingress::<(i8, i8, i32), ()>("input.0")?;
+    let output = tb.egress::<i32, ()>("output")?;
+    tb.seed(11);
+
+    for payload in [(0, 0, 0), (-128, -128, 0), (127, -1, -5), (3, 4, i32::MAX)] {
+        tb.push(input, payload)?;
+    }
+    tb.push_random(input, 64, |_| true)?;
+    tb.run_until_drained(128)?;
+
+    let model = |(a, b, c): &(i8, i8, i32)| Some(truncate(i64::from(*a) * i64::from(*b) + i64::from(*c), 20) as i32);
+    tb.scoreboard(&[input], output, Matching::InOrder, model).check()
+}
+
+#[hazardflow::test("rounding_shift_default")]
+fn rounding_shift_rounds_to_nearest_even(tb: &mut Testbench) -> VirgenResult<()> {
+    let input = tb.ingress::<(i32, u8), ()>("input.0")?;
+    let output = tb.egress::<i32, ()>("output")?;
+    tb.seed(5);
+
+    for payload in [(5, 1), (6, 2), (7, 1), (-5, 1), (-6, 2), (i32::MIN, 31), (12345, 0)] {
+        tb.push(input, payload)?;
+    }
+    tb.push_random(input, 64, |_| true)?;
+    tb.run_until_drained(128)?;
+
+    let model = |(val, shamt): &(i32, u8)| {
+        let (val, shamt) = (i64::from(*val), *shamt);
+        let round_down = val >> shamt;
+        let round_up =
+            shamt > 0 && (val >> (shamt - 1)) & 1 == 1 && (val & ((1 << (shamt - 1)) - 1) != 0 || round_down & 1 == 1);
+        Some(truncate(round_down + i64::from(round_up), 32) as i32)
+    };
+    tb.scoreboard(&[input], output, Matching::InOrder, model).check()
+}
+
+#[hazardflow::test("clip_with_saturation_default")]
+fn clip_with_saturation_saturates_wide_integers(tb: &mut Testbench) -> VirgenResult<()> {
+    let input = tb.ingress::<i64, ()>("input.0")?;
+    let output = tb.egress::<i32, ()>("output")?;
+    tb.seed(17);
+
+    for val in [0i64, 1 << 19, (1 << 19) - 1, -(1 << 19), -(1 << 19) - 1, 1 << 35, -(1 << 39)] {
+        tb.push(input, val)?;
+    }
+    tb.push_random(input, 64, |_| true)?;
+    tb.run_until_drained(128)?;
+
+    let model = |val: &i64| Some((*val).clamp(-(1 << 19), (1 << 19) - 1) as i32);
+    tb.scoreboard(&[input], output, Matching::InOrder, model).check()
+}
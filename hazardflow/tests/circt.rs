@@ -0,0 +1,84 @@
+//! Tests of the CIRCT output.
+
+#![feature(rustc_private)]
+extern crate rustc_driver;
+extern crate rustc_span;
+
+mod common;
+
+use std::collections::HashSet;
+
+use common::*;
+use hazardflow::compiler::error::VirgenResult;
+use hazardflow::testing::compile;
+use hazardflow::vir::circt::gen_circt;
+
+/// Checks that each SSA value is defined once, and that the used values are defined.
+fn assert_well_formed(mlir: &str) {
+    let mut defined = HashSet::new();
+    let mut used = vec![];
+    for line in mlir.lines().map(str::trim) {
+        if let Some(header) = line.strip_prefix("hw.module @") {
+            defined.clear();
+            let ports = header.split_once('(').unwrap().1.split(", ");
+            defined.extend(ports.filter_map(|port| Some(port.strip_prefix("in ")?.split(' ').next()?.to_string())));
+            continue;
+        }
+        let (results, op) = line.split_once(" = ").unwrap_or(("", line));
+        if let Some(result) = results.split(':').next().filter(|result| !result.is_empty()) {
+            assert!(defined.insert(result.to_string()), "`{}` is defined twice", result);
+        }
+        used.extend(
+            op.split(|c: char| !(c.is_alphanumeric() || c == '_' || c == '%' || c == '$'))
+                .filter(|word| word.starts_with('%'))
+                .map(str::to_string),
+        );
+        if line == "}" {
+            for value in used.drain(..) {
+                assert!(defined.contains(&value), "`{}` is not defined", value);
+            }
+        }
+    }
+}
+
+#[test]
+fn circt_lowers_registers_and_instances() -> VirgenResult<()> {
+    let mlir = gen_circt(&counter())?;
+    assert_well_formed(&mlir);
+
+    assert!(
+        mlir.starts_with("hw.module @counter(in %clk : !seq.clock, in %rst : i1, in %en : i1, out out : i8) {"),
+        "{}",
+        mlir
+    );
+    assert!(mlir.contains("%count = seq.compreg "));
+    assert!(mlir.contains("comb.add %count, "));
+    assert!(mlir.contains("comb.mux %rst, "));
+    assert!(mlir.contains("%out = hw.wire %count : i8"));
+    assert!(mlir.contains("hw.output %out : i8"));
+
+    assert!(mlir.contains("hw.module @top(in %clk : !seq.clock, in %rst : i1, out hi : i4) {"));
+    assert!(mlir.contains(" = hw.instance \"counter_inst\" @counter(clk: %clk: !seq.clock, rst: %rst: i1, en: %"));
+    assert!(mlir.contains(") -> (out: i8)"));
+    assert!(mlir.contains("comb.extract %count from 4 : (i8) -> i4"));
+    Ok(())
+}
+
+#[test]
+fn circt_requires_instantiated_modules() {
+    let modules = counter();
+    let err = gen_circt(&modules[1..]).unwrap_err();
+    assert!(format!("{:?}", err).contains("module `counter` instantiated as `counter_inst` is not given"));
+}
+
+#[test]
+fn circt_lowers_fifo() -> VirgenResult<()> {
+    let compiled = compile("custom_fifo", false)?;
+    let mlir = gen_circt(&[compiled.module.clone()])?;
+    assert_well_formed(&mlir);
+
+    assert!(mlir.starts_with(&format!("hw.module @{}(in %clk : !seq.clock, in %rst : i1, ", compiled.module.name)));
+    assert!(mlir.contains(" = seq.compreg "));
+    assert!(mlir.contains(" = comb.mux "));
+    Ok(())
+}
@@ -0,0 +1,155 @@
+//! Fixtures shared by the tests.
+
+// Each test uses only some of the fixtures.
+#![allow(dead_code)]
+
+use std::path::{Path, PathBuf};
+
+use hazardflow::compiler::{BinaryOp, Shape};
+use hazardflow::vir::*;
+use rustc_span::DUMMY_SP;
+
+pub fn ident(name: &str) -> Expression {
+    Expression::ident(name.to_string())
+}
+
+pub fn number(num: &str) -> Expression {
+    Expression::number(num.to_string())
+}
+
+/// Returns a counter which counts up when `en` is set, and a module instantiating it.
+pub fn counter() -> Vec<Module> {
+    counter_of(None)
+}
+
+/// Returns [`counter`] whose register is initialized to `init`.
+pub fn counter_with_init(init: &str) -> Vec<Module> {
+    counter_of(Some(init))
+}
+
+fn counter_of(init: Option<&str>) -> Vec<Module> {
+    let count = ident("count");
+    let count_reg = Declaration::reg(Shape::new([8], false), "count".to_string());
+    let count_reg = match init {
+        Some(init) => count_reg.with_init(number(init)),
+        None => count_reg,
+    };
+    let counter = Module {
+        name: "counter".to_string(),
+        port_decls: vec![
+            PortDeclaration::input(1, "clk".to_string()),
+            PortDeclaration::input(1, "rst".to_string()),
+            PortDeclaration::input(1, "en".to_string()),
+            PortDeclaration::output(8, "out".to_string()),
+        ],
+        module_items: vec![
+            ModuleItem::Declarations(vec![count_reg]),
+            ModuleItem::ContinuousAssigns(vec![ContinuousAssign::new(ident("out"), count.clone())]),
+            ModuleItem::AlwaysConstruct("always @(posedge clk)".to_string(), vec![Statement::Conditional(
+                vec![
+                    (ident("rst"), vec![Statement::nonblocking_assignment(count.clone(), number("8'd0"), DUMMY_SP)]),
+                    (ident("en"), vec![Statement::nonblocking_assignment(
+                        count.clone(),
+                        Expression::binary(BinaryOp::Add, count, number("8'd1")),
+                        DUMMY_SP,
+                    )]),
+                ],
+                vec![],
+                DUMMY_SP,
+            )]),
+        ],
+    };
+    let top = Module {
+        name: "top".to_string(),
+        port_decls: vec![
+            PortDeclaration::input(1, "clk".to_string()),
+            PortDeclaration::input(1, "rst".to_string()),
+            PortDeclaration::output(4, "hi".to_string()),
+        ],
+        module_items: vec![
+            ModuleItem::Declarations(vec![Declaration::net(Shape::new([8], false), "count".to_string())]),
+            ModuleItem::ModuleInstantiation(ModuleInstantiation::new(
+                "counter".to_string(),
+                "counter_inst".to_string(),
+                vec![],
+                vec![
+                    ("clk".to_string(), ident("clk")),
+                    ("rst".to_string(), ident("rst")),
+                    ("en".to_string(), number("1'b1")),
+                    ("out".to_string(), ident("count")),
+                ],
+            )),
+            ModuleItem::ContinuousAssigns(vec![ContinuousAssign::new(
+                ident("hi"),
+                ident("count").with_range(Range::new_range(number("4"), number("4"))),
+            )]),
+        ],
+    };
+    vec![counter, top]
+}
+
+/// Returns the `n`-bit counter named `name`, which counts up by `step` when `en` is set.
+pub fn sized_counter(name: &str, n: usize, step: usize) -> Module {
+    let count = ident("count");
+    Module {
+        name: name.to_string(),
+        port_decls: vec![
+            PortDeclaration::input(1, "clk".to_string()),
+            PortDeclaration::input(1, "en".to_string()),
+            PortDeclaration::output(n, "out".to_string()),
+        ],
+        module_items: vec![
+            ModuleItem::Declarations(vec![Declaration::reg(Shape::new([n], false), "count".to_string())]),
+            ModuleItem::ContinuousAssigns(vec![ContinuousAssign::new(ident("out"), count.clone())]),
+            ModuleItem::AlwaysConstruct("always @(posedge clk)".to_string(), vec![Statement::Conditional(
+                vec![(ident("en"), vec![Statement::nonblocking_assignment(
+                    count.clone(),
+                    Expression::binary(BinaryOp::Add, count, number(&format!("{}'d{}", n, step))),
+                    DUMMY_SP,
+                )])],
+                vec![],
+                DUMMY_SP,
+            )]),
+        ],
+    }
+}
+
+/// Returns the module named `name` instantiating the given submodules.
+pub fn parent(name: &str, submodules: &[&str]) -> Module {
+    Module {
+        name: name.to_string(),
+        port_decls: vec![PortDeclaration::input(1, "clk".to_string())],
+        module_items: vec![ModuleItem::Commented(
+            format!("Submodules of {}", name),
+            None,
+            submodules
+                .iter()
+                .map(|submodule| {
+                    ModuleItem::ModuleInstantiation(ModuleInstantiation::new(
+                        submodule.to_string(),
+                        format!("{}_inst", submodule),
+                        vec![],
+                        vec![("clk".to_string(), ident("clk"))],
+                    ))
+                })
+                .collect(),
+        )],
+    }
+}
+
+/// Returns the lower `width` bits of `value` as a signed integer.
+pub fn truncate(value: i64, width: u32) -> i64 {
+    (value << (64 - width)) >> (64 - width)
+}
+
+/// Returns the programs in `scripts/cpu/program/<dir>`.
+pub fn programs(dir: &str) -> Vec<PathBuf> {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("../scripts/cpu/program").join(dir);
+    let mut programs = std::fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_none())
+        .collect::<Vec<_>>();
+    programs.sort();
+    programs
+}
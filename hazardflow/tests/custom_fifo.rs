@@ -0,0 +1,172 @@
+//! Transaction-level tests of `examples::custom_fifo`.
+
+#![feature(rustc_private)]
+extern crate rustc_driver;
+
+use hazardflow::compiler::error::{VirgenError, VirgenResult};
+use hazardflow::testing::scoreboard::{Matching, Scoreboard};
+use hazardflow::testing::stimulus::Pattern;
+use hazardflow::testing::*;
+
+/// Returns the handles of the ingress channels.
+fn inputs(tb: &Testbench) -> VirgenResult<Vec<Channel<u32, Ready<()>>>> {
+    (0..5).map(|i| tb.ingress(&format!("input.0[{}]", i))).collect()
+}
+
+#[hazardflow::test("custom_fifo")]
+fn custom_fifo_forwards_payloads(tb: &mut Testbench) -> VirgenResult<()> {
+    let input = tb.ingress::<u32, Ready<()>>("input.0[0]")?;
+    let output = tb.egress::<u32, Ready<()>>("output")?;
+
+    for payload in [1u32, 2, 3] {
+        tb.push(input, payload)?;
+    }
+    tb.run_until(32, |tb| tb.num_transfers(output) == 3)?;
+
+    let payloads = tb.transfers(output).into_iter().map(|(_, payload)| payload).collect::<Vec<_>>();
+    assert_eq!(payloads, [1, 2, 3]);
+    Ok(())
+}
+
+#[hazardflow::test("custom_fifo")]
+fn custom_fifo_keeps_order_under_back_pressure(tb: &mut Testbench) -> VirgenResult<()> {
+    let inputs = inputs(tb)?;
+    let output = tb.egress::<u32, Ready<()>>("output")?;
+    tb.seed(7);
+    tb.set_policy(output, ResolverPolicy::Ready(Pattern::Random(30)))?;
+
+    for (i, input) in inputs.iter().enumerate() {
+        for j in 0..4 {
+            tb.push(*input, (i * 100 + j) as u32)?;
+        }
+    }
+    tb.run_until_drained(400)?;
+    tb.run_until(400, |tb| tb.num_transfers(output) == 20)?;
+
+    // Payloads from each ingress channel come out in order, and nothing is lost.
+    for i in 0..5 {
+        let payloads = tb
+            .transfers(output)
+            .into_iter()
+            .map(|(_, payload)| payload)
+            .filter(|payload| payload / 100 == i)
+            .collect::<Vec<_>>();
+        assert_eq!(payloads, (0..4).map(|j| i * 100 + j).collect::<Vec<_>>());
+    }
+    Ok(())
+}
+
+#[hazardflow::test("custom_fifo")]
+fn custom_fifo_masks_queued_channels(tb: &mut Testbench) -> VirgenResult<()> {
+    let inputs = inputs(tb)?;
+    let output = tb.egress::<u32, Ready<()>>("output")?;
+    tb.set_policy(output, ResolverPolicy::Ready(Pattern::Never))?;
+
+    for input in &inputs {
+        tb.push(*input, 1u32)?;
+        tb.push(*input, 2u32)?;
+    }
+    tb.run(20)?;
+
+    // Each ingress channel has at most one payload in the FIFO.
+    assert_eq!(tb.num_transfers(output), 0);
+    for input in &inputs {
+        assert_eq!(tb.num_transfers(*input), 1);
+        assert_eq!(tb.pending(*input), 1);
+    }
+    Ok(())
+}
+
+#[hazardflow::test("custom_fifo")]
+fn custom_fifo_follows_protocol_under_random_stimulus(tb: &mut Testbench) -> VirgenResult<()> {
+    let inputs = inputs(tb)?;
+    let output = tb.egress::<u32, Ready<()>>("output")?;
+    tb.seed(2024);
+    tb.set_policy(output, ResolverPolicy::Ready(Pattern::Bursts { on: 1..=4, off: 1..=6 }))?;
+    tb.check_protocol(output)?;
+
+    for (i, input) in inputs.iter().enumerate() {
+        tb.set_valid(*input, if i % 2 == 0 { Pattern::Random(50) } else { Pattern::Bursts { on: 2..=3, off: 0..=5 } })?;
+        tb.push_random(*input, 8, |payload| payload % 2 == 0)?;
+        tb.check_protocol(*input)?;
+    }
+    tb.run_until_drained(2000)?;
+    tb.run_until(2000, |tb| tb.num_transfers(output) == 40)?;
+
+    let mut sent = inputs.iter().flat_map(|input| tb.transfers(*input)).map(|(_, payload)| payload).collect::<Vec<_>>();
+    let mut received = tb.transfers(output).into_iter().map(|(_, payload)| payload).collect::<Vec<_>>();
+    assert!(received.iter().all(|payload| payload % 2 == 0));
+    sent.sort();
+    received.sort();
+    assert_eq!(sent, received);
+    tb.check_violations()
+}
+
+#[hazardflow::test("custom_fifo")]
+fn scoreboard_matches_reordered_payloads_by_key(tb: &mut Testbench) -> VirgenResult<()> {
+    let inputs = inputs(tb)?;
+    let output = tb.egress::<u32, Ready<()>>("output")?;
+    tb.seed(9);
+    tb.set_policy(output, ResolverPolicy::Ready(Pattern::Random(40)))?;
+
+    for (i, input) in inputs.iter().enumerate() {
+        tb.set_valid(*input, Pattern::Random(60))?;
+        for j in 0..4 {
+            tb.push(*input, (i * 100 + j) as u32)?;
+        }
+    }
+    tb.run_until_drained(400)?;
+    tb.run_until(400, |tb| tb.num_transfers(output) == 20)?;
+
+    // The payloads are expected channel by channel, while the FIFO interleaves them.
+    let scoreboard = |matching| {
+        let mut scoreboard = Scoreboard::new("egress.output", matching);
+        for (cycle, payload) in inputs.iter().flat_map(|input| tb.transfers(*input)) {
+            scoreboard.expect(cycle, payload);
+        }
+        for (cycle, payload) in tb.transfers(output) {
+            scoreboard.observe(cycle, payload);
+        }
+        scoreboard
+    };
+    assert!(scoreboard(Matching::InOrder).check().is_err());
+    scoreboard(Matching::ByKey(Clone::clone)).check()
+}
+
+#[hazardflow::test("custom_fifo")]
+fn protocol_checker_flags_ignored_transfers(tb: &mut Testbench) -> VirgenResult<()> {
+    let input = tb.ingress::<u32, Ready<()>>("input.0[0]")?;
+    let output = tb.egress::<u32, Ready<()>>("output")?;
+
+    // The module sees the payloads transferred, but the checker does not.
+    tb.set_transfer(output, |_, _| false);
+    tb.check_protocol(output)?;
+    for payload in [1u32, 2] {
+        tb.push(input, payload)?;
+    }
+    tb.run(16)?;
+
+    assert!(!tb.violations().is_empty());
+    assert!(tb.check_violations().is_err());
+    Ok(())
+}
+
+#[hazardflow::test("custom_fifo")]
+fn handles_check_the_types_of_channels(tb: &mut Testbench) -> VirgenResult<()> {
+    assert!(tb.ingress::<u32, Ready<()>>("input.0[4]").is_ok());
+    assert!(matches!(tb.ingress::<u32, Ready<()>>("input.0[5]"), Err(VirgenError::SimulationError { .. })));
+    assert!(matches!(tb.egress::<u32, Ready<()>>("input.0[0]"), Err(VirgenError::SimulationError { .. })));
+
+    // The payloads are `u32`, which do not fit in `u16` nor are signed.
+    let err = tb.egress::<u16, Ready<()>>("output").unwrap_err();
+    assert!(err.to_string().contains("payloads of `u32`"), "{}", err);
+    assert!(tb.egress::<i32, Ready<()>>("output").is_err());
+    assert!(tb.egress::<u32, bool>("output").is_err());
+
+    // `Value`s represent any type.
+    let output = tb.channel("egress.output")?;
+    tb.push(tb.ingress::<u32, Ready<()>>("input.0[0]")?, 7u32)?;
+    tb.run_until(16, |tb| tb.num_transfers(output) == 1)?;
+    assert_eq!(tb.transfers(output)[0].1, 7u32.into());
+    Ok(())
+}
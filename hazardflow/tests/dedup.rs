@@ -0,0 +1,83 @@
+//! Tests of the deduplication of structurally identical modules.
+
+#![feature(rustc_private)]
+extern crate rustc_driver;
+extern crate rustc_span;
+
+mod common;
+
+use std::collections::HashMap;
+
+use common::*;
+use hazardflow::vir::*;
+
+fn design(modules: Vec<Module>) -> HashMap<String, Module> {
+    modules.into_iter().map(|module| (module.name.clone(), module)).collect()
+}
+
+fn inst_modules(module: &Module) -> Vec<&str> {
+    let ModuleItem::Commented(_, _, items) = &module.module_items[0] else { panic!() };
+    items
+        .iter()
+        .map(|item| {
+            let ModuleItem::ModuleInstantiation(inst) = item else { panic!() };
+            inst.module_name.as_str()
+        })
+        .collect()
+}
+
+#[test]
+fn dedup_merges_identical_modules() {
+    let mut designs = [design(vec![
+        parent("top", &["top_00_wrap", "top_01_wrap", "top_02_counter"]),
+        parent("top_00_wrap", &["top_00_wrap_00_counter"]),
+        parent("top_01_wrap", &["top_01_wrap_00_counter"]),
+        sized_counter("top_00_wrap_00_counter", 8, 1),
+        sized_counter("top_01_wrap_00_counter", 8, 1),
+        sized_counter("top_02_counter", 16, 1),
+    ])];
+    let canonical_names = dedup(&mut designs, &["top".to_string()]);
+
+    // The counters are merged first, which makes their parents identical.
+    assert_eq!(
+        canonical_names,
+        HashMap::from([
+            ("top_01_wrap".to_string(), "top_00_wrap".to_string()),
+            ("top_01_wrap_00_counter".to_string(), "top_00_wrap_00_counter".to_string()),
+        ])
+    );
+
+    let mut names = designs[0].keys().map(String::as_str).collect::<Vec<_>>();
+    names.sort();
+    assert_eq!(names, ["top", "top_00_wrap", "top_00_wrap_00_counter", "top_02_counter"]);
+    assert_eq!(inst_modules(&designs[0]["top"]), ["top_00_wrap", "top_00_wrap", "top_02_counter"]);
+    assert_eq!(inst_modules(&designs[0]["top_00_wrap"]), ["top_00_wrap_00_counter"]);
+}
+
+#[test]
+fn dedup_merges_across_designs() {
+    let mut designs = [
+        design(vec![parent("a", &["a_00_counter"]), sized_counter("a_00_counter", 8, 1)]),
+        design(vec![
+            parent("b", &["b_00_counter", "b_01_counter"]),
+            sized_counter("b_00_counter", 8, 1),
+            sized_counter("b_01_counter", 8, 1),
+        ]),
+    ];
+    let canonical_names = dedup(&mut designs, &["a".to_string(), "b".to_string()]);
+
+    assert_eq!(
+        canonical_names,
+        HashMap::from([
+            ("b_00_counter".to_string(), "a_00_counter".to_string()),
+            ("b_01_counter".to_string(), "a_00_counter".to_string()),
+        ])
+    );
+
+    // The canonical module is added to the design instantiating it.
+    let mut names = designs[1].keys().map(String::as_str).collect::<Vec<_>>();
+    names.sort();
+    assert_eq!(names, ["a_00_counter", "b"]);
+    assert_eq!(designs[1]["a_00_counter"], designs[0]["a_00_counter"]);
+    assert_eq!(inst_modules(&designs[1]["b"]), ["a_00_counter", "a_00_counter"]);
+}
@@ -0,0 +1,54 @@
+//! Transaction-level tests of `examples::fir_filter`.
+
+#![feature(rustc_private)]
+extern crate rustc_driver;
+
+use hazardflow::compiler::error::VirgenResult;
+use hazardflow::testing::scoreboard::Matching;
+use hazardflow::testing::*;
+
+/// Returns the software FIR filter with the given weights, applied to each input in order.
+fn convolution(weights: [u32; 3]) -> impl FnMut(&u32) -> Option<u32> {
+    let mut window = [0u32; 3];
+    move |input| {
+        window = [*input, window[0], window[1]];
+        Some(window.iter().zip(weights).fold(0u32, |acc, (x, w)| acc.wrapping_add(x.wrapping_mul(w))))
+    }
+}
+
+#[hazardflow::test("fir_filter")]
+fn fir_filter_matches_convolution(tb: &mut Testbench) -> VirgenResult<()> {
+    let input = tb.ingress::<u32, ()>("input.0")?;
+    let output = tb.egress::<u32, ()>("output")?;
+    tb.seed(3);
+
+    tb.push_random(input, 64, |_| true)?;
+    tb.run_until_drained(256)?;
+    tb.run(4)?;
+
+    tb.scoreboard(&[input], output, Matching::InOrder, convolution([4, 2, 3])).check()
+}
+
+#[hazardflow::test("fir_filter")]
+fn scoreboard_reports_mismatches_with_cycles(tb: &mut Testbench) -> VirgenResult<()> {
+    let input = tb.ingress::<u32, ()>("input.0")?;
+    let output = tb.egress::<u32, ()>("output")?;
+
+    for payload in [1u32, 0, 0, 0] {
+        tb.push(input, payload)?;
+    }
+    tb.run_until_drained(16)?;
+    tb.run(4)?;
+
+    // The second output is `2 * 1`, not `3 * 1`.
+    let scoreboard = tb.scoreboard(&[input], output, Matching::InOrder, convolution([4, 3, 3]));
+    let mismatches = scoreboard.mismatches();
+    assert_eq!(mismatches.len(), 1);
+    assert!(scoreboard.check().is_err());
+
+    let report = scoreboard.report().unwrap();
+    let cycle = tb.transfers(output)[1].0;
+    assert!(report.contains(&format!("- 0x3 (expected from cycle {})", cycle)), "{}", report);
+    assert!(report.contains(&format!("+ 0x2 (actual at cycle {})", cycle)), "{}", report);
+    Ok(())
+}
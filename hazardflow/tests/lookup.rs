@@ -0,0 +1,32 @@
+//! Transaction-level tests of `examples::lookup`, whose `for` loop is unrolled.
+
+#![feature(rustc_private)]
+extern crate rustc_driver;
+
+use hazardflow::compiler::error::VirgenResult;
+use hazardflow::testing::*;
+
+#[hazardflow::test("lookup_table")]
+fn lookup_table_returns_first_match(tb: &mut Testbench) -> VirgenResult<()> {
+    let ingress = tb.ingress::<([Option<(u8, u32)>; 4], u8), ()>("input.0")?;
+    let egress = tb.egress::<Option<(u8, u32)>, ()>("output")?;
+
+    let cases = [
+        // The first entry with the key is returned.
+        ([Some((1, 10)), Some((2, 20)), Some((2, 30)), Some((4, 40))], 2, Some((1, 20))),
+        ([Some((1, 10)), Some((2, 20)), Some((3, 30)), Some((4, 40))], 4, Some((3, 40))),
+        ([Some((1, 10)), Some((2, 20)), Some((3, 30)), Some((4, 40))], 5, None),
+        // The entries after the first empty one are not looked up.
+        ([Some((1, 10)), None, Some((3, 30)), Some((4, 40))], 3, None),
+        ([None, Some((1, 10)), None, None], 1, None),
+    ];
+
+    for (entries, key, _) in cases {
+        tb.push(ingress, (entries, key))?;
+    }
+    tb.run_until(32, |tb| tb.num_transfers(egress) == cases.len())?;
+
+    let outputs = tb.transfers(egress).into_iter().map(|(_, payload)| payload).collect::<Vec<_>>();
+    assert_eq!(outputs, cases.map(|(_, _, output)| output));
+    Ok(())
+}
@@ -0,0 +1,86 @@
+//! Transaction-level tests of `examples::micro_op`, which destructures its input with or-patterns, `if let` guards,
+//! `let`-`else` and array patterns.
+
+#![feature(rustc_private)]
+extern crate rustc_driver;
+
+use hazardflow::compiler::debug_info::Value;
+use hazardflow::compiler::error::VirgenResult;
+use hazardflow::testing::*;
+
+/// Operand of a micro-operation.
+#[derive(Clone, Copy)]
+enum Operand {
+    Reg(u8),
+    Imm(u8),
+}
+
+/// Micro-operation.
+#[derive(Clone, Copy)]
+enum MicroOp {
+    Add(Operand, Operand),
+    Mov(Option<Operand>),
+    AddEnds,
+    Nop,
+}
+
+impl From<Operand> for Value {
+    fn from(operand: Operand) -> Self {
+        match operand {
+            Operand::Reg(r) => Value::Variant("Reg".to_string(), vec![("0".to_string(), Value::uint(r.into(), 2))]),
+            Operand::Imm(imm) => Value::Variant("Imm".to_string(), vec![("0".to_string(), Value::uint(imm.into(), 8))]),
+        }
+    }
+}
+
+impl From<MicroOp> for Value {
+    fn from(uop: MicroOp) -> Self {
+        match uop {
+            MicroOp::Add(lhs, rhs) => {
+                Value::Variant("Add".to_string(), vec![("0".to_string(), lhs.into()), ("1".to_string(), rhs.into())])
+            }
+            MicroOp::Mov(src) => {
+                let src = match src {
+                    Some(src) => Value::some(src),
+                    None => Value::none(),
+                };
+                Value::Variant("Mov".to_string(), vec![("0".to_string(), src)])
+            }
+            MicroOp::AddEnds => Value::Variant("AddEnds".to_string(), vec![]),
+            MicroOp::Nop => Value::Variant("Nop".to_string(), vec![]),
+        }
+    }
+}
+
+#[hazardflow::test("micro_op_exec")]
+fn micro_op_exec_destructures_micro_ops(tb: &mut Testbench) -> VirgenResult<()> {
+    // `MicroOp` is an enum of the design, so the payloads are driven as `Value`s.
+    let ingress = tb.channel("ingress.input.0")?;
+    let egress = tb.egress::<Option<u8>, ()>("output")?;
+
+    let regs = Value::Array([10, 20, 30, 40].map(|reg| Value::uint(reg, 8)).to_vec());
+    let cases = [
+        // Either operand of the or-pattern is bound.
+        (MicroOp::Add(Operand::Reg(1), Operand::Imm(0)), Some(20)),
+        (MicroOp::Add(Operand::Imm(0), Operand::Reg(2)), Some(30)),
+        // The `if let` guard, which does not match if the sum overflows.
+        (MicroOp::Add(Operand::Reg(3), Operand::Imm(5)), Some(45)),
+        (MicroOp::Add(Operand::Imm(250), Operand::Imm(10)), Some(255)),
+        // The `let`-`else`, which returns `None` if it does not match.
+        (MicroOp::Mov(Some(Operand::Reg(3))), Some(40)),
+        (MicroOp::Mov(Some(Operand::Imm(7))), Some(7)),
+        (MicroOp::Mov(None), None),
+        // The array pattern.
+        (MicroOp::AddEnds, Some(50)),
+        (MicroOp::Nop, None),
+    ];
+
+    for (uop, _) in cases {
+        tb.push(ingress, Value::tuple([regs.clone(), uop.into()]))?;
+    }
+    tb.run_until(32, |tb| tb.num_transfers(egress) == cases.len())?;
+
+    let outputs = tb.transfers(egress).into_iter().map(|(_, payload)| payload).collect::<Vec<_>>();
+    assert_eq!(outputs, cases.map(|(_, output)| output));
+    Ok(())
+}
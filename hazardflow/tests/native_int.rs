@@ -0,0 +1,52 @@
+//! Transaction-level tests of `examples::native_int`, whose arithmetic is on primitive integers.
+
+#![feature(rustc_private)]
+extern crate rustc_driver;
+
+use hazardflow::compiler::debug_info::Value;
+use hazardflow::compiler::error::VirgenResult;
+use hazardflow::testing::*;
+
+/// Returns the results of `compute`, with the arithmetic wrapping around as in hardware.
+fn compute(addr: u64, stride: u16, delta: i32) -> Value {
+    let clamped = delta.clamp(i16::MIN.into(), i16::MAX.into()) as i16;
+    let shifted_sum = (0..4).fold(0i32, |sum, shamt| sum.wrapping_add(delta >> shamt));
+    let fields: [(&str, Value); 10] = [
+        ("addr", addr.wrapping_add(u64::from(stride) * 4).into()),
+        ("rotated", addr.rotate_left(8).into()),
+        ("low", (addr as u8).into()),
+        ("swapped", stride.swap_bytes().into()),
+        ("neg", delta.wrapping_neg().into()),
+        ("quarter", (delta >> 2).into()),
+        ("is_neg", (delta < 0).into()),
+        ("shifted_sum", shifted_sum.into()),
+        ("clamped", clamped.into()),
+        ("scaled", (i64::from(delta) * 1000 - 5).into()),
+    ];
+    Value::Struct(Some("Results".to_string()), fields.map(|(name, value)| (name.to_string(), value)).to_vec())
+}
+
+#[hazardflow::test("native_int_ops")]
+fn native_int_ops_wraps_and_sign_extends(tb: &mut Testbench) -> VirgenResult<()> {
+    let ingress = tb.ingress::<(u64, u16, i32), ()>("input.0")?;
+    // `Results` is a struct of the design, so it is checked as a `Value`.
+    let egress = tb.egress::<Value, ()>("output")?;
+
+    let cases = [
+        (0x1000u64, 0x0102u16, 7i32),
+        (u64::MAX - 3, 0xffff, -7),
+        (0x0123_4567_89ab_cdef, 0x8000, 40000),
+        (0, 0, i32::MIN),
+        (0xff, 1, -40000),
+    ];
+
+    for payload in cases {
+        tb.push(ingress, payload)?;
+    }
+    tb.run_until(32, |tb| tb.num_transfers(egress) == cases.len())?;
+
+    let outputs = tb.transfers(egress).into_iter().map(|(_, payload)| payload).collect::<Vec<_>>();
+    let expected = cases.map(|(addr, stride, delta)| compute(addr, stride, delta)).to_vec();
+    assert_eq!(outputs, expected);
+    Ok(())
+}
@@ -0,0 +1,124 @@
+//! Tests of the Verilog parameters for the const generics.
+
+#![feature(rustc_private)]
+extern crate rustc_driver;
+extern crate rustc_span;
+
+mod common;
+
+use common::*;
+use hazardflow::vir::param::{gen_parameterized_verilog, ConstGenerics};
+use hazardflow::vir::src_map::SrcLocAnnotator;
+use hazardflow::vir::*;
+
+/// Returns `counter::<N>`, which counts up by `step` in `N` bits when `en` is set.
+fn counter(name: &str, n: usize, step: usize) -> (Module, ConstGenerics) {
+    let generics = ConstGenerics { item: "counter".to_string(), args: vec![("N".to_string(), n as u128)] };
+    (sized_counter(name, n, step), generics)
+}
+
+/// Returns the top module instantiating the counters with the given names and widths.
+fn top(counters: &[(&str, usize)]) -> (Module, ConstGenerics) {
+    let module = Module {
+        name: "top".to_string(),
+        port_decls: vec![PortDeclaration::input(1, "clk".to_string())],
+        module_items: vec![ModuleItem::Commented(
+            "Submodules of top".to_string(),
+            None,
+            counters
+                .iter()
+                .map(|(name, width)| {
+                    ModuleItem::ModuleInstantiation(ModuleInstantiation::new(
+                        name.to_string(),
+                        format!("{}_inst", name),
+                        vec![],
+                        vec![
+                            ("clk".to_string(), ident("clk")),
+                            ("en".to_string(), number("1'b1")),
+                            ("out".to_string(), ident(&format!("{}_out_{}", name, width))),
+                        ],
+                    ))
+                })
+                .collect(),
+        )],
+    };
+    (module, ConstGenerics { item: "top".to_string(), args: vec![] })
+}
+
+#[test]
+fn param_merges_widths() {
+    let modules = vec![
+        top(&[("top_00_counter", 8), ("top_01_counter", 16)]),
+        counter("top_00_counter", 8, 1),
+        counter("top_01_counter", 16, 1),
+    ];
+    let verilogs = gen_parameterized_verilog(modules, &SrcLocAnnotator::default());
+
+    let names = verilogs.iter().map(|(name, _)| name.as_str()).collect::<Vec<_>>();
+    assert_eq!(names, ["top", "top_00_counter"]);
+
+    let counter = &verilogs[1].1;
+    assert!(counter.starts_with(
+        "module top_00_counter #(
+    parameter N = 8,
+    parameter PARAM_0 = 8'd1
+)
+(
+    input wire clk,
+    input wire en,
+    output wire [N-1:0] out
+);"
+    ));
+    assert!(counter.contains("reg [N-1:0] count;"));
+    assert!(counter.contains("count <= count + PARAM_0;"));
+
+    let top = &verilogs[0].1;
+    assert!(top.contains(
+        "top_00_counter #(
+    .N(8),
+    .PARAM_0(8'd1)
+)
+top_00_counter_inst ("
+    ));
+    assert!(top.contains(
+        "top_00_counter #(
+    .N(16),
+    .PARAM_0(16'd1)
+)
+top_01_counter_inst ("
+    ));
+}
+
+#[test]
+fn param_keeps_different_code() {
+    // A 1-bit counter is declared without a range.
+    let modules = vec![
+        top(&[("top_00_counter", 1), ("top_01_counter", 8)]),
+        counter("top_00_counter", 1, 1),
+        counter("top_01_counter", 8, 1),
+    ];
+    let verilogs = gen_parameterized_verilog(modules.clone(), &SrcLocAnnotator::default());
+
+    assert_eq!(verilogs.len(), 3);
+    for ((name, verilog), (module, _)) in verilogs.iter().zip(&modules) {
+        assert_eq!(*name, module.name);
+        assert_eq!(*verilog, module.to_string());
+    }
+}
+
+#[test]
+fn param_keeps_different_values() {
+    // The counters step by different values, which are not widths.
+    let modules = vec![
+        top(&[("top_00_counter", 8), ("top_01_counter", 16)]),
+        counter("top_00_counter", 8, 1),
+        counter("top_01_counter", 16, 2),
+    ];
+    let verilogs = gen_parameterized_verilog(modules.clone(), &SrcLocAnnotator::default());
+
+    assert_eq!(verilogs.len(), 3);
+    for ((name, verilog), (module, _)) in verilogs.iter().zip(&modules) {
+        assert_eq!(*name, module.name);
+        assert_eq!(*verilog, module.to_string());
+    }
+}
@@ -0,0 +1,137 @@
+//! Runs the riscv-tests in `scripts/cpu/program` on `cpu::core`.
+
+#![feature(rustc_private)]
+extern crate rustc_driver;
+extern crate rustc_span;
+
+mod common;
+
+use std::path::{Path, PathBuf};
+
+use common::*;
+use hazardflow::compiler::debug_info::Value;
+use hazardflow::compiler::error::VirgenResult;
+use hazardflow::testing::riscv::*;
+
+/// Runs a program, and returns the testbench after it finished.
+fn run(program: &Path, max_cycles: usize) -> VirgenResult<(Exit, CoreTestbench)> {
+    let mut tb = CoreTestbench::new()?;
+    tb.load(&Elf::read(program)?);
+    let exit = tb.run(max_cycles)?;
+    Ok((exit, tb))
+}
+
+/// Runs the programs, and returns the failed ones with the reasons.
+fn failures(programs: &[PathBuf], max_cycles: usize) -> Vec<String> {
+    programs
+        .iter()
+        .filter_map(|program| {
+            let name = program.file_name().unwrap().to_string_lossy();
+            match run(program, max_cycles) {
+                Ok((Exit::Pass, _)) => None,
+                Ok((Exit::Fail(code), _)) => Some(format!("{}: failed test case {}", name, code)),
+                Err(err) => Some(format!("{}: {:?}", name, err)),
+            }
+        })
+        .collect()
+}
+
+#[test]
+#[ignore = "runs all the base ISA tests; run with `cargo test -- --ignored`"]
+fn core_passes_base_isa_tests() {
+    let programs = programs("isa/base");
+    assert_eq!(programs.len(), 42);
+    let failures = failures(&programs, 20_000);
+    assert!(failures.is_empty(), "{:#?}", failures);
+}
+
+#[test]
+fn core_logs_commits() -> VirgenResult<()> {
+    let program = programs("isa/base").into_iter().find(|program| program.ends_with("rv32ui-p-simple")).unwrap();
+    let (exit, tb) = run(&program, 20_000)?;
+    assert_eq!(exit, Exit::Pass);
+
+    // The first instruction is at the reset vector, and `lui` and `auipc` write their destination registers.
+    let commits = tb.commits();
+    assert_eq!(commits[0].pc, 0x8000_0000);
+    assert!(commits.windows(2).all(|commits| commits[0].cycle < commits[1].cycle));
+    let upper =
+        commits.iter().filter(|commit| matches!(commit.inst & 0x7f, 0x17 | 0x37) && (commit.inst >> 7) & 0x1f != 0);
+    for commit in upper {
+        assert_eq!(commit.write.map(|(rd, _)| rd), Some((commit.inst >> 7) & 0x1f), "{}", commit);
+    }
+
+    let log = Path::new(env!("CARGO_TARGET_TMPDIR")).join("rv32ui-p-simple.commits");
+    tb.write_commit_log(&log)?;
+    assert_eq!(std::fs::read_to_string(log).unwrap().lines().count(), commits.len());
+    Ok(())
+}
+
+#[test]
+fn elf_loader_finds_segments_and_symbols() -> VirgenResult<()> {
+    let program = programs("isa/base").into_iter().find(|program| program.ends_with("rv32ui-p-simple")).unwrap();
+    let elf = Elf::read(program)?;
+    assert_eq!(elf.entry, 0x8000_0000);
+    assert_eq!(elf.symbol("tohost"), Some(0x8000_1000));
+
+    // `rv32ui-p-simple` starts with `j reset_vector`.
+    let mut memory = Memory::new();
+    memory.load_elf(&elf);
+    assert_eq!(memory.read(0x8000_0000, 4) & 0x7f, 0x6f);
+    Ok(())
+}
+
+#[test]
+fn memory_extends_loads_and_masks_stores() -> VirgenResult<()> {
+    let req = |fcn: &str, typ: &str, addr: u32, data: u32| {
+        Value::Struct(None, vec![
+            ("addr".to_string(), addr.into()),
+            ("data".to_string(), data.into()),
+            ("fcn".to_string(), Value::Variant(fcn.to_string(), vec![])),
+            ("typ".to_string(), Value::Variant(typ.to_string(), vec![])),
+        ])
+    };
+
+    let mut memory = Memory::new();
+    memory.access(&req("Store", "W", 0x100, 0x1234_5678))?;
+    memory.access(&req("Store", "B", 0x101, 0xffff_ff80))?;
+    assert_eq!(memory.respond(&req("Load", "W", 0x100, 0))?, 0x1234_8078);
+    assert_eq!(memory.respond(&req("Load", "B", 0x101, 0))?, 0xffff_ff80);
+    assert_eq!(memory.respond(&req("Load", "BU", 0x101, 0))?, 0x80);
+    assert_eq!(memory.respond(&req("Load", "H", 0x100, 0))?, 0xffff_8078);
+    assert_eq!(memory.respond(&req("Load", "HU", 0x102, 0))?, 0x1234);
+    assert!(memory.respond(&req("Load", "X", 0x100, 0)).is_err());
+    Ok(())
+}
+
+#[test]
+fn core_saves_and_clears_mie_on_trap_entry() -> VirgenResult<()> {
+    // Sets `mstatus.MIE` and traps with `ecall`. The handler at the trap vector passes if `MIE` is cleared and `MPIE`
+    // holds its previous value, and fails with the bits otherwise.
+    let program: [u32; 14] = [
+        0x0100_006f, // j 0x80000010
+        0x3000_2573, // csrr a0, mstatus (trap vector)
+        0x0885_7513, // andi a0, a0, 0x88
+        0x0140_006f, // j 0x80000020
+        0x0080_0293, // li t0, 8
+        0x3002_a073, // csrs mstatus, t0
+        0x0000_0073, // ecall
+        0x0000_006f, // j .
+        0x0805_4513, // xori a0, a0, 0x80
+        0x0015_1513, // slli a0, a0, 1
+        0x0015_6513, // ori a0, a0, 1
+        0x8000_1337, // lui t1, 0x80001
+        0x00a3_2023, // sw a0, 0(t1)
+        0x0000_006f, // j .
+    ];
+    let elf = Elf {
+        entry: 0x8000_0000,
+        segments: vec![(0x8000_0000, program.iter().flat_map(|inst| inst.to_le_bytes()).collect())],
+        symbols: [("tohost".to_string(), 0x8000_1000)].into(),
+    };
+
+    let mut tb = CoreTestbench::new()?;
+    tb.load(&elf);
+    assert_eq!(tb.run(1_000)?, Exit::Pass);
+    Ok(())
+}
@@ -0,0 +1,129 @@
+//! Runs `cpu::core` in lockstep with the instruction-set simulator of `hazardflow-designs`.
+
+#![feature(rustc_private)]
+extern crate rustc_driver;
+extern crate rustc_span;
+
+mod common;
+
+use std::path::Path;
+
+use common::*;
+use hazardflow::compiler::error::VirgenResult;
+use hazardflow::testing::riscv::*;
+use hazardflow_designs::cpu::Iss;
+use hazardflow_designs::std::HOption;
+
+/// Returns the simulator with a program loaded.
+fn iss(elf: &Elf) -> Iss {
+    let mut iss = Iss::new(elf.entry);
+    for (addr, data) in &elf.segments {
+        iss.load(*addr, data);
+    }
+    iss
+}
+
+/// Executes the next instruction on the simulator, and returns it as compared in lockstep.
+fn step(iss: &mut Iss) -> Commit {
+    let retired = iss.step();
+    let write = match retired.write {
+        HOption::Some(register) => Some((u32::from(register.addr), register.data)),
+        HOption::None => None,
+    };
+    let csr = match retired.csr {
+        HOption::Some(csr) => Some(CsrCommit {
+            write: match csr.write {
+                HOption::Some((addr, data)) => Some((u32::from(addr), data)),
+                HOption::None => None,
+            },
+            mstatus: csr.mstatus,
+            mepc: csr.mepc,
+            mcause: csr.mcause,
+        }),
+        HOption::None => None,
+    };
+    Commit { cycle: 0, pc: retired.pc, inst: retired.inst, write, csr }
+}
+
+/// Runs a program in lockstep with the simulator.
+fn run_lockstep(program: &Path) -> VirgenResult<Exit> {
+    let elf = Elf::read(program)?;
+    let mut iss = iss(&elf);
+    let mut tb = CoreTestbench::new()?;
+    tb.load(&elf);
+    tb.run_lockstep(20_000, || step(&mut iss))
+}
+
+#[test]
+#[ignore = "runs all the base ISA tests; run with `cargo test -- --ignored`"]
+fn core_matches_iss_on_base_isa_tests() {
+    let failures = programs("isa/base")
+        .iter()
+        .filter_map(|program| {
+            let name = program.file_name().unwrap().to_string_lossy();
+            match run_lockstep(program) {
+                Ok(Exit::Pass) => None,
+                Ok(Exit::Fail(code)) => Some(format!("{}: failed test case {}", name, code)),
+                Err(err) => Some(format!("{}: {:?}", name, err)),
+            }
+        })
+        .collect::<Vec<_>>();
+    assert!(failures.is_empty(), "{:#?}", failures);
+}
+
+#[test]
+fn lockstep_reports_first_divergence() -> VirgenResult<()> {
+    let program = programs("isa/base").into_iter().find(|program| program.ends_with("rv32ui-p-add")).unwrap();
+    let elf = Elf::read(program)?;
+    let mut iss = iss(&elf);
+    let mut tb = CoreTestbench::new()?;
+    tb.load(&elf);
+
+    // A model which computes a wrong value for the 100-th instruction writing a register.
+    let mut writes = 0;
+    let err = tb
+        .run_lockstep(20_000, || {
+            let commit = step(&mut iss);
+            writes += usize::from(commit.write.is_some());
+            let write = commit.write.map(|(rd, data)| (rd, if writes == 100 { data ^ 1 } else { data }));
+            Commit { write, ..commit }
+        })
+        .unwrap_err();
+
+    let msg = format!("{:?}", err);
+    let diverged = tb.commits().iter().filter(|commit| commit.write.is_some()).nth(99).unwrap();
+    assert!(msg.contains(&format!("+ {} (core)", diverged)), "{}", msg);
+    assert!(msg.contains("preceding commits:"), "{}", msg);
+    Ok(())
+}
+
+#[test]
+fn lockstep_compares_csr_effects() -> VirgenResult<()> {
+    let program = programs("isa/base").into_iter().find(|program| program.ends_with("rv32mi-p-scall")).unwrap();
+    let elf = Elf::read(program)?;
+    let mut iss = iss(&elf);
+    let mut tb = CoreTestbench::new()?;
+    tb.load(&elf);
+
+    // A model which reports a wrong `mcause` for `ecall`, which writes no register.
+    let err = tb
+        .run_lockstep(20_000, || {
+            let commit = step(&mut iss);
+            let csr = commit.csr.map(|csr| match commit.inst {
+                0x0000_0073 => CsrCommit { mcause: 0x3, ..csr },
+                _ => csr,
+            });
+            Commit { csr, ..commit }
+        })
+        .unwrap_err();
+
+    let msg = format!("{:?}", err);
+    let ecall = tb.commits().iter().find(|commit| commit.inst == 0x0000_0073).unwrap();
+    assert_eq!(ecall.csr.map(|csr| csr.mcause), Some(0xb));
+    assert!(msg.contains(&format!("+ {} (core)", ecall)), "{}", msg);
+
+    // The CSR instructions report the written CSRs.
+    let writes = tb.commits().iter().filter_map(|commit| commit.csr?.write).collect::<Vec<_>>();
+    assert!(writes.iter().any(|(addr, _)| *addr == 0x305), "{:?}", writes);
+    Ok(())
+}
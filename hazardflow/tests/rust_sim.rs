@@ -0,0 +1,150 @@
+//! Tests of the Rust simulator generated by `--emit=rust-sim`, which is compiled and run against [`Simulator`].
+
+#![feature(rustc_private)]
+extern crate rustc_driver;
+extern crate rustc_span;
+
+mod common;
+
+use std::fmt::Write;
+use std::path::Path;
+use std::process::Command;
+
+use common::*;
+use hazardflow::compiler::error::VirgenResult;
+use hazardflow::compiler::{BinaryOp, Shape};
+use hazardflow::testing::compile;
+use hazardflow::testing::stimulus::Rng;
+use hazardflow::vir::sim::{gen_rust_sim, Bits, Simulator};
+use hazardflow::vir::*;
+use rustc_span::DUMMY_SP;
+
+/// Returns the field of the port in the generated simulator.
+fn field(name: &str) -> String {
+    match name {
+        "self" | "Self" | "super" | "crate" => format!("{name}_"),
+        _ => format!("r#{name}"),
+    }
+}
+
+/// Drives the inputs of `module` other than `clk` and `rst` with random values for `cycles` cycles after a reset if it
+/// has `rst`, and checks that the generated simulator computes the same outputs and displays as [`Simulator`].
+fn check_against_interpreter(module: &Module, cycles: usize, seed: u64) -> VirgenResult<()> {
+    let source = gen_rust_sim(module)?;
+    let name = source.lines().find_map(|line| line.strip_prefix("pub struct ")).unwrap().trim_end_matches(" {");
+
+    let mut sim = Simulator::new(module)?;
+    let ports = sim.port_decls().to_vec();
+    let mut rng = Rng::new(seed);
+
+    let mut expected = String::new();
+    let mut main = format!("fn main() {{\n    let mut sim = {name}::new();\n");
+    if ports.iter().any(|port| port.name() == "rst") {
+        main.push_str("    sim.reset(1);\n");
+        sim.reset(1)?;
+    }
+    for _ in 0..cycles {
+        for port in ports.iter() {
+            let PortDeclaration::Input(width, port) = port else { continue };
+            if *width == 0 || port == "clk" || port == "rst" {
+                continue;
+            }
+            let words = (0..(width + 63) / 64).map(|_| rng.next_u64()).collect::<Vec<_>>();
+            let value = Bits::from_words(&words, *width);
+            writeln!(main, "    sim.{} = {:?};", field(port), value.words()).unwrap();
+            sim.poke(port, value)?;
+        }
+        writeln!(main, "    sim.eval_comb();").unwrap();
+        for port in ports.iter() {
+            let PortDeclaration::Output(width, port) = port else { continue };
+            if *width == 0 {
+                continue;
+            }
+            writeln!(main, "    println!(\"{{:?}}\", sim.{});", field(port)).unwrap();
+            writeln!(expected, "{:?}", sim.peek(port)?.words()).unwrap();
+        }
+        writeln!(main, "    sim.tick();").unwrap();
+        sim.step()?;
+    }
+    main.push_str("}\n");
+
+    let dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("rust_sim").join(&module.name);
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("sim.rs"), source).unwrap();
+    std::fs::write(dir.join("main.rs"), format!("include!(\"sim.rs\");\n\n{main}")).unwrap();
+
+    let rustc = std::env::var("RUSTC").unwrap_or_else(|_| "rustc".to_string());
+    let output = Command::new(rustc)
+        .args(["--edition=2021", "-Awarnings", "main.rs", "-o", "main"])
+        .current_dir(&dir)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+    let output = Command::new(dir.join("main")).output().unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), expected);
+    let mut displays = String::new();
+    for (cycle, line) in sim.take_displays() {
+        writeln!(displays, "[{cycle}] {line}").unwrap();
+    }
+    assert_eq!(String::from_utf8(output.stderr).unwrap(), displays);
+    Ok(())
+}
+
+#[test]
+fn rust_sim_matches_interpreter() -> VirgenResult<()> {
+    let compiled = compile("custom_fifo", false)?;
+    check_against_interpreter(&compiled.module, 64, 11)
+}
+
+#[test]
+fn rust_sim_escapes_keywords() -> VirgenResult<()> {
+    let keywords = ["macro", "override", "priv", "typeof", "unsized", "virtual", "yield", "try", "self"];
+    let module = Module {
+        name: "keywords".to_string(),
+        port_decls: keywords
+            .iter()
+            .map(|name| PortDeclaration::input(8, name.to_string()))
+            .chain([PortDeclaration::output(8, "final".to_string())])
+            .collect(),
+        module_items: vec![ModuleItem::ContinuousAssigns(vec![ContinuousAssign::new(
+            ident("final"),
+            keywords
+                .iter()
+                .map(|name| ident(name))
+                .reduce(|lhs, rhs| Expression::binary(BinaryOp::Add, lhs, rhs))
+                .unwrap(),
+        )])],
+    };
+    check_against_interpreter(&module, 4, 5)
+}
+
+#[test]
+fn rust_sim_bookkeeping_names() -> VirgenResult<()> {
+    // The generated simulator keeps its own fields next to the signals, and `cycle` prints in a combinational process.
+    let add = |lhs: &str, rhs: &str| Expression::binary(BinaryOp::Add, ident(lhs), ident(rhs));
+    let module =
+        Module {
+            name: "bookkeeping".to_string(),
+            port_decls: vec![
+                PortDeclaration::input(1, "clk".to_string()),
+                PortDeclaration::input(8, "nba".to_string()),
+                PortDeclaration::input(8, "__nba".to_string()),
+                PortDeclaration::output(8, "__cycle".to_string()),
+            ],
+            module_items: vec![
+                ModuleItem::Declarations(vec![Declaration::reg(Shape::new([8], false), "cycle".to_string())]),
+                ModuleItem::ContinuousAssigns(vec![ContinuousAssign::new(ident("__cycle"), add("cycle", "__nba"))]),
+                ModuleItem::AlwaysConstruct("always @*".to_string(), vec![Statement::Display(
+                    "cycle = %d".to_string(),
+                    vec![ident("cycle")],
+                    DUMMY_SP,
+                )]),
+                ModuleItem::AlwaysConstruct("always @(posedge clk)".to_string(), vec![
+                    Statement::nonblocking_assignment(ident("cycle"), add("cycle", "nba"), DUMMY_SP),
+                ]),
+            ],
+        };
+    check_against_interpreter(&module, 8, 3)
+}
@@ -0,0 +1,39 @@
+//! Transaction-level tests of `examples::signed`, whose arithmetic is on `S<N>`.
+
+#![feature(rustc_private)]
+extern crate rustc_driver;
+extern crate rustc_span;
+
+mod common;
+
+use common::*;
+use hazardflow::compiler::debug_info::Value;
+use hazardflow::compiler::error::VirgenResult;
+use hazardflow::testing::scoreboard::Matching;
+use hazardflow::testing::*;
+
+#[hazardflow::test("signed_ops")]
+fn signed_ops_matches_integer_math(tb: &mut Testbench) -> VirgenResult<()> {
+    let input = tb.ingress::<(i16, i16), ()>("input.0")?;
+    // `Results` is a struct of the design, so it is checked as a `Value`.
+    let output = tb.egress::<Value, ()>("output")?;
+    tb.seed(23);
+
+    tb.push_random(input, 64, |_| true)?;
+    tb.run_until_drained(128)?;
+
+    let model = |(a, b): &(i16, i16)| {
+        let (a, b) = (i64::from(*a), i64::from(*b));
+        let fields: [(&str, Value); 7] = [
+            ("sum", (a + b).into()),
+            ("diff", truncate(a - b, 12).into()),
+            ("product", (a * b).into()),
+            ("neg", truncate(-a, 12).into()),
+            ("quarter", (a >> 3).into()),
+            ("min", a.min(b).into()),
+            ("ge", (a >= b).into()),
+        ];
+        Some(Value::Struct(Some("Results".to_string()), fields.map(|(name, value)| (name.to_string(), value)).to_vec()))
+    };
+    tb.scoreboard(&[input], output, Matching::InOrder, model).check()
+}
@@ -0,0 +1,102 @@
+//! Tests of [`Simulator`].
+
+#![feature(rustc_private)]
+extern crate rustc_driver;
+extern crate rustc_span;
+
+mod common;
+
+use common::*;
+use hazardflow::compiler::error::{VirgenError, VirgenResult};
+use hazardflow::compiler::{BinaryOp, Shape};
+use hazardflow::vir::sim::Simulator;
+use hazardflow::vir::*;
+use rustc_span::DUMMY_SP;
+
+/// Returns a module which prints `in` and asserts that it is at most 100 in an `always @*` block, as `display!` and
+/// `hassert!` are lowered.
+fn checker() -> Module {
+    let input = ident("in");
+    Module {
+        name: "checker".to_string(),
+        port_decls: vec![
+            PortDeclaration::input(1, "clk".to_string()),
+            PortDeclaration::input(8, "in".to_string()),
+            PortDeclaration::output(8, "out".to_string()),
+        ],
+        module_items: vec![
+            ModuleItem::Declarations(vec![Declaration::reg(Shape::new([8], false), "last".to_string())]),
+            ModuleItem::ContinuousAssigns(vec![ContinuousAssign::new(ident("out"), ident("last"))]),
+            ModuleItem::AlwaysConstruct("always @*".to_string(), vec![
+                Statement::Display("in = %d".to_string(), vec![input.clone()], DUMMY_SP),
+                Statement::Conditional(
+                    vec![(Expression::binary(BinaryOp::Greater, input.clone(), number("8'd100")), vec![
+                        Statement::Display("ERROR: too large".to_string(), vec![], DUMMY_SP),
+                        Statement::Fatal,
+                    ])],
+                    vec![],
+                    DUMMY_SP,
+                ),
+            ]),
+            ModuleItem::AlwaysConstruct("always @(posedge clk)".to_string(), vec![Statement::nonblocking_assignment(
+                ident("last"),
+                input,
+                DUMMY_SP,
+            )]),
+        ],
+    }
+}
+
+#[test]
+fn sim_counter() -> VirgenResult<()> {
+    let modules = counter().into_iter().map(|module| (module.name.clone(), module)).collect();
+    let mut sim = Simulator::from_modules(modules, "top")?;
+    sim.reset(2)?;
+    assert_eq!(sim.peek_u64("count")?, 0);
+
+    sim.run(20)?;
+    assert_eq!(sim.cycle(), 22);
+    assert_eq!(sim.peek_u64("count")?, 20);
+    assert_eq!(sim.peek_u64("hi")?, 1);
+    Ok(())
+}
+
+#[test]
+fn sim_poke_output() -> VirgenResult<()> {
+    let mut sim = Simulator::new(&checker())?;
+    assert!(matches!(sim.poke_u64("out", 1), Err(VirgenError::SimulationError { .. })));
+    assert!(matches!(sim.peek_u64("unknown"), Err(VirgenError::SimulationError { .. })));
+    Ok(())
+}
+
+#[test]
+fn sim_comb_display() -> VirgenResult<()> {
+    let mut sim = Simulator::new(&checker())?;
+
+    // The process is evaluated more than once while settling, but prints once per cycle with the settled value.
+    for value in [3, 40, 41] {
+        sim.poke_u64("in", 0)?;
+        sim.peek_u64("out")?;
+        sim.poke_u64("in", value)?;
+        sim.step()?;
+    }
+    assert_eq!(sim.peek_u64("out")?, 41);
+    assert_eq!(sim.take_displays(), [
+        (0, "in =   3".to_string()),
+        (1, "in =  40".to_string()),
+        (2, "in =  41".to_string())
+    ]);
+    Ok(())
+}
+
+#[test]
+fn sim_comb_fatal() -> VirgenResult<()> {
+    let mut sim = Simulator::new(&checker())?;
+    sim.poke_u64("in", 100)?;
+    sim.step()?;
+
+    sim.poke_u64("in", 101)?;
+    let Err(VirgenError::SimulationError { msg }) = sim.step() else { panic!("the assertion did not fail") };
+    assert_eq!(msg, "$fatal at cycle 1: ERROR: too large");
+    Ok(())
+}
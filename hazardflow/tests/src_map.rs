@@ -0,0 +1,98 @@
+//! Tests of the source location annotations in the generated Verilog.
+
+#![feature(rustc_private)]
+extern crate rustc_driver;
+extern crate rustc_span;
+
+mod common;
+
+use common::*;
+use hazardflow::vir::src_map::{gen_src_map, SrcLocAnnotator, SrcLocation, SrcMapping};
+use hazardflow::vir::*;
+use hazardflow::SrcLoc;
+use rustc_span::{BytePos, Span, DUMMY_SP};
+
+/// Returns a span which `locate` puts at `line` of `counter.rs`.
+fn span(line: u32) -> Span {
+    Span::with_root_ctxt(BytePos(line), BytePos(line + 1))
+}
+
+fn locate(span: Span) -> Option<SrcLocation> {
+    (!span.is_dummy()).then(|| SrcLocation {
+        file: "src/counter.rs".to_string(),
+        line: span.lo().0 as usize,
+        column: 5,
+    })
+}
+
+/// Returns a counter which counts up when `en` is set, where the statements are generated from `counter.rs`.
+fn located_counter() -> Module {
+    let count = ident("count");
+    Module {
+        name: "counter".to_string(),
+        port_decls: vec![PortDeclaration::input(1, "clk".to_string()), PortDeclaration::input(1, "en".to_string())],
+        module_items: vec![ModuleItem::AlwaysConstruct("always @(posedge clk)".to_string(), vec![
+            Statement::Conditional(
+                vec![(ident("en"), vec![Statement::nonblocking_assignment(count.clone(), number("8'd1"), span(12))])],
+                vec![Statement::nonblocking_assignment(count, number("8'd0"), DUMMY_SP)],
+                span(11),
+            ),
+            Statement::Display("count".to_string(), vec![], span(15)),
+        ])],
+    }
+}
+
+fn mapping(line: usize, src_line: usize) -> SrcMapping {
+    SrcMapping { line, src: SrcLocation { file: "src/counter.rs".to_string(), line: src_line, column: 5 } }
+}
+
+#[test]
+fn src_loc_comments() {
+    let verilog = located_counter().gen_verilog(&SrcLocAnnotator::new(SrcLoc::Comment, &locate));
+    assert!(verilog.contains(
+        "always @(posedge clk) begin
+    // src/counter.rs:11:5
+    if (en) begin
+        count <= 8'd1; // src/counter.rs:12:5
+    end
+    else begin
+        count <= 8'd0;
+    end
+    $fdisplay(32'h80000002,\"[%0t] count\", $time); // src/counter.rs:15:5
+end"
+    ));
+
+    let offset = verilog.lines().position(|line| line.starts_with("always")).unwrap() + 1;
+    assert_eq!(gen_src_map(&verilog), vec![mapping(offset + 2, 11), mapping(offset + 3, 12), mapping(offset + 8, 15)]);
+}
+
+#[test]
+fn src_loc_attributes() {
+    let verilog = located_counter().gen_verilog(&SrcLocAnnotator::new(SrcLoc::Attribute, &locate));
+    assert!(verilog.contains(
+        "always @(posedge clk) begin
+    (* src = \"src/counter.rs:11:5\" *)
+    if (en) begin
+        (* src = \"src/counter.rs:12:5\" *) count <= 8'd1;
+    end
+    else begin
+        count <= 8'd0;
+    end
+    (* src = \"src/counter.rs:15:5\" *) $fdisplay(32'h80000002,\"[%0t] count\", $time);
+end"
+    ));
+
+    let offset = verilog.lines().position(|line| line.starts_with("always")).unwrap() + 1;
+    assert_eq!(gen_src_map(&verilog), vec![mapping(offset + 2, 11), mapping(offset + 3, 12), mapping(offset + 8, 15)]);
+}
+
+#[test]
+fn src_loc_none() {
+    let module = located_counter();
+    let verilog = module.gen_verilog(&SrcLocAnnotator::new(SrcLoc::None, &locate));
+    assert_eq!(verilog, module.to_string());
+    assert!(!verilog.contains("counter.rs"));
+    assert!(verilog.contains(&format!("// {:?}\n    if (en) begin", span(11))));
+    assert!(verilog.contains(&format!("count <= 8'd1; // {:?}", span(12))));
+    assert!(gen_src_map(&verilog).is_empty());
+}
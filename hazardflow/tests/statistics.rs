@@ -0,0 +1,63 @@
+//! Transaction-level tests of `examples::statistics`, whose functions assign mutable locals in loops.
+
+#![feature(rustc_private)]
+extern crate rustc_driver;
+
+use hazardflow::compiler::debug_info::Value;
+use hazardflow::compiler::error::VirgenResult;
+use hazardflow::testing::*;
+
+#[hazardflow::test("sample_stats")]
+fn sample_stats_counts_valid_samples(tb: &mut Testbench) -> VirgenResult<()> {
+    let ingress = tb.ingress::<[Option<u8>; 4], ()>("input.0")?;
+    // `Stats` is a struct of the design, so it is checked as a `Value`.
+    let egress = tb.egress::<Value, ()>("output")?;
+
+    let cases = [
+        ([Some(1), Some(2), Some(3), Some(4)], (4, 10, 4)),
+        ([Some(200), None, Some(255), Some(7)], (3, 462, 255)),
+        ([None, Some(9), None, Some(3)], (2, 12, 9)),
+        ([None, None, None, None], (0, 0, 0)),
+    ];
+
+    for (input, _) in cases {
+        tb.push(ingress, input)?;
+    }
+    tb.run_until(32, |tb| tb.num_transfers(egress) == cases.len())?;
+
+    let outputs = tb.transfers(egress).into_iter().map(|(_, payload)| payload).collect::<Vec<_>>();
+    let expected = cases
+        .map(|(_, (count, sum, max))| {
+            Value::Struct(Some("Stats".to_string()), vec![
+                ("count".to_string(), Value::uint(count, 3)),
+                ("sum".to_string(), Value::uint(sum, 10)),
+                ("max".to_string(), Value::uint(max, 8)),
+            ])
+        })
+        .to_vec();
+    assert_eq!(outputs, expected);
+    Ok(())
+}
+
+#[hazardflow::test("sample_histogram")]
+fn sample_histogram_stops_at_invalid_sample(tb: &mut Testbench) -> VirgenResult<()> {
+    let ingress = tb.ingress::<[Option<u8>; 4], ()>("input.0")?;
+    let egress = tb.egress::<[u8; 4], ()>("output")?;
+
+    let cases = [
+        ([Some(0), Some(3), Some(3), Some(1)], [1, 1, 0, 2]),
+        ([Some(2), Some(2), Some(2), Some(2)], [0, 0, 4, 0]),
+        // The samples after the first invalid one are not counted.
+        ([Some(1), None, Some(1), Some(0)], [0, 1, 0, 0]),
+        ([None, Some(3), Some(3), Some(3)], [0, 0, 0, 0]),
+    ];
+
+    for (input, _) in cases {
+        tb.push(ingress, input)?;
+    }
+    tb.run_until(32, |tb| tb.num_transfers(egress) == cases.len())?;
+
+    let outputs = tb.transfers(egress).into_iter().map(|(_, payload)| payload).collect::<Vec<_>>();
+    assert_eq!(outputs, cases.map(|(_, counts)| counts));
+    Ok(())
+}
@@ -0,0 +1,49 @@
+//! Tests of the SystemVerilog output.
+
+#![feature(rustc_private)]
+extern crate rustc_driver;
+
+use hazardflow::compiler::error::VirgenResult;
+use hazardflow::testing::compile;
+use hazardflow::vir::sv::{gen_sv_module, SvTypes};
+
+/// Returns the package and the top module of `target` in SystemVerilog.
+fn sv(target: &str) -> VirgenResult<(String, String)> {
+    let compiled = compile(target, false)?;
+    let types = SvTypes::new(&compiled.debug_info);
+    let package = types.gen_package("top_pkg");
+    let module = gen_sv_module(&compiled.module, &compiled.debug_info, &types, "top_pkg");
+    Ok((package, module))
+}
+
+#[test]
+fn sv_declares_rust_types() -> VirgenResult<()> {
+    let (package, module) = sv("core")?;
+
+    assert!(package.starts_with("package top_pkg;"), "{}", package);
+    assert!(package.contains(
+        "typedef struct packed {\n    logic [32-1:0] addr;\n    logic [32-1:0] data;\n    MemOpFcn_e fcn;\n    MemOpTyp_e typ;\n} MemReq_t;"
+    ));
+    assert!(package.contains("typedef enum logic [3-1:0] {\n    MemOpTyp_B = 3'd1,"));
+    assert!(package
+        .contains("typedef struct packed {\n    HOption_e discriminant;\n    MemReq_t Some;\n} HOption_MemReq_t;"));
+
+    assert!(module.starts_with("module core_top\n    import top_pkg::*;\n"), "{}", module);
+    assert!(module.contains("output wire HOption_MemReq_t egress_input_0_input_0_payload,"));
+    assert!(module.contains("input wire Ready_t egress_input_0_input_0_resolver,"));
+    assert!(module.contains(
+        "assign egress_input_0_input_0_payload.Some.typ = MemOpTyp_e'(out_input_0_input_0_payload_Some_0_typ_discriminant);"
+    ));
+    Ok(())
+}
+
+#[test]
+fn sv_packs_arrays_of_channels() -> VirgenResult<()> {
+    let (_, module) = sv("custom_fifo")?;
+
+    assert!(module.contains("input wire HOption_u32_t [5-1:0] ingress_input_0_payload,"), "{}", module);
+    assert!(module.contains("output wire Ready_t [5-1:0] ingress_input_0_resolver,"));
+    assert!(module.contains("assign in_input_0_payload_Some_0[32 +: 32] = ingress_input_0_payload[1].Some;"));
+    assert!(module.contains("assign ingress_input_0_resolver[4].ready = in_input_0_resolver_ready[4 +: 1];"));
+    Ok(())
+}
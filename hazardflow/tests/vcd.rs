@@ -0,0 +1,129 @@
+//! Tests of the waveforms written by [`Simulator`].
+
+#![feature(rustc_private)]
+extern crate rustc_driver;
+extern crate rustc_span;
+
+mod common;
+
+use std::path::Path;
+
+use common::*;
+use hazardflow::compiler::debug_info::{ChannelInfo, DebugInfo, ModuleInfo, SignalInfo, TypeInfo};
+use hazardflow::compiler::error::VirgenResult;
+use hazardflow::testing::Testbench;
+use hazardflow::vir::sim::{read_vcd, Simulator};
+
+/// Simulates the counter for `cycles` cycles after a reset, and returns its waveform written to `name`.
+fn counter_vcd(name: &str, cycles: usize) -> VirgenResult<String> {
+    let modules = counter().into_iter().map(|module| (module.name.clone(), module)).collect();
+    let mut sim = Simulator::from_modules(modules, "top")?;
+    sim.reset(1)?;
+    sim.trace()?;
+    sim.run(cycles)?;
+
+    let path = Path::new(env!("CARGO_TARGET_TMPDIR")).join(name);
+    sim.write_vcd(&path)?;
+    Ok(std::fs::read_to_string(path).unwrap())
+}
+
+#[test]
+fn vcd_declares_scopes() -> VirgenResult<()> {
+    let vcd = counter_vcd("vcd_declares_scopes.vcd", 3)?;
+    let header = vcd.split_inclusive('\n').take_while(|line| !line.starts_with('#')).collect::<String>();
+    assert_eq!(
+        header,
+        "$version hazardflow $end
+$timescale 1ns $end
+$scope module top $end
+$var wire 1 ! clk $end
+$var wire 1 \" rst $end
+$var wire 4 # hi [3:0] $end
+$var wire 8 $ count [7:0] $end
+$scope module counter_inst $end
+$var wire 1 % en $end
+$var wire 8 & out [7:0] $end
+$var reg 8 ' count [7:0] $end
+$upscope $end
+$upscope $end
+$enddefinitions $end
+"
+    );
+    Ok(())
+}
+
+#[test]
+fn vcd_records_value_changes() -> VirgenResult<()> {
+    let dump = read_vcd(&counter_vcd("vcd_records_value_changes.vcd", 3)?)?;
+    let code = |scope: &[&str], name: &str| {
+        dump.vars.iter().find(|var| var.scope == scope && var.name == name).map(|var| var.code.clone()).unwrap()
+    };
+    let (clk, count) = (code(&["top"], "clk"), code(&["top", "counter_inst"], "count"));
+    let changes = |code: &str| {
+        dump.changes
+            .iter()
+            .flat_map(|(time, changes)| {
+                changes.iter().filter(|(c, _)| c == code).map(|(_, value)| (*time, value.to_u64()))
+            })
+            .collect::<Vec<_>>()
+    };
+
+    // The initial values are dumped when the trace starts, and only the changes follow.
+    assert_eq!(changes(&clk), [(10, 0), (20, 1), (25, 0), (30, 1), (35, 0), (40, 1)]);
+    assert_eq!(changes(&count), [(10, 0), (20, 1), (30, 2), (40, 3)]);
+    Ok(())
+}
+
+#[test]
+fn vcd_decodes_instances() -> VirgenResult<()> {
+    let bits = TypeInfo::Bits { ty: "U<8>".to_string(), width: 8, signed: false };
+    let out = ChannelInfo {
+        path: "egress.out.payload".to_string(),
+        count: 1,
+        ty: bits,
+        signals: vec![SignalInfo { name: "out".to_string(), path: String::new(), width: 8 }],
+    };
+    let debug_info = DebugInfo {
+        top: "top".to_string(),
+        modules: [
+            ("top".to_string(), ModuleInfo {
+                channels: vec![],
+                instances: [("counter_inst".to_string(), "counter".to_string())].into(),
+            }),
+            ("counter".to_string(), ModuleInfo { channels: vec![out], instances: Default::default() }),
+        ]
+        .into(),
+    };
+
+    // The scope of the instance is matched to the module through the instance name.
+    let decoded = debug_info.decode_vcd(&counter_vcd("vcd_decodes_instances.vcd", 3)?)?;
+    let values =
+        decoded.iter().map(|value| (value.time, value.path.as_str(), value.value.as_u64())).collect::<Vec<_>>();
+    assert_eq!(values, [
+        (10, "top.counter_inst.egress.out.payload", Some(0)),
+        (20, "top.counter_inst.egress.out.payload", Some(1)),
+        (30, "top.counter_inst.egress.out.payload", Some(2)),
+        (40, "top.counter_inst.egress.out.payload", Some(3)),
+    ]);
+    Ok(())
+}
+
+#[test]
+fn vcd_follows_instances_of_compiled_modules() -> VirgenResult<()> {
+    let mut tb = Testbench::new("custom_fifo")?;
+    tb.simulator().trace()?;
+    tb.run(2)?;
+    let path = Path::new(env!("CARGO_TARGET_TMPDIR")).join("vcd_follows_instances_of_compiled_modules.vcd");
+    tb.simulator().write_vcd(&path)?;
+    let dump = read_vcd(&std::fs::read_to_string(path).unwrap())?;
+
+    // Each scope below the top is named after an instance in the enclosing module.
+    let debug_info = hazardflow::testing::compile("custom_fifo", false)?.debug_info;
+    let instances = &debug_info.modules[&debug_info.top].instances;
+    assert!(!instances.is_empty());
+    for var in dump.vars.iter().filter(|var| var.scope.len() > 1) {
+        assert!(instances.contains_key(&var.scope[1]), "{:?}", var.scope);
+    }
+    assert!(instances.keys().all(|inst| dump.vars.iter().any(|var| var.scope.get(1) == Some(inst))));
+    Ok(())
+}
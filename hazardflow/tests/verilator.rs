@@ -0,0 +1,60 @@
+//! Tests of the Verilator harness and filelist.
+
+#![feature(rustc_private)]
+extern crate rustc_driver;
+extern crate rustc_span;
+
+mod common;
+
+use std::collections::HashMap;
+
+use common::*;
+use hazardflow::compiler::error::VirgenResult;
+use hazardflow::testing::compile;
+use hazardflow::vir::verilator::{dependency_order, gen_filelist, gen_harness, gen_main};
+
+#[test]
+fn verilator_lists_submodules_first() {
+    let modules = [
+        parent("top", &["top_00_a", "top_01_b"]),
+        parent("top_00_a", &["top_01_b", "MeshWrapper"]),
+        parent("top_01_b", &["top_01_b_00_c"]),
+        parent("top_01_b_00_c", &[]),
+    ]
+    .into_iter()
+    .map(|module| (module.name.clone(), module))
+    .collect::<HashMap<_, _>>();
+
+    let order = dependency_order(&modules);
+    assert_eq!(order, ["top_01_b_00_c", "top_01_b", "top_00_a", "top"]);
+
+    let files = order.iter().map(|name| format!("{}.v", name)).collect::<Vec<_>>();
+    let ffi_sources = ["MeshWrapper.v".to_string(), "MeshWrapper.v".to_string()];
+    assert_eq!(gen_filelist(&ffi_sources, &files), "MeshWrapper.v\ntop_01_b_00_c.v\ntop_01_b.v\ntop_00_a.v\ntop.v\n");
+}
+
+#[test]
+fn verilator_harness_accesses_channels() -> VirgenResult<()> {
+    let compiled = compile("core", false)?;
+    let harness = gen_harness(&compiled.module, &compiled.debug_info);
+
+    assert!(harness.contains("#include \"Vcore_top.h\""), "{}", harness);
+    assert!(harness.contains("class core_top_harness {"));
+    assert!(harness
+        .contains("    // egress.input.0.input.0.payload: std::value::option::HOption<cpu::mem_interface::MemReq>\n"));
+    assert!(harness.contains(
+        "    IData out_payload_input_0_input_0_Some_0_addr() const { return top->out_input_0_input_0_payload_Some_0_addr; }"
+    ));
+    assert!(harness.contains(
+        "    void out_resolver_input_0_input_0_ready(CData value) { top->out_input_0_input_0_resolver_ready = value; }"
+    ));
+    assert!(harness.contains(
+        "    void in_payload_input_0_output_discriminant(CData value) { top->in_input_0_output_payload_discriminant = value; }"
+    ));
+    assert!(!harness.contains("top->clk = value"));
+
+    let main = gen_main("core_top", "core.f", 100);
+    assert!(main.contains("verilator --cc --exe --build --top-module core_top -F core.f core_top_main.cpp"));
+    assert!(main.contains("harness.cycle < 100"));
+    Ok(())
+}
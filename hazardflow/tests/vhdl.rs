@@ -0,0 +1,127 @@
+//! Tests of the VHDL output.
+
+#![feature(rustc_private)]
+extern crate rustc_driver;
+extern crate rustc_span;
+
+mod common;
+
+use common::*;
+use hazardflow::compiler::error::VirgenResult;
+use hazardflow::testing::compile;
+use hazardflow::vir::vhdl::gen_vhdl;
+use hazardflow::vir::*;
+
+/// Returns the ports declared by the entity.
+fn entity_ports(vhdl: &str) -> Vec<PortDeclaration> {
+    let entity = &vhdl[vhdl.find("entity ").unwrap()..vhdl.find("end entity").unwrap()];
+    entity
+        .lines()
+        .filter_map(|line| {
+            let (name, ty) = line.trim().trim_end_matches(';').split_once(" : ")?;
+            let (dir, ty) = ty.split_once(' ')?;
+            let width = match ty {
+                "std_logic" => 1,
+                _ => ty.strip_prefix("std_logic_vector(")?.split_once("-1")?.0.parse().unwrap(),
+            };
+            Some(match dir {
+                "in" => PortDeclaration::input(width, name.to_string()),
+                _ => PortDeclaration::output(width, name.to_string()),
+            })
+        })
+        .collect()
+}
+
+/// Checks that the blocks are closed.
+fn assert_balanced(vhdl: &str) {
+    let lines = vhdl.lines().map(str::trim).collect::<Vec<_>>();
+    let count = |f: &dyn Fn(&str) -> bool| lines.iter().filter(|line| f(line)).count();
+
+    assert_eq!(count(&|line| line.starts_with("process (")), count(&|line| line == "end process;"));
+    assert_eq!(count(&|line| line.starts_with("if ") && line.ends_with(" then")), count(&|line| line == "end if;"));
+    assert_eq!(
+        count(&|line| line.starts_with("while ") && line.ends_with(" loop")),
+        count(&|line| line == "end loop;")
+    );
+    assert_eq!(count(&|line| line.starts_with("function ")), count(&|line| line == "end function;"));
+    assert_eq!(count(&|line| line.starts_with("entity ")), count(&|line| line.starts_with("end entity ")));
+    assert_eq!(count(&|line| line.starts_with("architecture ")), count(&|line| line == "end architecture rtl;"));
+}
+
+#[test]
+fn vhdl_instantiates_components() -> VirgenResult<()> {
+    let vhdl = gen_vhdl(&counter_with_init("8'd3"))?;
+    let (counter, top) = (&vhdl[0], &vhdl[1]);
+    vhdl.iter().for_each(|vhdl| assert_balanced(vhdl));
+
+    assert!(counter
+        .starts_with("library ieee;\nuse ieee.std_logic_1164.all;\nuse ieee.numeric_std.all;\n\nentity counter is\n"));
+    assert!(counter.contains("        clk : in std_logic;\n        rst : in std_logic_vector(1-1 downto 0);\n"));
+    assert!(counter.contains("\\out\\ : out std_logic_vector(8-1 downto 0) := (others => '0')"), "{}", counter);
+    assert!(counter.contains("signal count : std_logic_vector(8-1 downto 0) := x\"03\";"));
+    assert!(counter.contains("\\out\\ <= count;"));
+    assert!(counter.contains(
+        "    process (clk)
+    begin
+        if rising_edge(clk) then
+            if unsigned(rst) /= 0 then
+                count <= x\"00\";
+            elsif unsigned(en) /= 0 then
+                count <= std_logic_vector((unsigned(count) + unsigned'(x\"01\")));
+            end if;
+        end if;
+    end process;"
+    ));
+
+    assert!(top.contains("    component counter is\n        port (\n"));
+    assert!(top.contains("signal counter_inst_en : std_logic_vector(1-1 downto 0);"));
+    assert!(top.contains("counter_inst_en <= \"1\";"));
+    assert!(top.contains(
+        "    counter_inst : counter
+        port map (
+            clk => clk,
+            rst => rst,
+            en => counter_inst_en,
+            \\out\\ => count
+        );"
+    ));
+    assert!(top.contains("hi <= count(7 downto 4);"));
+    Ok(())
+}
+
+#[test]
+fn vhdl_requires_instantiated_modules() {
+    let modules = counter_with_init("8'd3");
+    let err = gen_vhdl(&modules[1..]).unwrap_err();
+    assert!(format!("{:?}", err).contains("module `counter` instantiated as `counter_inst` is not given"));
+}
+
+#[test]
+fn vhdl_round_trips_examples() -> VirgenResult<()> {
+    for target in ["custom_fifo", "fir_filter"] {
+        let module = compile(target, false)?.module;
+        let vhdl = gen_vhdl(&[module.clone()])?.remove(0);
+        assert_balanced(&vhdl);
+
+        // The ports of the entity are the ports of the module.
+        let port_decls = module
+            .port_decls
+            .iter()
+            .filter(|port_decl| !matches!(port_decl, PortDeclaration::Input(0, _) | PortDeclaration::Output(0, _)))
+            .cloned()
+            .collect::<Vec<_>>();
+        assert_eq!(entity_ports(&vhdl), port_decls, "{}", target);
+
+        // Every output is driven, as a whole or by slices.
+        for port_decl in &port_decls {
+            if let PortDeclaration::Output(_, name) = port_decl {
+                let driven =
+                    vhdl.contains(&format!("\n    {} <= ", name)) || vhdl.contains(&format!("\n    {}(", name));
+                assert!(driven, "{}: {} is not driven", target, name);
+            }
+        }
+        assert!(vhdl.contains("    process (clk)\n"));
+        assert!(vhdl.contains("        if rising_edge(clk) then\n"));
+    }
+    Ok(())
+}
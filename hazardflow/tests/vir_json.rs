@@ -0,0 +1,108 @@
+//! Tests of the JSON serialization of the IR.
+
+#![cfg(feature = "vir-json")]
+#![feature(rustc_private)]
+extern crate rustc_driver;
+extern crate rustc_span;
+
+mod common;
+
+use common::*;
+use hazardflow::compiler::error::VirgenResult;
+use hazardflow::testing::compile;
+use hazardflow::vir::json::{gen_vir_json, load_vir_json};
+use hazardflow::vir::src_map::{SrcLocAnnotator, SrcLocation};
+use hazardflow::vir::*;
+use hazardflow::SrcLoc;
+use rustc_span::{BytePos, Span, DUMMY_SP};
+use serde_json::{json, Value};
+
+fn no_location(_: Span) -> Option<SrcLocation> {
+    None
+}
+
+#[test]
+fn vir_json_snapshot() -> VirgenResult<()> {
+    let json: Value = serde_json::from_str(&gen_vir_json(&counter(), &no_location)?).unwrap();
+
+    assert_eq!(json["version"], 1);
+    let module = &json["modules"][0];
+    assert_eq!(module["name"], "counter");
+    assert_eq!(module["port_decls"][3], json!({ "Output": [8, "out"] }));
+    assert_eq!(
+        module["module_items"][0],
+        json!({ "Declarations": [{ "Reg": [{ "inner": [8], "is_signed": false }, "count", null] }] })
+    );
+    assert_eq!(
+        module["module_items"][1],
+        json!({ "ContinuousAssigns": [[
+            { "Primary": { "HierarchicalIdentifier": ["out", null] } },
+            { "Primary": { "HierarchicalIdentifier": ["count", null] } },
+        ]] })
+    );
+    let always = &module["module_items"][2]["AlwaysConstruct"];
+    assert_eq!(always[0], "always @(posedge clk)");
+    assert_eq!(
+        always[1][0]["Conditional"][0][1][1][0],
+        json!({ "NonblockingAssignment": [
+            { "Primary": { "HierarchicalIdentifier": ["count", null] } },
+            { "Binary": [
+                { "Primary": { "HierarchicalIdentifier": ["count", null] } },
+                "Add",
+                { "Primary": { "Number": "8'd1" } },
+            ] },
+            null,
+        ] })
+    );
+    Ok(())
+}
+
+#[test]
+fn vir_json_writes_locations_of_spans() -> VirgenResult<()> {
+    let span = Span::with_root_ctxt(BytePos(12), BytePos(13));
+    let module = |span| Module {
+        name: "top".to_string(),
+        port_decls: vec![PortDeclaration::input(1, "clk".to_string())],
+        module_items: vec![ModuleItem::AlwaysConstruct("always @(posedge clk)".to_string(), vec![
+            Statement::nonblocking_assignment(ident("x"), number("1'b1"), span),
+        ])],
+    };
+    let locate =
+        |span: Span| Some(SrcLocation { file: "src/top.rs".to_string(), line: span.lo().0 as usize, column: 5 });
+
+    let json = gen_vir_json(&[module(span)], &locate)?;
+    let value: Value = serde_json::from_str(&json).unwrap();
+    let always = &value["modules"][0]["module_items"][0]["AlwaysConstruct"];
+    assert_eq!(always[1][0]["NonblockingAssignment"][2], "src/top.rs:12:5");
+
+    // The spans are not read back.
+    assert_eq!(load_vir_json(&json)?, vec![module(DUMMY_SP)]);
+    Ok(())
+}
+
+#[test]
+fn vir_json_round_trips() -> VirgenResult<()> {
+    let modules = counter();
+    assert_eq!(load_vir_json(&gen_vir_json(&modules, &no_location)?)?, modules);
+
+    for target in ["custom_fifo", "fir_filter"] {
+        let module = compile(target, false)?.module;
+        let json = gen_vir_json(&[module.clone()], &no_location)?;
+        let loaded = load_vir_json(&json)?;
+
+        assert_eq!(gen_vir_json(&loaded, &no_location)?, json, "{}", target);
+        assert_eq!(loaded.len(), 1, "{}", target);
+        // The span comments of `SrcLoc::None` differ, as the loaded spans are dummy.
+        let annotator = SrcLocAnnotator::new(SrcLoc::Comment, &no_location);
+        assert_eq!(loaded[0].gen_verilog(&annotator), module.gen_verilog(&annotator), "{}", target);
+    }
+    Ok(())
+}
+
+#[test]
+fn vir_json_checks_version() {
+    let err = load_vir_json(r#"{ "version": 0, "modules": [] }"#).unwrap_err();
+    assert!(format!("{:?}", err).contains("unsupported IR version 0 (expected 1)"));
+
+    assert!(load_vir_json(r#"{ "version": 1, "modules": [{ "name": "top" }] }"#).is_err());
+}
@@ -0,0 +1,45 @@
+//! Tests of the names of the wires generated for local variables.
+
+#![feature(rustc_private)]
+extern crate rustc_driver;
+
+use hazardflow::compiler::error::VirgenResult;
+use hazardflow::testing::compile;
+use hazardflow::vir::*;
+
+/// Returns the identifiers declared in the module items.
+fn declared(items: &[ModuleItem]) -> Vec<String> {
+    items
+        .iter()
+        .flat_map(|item| match item {
+            ModuleItem::Declarations(decls) => decls.iter().map(Declaration::name).collect(),
+            ModuleItem::Commented(_, _, items) => declared(items),
+            _ => vec![],
+        })
+        .collect()
+}
+
+/// Returns `true` if `ident` is `<prefix>_<name>_t<id>`.
+fn is_named(ident: &str, name: &str) -> bool {
+    ident.rsplit_once("_t").is_some_and(|(prefix, id)| {
+        prefix.ends_with(&format!("_{}", name)) && !id.is_empty() && id.chars().all(|c| c.is_ascii_digit())
+    })
+}
+
+#[test]
+fn wires_keep_local_names() -> VirgenResult<()> {
+    // `fir_filter` computes `let ep = ..` and `let s_next = ..` in its `fsm_map`.
+    let module = compile("fir_filter", false)?.module;
+    let verilog = module.to_string();
+    let declared = declared(&module.module_items);
+
+    for name in ["ep", "s_next"] {
+        let ident = declared.iter().find(|ident| is_named(ident, name));
+        let ident = ident.unwrap_or_else(|| panic!("no wire is named after `{}`", name));
+
+        // The wire survives the wire-cache and deadcode optimizations.
+        assert!(verilog.contains(&format!("{} = ", ident)), "{} is not assigned", ident);
+        assert!(verilog.matches(ident.as_str()).count() > 2, "{} is not used", ident);
+    }
+    Ok(())
+}
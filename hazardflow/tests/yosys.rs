@@ -0,0 +1,143 @@
+//! Tests of the Yosys RTLIL and JSON outputs.
+
+#![feature(rustc_private)]
+extern crate rustc_driver;
+extern crate rustc_span;
+
+mod common;
+
+use std::collections::HashSet;
+
+use common::*;
+use hazardflow::compiler::error::VirgenResult;
+use hazardflow::testing::compile;
+use hazardflow::vir::yosys::{gen_rtlil, gen_yosys_json};
+use hazardflow::vir::*;
+use serde_json::Value;
+
+/// Checks that every input of the cells and every output port is driven by an input port, a cell, or a constant.
+fn assert_driven(json: &Value) {
+    for (name, module) in json["modules"].as_object().unwrap() {
+        let bits = |value: &Value| value.as_array().unwrap().iter().filter_map(Value::as_u64).collect::<Vec<_>>();
+
+        let mut driven = HashSet::new();
+        let mut used = vec![];
+        for port in module["ports"].as_object().unwrap().values() {
+            match port["direction"].as_str().unwrap() {
+                "input" => driven.extend(bits(&port["bits"])),
+                _ => used.extend(bits(&port["bits"])),
+            }
+        }
+        for cell in module["cells"].as_object().unwrap().values() {
+            for (port, connection) in cell["connections"].as_object().unwrap() {
+                match cell["port_directions"][port].as_str().unwrap() {
+                    "input" => used.extend(bits(connection)),
+                    _ => {
+                        for bit in bits(connection) {
+                            assert!(driven.insert(bit), "{}: bit {} is driven twice", name, bit);
+                        }
+                    }
+                }
+            }
+        }
+        for bit in used {
+            assert!(driven.contains(&bit), "{}: bit {} is not driven", name, bit);
+        }
+    }
+}
+
+#[test]
+fn rtlil_lowers_registers_and_instances() -> VirgenResult<()> {
+    let rtlil = gen_rtlil(&counter_with_init("8'd3"), "top")?;
+
+    assert!(rtlil.starts_with(
+        "module \\counter
+  wire input 1 \\clk
+  wire input 2 \\rst
+  wire input 3 \\en
+  wire width 8 output 4 \\out
+  attribute \\init 8'00000011
+  wire width 8 \\count
+"
+    ));
+    assert!(rtlil.contains("cell $add "));
+    assert!(rtlil.contains("    connect \\A \\count\n    connect \\B 8'00000001\n"));
+    assert!(rtlil.contains("cell $mux "));
+    assert!(rtlil.contains("    connect \\S \\rst\n"));
+    assert!(rtlil.contains(
+        "    parameter \\CLK_POLARITY 1
+    parameter \\WIDTH 8
+    connect \\CLK \\clk
+"
+    ));
+    assert!(rtlil.contains("    connect \\Q \\count\n"));
+    assert!(rtlil.contains("  connect \\out \\count\n"));
+
+    assert!(rtlil.contains("attribute \\top 1\nmodule \\top\n"));
+    assert!(rtlil.contains(
+        "  cell \\counter \\counter_inst
+    connect \\clk \\clk
+    connect \\rst \\rst
+    connect \\en 1'1
+    connect \\out $0_out
+  end
+"
+    ));
+    assert!(rtlil.contains("  connect \\count $0_out\n"));
+    assert!(rtlil.contains("  connect \\hi \\count [7:4]\n"));
+    Ok(())
+}
+
+#[test]
+fn yosys_json_resolves_connections() -> VirgenResult<()> {
+    let json: Value = serde_json::from_str(&gen_yosys_json(&counter_with_init("8'd3"), "top")?).unwrap();
+    assert_driven(&json);
+
+    let counter = &json["modules"]["counter"];
+    assert_eq!(counter["netnames"]["count"]["attributes"]["init"], "00000011");
+    assert_eq!(counter["ports"]["out"]["bits"], counter["netnames"]["count"]["bits"]);
+    let types = counter["cells"].as_object().unwrap().values().map(|cell| &cell["type"]).collect::<Vec<_>>();
+    assert!(types.contains(&&Value::from("$dff")));
+    assert!(types.contains(&&Value::from("$add")));
+    assert!(types.contains(&&Value::from("$mux")));
+
+    let top = &json["modules"]["top"];
+    assert_eq!(top["attributes"]["top"], "00000000000000000000000000000001");
+    let inst = &top["cells"]["counter_inst"];
+    assert_eq!(inst["type"], "counter");
+    assert_eq!(inst["connections"]["en"], serde_json::json!(["1"]));
+    assert_eq!(inst["port_directions"]["out"], "output");
+    let out = inst["connections"]["out"].as_array().unwrap();
+    assert_eq!(top["ports"]["hi"]["bits"].as_array().unwrap(), &out[4..]);
+    Ok(())
+}
+
+#[test]
+fn yosys_requires_instantiated_modules() {
+    let modules = counter_with_init("8'd3");
+    let err = gen_rtlil(&modules[1..], "top").unwrap_err();
+    assert!(format!("{:?}", err).contains("module `counter` instantiated as `counter_inst` is not given"));
+}
+
+#[test]
+fn yosys_lowers_examples() -> VirgenResult<()> {
+    for target in ["custom_fifo", "fir_filter"] {
+        let module = compile(target, false)?.module;
+        let json: Value = serde_json::from_str(&gen_yosys_json(&[module.clone()], &module.name)?).unwrap();
+        assert_driven(&json);
+
+        let ports = json["modules"][&module.name]["ports"].as_object().unwrap();
+        for port_decl in &module.port_decls {
+            let (width, name) = match port_decl {
+                PortDeclaration::Input(width, name) | PortDeclaration::Output(width, name) => (*width, name),
+            };
+            let bits = ports.get(name).map_or(0, |port| port["bits"].as_array().unwrap().len());
+            assert_eq!(bits, width, "{}: {}", target, name);
+        }
+
+        let rtlil = gen_rtlil(&[module.clone()], &module.name)?;
+        assert!(rtlil.contains("cell $dff "), "{}", target);
+        assert_eq!(rtlil.matches("\n  cell ").count(), rtlil.matches("\n  end").count(), "{}", target);
+    }
+    Ok(())
+}